@@ -0,0 +1,150 @@
+//! Click (count) reference track. The clicks are synthesized straight to a WAV
+//! stem in the same format as offline renders, so no server is needed.
+
+use std::f64::consts::TAU;
+use std::path::{Path, PathBuf};
+
+use crate::state::piano_roll::PianoRollState;
+
+/// Click pitch on the first beat of a bar, and on the other beats
+const ACCENT_FREQ: f64 = 1500.0;
+const BEAT_FREQ: f64 = 1000.0;
+/// Click peak on the first beat of a bar, and on the other beats
+const ACCENT_AMP: f64 = 0.9;
+const BEAT_AMP: f64 = 0.6;
+/// How long each click rings, in seconds
+const CLICK_SECS: f64 = 0.04;
+
+/// One click
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClickHit {
+    /// Seconds from the start of the render
+    pub time: f64,
+    /// First beat of a bar
+    pub accent: bool,
+}
+
+/// The span a click track covers: the loop region while looping, otherwise from
+/// the start to the end of the bar holding the last note (at least one bar)
+pub fn click_region(piano_roll: &PianoRollState) -> (u32, u32) {
+    if piano_roll.looping && piano_roll.loop_end > piano_roll.loop_start {
+        return (piano_roll.loop_start, piano_roll.loop_end);
+    }
+    let bar = piano_roll.ticks_per_bar();
+    let end = piano_roll.content_end().div_ceil(bar).max(1) * bar;
+    (0, end)
+}
+
+/// A click on every beat of the time signature from `start` to `end` ticks, at the
+/// piano roll's tempo. A beat is the signature's note value (eighths in 6/8), and
+/// bars count from tick 0, so a region starting mid-bar is still accented on the bar.
+pub fn click_hits(piano_roll: &PianoRollState, start: u32, end: u32) -> Vec<ClickHit> {
+    let beat = piano_roll.ticks_per_meter_beat();
+    let bar = piano_roll.ticks_per_bar();
+    let secs_per_tick = 60.0 / piano_roll.bpm as f64 / piano_roll.ticks_per_beat as f64;
+    let first = start.div_ceil(beat) * beat;
+    (first..end)
+        .step_by(beat as usize)
+        .map(|tick| ClickHit {
+            time: (tick - start) as f64 * secs_per_tick,
+            accent: tick % bar == 0,
+        })
+        .collect()
+}
+
+/// Write `hits` to a stereo 24-bit WAV `duration` seconds long at `out`
+pub fn render_click_track(hits: &[ClickHit], duration: f64, sample_rate: u32, out: &Path) -> Result<PathBuf, String> {
+    let frames = (duration * sample_rate as f64).round() as usize;
+    let mut samples = vec![0.0f64; frames];
+    let click_frames = (CLICK_SECS * sample_rate as f64) as usize;
+    for hit in hits {
+        let (freq, amp) = if hit.accent { (ACCENT_FREQ, ACCENT_AMP) } else { (BEAT_FREQ, BEAT_AMP) };
+        let start = (hit.time * sample_rate as f64).round() as usize;
+        for i in 0..click_frames {
+            let Some(sample) = samples.get_mut(start + i) else { break };
+            let t = i as f64 / sample_rate as f64;
+            // Decays to about -60 dB by the end of the click
+            *sample += amp * (TAU * freq * t).sin() * (-t * 6.9 / CLICK_SECS).exp();
+        }
+    }
+
+    if let Some(dir) = out.parent() {
+        std::fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+    }
+    let spec = hound::WavSpec {
+        channels: 2,
+        sample_rate,
+        bits_per_sample: 24,
+        sample_format: hound::SampleFormat::Int,
+    };
+    let mut writer = hound::WavWriter::create(out, spec).map_err(|e| e.to_string())?;
+    let full_scale = (1 << 23) as f64 - 1.0;
+    for sample in samples {
+        let value = (sample.clamp(-1.0, 1.0) * full_scale) as i32;
+        writer.write_sample(value).map_err(|e| e.to_string())?;
+        writer.write_sample(value).map_err(|e| e.to_string())?;
+    }
+    writer.finalize().map_err(|e| e.to_string())?;
+    Ok(out.to_path_buf())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_clicks_follow_time_signature_and_tempo() {
+        let mut piano_roll = PianoRollState::new();
+        piano_roll.bpm = 120.0;
+        piano_roll.time_signature = (3, 4);
+        let tpb = piano_roll.ticks_per_beat;
+        let hits = click_hits(&piano_roll, 0, tpb * 6);
+        let accents: Vec<bool> = hits.iter().map(|h| h.accent).collect();
+        assert_eq!(accents, [true, false, false, true, false, false]);
+        assert!((hits[1].time - 0.5).abs() < 1e-9);
+
+        // 6/8 clicks eighths, twice as often
+        piano_roll.time_signature = (6, 8);
+        let hits = click_hits(&piano_roll, 0, tpb * 3);
+        assert_eq!(hits.len(), 6);
+        assert!((hits[1].time - 0.25).abs() < 1e-9);
+        assert!(hits[0].accent && !hits[5].accent);
+    }
+
+    #[test]
+    fn test_region_starting_mid_bar_keeps_bar_accents() {
+        let mut piano_roll = PianoRollState::new();
+        piano_roll.time_signature = (4, 4);
+        let tpb = piano_roll.ticks_per_beat;
+        let hits = click_hits(&piano_roll, tpb * 2 + 1, tpb * 5);
+        // Beats 4 and the next bar's 1, timed from the region start
+        assert_eq!(hits.len(), 2);
+        assert!(!hits[0].accent && hits[1].accent);
+        assert!(hits[0].time > 0.0);
+    }
+
+    #[test]
+    fn test_region_covers_whole_bars_or_the_loop() {
+        let mut piano_roll = PianoRollState::new();
+        let bar = piano_roll.ticks_per_bar();
+        assert_eq!(click_region(&piano_roll), (0, bar));
+        piano_roll.add_track(1);
+        piano_roll.add_note(0, 60, bar, 10, 100);
+        assert_eq!(click_region(&piano_roll), (0, bar * 2));
+        piano_roll.looping = true;
+        piano_roll.loop_start = 480;
+        piano_roll.loop_end = 960;
+        assert_eq!(click_region(&piano_roll), (480, 960));
+    }
+
+    #[test]
+    fn test_render_writes_stereo_wav_of_duration() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("click.wav");
+        let hits = [ClickHit { time: 0.0, accent: true }];
+        render_click_track(&hits, 0.5, 48000, &path).unwrap();
+        let reader = hound::WavReader::open(&path).unwrap();
+        assert_eq!(reader.spec().channels, 2);
+        assert_eq!(reader.duration(), 24000);
+    }
+}
@@ -147,8 +147,7 @@ fn is_blacklisted_device(name: &str) -> bool {
 /// Resolve the default output and input device names.
 /// Used to always pass explicit `-H` to scsynth so it never probes
 /// problematic devices.
-pub fn default_device_names() -> (Option<String>, Option<String>) {
-    let devices = enumerate_devices();
+pub fn default_device_names(devices: &[AudioDevice]) -> (Option<String>, Option<String>) {
     let output = devices.iter()
         .find(|d| d.is_default_output)
         .map(|d| d.name.clone());
@@ -1,8 +1,9 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
 use std::fs;
+use std::io::Read;
 use std::path::{Path, PathBuf};
 use std::process::{Child, Command, Stdio};
-use std::sync::mpsc::{self, Receiver};
+use std::sync::mpsc::{self, Receiver, Sender};
 use std::thread;
 use std::time::{Duration, Instant};
 
@@ -15,8 +16,14 @@ struct RecordingState {
 }
 
 use super::bus_allocator::BusAllocator;
-use super::osc_client::OscClient;
-use crate::state::{AutomationTarget, BufferId, CustomSynthDefRegistry, EffectType, FilterType, SourceType, ParamValue, SessionState, InstrumentId, InstrumentState};
+use super::osc_client::{BufferReply, OscClient, ServerStats};
+use super::server_output::ServerOutput;
+use super::sample_decode::{self, PreparedSample};
+use crate::state::{AutomationTarget, BufferId, CustomSynthDefRegistry, EffectSlot, EffectType, FilterType, Instrument, LfoTarget, MixerSend, SourceType, ParamValue, SessionState, InstrumentId, InstrumentState, SynthDefImportReport};
+use crate::state::drum_sequencer::PadHit;
+use crate::state::modulation::{is_voice_destination, ModRouteSource};
+use crate::state::session::{validate_output_channel, SCSYNTH_DEFAULT_OUTPUTS, STEREO_OUTPUTS};
+use crate::state::sampler::{layers_for_velocity, Slice};
 
 #[allow(dead_code)]
 pub type ModuleId = u32;
@@ -36,8 +43,192 @@ pub enum ServerStatus {
     Error,
 }
 
+/// Whether live audio can be heard. Editing, saving and offline renders work in
+/// every case; only playing sound needs a connected server.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AudioAvailability {
+    /// Connected to a running server
+    Live,
+    /// SuperCollider is installed but no server is connected
+    Offline,
+    /// No scsynth executable was found
+    Missing,
+}
+
+impl AudioAvailability {
+    /// Short warning for the header; None while audio is live
+    pub fn warning(&self) -> Option<&'static str> {
+        match self {
+            AudioAvailability::Live => None,
+            AudioAvailability::Offline => Some("Audio offline"),
+            AudioAvailability::Missing => Some("No SuperCollider"),
+        }
+    }
+}
+
+/// Where to look for scsynth when no path is configured, in order
+const SCSYNTH_PATHS: [&str; 4] = [
+    "scsynth",
+    "/Applications/SuperCollider.app/Contents/Resources/scsynth",
+    "/usr/local/bin/scsynth",
+    "/usr/bin/scsynth",
+];
+
+/// Where to look for sclang when no path is configured, in order
+const SCLANG_PATHS: [&str; 6] = [
+    "sclang",
+    "/Applications/SuperCollider.app/Contents/MacOS/sclang",
+    "/Applications/SuperCollider/SuperCollider.app/Contents/MacOS/sclang",
+    "/opt/homebrew/bin/sclang",
+    "/usr/local/bin/sclang",
+    "/usr/bin/sclang",
+];
+
+/// Executables to try: only the configured one when there is one, otherwise the
+/// built-in locations
+fn executable_candidates(configured: Option<&Path>, builtin: &[&str]) -> Vec<PathBuf> {
+    match configured {
+        Some(path) => vec![path.to_path_buf()],
+        None => builtin.iter().map(PathBuf::from).collect(),
+    }
+}
+
+/// Whether `path` names an existing file, looking bare names up in the
+/// `search` directories (a PATH-style list)
+fn executable_exists(path: &Path, search: Option<&std::ffi::OsStr>) -> bool {
+    if path.components().count() > 1 {
+        return path.is_file();
+    }
+    search.is_some_and(|dirs| std::env::split_paths(dirs).any(|dir| dir.join(path).is_file()))
+}
+
+/// Error for an executable that none of `candidates` could start. A configured
+/// path is the only candidate, and the error says why it couldn't start.
+pub fn not_found_error(name: &str, candidates: &[PathBuf], last_error: Option<&std::io::Error>) -> String {
+    if let ([path], Some(e)) = (candidates, last_error) {
+        return format!(
+            "Could not start {} at {}: {}. Check [audio] {}_path in config.toml or ILEX_{}.",
+            name,
+            path.display(),
+            e,
+            name,
+            name.to_uppercase(),
+        );
+    }
+    let searched: Vec<String> = candidates.iter().map(|p| p.display().to_string()).collect();
+    format!(
+        "Could not find {}. Searched: {}. Install SuperCollider, or set [audio] {}_path in config.toml or ILEX_{}.",
+        name,
+        searched.join(", "),
+        name,
+        name.to_uppercase(),
+    )
+}
+
+/// Whether a note-off under sustain should be held back: only while the note has more
+/// sounding voices than held-back note-offs, so a retriggered note that's still down
+/// isn't released along with the copies let go earlier.
+fn holds_release(sustained: &[(InstrumentId, u8)], chains: &[VoiceChain], instrument_id: InstrumentId, pitch: u8) -> bool {
+    let voices = chains.iter().filter(|v| v.instrument_id == instrument_id && v.pitch == pitch).count();
+    let held = sustained.iter().filter(|&&n| n == (instrument_id, pitch)).count();
+    held < voices
+}
+
+/// Voices an instrument's sounding notes count against its limit
+fn voices_used(chains: &[VoiceChain], instrument_id: InstrumentId) -> usize {
+    chains.iter().filter(|v| v.instrument_id == instrument_id).map(|v| v.voices).sum()
+}
+
+/// Seconds after a note-off before a voice's group is freed: the instrument's
+/// release plus a second of margin
+fn voice_cleanup_secs(state: &InstrumentState, instrument_id: InstrumentId) -> f64 {
+    let release = state.instrument(instrument_id).map_or(1.0, |s| s.amp_envelope.release);
+    release as f64 + 1.0
+}
+
+/// Gate a voice off and free its group once the release has played out
+fn fade_out_voice(client: &OscClient, chain: &VoiceChain, offset_secs: f64, state: &InstrumentState) -> Result<(), String> {
+    let time = super::osc_client::osc_time_from_now(offset_secs);
+    client
+        .set_params_bundled(chain.midi_node_id, &[("gate", 0.0)], time)
+        .map_err(|e| e.to_string())?;
+    let cleanup_time = super::osc_client::osc_time_from_now(
+        offset_secs + voice_cleanup_secs(state, chain.instrument_id)
+    );
+    client
+        .send_bundle(
+            vec![rosc::OscMessage {
+                addr: "/n_free".to_string(),
+                args: vec![rosc::OscType::Int(chain.group_id)],
+            }],
+            cleanup_time,
+        )
+        .map_err(|e| e.to_string())
+}
+
+/// Gap between closing and reopening a mono voice's gate, a little over one
+/// control block, so the envelope sees a fresh note-on
+const RETRIGGER_GAP_SECS: f64 = 0.003;
+
+/// Gate values a running mono voice gets for its next note, with their delays.
+/// With glide the note slides in legato; without, the gate closes and reopens so
+/// the envelope retriggers like a new voice would.
+fn mono_gate_steps(glide: f32) -> Vec<(f64, f32)> {
+    if glide > 0.0 {
+        vec![(0.0, 1.0)]
+    } else {
+        vec![(0.0, 0.0), (RETRIGGER_GAP_SECS, 1.0)]
+    }
+}
+
+/// Synth control that reads an instrument-level mod matrix destination, or None
+/// if nothing on the instrument's chain can be modulated there yet
+fn mod_input(target: LfoTarget) -> Option<&'static str> {
+    match target {
+        LfoTarget::FilterCutoff => Some("cutoff_mod_in"),
+        LfoTarget::FilterResonance => Some("res_mod_in"),
+        LfoTarget::Pan => Some("pan_mod_in"),
+        LfoTarget::DelayTime => Some("time_mod_in"),
+        LfoTarget::DelayFeedback => Some("feedback_mod_in"),
+        LfoTarget::ReverbMix => Some("mix_mod_in"),
+        LfoTarget::GateRate => Some("rate_mod_in"),
+        LfoTarget::SendLevel => Some("level_mod_in"),
+        _ => None,
+    }
+}
+
+/// The mod inputs an effect's SynthDef has
+fn effect_mod_inputs(effect_type: EffectType) -> &'static [&'static str] {
+    match effect_type {
+        EffectType::Delay => &["time_mod_in", "feedback_mod_in"],
+        EffectType::Reverb => &["mix_mod_in"],
+        EffectType::Gate => &["rate_mod_in"],
+        EffectType::TapeComp | EffectType::SidechainComp => &[],
+    }
+}
+
+/// Synth params pointing each of `inputs` at its mod bus; inputs nothing is routed
+/// to are left at the SynthDef's -1 (unmodulated)
+fn mod_input_params(buses: &[(&str, i32)], inputs: &[&str]) -> Vec<(String, f32)> {
+    buses.iter()
+        .filter(|(name, _)| inputs.contains(name))
+        .map(|&(name, bus)| (name.to_string(), bus as f32))
+        .collect()
+}
+
 /// Maximum simultaneous voices per instrument
-const MAX_VOICES_PER_INSTRUMENT: usize = 16;
+/// An imported synthdef waiting for (or undergoing) compilation
+struct CustomCompile {
+    scd_path: PathBuf,
+    output_dir: PathBuf,
+    synthdef_name: String,
+}
+
+/// How long sclang may take to compile an imported synthdef before it is killed
+const CUSTOM_COMPILE_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Voices an instrument can sound at once before its oldest notes are stolen
+pub const MAX_VOICES_PER_INSTRUMENT: usize = 16;
 
 /// A polyphonic voice chain: entire signal chain spawned per note
 #[derive(Debug, Clone)]
@@ -46,14 +237,23 @@ pub struct VoiceChain {
     pub pitch: u8,
     pub group_id: i32,
     pub midi_node_id: i32,
-    pub source_node: i32,
+    /// Source synths playing the note: unison copies, or a sampler's zones and layers
+    pub source_nodes: Vec<i32>,
+    /// Voice-local LFOs (by LFO index), for polyphonic instruments that retrigger per note
+    pub lfo_nodes: Vec<(usize, i32)>,
+    /// Voices the note counts against the instrument's limit: one per unison copy
+    /// for a synth, one for a sampler note whatever its zones and layers
+    pub voices: usize,
     pub spawn_time: Instant,
 }
 
 #[derive(Debug, Clone)]
 pub struct InstrumentNodes {
     pub source: Option<i32>,
-    pub lfo: Option<i32>,
+    /// Running LFOs by LFO index
+    pub lfos: Vec<(usize, i32)>,
+    /// Mod matrix nodes scaling LFOs onto instrument-level destinations
+    pub mod_routes: Vec<i32>,
     pub filter: Option<i32>,
     pub effects: Vec<i32>,  // only enabled effects
     pub output: i32,
@@ -63,7 +263,8 @@ impl InstrumentNodes {
     pub fn all_node_ids(&self) -> Vec<i32> {
         let mut ids = Vec::new();
         if let Some(id) = self.source { ids.push(id); }
-        if let Some(id) = self.lfo { ids.push(id); }
+        ids.extend(self.lfos.iter().map(|&(_, id)| id));
+        ids.extend(&self.mod_routes);
         if let Some(id) = self.filter { ids.push(id); }
         ids.extend(&self.effects);
         ids.push(self.output);
@@ -80,6 +281,14 @@ pub struct AudioEngine {
     server_status: ServerStatus,
     compile_receiver: Option<Receiver<Result<String, String>>>,
     is_compiling: bool,
+    /// Imported synthdef being compiled; loaded once the compile succeeds
+    current_custom_compile: Option<CustomCompile>,
+    /// Imported synthdefs waiting for sclang, compiled one at a time
+    custom_compile_queue: VecDeque<CustomCompile>,
+    /// Folder import in progress; summarised once the queue drains
+    import_report: Option<SynthDefImportReport>,
+    /// Imported synthdefs whose compile or load failed, until the session forgets them
+    failed_custom_compiles: Vec<String>,
     bus_allocator: BusAllocator,
     groups_created: bool,
     /// Dedicated audio bus per mixer bus (bus_id -> SC audio bus index)
@@ -88,16 +297,32 @@ pub struct AudioEngine {
     send_node_map: HashMap<(usize, u8), i32>,
     /// Bus output synth nodes: bus_id -> node_id
     bus_node_map: HashMap<u8, i32>,
+    /// Effect synth nodes on each bus, in chain order: bus_id -> node_ids
+    bus_effect_node_map: HashMap<u8, Vec<i32>>,
     /// Active poly voice chains (full signal chain per note)
     voice_chains: Vec<VoiceChain>,
+    /// Sustain pedal down: note-offs are held back until it lifts
+    sustain: bool,
+    /// Note-offs held back by sustain, in arrival order
+    sustained: Vec<(InstrumentId, u8)>,
+    /// Notes spawned per instrument, for order-based voice spread
+    voice_spread_counts: HashMap<InstrumentId, usize>,
     /// Next available voice bus (audio)
     next_voice_audio_bus: i32,
     /// Next available voice bus (control)
     next_voice_control_bus: i32,
     /// Meter synth node ID
     meter_node_id: Option<i32>,
-    /// Sample buffer mapping: BufferId -> SuperCollider buffer number
-    buffer_map: HashMap<BufferId, i32>,
+    /// First hardware channel of the master pair, which the meter reads
+    master_output: u32,
+    /// Master stage synth node ID
+    master_node_id: Option<i32>,
+    /// Master stage sums to mono for checking (runtime only)
+    mono_check: bool,
+    /// Hardware output channels of the server we booted; None if we didn't boot it
+    output_channels: Option<u32>,
+    /// Sample buffer mapping: BufferId -> loaded SuperCollider buffer
+    buffer_map: HashMap<BufferId, LoadedBuffer>,
     /// Next available buffer number for SuperCollider
     #[allow(dead_code)]
     next_bufnum: i32,
@@ -105,10 +330,52 @@ pub struct AudioEngine {
     recording: Option<RecordingState>,
     /// Buffer pending free after recording stop (bufnum, when to free)
     pending_buffer_free: Option<(i32, Instant)>,
+    /// Background sample file checks: (buffer_id, readable path or error)
+    sample_load_sender: Sender<SampleCheck>,
+    sample_load_receiver: Receiver<SampleCheck>,
+    /// Buffer reads sent to scsynth awaiting /done: bufnum -> (BufferId, buffer info)
+    pending_buffer_reads: HashMap<i32, (BufferId, LoadedBuffer)>,
+    /// Resample samples to the server rate on load (off = native rate, lo-fi)
+    resample_samples: bool,
+    /// Smoothing time (seconds) for continuous params changed while playing; 0 = instant
+    param_lag: f32,
+    /// Stopping playback leaves effect nodes running so delays and reverbs ring out;
+    /// off, the effect chains are silenced along with the voices
+    stop_tails: bool,
+    /// Configured scsynth/sclang executables, used instead of the built-in locations
+    scsynth_path: Option<PathBuf>,
+    sclang_path: Option<PathBuf>,
+    /// Whether any scsynth candidate exists on disk, as of the last path change
+    scsynth_found: bool,
+    /// When /status was last requested, to throttle load polling
+    last_status_request: Option<Instant>,
+    /// Captured stdout/stderr of the scsynth we spawned
+    server_output: Option<ServerOutput>,
+}
+
+type SampleCheck = (BufferId, Result<PreparedSample, String>);
+
+/// How long a sample load waits for the server to report its sample rate
+const SAMPLE_RATE_WAIT: Duration = Duration::from_secs(2);
+
+/// How often the server is asked for its load figures
+const STATUS_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// A sample buffer loaded on the server
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LoadedBuffer {
+    pub bufnum: i32,
+    /// Sample rate of the source file (None if unknown)
+    pub original_sample_rate: Option<u32>,
+    /// Sample rate of the buffer data; equals the server rate when resampled.
+    /// The sampler synthdefs rate-scale by `BufRateScale`, so pitch-tracked
+    /// playback stays correct either way.
+    pub sample_rate: Option<u32>,
 }
 
 impl AudioEngine {
     pub fn new() -> Self {
+        let (sample_load_sender, sample_load_receiver) = mpsc::channel();
         Self {
             client: None,
             node_map: HashMap::new(),
@@ -118,30 +385,93 @@ impl AudioEngine {
             server_status: ServerStatus::Stopped,
             compile_receiver: None,
             is_compiling: false,
+            current_custom_compile: None,
+            custom_compile_queue: VecDeque::new(),
+            import_report: None,
+            failed_custom_compiles: Vec::new(),
             bus_allocator: BusAllocator::new(),
             groups_created: false,
             bus_audio_buses: HashMap::new(),
             send_node_map: HashMap::new(),
             bus_node_map: HashMap::new(),
+            bus_effect_node_map: HashMap::new(),
             voice_chains: Vec::new(),
+            sustain: false,
+            sustained: Vec::new(),
+            voice_spread_counts: HashMap::new(),
             next_voice_audio_bus: 16,
             next_voice_control_bus: 0,
             meter_node_id: None,
+            master_output: 0,
+            master_node_id: None,
+            mono_check: false,
+            output_channels: None,
             buffer_map: HashMap::new(),
             next_bufnum: 100, // Start at 100 to avoid conflicts with built-in buffers
             recording: None,
             pending_buffer_free: None,
+            sample_load_sender,
+            sample_load_receiver,
+            pending_buffer_reads: HashMap::new(),
+            resample_samples: true,
+            param_lag: 0.0,
+            stop_tails: false,
+            scsynth_path: None,
+            sclang_path: None,
+            scsynth_found: executable_candidates(None, &SCSYNTH_PATHS)
+                .iter()
+                .any(|p| executable_exists(p, std::env::var_os("PATH").as_deref())),
+            last_status_request: None,
+            server_output: None,
         }
     }
 
+    /// Voices an instrument is sounding, counted the way voice stealing counts
+    /// them; stealing starts at `MAX_VOICES_PER_INSTRUMENT`
+    pub fn voice_count(&self, instrument_id: InstrumentId) -> usize {
+        voices_used(&self.voice_chains, instrument_id)
+    }
+
     pub fn is_running(&self) -> bool {
         self.is_running
     }
 
+    /// Hardware output channels buses can be routed to. Plain stereo unless we booted
+    /// the server and so know how many outputs it opened.
+    pub fn output_channel_count(&self) -> u32 {
+        self.output_channels.unwrap_or(STEREO_OUTPUTS)
+    }
+
+    /// First hardware channel the master pair plays to, as of the last routing rebuild
+    pub fn master_output(&self) -> u32 {
+        self.master_output
+    }
+
+    /// The hardware channel to write a stereo pair to: `channel` if the server has it,
+    /// else out 1/2
+    fn hardware_output(&self, channel: u32) -> u32 {
+        if validate_output_channel(channel, self.output_channel_count()).is_ok() {
+            channel
+        } else {
+            0
+        }
+    }
+
     pub fn status(&self) -> ServerStatus {
         self.server_status
     }
 
+    /// Whether sound can play now, or why not
+    pub fn availability(&self) -> AudioAvailability {
+        if self.is_running {
+            AudioAvailability::Live
+        } else if self.scsynth_found {
+            AudioAvailability::Offline
+        } else {
+            AudioAvailability::Missing
+        }
+    }
+
     pub fn server_running(&self) -> bool {
         self.scsynth_process.is_some()
     }
@@ -151,6 +481,12 @@ impl AudioEngine {
         self.is_compiling
     }
 
+    /// Background work the main loop should pick up promptly: a synthdef compile,
+    /// a recording or its deferred buffer free
+    pub fn has_pending_work(&self) -> bool {
+        self.is_compiling || self.recording.is_some() || self.pending_buffer_free.is_some()
+    }
+
     #[allow(dead_code)]
     pub fn start_server(&mut self) -> Result<(), String> {
         self.start_server_with_devices(None, None)
@@ -167,20 +503,14 @@ impl AudioEngine {
 
         self.server_status = ServerStatus::Starting;
 
-        let scsynth_paths = [
-            "scsynth",
-            "/Applications/SuperCollider.app/Contents/Resources/scsynth",
-            "/usr/local/bin/scsynth",
-            "/usr/bin/scsynth",
-        ];
-
         // Build args: base port + optional device flags
         let mut args: Vec<String> = vec!["-u".to_string(), "57110".to_string()];
 
         // Resolve "System Default" to actual device names so we always
         // pass -H to scsynth. Without -H, scsynth probes all devices
         // and can crash on incompatible ones (e.g. iPhone continuity mic).
-        let (default_output, default_input) = super::devices::default_device_names();
+        let devices = super::devices::enumerate_devices();
+        let (default_output, default_input) = super::devices::default_device_names(&devices);
         let resolved_input = input_device
             .map(|s| s.to_string())
             .or(default_input);
@@ -206,47 +536,54 @@ impl AudioEngine {
             (None, None) => {}
         }
 
-        // Redirect scsynth output to a log file for crash diagnostics
+        // Open every output the device has, so buses can be routed past 1/2
+        let device_outputs = resolved_output.as_deref()
+            .and_then(|name| devices.iter().find(|d| d.name == name))
+            .and_then(|d| d.output_channels);
+        if let Some(outputs) = device_outputs {
+            args.push("-o".to_string());
+            args.push(outputs.to_string());
+        }
+
+        // Capture scsynth output for crash diagnostics, mirrored to a log file
         let log_path = dirs::config_dir()
             .unwrap_or_else(|| PathBuf::from("."))
             .join("ilex")
             .join("scsynth.log");
         let _ = fs::create_dir_all(log_path.parent().unwrap());
-        let stdout_file = fs::File::create(&log_path).ok();
-        let stderr_file = stdout_file.as_ref().and_then(|f| f.try_clone().ok());
+        let log_file = fs::File::create(&log_path).ok();
 
         let mut child = None;
+        let mut last_error = None;
         let arg_refs: Vec<&str> = args.iter().map(|s| s.as_str()).collect();
-        for path in &scsynth_paths {
+        let candidates = self.scsynth_candidates();
+        for path in &candidates {
             match Command::new(path)
                 .args(&arg_refs)
-                .stdout(stdout_file.as_ref()
-                    .and_then(|f| f.try_clone().ok())
-                    .map(Stdio::from)
-                    .unwrap_or_else(Stdio::null))
-                .stderr(stderr_file.as_ref()
-                    .and_then(|f| f.try_clone().ok())
-                    .map(Stdio::from)
-                    .unwrap_or_else(Stdio::null))
+                .stdout(Stdio::piped())
+                .stderr(Stdio::piped())
                 .spawn()
             {
                 Ok(c) => {
                     child = Some(c);
                     break;
                 }
-                Err(_) => continue,
+                Err(e) => last_error = Some(e),
             }
         }
 
         match child {
             Some(mut c) => {
                 self.server_status = ServerStatus::Running;
+                self.output_channels = Some(device_outputs.unwrap_or(SCSYNTH_DEFAULT_OUTPUTS));
+                self.server_output = Some(ServerOutput::capture(&mut c, log_file));
                 thread::sleep(Duration::from_millis(500));
 
                 // Verify scsynth didn't crash during startup
                 match c.try_wait() {
                     Ok(Some(status)) => {
                         self.server_status = ServerStatus::Error;
+                        self.finish_server_output();
                         Err(format!(
                             "scsynth crashed ({}) — see {}",
                             status, log_path.display()
@@ -260,7 +597,7 @@ impl AudioEngine {
             }
             None => {
                 self.server_status = ServerStatus::Error;
-                Err("Could not find scsynth. Install SuperCollider.".to_string())
+                Err(not_found_error("scsynth", &candidates, last_error.as_ref()))
             }
         }
     }
@@ -272,6 +609,7 @@ impl AudioEngine {
             match child.try_wait() {
                 Ok(Some(status)) => {
                     self.scsynth_process = None;
+                    self.finish_server_output();
                     self.is_running = false;
                     self.client = None;
                     self.server_status = ServerStatus::Error;
@@ -297,10 +635,25 @@ impl AudioEngine {
         if let Some(mut child) = self.scsynth_process.take() {
             let _ = child.kill();
             let _ = child.wait();
+            self.finish_server_output();
         }
+        self.output_channels = None;
         self.server_status = ServerStatus::Stopped;
     }
 
+    /// Join the output readers of a server that has exited. Its pipes are closed,
+    /// so they are at end of file; the captured lines stay available.
+    fn finish_server_output(&mut self) {
+        if let Some(output) = self.server_output.as_mut() {
+            output.finish();
+        }
+    }
+
+    /// The last `n` lines scsynth printed (kept after it exits, until the next start)
+    pub fn server_log_tail(&self, n: usize) -> Vec<String> {
+        self.server_output.as_ref().map(|o| o.tail(n)).unwrap_or_default()
+    }
+
     pub fn compile_synthdefs_async(&mut self, scd_path: &Path) -> Result<(), String> {
         if self.is_compiling {
             return Err("Compilation already in progress".to_string());
@@ -310,12 +663,85 @@ impl AudioEngine {
         }
 
         let path = scd_path.to_path_buf();
+        let candidates = self.sclang_candidates();
         let (tx, rx) = mpsc::channel();
         self.compile_receiver = Some(rx);
         self.is_compiling = true;
 
         thread::spawn(move || {
-            let result = Self::run_sclang(&path);
+            let result = Self::run_sclang(&path, &candidates);
+            let _ = tx.send(result);
+        });
+
+        Ok(())
+    }
+
+    /// Compile an imported .scd in the background, writing its .scsyndef to `output_dir`.
+    /// Queued behind any running compile; each result arrives through
+    /// `poll_compile_result`, which also loads the synthdef.
+    pub fn compile_custom_synthdef_async(
+        &mut self,
+        scd_path: &Path,
+        output_dir: &Path,
+        synthdef_name: &str,
+    ) -> Result<(), String> {
+        let job = CustomCompile {
+            scd_path: scd_path.to_path_buf(),
+            output_dir: output_dir.to_path_buf(),
+            synthdef_name: synthdef_name.to_string(),
+        };
+        if self.is_compiling {
+            self.custom_compile_queue.push_back(job);
+            return Ok(());
+        }
+        self.start_custom_compile(job)
+    }
+
+    /// Imported synthdefs compiling or waiting to compile
+    pub fn pending_custom_compiles(&self) -> usize {
+        self.custom_compile_queue.len() + usize::from(self.current_custom_compile.is_some())
+    }
+
+    /// Start collecting compile results for a folder import. `report` holds
+    /// the files that were already skipped or failed before compiling.
+    pub fn begin_import_report(&mut self, report: SynthDefImportReport) {
+        self.import_report = Some(report);
+    }
+
+    /// The folder import report, once all of its compiles have finished
+    pub fn take_import_report(&mut self) -> Option<SynthDefImportReport> {
+        if self.pending_custom_compiles() > 0 {
+            return None;
+        }
+        self.import_report.take()
+    }
+
+    fn start_custom_compile(&mut self, job: CustomCompile) -> Result<(), String> {
+        let (scd_path, output_dir) = (&job.scd_path, &job.output_dir);
+        let scd_content = fs::read_to_string(scd_path)
+            .map_err(|e| format!("Failed to read .scd file: {}", e))?;
+
+        // Point the script's output directory at ours and exit sclang when done
+        let output_dir_str = format!("\"{}\"", output_dir.display());
+        let modified_content = scd_content
+            .replace("dir ? thisProcess.nowExecutingPath.dirname", &output_dir_str)
+            .replace("thisProcess.nowExecutingPath.dirname", &output_dir_str);
+        let compile_script = format!("(\n{}\n\"SUCCESS\".postln;\n0.exit;\n)", modified_content);
+
+        let temp_script = std::env::temp_dir()
+            .join(format!("ilex_compile_{}_{}.scd", std::process::id(), job.synthdef_name));
+        fs::write(&temp_script, &compile_script)
+            .map_err(|e| format!("Failed to write compile script: {}", e))?;
+
+        let candidates = self.sclang_candidates();
+        let (tx, rx) = mpsc::channel();
+        self.compile_receiver = Some(rx);
+        self.is_compiling = true;
+        self.current_custom_compile = Some(job);
+
+        thread::spawn(move || {
+            let result = Self::run_sclang_script(&temp_script, &candidates, CUSTOM_COMPILE_TIMEOUT);
+            let _ = fs::remove_file(&temp_script);
             let _ = tx.send(result);
         });
 
@@ -323,34 +749,123 @@ impl AudioEngine {
     }
 
     pub fn poll_compile_result(&mut self) -> Option<Result<String, String>> {
-        if let Some(ref rx) = self.compile_receiver {
+        let result = if let Some(ref rx) = self.compile_receiver {
             match rx.try_recv() {
-                Ok(result) => {
-                    self.compile_receiver = None;
-                    self.is_compiling = false;
-                    Some(result)
-                }
-                Err(mpsc::TryRecvError::Empty) => None,
+                Ok(result) => result,
+                Err(mpsc::TryRecvError::Empty) => return None,
                 Err(mpsc::TryRecvError::Disconnected) => {
-                    self.compile_receiver = None;
-                    self.is_compiling = false;
-                    Some(Err("Compilation thread terminated unexpectedly".to_string()))
+                    Err("Compilation thread terminated unexpectedly".to_string())
                 }
             }
         } else {
-            None
+            return None;
+        };
+        self.compile_receiver = None;
+        self.is_compiling = false;
+
+        let result = match self.current_custom_compile.take() {
+            Some(job) => {
+                let result = result
+                    .and_then(|_| self.load_compiled_synthdef(&job.output_dir, &job.synthdef_name));
+                self.record_import_result(&job.synthdef_name, &result);
+                result.map_err(|e| format!("Import error ({}): {}", job.synthdef_name, e))
+            }
+            None => result,
+        };
+
+        // Move on to the next queued import; one that can't start is recorded and skipped
+        while let Some(job) = self.custom_compile_queue.pop_front() {
+            let name = job.synthdef_name.clone();
+            match self.start_custom_compile(job) {
+                Ok(()) => break,
+                Err(e) => self.record_import_result(&name, &Err(e)),
+            }
         }
+
+        Some(result)
     }
 
-    fn run_sclang(scd_path: &PathBuf) -> Result<String, String> {
-        let sclang_paths = [
-            "sclang",
-            "/Applications/SuperCollider.app/Contents/MacOS/sclang",
-            "/usr/local/bin/sclang",
-            "/usr/bin/sclang",
-        ];
+    /// Names of imported synthdefs that failed since the last call
+    pub fn take_failed_custom_compiles(&mut self) -> Vec<String> {
+        std::mem::take(&mut self.failed_custom_compiles)
+    }
 
-        for path in &sclang_paths {
+    fn record_import_result(&mut self, name: &str, result: &Result<String, String>) {
+        if result.is_err() {
+            self.failed_custom_compiles.push(name.to_string());
+        }
+        if let Some(report) = self.import_report.as_mut() {
+            match result {
+                Ok(_) => report.loaded.push(name.to_string()),
+                Err(e) => report.failed.push((name.to_string(), e.clone())),
+            }
+        }
+    }
+
+    /// Load a freshly compiled imported synthdef into scsynth, if it is running
+    fn load_compiled_synthdef(&self, output_dir: &Path, name: &str) -> Result<String, String> {
+        if !self.is_running {
+            return Ok(format!("Compiled custom synthdef: {} (loads when the server starts)", name));
+        }
+        let scsyndef_path = output_dir.join(format!("{}.scsyndef", name));
+        if scsyndef_path.exists() {
+            self.load_synthdef_file(&scsyndef_path)?;
+        } else {
+            // The script may name its output differently; load the whole directory
+            self.load_synthdefs(output_dir)?;
+        }
+        Ok(format!("Loaded custom synthdef: {}", name))
+    }
+
+    /// Run sclang on a script, killing it after `timeout`. Output is drained on
+    /// reader threads so a chatty sclang can't block on a full pipe.
+    fn run_sclang_script(script: &Path, candidates: &[PathBuf], timeout: Duration) -> Result<String, String> {
+        let mut last_error = None;
+        let mut child = candidates
+            .iter()
+            .find_map(|sclang| {
+                Command::new(sclang)
+                    .arg(script)
+                    .stdin(Stdio::null())
+                    .stdout(Stdio::piped())
+                    .stderr(Stdio::piped())
+                    .spawn()
+                    .map_err(|e| last_error = Some(e))
+                    .ok()
+            })
+            .ok_or_else(|| not_found_error("sclang", candidates, last_error.as_ref()))?;
+        let mut output = ServerOutput::capture(&mut child, None);
+
+        let start = Instant::now();
+        loop {
+            match child.try_wait() {
+                Ok(Some(_)) => break,
+                Ok(None) if start.elapsed() > timeout => {
+                    let _ = child.kill();
+                    let _ = child.wait();
+                    return Err(format!("sclang timed out after {}s", timeout.as_secs()));
+                }
+                Ok(None) => thread::sleep(Duration::from_millis(100)),
+                Err(e) => return Err(format!("Error waiting for sclang: {}", e)),
+            }
+        }
+        output.finish();
+
+        let errors: Vec<String> = output
+            .tail(super::server_output::SERVER_LOG_LINES)
+            .into_iter()
+            .filter(|line| line.contains("ERROR:") || line.contains("FAILURE"))
+            .collect();
+        if errors.is_empty() {
+            Ok("Synthdef compiled".to_string())
+        } else {
+            Err(format!("sclang error: {}", errors.join(" | ")))
+        }
+    }
+
+    fn run_sclang(scd_path: &PathBuf, candidates: &[PathBuf]) -> Result<String, String> {
+        let mut last_error = None;
+        for path in candidates {
             match Command::new(path).arg(scd_path).output() {
                 Ok(output) => {
                     if output.status.success() {
@@ -360,16 +875,18 @@ impl AudioEngine {
                         return Err(format!("Compilation failed: {}", stderr));
                     }
                 }
-                Err(_) => continue,
+                Err(e) => last_error = Some(e),
             }
         }
 
-        Err("Could not find sclang. Install SuperCollider.".to_string())
+        Err(not_found_error("sclang", candidates, last_error.as_ref()))
     }
 
     pub fn connect(&mut self, server_addr: &str) -> std::io::Result<()> {
         let client = OscClient::new(server_addr)?;
         client.send_message("/notify", vec![rosc::OscType::Int(1)])?;
+        // Ask for the server sample rate (used to resample loaded samples)
+        client.send_message("/status", vec![])?;
         self.client = Some(client);
         self.is_running = true;
         self.server_status = ServerStatus::Connected;
@@ -390,6 +907,8 @@ impl AudioEngine {
                 rosc::OscType::Int(node_id),
                 rosc::OscType::Int(3), // addAfter
                 rosc::OscType::Int(GROUP_OUTPUT),
+                rosc::OscType::String("in".to_string()),
+                rosc::OscType::Float(self.master_output as f32),
             ];
             if client.send_message("/s_new", args).is_ok() {
                 self.meter_node_id = Some(node_id);
@@ -397,28 +916,68 @@ impl AudioEngine {
         }
     }
 
+    /// (Re)create the master stage on the master pair. It runs after the record
+    /// group, so only what is heard changes: the meter and recordings keep the mix.
+    fn restart_master_stage(&mut self) {
+        if let Some(node_id) = self.master_node_id.take() {
+            if let Some(ref client) = self.client {
+                let _ = client.free_node(node_id);
+            }
+        }
+        if let Some(ref client) = self.client {
+            let node_id = self.next_node_id;
+            self.next_node_id += 1;
+            let args: Vec<rosc::OscType> = vec![
+                rosc::OscType::String("ilex_master".to_string()),
+                rosc::OscType::Int(node_id),
+                rosc::OscType::Int(3), // addAfter
+                rosc::OscType::Int(GROUP_RECORD),
+                rosc::OscType::String("out".to_string()),
+                rosc::OscType::Float(self.master_output as f32),
+                rosc::OscType::String("mono".to_string()),
+                rosc::OscType::Float(if self.mono_check { 1.0 } else { 0.0 }),
+            ];
+            if client.send_message("/s_new", args).is_ok() {
+                self.master_node_id = Some(node_id);
+            }
+        }
+    }
+
+    /// Sum the master pair to mono on the master stage, or return it to stereo
+    pub fn set_mono_check(&mut self, on: bool) {
+        self.mono_check = on;
+        if let (Some(client), Some(node_id)) = (&self.client, self.master_node_id) {
+            let _ = client.set_param(node_id, "mono", if on { 1.0 } else { 0.0 });
+        }
+    }
+
     pub fn disconnect(&mut self) {
         self.stop_recording();
         if let Some(ref client) = self.client {
             if let Some(node_id) = self.meter_node_id.take() {
                 let _ = client.free_node(node_id);
             }
+            if let Some(node_id) = self.master_node_id.take() {
+                let _ = client.free_node(node_id);
+            }
             for nodes in self.node_map.values() {
                 for node_id in nodes.all_node_ids() {
                     let _ = client.free_node(node_id);
                 }
             }
             // Free all loaded sample buffers
-            for &bufnum in self.buffer_map.values() {
-                let _ = client.free_buffer(bufnum);
+            for buffer in self.buffer_map.values() {
+                let _ = client.free_buffer(buffer.bufnum);
             }
         }
         self.node_map.clear();
         self.send_node_map.clear();
         self.bus_node_map.clear();
+        self.bus_effect_node_map.clear();
         self.bus_audio_buses.clear();
         self.voice_chains.clear();
         self.buffer_map.clear();
+        self.pending_buffer_reads.clear();
         self.bus_allocator.reset();
         self.groups_created = false;
         self.client = None;
@@ -455,12 +1014,55 @@ impl AudioEngine {
         et.synth_def_name()
     }
 
+    /// Controls for an effect synth reading `in_bus` and writing `out_bus`
+    fn effect_params(&self, effect: &EffectSlot, in_bus: i32, out_bus: i32, bpm: f32, bypassed: bool) -> Vec<(String, f32)> {
+        let mut params: Vec<(String, f32)> = vec![
+            ("in".to_string(), in_bus as f32),
+            ("out".to_string(), out_bus as f32),
+        ];
+        for p in &effect.params {
+            // For SidechainComp, resolve sc_bus to actual SC audio bus number
+            if effect.effect_type == EffectType::SidechainComp && p.name == "sc_bus" {
+                let bus_id = match &p.value {
+                    ParamValue::Int(v) => *v as u8,
+                    _ => 0,
+                };
+                let sidechain_in = if bus_id == 0 {
+                    0.0 // SynthDef uses self as sidechain
+                } else {
+                    self.bus_audio_buses.get(&bus_id).copied().unwrap_or(0) as f32
+                };
+                params.push(("sidechain_in".to_string(), sidechain_in));
+                continue;
+            }
+            let val = match &p.value {
+                ParamValue::Float(v) => *v,
+                ParamValue::Int(v) => *v as f32,
+                ParamValue::Bool(v) => if *v { 1.0 } else { 0.0 },
+            };
+            params.push((p.name.clone(), val));
+        }
+        if let Some(time) = effect.synced_time(bpm) {
+            if let Some(entry) = params.iter_mut().find(|(name, _)| name == "time") {
+                entry.1 = time;
+            }
+        }
+        params.push(("bypass".to_string(), if bypassed { 1.0 } else { 0.0 }));
+        params.push(("lag".to_string(), self.param_lag));
+        params
+    }
+
     /// Rebuild all routing based on instrument state.
     /// Per instrument, create a deterministic synth chain:
     /// 1. Source synth
     /// 2. Optional filter synth
     /// 3. Effect synths in order
     /// 4. Output synth with level/pan/mute
+    ///
+    /// Then, in the output group and in this order: send synths copying each
+    /// instrument's source bus onto mixer buses, each bus's effect chain, and the bus
+    /// output synths. A bus with a reverb on it is an aux return: send level sets how
+    /// much reaches the reverb, and its output joins master through the bus fader.
     pub fn rebuild_instrument_routing(&mut self, state: &InstrumentState, session: &SessionState) -> Result<(), String> {
         if !self.is_running {
             return Ok(());
@@ -481,6 +1083,9 @@ impl AudioEngine {
             for &node_id in self.bus_node_map.values() {
                 let _ = client.free_node(node_id);
             }
+            for &node_id in self.bus_effect_node_map.values().flatten() {
+                let _ = client.free_node(node_id);
+            }
             for chain in self.voice_chains.drain(..) {
                 let _ = client.free_node(chain.group_id);
             }
@@ -488,6 +1093,7 @@ impl AudioEngine {
         self.node_map.clear();
         self.send_node_map.clear();
         self.bus_node_map.clear();
+        self.bus_effect_node_map.clear();
         self.bus_audio_buses.clear();
         self.bus_allocator.reset();
 
@@ -506,7 +1112,8 @@ impl AudioEngine {
 
         for instrument in &state.instruments {
             let mut source_node: Option<i32> = None;
-            let mut lfo_node: Option<i32> = None;
+            let mut lfo_nodes: Vec<(usize, i32)> = Vec::new();
+            let mut mod_route_nodes: Vec<i32> = Vec::new();
             let mut filter_node: Option<i32> = None;
             let mut effect_nodes: Vec<i32> = Vec::new();
 
@@ -522,6 +1129,7 @@ impl AudioEngine {
                 let mut params: Vec<(String, f32)> = vec![
                     ("out".to_string(), source_out_bus as f32),
                     ("strip_id".to_string(), instrument.id as f32),
+                    ("lag".to_string(), self.param_lag),
                 ];
                 // Add source params (gain, channel, test_tone, test_freq)
                 for p in &instrument.source_params {
@@ -577,6 +1185,7 @@ impl AudioEngine {
                     ("in".to_string(), bus_audio_bus as f32),
                     ("gain".to_string(), gain),
                     ("strip_id".to_string(), instrument.id as f32),
+                    ("lag".to_string(), self.param_lag),
                 ];
 
                 let client = self.client.as_ref().ok_or("Not connected")?;
@@ -591,17 +1200,20 @@ impl AudioEngine {
             }
             // For oscillator instruments, voices are spawned dynamically via spawn_voice()
 
-            // LFO (if enabled)
-            let lfo_control_bus: Option<i32> = if instrument.lfo.enabled {
+            // LFOs: each enabled one writes its own control bus
+            for (i, lfo) in instrument.lfos.iter().enumerate() {
+                if !lfo.enabled {
+                    continue;
+                }
                 let lfo_node_id = self.next_node_id;
                 self.next_node_id += 1;
-                let lfo_out_bus = self.bus_allocator.get_or_alloc_control_bus(instrument.id, "lfo_out");
+                let lfo_out_bus = self.bus_allocator.get_or_alloc_control_bus(instrument.id, &Self::lfo_bus_port(i));
 
                 let params = vec![
                     ("out".to_string(), lfo_out_bus as f32),
-                    ("rate".to_string(), instrument.lfo.rate),
-                    ("depth".to_string(), instrument.lfo.depth),
-                    ("shape".to_string(), instrument.lfo.shape.index() as f32),
+                    ("rate".to_string(), lfo.rate_hz(session.bpm as f32)),
+                    ("depth".to_string(), lfo.depth),
+                    ("shape".to_string(), lfo.shape.index() as f32),
                 ];
 
                 let client = self.client.as_ref().ok_or("Not connected")?;
@@ -612,11 +1224,36 @@ impl AudioEngine {
                     &params,
                 ).map_err(|e| e.to_string())?;
 
-                lfo_node = Some(lfo_node_id);
-                Some(lfo_out_bus)
-            } else {
-                None
-            };
+                lfo_nodes.push((i, lfo_node_id));
+            }
+
+            // Mod matrix, instrument-level destinations: each LFO routing scales its LFO
+            // onto the destination's mod bus, where several routings sum. The synths that
+            // own a destination read its bus through their `*_mod_in` control. Envelopes
+            // and per-voice destinations are wired up per note in spawn_voice().
+            let mut mod_inputs: Vec<(&'static str, i32)> = Vec::new();
+            for routing in instrument.active_routings() {
+                let ModRouteSource::Lfo(lfo_idx) = routing.source else { continue };
+                let Some(mod_input) = mod_input(routing.destination) else { continue };
+                let Some(in_bus) = self.bus_allocator.get_control_bus(instrument.id, &Self::lfo_bus_port(lfo_idx)) else {
+                    continue;
+                };
+                let out_bus = self.bus_allocator.get_or_alloc_control_bus(instrument.id, mod_input);
+                let node_id = self.next_node_id;
+                self.next_node_id += 1;
+                let params = vec![
+                    ("in".to_string(), in_bus as f32),
+                    ("out".to_string(), out_bus as f32),
+                    ("amount".to_string(), routing.amount),
+                ];
+                let client = self.client.as_ref().ok_or("Not connected")?;
+                client.create_synth_in_group("ilex_mod_route", node_id, GROUP_SOURCES, &params)
+                    .map_err(|e| e.to_string())?;
+                mod_route_nodes.push(node_id);
+                if !mod_inputs.iter().any(|&(name, _)| name == mod_input) {
+                    mod_inputs.push((mod_input, out_bus));
+                }
+            }
 
             // Filter (if present)
             if let Some(ref filter) = instrument.filter {
@@ -624,20 +1261,14 @@ impl AudioEngine {
                 self.next_node_id += 1;
                 let filter_out_bus = self.bus_allocator.get_or_alloc_audio_bus(instrument.id, "filter_out");
 
-                // Determine if LFO should modulate the filter cutoff
-                let cutoff_mod_bus = if instrument.lfo.enabled && instrument.lfo.target == crate::state::LfoTarget::FilterCutoff {
-                    lfo_control_bus.map(|b| b as f32).unwrap_or(-1.0)
-                } else {
-                    -1.0 // No modulation
-                };
-
-                let params = vec![
+                let mut params = vec![
                     ("in".to_string(), current_bus as f32),
                     ("out".to_string(), filter_out_bus as f32),
                     ("cutoff".to_string(), filter.cutoff.value),
                     ("resonance".to_string(), filter.resonance.value),
-                    ("cutoff_mod_in".to_string(), cutoff_mod_bus),
+                    ("lag".to_string(), self.param_lag),
                 ];
+                params.extend(mod_input_params(&mod_inputs, &["cutoff_mod_in", "res_mod_in"]));
 
                 let client = self.client.as_ref().ok_or("Not connected")?;
                 client.create_synth_in_group(
@@ -663,32 +1294,9 @@ impl AudioEngine {
                     &format!("fx_{}_out", i),
                 );
 
-                let mut params: Vec<(String, f32)> = vec![
-                    ("in".to_string(), current_bus as f32),
-                    ("out".to_string(), effect_out_bus as f32),
-                ];
-                for p in &effect.params {
-                    // For SidechainComp, resolve sc_bus to actual SC audio bus number
-                    if effect.effect_type == EffectType::SidechainComp && p.name == "sc_bus" {
-                        let bus_id = match &p.value {
-                            ParamValue::Int(v) => *v as u8,
-                            _ => 0,
-                        };
-                        let sidechain_in = if bus_id == 0 {
-                            0.0 // SynthDef uses self as sidechain
-                        } else {
-                            self.bus_audio_buses.get(&bus_id).copied().unwrap_or(0) as f32
-                        };
-                        params.push(("sidechain_in".to_string(), sidechain_in));
-                        continue;
-                    }
-                    let val = match &p.value {
-                        ParamValue::Float(v) => *v,
-                        ParamValue::Int(v) => *v as f32,
-                        ParamValue::Bool(v) => if *v { 1.0 } else { 0.0 },
-                    };
-                    params.push((p.name.clone(), val));
-                }
+                let bypassed = session.effective_effect_bypass(state, instrument, effect);
+                let mut params = self.effect_params(effect, current_bus, effect_out_bus, session.bpm as f32, bypassed);
+                params.extend(mod_input_params(&mod_inputs, effect_mod_inputs(effect.effect_type)));
 
                 let client = self.client.as_ref().ok_or("Not connected")?;
                 client.create_synth_in_group(
@@ -704,17 +1312,21 @@ impl AudioEngine {
 
             // Output synth
             let output_node_id;
+            let master_output = self.hardware_output(session.master_output_channel);
             {
                 let node_id = self.next_node_id;
                 self.next_node_id += 1;
-                let any_solo = state.any_instrument_solo();
-                let mute = if any_solo { !instrument.solo } else { instrument.mute || session.master_mute };
-                let params = vec![
+                let mute = session.effective_instrument_mute(state, instrument);
+                let mut params = vec![
                     ("in".to_string(), current_bus as f32),
+                    ("trim".to_string(), instrument.trim_amp()),
                     ("level".to_string(), instrument.level * session.master_level),
                     ("mute".to_string(), if mute { 1.0 } else { 0.0 }),
-                    ("pan".to_string(), instrument.pan),
+                    ("pan".to_string(), session.effective_instrument_pan(state, instrument)),
+                    ("out".to_string(), master_output as f32),
+                    ("lag".to_string(), self.param_lag),
                 ];
+                params.extend(mod_input_params(&mod_inputs, &["pan_mod_in"]));
 
                 let client = self.client.as_ref().ok_or("Not connected")?;
                 client.create_synth_in_group(
@@ -729,7 +1341,8 @@ impl AudioEngine {
 
             self.node_map.insert(instrument.id, InstrumentNodes {
                 source: source_node,
-                lfo: lfo_node,
+                lfos: lfo_nodes,
+                mod_routes: mod_route_nodes,
                 filter: filter_node,
                 effects: effect_nodes,
                 output: output_node_id,
@@ -752,11 +1365,17 @@ impl AudioEngine {
                 if let Some(&bus_audio) = self.bus_audio_buses.get(&send.bus_id) {
                     let node_id = self.next_node_id;
                     self.next_node_id += 1;
-                    let params = vec![
+                    // Sends tap the source bus, before the output synth, so they apply the trim themselves
+                    let muted = session.effective_instrument_mute(state, instrument);
+                    let mut params = vec![
                         ("in".to_string(), instrument_audio_bus as f32),
                         ("out".to_string(), bus_audio as f32),
-                        ("level".to_string(), send.level),
+                        ("level".to_string(), Self::send_level(send, instrument.trim_amp(), muted)),
+                        ("lag".to_string(), self.param_lag),
                     ];
+                    if let Some(bus) = self.bus_allocator.get_control_bus(instrument.id, "level_mod_in") {
+                        params.push(("level_mod_in".to_string(), bus as f32));
+                    }
                     if let Some(ref client) = self.client {
                         client
                             .create_synth_in_group("ilex_send", node_id, GROUP_OUTPUT, &params)
@@ -767,17 +1386,49 @@ impl AudioEngine {
             }
         }
 
+        // Bus effect chains, after every send has written to the bus and before its
+        // output synth reads it
+        let mut bus_chain_out: HashMap<u8, i32> = HashMap::new();
+        for bus in &session.buses {
+            let Some(&bus_audio) = self.bus_audio_buses.get(&bus.id) else { continue };
+            let mut current_bus = bus_audio;
+            let mut effect_nodes = Vec::new();
+            for (i, effect) in bus.effects.iter().enumerate() {
+                if !effect.enabled {
+                    continue;
+                }
+                let node_id = self.next_node_id;
+                self.next_node_id += 1;
+                let effect_out_bus = self.bus_allocator.get_or_alloc_audio_bus(
+                    u32::MAX - bus.id as u32,
+                    &format!("fx_{}_out", i),
+                );
+                let params = self.effect_params(effect, current_bus, effect_out_bus, session.bpm as f32, effect.bypassed);
+                if let Some(ref client) = self.client {
+                    client
+                        .create_synth_in_group(Self::effect_synth_def(effect.effect_type), node_id, GROUP_OUTPUT, &params)
+                        .map_err(|e| e.to_string())?;
+                }
+                effect_nodes.push(node_id);
+                current_bus = effect_out_bus;
+            }
+            bus_chain_out.insert(bus.id, current_bus);
+            self.bus_effect_node_map.insert(bus.id, effect_nodes);
+        }
+
         // Create bus output synths
         for bus in &session.buses {
-            if let Some(&bus_audio) = self.bus_audio_buses.get(&bus.id) {
+            if let Some(&bus_audio) = bus_chain_out.get(&bus.id) {
                 let node_id = self.next_node_id;
                 self.next_node_id += 1;
-                let mute = session.effective_bus_mute(bus);
+                let mute = session.effective_bus_mute(state, bus);
                 let params = vec![
                     ("in".to_string(), bus_audio as f32),
                     ("level".to_string(), bus.level),
                     ("mute".to_string(), if mute { 1.0 } else { 0.0 }),
-                    ("pan".to_string(), bus.pan),
+                    ("pan".to_string(), session.effective_bus_pan(state, bus)),
+                    ("out".to_string(), self.hardware_output(bus.output_channel) as f32),
+                    ("lag".to_string(), self.param_lag),
                 ];
                 if let Some(ref client) = self.client {
                     client
@@ -788,8 +1439,10 @@ impl AudioEngine {
             }
         }
 
-        // (Re)create meter synth
+        // (Re)create meter and master stage synths
+        self.master_output = self.hardware_output(session.master_output_channel);
         self.restart_meter();
+        self.restart_master_stage();
 
         Ok(())
     }
@@ -806,25 +1459,157 @@ impl AudioEngine {
         Ok(())
     }
 
-    /// Update all instrument output mixer params (level, mute, pan) in real-time without rebuilding the graph
+    /// Update all instrument and bus output mixer params (level, mute, pan) and effect
+    /// bypasses in real-time without rebuilding the graph. Mutes, pans and bypasses are
+    /// resolved through the session's solo logic.
     pub fn update_all_instrument_mixer_params(&self, state: &InstrumentState, session: &SessionState) -> Result<(), String> {
         if !self.is_running { return Ok(()); }
         let client = self.client.as_ref().ok_or("Not connected")?;
-        let any_solo = state.any_instrument_solo();
+        for bus in &session.buses {
+            if self.bus_node_map.contains_key(&bus.id) {
+                let mute = session.effective_bus_mute(state, bus);
+                self.set_bus_mixer_params(bus.id, bus.level, mute, session.effective_bus_pan(state, bus))?;
+            }
+        }
         for instrument in &state.instruments {
             if let Some(nodes) = self.node_map.get(&instrument.id) {
-                let mute = instrument.mute || session.master_mute || (any_solo && !instrument.solo);
+                let mute = session.effective_instrument_mute(state, instrument);
                 client.set_param(nodes.output, "level", instrument.level * session.master_level)
                     .map_err(|e| e.to_string())?;
                 client.set_param(nodes.output, "mute", if mute { 1.0 } else { 0.0 })
                     .map_err(|e| e.to_string())?;
-                client.set_param(nodes.output, "pan", instrument.pan)
+                client.set_param(nodes.output, "pan", session.effective_instrument_pan(state, instrument))
                     .map_err(|e| e.to_string())?;
+                for (idx, effect) in instrument.effects.iter().enumerate() {
+                    self.set_effect_bypass(instrument, idx, session.effective_effect_bypass(state, instrument, effect))?;
+                }
+            }
+        }
+        for (idx, instrument) in state.instruments.iter().enumerate() {
+            let muted = session.effective_instrument_mute(state, instrument);
+            for send in &instrument.sends {
+                if let Some(&node_id) = self.send_node_map.get(&(idx, send.bus_id)) {
+                    client.set_param(node_id, "level", Self::send_level(send, instrument.trim_amp(), muted))
+                        .map_err(|e| e.to_string())?;
+                }
             }
         }
         Ok(())
     }
 
+    /// Level of a send synth. Muting an instrument (or muting it through solo)
+    /// closes its sends as well as its output, so no new signal reaches the bus,
+    /// but the bus and its effects keep running: a reverb tail already on the bus
+    /// rings out instead of being cut. Muting the bus itself cuts the return, tail
+    /// and all.
+    fn send_level(send: &MixerSend, trim_amp: f32, muted: bool) -> f32 {
+        if muted { 0.0 } else { send.level * trim_amp }
+    }
+
+    /// Bus-allocator port for an LFO's output
+    fn lfo_bus_port(index: usize) -> String {
+        format!("lfo_{}_out", index)
+    }
+
+    /// `/s_new` adding a synth to the tail of `group`
+    fn synth_msg(def: &str, node_id: i32, group: i32, params: &[(&str, f32)]) -> rosc::OscMessage {
+        let mut args = vec![
+            rosc::OscType::String(def.to_string()),
+            rosc::OscType::Int(node_id),
+            rosc::OscType::Int(1), // addToTail
+            rosc::OscType::Int(group),
+        ];
+        for (name, value) in params {
+            args.push(rosc::OscType::String(name.to_string()));
+            args.push(rosc::OscType::Float(*value));
+        }
+        rosc::OscMessage { addr: "/s_new".to_string(), args }
+    }
+
+    /// Restart the instrument's shared LFOs that retrigger with every note; they also
+    /// feed instrument-level destinations
+    fn lfo_reset_msgs(&self, instrument: &Instrument) -> Vec<rosc::OscMessage> {
+        let Some(nodes) = self.node_map.get(&instrument.id) else { return Vec::new() };
+        nodes.lfos.iter()
+            .filter(|&&(i, _)| instrument.lfos.get(i).is_some_and(|l| l.retrigger))
+            .map(|&(_, node)| rosc::OscMessage {
+                addr: "/n_set".to_string(),
+                args: vec![
+                    rosc::OscType::Int(node),
+                    rosc::OscType::String("t_reset".to_string()),
+                    rosc::OscType::Float(1.0),
+                ],
+            })
+            .collect()
+    }
+
+    /// Point the instrument's filter at a new note's key-tracked cutoff. The filter is
+    /// shared by all voices, so the most recent note wins. None when tracking is off.
+    fn keytrack_msg(&self, instrument: &Instrument, pitch: u8) -> Option<rosc::OscMessage> {
+        let filter = instrument.filter.as_ref().filter(|f| f.keytrack != 0.0)?;
+        let node = self.node_map.get(&instrument.id)?.filter?;
+        Some(rosc::OscMessage {
+            addr: "/n_set".to_string(),
+            args: vec![
+                rosc::OscType::Int(node),
+                rosc::OscType::String("keytrack".to_string()),
+                rosc::OscType::Float(filter.keytrack_ratio(pitch)),
+            ],
+        })
+    }
+
+    /// Re-send tempo-synced delay times and LFO rates after a tempo change
+    pub fn update_tempo_sync(&self, state: &InstrumentState, session: &SessionState) -> Result<(), String> {
+        if !self.is_running { return Ok(()); }
+        let client = self.client.as_ref().ok_or("Not connected")?;
+        for instrument in &state.instruments {
+            let shared = self.node_map.get(&instrument.id).into_iter().flat_map(|n| &n.lfos);
+            let voices = self.voice_chains.iter()
+                .filter(|v| v.instrument_id == instrument.id)
+                .flat_map(|v| &v.lfo_nodes);
+            for &(i, node) in shared.chain(voices) {
+                if let Some(lfo) = instrument.lfos.get(i).filter(|l| l.sync) {
+                    client.set_param(node, "rate", lfo.rate_hz(session.bpm as f32))
+                        .map_err(|e| e.to_string())?;
+                }
+            }
+            let Some(nodes) = self.node_map.get(&instrument.id) else { continue };
+            let enabled = instrument.effects.iter().filter(|e| e.enabled);
+            for (effect, &node) in enabled.zip(&nodes.effects) {
+                if let Some(time) = effect.synced_time(session.bpm as f32) {
+                    client.set_param(node, "time", time).map_err(|e| e.to_string())?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Crossfade an effect to dry or back without rebuilding the chain
+    pub fn set_effect_bypass(&self, instrument: &Instrument, effect_idx: usize, bypassed: bool) -> Result<(), String> {
+        if !self.is_running { return Ok(()); }
+        let client = self.client.as_ref().ok_or("Not connected")?;
+        if !instrument.effects.get(effect_idx).is_some_and(|e| e.enabled) {
+            return Ok(());
+        }
+        // Disabled effects have no node, so count the enabled ones before it
+        let enabled_idx = instrument.effects.iter().take(effect_idx).filter(|e| e.enabled).count();
+        if let Some(&node) = self.node_map.get(&instrument.id).and_then(|n| n.effects.get(enabled_idx)) {
+            client.set_param(node, "bypass", if bypassed { 1.0 } else { 0.0 })
+                .map_err(|e| e.to_string())?;
+        }
+        Ok(())
+    }
+
+    /// Set a filter parameter (cutoff/resonance) on an instrument's running filter node
+    pub fn set_filter_param(&self, instrument_id: InstrumentId, param: &str, value: f32) -> Result<(), String> {
+        if !self.is_running { return Ok(()); }
+        let client = self.client.as_ref().ok_or("Not connected")?;
+        if let Some(filter_node) = self.node_map.get(&instrument_id).and_then(|n| n.filter) {
+            client.set_param(filter_node, param, value).map_err(|e| e.to_string())?;
+        }
+        Ok(())
+    }
+
     /// Set a source parameter on an instrument in real-time.
     /// Updates the persistent source node (AudioIn) and all active voice source nodes.
     pub fn set_source_param(&self, instrument_id: InstrumentId, param: &str, value: f32) -> Result<(), String> {
@@ -854,7 +1639,9 @@ impl AudioEngine {
         // Set on all active voice source nodes (oscillator/sampler instruments)
         for voice in &self.voice_chains {
             if voice.instrument_id == instrument_id {
-                let _ = client.set_param(voice.source_node, param, value);
+                for &node in &voice.source_nodes {
+                    let _ = client.set_param(node, param, value);
+                }
             }
         }
 
@@ -884,20 +1671,70 @@ impl AudioEngine {
             return self.spawn_sampler_voice(instrument_id, pitch, velocity, offset_secs, state, session);
         }
 
+        // Global transpose changes what sounds. Voices stay keyed by the played pitch,
+        // so note-offs find them even if the transpose changes while a note is held.
+        let played = pitch;
+        let pitch = session.transposed(pitch);
+        let voice_pan = self.next_voice_pan(instrument, pitch);
         let client = self.client.as_ref().ok_or("Not connected")?;
 
-        // Voice-steal: if at limit, free oldest by spawn_time
-        let count = self.voice_chains.iter().filter(|v| v.instrument_id == instrument_id).count();
-        if count >= MAX_VOICES_PER_INSTRUMENT {
-            if let Some(pos) = self.voice_chains.iter()
+        let tuning = session.tuning_a4 as f64;
+        let freq = tuning * (2.0_f64).powf((pitch as f64 - 69.0) / 12.0);
+
+        // Monophonic instruments keep one persistent voice: later notes re-pitch it,
+        // sliding legato over `glide` seconds (or retriggering it with no glide),
+        // instead of spawning a new chain
+        if !instrument.polyphonic {
+            if let Some(pos) = self.voice_chains.iter().position(|v| v.instrument_id == instrument_id) {
+                let node = self.voice_chains[pos].midi_node_id;
+                let gates = mono_gate_steps(instrument.glide);
+                let mut messages = self.lfo_reset_msgs(instrument);
+                messages.push(rosc::OscMessage {
+                    addr: "/n_set".to_string(),
+                    args: vec![
+                        rosc::OscType::Int(node),
+                        rosc::OscType::String("note".to_string()),
+                        rosc::OscType::Float(pitch as f32),
+                        rosc::OscType::String("freq".to_string()),
+                        rosc::OscType::Float(freq as f32),
+                        rosc::OscType::String("vel".to_string()),
+                        rosc::OscType::Float(velocity),
+                        rosc::OscType::String("glide".to_string()),
+                        rosc::OscType::Float(instrument.glide),
+                        rosc::OscType::String("gate".to_string()),
+                        rosc::OscType::Float(gates[0].1),
+                    ],
+                });
+                messages.extend(self.keytrack_msg(instrument, pitch));
+                let time = super::osc_client::osc_time_from_now(offset_secs);
+                client.send_bundle(messages, time).map_err(|e| e.to_string())?;
+                for &(delay, gate) in &gates[1..] {
+                    let time = super::osc_client::osc_time_from_now(offset_secs + delay);
+                    client.set_params_bundled(node, &[("gate", gate)], time).map_err(|e| e.to_string())?;
+                }
+                self.voice_chains[pos].pitch = played;
+                return Ok(());
+            }
+        }
+
+        // Voice-steal: each unison copy counts as a voice, so free the oldest notes
+        // until this one fits
+        let unison = instrument.unison_spread();
+        loop {
+            let used = voices_used(&self.voice_chains, instrument_id);
+            if used + unison.len() <= MAX_VOICES_PER_INSTRUMENT {
+                break;
+            }
+            let Some(pos) = self.voice_chains.iter()
                 .enumerate()
                 .filter(|(_, v)| v.instrument_id == instrument_id)
                 .min_by_key(|(_, v)| v.spawn_time)
                 .map(|(i, _)| i)
-            {
-                let old = self.voice_chains.remove(pos);
-                let _ = client.free_node(old.group_id);
-            }
+            else {
+                break;
+            };
+            let old = self.voice_chains.remove(pos);
+            let _ = client.free_node(old.group_id);
         }
 
         // Get the audio bus where voices should write their output
@@ -915,9 +1752,6 @@ impl AudioEngine {
         let voice_vel_bus = self.next_voice_control_bus;
         self.next_voice_control_bus += 1;
 
-        let tuning = session.tuning_a4 as f64;
-        let freq = tuning * (2.0_f64).powf((pitch as f64 - 69.0) / 12.0);
-
         let mut messages: Vec<rosc::OscMessage> = Vec::new();
 
         // 1. Create group
@@ -945,6 +1779,7 @@ impl AudioEngine {
                 ("freq".to_string(), freq as f32),
                 ("vel".to_string(), velocity),
                 ("gate".to_string(), 1.0),
+                ("glide".to_string(), if instrument.polyphonic { 0.0 } else { instrument.glide }),
                 ("freq_out".to_string(), voice_freq_bus as f32),
                 ("gate_out".to_string(), voice_gate_bus as f32),
                 ("vel_out".to_string(), voice_vel_bus as f32),
@@ -959,13 +1794,87 @@ impl AudioEngine {
             });
         }
 
-        // 3. Source synth
+        // Mod matrix routings to per-voice destinations (pitch, amp): each destination gets
+        // a voice-local bus its routings sum onto. Envelopes, and LFOs retriggered on a
+        // polyphonic instrument, run inside the voice so every note starts at phase 0.
+        let bpm = session.bpm as f32;
+        messages.extend(self.lfo_reset_msgs(instrument));
+        let mut voice_lfo_nodes: Vec<(usize, i32)> = Vec::new();
+        let mut source_buses: Vec<(ModRouteSource, i32)> = Vec::new();
+        let mut dest_buses: Vec<(LfoTarget, i32)> = Vec::new();
+        let routings = instrument.active_routings().filter(|r| is_voice_destination(r.destination));
+        for routing in routings {
+            let source_bus = match source_buses.iter().find(|(s, _)| *s == routing.source) {
+                Some(&(_, bus)) => bus,
+                None => {
+                    let bus = match routing.source {
+                        ModRouteSource::Lfo(i) => {
+                            let lfo = &instrument.lfos[i];
+                            if lfo.retrigger && instrument.polyphonic {
+                                let (node_id, bus) = (self.next_node_id, self.next_voice_control_bus);
+                                self.next_node_id += 1;
+                                self.next_voice_control_bus += 1;
+                                messages.push(Self::synth_msg("ilex_lfo", node_id, group_id, &[
+                                    ("out", bus as f32),
+                                    ("rate", lfo.rate_hz(bpm)),
+                                    ("depth", lfo.depth),
+                                    ("shape", lfo.shape.index() as f32),
+                                ]));
+                                voice_lfo_nodes.push((i, node_id));
+                                bus
+                            } else {
+                                match self.bus_allocator.get_control_bus(instrument_id, &Self::lfo_bus_port(i)) {
+                                    Some(bus) => bus,
+                                    None => continue,
+                                }
+                            }
+                        }
+                        ModRouteSource::Envelope(i) => {
+                            let env = &instrument.mod_envelopes[i];
+                            let (node_id, bus) = (self.next_node_id, self.next_voice_control_bus);
+                            self.next_node_id += 1;
+                            self.next_voice_control_bus += 1;
+                            messages.push(Self::synth_msg("ilex_mod_env", node_id, group_id, &[
+                                ("out", bus as f32),
+                                ("gate_in", voice_gate_bus as f32),
+                                ("attack", env.attack),
+                                ("decay", env.decay),
+                                ("sustain", env.sustain),
+                                ("release", env.release),
+                            ]));
+                            bus
+                        }
+                    };
+                    source_buses.push((routing.source, bus));
+                    bus
+                }
+            };
+            let dest_bus = match dest_buses.iter().find(|(d, _)| *d == routing.destination) {
+                Some(&(_, bus)) => bus,
+                None => {
+                    let bus = self.next_voice_control_bus;
+                    self.next_voice_control_bus += 1;
+                    dest_buses.push((routing.destination, bus));
+                    bus
+                }
+            };
+            let node_id = self.next_node_id;
+            self.next_node_id += 1;
+            messages.push(Self::synth_msg("ilex_mod_route", node_id, group_id, &[
+                ("in", source_bus as f32),
+                ("out", dest_bus as f32),
+                ("amount", routing.amount),
+            ]));
+        }
+
+        // 3. Source synths, one per unison copy, detuned and spread across the stereo field
         let source_node_id = self.next_node_id;
-        self.next_node_id += 1;
-        {
+        self.next_node_id += unison.len() as i32;
+        let unison_amp = 1.0 / (unison.len() as f32).sqrt();
+        for (copy, &(detune_cents, pan)) in unison.iter().enumerate() {
             let mut args: Vec<rosc::OscType> = vec![
                 rosc::OscType::String(Self::source_synth_def(instrument.source, &session.custom_synthdefs)),
-                rosc::OscType::Int(source_node_id),
+                rosc::OscType::Int(source_node_id + copy as i32),
                 rosc::OscType::Int(1),
                 rosc::OscType::Int(group_id),
             ];
@@ -984,6 +1893,14 @@ impl AudioEngine {
             args.push(rosc::OscType::Float(voice_freq_bus as f32));
             args.push(rosc::OscType::String("gate_in".to_string()));
             args.push(rosc::OscType::Float(voice_gate_bus as f32));
+            for (destination, bus) in &dest_buses {
+                let param = match destination {
+                    LfoTarget::Pitch => "pitch_mod_in",
+                    _ => "amp_mod_in",
+                };
+                args.push(rosc::OscType::String(param.to_string()));
+                args.push(rosc::OscType::Float(*bus as f32));
+            }
             // Amp envelope (ADSR)
             args.push(rosc::OscType::String("attack".to_string()));
             args.push(rosc::OscType::Float(instrument.amp_envelope.attack));
@@ -993,6 +1910,20 @@ impl AudioEngine {
             args.push(rosc::OscType::Float(instrument.amp_envelope.sustain));
             args.push(rosc::OscType::String("release".to_string()));
             args.push(rosc::OscType::Float(instrument.amp_envelope.release));
+            if unison.len() > 1 {
+                args.push(rosc::OscType::String("detune".to_string()));
+                args.push(rosc::OscType::Float(detune_cents / 100.0));
+                args.push(rosc::OscType::String("unison_amp".to_string()));
+                args.push(rosc::OscType::Float(unison_amp));
+            }
+            // Unison copies spread around the voice's own place in the stereo field
+            let pan = (pan + voice_pan).clamp(-1.0, 1.0);
+            if pan != 0.0 {
+                args.push(rosc::OscType::String("pan".to_string()));
+                args.push(rosc::OscType::Float(pan));
+            }
+            args.push(rosc::OscType::String("lag".to_string()));
+            args.push(rosc::OscType::Float(self.param_lag));
             // Output to source_out_bus
             args.push(rosc::OscType::String("out".to_string()));
             args.push(rosc::OscType::Float(source_out_bus as f32));
@@ -1003,6 +1934,8 @@ impl AudioEngine {
             });
         }
 
+        messages.extend(self.keytrack_msg(instrument, pitch));
+
         // Send all as one timed bundle
         let time = super::osc_client::osc_time_from_now(offset_secs);
         client
@@ -1011,10 +1944,12 @@ impl AudioEngine {
 
         self.voice_chains.push(VoiceChain {
             instrument_id,
-            pitch,
+            pitch: played,
             group_id,
             midi_node_id,
-            source_node: source_node_id,
+            source_nodes: (0..unison.len() as i32).map(|i| source_node_id + i).collect(),
+            lfo_nodes: voice_lfo_nodes,
+            voices: unison.len(),
             spawn_time: Instant::now(),
         });
 
@@ -1022,6 +1957,14 @@ impl AudioEngine {
     }
 
     /// Spawn a sampler voice (separate method for sampler-specific handling)
+    /// Stereo offset for an instrument's next voice, advancing its spawn count
+    fn next_voice_pan(&mut self, instrument: &Instrument, pitch: u8) -> f32 {
+        let order = self.voice_spread_counts.entry(instrument.id).or_insert(0);
+        let pan = instrument.voice_pan(pitch, *order);
+        *order = order.wrapping_add(1);
+        pan
+    }
+
     fn spawn_sampler_voice(
         &mut self,
         instrument_id: InstrumentId,
@@ -1040,20 +1983,37 @@ impl AudioEngine {
         let buffer_id = sampler_config.buffer_id
             .ok_or("Sampler has no buffer loaded")?;
 
-        let bufnum = self.buffer_map.get(&buffer_id)
-            .copied()
-            .ok_or("Buffer not loaded in audio engine")?;
+        if !self.buffer_map.contains_key(&buffer_id) {
+            return Err("Buffer not loaded in audio engine".to_string());
+        }
 
-        // Get slice for this note (or current selected slice)
-        let (slice_start, slice_end) = sampler_config.slice_for_note(pitch)
-            .map(|s| (s.start, s.end))
-            .unwrap_or((0.0, 1.0));
+        // Transposed like synth voices, so zones are picked by the sounding pitch
+        let played = pitch;
+        let pitch = session.transposed(pitch);
+        let voice_pan = self.next_voice_pan(instrument, pitch);
+
+        // Zones covering this pitch (notes outside every zone are silent), each
+        // resolved to the velocity layer(s) it should play
+        let vel = (velocity * 127.0).round().clamp(1.0, 127.0) as u8;
+        let hits: Vec<(&Slice, i32, f32)> = sampler_config.zones_for_note(pitch)
+            .into_iter()
+            .flat_map(|zone| {
+                layers_for_velocity(Some(buffer_id), &zone.layers, sampler_config.crossfade_layers, vel)
+                    .into_iter()
+                    .map(move |(layer_buffer, gain)| (zone, layer_buffer, gain))
+            })
+            .filter_map(|(zone, layer_buffer, gain)| {
+                self.buffer_map.get(&layer_buffer).map(|b| (zone, b.bufnum, gain))
+            })
+            .collect();
+        if hits.is_empty() {
+            return Ok(());
+        }
 
         let client = self.client.as_ref().ok_or("Not connected")?;
 
         // Voice-steal: if at limit, free oldest by spawn_time
-        let count = self.voice_chains.iter().filter(|v| v.instrument_id == instrument_id).count();
-        if count >= MAX_VOICES_PER_INSTRUMENT {
+        if voices_used(&self.voice_chains, instrument_id) >= MAX_VOICES_PER_INSTRUMENT {
             if let Some(pos) = self.voice_chains.iter()
                 .enumerate()
                 .filter(|(_, v)| v.instrument_id == instrument_id)
@@ -1124,49 +2084,58 @@ impl AudioEngine {
             });
         }
 
-        // 3. Sampler synth
+        // 3. Sampler synths, one per zone and velocity layer played;
+        // all read the same voice control buses so they gate together
+        // Get rate and amp from source params
+        let rate = instrument.source_params.iter()
+            .find(|p| p.name == "rate")
+            .map(|p| match &p.value {
+                ParamValue::Float(v) => *v,
+                _ => 1.0,
+            })
+            .unwrap_or(1.0);
+
+        let amp = instrument.source_params.iter()
+            .find(|p| p.name == "amp")
+            .map(|p| match &p.value {
+                ParamValue::Float(v) => *v,
+                _ => 0.8,
+            })
+            .unwrap_or(0.8);
+
+        let loop_mode = sampler_config.loop_mode;
+
         let sampler_node_id = self.next_node_id;
-        self.next_node_id += 1;
-        {
+        for (i, (zone, bufnum, gain)) in hits.iter().enumerate() {
+            let node_id = sampler_node_id + i as i32;
             let mut args: Vec<rosc::OscType> = vec![
                 rosc::OscType::String("ilex_sampler".to_string()),
-                rosc::OscType::Int(sampler_node_id),
+                rosc::OscType::Int(node_id),
                 rosc::OscType::Int(1),
                 rosc::OscType::Int(group_id),
             ];
 
-            // Get rate and amp from source params
-            let rate = instrument.source_params.iter()
-                .find(|p| p.name == "rate")
-                .map(|p| match &p.value {
-                    ParamValue::Float(v) => *v,
-                    _ => 1.0,
-                })
-                .unwrap_or(1.0);
-
-            let amp = instrument.source_params.iter()
-                .find(|p| p.name == "amp")
-                .map(|p| match &p.value {
-                    ParamValue::Float(v) => *v,
-                    _ => 0.8,
-                })
-                .unwrap_or(0.8);
-
-            let loop_mode = sampler_config.loop_mode;
+            // Each zone is pitched relative to its own root note
+            let root_note = sampler_config.zone_root(zone);
+            let root_freq = tuning * (2.0_f64).powf((root_note as f64 - 69.0) / 12.0);
 
             // Sampler params
             args.push(rosc::OscType::String("bufnum".to_string()));
-            args.push(rosc::OscType::Float(bufnum as f32));
+            args.push(rosc::OscType::Float(*bufnum as f32));
             args.push(rosc::OscType::String("sliceStart".to_string()));
-            args.push(rosc::OscType::Float(slice_start));
+            args.push(rosc::OscType::Float(zone.start));
             args.push(rosc::OscType::String("sliceEnd".to_string()));
-            args.push(rosc::OscType::Float(slice_end));
+            args.push(rosc::OscType::Float(zone.end));
+            args.push(rosc::OscType::String("rootFreq".to_string()));
+            args.push(rosc::OscType::Float(root_freq as f32));
             args.push(rosc::OscType::String("rate".to_string()));
             args.push(rosc::OscType::Float(rate));
             args.push(rosc::OscType::String("amp".to_string()));
-            args.push(rosc::OscType::Float(amp));
+            args.push(rosc::OscType::Float(amp * gain));
             args.push(rosc::OscType::String("loop".to_string()));
             args.push(rosc::OscType::Float(if loop_mode { 1.0 } else { 0.0 }));
+            args.push(rosc::OscType::String("reverse".to_string()));
+            args.push(rosc::OscType::Float(if sampler_config.reverse { 1.0 } else { 0.0 }));
 
             // Wire control inputs (for pitch tracking if enabled)
             if sampler_config.pitch_tracking {
@@ -1188,6 +2157,12 @@ impl AudioEngine {
             args.push(rosc::OscType::String("release".to_string()));
             args.push(rosc::OscType::Float(instrument.amp_envelope.release));
 
+            if voice_pan != 0.0 {
+                args.push(rosc::OscType::String("pan".to_string()));
+                args.push(rosc::OscType::Float(voice_pan));
+            }
+            args.push(rosc::OscType::String("lag".to_string()));
+            args.push(rosc::OscType::Float(self.param_lag));
             // Output to source_out_bus
             args.push(rosc::OscType::String("out".to_string()));
             args.push(rosc::OscType::Float(source_out_bus as f32));
@@ -1197,6 +2172,9 @@ impl AudioEngine {
                 args,
             });
         }
+        self.next_node_id += hits.len() as i32;
+
+        messages.extend(self.keytrack_msg(instrument, pitch));
 
         // Send all as one timed bundle
         let time = super::osc_client::osc_time_from_now(offset_secs);
@@ -1206,10 +2184,12 @@ impl AudioEngine {
 
         self.voice_chains.push(VoiceChain {
             instrument_id,
-            pitch,
+            pitch: played,
             group_id,
             midi_node_id,
-            source_node: sampler_node_id,
+            source_nodes: (0..hits.len() as i32).map(|i| sampler_node_id + i).collect(),
+            lfo_nodes: Vec::new(),
+            voices: 1,
             spawn_time: Instant::now(),
         });
 
@@ -1226,56 +2206,78 @@ impl AudioEngine {
     ) -> Result<(), String> {
         let client = self.client.as_ref().ok_or("Not connected")?;
 
+        if self.sustain && holds_release(&self.sustained, &self.voice_chains, instrument_id, pitch) {
+            self.sustained.push((instrument_id, pitch));
+            return Ok(());
+        }
+
         if let Some(pos) = self
             .voice_chains
             .iter()
             .position(|v| v.instrument_id == instrument_id && v.pitch == pitch)
         {
-            let chain = self.voice_chains.remove(pos);
             let time = super::osc_client::osc_time_from_now(offset_secs);
-            client
-                .set_params_bundled(chain.midi_node_id, &[("gate", 0.0)], time)
-                .map_err(|e| e.to_string())?;
-            // Schedule group free after envelope release completes (+1s margin)
-            let release_time = state.instrument(instrument_id)
-                .map(|s| s.amp_envelope.release)
-                .unwrap_or(1.0);
-            let cleanup_time = super::osc_client::osc_time_from_now(
-                offset_secs + release_time as f64 + 1.0
-            );
-            client
-                .send_bundle(
-                    vec![rosc::OscMessage {
-                        addr: "/n_free".to_string(),
-                        args: vec![rosc::OscType::Int(chain.group_id)],
-                    }],
-                    cleanup_time,
-                )
-                .map_err(|e| e.to_string())?;
+            // A monophonic instrument's voice stays alive for the next note to glide from
+            let mono = state.instrument(instrument_id).is_some_and(|i| !i.polyphonic && !i.source.is_sample());
+            if mono {
+                return client
+                    .set_params_bundled(self.voice_chains[pos].midi_node_id, &[("gate", 0.0)], time)
+                    .map_err(|e| e.to_string());
+            }
+            let chain = self.voice_chains.remove(pos);
+            fade_out_voice(client, &chain, offset_secs, state)?;
         }
         Ok(())
     }
 
-    /// Release all active voices
-    pub fn release_all_voices(&mut self) {
-        if let Some(ref client) = self.client {
-            for chain in self.voice_chains.drain(..) {
+    /// Silence playing notes, as on stopping playback. Voices are freed at once and
+    /// stop being tracked, so playing again starts fresh voices rather than adding to
+    /// the old ones. With stop tails on, effect nodes are left alone and delays and
+    /// reverbs ring out; off (hard cut), the effect chains are silenced too.
+    pub fn stop_voices(&mut self, state: &InstrumentState, session: &SessionState) {
+        self.cut_all_voices();
+        if !self.stop_tails {
+            self.silence_effects(state, session);
+        }
+    }
+
+    /// Cut whatever the effect chains are still sounding by freeing and re-creating
+    /// their nodes, which drops delay lines and reverb tails
+    pub fn silence_effects(&mut self, state: &InstrumentState, session: &SessionState) {
+        let _ = self.rebuild_instrument_routing(state, session);
+    }
+
+    /// Free all active voices at once, cutting off their release
+    pub fn cut_all_voices(&mut self) {
+        self.sustained.clear();
+        for chain in self.voice_chains.drain(..) {
+            if let Some(ref client) = self.client {
                 let _ = client.free_node(chain.group_id);
             }
         }
     }
 
+    /// Press or lift the sustain pedal. Lifting releases exactly the notes let go while
+    /// it was down; notes still held keep sounding.
+    pub fn set_sustain(&mut self, on: bool, state: &InstrumentState) {
+        self.sustain = on;
+        if !on {
+            for (instrument_id, pitch) in std::mem::take(&mut self.sustained) {
+                let _ = self.release_voice(instrument_id, pitch, 0.0, state);
+            }
+        }
+    }
+
     /// Play a one-shot drum sample routed through an instrument's signal chain
     pub fn play_drum_hit_to_instrument(
         &mut self,
         buffer_id: BufferId,
         amp: f32,
         instrument_id: InstrumentId,
-        slice_start: f32,
-        slice_end: f32,
+        hit: &PadHit,
     ) -> Result<(), String> {
         let client = self.client.as_ref().ok_or("Not connected")?;
-        let bufnum = *self.buffer_map.get(&buffer_id).ok_or("Buffer not loaded")?;
+        let bufnum = self.buffer_map.get(&buffer_id).ok_or("Buffer not loaded")?.bufnum;
         let out_bus = self
             .bus_allocator
             .get_audio_bus(instrument_id, "source_out")
@@ -1297,9 +2299,13 @@ impl AudioEngine {
                     rosc::OscType::String("amp".to_string()),
                     rosc::OscType::Float(amp),
                     rosc::OscType::String("sliceStart".to_string()),
-                    rosc::OscType::Float(slice_start),
+                    rosc::OscType::Float(hit.slice_start),
                     rosc::OscType::String("sliceEnd".to_string()),
-                    rosc::OscType::Float(slice_end),
+                    rosc::OscType::Float(hit.slice_end),
+                    rosc::OscType::String("reverse".to_string()),
+                    rosc::OscType::Float(if hit.reverse { 1.0 } else { 0.0 }),
+                    rosc::OscType::String("rate".to_string()),
+                    rosc::OscType::Float(hit.rate),
                     rosc::OscType::String("out".to_string()),
                     rosc::OscType::Int(out_bus), // Route to instrument's source bus
                 ],
@@ -1309,6 +2315,18 @@ impl AudioEngine {
         Ok(())
     }
 
+    /// Clear the master meter's held peak. The meter synth is restarted because its
+    /// peak follower takes seconds to fall; anything still sounding, like a live
+    /// audio input, is measured again from the next reply.
+    pub fn reset_meter(&mut self) {
+        if let Some(ref client) = self.client {
+            client.reset_meter();
+        }
+        if self.is_running {
+            self.restart_meter();
+        }
+    }
+
     /// Get the current master peak level
     pub fn master_peak(&self) -> f32 {
         self.client
@@ -1328,6 +2346,32 @@ impl AudioEngine {
             .unwrap_or_default()
     }
 
+    /// Ask the server for its load figures, at most once per `STATUS_POLL_INTERVAL`.
+    /// Does nothing unless connected.
+    pub fn poll_server_status(&mut self) {
+        let Some(client) = self.client.as_ref().filter(|_| self.is_running) else {
+            return;
+        };
+        let now = Instant::now();
+        if self.last_status_request.is_some_and(|t| now.duration_since(t) < STATUS_POLL_INTERVAL) {
+            return;
+        }
+        self.last_status_request = Some(now);
+        let _ = client.send_message("/status", vec![]);
+    }
+
+    /// Latest server load figures; None when not connected or before the first reply
+    pub fn server_stats(&self) -> Option<ServerStats> {
+        self.client.as_ref().filter(|_| self.is_running)?.server_stats()
+    }
+
+    /// The server's sample rate, once it has reported it
+    pub fn server_sample_rate(&self) -> Option<u32> {
+        let client = self.client.as_ref()?;
+        let rate = *client.sample_rate_handle().lock().ok()?;
+        rate.map(|r| r.round() as u32)
+    }
+
     pub fn load_synthdefs(&self, dir: &Path) -> Result<(), String> {
         let client = self.client.as_ref().ok_or("Not connected")?;
 
@@ -1367,26 +2411,170 @@ impl AudioEngine {
         let client = self.client.as_ref().ok_or("Not connected")?;
 
         // Check if already loaded
-        if let Some(&bufnum) = self.buffer_map.get(&buffer_id) {
-            return Ok(bufnum);
+        if let Some(buffer) = self.buffer_map.get(&buffer_id) {
+            return Ok(buffer.bufnum);
         }
 
+        let target_rate = if self.resample_samples {
+            client.sample_rate_handle().lock().ok().and_then(|r| *r).map(|r| r.round() as u32)
+        } else {
+            None
+        };
+        let prepared = sample_decode::prepare_sample(path, target_rate)?;
         let bufnum = self.next_bufnum;
         self.next_bufnum += 1;
 
-        client.load_buffer(bufnum, path).map_err(|e| e.to_string())?;
+        client.load_buffer(bufnum, &prepared.path).map_err(|e| e.to_string())?;
 
-        self.buffer_map.insert(buffer_id, bufnum);
+        self.buffer_map.insert(buffer_id, LoadedBuffer {
+            bufnum,
+            original_sample_rate: prepared.original_sample_rate,
+            sample_rate: prepared.sample_rate,
+        });
         Ok(bufnum)
     }
 
+    /// Set explicit scsynth/sclang executables (None = auto-discover)
+    pub fn set_sc_paths(&mut self, scsynth: Option<PathBuf>, sclang: Option<PathBuf>) {
+        self.scsynth_path = scsynth;
+        self.sclang_path = sclang;
+        let search = std::env::var_os("PATH");
+        self.scsynth_found = self.scsynth_candidates().iter().any(|p| executable_exists(p, search.as_deref()));
+    }
+
+    /// scsynth executables to try, in order
+    pub fn scsynth_candidates(&self) -> Vec<PathBuf> {
+        executable_candidates(self.scsynth_path.as_deref(), &SCSYNTH_PATHS)
+    }
+
+    /// sclang executables to try, in order
+    pub fn sclang_candidates(&self) -> Vec<PathBuf> {
+        executable_candidates(self.sclang_path.as_deref(), &SCLANG_PATHS)
+    }
+
+    /// Enable or disable resampling samples to the server rate on load
+    pub fn set_resample_samples(&mut self, enabled: bool) {
+        self.resample_samples = enabled;
+    }
+
+    /// Set the smoothing time for param changes; applies to synths created from now on
+    pub fn set_param_lag(&mut self, seconds: f32) {
+        self.param_lag = seconds.max(0.0);
+    }
+
+    /// Let effects ring out when playback stops, rather than silencing them along
+    /// with the voices
+    pub fn set_stop_tails(&mut self, enabled: bool) {
+        self.stop_tails = enabled;
+    }
+
+    /// Load a sample in the background. The file is checked (and converted or
+    /// resampled if needed) on a worker thread, then read by scsynth; the buffer
+    /// is only playable once `poll_sample_loads` reports it ready.
+    pub fn load_sample_async(&mut self, buffer_id: BufferId, path: &str) -> Result<(), String> {
+        let client = self.client.as_ref().ok_or("Not connected")?;
+        let rate_handle = self.resample_samples.then(|| client.sample_rate_handle());
+
+        let tx = self.sample_load_sender.clone();
+        let path = path.to_string();
+        thread::spawn(move || {
+            // The server reports its rate shortly after connecting; wait briefly for it
+            let target_rate = rate_handle.and_then(|handle| {
+                let deadline = Instant::now() + SAMPLE_RATE_WAIT;
+                loop {
+                    if let Some(rate) = handle.lock().ok().and_then(|r| *r) {
+                        return Some(rate.round() as u32);
+                    }
+                    if Instant::now() >= deadline {
+                        return None;
+                    }
+                    thread::sleep(Duration::from_millis(20));
+                }
+            });
+            let result = Self::prepare_sample_file(&path, target_rate);
+            let _ = tx.send((buffer_id, result));
+        });
+
+        Ok(())
+    }
+
+    /// Check a sample file is readable and convert/resample it if needed.
+    /// Returns the file to hand to `/b_allocRead`.
+    fn prepare_sample_file(path: &str, target_rate: Option<u32>) -> Result<PreparedSample, String> {
+        if sample_decode::is_server_native(Path::new(path)) {
+            let mut file = fs::File::open(path).map_err(|e| format!("{}: {}", path, e))?;
+            let mut header = [0u8; 12];
+            file.read_exact(&mut header)
+                .map_err(|_| format!("{}: not an audio file", path))?;
+        }
+        sample_decode::prepare_sample(path, target_rate)
+    }
+
+    /// Poll background sample loads. Returns buffers that became ready or failed.
+    pub fn poll_sample_loads(&mut self) -> Vec<(BufferId, Result<(), String>)> {
+        let mut finished = Vec::new();
+
+        while let Ok((buffer_id, result)) = self.sample_load_receiver.try_recv() {
+            let Some(client) = self.client.as_ref() else {
+                finished.push((buffer_id, Err("Not connected".to_string())));
+                continue;
+            };
+            let prepared = match result {
+                Ok(prepared) => prepared,
+                Err(e) => {
+                    finished.push((buffer_id, Err(e)));
+                    continue;
+                }
+            };
+            if self.buffer_map.contains_key(&buffer_id) {
+                finished.push((buffer_id, Ok(())));
+                continue;
+            }
+
+            let bufnum = self.next_bufnum;
+            self.next_bufnum += 1;
+            match client.load_buffer(bufnum, &prepared.path) {
+                Ok(()) => {
+                    let buffer = LoadedBuffer {
+                        bufnum,
+                        original_sample_rate: prepared.original_sample_rate,
+                        sample_rate: prepared.sample_rate,
+                    };
+                    self.pending_buffer_reads.insert(bufnum, (buffer_id, buffer));
+                }
+                Err(e) => finished.push((buffer_id, Err(e.to_string()))),
+            }
+        }
+
+        if let Some(replies) = self.client.as_ref().map(|c| c.take_buffer_replies()) {
+            finished.extend(self.finish_buffer_reads(replies));
+        }
+
+        finished
+    }
+
+    /// Match the server's buffer read replies to pending loads; a buffer becomes
+    /// playable only once its read succeeded. Replies for other buffers are ignored.
+    fn finish_buffer_reads(&mut self, replies: Vec<BufferReply>) -> Vec<(BufferId, Result<(), String>)> {
+        let mut finished = Vec::new();
+        for (bufnum, result) in replies {
+            if let Some((buffer_id, buffer)) = self.pending_buffer_reads.remove(&bufnum) {
+                if result.is_ok() {
+                    self.buffer_map.insert(buffer_id, buffer);
+                }
+                finished.push((buffer_id, result));
+            }
+        }
+        finished
+    }
+
     /// Free a sample buffer from SuperCollider
     #[allow(dead_code)]
     pub fn free_sample(&mut self, buffer_id: BufferId) -> Result<(), String> {
         let client = self.client.as_ref().ok_or("Not connected")?;
 
-        if let Some(bufnum) = self.buffer_map.remove(&buffer_id) {
-            client.free_buffer(bufnum).map_err(|e| e.to_string())?;
+        if let Some(buffer) = self.buffer_map.remove(&buffer_id) {
+            client.free_buffer(buffer.bufnum).map_err(|e| e.to_string())?;
         }
         Ok(())
     }
@@ -1394,7 +2582,13 @@ impl AudioEngine {
     /// Get the SuperCollider buffer number for a loaded buffer
     #[allow(dead_code)]
     pub fn get_sc_bufnum(&self, buffer_id: BufferId) -> Option<i32> {
-        self.buffer_map.get(&buffer_id).copied()
+        self.buffer_map.get(&buffer_id).map(|b| b.bufnum)
+    }
+
+    /// Get the loaded buffer info (bufnum and original/loaded sample rates)
+    #[allow(dead_code)]
+    pub fn buffer_info(&self, buffer_id: BufferId) -> Option<&LoadedBuffer> {
+        self.buffer_map.get(&buffer_id)
     }
 
     /// Check if a buffer is loaded
@@ -1423,7 +2617,25 @@ impl AudioEngine {
                         .map_err(|e| e.to_string())?;
                 }
             }
+            AutomationTarget::InstrumentTrim(instrument_id) => {
+                let amp = crate::state::instrument::db_to_amp(value);
+                if let Some(nodes) = self.node_map.get(instrument_id) {
+                    client.set_param(nodes.output, "trim", amp)
+                        .map_err(|e| e.to_string())?;
+                }
+                if let Some((idx, instrument)) = state.instruments.iter().enumerate().find(|(_, i)| i.id == *instrument_id) {
+                    let muted = session.effective_instrument_mute(state, instrument);
+                    for send in &instrument.sends {
+                        if let Some(&node_id) = self.send_node_map.get(&(idx, send.bus_id)) {
+                            client.set_param(node_id, "level", Self::send_level(send, amp, muted))
+                                .map_err(|e| e.to_string())?;
+                        }
+                    }
+                }
+            }
             AutomationTarget::InstrumentPan(instrument_id) => {
+                let afl = session.afl_listening(state) && state.instrument(*instrument_id).is_some_and(|i| i.solo);
+                let value = if afl { 0.0 } else { value };
                 if let Some(nodes) = self.node_map.get(instrument_id) {
                     client.set_param(nodes.output, "pan", value)
                         .map_err(|e| e.to_string())?;
@@ -1457,6 +2669,10 @@ impl AudioEngine {
                         if let Some(&effect_node) = nodes.effects.get(enabled_idx) {
                             if let Some(effect) = instrument.effects.get(*effect_idx) {
                                 if let Some(param) = effect.params.get(*param_idx) {
+                                    // A synced delay's time follows the tempo, not automation
+                                    if effect.sync && param.name == "time" {
+                                        return Ok(());
+                                    }
                                     client.set_param(effect_node, &param.name, value)
                                         .map_err(|e| e.to_string())?;
                                 }
@@ -1468,16 +2684,20 @@ impl AudioEngine {
             AutomationTarget::SampleRate(instrument_id) => {
                 for voice in &self.voice_chains {
                     if voice.instrument_id == *instrument_id {
-                        client.set_param(voice.source_node, "rate", value)
-                            .map_err(|e| e.to_string())?;
+                        for &node in &voice.source_nodes {
+                            client.set_param(node, "rate", value)
+                                .map_err(|e| e.to_string())?;
+                        }
                     }
                 }
             }
             AutomationTarget::SampleAmp(instrument_id) => {
                 for voice in &self.voice_chains {
                     if voice.instrument_id == *instrument_id {
-                        client.set_param(voice.source_node, "amp", value)
-                            .map_err(|e| e.to_string())?;
+                        for &node in &voice.source_nodes {
+                            client.set_param(node, "amp", value)
+                                .map_err(|e| e.to_string())?;
+                        }
                     }
                 }
             }
@@ -1586,3 +2806,311 @@ impl Default for AudioEngine {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chain(instrument_id: InstrumentId, pitch: u8) -> VoiceChain {
+        VoiceChain {
+            instrument_id,
+            pitch,
+            group_id: 0,
+            midi_node_id: 0,
+            source_nodes: Vec::new(),
+            lfo_nodes: Vec::new(),
+            voices: 1,
+            spawn_time: Instant::now(),
+        }
+    }
+
+    #[test]
+    fn test_sustain_holds_one_release_per_voice() {
+        let chains = vec![chain(1, 60), chain(1, 60), chain(1, 64)];
+        let mut sustained = Vec::new();
+        for (id, pitch) in [(1, 60), (1, 60), (1, 60), (1, 64), (2, 60)] {
+            if holds_release(&sustained, &chains, id, pitch) {
+                sustained.push((id, pitch));
+            }
+        }
+        assert_eq!(sustained, vec![(1, 60), (1, 60), (1, 64)]);
+    }
+
+    #[test]
+    fn test_voices_used_counts_unison_copies() {
+        let mut chains = vec![chain(1, 60), chain(1, 64), chain(2, 60)];
+        chains[1].voices = 3;
+        assert_eq!(voices_used(&chains, 1), 4);
+        assert_eq!(voices_used(&chains, 2), 1);
+        assert_eq!(voices_used(&chains, 3), 0);
+    }
+
+    #[test]
+    fn test_executable_lookup() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("scsynth"), "").unwrap();
+        let search = std::env::join_paths([Path::new("/nonexistent"), dir.path()]).unwrap();
+        assert!(executable_exists(Path::new("scsynth"), Some(&search)));
+        assert!(!executable_exists(Path::new("sclang"), Some(&search)));
+        assert!(!executable_exists(Path::new("scsynth"), None));
+        assert!(executable_exists(&dir.path().join("scsynth"), None));
+        assert!(!executable_exists(&dir.path().join("missing"), Some(&search)));
+
+        // A stopped engine is never live
+        let engine = AudioEngine::new();
+        assert_ne!(engine.availability(), AudioAvailability::Live);
+        assert_eq!(AudioAvailability::Live.warning(), None);
+    }
+
+    #[test]
+    fn test_stopping_forgets_voices_in_either_mode() {
+        let mut state = InstrumentState::new();
+        let id = state.add_instrument(crate::state::SourceType::Saw);
+        state.instrument_mut(id).unwrap().amp_envelope.release = 2.5;
+        assert!((voice_cleanup_secs(&state, id) - 3.5).abs() < 1e-6);
+        assert!((voice_cleanup_secs(&state, id + 1) - 2.0).abs() < 1e-6);
+
+        for tails in [false, true] {
+            let mut engine = AudioEngine::new();
+            engine.set_stop_tails(tails);
+            engine.voice_chains = vec![chain(id, 60), chain(id, 64)];
+            engine.sustained = vec![(id, 60)];
+            engine.stop_voices(&state, &SessionState::new());
+            // Playing again must not find the stopped voices still counted
+            assert!(engine.voice_chains.is_empty());
+            assert!(engine.sustained.is_empty());
+            assert_eq!(voices_used(&engine.voice_chains, id), 0);
+        }
+    }
+
+    #[test]
+    fn test_mono_voice_retriggers_without_glide() {
+        // Gliding notes stay legato on the open gate
+        assert_eq!(mono_gate_steps(0.2), vec![(0.0, 1.0)]);
+        // Without glide the gate closes, then reopens after the gap
+        let steps = mono_gate_steps(0.0);
+        assert_eq!(steps.len(), 2);
+        assert_eq!(steps[0], (0.0, 0.0));
+        assert_eq!(steps[1].1, 1.0);
+        assert!(steps[1].0 > 0.0);
+    }
+
+    #[test]
+    fn test_instrument_level_destinations_reach_a_synth() {
+        let mut inputs: Vec<&str> = vec!["cutoff_mod_in", "res_mod_in", "pan_mod_in", "level_mod_in"];
+        for effect_type in [EffectType::Delay, EffectType::Reverb, EffectType::Gate, EffectType::TapeComp, EffectType::SidechainComp] {
+            inputs.extend(effect_mod_inputs(effect_type));
+        }
+        for target in LfoTarget::all() {
+            if let Some(input) = mod_input(target) {
+                assert!(!is_voice_destination(target), "{:?}", target);
+                assert!(inputs.contains(&input), "{:?} routes to {} which no synth reads", target, input);
+            }
+        }
+        assert_eq!(mod_input(LfoTarget::PulseWidth), None);
+
+        let buses = [("cutoff_mod_in", 40), ("time_mod_in", 41), ("pan_mod_in", 42)];
+        assert_eq!(
+            mod_input_params(&buses, effect_mod_inputs(EffectType::Delay)),
+            vec![("time_mod_in".to_string(), 41.0)],
+        );
+        assert!(mod_input_params(&buses, effect_mod_inputs(EffectType::Reverb)).is_empty());
+    }
+
+    #[test]
+    fn test_sample_loads_finish_on_the_server_reply() {
+        let mut engine = AudioEngine::new();
+        let buffer = |bufnum| LoadedBuffer { bufnum, original_sample_rate: Some(44100), sample_rate: Some(44100) };
+        engine.pending_buffer_reads.insert(3, (100, buffer(3)));
+        engine.pending_buffer_reads.insert(4, (101, buffer(4)));
+        assert!(!engine.is_buffer_loaded(100));
+
+        let finished = engine.finish_buffer_reads(vec![
+            (4, Err("File could not be opened".to_string())),
+            (9, Ok(())),
+            (3, Ok(())),
+        ]);
+        assert_eq!(finished, vec![(101, Err("File could not be opened".to_string())), (100, Ok(()))]);
+        assert_eq!(engine.get_sc_bufnum(100), Some(3));
+        assert!(!engine.is_buffer_loaded(101));
+        assert!(engine.pending_buffer_reads.is_empty());
+
+        // Files that can't be read fail before anything is sent to the server
+        let dir = tempfile::tempdir().unwrap();
+        let fake = dir.path().join("fake.wav");
+        std::fs::write(&fake, "text").unwrap();
+        assert!(AudioEngine::prepare_sample_file(fake.to_str().unwrap(), None).is_err());
+        assert!(AudioEngine::prepare_sample_file("/nonexistent/kick.wav", None).is_err());
+        // Loading needs a server
+        assert!(engine.load_sample_async(102, "/nonexistent/kick.wav").is_err());
+    }
+
+    /// Write an executable shell script standing in for sclang
+    #[cfg(unix)]
+    fn fake_sclang(dir: &Path, body: &str) -> PathBuf {
+        use std::os::unix::fs::PermissionsExt;
+        let path = dir.join("sclang");
+        std::fs::write(&path, format!("#!/bin/sh\n{}\n", body)).unwrap();
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+        path
+    }
+
+    /// Poll until `count` compile results have arrived
+    #[cfg(unix)]
+    fn wait_for_compiles(engine: &mut AudioEngine, count: usize) -> Vec<Result<String, String>> {
+        let deadline = Instant::now() + Duration::from_secs(10);
+        let mut results = Vec::new();
+        while results.len() < count && Instant::now() < deadline {
+            match engine.poll_compile_result() {
+                Some(result) => results.push(result),
+                None => thread::sleep(Duration::from_millis(20)),
+            }
+        }
+        results
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_imports_compile_in_the_background_and_report_failures() {
+        let dir = tempfile::tempdir().unwrap();
+        let sclang = fake_sclang(dir.path(), "grep -q broken \"$1\" && echo 'ERROR: Parse error'\nexit 0");
+        let mut engine = AudioEngine::new();
+        engine.set_sc_paths(None, Some(sclang));
+
+        let good = dir.path().join("good.scd");
+        let broken = dir.path().join("broken.scd");
+        std::fs::write(&good, "SynthDef(\\good, {}).writeDefFile;").unwrap();
+        std::fs::write(&broken, "SynthDef(\\broken, {").unwrap();
+        engine.begin_import_report(SynthDefImportReport::default());
+        engine.compile_custom_synthdef_async(&good, dir.path(), "good").unwrap();
+        engine.compile_custom_synthdef_async(&broken, dir.path(), "broken").unwrap();
+        engine.compile_custom_synthdef_async(&dir.path().join("missing.scd"), dir.path(), "missing").unwrap();
+        assert_eq!(engine.pending_custom_compiles(), 3);
+        assert!(engine.take_import_report().is_none());
+
+        let results = wait_for_compiles(&mut engine, 2);
+        assert_eq!(results.len(), 2);
+        assert!(results[0].is_ok());
+        assert!(results[1].as_ref().unwrap_err().contains("Parse error"));
+        assert_eq!(engine.pending_custom_compiles(), 0);
+
+        let report = engine.take_import_report().unwrap();
+        assert_eq!(report.loaded, vec!["good".to_string()]);
+        let failed: Vec<&str> = report.failed.iter().map(|(name, _)| name.as_str()).collect();
+        assert_eq!(failed, vec!["broken", "missing"]);
+        assert_eq!(engine.take_failed_custom_compiles(), vec!["broken", "missing"]);
+        assert!(engine.take_failed_custom_compiles().is_empty());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_sclang_is_killed_after_the_timeout() {
+        let dir = tempfile::tempdir().unwrap();
+        let sclang = fake_sclang(dir.path(), "sleep 5");
+        let start = Instant::now();
+        let result = AudioEngine::run_sclang_script(&dir.path().join("x.scd"), &[sclang], Duration::from_millis(200));
+        assert!(result.unwrap_err().contains("timed out"));
+        assert!(start.elapsed() < Duration::from_secs(4));
+    }
+
+    #[test]
+    fn test_param_lag_reaches_synths_and_skips_envelopes() {
+        let lag_of = |params: Vec<(String, f32)>| params.iter().find(|(name, _)| name == "lag").map(|(_, v)| *v);
+        let delay = EffectSlot::new(EffectType::Delay);
+        let mut engine = AudioEngine::new();
+        assert_eq!(lag_of(engine.effect_params(&delay, 16, 18, 120.0, false)), Some(0.0));
+        engine.set_param_lag(0.05);
+        assert_eq!(lag_of(engine.effect_params(&delay, 16, 18, 120.0, false)), Some(0.05));
+        engine.set_param_lag(-1.0);
+        assert_eq!(lag_of(engine.effect_params(&delay, 16, 18, 120.0, false)), Some(0.0));
+
+        // Every synthdef that glides declares `lag`, and envelopes and gates never glide
+        let source = include_str!("../../synthdefs/compile.scd");
+        for def in source.split("SynthDef(\\").skip(1) {
+            let name = def.split(',').next().unwrap();
+            if def.contains(", lag)") {
+                assert!(def.contains("lag=0"), "{} lags params without a lag control", name);
+            }
+            for param in ["attack", "decay", "sustain", "release", "gate"] {
+                assert!(!def.contains(&format!("Lag.kr({},", param)), "{} lags {}", name, param);
+            }
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_imports_compile_with_the_configured_sclang() {
+        let mut engine = AudioEngine::new();
+        assert_eq!(engine.sclang_candidates().len(), SCLANG_PATHS.len());
+
+        // A configured path is the only one tried, and its own error is reported
+        let dir = tempfile::tempdir().unwrap();
+        let missing = dir.path().join("no-sclang");
+        engine.set_sc_paths(None, Some(missing.clone()));
+        assert_eq!(engine.sclang_candidates(), vec![missing.clone()]);
+        let error = AudioEngine::run_sclang_script(&dir.path().join("x.scd"), &engine.sclang_candidates(), Duration::from_secs(5))
+            .unwrap_err();
+        assert!(error.contains(&missing.display().to_string()), "{}", error);
+        assert!(!error.contains("/usr/bin/sclang"), "{}", error);
+
+        let marker = dir.path().join("ran");
+        let sclang = fake_sclang(dir.path(), &format!("touch '{}'", marker.display()));
+        engine.set_sc_paths(None, Some(sclang.clone()));
+        assert_eq!(engine.sclang_candidates(), vec![sclang]);
+
+        let scd = dir.path().join("pad.scd");
+        std::fs::write(&scd, "SynthDef(\\pad, {}).writeDefFile;").unwrap();
+        engine.compile_custom_synthdef_async(&scd, dir.path(), "pad").unwrap();
+        let results = wait_for_compiles(&mut engine, 1);
+        assert!(results[0].is_ok(), "{:?}", results);
+        assert!(marker.exists());
+    }
+
+    /// Name and control names of the single SynthDef in an SCgf v2 file
+    fn scsyndef_controls(data: &[u8]) -> (String, Vec<String>) {
+        fn string(take: &mut impl FnMut(usize) -> Vec<u8>) -> String {
+            let len = take(1)[0] as usize;
+            String::from_utf8(take(len)).unwrap()
+        }
+        fn count(bytes: Vec<u8>) -> usize {
+            i32::from_be_bytes(bytes.try_into().unwrap()) as usize
+        }
+        let mut pos = 0;
+        let mut take = |n: usize| {
+            pos += n;
+            data[pos - n..pos].to_vec()
+        };
+        assert_eq!(take(4), b"SCgf");
+        assert_eq!(count(take(4)), 2);
+        take(2);
+        let name = string(&mut take);
+        let constants = count(take(4));
+        take(constants * 4);
+        let params = count(take(4));
+        take(params * 4);
+        let controls = (0..count(take(4)))
+            .map(|_| {
+                let control = string(&mut take);
+                take(4);
+                control
+            })
+            .collect();
+        (name, controls)
+    }
+
+    #[test]
+    fn test_compiled_synthdefs_match_compile_scd() {
+        let source = include_str!("../../synthdefs/compile.scd");
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("synthdefs");
+        for def in source.split("SynthDef(\\").skip(1) {
+            let name = def.split(',').next().unwrap();
+            let args = def.split('|').nth(1).unwrap();
+            let expected: Vec<&str> = args.split(',').map(|arg| arg.split('=').next().unwrap().trim()).collect();
+            let data = std::fs::read(dir.join(format!("{}.scsyndef", name)))
+                .unwrap_or_else(|_| panic!("{} has no .scsyndef; run synthdefs/compile.scd", name));
+            let (compiled_name, controls) = scsyndef_controls(&data);
+            assert_eq!(compiled_name, name);
+            assert_eq!(controls, expected, "{}.scsyndef is stale; run synthdefs/compile.scd", name);
+        }
+    }
+}
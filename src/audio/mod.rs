@@ -1,6 +1,12 @@
 pub mod bus_allocator;
+pub mod click;
 pub mod devices;
 pub mod engine;
+pub mod nrt;
 pub mod osc_client;
+pub mod peak_cache;
+pub mod sample_decode;
+pub mod server_output;
 
 pub use engine::{AudioEngine, ServerStatus};
+pub use osc_client::ServerStats;
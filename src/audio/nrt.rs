@@ -0,0 +1,357 @@
+//! Offline rendering with scsynth's non-realtime mode (`scsynth -N`).
+//!
+//! A score is a list of timed OSC bundles, written to a file with each bundle
+//! prefixed by its big-endian length. scsynth runs through it with no audio
+//! hardware and writes its hardware outputs to a sound file.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+use rosc::{OscBundle, OscMessage, OscPacket, OscTime, OscType};
+
+use super::engine::not_found_error;
+use super::sample_decode;
+use crate::state::drum_sequencer::{DrumSequencerState, DrumStep};
+use crate::state::sampler::BufferId;
+
+/// Output channels rendered
+const NRT_CHANNELS: i32 = 2;
+
+/// Timed OSC commands for an offline render
+#[derive(Debug, Default)]
+pub struct Score {
+    events: Vec<(f64, OscMessage)>,
+}
+
+impl Score {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Schedule a command `time` seconds into the render
+    pub fn add(&mut self, time: f64, addr: &str, args: Vec<OscType>) {
+        self.events.push((time.max(0.0), OscMessage { addr: addr.to_string(), args }));
+    }
+
+    /// Encode as an NRT command file, bundles in time order
+    fn encode(&self) -> Result<Vec<u8>, String> {
+        let mut events: Vec<&(f64, OscMessage)> = self.events.iter().collect();
+        events.sort_by(|a, b| a.0.total_cmp(&b.0));
+        let mut out = Vec::new();
+        for (time, msg) in events {
+            let bundle = OscPacket::Bundle(OscBundle {
+                timetag: score_time(*time),
+                content: vec![OscPacket::Message(msg.clone())],
+            });
+            let bytes = rosc::encoder::encode(&bundle).map_err(|e| e.to_string())?;
+            out.extend_from_slice(&(bytes.len() as i32).to_be_bytes());
+            out.extend_from_slice(&bytes);
+        }
+        Ok(out)
+    }
+}
+
+/// NRT timetags count seconds from the start of the render
+fn score_time(secs: f64) -> OscTime {
+    let whole = secs.floor();
+    OscTime::from((whole as u32, ((secs - whole) * 4_294_967_296.0) as u32))
+}
+
+/// Render `score` for `duration` seconds into a stereo 24-bit WAV at `out`, with the
+/// first of `scsynth` that runs (see `AudioEngine::scsynth_candidates`)
+pub fn render(score: &Score, duration: f64, sample_rate: u32, scsynth: &[PathBuf], out: &Path) -> Result<(), String> {
+    let score_path = std::env::temp_dir().join(format!("ilex_nrt_{}.osc", std::process::id()));
+    let mut bytes = score.encode()?;
+    // A trailing no-op sets the render length
+    let mut end = Score::new();
+    end.add(duration, "/c_set", vec![OscType::Int(0), OscType::Float(0.0)]);
+    bytes.extend(end.encode()?);
+    fs::write(&score_path, bytes).map_err(|e| e.to_string())?;
+
+    let args = [
+        "-N".to_string(),
+        score_path.to_string_lossy().to_string(),
+        "_".to_string(),
+        out.to_string_lossy().to_string(),
+        sample_rate.to_string(),
+        "WAV".to_string(),
+        "int24".to_string(),
+        "-o".to_string(),
+        NRT_CHANNELS.to_string(),
+    ];
+    let mut last_error = None;
+    let status = scsynth.iter().find_map(|path| {
+        Command::new(path)
+            .args(&args)
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status()
+            .map_err(|e| last_error = Some(e))
+            .ok()
+    });
+    let _ = fs::remove_file(&score_path);
+    match status {
+        Some(s) if s.success() => Ok(()),
+        Some(s) => Err(format!("scsynth render failed ({})", s)),
+        None => Err(not_found_error("scsynth", scsynth, last_error.as_ref())),
+    }
+}
+
+/// Rewrite a WAV to exactly `frames` frames starting at frame `skip`, padding
+/// with silence if it runs short
+pub fn fit_wav(path: &Path, skip: usize, frames: usize) -> Result<(), String> {
+    let reader = hound::WavReader::open(path).map_err(|e| e.to_string())?;
+    let spec = reader.spec();
+    let channels = spec.channels as usize;
+    let range = skip * channels..(skip + frames) * channels;
+    match spec.sample_format {
+        hound::SampleFormat::Float => {
+            let samples: Vec<f32> = reader.into_samples().collect::<Result<_, _>>().map_err(|e| e.to_string())?;
+            write_range(path, spec, &samples, range)
+        }
+        hound::SampleFormat::Int => {
+            let samples: Vec<i32> = reader.into_samples().collect::<Result<_, _>>().map_err(|e| e.to_string())?;
+            write_range(path, spec, &samples, range)
+        }
+    }
+}
+
+fn write_range<S: hound::Sample + Copy + Default>(
+    path: &Path,
+    spec: hound::WavSpec,
+    samples: &[S],
+    range: std::ops::Range<usize>,
+) -> Result<(), String> {
+    let mut writer = hound::WavWriter::create(path, spec).map_err(|e| e.to_string())?;
+    for i in range {
+        writer.write_sample(samples.get(i).copied().unwrap_or_default()).map_err(|e| e.to_string())?;
+    }
+    writer.finalize().map_err(|e| e.to_string())
+}
+
+/// One scheduled drum hit
+#[derive(Debug, Clone, PartialEq)]
+pub struct LoopHit {
+    /// Seconds from the start of the loop
+    pub time: f64,
+    pub buffer_id: BufferId,
+    pub amp: f32,
+    pub slice_start: f32,
+    pub slice_end: f32,
+    pub reverse: bool,
+    pub rate: f32,
+}
+
+/// Seconds per sequencer step (16th notes) at `bpm`
+pub fn step_seconds(bpm: f32) -> f64 {
+    60.0 / bpm as f64 / 4.0
+}
+
+/// Length of the current pattern in frames at `bpm`
+pub fn loop_frames(seq: &DrumSequencerState, bpm: f32, sample_rate: u32) -> usize {
+    (seq.pattern().length as f64 * step_seconds(bpm) * sample_rate as f64).round() as usize
+}
+
+/// The current pattern's hits over `passes` loops, as playback would trigger them:
+/// grooved nudges, velocity and pad level, velocity layers and round-robins.
+/// Hits nudged before the first step wrap to the end of the loop.
+pub fn loop_hits(seq: &DrumSequencerState, bpm: f32, passes: usize) -> Vec<LoopHit> {
+    let pattern = seq.pattern();
+    let len = pattern.length as f32;
+    let step = step_seconds(bpm);
+    let mut hits: Vec<(f32, usize, &DrumStep)> = pattern.steps.iter().enumerate()
+        .flat_map(|(pad_idx, steps)| {
+            steps.iter().take(pattern.length).enumerate()
+                .filter(|(_, s)| s.active)
+                .map(move |(i, s)| ((i as f32 + s.nudge).rem_euclid(len), pad_idx, s))
+        })
+        .collect();
+    hits.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+    let mut round_robin = vec![0usize; seq.pads.len()];
+    let mut out = Vec::new();
+    for pass in 0..passes {
+        for &(pos, pad_idx, drum_step) in &hits {
+            let Some(pad) = seq.pads.get(pad_idx) else {
+                continue;
+            };
+            let velocity = drum_step.played_velocity();
+            let buffers = pad.buffers_for_hit(velocity, round_robin[pad_idx]);
+            round_robin[pad_idx] += 1;
+            let hit = pad.hit(&drum_step.locks);
+            let amp = (velocity as f32 / 127.0) * hit.level;
+            let time = (pass as f64 * len as f64 + pos as f64) * step;
+            for (buffer_id, gain) in buffers {
+                out.push(LoopHit {
+                    time,
+                    buffer_id,
+                    amp: amp * gain,
+                    slice_start: hit.slice_start,
+                    slice_end: hit.slice_end,
+                    reverse: hit.reverse,
+                    rate: hit.rate,
+                });
+            }
+        }
+    }
+    out
+}
+
+/// Every sample file the sequencer's pads can play
+fn pad_sample_paths(seq: &DrumSequencerState) -> HashMap<BufferId, String> {
+    let mut paths = HashMap::new();
+    for pad in &seq.pads {
+        if let (Some(id), Some(path)) = (pad.buffer_id, &pad.path) {
+            paths.insert(id, path.clone());
+        }
+        for layer in &pad.layers {
+            paths.insert(layer.buffer_id, layer.path.clone());
+        }
+        for rr in &pad.round_robin {
+            paths.insert(rr.buffer_id, rr.path.clone());
+        }
+    }
+    paths
+}
+
+/// Render the current pattern to `out` as a loop exactly one pattern long at `bpm`.
+/// Two passes are rendered and the second kept, so tails ringing past the end of
+/// the loop carry over into its start. Pads play dry, without the kit's effects.
+pub fn export_drum_loop(
+    seq: &DrumSequencerState,
+    bpm: f32,
+    sample_rate: u32,
+    synthdef_dir: &Path,
+    scsynth: &[PathBuf],
+    out: &Path,
+) -> Result<PathBuf, String> {
+    let paths = pad_sample_paths(seq);
+    let hits = loop_hits(seq, bpm, 2);
+    if hits.is_empty() {
+        return Err("Pattern has no hits with samples".to_string());
+    }
+
+    let mut score = Score::new();
+    let synthdef = fs::read(synthdef_dir.join("ilex_sampler_oneshot.scsyndef")).map_err(|e| e.to_string())?;
+    score.add(0.0, "/d_recv", vec![OscType::Blob(synthdef)]);
+
+    let mut bufnums: HashMap<BufferId, i32> = HashMap::new();
+    for hit in &hits {
+        if bufnums.contains_key(&hit.buffer_id) {
+            continue;
+        }
+        let Some(path) = paths.get(&hit.buffer_id) else {
+            continue;
+        };
+        let prepared = sample_decode::prepare_sample(path, None)?;
+        let bufnum = bufnums.len() as i32;
+        score.add(0.0, "/b_allocRead", vec![
+            OscType::Int(bufnum),
+            OscType::String(prepared.path),
+            OscType::Int(0),
+            OscType::Int(0),
+        ]);
+        bufnums.insert(hit.buffer_id, bufnum);
+    }
+
+    for hit in &hits {
+        let Some(&bufnum) = bufnums.get(&hit.buffer_id) else {
+            continue;
+        };
+        score.add(hit.time, "/s_new", vec![
+            OscType::String("ilex_sampler_oneshot".to_string()),
+            OscType::Int(-1),
+            OscType::Int(0), // addToHead
+            OscType::Int(0), // root group
+            OscType::String("bufnum".to_string()),
+            OscType::Int(bufnum),
+            OscType::String("amp".to_string()),
+            OscType::Float(hit.amp),
+            OscType::String("sliceStart".to_string()),
+            OscType::Float(hit.slice_start),
+            OscType::String("sliceEnd".to_string()),
+            OscType::Float(hit.slice_end),
+            OscType::String("reverse".to_string()),
+            OscType::Float(if hit.reverse { 1.0 } else { 0.0 }),
+            OscType::String("rate".to_string()),
+            OscType::Float(hit.rate),
+            OscType::String("out".to_string()),
+            OscType::Int(0),
+        ]);
+    }
+
+    let frames = loop_frames(seq, bpm, sample_rate);
+    let loop_secs = seq.pattern().length as f64 * step_seconds(bpm);
+    if let Some(dir) = out.parent() {
+        fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+    }
+    render(&score, loop_secs * 2.0, sample_rate, scsynth, out)?;
+    fit_wav(out, frames, frames)?;
+    Ok(out.to_path_buf())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::drum_sequencer::DrumSequencerState;
+
+    fn kit() -> DrumSequencerState {
+        let mut seq = DrumSequencerState::new();
+        seq.pads[0].buffer_id = Some(1);
+        seq.pads[0].path = Some("kick.wav".to_string());
+        seq.pads[0].level = 0.5;
+        seq
+    }
+
+    #[test]
+    fn test_loop_hits_follow_nudge_velocity_and_level() {
+        let mut seq = kit();
+        let steps = &mut seq.pattern_mut().steps[0];
+        steps[0].active = true;
+        steps[0].nudge = -0.25;
+        steps[4].active = true;
+        steps[4].velocity = 127;
+        steps[4].nudge = 0.5;
+
+        // 120 BPM: 0.125s per step, 16 steps = 2s
+        let hits = loop_hits(&seq, 120.0, 1);
+        assert_eq!(hits.len(), 2);
+        assert!((hits[0].time - 4.5 * 0.125).abs() < 1e-6);
+        assert!((hits[0].amp - 0.5).abs() < 1e-6);
+        // Early first hit wraps to the end of the loop
+        assert!((hits[1].time - 15.75 * 0.125).abs() < 1e-6);
+        assert!((hits[1].amp - 100.0 / 127.0 * 0.5).abs() < 1e-6);
+
+        let two = loop_hits(&seq, 120.0, 2);
+        assert!((two[2].time - (2.0 + 4.5 * 0.125)).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_loop_frames_match_tempo() {
+        let seq = kit();
+        assert_eq!(loop_frames(&seq, 120.0, 48000), 96000);
+        assert_eq!(loop_frames(&seq, 93.0, 44100), (16.0 * 60.0 / 93.0 / 4.0 * 44100.0_f64).round() as usize);
+    }
+
+    #[test]
+    fn test_fit_wav_keeps_exact_range() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("loop.wav");
+        let spec = hound::WavSpec { channels: 2, sample_rate: 48000, bits_per_sample: 24, sample_format: hound::SampleFormat::Int };
+        let mut writer = hound::WavWriter::create(&path, spec).unwrap();
+        for i in 0..20 {
+            writer.write_sample(i).unwrap();
+        }
+        writer.finalize().unwrap();
+
+        fit_wav(&path, 4, 8).unwrap();
+        let reader = hound::WavReader::open(&path).unwrap();
+        assert_eq!(reader.duration(), 8);
+        let samples: Vec<i32> = reader.into_samples().map(|s| s.unwrap()).collect();
+        // 10 stereo frames in: frames 4-9 kept, then two frames of silence
+        assert_eq!(&samples[..12], &(8..20).collect::<Vec<_>>()[..]);
+        assert!(samples[12..].iter().all(|&s| s == 0));
+    }
+}
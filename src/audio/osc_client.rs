@@ -2,26 +2,126 @@ use std::collections::{HashMap, VecDeque};
 use std::net::UdpSocket;
 use std::sync::{Arc, Mutex};
 use std::thread::{self, JoinHandle};
-use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 use rosc::{OscBundle, OscMessage, OscPacket, OscTime, OscType};
 
 /// Maximum number of waveform samples to keep per audio input instrument
 const WAVEFORM_BUFFER_SIZE: usize = 100;
 
+/// A meter reading older than this counts as silence. The meter synth replies 10
+/// times a second, so a gap this long means it was freed or the server went away.
+const METER_STALE_AFTER: Duration = Duration::from_millis(500);
+
+/// Latest master peak (left, right) and when it arrived
+#[derive(Debug, Clone, Copy, Default)]
+struct MeterReading {
+    peak: (f32, f32),
+    received: Option<Instant>,
+}
+
+impl MeterReading {
+    fn peak_at(&self, now: Instant) -> (f32, f32) {
+        match self.received {
+            Some(at) if now.duration_since(at) < METER_STALE_AFTER => self.peak,
+            _ => (0.0, 0.0),
+        }
+    }
+}
+
+/// Peak CPU (percent) at which the server counts as overloaded
+pub const HIGH_CPU_PERCENT: f32 = 80.0;
+
+/// Load figures from the server's /status.reply
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ServerStats {
+    pub ugens: i32,
+    pub synths: i32,
+    pub groups: i32,
+    pub synthdefs: i32,
+    /// Average and peak DSP load, percent
+    pub avg_cpu: f32,
+    pub peak_cpu: f32,
+}
+
+impl ServerStats {
+    /// Close enough to the limit that audio is likely dropping out
+    pub fn is_overloaded(&self) -> bool {
+        self.peak_cpu >= HIGH_CPU_PERCENT
+    }
+}
+
+/// Parse /status.reply: 1, ugens, synths, groups, synthdefs, avg cpu, peak cpu, nominal SR, actual SR
+fn parse_status_reply(msg: &OscMessage) -> Option<ServerStats> {
+    let int = |i: usize| match msg.args.get(i) {
+        Some(OscType::Int(v)) => Some(*v),
+        _ => None,
+    };
+    let float = |i: usize| match msg.args.get(i) {
+        Some(OscType::Float(v)) => Some(*v),
+        Some(OscType::Double(v)) => Some(*v as f32),
+        _ => None,
+    };
+    Some(ServerStats {
+        ugens: int(1)?,
+        synths: int(2)?,
+        groups: int(3)?,
+        synthdefs: int(4)?,
+        avg_cpu: float(5)?,
+        peak_cpu: float(6)?,
+    })
+}
+
 pub struct OscClient {
     socket: UdpSocket,
     server_addr: String,
-    meter_data: Arc<Mutex<(f32, f32)>>,
+    meter_data: Arc<Mutex<MeterReading>>,
     /// Waveform data per audio input instrument: instrument_id -> ring buffer of peak values
     audio_in_waveforms: Arc<Mutex<HashMap<u32, VecDeque<f32>>>>,
+    /// Completed /b_allocRead replies: (bufnum, Ok or failure message)
+    buffer_replies: Arc<Mutex<Vec<BufferReply>>>,
+    /// Nominal server sample rate from /status.reply
+    sample_rate: Arc<Mutex<Option<f64>>>,
+    /// Latest load figures from /status.reply
+    stats: Arc<Mutex<Option<ServerStats>>>,
     _recv_thread: Option<JoinHandle<()>>,
 }
 
+/// Server reply to a buffer read: bufnum and success or the server's error
+pub type BufferReply = (i32, Result<(), String>);
+
+/// Bufnum and outcome of a /done or /fail reply to /b_allocRead; None for any
+/// other message
+fn buffer_read_reply(msg: &OscMessage) -> Option<BufferReply> {
+    match msg.args.first() {
+        Some(OscType::String(cmd)) if cmd == "/b_allocRead" => {}
+        _ => return None,
+    }
+    let bufnum = msg.args.iter().skip(1).find_map(|arg| match arg {
+        OscType::Int(bufnum) => Some(*bufnum),
+        _ => None,
+    })?;
+    let result = match msg.addr.as_str() {
+        "/done" => Ok(()),
+        "/fail" => {
+            let err = msg.args.iter().skip(1).find_map(|arg| match arg {
+                OscType::String(s) => Some(s.clone()),
+                _ => None,
+            });
+            Err(err.unwrap_or_else(|| "Buffer read failed".to_string()))
+        }
+        _ => return None,
+    };
+    Some((bufnum, result))
+}
+
 /// Recursively process an OSC packet (handles bundles wrapping messages)
 fn handle_osc_packet(
     packet: &OscPacket,
-    meter_ref: &Arc<Mutex<(f32, f32)>>,
+    meter_ref: &Arc<Mutex<MeterReading>>,
     waveform_ref: &Arc<Mutex<HashMap<u32, VecDeque<f32>>>>,
+    buffer_ref: &Arc<Mutex<Vec<BufferReply>>>,
+    sample_rate_ref: &Arc<Mutex<Option<f64>>>,
+    stats_ref: &Arc<Mutex<Option<ServerStats>>>,
 ) {
     match packet {
         OscPacket::Message(msg) => {
@@ -35,7 +135,7 @@ fn handle_osc_packet(
                     _ => 0.0,
                 };
                 if let Ok(mut data) = meter_ref.lock() {
-                    *data = (peak_l, peak_r);
+                    *data = MeterReading { peak: (peak_l, peak_r), received: Some(Instant::now()) };
                 }
             } else if msg.addr == "/audio_in_level" && msg.args.len() >= 4 {
                 // SendPeakRMS format: /audio_in_level nodeID replyID peakL rmsL peakR rmsR
@@ -56,11 +156,30 @@ fn handle_osc_packet(
                         buffer.pop_front();
                     }
                 }
+            } else if msg.addr == "/status.reply" {
+                // args: 1, ugens, synths, groups, synthdefs, avg cpu, peak cpu, nominal SR, actual SR
+                let nominal = match msg.args.get(7) {
+                    Some(OscType::Double(v)) => Some(*v),
+                    Some(OscType::Float(v)) => Some(*v as f64),
+                    _ => None,
+                };
+                if let (Some(rate), Ok(mut data)) = (nominal, sample_rate_ref.lock()) {
+                    *data = Some(rate);
+                }
+                if let (Some(stats), Ok(mut data)) = (parse_status_reply(msg), stats_ref.lock()) {
+                    *data = Some(stats);
+                }
+            } else if msg.addr == "/done" || msg.addr == "/fail" {
+                if let Some(reply) = buffer_read_reply(msg) {
+                    if let Ok(mut replies) = buffer_ref.lock() {
+                        replies.push(reply);
+                    }
+                }
             }
         }
         OscPacket::Bundle(bundle) => {
             for p in &bundle.content {
-                handle_osc_packet(p, meter_ref, waveform_ref);
+                handle_osc_packet(p, meter_ref, waveform_ref, buffer_ref, sample_rate_ref, stats_ref);
             }
         }
     }
@@ -69,14 +188,20 @@ fn handle_osc_packet(
 impl OscClient {
     pub fn new(server_addr: &str) -> std::io::Result<Self> {
         let socket = UdpSocket::bind("0.0.0.0:0")?;
-        let meter_data = Arc::new(Mutex::new((0.0_f32, 0.0_f32)));
+        let meter_data = Arc::new(Mutex::new(MeterReading::default()));
         let audio_in_waveforms = Arc::new(Mutex::new(HashMap::new()));
+        let buffer_replies = Arc::new(Mutex::new(Vec::new()));
+        let sample_rate = Arc::new(Mutex::new(None));
+        let stats = Arc::new(Mutex::new(None));
 
         // Clone socket for receive thread
         let recv_socket = socket.try_clone()?;
         recv_socket.set_read_timeout(Some(Duration::from_millis(50)))?;
         let meter_ref = Arc::clone(&meter_data);
         let waveform_ref = Arc::clone(&audio_in_waveforms);
+        let buffer_ref = Arc::clone(&buffer_replies);
+        let sample_rate_ref = Arc::clone(&sample_rate);
+        let stats_ref = Arc::clone(&stats);
 
         let handle = thread::spawn(move || {
             let mut buf = [0u8; 4096];
@@ -84,7 +209,7 @@ impl OscClient {
                 match recv_socket.recv(&mut buf) {
                     Ok(n) => {
                         if let Ok((_, packet)) = rosc::decoder::decode_udp(&buf[..n]) {
-                            handle_osc_packet(&packet, &meter_ref, &waveform_ref);
+                            handle_osc_packet(&packet, &meter_ref, &waveform_ref, &buffer_ref, &sample_rate_ref, &stats_ref);
                         }
                     }
                     Err(ref e) if e.kind() == std::io::ErrorKind::WouldBlock => continue,
@@ -98,13 +223,24 @@ impl OscClient {
             server_addr: server_addr.to_string(),
             meter_data,
             audio_in_waveforms,
+            buffer_replies,
+            sample_rate,
+            stats,
             _recv_thread: Some(handle),
         })
     }
 
-    /// Get current peak levels (left, right) from the meter synth
+    /// Get current peak levels (left, right) from the meter synth; silence once
+    /// replies stop arriving
     pub fn meter_peak(&self) -> (f32, f32) {
-        self.meter_data.lock().map(|d| *d).unwrap_or((0.0, 0.0))
+        self.meter_data.lock().map(|d| d.peak_at(Instant::now())).unwrap_or((0.0, 0.0))
+    }
+
+    /// Drop the last meter reading so nothing is shown until the next reply
+    pub fn reset_meter(&self) {
+        if let Ok(mut data) = self.meter_data.lock() {
+            *data = MeterReading::default();
+        }
     }
 
     /// Get waveform data for an audio input instrument (returns a copy of the buffer)
@@ -115,6 +251,24 @@ impl OscClient {
             .unwrap_or_default()
     }
 
+    /// Take all buffer read replies received since the last call
+    pub fn take_buffer_replies(&self) -> Vec<BufferReply> {
+        self.buffer_replies
+            .lock()
+            .map(|mut r| std::mem::take(&mut *r))
+            .unwrap_or_default()
+    }
+
+    /// Shared handle to the server sample rate (None until /status.reply arrives)
+    pub fn sample_rate_handle(&self) -> Arc<Mutex<Option<f64>>> {
+        Arc::clone(&self.sample_rate)
+    }
+
+    /// Latest server load figures (None until the first /status.reply)
+    pub fn server_stats(&self) -> Option<ServerStats> {
+        self.stats.lock().ok().and_then(|s| *s)
+    }
+
     pub fn send_message(&self, addr: &str, args: Vec<OscType>) -> std::io::Result<()> {
         let msg = OscPacket::Message(OscMessage {
             addr: addr.to_string(),
@@ -288,3 +442,51 @@ pub fn osc_time_from_now(offset_secs: f64) -> OscTime {
 pub fn osc_time_immediate() -> OscTime {
     OscTime { seconds: 0, fractional: 1 }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_status_reply() {
+        let msg = OscMessage {
+            addr: "/status.reply".to_string(),
+            args: vec![
+                OscType::Int(1), OscType::Int(120), OscType::Int(14), OscType::Int(6),
+                OscType::Int(40), OscType::Float(12.5), OscType::Float(85.0),
+                OscType::Double(48000.0), OscType::Double(47999.9),
+            ],
+        };
+        let stats = parse_status_reply(&msg).unwrap();
+        assert_eq!((stats.ugens, stats.synths, stats.groups, stats.synthdefs), (120, 14, 6, 40));
+        assert_eq!(stats.avg_cpu, 12.5);
+        assert!(stats.is_overloaded());
+
+        let short = OscMessage { addr: "/status.reply".to_string(), args: vec![OscType::Int(1)] };
+        assert_eq!(parse_status_reply(&short), None);
+    }
+
+    #[test]
+    fn test_meter_reading_goes_stale() {
+        let now = Instant::now();
+        assert_eq!(MeterReading::default().peak_at(now), (0.0, 0.0));
+        let reading = MeterReading { peak: (0.5, 0.25), received: Some(now) };
+        assert_eq!(reading.peak_at(now + Duration::from_millis(100)), (0.5, 0.25));
+        assert_eq!(reading.peak_at(now + METER_STALE_AFTER), (0.0, 0.0));
+    }
+
+    #[test]
+    fn test_buffer_read_replies() {
+        let reply = |addr: &str, args: Vec<OscType>| buffer_read_reply(&OscMessage { addr: addr.to_string(), args });
+        let cmd = || OscType::String("/b_allocRead".to_string());
+        assert_eq!(reply("/done", vec![cmd(), OscType::Int(12)]), Some((12, Ok(()))));
+        assert_eq!(
+            reply("/fail", vec![cmd(), OscType::String("File 'x.wav' could not be opened".to_string()), OscType::Int(13)]),
+            Some((13, Err("File 'x.wav' could not be opened".to_string()))),
+        );
+        assert_eq!(reply("/fail", vec![cmd(), OscType::Int(14)]), Some((14, Err("Buffer read failed".to_string()))));
+        // Replies to other commands aren't sample loads
+        assert_eq!(reply("/done", vec![OscType::String("/d_recv".to_string())]), None);
+        assert_eq!(reply("/done", vec![cmd()]), None);
+    }
+}
@@ -0,0 +1,267 @@
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use super::sample_decode::read_samples;
+
+/// Number of peaks computed per file
+const TARGET_PEAKS: usize = 512;
+
+/// Header for on-disk peak files
+const DISK_MAGIC: &[u8; 8] = b"ILXPEAK1";
+
+#[derive(Debug, Clone)]
+struct CachedPeaks {
+    /// File modification time when the peaks were computed (None if unreadable)
+    mtime: Option<SystemTime>,
+    peaks: Vec<f32>,
+    duration_secs: f32,
+}
+
+/// Waveform peak cache keyed by file path + modification time.
+///
+/// Peaks are kept in memory and, when a disk directory is set, persisted there so
+/// they survive restarts. A file whose mtime changed is recomputed; unreadable or
+/// corrupt files are cached as empty peaks so they aren't re-read on every request.
+#[derive(Debug, Clone, Default)]
+pub struct PeakCache {
+    entries: HashMap<PathBuf, CachedPeaks>,
+    disk_dir: Option<PathBuf>,
+}
+
+impl PeakCache {
+    /// Memory-only cache
+    #[allow(dead_code)]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Cache that also persists peaks under `dir`
+    pub fn with_disk_dir(dir: PathBuf) -> Self {
+        Self {
+            entries: HashMap::new(),
+            disk_dir: Some(dir),
+        }
+    }
+
+    /// Default on-disk location: ~/.config/ilex/peaks
+    pub fn default_disk_dir() -> Option<PathBuf> {
+        dirs::config_dir().map(|d| d.join("ilex").join("peaks"))
+    }
+
+    /// Get peaks and duration for a file, computing them only if not cached
+    /// for the file's current modification time.
+    pub fn peaks(&mut self, path: &Path) -> (Vec<f32>, f32) {
+        let mtime = fs::metadata(path).and_then(|m| m.modified()).ok();
+
+        if let Some(entry) = self.entries.get(path) {
+            if entry.mtime == mtime {
+                return (entry.peaks.clone(), entry.duration_secs);
+            }
+        }
+
+        let entry = match self.read_disk(path).filter(|e| e.mtime == mtime) {
+            Some(entry) => entry,
+            None => {
+                let (peaks, duration_secs) = compute_waveform_peaks(&path.to_string_lossy());
+                let entry = CachedPeaks { mtime, peaks, duration_secs };
+                let _ = self.write_disk(path, &entry);
+                entry
+            }
+        };
+
+        let result = (entry.peaks.clone(), entry.duration_secs);
+        self.entries.insert(path.to_path_buf(), entry);
+        result
+    }
+
+    /// Drop any cached peaks for a file
+    #[allow(dead_code)]
+    pub fn invalidate(&mut self, path: &Path) {
+        self.entries.remove(path);
+        if let Some(disk_path) = self.disk_path(path) {
+            let _ = fs::remove_file(disk_path);
+        }
+    }
+
+    fn disk_path(&self, path: &Path) -> Option<PathBuf> {
+        let dir = self.disk_dir.as_ref()?;
+        let mut hasher = DefaultHasher::new();
+        path.hash(&mut hasher);
+        Some(dir.join(format!("{:016x}.peaks", hasher.finish())))
+    }
+
+    fn read_disk(&self, path: &Path) -> Option<CachedPeaks> {
+        let mut bytes = Vec::new();
+        fs::File::open(self.disk_path(path)?).ok()?.read_to_end(&mut bytes).ok()?;
+        let mut reader = ByteReader { bytes: &bytes, pos: 0 };
+
+        if reader.take(DISK_MAGIC.len())? != DISK_MAGIC {
+            return None;
+        }
+        // Guard against hash collisions: the source path is stored in the file
+        let path_len = reader.u32()? as usize;
+        if reader.take(path_len)? != path.to_string_lossy().as_bytes() {
+            return None;
+        }
+        let mtime = match reader.take(1)?[0] {
+            0 => None,
+            _ => {
+                let secs = reader.u64()?;
+                let nanos = reader.u32()?;
+                Some(UNIX_EPOCH + Duration::new(secs, nanos))
+            }
+        };
+        let duration_secs = f32::from_bits(reader.u32()?);
+        let count = reader.u32()? as usize;
+        let peaks = (0..count)
+            .map(|_| reader.u32().map(f32::from_bits))
+            .collect::<Option<Vec<f32>>>()?;
+
+        Some(CachedPeaks { mtime, peaks, duration_secs })
+    }
+
+    fn write_disk(&self, path: &Path, entry: &CachedPeaks) -> io::Result<()> {
+        let Some(disk_path) = self.disk_path(path) else {
+            return Ok(());
+        };
+        if let Some(dir) = &self.disk_dir {
+            fs::create_dir_all(dir)?;
+        }
+
+        let path_bytes = path.to_string_lossy().into_owned().into_bytes();
+        let mut out = Vec::with_capacity(64 + path_bytes.len() + entry.peaks.len() * 4);
+        out.extend_from_slice(DISK_MAGIC);
+        out.extend_from_slice(&(path_bytes.len() as u32).to_le_bytes());
+        out.extend_from_slice(&path_bytes);
+        match entry.mtime.and_then(|t| t.duration_since(UNIX_EPOCH).ok()) {
+            Some(since_epoch) => {
+                out.push(1);
+                out.extend_from_slice(&since_epoch.as_secs().to_le_bytes());
+                out.extend_from_slice(&since_epoch.subsec_nanos().to_le_bytes());
+            }
+            None => out.push(0),
+        }
+        out.extend_from_slice(&entry.duration_secs.to_bits().to_le_bytes());
+        out.extend_from_slice(&(entry.peaks.len() as u32).to_le_bytes());
+        for peak in &entry.peaks {
+            out.extend_from_slice(&peak.to_bits().to_le_bytes());
+        }
+
+        fs::File::create(disk_path)?.write_all(&out)
+    }
+}
+
+struct ByteReader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> ByteReader<'a> {
+    fn take(&mut self, n: usize) -> Option<&'a [u8]> {
+        let slice = self.bytes.get(self.pos..self.pos.checked_add(n)?)?;
+        self.pos += n;
+        Some(slice)
+    }
+
+    fn u32(&mut self) -> Option<u32> {
+        self.take(4).map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+    }
+
+    fn u64(&mut self) -> Option<u64> {
+        self.take(8).map(|b| {
+            let mut arr = [0u8; 8];
+            arr.copy_from_slice(b);
+            u64::from_le_bytes(arr)
+        })
+    }
+}
+
+/// Compute waveform peaks from a sample file for display (WAV or any decodable format)
+pub fn compute_waveform_peaks(path: &str) -> (Vec<f32>, f32) {
+    let audio = match read_samples(Path::new(path)) {
+        Ok(a) => a,
+        Err(_) => return (Vec::new(), 0.0),
+    };
+    let duration_secs = audio.duration_secs();
+
+    let samples_per_peak = (audio.samples.len() / TARGET_PEAKS).max(1);
+    let peaks = audio
+        .samples
+        .chunks(samples_per_peak)
+        .map(|chunk| chunk.iter().fold(0.0f32, |acc, &s| acc.max(s.abs())))
+        .collect();
+
+    (peaks, duration_secs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("ilex_peak_cache_{}_{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn write_wav(path: &Path, amplitude: f32) {
+        let spec = hound::WavSpec {
+            channels: 1,
+            sample_rate: 1000,
+            bits_per_sample: 32,
+            sample_format: hound::SampleFormat::Float,
+        };
+        let mut writer = hound::WavWriter::create(path, spec).unwrap();
+        for _ in 0..1000 {
+            writer.write_sample(amplitude).unwrap();
+        }
+        writer.finalize().unwrap();
+    }
+
+    #[test]
+    fn test_changed_mtime_invalidates() {
+        let dir = temp_dir("mtime");
+        let wav = dir.join("a.wav");
+        write_wav(&wav, 0.5);
+        let mut cache = PeakCache::with_disk_dir(dir.join("peaks"));
+
+        let (peaks, duration) = cache.peaks(&wav);
+        assert!((peaks[0] - 0.5).abs() < 1e-6);
+        assert!((duration - 1.0).abs() < 1e-6);
+
+        // Rewrite with new content and a different mtime
+        write_wav(&wav, 0.25);
+        let file = fs::File::options().write(true).open(&wav).unwrap();
+        file.set_modified(SystemTime::now() + Duration::from_secs(10)).unwrap();
+        let (peaks, _) = cache.peaks(&wav);
+        assert!((peaks[0] - 0.25).abs() < 1e-6);
+
+        // A fresh cache picks up the persisted peaks for the current mtime
+        let mut reloaded = PeakCache::with_disk_dir(dir.join("peaks"));
+        assert_eq!(reloaded.read_disk(&wav).map(|e| e.peaks.len()), Some(peaks.len()));
+        assert!((reloaded.peaks(&wav).0[0] - 0.25).abs() < 1e-6);
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_corrupt_file_cached_as_empty() {
+        let dir = temp_dir("corrupt");
+        let bad = dir.join("bad.wav");
+        fs::write(&bad, b"not a wav file").unwrap();
+        let mut cache = PeakCache::new();
+
+        assert_eq!(cache.peaks(&bad), (Vec::new(), 0.0));
+        let entry = cache.entries.get(&bad).unwrap();
+        assert!(entry.peaks.is_empty());
+        assert!(entry.mtime.is_some());
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,538 @@
+//! Decoding of non-WAV sample files (mp3, flac, ogg, ...) via symphonia.
+//!
+//! WAV and AIFF files are passed to SuperCollider untouched. Anything else is decoded to
+//! PCM and written to a converted WAV in the temp directory, since scsynth's
+//! `/b_allocRead` only reads formats libsndfile understands.
+//!
+//! When the server's sample rate is known, files at a different rate are
+//! resampled to it (see [`prepare_sample`]) so buffers play back 1:1. With
+//! resampling disabled, buffers keep the file's rate and the sampler synthdefs
+//! compensate with `BufRateScale`/`BufSampleRate` at playback — pitch stays right
+//! but the runtime interpolation adds aliasing. Converted files are always stereo because the sampler
+//! synthdefs read buffers with `BufRd.ar(2, ...)`: mono is duplicated to both
+//! sides and files with more than two channels keep the first two.
+
+use std::collections::hash_map::DefaultHasher;
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+
+use symphonia::core::audio::SampleBuffer;
+use symphonia::core::codecs::{DecoderOptions, CODEC_TYPE_NULL};
+use symphonia::core::errors::Error as SymphoniaError;
+use symphonia::core::formats::FormatOptions;
+use symphonia::core::io::MediaSourceStream;
+use symphonia::core::meta::MetadataOptions;
+use symphonia::core::probe::Hint;
+
+/// Interleaved PCM decoded from a sample file
+#[derive(Debug, Clone)]
+pub struct DecodedAudio {
+    pub samples: Vec<f32>,
+    pub channels: usize,
+    pub sample_rate: u32,
+}
+
+impl DecodedAudio {
+    /// Duration in seconds
+    pub fn duration_secs(&self) -> f32 {
+        if self.channels == 0 || self.sample_rate == 0 {
+            return 0.0;
+        }
+        self.samples.len() as f32 / (self.sample_rate as f32 * self.channels as f32)
+    }
+
+    /// Convert to two interleaved channels (mono duplicated, extra channels dropped)
+    pub fn to_stereo(&self) -> Vec<f32> {
+        match self.channels {
+            0 => Vec::new(),
+            1 => self.samples.iter().flat_map(|&s| [s, s]).collect(),
+            2 => self.samples.clone(),
+            n => self.samples.chunks(n).flat_map(|frame| [frame[0], frame[1]]).collect(),
+        }
+    }
+}
+
+/// Whether a path is a WAV file (loaded directly, without conversion)
+pub fn is_wav(path: &Path) -> bool {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .map(|e| e.eq_ignore_ascii_case("wav") || e.eq_ignore_ascii_case("wave"))
+        .unwrap_or(false)
+}
+
+/// Whether scsynth can read the file directly (WAV/AIFF), so no conversion is needed
+pub fn is_server_native(path: &Path) -> bool {
+    is_wav(path)
+        || path.extension()
+            .and_then(|e| e.to_str())
+            .map(|e| e.eq_ignore_ascii_case("aif") || e.eq_ignore_ascii_case("aiff"))
+            .unwrap_or(false)
+}
+
+/// Read a sample file to interleaved f32 PCM. WAVs use hound; everything else symphonia.
+pub fn read_samples(path: &Path) -> Result<DecodedAudio, String> {
+    if is_wav(path) {
+        read_wav(path)
+    } else {
+        decode_file(path)
+    }
+}
+
+fn read_wav(path: &Path) -> Result<DecodedAudio, String> {
+    let reader = hound::WavReader::open(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    let spec = reader.spec();
+    let samples: Vec<f32> = match spec.sample_format {
+        hound::SampleFormat::Int => {
+            let max_val = (1i64 << (spec.bits_per_sample - 1)) as f32;
+            reader.into_samples::<i32>()
+                .filter_map(|s| s.ok())
+                .map(|s| s as f32 / max_val)
+                .collect()
+        }
+        hound::SampleFormat::Float => {
+            reader.into_samples::<f32>()
+                .filter_map(|s| s.ok())
+                .collect()
+        }
+    };
+    Ok(DecodedAudio {
+        samples,
+        channels: spec.channels as usize,
+        sample_rate: spec.sample_rate,
+    })
+}
+
+/// Decode any format symphonia supports to interleaved f32 PCM
+pub fn decode_file(path: &Path) -> Result<DecodedAudio, String> {
+    let file = fs::File::open(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    let mss = MediaSourceStream::new(Box::new(file), Default::default());
+
+    let mut hint = Hint::new();
+    if let Some(ext) = path.extension().and_then(|e| e.to_str()) {
+        hint.with_extension(ext);
+    }
+
+    let unsupported = || {
+        let ext = path.extension().map(|e| e.to_string_lossy().to_string()).unwrap_or_default();
+        format!("Unsupported audio format '{}': {}", ext, path.display())
+    };
+
+    let probed = symphonia::default::get_probe()
+        .format(&hint, mss, &FormatOptions::default(), &MetadataOptions::default())
+        .map_err(|_| unsupported())?;
+    let mut format = probed.format;
+
+    let track = format
+        .tracks()
+        .iter()
+        .find(|t| t.codec_params.codec != CODEC_TYPE_NULL)
+        .ok_or_else(unsupported)?;
+    let track_id = track.id;
+    let mut sample_rate = track.codec_params.sample_rate.unwrap_or(0);
+    let mut channels = track.codec_params.channels.map(|c| c.count()).unwrap_or(0);
+
+    let mut decoder = symphonia::default::get_codecs()
+        .make(&track.codec_params, &DecoderOptions::default())
+        .map_err(|_| unsupported())?;
+
+    let mut samples = Vec::new();
+    loop {
+        let packet = match format.next_packet() {
+            Ok(packet) => packet,
+            Err(SymphoniaError::IoError(e)) if e.kind() == std::io::ErrorKind::UnexpectedEof => break,
+            Err(SymphoniaError::ResetRequired) => break,
+            Err(e) => return Err(format!("{}: {}", path.display(), e)),
+        };
+        if packet.track_id() != track_id {
+            continue;
+        }
+        match decoder.decode(&packet) {
+            Ok(decoded) => {
+                let spec = *decoded.spec();
+                sample_rate = spec.rate;
+                channels = spec.channels.count();
+                let mut buf = SampleBuffer::<f32>::new(decoded.capacity() as u64, spec);
+                buf.copy_interleaved_ref(decoded);
+                samples.extend_from_slice(buf.samples());
+            }
+            // Skip corrupt packets rather than failing the whole file
+            Err(SymphoniaError::DecodeError(_)) => continue,
+            Err(e) => return Err(format!("{}: {}", path.display(), e)),
+        }
+    }
+
+    if channels == 0 || sample_rate == 0 {
+        return Err(unsupported());
+    }
+    Ok(DecodedAudio { samples, channels, sample_rate })
+}
+
+/// A sample file ready for `/b_allocRead`
+#[derive(Debug, Clone, PartialEq)]
+pub struct PreparedSample {
+    /// Path scsynth should read
+    pub path: String,
+    /// Sample rate of the source file (None if it couldn't be determined)
+    pub original_sample_rate: Option<u32>,
+    /// Sample rate of the file at `path` (differs from the original when resampled)
+    pub sample_rate: Option<u32>,
+}
+
+/// Sample rate from a WAV header (None for other formats)
+fn wav_sample_rate(path: &Path) -> Option<u32> {
+    if !is_wav(path) {
+        return None;
+    }
+    hound::WavReader::open(path).ok().map(|r| r.spec().sample_rate)
+}
+
+/// Prepare a file for scsynth: convert non-native formats, then resample to
+/// `target_rate` if given and different from the file's rate.
+pub fn prepare_sample(path: &str, target_rate: Option<u32>) -> Result<PreparedSample, String> {
+    let server_path = prepare_for_server(path)?;
+    let original = wav_sample_rate(Path::new(&server_path));
+
+    let target = match (original, target_rate) {
+        (Some(from), Some(to)) if from != to && to > 0 => to,
+        _ => {
+            return Ok(PreparedSample {
+                path: server_path,
+                original_sample_rate: original,
+                sample_rate: original,
+            })
+        }
+    };
+
+    let dest = converted_path(Path::new(path), &format!("_{}", target))?;
+    if !dest.exists() {
+        let resampled = resample(&read_samples(Path::new(&server_path))?, target);
+        write_wav(&dest, &resampled.samples, resampled.channels as u16, target)?;
+    }
+    Ok(PreparedSample {
+        path: dest.to_string_lossy().to_string(),
+        original_sample_rate: original,
+        sample_rate: Some(target),
+    })
+}
+
+/// Resample interleaved audio to `target_rate` with a windowed-sinc filter.
+/// When downsampling, the filter cutoff drops to the new Nyquist to avoid aliasing.
+pub fn resample(audio: &DecodedAudio, target_rate: u32) -> DecodedAudio {
+    if audio.sample_rate == target_rate || audio.sample_rate == 0 || audio.channels == 0 {
+        return audio.clone();
+    }
+    const HALF_TAPS: i64 = 16;
+
+    let channels = audio.channels;
+    let ratio = target_rate as f64 / audio.sample_rate as f64;
+    let cutoff = ratio.min(1.0);
+    let in_frames = audio.samples.len() / channels;
+    let out_frames = (in_frames as f64 * ratio).round() as usize;
+
+    let sinc = |x: f64| {
+        if x.abs() < 1e-9 {
+            1.0
+        } else {
+            (std::f64::consts::PI * x).sin() / (std::f64::consts::PI * x)
+        }
+    };
+    let hann = |t: f64| {
+        if t.abs() >= 1.0 {
+            0.0
+        } else {
+            0.5 * (1.0 + (std::f64::consts::PI * t).cos())
+        }
+    };
+
+    let mut samples = Vec::with_capacity(out_frames * channels);
+    let mut weights = Vec::with_capacity(2 * HALF_TAPS as usize);
+    for frame in 0..out_frames {
+        let pos = frame as f64 / ratio;
+        let center = pos.floor() as i64;
+        let first = (center - HALF_TAPS + 1).max(0);
+        let last = (center + HALF_TAPS).min(in_frames as i64 - 1);
+
+        weights.clear();
+        for i in first..=last {
+            let x = pos - i as f64;
+            weights.push(cutoff * sinc(x * cutoff) * hann(x / HALF_TAPS as f64));
+        }
+        // Normalize so DC gain stays 1, including at the edges
+        let total: f64 = weights.iter().sum();
+        let norm = if total.abs() > 1e-9 { 1.0 / total } else { 0.0 };
+
+        for ch in 0..channels {
+            let acc: f64 = (first..=last)
+                .zip(&weights)
+                .map(|(i, w)| audio.samples[i as usize * channels + ch] as f64 * w)
+                .sum();
+            samples.push((acc * norm) as f32);
+        }
+    }
+
+    DecodedAudio {
+        samples,
+        channels,
+        sample_rate: target_rate,
+    }
+}
+
+/// Peak below which a frame counts as silence when trimming (about -40 dBFS)
+pub const TRIM_THRESHOLD: f32 = 0.01;
+/// Audio kept on either side of the detected sound when trimming, so soft attacks
+/// and tails just under the threshold aren't cut
+pub const TRIM_PRE_ROLL_SECS: f32 = 0.01;
+/// Target peak for normalize (about -0.2 dBFS)
+pub const NORMALIZE_PEAK: f32 = 0.98;
+
+/// Frames `start..end` that `trim_silence` keeps: from the first to the last frame
+/// whose peak reaches `threshold`, padded by `pre_roll_secs`. All of it when silent.
+pub fn sound_bounds(audio: &DecodedAudio, threshold: f32, pre_roll_secs: f32) -> (usize, usize) {
+    if audio.channels == 0 {
+        return (0, 0);
+    }
+    let loud = |frame: &[f32]| frame.iter().any(|s| s.abs() >= threshold);
+    let frames: Vec<&[f32]> = audio.samples.chunks(audio.channels).collect();
+    let (Some(first), Some(last)) = (frames.iter().position(|f| loud(f)), frames.iter().rposition(|f| loud(f))) else {
+        return (0, frames.len());
+    };
+
+    let pad = (pre_roll_secs * audio.sample_rate as f32).round() as usize;
+    (first.saturating_sub(pad), (last + 1 + pad).min(frames.len()))
+}
+
+/// Remove leading and trailing frames whose peak across channels is below `threshold`,
+/// keeping `pre_roll_secs` of padding around the sound. All-silent audio is returned unchanged.
+pub fn trim_silence(audio: &DecodedAudio, threshold: f32, pre_roll_secs: f32) -> DecodedAudio {
+    if audio.channels == 0 {
+        return audio.clone();
+    }
+    let (start, end) = sound_bounds(audio, threshold, pre_roll_secs);
+    DecodedAudio {
+        samples: audio.samples[start * audio.channels..end * audio.channels].to_vec(),
+        channels: audio.channels,
+        sample_rate: audio.sample_rate,
+    }
+}
+
+/// Scale audio so its absolute peak is `target_peak`. Returns None for silent audio,
+/// which has no level to scale.
+pub fn normalize(audio: &DecodedAudio, target_peak: f32) -> Option<DecodedAudio> {
+    let peak = audio.samples.iter().fold(0.0f32, |m, s| m.max(s.abs()));
+    if peak < 1e-6 {
+        return None;
+    }
+    let gain = target_peak / peak;
+    Some(DecodedAudio {
+        samples: audio.samples.iter().map(|s| s * gain).collect(),
+        channels: audio.channels,
+        sample_rate: audio.sample_rate,
+    })
+}
+
+/// Directory for trimmed/normalized copies of samples. These are referenced by saved
+/// projects, so they live in the user's data directory rather than temp.
+fn processed_dir() -> PathBuf {
+    dirs::data_local_dir()
+        .map(|d| d.join("ilex").join("processed"))
+        .unwrap_or_else(|| std::env::temp_dir().join("ilex").join("processed"))
+}
+
+/// Write the result of an edit on `source` as a stereo float WAV and return its path.
+/// The source file is never modified; `op` tags the file name (e.g. "trim").
+pub fn write_processed(source: &Path, audio: &DecodedAudio, op: &str) -> Result<String, String> {
+    let stem = source.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default();
+    let dest = processed_dir().join(format!("{}_{}_{:016x}.wav", stem, op, source_hash(source)?));
+    write_wav(&dest, &audio.to_stereo(), 2, audio.sample_rate)?;
+    Ok(dest.to_string_lossy().to_string())
+}
+
+/// Directory for converted WAVs
+fn converted_dir() -> PathBuf {
+    std::env::temp_dir().join("ilex").join("converted")
+}
+
+/// Return a path SuperCollider can read: WAV/AIFF unchanged, other formats decoded to
+/// a stereo float WAV in the temp directory (reused while the source is unmodified).
+pub fn prepare_for_server(path: &str) -> Result<String, String> {
+    let source = Path::new(path);
+    if is_server_native(source) {
+        return Ok(path.to_string());
+    }
+
+    let dest = converted_path(source, "")?;
+    if dest.exists() {
+        return Ok(dest.to_string_lossy().to_string());
+    }
+
+    let decoded = decode_file(source)?;
+    write_wav(&dest, &decoded.to_stereo(), 2, decoded.sample_rate)?;
+    Ok(dest.to_string_lossy().to_string())
+}
+
+/// Converted-file path for a source, unique per source path + mtime
+fn converted_path(source: &Path, suffix: &str) -> Result<PathBuf, String> {
+    Ok(converted_dir().join(format!("{:016x}{}.wav", source_hash(source)?, suffix)))
+}
+
+/// Hash of a source's path and mtime
+fn source_hash(source: &Path) -> Result<u64, String> {
+    let mtime = fs::metadata(source)
+        .and_then(|m| m.modified())
+        .map_err(|e| format!("{}: {}", source.display(), e))?;
+    let mut hasher = DefaultHasher::new();
+    source.hash(&mut hasher);
+    mtime.hash(&mut hasher);
+    Ok(hasher.finish())
+}
+
+fn write_wav(dest: &Path, samples: &[f32], channels: u16, sample_rate: u32) -> Result<(), String> {
+    if let Some(dir) = dest.parent() {
+        fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+    }
+    let spec = hound::WavSpec {
+        channels,
+        sample_rate,
+        bits_per_sample: 32,
+        sample_format: hound::SampleFormat::Float,
+    };
+    // Write to a temporary name first so a partial file is never reused
+    let partial = dest.with_extension("part");
+    let mut writer = hound::WavWriter::create(&partial, spec).map_err(|e| e.to_string())?;
+    for &sample in samples {
+        writer.write_sample(sample).map_err(|e| e.to_string())?;
+    }
+    writer.finalize().map_err(|e| e.to_string())?;
+    fs::rename(&partial, dest).map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_test_wav(path: &Path, channels: u16, samples: &[f32]) {
+        let spec = hound::WavSpec {
+            channels,
+            sample_rate: 22050,
+            bits_per_sample: 32,
+            sample_format: hound::SampleFormat::Float,
+        };
+        let mut writer = hound::WavWriter::create(path, spec).unwrap();
+        for &s in samples {
+            writer.write_sample(s).unwrap();
+        }
+        writer.finalize().unwrap();
+    }
+
+    #[test]
+    fn test_symphonia_matches_hound() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("mono.wav");
+        let samples: Vec<f32> = (0..1000).map(|i| (i as f32 / 1000.0) - 0.5).collect();
+        write_test_wav(&path, 1, &samples);
+
+        let via_hound = read_samples(&path).unwrap();
+        let via_symphonia = decode_file(&path).unwrap();
+        assert_eq!(via_symphonia.channels, 1);
+        assert_eq!(via_symphonia.sample_rate, 22050);
+        assert_eq!(via_symphonia.samples.len(), via_hound.samples.len());
+        assert!((via_symphonia.duration_secs() - via_hound.duration_secs()).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_to_stereo() {
+        let mono = DecodedAudio { samples: vec![0.1, 0.2], channels: 1, sample_rate: 44100 };
+        assert_eq!(mono.to_stereo(), vec![0.1, 0.1, 0.2, 0.2]);
+
+        let quad = DecodedAudio { samples: vec![1.0, 2.0, 3.0, 4.0], channels: 4, sample_rate: 44100 };
+        assert_eq!(quad.to_stereo(), vec![1.0, 2.0]);
+    }
+
+    #[test]
+    fn test_unsupported_format() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("notes.xyz");
+        fs::write(&path, b"definitely not audio").unwrap();
+        let err = decode_file(&path).unwrap_err();
+        assert!(err.starts_with("Unsupported audio format 'xyz'"), "{}", err);
+        assert!(prepare_for_server(&path.to_string_lossy()).is_err());
+    }
+
+    #[test]
+    fn test_wav_passes_through() {
+        assert_eq!(prepare_for_server("/samples/kick.WAV").unwrap(), "/samples/kick.WAV");
+        assert_eq!(prepare_for_server("/samples/snare.aiff").unwrap(), "/samples/snare.aiff");
+    }
+
+    #[test]
+    fn test_resample_keeps_pitch_and_length() {
+        // 1 second of 1 kHz at 48k
+        let samples: Vec<f32> = (0..48000)
+            .map(|i| (2.0 * std::f32::consts::PI * 1000.0 * i as f32 / 48000.0).sin())
+            .collect();
+        let audio = DecodedAudio { samples, channels: 1, sample_rate: 48000 };
+        let out = resample(&audio, 44100);
+        assert_eq!(out.sample_rate, 44100);
+        assert_eq!(out.samples.len(), 44100);
+        assert!((out.duration_secs() - audio.duration_secs()).abs() < 1e-4);
+
+        // Zero crossings over the second still count a 1 kHz tone
+        let crossings = out.samples.windows(2).filter(|w| (w[0] < 0.0) != (w[1] < 0.0)).count();
+        assert!((1995..=2005).contains(&crossings), "{}", crossings);
+    }
+
+    #[test]
+    fn test_prepare_sample_resamples_wav() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("hat.wav");
+        write_test_wav(&path, 2, &[0.5; 2205 * 2]);
+        let path_str = path.to_string_lossy().to_string();
+
+        let prepared = prepare_sample(&path_str, Some(44100)).unwrap();
+        assert_eq!(prepared.original_sample_rate, Some(22050));
+        assert_eq!(prepared.sample_rate, Some(44100));
+        assert_ne!(prepared.path, path_str);
+        let reader = hound::WavReader::open(&prepared.path).unwrap();
+        assert_eq!(reader.spec().sample_rate, 44100);
+        assert_eq!(reader.spec().channels, 2);
+        assert_eq!(reader.duration(), 4410);
+
+        // Disabled (no target) or already matching: original file is used
+        let unchanged = prepare_sample(&path_str, None).unwrap();
+        assert_eq!(unchanged.path, path_str);
+        assert_eq!(unchanged.sample_rate, Some(22050));
+        assert_eq!(prepare_sample(&path_str, Some(22050)).unwrap().path, path_str);
+    }
+
+    #[test]
+    fn test_trim_keeps_pre_roll() {
+        // 1 kHz rate: 100 silent frames, a quiet lead-in, the hit, then 100 silent frames
+        let mut samples = vec![0.0; 100];
+        samples.extend([0.005; 5]);
+        samples.extend([0.8; 10]);
+        samples.extend(vec![0.0; 100]);
+        let audio = DecodedAudio { samples, channels: 1, sample_rate: 1000 };
+
+        assert_eq!(sound_bounds(&audio, TRIM_THRESHOLD, 0.01), (95, 125));
+        let trimmed = trim_silence(&audio, TRIM_THRESHOLD, 0.01);
+        // 10 frames of pre-roll covers the sub-threshold lead-in, plus 10 after the hit
+        assert_eq!(trimmed.samples.len(), 30);
+        assert_eq!(&trimmed.samples[5..10], &[0.005; 5]);
+        assert_eq!(trimmed.samples[10], 0.8);
+
+        let silent = DecodedAudio { samples: vec![0.0; 50], channels: 1, sample_rate: 1000 };
+        assert_eq!(trim_silence(&silent, TRIM_THRESHOLD, 0.01).samples.len(), 50);
+        assert_eq!(sound_bounds(&silent, TRIM_THRESHOLD, 0.01), (0, 50));
+    }
+
+    #[test]
+    fn test_normalize() {
+        let audio = DecodedAudio { samples: vec![0.25, -0.5, 0.1, 0.0], channels: 2, sample_rate: 44100 };
+        let out = normalize(&audio, 1.0).unwrap();
+        assert_eq!(out.samples, vec![0.5, -1.0, 0.2, 0.0]);
+
+        let silent = DecodedAudio { samples: vec![0.0; 8], channels: 2, sample_rate: 44100 };
+        assert!(normalize(&silent, 1.0).is_none());
+    }
+}
@@ -0,0 +1,107 @@
+//! Capture of scsynth's stdout/stderr.
+//!
+//! Reader threads copy each line to the log file and into a capped ring buffer,
+//! so the last lines can be shown when the server fails. The threads end when
+//! the pipes close, which happens once the process exits.
+
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::process::Child;
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+
+/// Lines kept in memory; older boot noise is dropped
+pub const SERVER_LOG_LINES: usize = 200;
+
+type Lines = Arc<Mutex<VecDeque<String>>>;
+
+pub struct ServerOutput {
+    lines: Lines,
+    readers: Vec<JoinHandle<()>>,
+}
+
+impl ServerOutput {
+    /// Start reading a spawned server's piped stdout and stderr, mirroring to `log`
+    pub fn capture(child: &mut Child, log: Option<File>) -> Self {
+        let lines: Lines = Arc::new(Mutex::new(VecDeque::new()));
+        let log = log.map(|f| Arc::new(Mutex::new(f)));
+        let mut readers = Vec::new();
+        if let Some(stdout) = child.stdout.take() {
+            readers.push(spawn_reader(stdout, Arc::clone(&lines), log.clone()));
+        }
+        if let Some(stderr) = child.stderr.take() {
+            readers.push(spawn_reader(stderr, Arc::clone(&lines), log));
+        }
+        Self { lines, readers }
+    }
+
+    /// The last `n` lines, oldest first
+    pub fn tail(&self, n: usize) -> Vec<String> {
+        self.lines
+            .lock()
+            .map(|l| l.iter().skip(l.len().saturating_sub(n)).cloned().collect())
+            .unwrap_or_default()
+    }
+
+    /// Wait for the readers to drain; call once the process has exited
+    pub fn finish(&mut self) {
+        for reader in self.readers.drain(..) {
+            let _ = reader.join();
+        }
+    }
+}
+
+fn spawn_reader<R: Read + Send + 'static>(
+    source: R,
+    lines: Lines,
+    log: Option<Arc<Mutex<File>>>,
+) -> JoinHandle<()> {
+    thread::spawn(move || {
+        let mut reader = BufReader::new(source);
+        let mut raw = Vec::new();
+        loop {
+            raw.clear();
+            match reader.read_until(b'\n', &mut raw) {
+                Ok(0) | Err(_) => break,
+                Ok(_) => {}
+            }
+            let line = String::from_utf8_lossy(&raw).trim_end().to_string();
+            if let Some(log) = &log {
+                if let Ok(mut file) = log.lock() {
+                    let _ = writeln!(file, "{}", line);
+                }
+            }
+            if line.is_empty() {
+                continue;
+            }
+            if let Ok(mut lines) = lines.lock() {
+                if lines.len() == SERVER_LOG_LINES {
+                    lines.pop_front();
+                }
+                lines.push_back(line);
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    #[test]
+    fn test_reader_keeps_capped_tail() {
+        let text: String = (0..SERVER_LOG_LINES + 5).map(|i| format!("line {}\n", i)).collect();
+        let mut output = ServerOutput {
+            lines: Arc::new(Mutex::new(VecDeque::new())),
+            readers: Vec::new(),
+        };
+        output.readers.push(spawn_reader(Cursor::new(text.into_bytes()), Arc::clone(&output.lines), None));
+        output.finish();
+
+        assert_eq!(output.lines.lock().unwrap().len(), SERVER_LOG_LINES);
+        let last = SERVER_LOG_LINES + 4;
+        assert_eq!(output.tail(2), [format!("line {}", last - 1), format!("line {}", last)]);
+    }
+}
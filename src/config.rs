@@ -1,10 +1,14 @@
+use std::collections::HashMap;
 use std::path::PathBuf;
 
 use serde::Deserialize;
 
 use crate::state::music::{Key, Scale};
-use crate::state::MusicalSettings;
-use crate::ui::KeyboardLayout;
+use crate::state::session::{MAX_BPM, MAX_TICKS_PER_BEAT, MAX_TUNING_A4, MIN_BPM, MIN_TICKS_PER_BEAT, MIN_TUNING_A4};
+use crate::state::{
+    EffectType, FilterType, MusicalSettings, ParamDefaults, DEFAULT_LIVE_NOTE_LENGTH, MAX_RECORD_OFFSET_MS,
+};
+use crate::ui::{Color, ColorDepth, KeyboardLayout, Theme};
 
 const DEFAULT_CONFIG: &str = include_str!("../config.toml");
 
@@ -12,6 +16,53 @@ const DEFAULT_CONFIG: &str = include_str!("../config.toml");
 struct ConfigFile {
     #[serde(default)]
     defaults: DefaultsConfig,
+    #[serde(default)]
+    audio: AudioConfig,
+    #[serde(default)]
+    ui: UiConfig,
+}
+
+#[derive(Deserialize, Default)]
+struct AudioConfig {
+    /// Resample loaded samples to the server's sample rate
+    resample_samples: Option<bool>,
+    /// Seconds over which param changes glide (filter cutoff, levels, mix...); 0 = instant
+    param_smoothing: Option<f32>,
+    /// Beats a live-played note sounds before auto-release
+    live_note_length: Option<f32>,
+    /// Keep a note played on the computer keyboard sounding while its key is held
+    live_note_hold: Option<bool>,
+    /// Milliseconds recorded MIDI is moved earlier to cancel input latency
+    record_offset_ms: Option<f32>,
+    /// Let effects ring out on stop instead of silencing them with the voices
+    stop_tails: Option<bool>,
+    /// Explicit scsynth executable, used instead of the built-in locations
+    scsynth_path: Option<String>,
+    /// Explicit sclang executable, used instead of the built-in locations
+    sclang_path: Option<String>,
+}
+
+#[derive(Deserialize, Default)]
+struct UiConfig {
+    /// Capture mouse events (click notes/steps, drag faders)
+    mouse: Option<bool>,
+    /// Built-in theme name: "dark", "light" or "high-contrast"
+    theme: Option<String>,
+    /// "auto" (detect from the environment), "truecolor", "256", "16" or "mono"
+    color_depth: Option<String>,
+    /// Per-color overrides on top of the theme, as "#rrggbb"
+    #[serde(default)]
+    colors: ThemeColors,
+}
+
+#[derive(Deserialize, Default)]
+struct ThemeColors {
+    background: Option<String>,
+    foreground: Option<String>,
+    accent: Option<String>,
+    selection: Option<String>,
+    muted: Option<String>,
+    border: Option<String>,
 }
 
 #[derive(Deserialize, Default)]
@@ -21,12 +72,21 @@ struct DefaultsConfig {
     scale: Option<String>,
     tuning_a4: Option<f32>,
     time_signature: Option<[u8; 2]>,
+    ticks_per_beat: Option<u32>,
     snap: Option<bool>,
     keyboard_layout: Option<String>,
+    /// Starting params for newly added effects, by effect then param name
+    #[serde(default)]
+    effects: HashMap<String, HashMap<String, f32>>,
+    /// Starting params for newly added filters, by filter then param name
+    #[serde(default)]
+    filters: HashMap<String, HashMap<String, f32>>,
 }
 
 pub struct Config {
     defaults: DefaultsConfig,
+    audio: AudioConfig,
+    ui: UiConfig,
 }
 
 impl Config {
@@ -37,8 +97,13 @@ impl Config {
         if let Some(path) = user_config_path() {
             if path.exists() {
                 if let Ok(contents) = std::fs::read_to_string(&path) {
-                    if let Ok(user) = toml::from_str::<ConfigFile>(&contents) {
-                        merge_defaults(&mut base.defaults, user.defaults);
+                    match toml::from_str::<ConfigFile>(&contents) {
+                        Ok(user) => {
+                            merge_defaults(&mut base.defaults, user.defaults);
+                            merge_audio(&mut base.audio, user.audio);
+                            merge_ui(&mut base.ui, user.ui);
+                        }
+                        Err(e) => eprintln!("Ignoring invalid {}: {}", path.display(), e),
                     }
                 }
             }
@@ -46,7 +111,82 @@ impl Config {
 
         Config {
             defaults: base.defaults,
+            audio: base.audio,
+            ui: base.ui,
+        }
+    }
+
+    /// Whether samples are resampled to the server rate on load (off = lo-fi playback)
+    pub fn resample_samples(&self) -> bool {
+        self.audio.resample_samples.unwrap_or(true)
+    }
+
+    /// Param smoothing time in seconds; negative or non-finite values mean none
+    pub fn param_smoothing(&self) -> f32 {
+        self.audio.param_smoothing.filter(|s| s.is_finite() && *s > 0.0).unwrap_or(0.0)
+    }
+
+    /// Explicit scsynth executable: `$ILEX_SCSYNTH`, else `[audio] scsynth_path`
+    pub fn scsynth_path(&self) -> Option<PathBuf> {
+        path_setting("ILEX_SCSYNTH", &self.audio.scsynth_path)
+    }
+
+    /// Live-play auto-release in beats; non-positive or non-finite values fall back to half a beat
+    pub fn live_note_length(&self) -> f32 {
+        self.audio.live_note_length.filter(|l| l.is_finite() && *l > 0.0).unwrap_or(DEFAULT_LIVE_NOTE_LENGTH)
+    }
+
+    /// Whether computer-keyboard notes sound until the key comes up; off by default
+    pub fn live_note_hold(&self) -> bool {
+        self.audio.live_note_hold.unwrap_or(false)
+    }
+
+    /// Record latency offset in ms, limited to `MAX_RECORD_OFFSET_MS` either way;
+    /// non-finite values mean none
+    pub fn record_offset_ms(&self) -> f32 {
+        self.audio.record_offset_ms
+            .filter(|ms| ms.is_finite())
+            .map_or(0.0, |ms| ms.clamp(-MAX_RECORD_OFFSET_MS, MAX_RECORD_OFFSET_MS))
+    }
+
+    /// Whether stopping playback leaves delays and reverbs ringing; off (hard cut) by default
+    pub fn stop_tails(&self) -> bool {
+        self.audio.stop_tails.unwrap_or(false)
+    }
+
+    /// Explicit sclang executable: `$ILEX_SCLANG`, else `[audio] sclang_path`
+    pub fn sclang_path(&self) -> Option<PathBuf> {
+        path_setting("ILEX_SCLANG", &self.audio.sclang_path)
+    }
+
+    /// Whether mouse input is captured; off leaves the terminal's own selection working
+    pub fn mouse_enabled(&self) -> bool {
+        self.ui.mouse.unwrap_or(true)
+    }
+
+    /// The configured theme: a built-in (dark by default) with any color overrides applied
+    pub fn theme(&self) -> Theme {
+        let mut theme = self.ui.theme.as_deref().and_then(Theme::by_name).unwrap_or_default();
+        let colors = &self.ui.colors;
+        let overrides = [
+            (&colors.background, &mut theme.background),
+            (&colors.foreground, &mut theme.foreground),
+            (&colors.accent, &mut theme.accent),
+            (&colors.selection, &mut theme.selection),
+            (&colors.muted, &mut theme.muted),
+            (&colors.border, &mut theme.border),
+        ];
+        for (hex, color) in overrides {
+            if let Some(parsed) = hex.as_deref().and_then(Color::from_hex) {
+                *color = parsed;
+            }
         }
+        theme
+    }
+
+    /// Terminal color depth: the configured value, or detected when "auto"
+    pub fn color_depth(&self) -> ColorDepth {
+        self.ui.color_depth.as_deref().and_then(ColorDepth::from_name).unwrap_or_else(ColorDepth::detect)
     }
 
     pub fn keyboard_layout(&self) -> KeyboardLayout {
@@ -57,10 +197,32 @@ impl Config {
             .unwrap_or_default()
     }
 
+    /// Starting values for effects and filters added to an instrument; unknown types
+    /// are skipped, and param names and ranges are checked when a slot is made
+    pub fn param_defaults(&self) -> ParamDefaults {
+        let mut defaults = ParamDefaults::default();
+        for (name, params) in &self.defaults.effects {
+            if let Some(effect_type) = parse_effect_type(name) {
+                defaults.effects.extend(params.iter().map(|(param, &value)| (effect_type, param.clone(), value)));
+            }
+        }
+        for (name, params) in &self.defaults.filters {
+            if let Some(filter_type) = parse_filter_type(name) {
+                defaults.filters.extend(params.iter().map(|(param, &value)| (filter_type, param.clone(), value)));
+            }
+        }
+        defaults
+    }
+
+    /// Musical settings for a new project; out-of-range values fall back to the built-in defaults
     pub fn defaults(&self) -> MusicalSettings {
         let fallback = MusicalSettings::default();
         MusicalSettings {
-            bpm: self.defaults.bpm.unwrap_or(fallback.bpm),
+            bpm: self
+                .defaults
+                .bpm
+                .filter(|bpm| (MIN_BPM..=MAX_BPM).contains(bpm))
+                .unwrap_or(fallback.bpm),
             key: self
                 .defaults
                 .key
@@ -73,13 +235,24 @@ impl Config {
                 .as_deref()
                 .and_then(parse_scale)
                 .unwrap_or(fallback.scale),
-            tuning_a4: self.defaults.tuning_a4.unwrap_or(fallback.tuning_a4),
+            tuning_a4: self
+                .defaults
+                .tuning_a4
+                .filter(|hz| (MIN_TUNING_A4..=MAX_TUNING_A4).contains(hz))
+                .unwrap_or(fallback.tuning_a4),
             time_signature: self
                 .defaults
                 .time_signature
+                .filter(|&[beats, unit]| (1..=16).contains(&beats) && [1, 2, 4, 8, 16].contains(&unit))
                 .map(|ts| (ts[0], ts[1]))
                 .unwrap_or(fallback.time_signature),
             snap: self.defaults.snap.unwrap_or(fallback.snap),
+            transpose: fallback.transpose,
+            ticks_per_beat: self
+                .defaults
+                .ticks_per_beat
+                .filter(|tpb| (MIN_TICKS_PER_BEAT..=MAX_TICKS_PER_BEAT).contains(tpb))
+                .unwrap_or(fallback.ticks_per_beat),
         }
     }
 }
@@ -88,6 +261,66 @@ fn user_config_path() -> Option<PathBuf> {
     dirs::config_dir().map(|d| d.join("ilex").join("config.toml"))
 }
 
+/// A path from an environment variable, falling back to a config value; empty means unset
+fn path_setting(var: &str, configured: &Option<String>) -> Option<PathBuf> {
+    std::env::var_os(var)
+        .filter(|v| !v.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| configured.as_deref().filter(|s| !s.is_empty()).map(PathBuf::from))
+}
+
+fn merge_audio(base: &mut AudioConfig, user: AudioConfig) {
+    if user.resample_samples.is_some() {
+        base.resample_samples = user.resample_samples;
+    }
+    if user.param_smoothing.is_some() {
+        base.param_smoothing = user.param_smoothing;
+    }
+    if user.live_note_length.is_some() {
+        base.live_note_length = user.live_note_length;
+    }
+    if user.live_note_hold.is_some() {
+        base.live_note_hold = user.live_note_hold;
+    }
+    if user.record_offset_ms.is_some() {
+        base.record_offset_ms = user.record_offset_ms;
+    }
+    if user.stop_tails.is_some() {
+        base.stop_tails = user.stop_tails;
+    }
+    if user.scsynth_path.is_some() {
+        base.scsynth_path = user.scsynth_path;
+    }
+    if user.sclang_path.is_some() {
+        base.sclang_path = user.sclang_path;
+    }
+}
+
+fn merge_ui(base: &mut UiConfig, user: UiConfig) {
+    if user.mouse.is_some() {
+        base.mouse = user.mouse;
+    }
+    if user.theme.is_some() {
+        base.theme = user.theme;
+    }
+    if user.color_depth.is_some() {
+        base.color_depth = user.color_depth;
+    }
+    let (base_colors, user_colors) = (&mut base.colors, user.colors);
+    for (base_color, user_color) in [
+        (&mut base_colors.background, user_colors.background),
+        (&mut base_colors.foreground, user_colors.foreground),
+        (&mut base_colors.accent, user_colors.accent),
+        (&mut base_colors.selection, user_colors.selection),
+        (&mut base_colors.muted, user_colors.muted),
+        (&mut base_colors.border, user_colors.border),
+    ] {
+        if user_color.is_some() {
+            *base_color = user_color;
+        }
+    }
+}
+
 fn merge_defaults(base: &mut DefaultsConfig, user: DefaultsConfig) {
     if user.bpm.is_some() {
         base.bpm = user.bpm;
@@ -104,12 +337,21 @@ fn merge_defaults(base: &mut DefaultsConfig, user: DefaultsConfig) {
     if user.time_signature.is_some() {
         base.time_signature = user.time_signature;
     }
+    if user.ticks_per_beat.is_some() {
+        base.ticks_per_beat = user.ticks_per_beat;
+    }
     if user.snap.is_some() {
         base.snap = user.snap;
     }
     if user.keyboard_layout.is_some() {
         base.keyboard_layout = user.keyboard_layout;
     }
+    for (name, params) in user.effects {
+        base.effects.entry(name).or_default().extend(params);
+    }
+    for (name, params) in user.filters {
+        base.filters.entry(name).or_default().extend(params);
+    }
 }
 
 fn parse_key(s: &str) -> Option<Key> {
@@ -138,6 +380,26 @@ fn parse_keyboard_layout(s: &str) -> Option<KeyboardLayout> {
     }
 }
 
+fn parse_effect_type(s: &str) -> Option<EffectType> {
+    match s.to_lowercase().as_str() {
+        "delay" => Some(EffectType::Delay),
+        "reverb" => Some(EffectType::Reverb),
+        "gate" => Some(EffectType::Gate),
+        "tape_comp" => Some(EffectType::TapeComp),
+        "sc_comp" | "sidechain_comp" => Some(EffectType::SidechainComp),
+        _ => None,
+    }
+}
+
+fn parse_filter_type(s: &str) -> Option<FilterType> {
+    match s.to_lowercase().as_str() {
+        "lpf" => Some(FilterType::Lpf),
+        "hpf" => Some(FilterType::Hpf),
+        "bpf" => Some(FilterType::Bpf),
+        _ => None,
+    }
+}
+
 fn parse_scale(s: &str) -> Option<Scale> {
     match s {
         "Major" => Some(Scale::Major),
@@ -168,8 +430,17 @@ mod tests {
         assert_eq!(defaults.scale, Scale::Major);
         assert!((defaults.tuning_a4 - 440.0).abs() < f32::EPSILON);
         assert_eq!(defaults.time_signature, (4, 4));
+        assert_eq!(defaults.ticks_per_beat, 480);
         assert!(!defaults.snap);
         assert_eq!(config.keyboard_layout(), KeyboardLayout::Colemak);
+        assert!(config.resample_samples());
+        assert!((config.param_smoothing() - 0.02).abs() < f32::EPSILON);
+        assert!((config.live_note_length() - 0.5).abs() < f32::EPSILON);
+        assert!(!config.live_note_hold());
+        assert_eq!(config.record_offset_ms(), 0.0);
+        assert!(!config.stop_tails());
+        assert!(config.mouse_enabled());
+        assert_eq!(config.theme(), Theme::DARK);
     }
 
     #[test]
@@ -188,4 +459,81 @@ mod tests {
         assert_eq!(parse_scale("Blues"), Some(Scale::Blues));
         assert_eq!(parse_scale("Nope"), None);
     }
+
+    #[test]
+    fn test_theme_overrides() {
+        let file: ConfigFile = toml::from_str(
+            "[ui]\ntheme = \"light\"\n[ui.colors]\naccent = \"#ff0000\"\nmuted = \"bogus\"\n",
+        )
+        .unwrap();
+        let config = Config { defaults: file.defaults, audio: file.audio, ui: file.ui };
+        let theme = config.theme();
+        assert_eq!(theme.name, "light");
+        assert_eq!(theme.accent, Color::new(255, 0, 0));
+        assert_eq!(theme.muted, Theme::LIGHT.muted);
+    }
+
+    #[test]
+    fn test_defaults_override_and_fallback() {
+        let mut base: ConfigFile = toml::from_str(DEFAULT_CONFIG).unwrap();
+        let user: ConfigFile = toml::from_str(
+            "[defaults]\nbpm = 90\nkey = \"F#\"\nscale = \"Dorian\"\ntime_signature = [6, 8]\nticks_per_beat = 960\n",
+        )
+        .unwrap();
+        merge_defaults(&mut base.defaults, user.defaults);
+        let config = Config { defaults: base.defaults, audio: base.audio, ui: base.ui };
+        let defaults = config.defaults();
+        assert_eq!(defaults.bpm, 90);
+        assert_eq!(defaults.key, Key::Fs);
+        assert_eq!(defaults.scale, Scale::Dorian);
+        assert_eq!(defaults.time_signature, (6, 8));
+        assert_eq!(defaults.ticks_per_beat, 960);
+        assert!((defaults.tuning_a4 - 440.0).abs() < f32::EPSILON);
+
+        // Out-of-range or unknown values keep the built-in defaults
+        let file: ConfigFile = toml::from_str(
+            "[defaults]\nbpm = 0\nkey = \"H\"\ntuning_a4 = 10.0\ntime_signature = [4, 3]\nticks_per_beat = 1\n",
+        )
+        .unwrap();
+        let config = Config { defaults: file.defaults, audio: file.audio, ui: file.ui };
+        assert_eq!(config.defaults(), MusicalSettings::default());
+    }
+
+    #[test]
+    fn test_server_paths_merge() {
+        let mut base: ConfigFile = toml::from_str(DEFAULT_CONFIG).unwrap();
+        assert_eq!(base.audio.scsynth_path, None);
+        let user: ConfigFile = toml::from_str(
+            "[audio]\nscsynth_path = \"/opt/sc/bin/scsynth\"\nsclang_path = \"\"\n",
+        )
+        .unwrap();
+        merge_audio(&mut base.audio, user.audio);
+        assert_eq!(base.audio.resample_samples, Some(true));
+        assert_eq!(
+            path_setting("ILEX_TEST_UNSET_SCSYNTH", &base.audio.scsynth_path),
+            Some(PathBuf::from("/opt/sc/bin/scsynth"))
+        );
+        // Empty means unset, leaving auto-discovery
+        assert_eq!(path_setting("ILEX_TEST_UNSET_SCLANG", &base.audio.sclang_path), None);
+    }
+
+    #[test]
+    fn test_effect_and_filter_defaults() {
+        // The shipped config sets none, leaving the built-in values
+        let base: ConfigFile = toml::from_str(DEFAULT_CONFIG).unwrap();
+        let config = Config { defaults: base.defaults, audio: base.audio, ui: base.ui };
+        assert_eq!(config.param_defaults(), ParamDefaults::default());
+
+        let mut base: ConfigFile = toml::from_str(DEFAULT_CONFIG).unwrap();
+        let user: ConfigFile = toml::from_str(
+            "[defaults.effects.delay]\nfeedback = 0.2\n[defaults.effects.flanger]\nrate = 1\n\
+             [defaults.filters.lpf]\ncutoff = 2000\n",
+        )
+        .unwrap();
+        merge_defaults(&mut base.defaults, user.defaults);
+        let config = Config { defaults: base.defaults, audio: base.audio, ui: base.ui };
+        let defaults = config.param_defaults();
+        assert_eq!(defaults.effects, [(EffectType::Delay, "feedback".to_string(), 0.2)]);
+        assert_eq!(defaults.filters, [(FilterType::Lpf, "cutoff".to_string(), 2000.0)]);
+    }
 }
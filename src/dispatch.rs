@@ -1,13 +1,31 @@
-use std::path::PathBuf;
-use std::time::{SystemTime, UNIX_EPOCH};
-
-use crate::audio::{self, AudioEngine};
-use crate::panes::{FileBrowserPane, InstrumentEditPane, PianoRollPane, ServerPane};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+use crate::audio::{self, click, sample_decode, AudioEngine};
+use crate::midi::{MidiEvent, MidiInputManager, MidiOutputManager};
+use crate::panes::{FileBrowserPane, InstrumentEditPane, MidiPane, PianoRollPane, ServerPane, SERVER_LOG_TAIL};
+use crate::playback;
 use crate::scd_parser;
-use crate::state::drum_sequencer::{ChopperState, DrumPattern};
-use crate::state::sampler::Slice;
-use crate::state::{AppState, CustomSynthDef, MixerSelection, ParamSpec};
-use crate::ui::{Action, ChopperAction, Frame, InstrumentAction, MixerAction, PaneManager, PianoRollAction, SequencerAction, ServerAction, SessionAction};
+use crate::state::automation::{AutomationTarget, RECORD_THIN_TOLERANCE};
+use crate::state::drum_sequencer::{ChopperState, DrumPattern, PadHit, RoundRobinSample, MAX_FOLLOW_REPEATS};
+use crate::state::fader;
+use crate::state::groove::{GrooveTemplate, DEFAULT_GROOVE_STEPS};
+use crate::state::midi_file::{import_midi_file, parse_midi_file};
+use crate::state::midi_recording::cc;
+use crate::state::note_repeat::RepeatTarget;
+use crate::state::phrase::Phrase;
+use crate::state::piano_roll::{snap_to_grid, MAX_CLIP_LOOPS};
+use crate::state::preset::factory_presets;
+use crate::state::project_io::{ProjectIoResult, ProjectJob};
+use crate::state::rng::format_seed;
+use crate::state::sampler::{spread_layer_ranges, Slice, VelocityLayer};
+use crate::state::session::{cycle_output_channel, validate_output_channel, SessionState};
+use crate::state::{AppState, BufferId, CustomSynthDef, EffectSlot, EffectType, InstrumentId, MixerSelection, ParamSpec, SampleLoadStatus, SynthDefImportReport};
+use crate::ui::{Action, ChopperAction, Frame, InstrumentAction, MidiAction, MixerAction, PaneManager, PianoRollAction, SequencerAction, ServerAction, SessionAction};
+
+/// How far "mutate" moves each param, as a fraction of its range
+const MUTATE_AMOUNT: f32 = 0.1;
 
 /// Default path for save file
 pub fn default_rack_path() -> PathBuf {
@@ -31,25 +49,53 @@ fn recording_path(prefix: &str) -> PathBuf {
     dir.join(format!("{}_{}.wav", prefix, secs))
 }
 
+/// Shown when an action that only makes sound is used with no server connected
+const NOT_CONNECTED_TOAST: &str = "Audio not connected (F5: start the server); editing still works";
+
+/// Actions whose purpose is to be heard. They still run offline (notes are still
+/// recorded, the playhead still moves) but the user is told why it's silent.
+fn needs_live_audio(action: &Action) -> bool {
+    matches!(
+        action,
+        Action::Instrument(
+            InstrumentAction::PlayNote(..)
+                | InstrumentAction::PlayNotes(..)
+                | InstrumentAction::PlayDrumPad(_)
+                | InstrumentAction::Audition(_)
+        ) | Action::PianoRoll(
+            PianoRollAction::PlayNote(..) | PianoRollAction::PlayNotes(..) | PianoRollAction::PlayStop
+        ) | Action::Sequencer(SequencerAction::PlayStop)
+            | Action::Chopper(ChopperAction::PreviewSlice)
+            | Action::Server(ServerAction::RecordMaster | ServerAction::RecordInput | ServerAction::LoadSynthDefs)
+    )
+}
+
 /// Dispatch an action. Returns true if the app should quit.
+#[allow(clippy::too_many_arguments)]
 pub fn dispatch_action(
     action: &Action,
     state: &mut AppState,
     panes: &mut PaneManager,
     audio_engine: &mut AudioEngine,
+    midi_input: &mut MidiInputManager,
+    midi_output: &mut MidiOutputManager,
     app_frame: &mut Frame,
     active_notes: &mut Vec<(u32, u8, u32)>,
 ) -> bool {
+    if !audio_engine.is_running() && needs_live_audio(action) {
+        app_frame.show_toast(NOT_CONNECTED_TOAST);
+    }
     match action {
         Action::Quit => return true,
         Action::Nav(_) => {} // Handled by PaneManager
-        Action::Instrument(a) => dispatch_instrument(a, state, panes, audio_engine, active_notes),
-        Action::Mixer(a) => dispatch_mixer(a, state, audio_engine),
-        Action::PianoRoll(a) => dispatch_piano_roll(a, state, panes, audio_engine, active_notes),
+        Action::Instrument(a) => dispatch_instrument(a, state, panes, audio_engine, app_frame, active_notes),
+        Action::Mixer(a) => dispatch_mixer(a, state, audio_engine, app_frame),
+        Action::PianoRoll(a) => dispatch_piano_roll(a, state, panes, audio_engine, app_frame, active_notes),
         Action::Server(a) => dispatch_server(a, state, panes, audio_engine),
         Action::Session(a) => dispatch_session(a, state, panes, audio_engine, app_frame),
         Action::Sequencer(a) => dispatch_sequencer(a, state, panes, audio_engine),
         Action::Chopper(a) => dispatch_chopper(a, state, panes, audio_engine),
+        Action::Midi(a) => dispatch_midi(a, state, panes, midi_input, midi_output),
         Action::None => {}
         // Layer management actions — handled in main.rs before dispatch
         Action::ExitPerformanceMode | Action::PushLayer(_) | Action::PopLayer(_) => {}
@@ -62,6 +108,7 @@ fn dispatch_instrument(
     state: &mut AppState,
     panes: &mut PaneManager,
     audio_engine: &mut AudioEngine,
+    app_frame: &mut Frame,
     active_notes: &mut Vec<(u32, u8, u32)>,
 ) {
     match action {
@@ -72,6 +119,16 @@ fn dispatch_instrument(
             }
             panes.switch_to("instrument", &*state);
         }
+        InstrumentAction::AddFactoryPreset(index) => {
+            let Some(preset) = factory_presets().get(*index) else {
+                return;
+            };
+            state.add_instrument_from_preset(preset);
+            if audio_engine.is_running() {
+                let _ = audio_engine.rebuild_instrument_routing(&state.instruments, &state.session);
+            }
+            panes.switch_to("instrument", &*state);
+        }
         InstrumentAction::Delete(inst_id) => {
             let inst_id = *inst_id;
             state.remove_instrument(inst_id);
@@ -103,16 +160,100 @@ fn dispatch_instrument(
                     instrument.source_params = edited.source_params;
                     instrument.filter = edited.filter;
                     instrument.effects = edited.effects;
+                    instrument.lfos = edited.lfos;
                     instrument.amp_envelope = edited.amp_envelope;
+                    instrument.mod_envelopes = edited.mod_envelopes;
+                    instrument.mod_routings = edited.mod_routings;
                     instrument.polyphonic = edited.polyphonic;
                     instrument.active = edited.active;
-                }
+                    instrument.trim = edited.trim;
+                    instrument.glide = edited.glide;
+                    instrument.unison_voices = edited.unison_voices;
+                    instrument.detune = edited.detune;
+                    instrument.voice_spread = edited.voice_spread;
+                    instrument.voice_spread_mode = edited.voice_spread_mode;
+                    instrument.key_low = edited.key_low;
+                    instrument.key_high = edited.key_high;
+                }
+            }
+            // Zones are edited on a copy of the sampler config; the rest of it (buffer,
+            // reverse) is owned by other actions, so only the zone layout is copied back
+            let zones = panes.get_pane_mut::<InstrumentEditPane>("instrument_edit")
+                .and_then(|edit| edit.sampler_zones().cloned());
+            if let (Some(zones), Some(config)) = (
+                zones,
+                state.instruments.instrument_mut(id).and_then(|i| i.sampler_config.as_mut()),
+            ) {
+                // A pre-zone project plays its old slice mapping until the zones change
+                if zones.slices != config.slices || zones.layer_zones != config.layer_zones {
+                    config.legacy_mapping = false;
+                }
+                config.set_next_slice_id(zones.next_slice_id());
+                config.layer_zones = zones.layer_zones;
+                config.crossfade_layers = zones.crossfade_layers;
+                config.slices = zones.slices;
+                config.selected_slice = config.selected_slice.min(config.slices.len().saturating_sub(1));
             }
             if audio_engine.is_running() {
                 let _ = audio_engine.rebuild_instrument_routing(&state.instruments, &state.session);
             }
             // Don't switch pane - stay in edit
         }
+        InstrumentAction::Rename(id, ref name) => {
+            // Names are display-only, so duplicates are fine; blank input keeps the old name
+            let name = name.trim();
+            if !name.is_empty() {
+                if let Some(instrument) = state.instruments.instrument_mut(*id) {
+                    instrument.name = name.to_string();
+                }
+            }
+        }
+        InstrumentAction::RandomizeParams(id) | InstrumentAction::MutateParams(id) => {
+            let id = *id;
+            let rng = &mut state.rng;
+            let Some(instrument) = state.instruments.instrument_mut(id) else {
+                return;
+            };
+            if matches!(action, InstrumentAction::RandomizeParams(_)) {
+                instrument.randomize_params(rng);
+            } else {
+                instrument.mutate_params(rng, MUTATE_AMOUNT);
+            }
+            if audio_engine.is_running() {
+                for param in &instrument.source_params {
+                    let _ = audio_engine.set_source_param(id, &param.name, param.value_f32());
+                }
+                // The amp envelope is read at voice spawn; the filter node is persistent
+                if let Some(ref filter) = instrument.filter {
+                    let _ = audio_engine.set_filter_param(id, "cutoff", filter.cutoff.value);
+                    let _ = audio_engine.set_filter_param(id, "resonance", filter.resonance.value);
+                }
+            }
+            // Keep an open editor in sync so its next edit doesn't overwrite the new patch
+            let instrument = instrument.clone();
+            if let Some(edit) = panes.get_pane_mut::<InstrumentEditPane>("instrument_edit") {
+                if edit.instrument_id() == Some(id) {
+                    edit.refresh_instrument(&instrument);
+                }
+            }
+        }
+        InstrumentAction::MoveUp(id) => {
+            state.move_instrument(*id, true);
+        }
+        InstrumentAction::MoveDown(id) => {
+            state.move_instrument(*id, false);
+        }
+        InstrumentAction::CycleColor(id) => {
+            if let Some(instrument) = state.instruments.instrument_mut(*id) {
+                instrument.color = crate::state::ColorTag::cycle(instrument.color);
+            }
+        }
+        InstrumentAction::ToggleSampleReverse(id) => {
+            // Sent with each new voice, so no routing rebuild is needed
+            if let Some(config) = state.instruments.instrument_mut(*id).and_then(|i| i.sampler_config.as_mut()) {
+                config.reverse = !config.reverse;
+            }
+        }
         InstrumentAction::SetParam(instrument_id, ref param, value) => {
             // Update state
             if let Some(instrument) = state.instruments.instrument_mut(*instrument_id) {
@@ -131,11 +272,19 @@ fn dispatch_instrument(
             let instrument_info: Option<u32> = state.instruments.selected_instrument().map(|s| s.id);
 
             if let Some(instrument_id) = instrument_info {
+                if pitches_in_range(state, instrument_id, &[pitch]).is_empty() {
+                    return;
+                }
+                if !press_note_repeat(state, instrument_id, &[pitch], velocity) {
+                    return;
+                }
+                if hold_key_notes(state, active_notes, instrument_id, &[pitch]) {
+                    return;
+                }
                 if audio_engine.is_running() {
                     let vel_f = velocity as f32 / 127.0;
                     let _ = audio_engine.spawn_voice(instrument_id, pitch, vel_f, 0.0, &state.instruments, &state.session);
-                    let duration_ticks = 240;
-                    active_notes.push((instrument_id, pitch, duration_ticks));
+                    active_notes.push((instrument_id, pitch, state.key_note_ticks()));
                 }
             }
         }
@@ -144,11 +293,22 @@ fn dispatch_instrument(
             let instrument_info: Option<u32> = state.instruments.selected_instrument().map(|s| s.id);
 
             if let Some(instrument_id) = instrument_info {
+                let pitches = &pitches_in_range(state, instrument_id, pitches);
+                if pitches.is_empty() {
+                    return;
+                }
+                if !press_note_repeat(state, instrument_id, pitches, velocity) {
+                    return;
+                }
+                if hold_key_notes(state, active_notes, instrument_id, pitches) {
+                    return;
+                }
                 if audio_engine.is_running() {
                     let vel_f = velocity as f32 / 127.0;
+                    let duration_ticks = state.key_note_ticks();
                     for &pitch in pitches {
                         let _ = audio_engine.spawn_voice(instrument_id, pitch, vel_f, 0.0, &state.instruments, &state.session);
-                        active_notes.push((instrument_id, pitch, 240));
+                        active_notes.push((instrument_id, pitch, duration_ticks));
                     }
                 }
             }
@@ -175,21 +335,19 @@ fn dispatch_instrument(
             }
         }
         InstrumentAction::PlayDrumPad(pad_idx) => {
-            if let Some(instrument) = state.instruments.selected_instrument() {
-                if let Some(seq) = &instrument.drum_sequencer {
-                    if let Some(pad) = seq.pads.get(*pad_idx) {
-                        if let (Some(buffer_id), instrument_id) = (pad.buffer_id, instrument.id) {
-                            let amp = pad.level;
-                            if audio_engine.is_running() {
-                                let _ = audio_engine.play_drum_hit_to_instrument(
-                                    buffer_id, amp, instrument_id,
-                                    pad.slice_start, pad.slice_end,
-                                );
-                            }
-                        }
-                    }
+            let Some(instrument_id) = state.instruments.selected_instrument().map(|s| s.id) else {
+                return;
+            };
+            if state.note_repeat.enabled {
+                let target = RepeatTarget::Pad { instrument_id, pad: *pad_idx };
+                if !state.note_repeat.press(target, Instant::now(), state.session.piano_roll.bpm) {
+                    return;
                 }
             }
+            play_drum_pad(state, audio_engine, instrument_id, *pad_idx);
+        }
+        InstrumentAction::Audition(instrument_id) => {
+            audition_instrument(state, audio_engine, active_notes, *instrument_id);
         }
         InstrumentAction::LoadSampleResult(instrument_id, ref path) => {
             let instrument_id = *instrument_id;
@@ -199,12 +357,89 @@ fn dispatch_instrument(
             state.instruments.next_sampler_buffer_id += 1;
 
             if audio_engine.is_running() {
-                let _ = audio_engine.load_sample(buffer_id, &path_str);
+                start_sample_load(audio_engine, &mut state.sample_loads, buffer_id, &path_str);
             }
 
             if let Some(instrument) = state.instruments.instrument_mut(instrument_id) {
                 if let Some(ref mut config) = instrument.sampler_config {
                     config.buffer_id = Some(buffer_id);
+                    config.path = Some(path_str);
+                    config.original_path = None;
+                }
+            }
+
+            panes.pop(&*state);
+        }
+        InstrumentAction::TrimSample(id) | InstrumentAction::NormalizeSample(id) => {
+            let id = *id;
+            let Some(path) = state.instruments.instrument(id)
+                .and_then(|i| i.sampler_config.as_ref())
+                .and_then(|c| c.path.clone())
+            else {
+                app_frame.show_toast("No sample loaded");
+                return;
+            };
+            let trim = matches!(action, InstrumentAction::TrimSample(_));
+            match edit_sample(&path, trim) {
+                Ok(Some(edit)) => {
+                    if let Some(config) = state.instruments.instrument_mut(id).and_then(|i| i.sampler_config.as_mut()) {
+                        config.slices.iter_mut().for_each(|s| s.rescale_to(edit.kept));
+                        config.original_path.get_or_insert(path);
+                    }
+                    replace_sampler_sample(state, panes, audio_engine, id, edit.path);
+                    app_frame.show_toast(if trim { "Trimmed silence" } else { "Normalized" });
+                }
+                Ok(None) => app_frame.show_toast("Sample is silent; nothing to normalize"),
+                Err(e) => app_frame.show_toast(e),
+            }
+        }
+        InstrumentAction::RevertSample(id) => {
+            let id = *id;
+            let Some(config) = state.instruments.instrument(id).and_then(|i| i.sampler_config.as_ref()) else {
+                return;
+            };
+            let (Some(original), Some(path)) = (config.original_path.clone(), config.path.clone()) else {
+                app_frame.show_toast("No edits to revert");
+                return;
+            };
+            // Zone bounds set on a trimmed copy don't line up with the original
+            let trimmed = (state.peak_cache.peaks(Path::new(&original)).1
+                - state.peak_cache.peaks(Path::new(&path)).1).abs() > 1e-3;
+            if let Some(config) = state.instruments.instrument_mut(id).and_then(|i| i.sampler_config.as_mut()) {
+                config.original_path = None;
+                if trimmed {
+                    config.slices.iter_mut().for_each(|s| (s.start, s.end) = (0.0, 1.0));
+                }
+            }
+            replace_sampler_sample(state, panes, audio_engine, id, original);
+            app_frame.show_toast(if trimmed { "Reverted to original; zone bounds reset" } else { "Reverted to original" });
+        }
+        InstrumentAction::LoadZoneLayerResult(instrument_id, slice_id, ref path) => {
+            let path_str = path.to_string_lossy().to_string();
+
+            let buffer_id = state.instruments.next_sampler_buffer_id;
+            state.instruments.next_sampler_buffer_id += 1;
+
+            if audio_engine.is_running() {
+                start_sample_load(audio_engine, &mut state.sample_loads, buffer_id, &path_str);
+            }
+
+            if let Some(instrument) = state.instruments.instrument_mut(*instrument_id) {
+                let slice = instrument.sampler_config.as_mut()
+                    .and_then(|c| c.slices.iter_mut().find(|s| s.id == *slice_id));
+                if let Some(slice) = slice {
+                    slice.layers.push(VelocityLayer {
+                        buffer_id,
+                        path: path_str,
+                        vel_range: (0, 127),
+                    });
+                    spread_layer_ranges(&mut slice.layers);
+                }
+                // The editor works on a copy of the zones; refresh it so its next update keeps the layer
+                if let Some(edit) = panes.get_pane_mut::<InstrumentEditPane>("instrument_edit") {
+                    if edit.instrument_id() == Some(*instrument_id) {
+                        edit.refresh_instrument(instrument);
+                    }
                 }
             }
 
@@ -216,6 +451,20 @@ fn dispatch_instrument(
         | InstrumentAction::SetFilter(_, _) => {
             // Reserved for future direct dispatch (currently handled inside InstrumentEditPane)
         }
+        InstrumentAction::SetEffectBypass(id, effect_idx, bypassed) => {
+            // Sent straight to the running node: a routing rebuild would cut the sound
+            if let Some(instrument) = state.instruments.instrument_mut(*id) {
+                if let Some(effect) = instrument.effects.get_mut(*effect_idx) {
+                    effect.bypassed = *bypassed;
+                }
+            }
+            // An instrument heard AFL stays dry until its solo ends
+            if let Some(instrument) = state.instruments.instrument(*id) {
+                let bypassed = instrument.effects.get(*effect_idx)
+                    .is_some_and(|e| state.session.effective_effect_bypass(&state.instruments, instrument, e));
+                let _ = audio_engine.set_effect_bypass(instrument, *effect_idx, bypassed);
+            }
+        }
     }
 }
 
@@ -223,6 +472,7 @@ fn dispatch_mixer(
     action: &MixerAction,
     state: &mut AppState,
     audio_engine: &mut AudioEngine,
+    app_frame: &mut Frame,
 ) {
     match action {
         MixerAction::Move(delta) => {
@@ -235,35 +485,20 @@ fn dispatch_mixer(
             state.session.mixer_selection = *selection;
         }
         MixerAction::AdjustLevel(delta) => {
-            let mut bus_update: Option<(u8, f32, bool, f32)> = None;
-            match state.session.mixer_selection {
-                MixerSelection::Instrument(idx) => {
-                    if let Some(instrument) = state.instruments.instruments.get_mut(idx) {
-                        instrument.level = (instrument.level + delta).clamp(0.0, 1.0);
-                    }
-                }
-                MixerSelection::Bus(id) => {
-                    if let Some(bus) = state.session.bus_mut(id) {
-                        bus.level = (bus.level + delta).clamp(0.0, 1.0);
-                    }
-                    if let Some(bus) = state.session.bus(id) {
-                        let mute = state.session.effective_bus_mute(bus);
-                        bus_update = Some((id, bus.level, mute, bus.pan));
-                    }
-                }
-                MixerSelection::Master => {
-                    state.session.master_level = (state.session.master_level + delta).clamp(0.0, 1.0);
-                }
-            }
-            if audio_engine.is_running() {
-                if let Some((bus_id, level, mute, pan)) = bus_update {
-                    let _ = audio_engine.set_bus_mixer_params(bus_id, level, mute, pan);
-                }
-                let _ = audio_engine.update_all_instrument_mixer_params(&state.instruments, &state.session);
-            }
+            set_selected_mixer_level(state, audio_engine, |level| fader::step_level(level, *delta));
+        }
+        MixerAction::SetLevelAt(selection, level) => {
+            state.session.mixer_selection = *selection;
+            set_selected_mixer_level(state, audio_engine, |_| *level);
+        }
+        MixerAction::AdjustPan(delta) => {
+            set_selected_mixer_pan(state, audio_engine, |pan| fader::step_pan(pan, *delta));
+        }
+        MixerAction::SetPanAt(selection, pan) => {
+            state.session.mixer_selection = *selection;
+            set_selected_mixer_pan(state, audio_engine, |_| *pan);
         }
         MixerAction::ToggleMute => {
-            let mut bus_update: Option<(u8, f32, bool, f32)> = None;
             match state.session.mixer_selection {
                 MixerSelection::Instrument(idx) => {
                     if let Some(instrument) = state.instruments.instruments.get_mut(idx) {
@@ -274,24 +509,20 @@ fn dispatch_mixer(
                     if let Some(bus) = state.session.bus_mut(id) {
                         bus.mute = !bus.mute;
                     }
-                    if let Some(bus) = state.session.bus(id) {
-                        let mute = state.session.effective_bus_mute(bus);
-                        bus_update = Some((id, bus.level, mute, bus.pan));
-                    }
                 }
                 MixerSelection::Master => {
                     state.session.master_mute = !state.session.master_mute;
                 }
             }
             if audio_engine.is_running() {
-                if let Some((bus_id, level, mute, pan)) = bus_update {
-                    let _ = audio_engine.set_bus_mixer_params(bus_id, level, mute, pan);
-                }
                 let _ = audio_engine.update_all_instrument_mixer_params(&state.instruments, &state.session);
             }
         }
+        MixerAction::ToggleMonoCheck => {
+            state.mono_check = !state.mono_check;
+            audio_engine.set_mono_check(state.mono_check);
+        }
         MixerAction::ToggleSolo => {
-            let mut bus_updates: Vec<(u8, f32, bool, f32)> = Vec::new();
             match state.session.mixer_selection {
                 MixerSelection::Instrument(idx) => {
                     if let Some(instrument) = state.instruments.instruments.get_mut(idx) {
@@ -305,20 +536,40 @@ fn dispatch_mixer(
                 }
                 MixerSelection::Master => {}
             }
-            for bus in &state.session.buses {
-                let mute = state.session.effective_bus_mute(bus);
-                bus_updates.push((bus.id, bus.level, mute, bus.pan));
+            if audio_engine.is_running() {
+                let _ = audio_engine.update_all_instrument_mixer_params(&state.instruments, &state.session);
             }
+        }
+        MixerAction::CycleSoloMode => {
+            state.session.solo_mode = state.session.solo_mode.next();
+            app_frame.show_toast(format!("Solo mode: {}", state.session.solo_mode.name()));
             if audio_engine.is_running() {
-                for (bus_id, level, mute, pan) in bus_updates {
-                    let _ = audio_engine.set_bus_mixer_params(bus_id, level, mute, pan);
-                }
                 let _ = audio_engine.update_all_instrument_mixer_params(&state.instruments, &state.session);
             }
         }
         MixerAction::CycleSection => {
             state.session.mixer_cycle_section();
         }
+        MixerAction::CycleOutput | MixerAction::CycleOutputReverse
+            if !matches!(state.session.mixer_selection, MixerSelection::Instrument(_)) =>
+        {
+            // Buses and master go out to hardware: step through the server's stereo pairs
+            let selection = state.session.mixer_selection;
+            let Some(channel) = state.session.output_channel(selection) else { return };
+            let outputs = audio_engine.output_channel_count();
+            let forward = *action == MixerAction::CycleOutput;
+            let next = cycle_output_channel(channel, outputs, forward);
+            if next == channel {
+                if let Err(e) = validate_output_channel(next + 2, outputs) {
+                    app_frame.show_toast(e);
+                }
+                return;
+            }
+            state.session.set_output_channel(selection, next);
+            if audio_engine.is_running() {
+                let _ = audio_engine.rebuild_instrument_routing(&state.instruments, &state.session);
+            }
+        }
         MixerAction::CycleOutput => {
             state.mixer_cycle_output();
         }
@@ -336,6 +587,15 @@ fn dispatch_mixer(
                 }
             }
         }
+        MixerAction::SetSend(bus_id, level) => {
+            if let MixerSelection::Instrument(idx) = state.session.mixer_selection {
+                if let Some(instrument) = state.instruments.instruments.get_mut(idx) {
+                    if let Some(send) = instrument.sends.iter_mut().find(|s| s.bus_id == *bus_id) {
+                        send.level = level.clamp(0.0, 1.0);
+                    }
+                }
+            }
+        }
         MixerAction::ToggleSend(bus_id) => {
             let bus_id = *bus_id;
             if let MixerSelection::Instrument(idx) = state.session.mixer_selection {
@@ -352,6 +612,82 @@ fn dispatch_mixer(
                 let _ = audio_engine.rebuild_instrument_routing(&state.instruments, &state.session);
             }
         }
+        MixerAction::AddBusEffect(bus_id) => {
+            let Some(bus) = state.session.bus_mut(*bus_id) else { return };
+            // An aux bus usually wants a reverb first
+            let effect_type = bus.effects.last().map_or(EffectType::Reverb, |e| e.effect_type.next());
+            bus.effects.push(EffectSlot::for_bus(effect_type, &state.param_defaults));
+            app_frame.show_toast(format!("{}: added {}", bus.name, effect_type.name()));
+            if audio_engine.is_running() {
+                let _ = audio_engine.rebuild_instrument_routing(&state.instruments, &state.session);
+            }
+        }
+        MixerAction::RemoveBusEffect(bus_id) => {
+            let Some(bus) = state.session.bus_mut(*bus_id) else { return };
+            let Some(effect) = bus.effects.pop() else { return };
+            app_frame.show_toast(format!("{}: removed {}", bus.name, effect.effect_type.name()));
+            if audio_engine.is_running() {
+                let _ = audio_engine.rebuild_instrument_routing(&state.instruments, &state.session);
+            }
+        }
+    }
+}
+
+/// Set the level of the selected mixer strip (clamped to 0..1) and push it to the engine
+fn set_selected_mixer_level(state: &mut AppState, audio_engine: &mut AudioEngine, level_fn: impl Fn(f32) -> f32) {
+    let mut bus_update: Option<(u8, f32, bool, f32)> = None;
+    match state.session.mixer_selection {
+        MixerSelection::Instrument(idx) => {
+            if let Some(instrument) = state.instruments.instruments.get_mut(idx) {
+                instrument.level = level_fn(instrument.level).clamp(0.0, 1.0);
+            }
+        }
+        MixerSelection::Bus(id) => {
+            if let Some(bus) = state.session.bus_mut(id) {
+                bus.level = level_fn(bus.level).clamp(0.0, 1.0);
+            }
+            if let Some(bus) = state.session.bus(id) {
+                let mute = state.session.effective_bus_mute(&state.instruments, bus);
+                bus_update = Some((id, bus.level, mute, state.session.effective_bus_pan(&state.instruments, bus)));
+            }
+        }
+        MixerSelection::Master => {
+            state.session.master_level = level_fn(state.session.master_level).clamp(0.0, 1.0);
+        }
+    }
+    if audio_engine.is_running() {
+        if let Some((bus_id, level, mute, pan)) = bus_update {
+            let _ = audio_engine.set_bus_mixer_params(bus_id, level, mute, pan);
+        }
+        let _ = audio_engine.update_all_instrument_mixer_params(&state.instruments, &state.session);
+    }
+}
+
+/// Set the selected strip's pan via `pan_fn`, clamped to -1.0..1.0. Master has no pan.
+fn set_selected_mixer_pan(state: &mut AppState, audio_engine: &mut AudioEngine, pan_fn: impl Fn(f32) -> f32) {
+    let mut bus_update: Option<(u8, f32, bool, f32)> = None;
+    match state.session.mixer_selection {
+        MixerSelection::Instrument(idx) => {
+            if let Some(instrument) = state.instruments.instruments.get_mut(idx) {
+                instrument.pan = pan_fn(instrument.pan).clamp(-1.0, 1.0);
+            }
+        }
+        MixerSelection::Bus(id) => {
+            if let Some(bus) = state.session.bus_mut(id) {
+                bus.pan = pan_fn(bus.pan).clamp(-1.0, 1.0);
+            }
+            if let Some(bus) = state.session.bus(id) {
+                let mute = state.session.effective_bus_mute(&state.instruments, bus);
+                bus_update = Some((id, bus.level, mute, state.session.effective_bus_pan(&state.instruments, bus)));
+            }
+        }
+        MixerSelection::Master => return,
+    }
+    if audio_engine.is_running() {
+        if let Some((bus_id, level, mute, pan)) = bus_update {
+            let _ = audio_engine.set_bus_mixer_params(bus_id, level, mute, pan);
+        }
+        let _ = audio_engine.update_all_instrument_mixer_params(&state.instruments, &state.session);
     }
 }
 
@@ -360,6 +696,7 @@ fn dispatch_piano_roll(
     state: &mut AppState,
     panes: &mut PaneManager,
     audio_engine: &mut AudioEngine,
+    app_frame: &mut Frame,
     active_notes: &mut Vec<(u32, u8, u32)>,
 ) {
     match action {
@@ -370,6 +707,11 @@ fn dispatch_piano_roll(
                 let dur = pr_pane.default_duration();
                 let vel = pr_pane.default_velocity();
                 let track = pr_pane.current_track();
+                // Toggling would stack a loose note on the clip's rather than remove it
+                if let Some(clip) = state.session.piano_roll.clip_note_at(track, pitch, tick) {
+                    app_frame.show_toast(format!("That note is in {}; unpack the clip to edit its notes", clip));
+                    return;
+                }
                 state.session.piano_roll.toggle_note(track, pitch, tick, dur, vel);
             }
         }
@@ -386,16 +728,19 @@ fn dispatch_piano_roll(
             }
         }
         PianoRollAction::PlayStop => {
+            // Clear recording if stopping via normal play/stop; held notes close at
+            // the playhead, so this comes before the reset below
+            finish_midi_recording(state);
             let pr = &mut state.session.piano_roll;
             pr.playing = !pr.playing;
             if !pr.playing {
                 pr.playhead = 0;
                 if audio_engine.is_running() {
-                    audio_engine.release_all_voices();
+                    audio_engine.stop_voices(&state.instruments, &state.session);
                 }
                 active_notes.clear();
+                reset_meter_if_idle(state, audio_engine);
             }
-            // Clear recording if stopping via normal play/stop
             if let Some(pr_pane) = panes.get_pane_mut::<PianoRollPane>("piano_roll") {
                 pr_pane.set_recording(false);
             }
@@ -406,18 +751,22 @@ fn dispatch_piano_roll(
             if !is_playing {
                 // Start playing + recording
                 state.session.piano_roll.playing = true;
+                state.session.midi_recording.arm();
+                state.session.midi_recording.start_recording();
                 if let Some(pr_pane) = panes.get_pane_mut::<PianoRollPane>("piano_roll") {
                     pr_pane.set_recording(true);
                 }
             } else {
                 // Stop playing + recording
+                finish_midi_recording(state);
                 let pr = &mut state.session.piano_roll;
                 pr.playing = false;
                 pr.playhead = 0;
                 if audio_engine.is_running() {
-                    audio_engine.release_all_voices();
+                    audio_engine.stop_voices(&state.instruments, &state.session);
                 }
                 active_notes.clear();
+                reset_meter_if_idle(state, audio_engine);
                 if let Some(pr_pane) = panes.get_pane_mut::<PianoRollPane>("piano_roll") {
                     pr_pane.set_recording(false);
                 }
@@ -426,16 +775,206 @@ fn dispatch_piano_roll(
         PianoRollAction::ToggleLoop => {
             state.session.piano_roll.looping = !state.session.piano_roll.looping;
         }
-        PianoRollAction::SetLoopStart => {
+        PianoRollAction::ApplyGroove | PianoRollAction::ExtractGroove => {
+            // The loop range doubles as the selection; grooves run on a 16th-note grid
+            let Some(pr_pane) = panes.get_pane_mut::<PianoRollPane>("piano_roll") else {
+                return;
+            };
+            let pr = &mut state.session.piano_roll;
+            let range = pr.loop_start..pr.loop_end;
+            let ticks_per_step = pr.ticks_per_beat / 4;
+            let Some(track) = pr.track_at_mut(pr_pane.current_track()) else {
+                return;
+            };
+            if *action == PianoRollAction::ApplyGroove {
+                if let Some(groove) = state.session.grooves.get(state.session.selected_groove) {
+                    track.edit_on_timeline(|notes| {
+                        groove.apply_to_notes(notes.iter_mut(), range.clone(), ticks_per_step, 1.0);
+                    });
+                }
+            } else {
+                let played = track.notes_between(range.start, range.end);
+                let groove = GrooveTemplate::extract_from_notes(
+                    "", played.iter(), range, ticks_per_step, DEFAULT_GROOVE_STEPS,
+                );
+                state.session.add_groove(groove);
+            }
+        }
+        PianoRollAction::TransposeNotes(indices, semitones) => {
             if let Some(pr_pane) = panes.get_pane_mut::<PianoRollPane>("piano_roll") {
-                let tick = pr_pane.cursor_tick();
-                state.session.piano_roll.loop_start = tick;
+                state.session.piano_roll.transpose_notes(pr_pane.current_track(), indices, *semitones);
+            }
+        }
+        PianoRollAction::QuantizeNotes(indices, grid) => {
+            if let Some(pr_pane) = panes.get_pane_mut::<PianoRollPane>("piano_roll") {
+                state.session.piano_roll.quantize_notes(pr_pane.current_track(), indices, *grid);
+            }
+        }
+        PianoRollAction::AdjustNoteVelocities(indices, delta) => {
+            if let Some(pr_pane) = panes.get_pane_mut::<PianoRollPane>("piano_roll") {
+                state.session.piano_roll.adjust_velocities(pr_pane.current_track(), indices, *delta);
+            }
+        }
+        PianoRollAction::PasteNotes(notes) => {
+            if let Some(pr_pane) = panes.get_pane_mut::<PianoRollPane>("piano_roll") {
+                let (track, tick) = (pr_pane.current_track(), pr_pane.cursor_tick());
+                state.session.piano_roll.paste_notes(track, tick, notes);
+            }
+        }
+        PianoRollAction::SavePhrase(ref name, notes) => {
+            let name = if name.trim().is_empty() { state.phrases.next_name() } else { name.trim().to_string() };
+            let tpb = state.session.piano_roll.ticks_per_beat;
+            state.phrases.add(Phrase::from_notes(name.as_str(), notes, tpb));
+            match state.phrases.save() {
+                Ok(()) => app_frame.show_toast(format!("Saved phrase \"{}\"", name)),
+                Err(e) => app_frame.show_toast(format!("Phrase library not saved: {}", e)),
+            }
+        }
+        PianoRollAction::InsertPhrase(idx, at_cursor_pitch) => {
+            let Some(phrase) = state.phrases.phrases.get(*idx) else {
+                return;
+            };
+            if let Some(pr_pane) = panes.get_pane_mut::<PianoRollPane>("piano_roll") {
+                let root = if *at_cursor_pitch { pr_pane.cursor_pitch() } else { phrase.root };
+                let notes = phrase.notes_at(root, state.session.piano_roll.ticks_per_beat);
+                let (track, tick) = (pr_pane.current_track(), pr_pane.cursor_tick());
+                pr_pane.clear_selection();
+                state.session.piano_roll.paste_notes(track, tick, &notes);
+            }
+            panes.pop(&*state);
+        }
+        PianoRollAction::DeletePhrase(idx) => {
+            if state.phrases.remove(*idx).is_some() {
+                if let Err(e) = state.phrases.save() {
+                    app_frame.show_toast(format!("Phrase library not saved: {}", e));
+                }
+            }
+        }
+        PianoRollAction::DeleteAtCursor => {
+            if let Some(pr_pane) = panes.get_pane_mut::<PianoRollPane>("piano_roll") {
+                let pr = &mut state.session.piano_roll;
+                let track = pr_pane.current_track();
+                if let Some(idx) = pr.note_under(track, pr_pane.cursor_pitch(), pr_pane.cursor_tick()) {
+                    pr.remove_notes(track, &[idx]);
+                }
+            }
+        }
+        PianoRollAction::DeleteRange(start, end) => {
+            if let Some(pr_pane) = panes.get_pane_mut::<PianoRollPane>("piano_roll") {
+                state.session.piano_roll.delete_range(pr_pane.current_track(), *start, *end);
+            }
+        }
+        PianoRollAction::DeleteNotes(indices) => {
+            if let Some(pr_pane) = panes.get_pane_mut::<PianoRollPane>("piano_roll") {
+                state.session.piano_roll.remove_notes(pr_pane.current_track(), indices);
             }
         }
-        PianoRollAction::SetLoopEnd => {
+        PianoRollAction::MakeClip => {
+            // The loop range doubles as the selection, as for grooves
+            if let Some(pr_pane) = panes.get_pane_mut::<PianoRollPane>("piano_roll") {
+                let pr = &mut state.session.piano_roll;
+                let (start, end) = (pr.loop_start, pr.loop_end);
+                pr.make_clip(pr_pane.current_track(), start, end);
+            }
+        }
+        PianoRollAction::DuplicateClip
+        | PianoRollAction::MoveClip(_)
+        | PianoRollAction::AdjustClipLoops(_)
+        | PianoRollAction::UnpackClip
+        | PianoRollAction::DeleteClip => {
+            let Some(pr_pane) = panes.get_pane_mut::<PianoRollPane>("piano_roll") else {
+                return;
+            };
+            let track_idx = pr_pane.current_track();
+            let pr = &mut state.session.piano_roll;
+            let Some(clip_idx) = pr.clip_at(track_idx, pr_pane.cursor_tick()) else {
+                return;
+            };
+            match *action {
+                PianoRollAction::DuplicateClip => {
+                    pr.duplicate_clip(track_idx, clip_idx);
+                }
+                PianoRollAction::MoveClip(bars) => {
+                    let delta = bars as i64 * pr.ticks_per_bar() as i64;
+                    if let Some(idx) = pr.move_clip(track_idx, clip_idx, delta) {
+                        // Keep the cursor on the clip so repeated moves keep working
+                        if let Some(clip) = pr.track_at(track_idx).and_then(|t| t.clips.get(idx)) {
+                            pr_pane.set_cursor_tick(clip.start);
+                        }
+                    }
+                }
+                PianoRollAction::AdjustClipLoops(delta) => {
+                    if let Some(clip) = pr.track_at_mut(track_idx).and_then(|t| t.clips.get_mut(clip_idx)) {
+                        clip.loops = (clip.loops as i32 + delta as i32).clamp(1, MAX_CLIP_LOOPS as i32) as u32;
+                    }
+                }
+                PianoRollAction::UnpackClip => pr.unpack_clip(track_idx, clip_idx),
+                _ => {
+                    if let Some(track) = pr.track_at_mut(track_idx) {
+                        track.clips.remove(clip_idx);
+                    }
+                }
+            }
+        }
+        PianoRollAction::ConsolidateClips => {
+            state.session.piano_roll.consolidate_into_clips();
+        }
+        PianoRollAction::AddMarker => {
             if let Some(pr_pane) = panes.get_pane_mut::<PianoRollPane>("piano_roll") {
                 let tick = pr_pane.cursor_tick();
-                state.session.piano_roll.loop_end = tick;
+                let pr = &mut state.session.piano_roll;
+                if pr.marker_at(tick).is_none() {
+                    let name = format!("M{}", pr.markers.len() + 1);
+                    pr.add_marker(tick, name);
+                }
+            }
+        }
+        PianoRollAction::RemoveMarker => {
+            if let Some(pr_pane) = panes.get_pane_mut::<PianoRollPane>("piano_roll") {
+                state.session.piano_roll.remove_marker(pr_pane.cursor_tick());
+            }
+        }
+        PianoRollAction::RenameMarker(tick, ref name) => {
+            let name = name.trim();
+            if !name.is_empty() && state.session.piano_roll.marker_at(*tick).is_some() {
+                state.session.piano_roll.add_marker(*tick, name.to_string());
+            }
+        }
+        PianoRollAction::JumpMarker(direction) => {
+            if let Some(pr_pane) = panes.get_pane_mut::<PianoRollPane>("piano_roll") {
+                let pr = &mut state.session.piano_roll;
+                let from = if pr.playing { pr.playhead } else { pr_pane.cursor_tick() };
+                let target = if *direction > 0 { pr.next_marker(from) } else { pr.prev_marker(from) };
+                if let Some(tick) = target.map(|m| m.tick) {
+                    if pr.playing {
+                        pr.playhead = tick;
+                    }
+                    pr_pane.set_cursor_tick(tick);
+                }
+            }
+        }
+        PianoRollAction::SetLoopStart | PianoRollAction::SetLoopEnd => {
+            if let Some(pr_pane) = panes.get_pane_mut::<PianoRollPane>("piano_roll") {
+                // With snap on the point lands on the nearest grid line and the loop is
+                // at least a grid step long; a point moved past the other swaps them
+                let grid = if state.session.snap { pr_pane.ticks_per_cell() } else { 1 };
+                let tick = snap_to_grid(pr_pane.cursor_tick(), grid);
+                let pr = &mut state.session.piano_roll;
+                if matches!(action, PianoRollAction::SetLoopStart) {
+                    pr.set_loop(tick, pr.loop_end, grid);
+                } else {
+                    pr.set_loop(pr.loop_start, tick, grid);
+                }
+            }
+        }
+        PianoRollAction::LoopToSelection(indices, grid) => {
+            if let Some(pr_pane) = panes.get_pane_mut::<PianoRollPane>("piano_roll") {
+                let pr = &mut state.session.piano_roll;
+                if let Some((start, end)) = pr.notes_span(pr_pane.current_track(), indices) {
+                    let grid = if state.session.snap { (*grid).max(1) } else { 1 };
+                    pr.set_loop(start / grid * grid, end.div_ceil(grid) * grid, grid);
+                    app_frame.show_toast("Loop set to selection");
+                }
             }
         }
         PianoRollAction::ChangeTrack(delta) => {
@@ -467,29 +1006,29 @@ fn dispatch_piano_roll(
         }
         PianoRollAction::Jump(_direction) => {
             if let Some(pr_pane) = panes.get_pane_mut::<PianoRollPane>("piano_roll") {
-                pr_pane.jump_to_end();
+                pr_pane.jump_to_end(&state.session.piano_roll);
             }
         }
         PianoRollAction::PlayNote(pitch, velocity) => {
             let pitch = *pitch;
             let velocity = *velocity;
             // Get the current track's instrument_id
-            let track_instrument_id: Option<u32> = {
-                let track_idx = panes
-                    .get_pane_mut::<PianoRollPane>("piano_roll")
-                    .map(|pr| pr.current_track());
-                if let Some(idx) = track_idx {
-                    state.session.piano_roll.track_at(idx).map(|t| t.module_id)
-                } else {
-                    None
-                }
-            };
+            let track_instrument_id = piano_roll_track_instrument(state, panes);
 
             if let Some(instrument_id) = track_instrument_id {
+                if pitches_in_range(state, instrument_id, &[pitch]).is_empty() {
+                    return;
+                }
+                if !press_note_repeat(state, instrument_id, &[pitch], velocity) {
+                    return;
+                }
+                if !piano_roll_recording(panes) && hold_key_notes(state, active_notes, instrument_id, &[pitch]) {
+                    return;
+                }
                 if audio_engine.is_running() {
                     let vel_f = velocity as f32 / 127.0;
                     let _ = audio_engine.spawn_voice(instrument_id, pitch, vel_f, 0.0, &state.instruments, &state.session);
-                    let duration_ticks = 240; // Half beat for staccato feel
+                    let duration_ticks = piano_roll_live_ticks(state, panes);
                     active_notes.push((instrument_id, pitch, duration_ticks));
                 }
 
@@ -497,32 +1036,40 @@ fn dispatch_piano_roll(
                 let recording_info = panes
                     .get_pane_mut::<PianoRollPane>("piano_roll")
                     .filter(|pr| pr.is_recording())
-                    .map(|pr| (pr.current_track(), pr.default_duration(), pr.default_velocity()));
-                if let Some((track_idx, duration, vel)) = recording_info {
-                    let playhead = state.session.piano_roll.playhead;
-                    state.session.piano_roll.toggle_note(track_idx, pitch, playhead, duration, vel);
+                    .map(|pr| (pr.current_track(), pr.default_duration(), pr.default_velocity(), pr.live_quantize_grid()));
+                if let Some((track_idx, duration, vel, grid)) = recording_info {
+                    let played = state.record_tick();
+                    let pr = &mut state.session.piano_roll;
+                    let tick = match grid {
+                        Some(grid) => pr.live_quantize_tick(track_idx, pitch, played, grid),
+                        None => played,
+                    };
+                    pr.toggle_note(track_idx, pitch, tick, duration, vel);
                 }
+                step_record_notes(&[pitch], state, panes);
             }
         }
         PianoRollAction::PlayNotes(ref pitches, velocity) => {
             let velocity = *velocity;
-            let track_instrument_id: Option<u32> = {
-                let track_idx = panes
-                    .get_pane_mut::<PianoRollPane>("piano_roll")
-                    .map(|pr| pr.current_track());
-                if let Some(idx) = track_idx {
-                    state.session.piano_roll.track_at(idx).map(|t| t.module_id)
-                } else {
-                    None
-                }
-            };
+            let track_instrument_id = piano_roll_track_instrument(state, panes);
 
             if let Some(instrument_id) = track_instrument_id {
+                let pitches = &pitches_in_range(state, instrument_id, pitches);
+                if pitches.is_empty() {
+                    return;
+                }
+                if !press_note_repeat(state, instrument_id, pitches, velocity) {
+                    return;
+                }
+                if !piano_roll_recording(panes) && hold_key_notes(state, active_notes, instrument_id, pitches) {
+                    return;
+                }
                 if audio_engine.is_running() {
                     let vel_f = velocity as f32 / 127.0;
+                    let duration_ticks = piano_roll_live_ticks(state, panes);
                     for &pitch in pitches {
                         let _ = audio_engine.spawn_voice(instrument_id, pitch, vel_f, 0.0, &state.instruments, &state.session);
-                        active_notes.push((instrument_id, pitch, 240));
+                        active_notes.push((instrument_id, pitch, duration_ticks));
                     }
                 }
 
@@ -530,11 +1077,30 @@ fn dispatch_piano_roll(
                 let recording_info = panes
                     .get_pane_mut::<PianoRollPane>("piano_roll")
                     .filter(|pr| pr.is_recording())
-                    .map(|pr| (pr.current_track(), pr.default_duration(), pr.default_velocity()));
-                if let Some((track_idx, duration, vel)) = recording_info {
-                    let playhead = state.session.piano_roll.playhead;
+                    .map(|pr| (pr.current_track(), pr.default_duration(), pr.default_velocity(), pr.live_quantize_grid()));
+                if let Some((track_idx, duration, vel, grid)) = recording_info {
+                    let played = state.record_tick();
+                    let pr = &mut state.session.piano_roll;
                     for &pitch in pitches {
-                        state.session.piano_roll.toggle_note(track_idx, pitch, playhead, duration, vel);
+                        let tick = match grid {
+                            Some(grid) => pr.live_quantize_tick(track_idx, pitch, played, grid),
+                            None => played,
+                        };
+                        pr.toggle_note(track_idx, pitch, tick, duration, vel);
+                    }
+                }
+                step_record_notes(pitches, state, panes);
+            }
+        }
+        PianoRollAction::StepBack => {
+            if let Some(pr_pane) = panes.get_pane_mut::<PianoRollPane>("piano_roll") {
+                if let Some(entry) = pr_pane.step_back() {
+                    let pr = &mut state.session.piano_roll;
+                    for &pitch in &entry.pitches {
+                        pr.remove_note(entry.track, pitch, entry.tick);
+                    }
+                    for note in entry.replaced {
+                        pr.add_note(entry.track, note.pitch, note.tick, note.duration, note.velocity);
                     }
                 }
             }
@@ -560,42 +1126,18 @@ fn dispatch_server(
             if let Some(server) = panes.get_pane_mut::<ServerPane>("server") {
                 match result {
                     Ok(()) => {
-                        // Load built-in synthdefs
-                        let synthdef_dir = std::path::Path::new("synthdefs");
-                        let builtin_result = audio_engine.load_synthdefs(synthdef_dir);
-
-                        // Also load custom synthdefs from config dir
-                        let config_dir = config_synthdefs_dir();
-                        let custom_result = if config_dir.exists() {
-                            audio_engine.load_synthdefs(&config_dir)
-                        } else {
-                            Ok(())
-                        };
+                        let (builtin_result, custom_result) = load_all_synthdefs(audio_engine, &state.session);
 
-                        // Load drum sequencer samples for all drum machine instruments
-                        for instrument in &state.instruments.instruments {
-                            if let Some(seq) = &instrument.drum_sequencer {
-                                for pad in &seq.pads {
-                                    if let Some(buffer_id) = pad.buffer_id {
-                                        if let Some(ref path) = pad.path {
-                                            let _ = audio_engine.load_sample(buffer_id, path);
-                                        }
-                                    }
-                                }
-                            }
-                        }
+                        load_instrument_samples(state, audio_engine);
 
-                        match (builtin_result, custom_result) {
-                            (Ok(()), Ok(())) => {
-                                server.set_status(audio::ServerStatus::Connected, "Connected");
-                            }
-                            (Err(e), _) | (_, Err(e)) => {
-                                server.set_status(
-                                    audio::ServerStatus::Connected,
-                                    &format!("Connected (synthdef warning: {})", e),
-                                );
-                            }
+                        let mut status = match (builtin_result, custom_result) {
+                            (Ok(()), Ok(())) => "Connected".to_string(),
+                            (Err(e), _) | (_, Err(e)) => format!("Connected (synthdef warning: {})", e),
+                        };
+                        if let Some(warning) = fit_output_channels(state, audio_engine) {
+                            status = format!("{}; {}", status, warning);
                         }
+                        server.set_status(audio::ServerStatus::Connected, &status);
                     }
                     Err(e) => {
                         server.set_status(audio::ServerStatus::Error, &e.to_string())
@@ -605,6 +1147,7 @@ fn dispatch_server(
         }
         ServerAction::Disconnect => {
             audio_engine.disconnect();
+            audio_engine.reset_meter();
             if let Some(server) = panes.get_pane_mut::<ServerPane>("server") {
                 server.set_status(audio_engine.status(), "Disconnected");
                 server.set_server_running(audio_engine.server_running());
@@ -621,11 +1164,17 @@ fn dispatch_server(
             if let Some(server) = panes.get_pane_mut::<ServerPane>("server") {
                 match result {
                     Ok(()) => {
-                        server.set_status(audio::ServerStatus::Running, "Server started");
+                        let outputs = audio_engine.output_channel_count();
+                        let mut status = format!("Server started ({} outputs)", outputs);
+                        if let Some(warning) = fit_output_channels(state, audio_engine) {
+                            status = format!("{}; {}", status, warning);
+                        }
+                        server.set_status(audio::ServerStatus::Running, &status);
                         server.set_server_running(true);
                     }
                     Err(e) => {
                         server.set_status(audio::ServerStatus::Error, &e);
+                        server.set_log_tail(audio_engine.server_log_tail(SERVER_LOG_TAIL));
                         server.set_server_running(false);
                     }
                 }
@@ -654,17 +1203,7 @@ fn dispatch_server(
             }
         }
         ServerAction::LoadSynthDefs => {
-            // Load built-in synthdefs
-            let synthdef_dir = std::path::Path::new("synthdefs");
-            let builtin_result = audio_engine.load_synthdefs(synthdef_dir);
-
-            // Also load custom synthdefs from config dir
-            let config_dir = config_synthdefs_dir();
-            let custom_result = if config_dir.exists() {
-                audio_engine.load_synthdefs(&config_dir)
-            } else {
-                Ok(())
-            };
+            let (builtin_result, custom_result) = load_all_synthdefs(audio_engine, &state.session);
 
             if let Some(server) = panes.get_pane_mut::<ServerPane>("server") {
                 match (builtin_result, custom_result) {
@@ -708,7 +1247,7 @@ fn dispatch_server(
                     }
                 }
                 let path = recording_path("master");
-                match audio_engine.start_recording(0, &path) {
+                match audio_engine.start_recording(audio_engine.master_output() as i32, &path) {
                     Ok(()) => {
                         if let Some(server) = panes.get_pane_mut::<ServerPane>("server") {
                             server.set_status(
@@ -758,7 +1297,7 @@ fn dispatch_server(
                     let path = recording_path(&format!("input_{}", inst_id));
                     // Bus 0 is hardware out; for instrument recording we use bus 0
                     // since instruments route through output to bus 0
-                    match audio_engine.start_recording(0, &path) {
+                    match audio_engine.start_recording(audio_engine.master_output() as i32, &path) {
                         Ok(()) => {
                             if let Some(server) = panes.get_pane_mut::<ServerPane>("server") {
                                 server.set_status(
@@ -806,45 +1345,22 @@ fn dispatch_server(
                     if let Some(server) = panes.get_pane_mut::<ServerPane>("server") {
                         match connect_result {
                             Ok(()) => {
-                                // Load built-in synthdefs
-                                let synthdef_dir = std::path::Path::new("synthdefs");
-                                let builtin_result = audio_engine.load_synthdefs(synthdef_dir);
-
-                                // Load custom synthdefs
-                                let config_dir = config_synthdefs_dir();
-                                let custom_result = if config_dir.exists() {
-                                    audio_engine.load_synthdefs(&config_dir)
-                                } else {
-                                    Ok(())
-                                };
+                                let (builtin_result, custom_result) = load_all_synthdefs(audio_engine, &state.session);
 
-                                // Load drum samples
-                                for instrument in &state.instruments.instruments {
-                                    if let Some(seq) = &instrument.drum_sequencer {
-                                        for pad in &seq.pads {
-                                            if let Some(buffer_id) = pad.buffer_id {
-                                                if let Some(ref path) = pad.path {
-                                                    let _ = audio_engine.load_sample(buffer_id, path);
-                                                }
-                                            }
-                                        }
-                                    }
-                                }
+                                load_instrument_samples(state, audio_engine);
+                                let warning = fit_output_channels(state, audio_engine);
 
                                 // Rebuild instrument routing
                                 let _ = audio_engine.rebuild_instrument_routing(&state.instruments, &state.session);
 
-                                match (builtin_result, custom_result) {
-                                    (Ok(()), Ok(())) => {
-                                        server.set_status(audio::ServerStatus::Connected, "Server restarted");
-                                    }
-                                    (Err(e), _) | (_, Err(e)) => {
-                                        server.set_status(
-                                            audio::ServerStatus::Connected,
-                                            &format!("Restarted (synthdef warning: {})", e),
-                                        );
-                                    }
+                                let mut status = match (builtin_result, custom_result) {
+                                    (Ok(()), Ok(())) => "Server restarted".to_string(),
+                                    (Err(e), _) | (_, Err(e)) => format!("Restarted (synthdef warning: {})", e),
+                                };
+                                if let Some(warning) = warning {
+                                    status = format!("{}; {}", status, warning);
                                 }
+                                server.set_status(audio::ServerStatus::Connected, &status);
                                 server.clear_device_config_dirty();
                             }
                             Err(e) => {
@@ -856,6 +1372,7 @@ fn dispatch_server(
                 Err(e) => {
                     if let Some(server) = panes.get_pane_mut::<ServerPane>("server") {
                         server.set_status(audio::ServerStatus::Error, &e);
+                        server.set_log_tail(audio_engine.server_log_tail(SERVER_LOG_TAIL));
                         server.set_server_running(false);
                     }
                 }
@@ -864,6 +1381,66 @@ fn dispatch_server(
     }
 }
 
+/// Store the output pairs the engine actually plays on the current server: a saved
+/// pair the device doesn't have falls back to out 1/2, and the mixer should say so.
+/// Returns a warning naming the strips moved.
+fn fit_output_channels(state: &mut AppState, audio_engine: &AudioEngine) -> Option<String> {
+    let outputs = audio_engine.output_channel_count();
+    let moved = state.session.fit_output_channels(outputs);
+    (!moved.is_empty()).then(|| {
+        format!("{} moved to out 1/2 (the server has {} outputs)", moved.join(", "), outputs)
+    })
+}
+
+/// Apply a finished background save or load
+pub fn finish_project_io(
+    result: ProjectIoResult,
+    state: &mut AppState,
+    panes: &mut PaneManager,
+    audio_engine: &AudioEngine,
+    app_frame: &mut Frame,
+) {
+    let path = match result {
+        ProjectIoResult::Saved(path) => path,
+        ProjectIoResult::Loaded(path, session, instruments, repairs) => {
+            state.session = *session;
+            state.instruments = *instruments;
+            // Randomize and mutate repeat themselves each time the project is opened
+            state.set_rng_seed(state.session.rng_seed);
+            let ui = state.session.ui.clone();
+            if let Some(pr_pane) = panes.get_pane_mut::<PianoRollPane>("piano_roll") {
+                pr_pane.restore_view(&ui.piano_roll, &state.session.piano_roll);
+            }
+            if let Some(pane) = ui.resumable_pane() {
+                panes.switch_to(pane, &*state);
+            }
+            load_project_synthdefs(audio_engine, &state.session, app_frame);
+            let mut repairs = repairs;
+            if audio_engine.is_running() || audio_engine.server_running() {
+                repairs.extend(fit_output_channels(state, audio_engine));
+            }
+            // Shown last so a change to the loaded data is never hidden by another toast
+            if !repairs.is_empty() {
+                app_frame.show_toast(format!("Repaired project: {}", repairs.join("; ")));
+            }
+            path
+        }
+        ProjectIoResult::Failed(ProjectJob::Saving, e) => {
+            app_frame.show_toast(format!("Save failed: {}", e));
+            return;
+        }
+        ProjectIoResult::Failed(ProjectJob::Loading, e) => {
+            app_frame.show_toast(format!("Load failed: {}", e));
+            return;
+        }
+    };
+    let name = path.file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or("default")
+        .to_string();
+    app_frame.set_project_name(name);
+}
+
 fn dispatch_session(
     action: &SessionAction,
     state: &mut AppState,
@@ -874,49 +1451,78 @@ fn dispatch_session(
     match action {
         SessionAction::Save => {
             let path = default_rack_path();
-            if let Some(parent) = path.parent() {
-                let _ = std::fs::create_dir_all(parent);
-            }
             // Sync piano roll time_signature from session
             state.session.piano_roll.time_signature = state.session.time_signature;
-            if let Err(e) = crate::state::persistence::save_project(&path, &state.session, &state.instruments) {
-                eprintln!("Failed to save: {}", e);
+            state.session.ui.active_pane = Some(panes.active().id().to_string());
+            if let Some(pr_pane) = panes.get_pane_mut::<PianoRollPane>("piano_roll") {
+                state.session.ui.piano_roll = pr_pane.view();
+            }
+            // The worker writes a snapshot, so editing can go on while it saves
+            if let Err(e) = state.project_io.start_save(path, state.session.clone(), state.instruments.clone()) {
+                app_frame.show_toast(e);
+                return;
+            }
+            let automation = &state.session.automation;
+            if let Some(resolution) = automation.freeze_resolution(state.session.piano_roll.ticks_per_beat) {
+                let coarsened = automation.coarsened_lanes(resolution).len();
+                if coarsened > 0 {
+                    app_frame.show_toast(format!(
+                        "Froze {} automation lane(s) at a coarser grid (max {} points per lane)",
+                        coarsened,
+                        crate::state::automation::MAX_RENDERED_POINTS,
+                    ));
+                }
             }
-            let name = path.file_stem()
-                .and_then(|s| s.to_str())
-                .unwrap_or("default")
-                .to_string();
-            app_frame.set_project_name(name);
         }
         SessionAction::Load => {
             let path = default_rack_path();
             if path.exists() {
-                match crate::state::persistence::load_project(&path) {
-                    Ok((loaded_session, loaded_instruments)) => {
-                        state.session = loaded_session;
-                        state.instruments = loaded_instruments;
-                        let name = path.file_stem()
-                            .and_then(|s| s.to_str())
-                            .unwrap_or("default")
-                            .to_string();
-                        app_frame.set_project_name(name);
-                    }
-                    Err(e) => {
-                        eprintln!("Failed to load: {}", e);
-                    }
+                if let Err(e) = state.project_io.start_load(path) {
+                    app_frame.show_toast(e);
                 }
             }
         }
+        SessionAction::CycleGroove(delta) => {
+            state.session.cycle_groove(*delta);
+        }
+        SessionAction::RenderClick => {
+            let piano_roll = &state.session.piano_roll;
+            let (start, end) = click::click_region(piano_roll);
+            let hits = click::click_hits(piano_roll, start, end);
+            let secs_per_tick = 60.0 / piano_roll.bpm as f64 / piano_roll.ticks_per_beat as f64;
+            let duration = (end - start) as f64 * secs_per_tick;
+            let sample_rate = audio_engine.server_sample_rate().unwrap_or(48000);
+            match click::render_click_track(&hits, duration, sample_rate, &recording_path("click")) {
+                Ok(path) => app_frame.show_toast(format!("Rendered click to {}", path.display())),
+                Err(e) => app_frame.show_toast(format!("Click render failed: {}", e)),
+            }
+        }
         SessionAction::UpdateSession(ref settings) => {
             state.session.apply_musical_settings(settings);
+            if settings.ticks_per_beat != state.session.piano_roll.ticks_per_beat {
+                state.session.set_ticks_per_beat(settings.ticks_per_beat);
+                if let Some(pr_pane) = panes.get_pane_mut::<PianoRollPane>("piano_roll") {
+                    pr_pane.set_ticks_per_beat(state.session.piano_roll.ticks_per_beat);
+                }
+            }
             state.session.piano_roll.time_signature = state.session.time_signature;
             state.session.piano_roll.bpm = state.session.bpm as f32;
+            let _ = audio_engine.update_tempo_sync(&state.instruments, &state.session);
             panes.switch_to("instrument", &*state);
         }
+        SessionAction::SetSynthDefDir(dir) => {
+            state.session.synthdef_dir = dir.clone();
+            load_project_synthdefs(audio_engine, &state.session, app_frame);
+        }
+        SessionAction::SetRngSeed(seed) => {
+            state.set_rng_seed(*seed);
+            app_frame.show_toast(format!("Random seed {}", format_seed(*seed)));
+        }
         SessionAction::UpdateSessionLive(ref settings) => {
             state.session.apply_musical_settings(settings);
             state.session.piano_roll.time_signature = state.session.time_signature;
             state.session.piano_roll.bpm = state.session.bpm as f32;
+            let _ = audio_engine.update_tempo_sync(&state.instruments, &state.session);
         }
         SessionAction::OpenFileBrowser(ref file_action) => {
             if let Some(fb) = panes.get_pane_mut::<FileBrowserPane>("file_browser") {
@@ -925,79 +1531,88 @@ fn dispatch_session(
             panes.push_to("file_browser", &*state);
         }
         SessionAction::ImportCustomSynthDef(ref path) => {
-            // Read and parse the .scd file
-            match std::fs::read_to_string(path) {
-                Ok(content) => {
-                    match scd_parser::parse_scd_file(&content) {
-                        Ok(parsed) => {
-                            // Create params with inferred ranges
-                            let params: Vec<ParamSpec> = parsed
-                                .params
-                                .iter()
-                                .map(|(name, default)| {
-                                    let (min, max) =
-                                        scd_parser::infer_param_range(name, *default);
-                                    ParamSpec {
-                                        name: name.clone(),
-                                        default: *default,
-                                        min,
-                                        max,
-                                    }
-                                })
-                                .collect();
-
-                            // Create the custom synthdef entry
-                            let synthdef_name = parsed.name.clone();
-                            let custom = CustomSynthDef {
-                                id: 0, // Will be set by registry.add()
-                                name: parsed.name.clone(),
-                                synthdef_name: synthdef_name.clone(),
-                                source_path: path.clone(),
-                                params,
-                            };
-
-                            // Register it
-                            let _id = state.session.custom_synthdefs.add(custom);
-
-                            // Copy the .scd file to the config synthdefs directory
-                            let config_dir = config_synthdefs_dir();
-                            let _ = std::fs::create_dir_all(&config_dir);
-
-                            // Copy .scd file
-                            if let Some(filename) = path.file_name() {
-                                let dest = config_dir.join(filename);
-                                let _ = std::fs::copy(path, &dest);
-                            }
-
-                            // Compile and load the synthdef
-                            match compile_and_load_synthdef(path, &config_dir, &synthdef_name, audio_engine) {
-                                Ok(_) => {
-                                    if let Some(server) = panes.get_pane_mut::<ServerPane>("server") {
-                                        server.set_status(audio_engine.status(), &format!("Loaded custom synthdef: {}", synthdef_name));
-                                    }
-                                }
-                                Err(e) => {
-                                    eprintln!("Failed to compile/load synthdef: {}", e);
-                                    if let Some(server) = panes.get_pane_mut::<ServerPane>("server") {
-                                        server.set_status(audio_engine.status(), &format!("Import error: {}", e));
-                                    }
-                                }
-                            }
+            let msg = match parse_custom_synthdef(path) {
+                Ok(custom) => {
+                    let name = custom.synthdef_name.clone();
+                    match register_custom_synthdef(custom, &config_synthdefs_dir(), state, audio_engine) {
+                        Ok(()) => format!("Compiling {}...", name),
+                        Err(e) => format!("Import error: {}", e),
+                    }
+                }
+                Err(e) => format!("Import error: {}", e),
+            };
+            if let Some(server) = panes.get_pane_mut::<ServerPane>("server") {
+                server.set_status(audio_engine.status(), &msg);
+            }
 
-                            // Pop back to the pane that opened the file browser
-                            panes.pop(&*state);
-                        }
-                        Err(e) => {
-                            eprintln!("Failed to parse .scd file: {}", e);
-                            panes.pop(&*state);
-                        }
+            // Pop back to the pane that opened the file browser
+            panes.pop(&*state);
+        }
+        SessionAction::ImportMidiFile(ref path) => {
+            let parsed = std::fs::read(path)
+                .map_err(|e| e.to_string())
+                .and_then(|bytes| parse_midi_file(&bytes));
+            match parsed {
+                Ok(file) => {
+                    let import = import_midi_file(state, &file);
+                    if audio_engine.is_running() {
+                        let _ = audio_engine.rebuild_instrument_routing(&state.instruments, &state.session);
                     }
+                    app_frame.show_toast(import.summary());
                 }
+                Err(e) => app_frame.show_toast(format!("MIDI import error: {}", e)),
+            }
+            panes.pop(&*state);
+        }
+        SessionAction::ImportSynthDefFolder(ref dir) => {
+            let mut files: Vec<PathBuf> = match std::fs::read_dir(dir) {
+                Ok(entries) => entries
+                    .filter_map(|e| e.ok())
+                    .map(|e| e.path())
+                    .filter(|p| p.is_file() && p.extension().is_some_and(|e| e == "scd"))
+                    .collect(),
                 Err(e) => {
-                    eprintln!("Failed to read .scd file: {}", e);
-                    panes.pop(&*state);
+                    if let Some(server) = panes.get_pane_mut::<ServerPane>("server") {
+                        server.set_status(audio_engine.status(), &format!("Import error: {}", e));
+                    }
+                    return;
+                }
+            };
+            files.sort();
+
+            // A bad file is recorded and the batch carries on
+            let mut report = SynthDefImportReport::default();
+            let mut queued = 0;
+            for path in &files {
+                let file_name = path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+                let custom = match parse_custom_synthdef(path) {
+                    Ok(custom) => custom,
+                    Err(e) => {
+                        report.failed.push((file_name, e));
+                        continue;
+                    }
+                };
+                if state.session.custom_synthdefs.by_name(&custom.synthdef_name).is_some() {
+                    report.skipped.push(custom.synthdef_name);
+                    continue;
+                }
+                let name = custom.synthdef_name.clone();
+                match register_custom_synthdef(custom, &config_synthdefs_dir(), state, audio_engine) {
+                    Ok(()) => queued += 1,
+                    Err(e) => report.failed.push((name, e)),
                 }
             }
+            audio_engine.begin_import_report(report);
+
+            let msg = if files.is_empty() {
+                format!("No .scd files in {}", dir.display())
+            } else {
+                format!("Compiling {} of {} synthdefs...", queued, files.len())
+            };
+            if let Some(server) = panes.get_pane_mut::<ServerPane>("server") {
+                server.set_status(audio_engine.status(), &msg);
+            }
+            panes.pop(&*state);
         }
     }
 }
@@ -1011,16 +1626,25 @@ fn dispatch_sequencer(
     match action {
         SequencerAction::ToggleStep(pad_idx, step_idx) => {
             if let Some(seq) = state.instruments.selected_drum_sequencer_mut() {
-                if let Some(step) = seq
-                    .pattern_mut()
+                let pattern = seq.pattern_mut();
+                if let Some(step) = pattern
                     .steps
                     .get_mut(*pad_idx)
                     .and_then(|s| s.get_mut(*step_idx))
                 {
                     step.active = !step.active;
+                    if !step.active {
+                        step.locks.clear();
+                    }
+                    pattern.clear_euclid(*pad_idx);
                 }
             }
         }
+        SequencerAction::Euclid(pad_idx, pulses, rotation) => {
+            if let Some(seq) = state.instruments.selected_drum_sequencer_mut() {
+                seq.pattern_mut().fill_euclid(*pad_idx, *pulses, *rotation);
+            }
+        }
         SequencerAction::AdjustVelocity(pad_idx, step_idx, delta) => {
             if let Some(seq) = state.instruments.selected_drum_sequencer_mut() {
                 if let Some(step) = seq
@@ -1033,6 +1657,32 @@ fn dispatch_sequencer(
                 }
             }
         }
+        SequencerAction::AdjustLock(pad_idx, step_idx, param, direction) => {
+            if let Some(seq) = state.instruments.selected_drum_sequencer_mut() {
+                let Some(pad) = seq.pads.get(*pad_idx) else { return };
+                let base = pad.lock_base(*param);
+                if let Some(step) = seq
+                    .pattern_mut()
+                    .steps
+                    .get_mut(*pad_idx)
+                    .and_then(|s| s.get_mut(*step_idx))
+                {
+                    // Only steps that trigger can carry locks; a new lock starts from the pad
+                    if step.active {
+                        let value = step.lock(*param).unwrap_or(base);
+                        step.set_lock(*param, value + *direction as f32 * param.increment());
+                    }
+                }
+            }
+        }
+        SequencerAction::ClearLocks(pad_idx, step_idx) => {
+            if let Some(step) = state.instruments.selected_drum_sequencer_mut()
+                .and_then(|seq| seq.pattern_mut().steps.get_mut(*pad_idx))
+                .and_then(|s| s.get_mut(*step_idx))
+            {
+                step.locks.clear();
+            }
+        }
         SequencerAction::ClearPad(pad_idx) => {
             if let Some(seq) = state.instruments.selected_drum_sequencer_mut() {
                 for step in seq
@@ -1043,7 +1693,9 @@ fn dispatch_sequencer(
                     .flat_map(|s| s.iter_mut())
                 {
                     step.active = false;
+                    step.locks.clear();
                 }
+                seq.pattern_mut().clear_euclid(*pad_idx);
             }
         }
         SequencerAction::ClearPattern => {
@@ -1060,6 +1712,7 @@ fn dispatch_sequencer(
                 let new_len = lengths[(idx + 1) % lengths.len()];
                 let old_pattern = seq.pattern().clone();
                 let mut new_pattern = DrumPattern::new(new_len);
+                new_pattern.follow_action = old_pattern.follow_action;
                 for (pad_idx, old_steps) in old_pattern.steps.iter().enumerate() {
                     for (step_idx, step) in old_steps.iter().enumerate() {
                         if step_idx < new_len {
@@ -1067,21 +1720,47 @@ fn dispatch_sequencer(
                         }
                     }
                 }
+                // Generated rows are regenerated to span the new length
+                for (pad_idx, generated) in old_pattern.euclid.iter().enumerate() {
+                    if let Some((pulses, rotation)) = *generated {
+                        new_pattern.fill_euclid(pad_idx, pulses, rotation);
+                    }
+                }
                 *seq.pattern_mut() = new_pattern;
             }
         }
         SequencerAction::NextPattern => {
             if let Some(seq) = state.instruments.selected_drum_sequencer_mut() {
-                seq.current_pattern = (seq.current_pattern + 1) % seq.patterns.len();
+                seq.select_pattern((seq.current_pattern + 1) % seq.patterns.len());
             }
         }
         SequencerAction::PrevPattern => {
             if let Some(seq) = state.instruments.selected_drum_sequencer_mut() {
-                seq.current_pattern = if seq.current_pattern == 0 {
+                let prev = if seq.current_pattern == 0 {
                     seq.patterns.len() - 1
                 } else {
                     seq.current_pattern - 1
                 };
+                seq.select_pattern(prev);
+            }
+        }
+        SequencerAction::CycleFollowTarget => {
+            // Off, then each pattern in turn
+            if let Some(seq) = state.instruments.selected_drum_sequencer_mut() {
+                let count = seq.patterns.len();
+                let pattern = seq.pattern_mut();
+                pattern.follow_action = match pattern.follow_action {
+                    None => Some((0, 1)),
+                    Some((target, _)) if target + 1 >= count => None,
+                    Some((target, repeats)) => Some((target + 1, repeats)),
+                };
+            }
+        }
+        SequencerAction::AdjustFollowRepeats(delta) => {
+            if let Some(seq) = state.instruments.selected_drum_sequencer_mut() {
+                if let Some((_, repeats)) = &mut seq.pattern_mut().follow_action {
+                    *repeats = (*repeats as i16 + *delta as i16).clamp(1, MAX_FOLLOW_REPEATS as i16) as u8;
+                }
             }
         }
         SequencerAction::AdjustPadLevel(pad_idx, delta) => {
@@ -1091,14 +1770,123 @@ fn dispatch_sequencer(
                 }
             }
         }
+        SequencerAction::ToggleReverse(pad_idx) => {
+            // Read on each hit, so toggling takes effect from the next step
+            if let Some(seq) = state.instruments.selected_drum_sequencer_mut() {
+                if let Some(pad) = seq.pads.get_mut(*pad_idx) {
+                    pad.reverse = !pad.reverse;
+                }
+            }
+        }
+        SequencerAction::LoadLayer(pad_idx) => {
+            if let Some(fb) = panes.get_pane_mut::<FileBrowserPane>("file_browser") {
+                fb.open_for(crate::ui::FileSelectAction::LoadPadLayer(*pad_idx), None);
+            }
+            panes.push_to("file_browser", &*state);
+        }
+        SequencerAction::ClearLayers(pad_idx) => {
+            if let Some(seq) = state.instruments.selected_drum_sequencer_mut() {
+                if let Some(pad) = seq.pads.get_mut(*pad_idx) {
+                    pad.layers.clear();
+                }
+            }
+        }
+        SequencerAction::ApplyGroove => {
+            if let Some(groove) = state.session.selected_groove().cloned() {
+                if let Some(seq) = state.instruments.selected_drum_sequencer_mut() {
+                    groove.apply_to_pattern(seq.pattern_mut(), 1.0);
+                }
+            }
+        }
+        SequencerAction::ExportLoop(path) => {
+            let Some(seq) = state.instruments.selected_drum_sequencer() else {
+                return;
+            };
+            let sample_rate = audio_engine.server_sample_rate().unwrap_or(48000);
+            let bpm = state.session.piano_roll.bpm;
+            let message = match audio::nrt::export_drum_loop(
+                seq, bpm, sample_rate, std::path::Path::new("synthdefs"),
+                &audio_engine.scsynth_candidates(), path,
+            ) {
+                Ok(path) => format!("Exported loop to {}", path.display()),
+                Err(e) => format!("Export error: {}", e),
+            };
+            if let Some(server) = panes.get_pane_mut::<ServerPane>("server") {
+                server.set_status(audio_engine.status(), &message);
+            }
+        }
+        SequencerAction::BounceToPad(pad_idx, path) => {
+            let Some(seq) = state.instruments.selected_drum_sequencer() else {
+                return;
+            };
+            let sample_rate = audio_engine.server_sample_rate().unwrap_or(48000);
+            let bpm = state.session.piano_roll.bpm;
+            let rendered = audio::nrt::export_drum_loop(
+                seq, bpm, sample_rate, std::path::Path::new("synthdefs"),
+                &audio_engine.scsynth_candidates(), path,
+            );
+            let message = match rendered {
+                Ok(path) => {
+                    let path_str = path.to_string_lossy().to_string();
+                    let name = path.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default();
+                    if let Some(seq) = state.instruments.selected_drum_sequencer_mut() {
+                        let buffer_id = seq.next_buffer_id;
+                        seq.next_buffer_id += 1;
+                        if audio_engine.is_running() {
+                            start_sample_load(audio_engine, &mut state.sample_loads, buffer_id, &path_str);
+                        }
+                        if let Some(pad) = seq.pads.get_mut(*pad_idx) {
+                            pad.load_whole_sample(buffer_id, path_str, name);
+                        }
+                    }
+                    format!("Bounced pattern to pad {} ({})", pad_idx + 1, path.display())
+                }
+                Err(e) => format!("Bounce error: {}", e),
+            };
+            if let Some(server) = panes.get_pane_mut::<ServerPane>("server") {
+                server.set_status(audio_engine.status(), &message);
+            }
+        }
+        SequencerAction::ExtractGroove => {
+            let groove = state.instruments.selected_drum_sequencer().map(|seq| {
+                let steps = seq.pattern().length.min(DEFAULT_GROOVE_STEPS);
+                GrooveTemplate::extract_from_pattern("", seq.pattern(), steps)
+            });
+            if let Some(groove) = groove {
+                state.session.add_groove(groove);
+            }
+        }
+        SequencerAction::LoadRoundRobin(pad_idx) => {
+            if let Some(fb) = panes.get_pane_mut::<FileBrowserPane>("file_browser") {
+                fb.open_for(crate::ui::FileSelectAction::LoadPadRoundRobin(*pad_idx), None);
+            }
+            panes.push_to("file_browser", &*state);
+        }
+        SequencerAction::ClearRoundRobin(pad_idx) => {
+            if let Some(seq) = state.instruments.selected_drum_sequencer_mut() {
+                if let Some(pad) = seq.pads.get_mut(*pad_idx) {
+                    pad.round_robin.clear();
+                }
+            }
+        }
+        SequencerAction::ToggleLayerCrossfade(pad_idx) => {
+            if let Some(seq) = state.instruments.selected_drum_sequencer_mut() {
+                if let Some(pad) = seq.pads.get_mut(*pad_idx) {
+                    pad.crossfade = !pad.crossfade;
+                }
+            }
+        }
         SequencerAction::PlayStop => {
             if let Some(seq) = state.instruments.selected_drum_sequencer_mut() {
                 seq.playing = !seq.playing;
                 if !seq.playing {
                     seq.current_step = 0;
                     seq.step_accumulator = 0.0;
+                    seq.pattern_passes = 0;
+                    seq.reset_round_robin();
                 }
             }
+            reset_meter_if_idle(state, audio_engine);
         }
         SequencerAction::LoadSample(pad_idx) => {
             if let Some(fb) = panes.get_pane_mut::<FileBrowserPane>("file_browser") {
@@ -1121,7 +1909,7 @@ fn dispatch_sequencer(
                 seq.next_buffer_id += 1;
 
                 if audio_engine.is_running() {
-                    let _ = audio_engine.load_sample(buffer_id, &path_str);
+                    start_sample_load(audio_engine, &mut state.sample_loads, buffer_id, &path_str);
                 }
 
                 if let Some(pad) = seq.pads.get_mut(*pad_idx) {
@@ -1131,6 +1919,47 @@ fn dispatch_sequencer(
                 }
             }
 
+            panes.pop(&*state);
+        }
+        SequencerAction::LoadRoundRobinResult(pad_idx, path) => {
+            let path_str = path.to_string_lossy().to_string();
+
+            if let Some(seq) = state.instruments.selected_drum_sequencer_mut() {
+                let buffer_id = seq.next_buffer_id;
+                seq.next_buffer_id += 1;
+
+                if audio_engine.is_running() {
+                    start_sample_load(audio_engine, &mut state.sample_loads, buffer_id, &path_str);
+                }
+
+                if let Some(pad) = seq.pads.get_mut(*pad_idx) {
+                    pad.round_robin.push(RoundRobinSample { buffer_id, path: path_str });
+                }
+            }
+
+            panes.pop(&*state);
+        }
+        SequencerAction::LoadLayerResult(pad_idx, path) => {
+            let path_str = path.to_string_lossy().to_string();
+
+            if let Some(seq) = state.instruments.selected_drum_sequencer_mut() {
+                let buffer_id = seq.next_buffer_id;
+                seq.next_buffer_id += 1;
+
+                if audio_engine.is_running() {
+                    start_sample_load(audio_engine, &mut state.sample_loads, buffer_id, &path_str);
+                }
+
+                if let Some(pad) = seq.pads.get_mut(*pad_idx) {
+                    pad.layers.push(VelocityLayer {
+                        buffer_id,
+                        path: path_str,
+                        vel_range: (0, 127),
+                    });
+                    spread_layer_ranges(&mut pad.layers);
+                }
+            }
+
             panes.pop(&*state);
         }
     }
@@ -1156,15 +1985,15 @@ fn dispatch_chopper(
                 .map(|s| s.to_string_lossy().to_string())
                 .unwrap_or_default();
 
-            // Compute waveform peaks from WAV file
-            let (peaks, duration_secs) = compute_waveform_peaks(&path_str);
+            // Waveform peaks from WAV file (cached by path + mtime)
+            let (peaks, duration_secs) = state.peak_cache.peaks(path);
 
             if let Some(seq) = state.instruments.selected_drum_sequencer_mut() {
                 let buffer_id = seq.next_buffer_id;
                 seq.next_buffer_id += 1;
 
                 if audio_engine.is_running() {
-                    let _ = audio_engine.load_sample(buffer_id, &path_str);
+                    start_sample_load(audio_engine, &mut state.sample_loads, buffer_id, &path_str);
                 }
 
                 let initial_slice = Slice::full(0);
@@ -1177,6 +2006,8 @@ fn dispatch_chopper(
                     next_slice_id: 1,
                     waveform_peaks: peaks,
                     duration_secs,
+                    original_path: None,
+                    status: None,
                 });
             }
 
@@ -1265,9 +2096,15 @@ fn dispatch_chopper(
                         if let Some(slice) = chopper.slices.get(chopper.selected_slice) {
                             if let Some(buffer_id) = chopper.buffer_id {
                                 if audio_engine.is_running() {
+                                    let hit = PadHit {
+                                        level: 0.8,
+                                        slice_start: slice.start,
+                                        slice_end: slice.end,
+                                        reverse: false,
+                                        rate: 1.0,
+                                    };
                                     let _ = audio_engine.play_drum_hit_to_instrument(
-                                        buffer_id, 0.8, instrument.id,
-                                        slice.start, slice.end,
+                                        buffer_id, hit.level, instrument.id, &hit,
                                     );
                                 }
                             }
@@ -1325,184 +2162,934 @@ fn dispatch_chopper(
             }
             panes.pop(&*state);
         }
+        ChopperAction::TrimSilence | ChopperAction::Normalize => {
+            let Some(path) = state.instruments.selected_drum_sequencer_mut()
+                .and_then(|seq| seq.chopper.as_ref())
+                .and_then(|c| c.path.clone())
+            else {
+                return;
+            };
+            let trim = *action == ChopperAction::TrimSilence;
+            match edit_sample(&path, trim) {
+                Ok(Some(edit)) => {
+                    if let Some(chopper) = state.instruments.selected_drum_sequencer_mut().and_then(|seq| seq.chopper.as_mut()) {
+                        chopper.slices.iter_mut().for_each(|s| s.rescale_to(edit.kept));
+                    }
+                    let status = if trim { "Trimmed silence" } else { "Normalized" };
+                    replace_chopper_sample(state, audio_engine, edit.path, Some(path), status);
+                }
+                Ok(None) => set_chopper_status(state, "Sample is silent; nothing to normalize".to_string()),
+                Err(e) => set_chopper_status(state, e),
+            }
+        }
+        ChopperAction::RevertSample => {
+            let original = state.instruments.selected_drum_sequencer_mut()
+                .and_then(|seq| seq.chopper.as_ref())
+                .and_then(|c| c.original_path.clone());
+            let Some(original) = original else {
+                set_chopper_status(state, "No edits to revert".to_string());
+                return;
+            };
+            // Slices cut on a trimmed copy don't line up with the original; start over
+            let (_, duration_secs) = state.peak_cache.peaks(Path::new(&original));
+            let mut status = "Reverted to original";
+            if let Some(chopper) = state.instruments.selected_drum_sequencer_mut().and_then(|seq| seq.chopper.as_mut()) {
+                if (duration_secs - chopper.duration_secs).abs() > 1e-3 {
+                    chopper.slices = vec![Slice::full(chopper.next_slice_id)];
+                    chopper.next_slice_id += 1;
+                    chopper.selected_slice = 0;
+                    status = "Reverted to original; slices reset";
+                }
+            }
+            replace_chopper_sample(state, audio_engine, original, None, status);
+        }
         ChopperAction::MoveCursor(_) => {
             // Cursor tracked locally in pane
         }
     }
 }
 
-/// Compute waveform peaks from a WAV file for display
-pub fn compute_waveform_peaks(path: &str) -> (Vec<f32>, f32) {
-    let reader = match hound::WavReader::open(path) {
-        Ok(r) => r,
-        Err(_) => return (Vec::new(), 0.0),
+/// Point the selected chopper at an edited (or restored) sample file and load it into a
+/// fresh buffer. Pads keep the buffer they were assigned until slices are re-committed.
+/// `original` is remembered the first time a sample is edited so revert goes all the way back.
+fn replace_chopper_sample(
+    state: &mut AppState,
+    audio_engine: &mut AudioEngine,
+    path: String,
+    original: Option<String>,
+    status: &str,
+) {
+    let (peaks, duration_secs) = state.peak_cache.peaks(Path::new(&path));
+    let Some(seq) = state.instruments.selected_drum_sequencer_mut() else {
+        return;
     };
-    let spec = reader.spec();
-    let num_channels = spec.channels as usize;
-    let sample_rate = spec.sample_rate;
-    let num_samples = reader.len() as usize;
-    let duration_secs = num_samples as f32 / (sample_rate as f32 * num_channels as f32);
-
-    let target_peaks = 512;
-    let samples_per_peak = (num_samples / target_peaks).max(1);
-
-    let mut peaks = Vec::with_capacity(target_peaks);
-    let samples: Vec<f32> = match spec.sample_format {
-        hound::SampleFormat::Int => {
-            let max_val = (1i64 << (spec.bits_per_sample - 1)) as f32;
-            reader.into_samples::<i32>()
-                .filter_map(|s| s.ok())
-                .map(|s| s as f32 / max_val)
-                .collect()
-        }
-        hound::SampleFormat::Float => {
-            reader.into_samples::<f32>()
-                .filter_map(|s| s.ok())
-                .collect()
-        }
+    let buffer_id = seq.next_buffer_id;
+    seq.next_buffer_id += 1;
+    let Some(chopper) = &mut seq.chopper else {
+        return;
     };
+    if audio_engine.is_running() {
+        start_sample_load(audio_engine, &mut state.sample_loads, buffer_id, &path);
+    }
+    chopper.buffer_id = Some(buffer_id);
+    chopper.path = Some(path);
+    chopper.waveform_peaks = peaks;
+    chopper.duration_secs = duration_secs;
+    chopper.original_path = match original {
+        Some(original) => chopper.original_path.take().or(Some(original)),
+        None => None,
+    };
+    chopper.status = Some(status.to_string());
+}
 
-    for chunk in samples.chunks(samples_per_peak) {
-        let peak = chunk.iter().fold(0.0f32, |acc, &s| acc.max(s.abs()));
-        peaks.push(peak);
+/// Point a sampler at an edited (or restored) sample file, loading it into a fresh
+/// buffer, and refresh an open editor so its zone copy picks up the new bounds
+fn replace_sampler_sample(
+    state: &mut AppState,
+    panes: &mut PaneManager,
+    audio_engine: &mut AudioEngine,
+    id: InstrumentId,
+    path: String,
+) {
+    let buffer_id = state.instruments.next_sampler_buffer_id;
+    state.instruments.next_sampler_buffer_id += 1;
+    if audio_engine.is_running() {
+        start_sample_load(audio_engine, &mut state.sample_loads, buffer_id, &path);
+    }
+    let Some(instrument) = state.instruments.instrument_mut(id) else {
+        return;
+    };
+    if let Some(config) = instrument.sampler_config.as_mut() {
+        config.buffer_id = Some(buffer_id);
+        config.path = Some(path);
     }
+    let instrument = instrument.clone();
+    if let Some(edit) = panes.get_pane_mut::<InstrumentEditPane>("instrument_edit") {
+        if edit.instrument_id() == Some(id) {
+            edit.refresh_instrument(&instrument);
+        }
+    }
+}
 
-    (peaks, duration_secs)
+/// A trimmed or normalized copy of a sample
+struct SampleEdit {
+    path: String,
+    /// Part of the old sample the copy keeps, as start/end fractions (all of it for normalize)
+    kept: (f32, f32),
 }
 
-/// Get the config directory for custom synthdefs
-fn config_synthdefs_dir() -> PathBuf {
-    if let Some(home) = std::env::var_os("HOME") {
-        PathBuf::from(home)
-            .join(".config")
-            .join("ilex")
-            .join("synthdefs")
+/// Trim or normalize the sample at `path` into a processed copy. None means the
+/// sample is silent and there is nothing to normalize.
+fn edit_sample(path: &str, trim: bool) -> Result<Option<SampleEdit>, String> {
+    let audio = sample_decode::read_samples(Path::new(path))?;
+    let (processed, kept) = if trim {
+        let (start, end) = sample_decode::sound_bounds(
+            &audio, sample_decode::TRIM_THRESHOLD, sample_decode::TRIM_PRE_ROLL_SECS,
+        );
+        let frames = (audio.samples.len() / audio.channels.max(1)).max(1) as f32;
+        let trimmed = sample_decode::trim_silence(
+            &audio, sample_decode::TRIM_THRESHOLD, sample_decode::TRIM_PRE_ROLL_SECS,
+        );
+        (trimmed, (start as f32 / frames, end as f32 / frames))
     } else {
-        PathBuf::from("synthdefs")
+        match sample_decode::normalize(&audio, sample_decode::NORMALIZE_PEAK) {
+            Some(normalized) => (normalized, (0.0, 1.0)),
+            None => return Ok(None),
+        }
+    };
+    let new_path = sample_decode::write_processed(Path::new(path), &processed, if trim { "trim" } else { "norm" })?;
+    Ok(Some(SampleEdit { path: new_path, kept }))
+}
+
+fn set_chopper_status(state: &mut AppState, status: String) {
+    if let Some(chopper) = state.instruments.selected_drum_sequencer_mut().and_then(|seq| seq.chopper.as_mut()) {
+        chopper.status = Some(status);
     }
 }
 
-/// Find sclang executable, checking common locations
-fn find_sclang() -> Option<PathBuf> {
-    // Check if sclang is in PATH
-    if let Ok(output) = std::process::Command::new("which").arg("sclang").output() {
-        if output.status.success() {
-            let path = String::from_utf8_lossy(&output.stdout).trim().to_string();
-            if !path.is_empty() {
-                return Some(PathBuf::from(path));
+fn dispatch_midi(
+    action: &MidiAction,
+    state: &mut AppState,
+    panes: &mut PaneManager,
+    midi_input: &mut MidiInputManager,
+    midi_output: &mut MidiOutputManager,
+) {
+    match action {
+        MidiAction::ConnectPort(port_index) => {
+            let result = midi_input.connect(*port_index);
+            if let Some(midi_pane) = panes.get_pane_mut::<MidiPane>("midi") {
+                match result {
+                    Ok(()) => {
+                        let name = midi_input.connected_port_name().map(|s| s.to_string());
+                        midi_pane.set_connection(name, "Connected");
+                    }
+                    Err(e) => midi_pane.set_connection(None, &format!("Connect error: {}", e)),
+                }
+            }
+        }
+        MidiAction::Disconnect => {
+            midi_input.disconnect();
+            if let Some(midi_pane) = panes.get_pane_mut::<MidiPane>("midi") {
+                midi_pane.set_connection(None, "Disconnected");
             }
         }
+        MidiAction::SetChannelRoute(channel, route) => {
+            state.session.midi_recording.set_channel_route(*channel, *route);
+        }
+        MidiAction::CycleProgramChangeMode => {
+            let midi = &mut state.session.midi_recording;
+            midi.program_change_mode = midi.program_change_mode.next();
+        }
+        MidiAction::CycleRecordQuantize => {
+            state.session.midi_recording.cycle_record_quantize();
+        }
+        MidiAction::ToggleRecordAutomation => {
+            let midi = &mut state.session.midi_recording;
+            midi.record_automation = !midi.record_automation;
+        }
+        MidiAction::CycleAutomationFreeze => {
+            state.session.automation.cycle_freeze_division();
+        }
+        MidiAction::ConnectOutput(port_index) => {
+            midi_output.all_notes_off();
+            let result = midi_output.connect(*port_index);
+            if let Some(midi_pane) = panes.get_pane_mut::<MidiPane>("midi") {
+                match result {
+                    Ok(()) => {
+                        let name = midi_output.connected_port_name().map(|s| s.to_string());
+                        midi_pane.set_output_connection(name, "Output connected");
+                    }
+                    Err(e) => midi_pane.set_output_connection(None, &format!("Output error: {}", e)),
+                }
+            }
+        }
+        MidiAction::DisconnectOutput => {
+            midi_output.all_notes_off();
+            midi_output.disconnect();
+            if let Some(midi_pane) = panes.get_pane_mut::<MidiPane>("midi") {
+                midi_pane.set_output_connection(None, "Output disconnected");
+            }
+        }
+        MidiAction::ToggleThru => {
+            let thru = &mut state.session.midi_recording.thru;
+            thru.enabled = !thru.enabled;
+            // Notes held when thru goes off would never get their note-off
+            if !thru.enabled {
+                midi_output.all_notes_off();
+            }
+        }
+        MidiAction::CycleThruChannel => {
+            // Release on the old channel before notes start arriving on the new one
+            midi_output.all_notes_off();
+            state.session.midi_recording.thru.cycle_channel();
+        }
+        MidiAction::AdjustThruVelocity(delta) => {
+            state.session.midi_recording.thru.adjust_velocity_scale(*delta);
+        }
+        MidiAction::LearnCc(target) => {
+            state.session.midi_recording.learn_target = target.clone();
+        }
+        MidiAction::ForgetCc(target) => {
+            state.session.midi_recording.forget_cc_mappings(target);
+        }
     }
+}
 
-    // Common macOS locations
-    let candidates = [
-        "/Applications/SuperCollider.app/Contents/MacOS/sclang",
-        "/Applications/SuperCollider/SuperCollider.app/Contents/MacOS/sclang",
-        "/usr/local/bin/sclang",
-        "/opt/homebrew/bin/sclang",
-    ];
+/// Write notes entered together as one step at the piano roll cursor (step-record mode,
+/// transport stopped). The cursor then advances by the current note length.
+fn step_record_notes(pitches: &[u8], state: &mut AppState, panes: &mut PaneManager) {
+    if state.session.piano_roll.playing {
+        return;
+    }
+    let Some(pr_pane) = panes.get_pane_mut::<PianoRollPane>("piano_roll") else {
+        return;
+    };
+    if !pr_pane.is_step_recording() {
+        return;
+    }
+    let (track_idx, duration, vel) = (pr_pane.current_track(), pr_pane.default_duration(), pr_pane.default_velocity());
+    let tick = pr_pane.begin_step(pitches);
+    for &pitch in pitches {
+        let replaced = state.session.piano_roll.add_note(track_idx, pitch, tick, duration, vel);
+        pr_pane.step_replaced(replaced);
+    }
+}
 
-    for candidate in candidates {
-        let path = PathBuf::from(candidate);
-        if path.exists() {
-            return Some(path);
+/// Convert MIDI captured during recording into piano roll notes and, if enabled,
+/// automation points, then stop recording. Must run before the playhead is reset.
+fn finish_midi_recording(state: &mut AppState) {
+    if !state.session.midi_recording.is_recording() {
+        return;
+    }
+    let pr = &state.session.piano_roll;
+    let loop_range = pr.looping.then_some((pr.loop_start, pr.loop_end));
+    let notes = state.session.midi_recording.recorded_notes(pr.playhead, pr.ticks_per_beat, loop_range);
+    for (instrument_id, note) in notes {
+        if let Some(track) = state.session.piano_roll.tracks.get_mut(&instrument_id) {
+            track.notes.push(note);
         }
     }
 
-    None
+    // Lanes written live from CC and pitch bend: the last value holds until recording
+    // stopped, then the pass is thinned down to the points its shape needs
+    let end_tick = state.session.piano_roll.playhead;
+    let automation = &mut state.session.automation;
+    for (target, span) in &state.session.midi_recording.automation_writes {
+        let Some(lane) = automation.lane_for_target_mut(target) else {
+            continue;
+        };
+        let mut end = span.end;
+        if end_tick > span.last {
+            if let Some(value) = lane.point_at(span.last).map(|p| p.value) {
+                lane.write_point(Some(span.last), end_tick, value);
+                end = end.max(end_tick);
+            }
+        }
+        lane.thin(span.start, end, RECORD_THIN_TOLERANCE);
+    }
+
+    state.session.midi_recording.stop_recording();
 }
 
-/// Compile a .scd file using sclang and load it into scsynth
-fn compile_and_load_synthdef(
-    scd_path: &std::path::Path,
-    output_dir: &std::path::Path,
-    synthdef_name: &str,
+/// Handle one incoming MIDI event, routing notes by channel.
+/// While recording, events are also captured at the current playhead.
+pub fn dispatch_midi_event(
+    event: &MidiEvent,
+    state: &mut AppState,
+    panes: &mut PaneManager,
     audio_engine: &mut AudioEngine,
-) -> Result<(), String> {
-    // Find sclang
-    let sclang = find_sclang().ok_or_else(|| {
-        "sclang not found. Install SuperCollider or add sclang to PATH.".to_string()
-    })?;
+) {
+    let recording = state.session.midi_recording.is_recording() && state.session.piano_roll.playing;
+    let tick = state.record_tick();
+    // Unrouted channels play the piano roll's current track while it's in front,
+    // elsewhere the globally selected instrument
+    let selected = if panes.active().id() == "piano_roll" {
+        piano_roll_track_instrument(state, panes)
+    } else {
+        state.instruments.selected_instrument().map(|s| s.id)
+    };
 
-    // Read the original .scd file
-    let scd_content = std::fs::read_to_string(scd_path)
-        .map_err(|e| format!("Failed to read .scd file: {}", e))?;
+    match *event {
+        MidiEvent::NoteOn { channel, note, velocity } => {
+            if !state.session.midi_recording.should_process_channel(channel) {
+                return;
+            }
+            // Outside the instrument's key range the note is dropped entirely, so its
+            // note-off finds nothing held either
+            let routed = state.session.midi_recording.route_channel(channel, selected);
+            if routed.is_some_and(|id| pitches_in_range(state, id, &[note]).is_empty()) {
+                return;
+            }
+            let target = state.session.midi_recording.note_on_target(channel, note, selected);
+            if recording {
+                state.session.midi_recording.capture(tick, target, *event);
+            }
+            // Step input pairs with the note-off via the held-note lookup, so only routed notes count
+            if target.is_some() && !state.session.piano_roll.playing {
+                if let Some(pr_pane) = panes
+                    .get_pane_mut::<PianoRollPane>("piano_roll")
+                    .filter(|pr| pr.is_step_recording())
+                {
+                    let duration = pr_pane.default_duration();
+                    let (track_idx, step_tick) = pr_pane.step_note_on(note);
+                    let replaced = state.session.piano_roll.add_note(track_idx, note, step_tick, duration, velocity);
+                    pr_pane.step_replaced(replaced);
+                }
+            }
+            if let Some(instrument_id) = target {
+                if state.note_repeat.enabled {
+                    let held = RepeatTarget::Note { instrument_id, pitch: note, velocity, channel: Some(channel) };
+                    state.note_repeat.press(held, Instant::now(), state.session.piano_roll.bpm);
+                }
+                if audio_engine.is_running() {
+                    let vel_f = velocity as f32 / 127.0;
+                    let _ = audio_engine.spawn_voice(instrument_id, note, vel_f, 0.0, &state.instruments, &state.session);
+                }
+            }
+        }
+        MidiEvent::NoteOff { channel, note } => {
+            state.note_repeat.release_midi(channel, note);
+            // Released by held-note lookup, not the current route or channel filter,
+            // so notes never hang when either changes mid-note.
+            let target = state.session.midi_recording.note_off_target(channel, note);
+            if recording {
+                state.session.midi_recording.capture(tick, target, *event);
+            }
+            if target.is_some() {
+                if let Some(pr_pane) = panes.get_pane_mut::<PianoRollPane>("piano_roll") {
+                    pr_pane.step_note_off();
+                }
+            }
+            if let Some(instrument_id) = target {
+                if audio_engine.is_running() {
+                    let _ = audio_engine.release_voice(instrument_id, note, 0.0, &state.instruments);
+                }
+            }
+        }
+        MidiEvent::ControlChange { channel, controller, value } => {
+            let midi = &mut state.session.midi_recording;
+            if !midi.should_process_channel(channel) {
+                return;
+            }
+            if let Some(target) = midi.learn_cc(channel, controller) {
+                if let Some(midi_pane) = panes.get_pane_mut::<MidiPane>("midi") {
+                    midi_pane.set_message(&format!("CC {} → {}", controller, target.name()));
+                }
+            }
+            if let Some(mapping) = state.session.midi_recording.find_cc_mapping(controller, channel) {
+                let (target, mapped) = (mapping.target.clone(), mapping.map_value(value));
+                apply_live_automation(state, audio_engine, target, mapped, recording, tick);
+            }
+            if controller == cc::SUSTAIN {
+                state.sustain = value >= 64;
+                audio_engine.set_sustain(state.sustain, &state.instruments);
+            }
+            if matches!(controller, cc::ALL_NOTES_OFF | cc::ALL_SOUNDS_OFF) && audio_engine.is_running() {
+                // All Sound Off means silence now, effects included, whatever the stop
+                // tail setting
+                if controller == cc::ALL_SOUNDS_OFF {
+                    audio_engine.cut_all_voices();
+                    audio_engine.silence_effects(&state.instruments, &state.session);
+                } else {
+                    audio_engine.stop_voices(&state.instruments, &state.session);
+                }
+                reset_meter_if_idle(state, audio_engine);
+            }
+        }
+        MidiEvent::PitchBend { channel, value } => {
+            let midi = &state.session.midi_recording;
+            if !midi.should_process_channel(channel) {
+                return;
+            }
+            let config = midi.route_channel(channel, selected).and_then(|id| midi.find_pitch_bend_config(id));
+            if let Some(config) = config {
+                let (target, mapped) = (config.target.clone(), config.map_value(value));
+                apply_live_automation(state, audio_engine, target, mapped, recording, tick);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// The live pitches an instrument responds to: notes outside its key range are ignored
+fn pitches_in_range(state: &AppState, instrument_id: InstrumentId, pitches: &[u8]) -> Vec<u8> {
+    match state.instruments.instrument(instrument_id) {
+        Some(instrument) => pitches.iter().copied().filter(|&p| instrument.accepts_note(p)).collect(),
+        None => pitches.to_vec(),
+    }
+}
+
+/// Send a mapped controller value to its target now and, while recording with
+/// automation capture on, write it at the playhead on the target's lane, which is
+/// created the first time a target is recorded
+fn apply_live_automation(
+    state: &mut AppState,
+    audio_engine: &mut AudioEngine,
+    target: AutomationTarget,
+    value: f32,
+    recording: bool,
+    tick: u32,
+) {
+    let _ = audio_engine.apply_automation(&target, value, &state.instruments, &state.session);
+    if !recording || !state.session.midi_recording.record_automation {
+        return;
+    }
+    let since = state.session.midi_recording.track_automation_write(&target, tick);
+    let automation = &mut state.session.automation;
+    let lane_id = automation.add_lane(target);
+    if let Some(lane) = automation.lane_mut(lane_id) {
+        let normalized = lane.normalize(value);
+        lane.write_point(since, tick, normalized);
+    }
+}
 
-    // Replace directory references with the actual output directory
-    // Handle both patterns: `dir ? thisProcess...` and just `thisProcess...`
-    let output_dir_str = format!("\"{}\"", output_dir.display());
-    let modified_content = scd_content
-        .replace("dir ? thisProcess.nowExecutingPath.dirname", &output_dir_str)
-        .replace("thisProcess.nowExecutingPath.dirname", &output_dir_str);
-
-    // Wrap in a block that exits when done
-    let compile_script = format!(
-        "(\n{}\n\"SUCCESS\".postln;\n0.exit;\n)",
-        modified_content
-    );
-
-    // Write temp compile script
-    let temp_script = std::env::temp_dir().join("ilex_compile_custom.scd");
-    std::fs::write(&temp_script, &compile_script)
-        .map_err(|e| format!("Failed to write compile script: {}", e))?;
-
-    // Run sclang with a timeout by spawning and waiting
-    let mut child = std::process::Command::new(&sclang)
-        .arg(&temp_script)
-        .stdout(std::process::Stdio::piped())
-        .stderr(std::process::Stdio::piped())
-        .spawn()
-        .map_err(|e| format!("Failed to run sclang: {}", e))?;
-
-    // Wait up to 30 seconds for compilation
-    let timeout = std::time::Duration::from_secs(30);
-    let start = std::time::Instant::now();
-
-    loop {
-        match child.try_wait() {
-            Ok(Some(_status)) => break,
-            Ok(None) => {
-                if start.elapsed() > timeout {
-                    let _ = child.kill();
-                    return Err("sclang compilation timed out".to_string());
-                }
-                std::thread::sleep(std::time::Duration::from_millis(100));
-            }
-            Err(e) => return Err(format!("Error waiting for sclang: {}", e)),
+/// Drop the master meter's held peak once nothing is playing back. Voices still
+/// releasing and live inputs show up again on the next meter reply.
+fn reset_meter_if_idle(state: &AppState, audio_engine: &mut AudioEngine) {
+    let sequencer_playing = state
+        .instruments
+        .instruments
+        .iter()
+        .any(|inst| inst.drum_sequencer.as_ref().is_some_and(|seq| seq.playing));
+    if !state.session.piano_roll.playing && !sequencer_playing {
+        audio_engine.reset_meter();
+    }
+}
+
+/// Instrument of the piano roll's current track, which its live play and recording use
+/// regardless of the global instrument selection
+fn piano_roll_track_instrument(state: &AppState, panes: &mut PaneManager) -> Option<InstrumentId> {
+    let track_idx = panes.get_pane_mut::<PianoRollPane>("piano_roll")?.current_track();
+    state.session.piano_roll.track_at(track_idx).map(|t| t.module_id)
+}
+
+/// How long a note played in the piano roll sounds: the recorded note length while
+/// recording, so what you hear matches what lands, else the keyboard auto-release.
+fn piano_roll_live_ticks(state: &AppState, panes: &mut PaneManager) -> u32 {
+    panes
+        .get_pane_mut::<PianoRollPane>("piano_roll")
+        .filter(|pr| pr.is_recording())
+        .map(|pr| pr.default_duration())
+        .unwrap_or_else(|| state.key_note_ticks())
+}
+
+fn piano_roll_recording(panes: &mut PaneManager) -> bool {
+    panes.get_pane_mut::<PianoRollPane>("piano_roll").is_some_and(|pr| pr.is_recording())
+}
+
+/// In hold mode, treat a key press whose notes are all still sounding as the key's
+/// auto-repeat: keep them sounding a little longer instead of playing them again.
+/// True when the press was taken as a repeat. Note repeat handles held keys itself.
+fn hold_key_notes(state: &AppState, active_notes: &mut [(u32, u8, u32)], instrument_id: InstrumentId, pitches: &[u8]) -> bool {
+    state.live_note_hold
+        && !state.note_repeat.enabled
+        && playback::hold_live_notes(active_notes, instrument_id, pitches, state.key_release_ticks())
+}
+
+/// Register a key press with note repeat. False when every pitch is the auto-repeat of
+/// a key already held, which the repeats stand in for, so the press should be ignored.
+fn press_note_repeat(state: &mut AppState, instrument_id: InstrumentId, pitches: &[u8], velocity: u8) -> bool {
+    if !state.note_repeat.enabled {
+        return true;
+    }
+    let now = Instant::now();
+    let bpm = state.session.piano_roll.bpm;
+    let mut any_new = false;
+    for &pitch in pitches {
+        let target = RepeatTarget::Note { instrument_id, pitch, velocity, channel: None };
+        any_new |= state.note_repeat.press(target, now, bpm);
+    }
+    any_new
+}
+
+/// Hit a kit pad at full velocity, writing it into the playing pattern while recording
+fn play_drum_pad(state: &mut AppState, audio_engine: &mut AudioEngine, instrument_id: InstrumentId, pad_idx: usize) {
+    let recording = state.session.midi_recording.is_recording();
+    let Some(instrument) = state.instruments.instrument_mut(instrument_id) else {
+        return;
+    };
+    let Some(seq) = &mut instrument.drum_sequencer else {
+        return;
+    };
+    if pad_idx >= seq.pads.len() {
+        return;
+    }
+    if recording {
+        seq.record_hit(pad_idx, 127);
+    }
+    // Pads are auditioned at full velocity
+    if audio_engine.is_running() {
+        let buffers = seq.next_hit(pad_idx, 127);
+        let hit = seq.pads[pad_idx].hit(&[]);
+        for (buffer_id, gain) in buffers {
+            let _ = audio_engine.play_drum_hit_to_instrument(buffer_id, hit.level * gain, instrument_id, &hit);
         }
     }
+}
 
-    let output = child.wait_with_output()
-        .map_err(|e| format!("Failed to get sclang output: {}", e))?;
+/// Sound a test note through the instrument's current settings, released after the
+/// live note length: the first loaded pad of a kit, otherwise its `audition_pitch`.
+/// Never recorded.
+fn audition_instrument(
+    state: &mut AppState,
+    audio_engine: &mut AudioEngine,
+    active_notes: &mut Vec<(u32, u8, u32)>,
+    instrument_id: InstrumentId,
+) {
+    if !audio_engine.is_running() {
+        return;
+    }
+    let duration_ticks = state.live_note_ticks();
+    let Some(instrument) = state.instruments.instrument_mut(instrument_id) else {
+        return;
+    };
+    if let Some(seq) = &mut instrument.drum_sequencer {
+        let Some(pad_idx) = seq.pads.iter().position(|p| p.buffer_id.is_some()) else {
+            return;
+        };
+        let hit = seq.pads[pad_idx].hit(&[]);
+        for (buffer_id, gain) in seq.next_hit(pad_idx, 127) {
+            let _ = audio_engine.play_drum_hit_to_instrument(buffer_id, hit.level * gain, instrument_id, &hit);
+        }
+        return;
+    }
+    let pitch = instrument.audition_pitch();
+    let _ = audio_engine.spawn_voice(instrument_id, pitch, 100.0 / 127.0, 0.0, &state.instruments, &state.session);
+    active_notes.push((instrument_id, pitch, duration_ticks));
+}
 
-    // Check for errors (but ignore common non-error messages)
-    let stdout = String::from_utf8_lossy(&output.stdout);
-    let stderr = String::from_utf8_lossy(&output.stderr);
+/// Retrigger inputs held in note-repeat mode and release keys that were let go.
+/// Called every frame from the main loop. Repeats are written to the piano roll
+/// (or drum pattern) the same way the held input's first hit was.
+pub fn dispatch_note_repeat(state: &mut AppState, panes: &mut PaneManager, audio_engine: &mut AudioEngine) {
+    if !state.note_repeat.is_active() {
+        return;
+    }
+    let now = Instant::now();
+    let bpm = state.session.piano_roll.bpm;
 
-    // Look for actual errors, not just any "ERROR" in output
-    let has_error = stderr.lines().any(|line| {
-        line.contains("ERROR:") || line.contains("FAILURE")
-    }) || stdout.lines().any(|line| {
-        line.starts_with("ERROR:") || line.contains("FAILURE")
-    });
+    for target in state.note_repeat.released_keys(now) {
+        if let RepeatTarget::Note { instrument_id, pitch, .. } = target {
+            if audio_engine.is_running() {
+                let _ = audio_engine.release_voice(instrument_id, pitch, 0.0, &state.instruments);
+            }
+        }
+    }
 
-    if has_error {
-        return Err(format!("sclang error: {}{}", stdout, stderr));
+    let repeat_ticks = (state.note_repeat.division.beats() * state.session.piano_roll.ticks_per_beat as f32)
+        .round()
+        .max(1.0) as u32;
+    for target in state.note_repeat.due(now, bpm) {
+        match target {
+            RepeatTarget::Note { instrument_id, pitch, velocity, channel } => {
+                // The previous hit ends where the next begins
+                if audio_engine.is_running() {
+                    let vel_f = velocity as f32 / 127.0;
+                    let _ = audio_engine.release_voice(instrument_id, pitch, 0.0, &state.instruments);
+                    let _ = audio_engine.spawn_voice(instrument_id, pitch, vel_f, 0.0, &state.instruments, &state.session);
+                }
+                let tick = state.record_tick();
+                match channel {
+                    Some(channel) => {
+                        let midi = &mut state.session.midi_recording;
+                        if midi.is_recording() && state.session.piano_roll.playing {
+                            midi.capture(tick, Some(instrument_id), MidiEvent::NoteOff { channel, note: pitch });
+                            midi.capture(tick, Some(instrument_id), MidiEvent::NoteOn { channel, note: pitch, velocity });
+                        }
+                    }
+                    None => {
+                        let recording_track = panes
+                            .get_pane_mut::<PianoRollPane>("piano_roll")
+                            .filter(|pr| pr.is_recording())
+                            .map(|pr| (pr.current_track(), pr.default_velocity()));
+                        if let Some((track_idx, vel)) = recording_track {
+                            let pr = &mut state.session.piano_roll;
+                            if pr.track_at(track_idx).is_some_and(|t| t.module_id == instrument_id) {
+                                pr.add_note(track_idx, pitch, tick, repeat_ticks, vel);
+                            }
+                        }
+                    }
+                }
+            }
+            RepeatTarget::Pad { instrument_id, pad } => {
+                play_drum_pad(state, audio_engine, instrument_id, pad);
+            }
+        }
     }
+}
 
-    // Clean up temp file
-    let _ = std::fs::remove_file(&temp_script);
+/// Start loading a sample in the background and mark its buffer as loading
+fn start_sample_load(
+    audio_engine: &mut AudioEngine,
+    sample_loads: &mut HashMap<BufferId, SampleLoadStatus>,
+    buffer_id: BufferId,
+    path: &str,
+) {
+    let status = match audio_engine.load_sample_async(buffer_id, path) {
+        Ok(()) => SampleLoadStatus::Loading,
+        Err(e) => SampleLoadStatus::Failed(e),
+    };
+    sample_loads.insert(buffer_id, status);
+}
 
-    // Load the .scsyndef into scsynth if connected
-    if audio_engine.is_running() {
-        let scsyndef_path = output_dir.join(format!("{}.scsyndef", synthdef_name));
-        if scsyndef_path.exists() {
-            audio_engine.load_synthdef_file(&scsyndef_path)?;
-        } else {
-            // Try loading all synthdefs from the directory as fallback
-            audio_engine.load_synthdefs(output_dir)?;
+/// Load every sample instruments play (drum pads, sampler buffers and their velocity
+/// layers) into a freshly connected server
+fn load_instrument_samples(state: &mut AppState, audio_engine: &mut AudioEngine) {
+    let mut loads: Vec<(BufferId, &str)> = Vec::new();
+    for instrument in &state.instruments.instruments {
+        if let Some(seq) = &instrument.drum_sequencer {
+            for pad in &seq.pads {
+                if let (Some(buffer_id), Some(path)) = (pad.buffer_id, &pad.path) {
+                    loads.push((buffer_id, path));
+                }
+                loads.extend(pad.layers.iter().map(|l| (l.buffer_id, l.path.as_str())));
+                loads.extend(pad.round_robin.iter().map(|s| (s.buffer_id, s.path.as_str())));
+            }
+        }
+        if let Some(config) = &instrument.sampler_config {
+            if let (Some(buffer_id), Some(path)) = (config.buffer_id, &config.path) {
+                loads.push((buffer_id, path));
+            }
+            for zone in &config.slices {
+                loads.extend(zone.layers.iter().map(|l| (l.buffer_id, l.path.as_str())));
+            }
+        }
+    }
+    for (buffer_id, path) in loads {
+        start_sample_load(audio_engine, &mut state.sample_loads, buffer_id, path);
+    }
+}
+
+/// Load the built-in synthdefs, then custom ones from the config directory and the
+/// project's synthdef directory, in that order so the project's win on a name clash.
+/// Custom directories that don't exist are skipped.
+fn load_all_synthdefs(audio_engine: &AudioEngine, session: &SessionState) -> (Result<(), String>, Result<(), String>) {
+    let builtin = audio_engine.load_synthdefs(Path::new("synthdefs"));
+    let custom = [Some(config_synthdefs_dir()), session.synthdef_dir.clone()]
+        .into_iter()
+        .flatten()
+        .filter(|dir| dir.is_dir())
+        .try_for_each(|dir| audio_engine.load_synthdefs(&dir));
+    (builtin, custom)
+}
+
+/// Load the project's synthdef directory into a running server, reporting the outcome
+fn load_project_synthdefs(audio_engine: &AudioEngine, session: &SessionState, app_frame: &mut Frame) {
+    let Some(dir) = &session.synthdef_dir else {
+        return;
+    };
+    if !audio_engine.is_running() {
+        return;
+    }
+    let message = if !dir.is_dir() {
+        format!("Synthdef folder not found, skipped: {}", dir.display())
+    } else {
+        match audio_engine.load_synthdefs(dir) {
+            Ok(()) => format!("Loaded synthdefs from {}", dir.display()),
+            Err(e) => format!("Error loading synthdefs from {}: {}", dir.display(), e),
         }
+    };
+    app_frame.show_toast(message);
+}
+
+/// Get the config directory for custom synthdefs
+fn config_synthdefs_dir() -> PathBuf {
+    if let Some(home) = std::env::var_os("HOME") {
+        PathBuf::from(home)
+            .join(".config")
+            .join("ilex")
+            .join("synthdefs")
+    } else {
+        PathBuf::from("synthdefs")
     }
+}
 
+/// Read a .scd file into a custom synthdef entry, inferring parameter ranges
+fn parse_custom_synthdef(path: &std::path::Path) -> Result<CustomSynthDef, String> {
+    let content = std::fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read .scd file: {}", e))?;
+    let parsed = scd_parser::parse_scd_file(&content)?;
+    let params: Vec<ParamSpec> = parsed
+        .params
+        .iter()
+        .map(|(name, default)| {
+            let (min, max) = scd_parser::infer_param_range(name, *default);
+            ParamSpec {
+                name: name.clone(),
+                default: *default,
+                min,
+                max,
+            }
+        })
+        .collect();
+
+    Ok(CustomSynthDef {
+        id: 0, // Will be set by registry.add()
+        name: parsed.name.clone(),
+        synthdef_name: parsed.name,
+        source_path: path.to_path_buf(),
+        params,
+    })
+}
+
+/// Register an imported synthdef, copy its source to `config_dir` and queue it
+/// for compiling there; the main loop reports the result and
+/// `forget_failed_synthdefs` drops it again if the compile fails.
+fn register_custom_synthdef(
+    custom: CustomSynthDef,
+    config_dir: &Path,
+    state: &mut AppState,
+    audio_engine: &mut AudioEngine,
+) -> Result<(), String> {
+    let _ = std::fs::create_dir_all(config_dir);
+    if let Some(filename) = custom.source_path.file_name() {
+        let _ = std::fs::copy(&custom.source_path, config_dir.join(filename));
+    }
+
+    audio_engine.compile_custom_synthdef_async(&custom.source_path, config_dir, &custom.synthdef_name)?;
+    state.session.custom_synthdefs.add(custom);
     Ok(())
 }
+
+/// Unregister imported synthdefs that failed to compile or load, so importing
+/// them again isn't skipped as a duplicate
+pub fn forget_failed_synthdefs(state: &mut AppState, audio_engine: &mut AudioEngine) {
+    for name in audio_engine.take_failed_custom_compiles() {
+        state.session.custom_synthdefs.remove_by_name(&name);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::panes::InstrumentPane;
+    use crate::state::SourceType;
+    use crate::ui::{InputEvent, KeyCode, Modifiers, Pane};
+
+    #[test]
+    fn test_only_sounding_actions_need_audio() {
+        assert!(needs_live_audio(&Action::Instrument(InstrumentAction::Audition(0))));
+        assert!(needs_live_audio(&Action::Server(ServerAction::RecordMaster)));
+        assert!(needs_live_audio(&Action::PianoRoll(PianoRollAction::PlayStop)));
+        // Editing, saving and offline renders work without a server
+        assert!(!needs_live_audio(&Action::PianoRoll(PianoRollAction::ToggleNote)));
+        assert!(!needs_live_audio(&Action::Session(SessionAction::Save)));
+        assert!(!needs_live_audio(&Action::Session(SessionAction::RenderClick)));
+        assert!(!needs_live_audio(&Action::Server(ServerAction::Start)));
+    }
+
+    #[test]
+    fn test_stop_closes_held_recorded_notes_at_the_playhead() {
+        let mut state = AppState::new();
+        let id = state.add_instrument(SourceType::Saw);
+        let mut panes = PaneManager::new(Box::new(PianoRollPane::default()));
+        let mut audio_engine = AudioEngine::new();
+        let mut app_frame = Frame::new();
+
+        let pr = &mut state.session.piano_roll;
+        pr.playing = true;
+        pr.playhead = 1440;
+        let midi = &mut state.session.midi_recording;
+        midi.arm();
+        midi.start_recording();
+        midi.capture(480, Some(id), MidiEvent::NoteOn { channel: 0, note: 60, velocity: 90 });
+
+        dispatch_piano_roll(&PianoRollAction::PlayStop, &mut state, &mut panes, &mut audio_engine, &mut app_frame, &mut Vec::new());
+
+        let pr = &state.session.piano_roll;
+        assert!(!pr.playing);
+        assert_eq!(pr.playhead, 0);
+        let notes = &pr.tracks[&id].notes;
+        assert_eq!(notes.iter().map(|n| (n.tick, n.duration, n.pitch)).collect::<Vec<_>>(), [(480, 960, 60)]);
+        assert!(!state.session.midi_recording.is_recording());
+    }
+
+    #[test]
+    fn test_keyboard_recording_lands_at_the_offset_record_tick() {
+        let mut state = AppState::new();
+        let id = state.add_instrument(SourceType::Saw);
+        state.record_offset_ms = 100.0;
+        let mut panes = PaneManager::new(Box::new(PianoRollPane::default()));
+        panes.get_pane_mut::<PianoRollPane>("piano_roll").unwrap().set_recording(true);
+        let mut audio_engine = AudioEngine::new();
+        let mut app_frame = Frame::new();
+        let pr = &mut state.session.piano_roll;
+        pr.playing = true;
+        pr.playhead = 960;
+        let tick = state.record_tick();
+        assert!(tick < 960);
+
+        dispatch_piano_roll(&PianoRollAction::PlayNote(60, 100), &mut state, &mut panes, &mut audio_engine, &mut app_frame, &mut Vec::new());
+        dispatch_piano_roll(&PianoRollAction::PlayNotes(vec![64, 67], 100), &mut state, &mut panes, &mut audio_engine, &mut app_frame, &mut Vec::new());
+        let mut notes: Vec<(u32, u8)> = state.session.piano_roll.tracks[&id].notes.iter().map(|n| (n.tick, n.pitch)).collect();
+        notes.sort();
+        assert_eq!(notes, [(tick, 60), (tick, 64), (tick, 67)]);
+    }
+
+    #[test]
+    fn test_step_back_removes_the_step_and_restores_what_it_replaced() {
+        let mut state = AppState::new();
+        state.add_instrument(SourceType::Saw);
+        let mut panes = PaneManager::new(Box::new(PianoRollPane::default()));
+        let mut audio_engine = AudioEngine::new();
+        let mut app_frame = Frame::new();
+        let event = InputEvent::new(KeyCode::Tab, Modifiers::none());
+        let pr_pane = panes.get_pane_mut::<PianoRollPane>("piano_roll").unwrap();
+        pr_pane.handle_action("toggle_step_record", &event, &state);
+        assert!(pr_pane.is_step_recording());
+        state.session.piano_roll.add_note(0, 60, 0, 120, 50);
+
+        step_record_notes(&[60, 64], &mut state, &mut panes);
+        step_record_notes(&[67], &mut state, &mut panes);
+        let notes = |state: &AppState| {
+            let mut notes: Vec<(u32, u8, u32, u8)> = state.session.piano_roll.track_at(0).unwrap().notes.iter()
+                .map(|n| (n.tick, n.pitch, n.duration, n.velocity))
+                .collect();
+            notes.sort();
+            notes
+        };
+        assert_eq!(notes(&state), [(0, 60, 480, 100), (0, 64, 480, 100), (480, 67, 480, 100)]);
+
+        let mut step_back = |state: &mut AppState, panes: &mut PaneManager| {
+            dispatch_piano_roll(&PianoRollAction::StepBack, state, panes, &mut audio_engine, &mut app_frame, &mut Vec::new());
+        };
+        step_back(&mut state, &mut panes);
+        assert_eq!(notes(&state), [(0, 60, 480, 100), (0, 64, 480, 100)]);
+        assert_eq!(panes.get_pane_mut::<PianoRollPane>("piano_roll").unwrap().cursor_tick(), 480);
+
+        // The first step wrote over an existing note, which comes back as it was
+        step_back(&mut state, &mut panes);
+        assert_eq!(notes(&state), [(0, 60, 120, 50)]);
+        assert_eq!(panes.get_pane_mut::<PianoRollPane>("piano_roll").unwrap().cursor_tick(), 0);
+
+        // Nothing left to step back over
+        step_back(&mut state, &mut panes);
+        assert_eq!(notes(&state), [(0, 60, 120, 50)]);
+    }
+
+    #[test]
+    fn test_midi_plays_the_piano_roll_track_only_while_it_is_in_front() {
+        let mut state = AppState::new();
+        let first = state.add_instrument(SourceType::Saw);
+        let second = state.add_instrument(SourceType::Sin);
+        state.instruments.selected = Some(0);
+        let mut panes = PaneManager::new(Box::new(InstrumentPane::default()));
+        panes.add_pane(Box::new(PianoRollPane::default()));
+        let mut audio_engine = AudioEngine::new();
+        let mut play = |note: u8, state: &mut AppState, panes: &mut PaneManager| {
+            let event = MidiEvent::NoteOn { channel: 0, note, velocity: 100 };
+            dispatch_midi_event(&event, state, panes, &mut audio_engine);
+            state.session.midi_recording.note_off_target(0, note)
+        };
+
+        // Elsewhere, unrouted channels follow the global selection
+        assert_eq!(play(60, &mut state, &mut panes), Some(first));
+
+        // The piano roll plays its own current track, whatever is selected globally
+        panes.switch_to("piano_roll", &state);
+        panes.get_pane_mut::<PianoRollPane>("piano_roll").unwrap().set_current_track(1);
+        assert_eq!(state.session.piano_roll.track_at(1).map(|t| t.module_id), Some(second));
+        assert_eq!(play(62, &mut state, &mut panes), Some(second));
+        assert_eq!(state.instruments.selected, Some(0));
+
+        panes.switch_to("instrument", &state);
+        assert_eq!(play(64, &mut state, &mut panes), Some(first));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_a_failed_import_can_be_imported_again() {
+        use std::os::unix::fs::PermissionsExt;
+        let dir = tempfile::tempdir().unwrap();
+        let sclang = dir.path().join("sclang");
+        std::fs::write(&sclang, "#!/bin/sh\necho 'ERROR: Parse error'\n").unwrap();
+        std::fs::set_permissions(&sclang, std::fs::Permissions::from_mode(0o755)).unwrap();
+        let scd = dir.path().join("broken.scd");
+        std::fs::write(&scd, "SynthDef(\\broken, { |freq = 440| Out.ar(0, SinOsc.ar(freq)) }").unwrap();
+
+        let mut state = AppState::new();
+        let mut audio_engine = AudioEngine::new();
+        audio_engine.set_sc_paths(None, Some(sclang));
+        let config_dir = dir.path().join("config");
+        let custom = parse_custom_synthdef(&scd).unwrap();
+        register_custom_synthdef(custom, &config_dir, &mut state, &mut audio_engine).unwrap();
+        assert!(state.session.custom_synthdefs.by_name("broken").is_some());
+
+        let deadline = Instant::now() + std::time::Duration::from_secs(10);
+        let result = loop {
+            if let Some(result) = audio_engine.poll_compile_result() {
+                break result;
+            }
+            assert!(Instant::now() < deadline, "compile never finished");
+            std::thread::sleep(std::time::Duration::from_millis(20));
+        };
+        assert!(result.is_err());
+        forget_failed_synthdefs(&mut state, &mut audio_engine);
+        assert!(state.session.custom_synthdefs.by_name("broken").is_none());
+
+        // Not a duplicate any more, so the next import registers it again
+        let custom = parse_custom_synthdef(&scd).unwrap();
+        register_custom_synthdef(custom, &config_dir, &mut state, &mut audio_engine).unwrap();
+        assert_eq!(state.session.custom_synthdefs.len(), 1);
+    }
+}
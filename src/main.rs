@@ -9,21 +9,27 @@ mod setup;
 mod state;
 mod ui;
 
-use std::time::{Duration, Instant};
+use std::time::Instant;
 
 use audio::AudioEngine;
-use panes::{AddPane, FileBrowserPane, FrameEditPane, HelpPane, HomePane, InstrumentEditPane, InstrumentPane, LogoPane, MixerPane, PianoRollPane, SampleChopperPane, SequencerPane, ServerPane, TrackPane, WaveformPane};
+use panes::{AddPane, FileBrowserPane, FrameEditPane, HelpPane, HomePane, InstrumentEditPane, InstrumentPane, LogoPane, MidiPane, MixerPane, PhraseBrowserPane, PianoRollPane, SampleChopperPane, SequencerPane, ServerPane, TrackPane, WaveformPane};
 use state::AppState;
+use state::midi_recording::ProgramChangeMode;
+use ui::macros::{MacroRecorder, MacroStep};
+use ui::widgets::TextInput;
 use ui::{
     Action, AppEvent, Frame, InputSource, KeyCode, Keymap, LayerResult, LayerStack,
-    PaneManager, RatatuiBackend, SessionAction, ToggleResult, ViewState, keybindings,
+    PaneManager, PollInterval, RatatuiBackend, SessionAction, ToggleResult, keybindings,
 };
 
 fn main() -> std::io::Result<()> {
+    let config = config::Config::load();
+    ui::set_color_depth(config.color_depth());
+    ui::set_theme(config.theme());
     let mut backend = RatatuiBackend::new()?;
-    backend.start()?;
+    backend.start(config.mouse_enabled())?;
 
-    let result = run(&mut backend);
+    let result = run(&mut backend, config);
 
     backend.stop()?;
     result
@@ -40,10 +46,15 @@ enum InstrumentSelectMode {
     WaitingSecondDigit(u8),
 }
 
-fn run(backend: &mut RatatuiBackend) -> std::io::Result<()> {
-    let config = config::Config::load();
+fn run(backend: &mut RatatuiBackend, config: config::Config) -> std::io::Result<()> {
     let mut state = AppState::new_with_defaults(config.defaults());
     state.keyboard_layout = config.keyboard_layout();
+    state.live_note_length = config.live_note_length();
+    state.live_note_hold = config.live_note_hold();
+    state.record_offset_ms = config.record_offset_ms();
+    state.param_defaults = config.param_defaults();
+    let phrase_error = state::phrase::PhraseLibrary::default_path()
+        .and_then(|path| state::phrase::PhraseLibrary::load(&path).map(|phrases| state.phrases = phrases).err());
 
     // Load keybindings from embedded TOML (with optional user override)
     let (layers, mut keymaps) = keybindings::load_keybindings();
@@ -58,7 +69,9 @@ fn run(backend: &mut RatatuiBackend) -> std::io::Result<()> {
     panes.add_pane(Box::new(ServerPane::new(pane_keymap(&mut keymaps, "server"))));
     panes.add_pane(Box::new(MixerPane::new(pane_keymap(&mut keymaps, "mixer"))));
     panes.add_pane(Box::new(HelpPane::new(pane_keymap(&mut keymaps, "help"))));
-    panes.add_pane(Box::new(PianoRollPane::new(pane_keymap(&mut keymaps, "piano_roll"))));
+    let mut piano_roll_pane = PianoRollPane::new(pane_keymap(&mut keymaps, "piano_roll"));
+    piano_roll_pane.set_ticks_per_beat(state.session.piano_roll.ticks_per_beat);
+    panes.add_pane(Box::new(piano_roll_pane));
     panes.add_pane(Box::new(SequencerPane::new(pane_keymap(&mut keymaps, "sequencer"))));
     panes.add_pane(Box::new(FrameEditPane::new(pane_keymap(&mut keymaps, "frame_edit"))));
     panes.add_pane(Box::new(SampleChopperPane::new(pane_keymap(&mut keymaps, "sample_chopper"), file_browser_km)));
@@ -66,6 +79,8 @@ fn run(backend: &mut RatatuiBackend) -> std::io::Result<()> {
     panes.add_pane(Box::new(LogoPane::new(pane_keymap(&mut keymaps, "logo"))));
     panes.add_pane(Box::new(TrackPane::new(pane_keymap(&mut keymaps, "track"))));
     panes.add_pane(Box::new(WaveformPane::new(pane_keymap(&mut keymaps, "waveform"))));
+    panes.add_pane(Box::new(MidiPane::new(pane_keymap(&mut keymaps, "midi"))));
+    panes.add_pane(Box::new(PhraseBrowserPane::new(pane_keymap(&mut keymaps, "phrase_browser"))));
 
     // Create layer stack
     let mut layer_stack = LayerStack::new(layers);
@@ -73,37 +88,76 @@ fn run(backend: &mut RatatuiBackend) -> std::io::Result<()> {
     layer_stack.set_pane_layer(panes.active().id());
 
     let mut audio_engine = AudioEngine::new();
+    audio_engine.set_resample_samples(config.resample_samples());
+    audio_engine.set_param_lag(config.param_smoothing());
+    audio_engine.set_stop_tails(config.stop_tails());
+    audio_engine.set_sc_paths(config.scsynth_path(), config.sclang_path());
+    let mut midi_input = midi::MidiInputManager::new();
+    let mut midi_output = midi::MidiOutputManager::new();
     let mut app_frame = Frame::new();
+    if let Some(e) = phrase_error {
+        app_frame.show_toast(format!("Phrases not loaded, library won't be saved: {}", e));
+    }
     let mut last_frame_time = Instant::now();
+    let mut cpu_warned = false;
     let mut active_notes: Vec<(u32, u8, u32)> = Vec::new();
     let mut select_mode = InstrumentSelectMode::Normal;
+    let mut macros = MacroRecorder::default_path()
+        .map(|path| MacroRecorder::load(&path))
+        .unwrap_or_default();
 
     setup::auto_start_sc(&mut audio_engine, &state, &mut panes);
 
     // Track last render area for mouse hit-testing
     let mut last_area = ratatui::layout::Rect::new(0, 0, 80, 24);
+    let mut poll = PollInterval::new();
 
     loop {
         // Sync layer stack in case dispatch switched panes last iteration
         layer_stack.set_pane_layer(panes.active().id());
 
-        if let Some(app_event) = backend.poll_event(Duration::from_millis(16)) {
+        // Keys held back by an unfinished or broken key sequence come first
+        let app_event = layer_stack
+            .next_replay(Instant::now())
+            .map(AppEvent::Key)
+            .or_else(|| backend.poll_event(poll.timeout()));
+        // Input is dropped while a project loads: the loaded state would replace any edit
+        let app_event = app_event.filter(|_| !state.project_io.is_loading());
+        let had_input = app_event.is_some();
+        if let Some(app_event) = app_event {
+            let mut ctx = GlobalContext {
+                state: &mut state,
+                panes: &mut panes,
+                audio_engine: &mut audio_engine,
+                midi_input: &mut midi_input,
+                midi_output: &mut midi_output,
+                app_frame: &mut app_frame,
+                active_notes: &mut active_notes,
+                select_mode: &mut select_mode,
+                layer_stack: &mut layer_stack,
+                macros: &mut macros,
+            };
             let pane_action = match app_event {
                 AppEvent::Mouse(mouse_event) => {
-                    panes.active_mut().handle_mouse(&mouse_event, last_area, &state)
+                    if ui::layout_helpers::is_too_small(last_area) {
+                        Action::None
+                    } else {
+                        let pane_area = ctx.panes.active_area(last_area);
+                        ctx.panes.active_mut().handle_mouse(&mouse_event, pane_area, ctx.state)
+                    }
                 }
                 AppEvent::Key(event) => {
                     // Two-digit instrument selection state machine (pre-layer)
-                    match &select_mode {
+                    match &*ctx.select_mode {
                         InstrumentSelectMode::WaitingFirstDigit => {
                             if let KeyCode::Char(c) = event.key {
                                 if let Some(d) = c.to_digit(10) {
-                                    select_mode = InstrumentSelectMode::WaitingSecondDigit(d as u8);
+                                    *ctx.select_mode = InstrumentSelectMode::WaitingSecondDigit(d as u8);
                                     continue;
                                 }
                             }
                             // Non-digit cancels
-                            select_mode = InstrumentSelectMode::Normal;
+                            *ctx.select_mode = InstrumentSelectMode::Normal;
                             // Fall through to normal handling
                         }
                         InstrumentSelectMode::WaitingSecondDigit(first) => {
@@ -112,92 +166,70 @@ fn run(backend: &mut RatatuiBackend) -> std::io::Result<()> {
                                 if let Some(d) = c.to_digit(10) {
                                     let combined = first * 10 + d as u8;
                                     let target = if combined == 0 { 10 } else { combined };
-                                    select_instrument(target as usize, &mut state, &mut panes);
-                                    select_mode = InstrumentSelectMode::Normal;
+                                    select_instrument(target as usize, ctx.state, ctx.panes);
+                                    *ctx.select_mode = InstrumentSelectMode::Normal;
+                                    ctx.macros.record(MacroStep::Global(format!("select:{}", target)));
                                     continue;
                                 }
                             }
                             // Non-digit cancels
-                            select_mode = InstrumentSelectMode::Normal;
+                            *ctx.select_mode = InstrumentSelectMode::Normal;
                             // Fall through to normal handling
                         }
                         InstrumentSelectMode::Normal => {}
                     }
 
                     // Layer resolution
-                    match layer_stack.resolve(&event) {
-                        LayerResult::Action(action) => {
-                            match handle_global_action(
-                                action,
-                                &mut state,
-                                &mut panes,
-                                &mut audio_engine,
-                                &mut app_frame,
-                                &mut active_notes,
-                                &mut select_mode,
-                                &mut layer_stack,
-                            ) {
-                                GlobalResult::Quit => break,
-                                GlobalResult::Handled => continue,
-                                GlobalResult::NotHandled => {
-                                    panes.active_mut().handle_action(action, &event, &state)
+                    match ctx.layer_stack.resolve(&event) {
+                        LayerResult::Action(action) => match handle_global_action(action, &mut ctx) {
+                            GlobalResult::Quit => break,
+                            GlobalResult::Handled => {
+                                if let Some(skipped) = ctx.macros.record(MacroStep::Global(action.to_string())) {
+                                    ctx.app_frame.show_toast(format!("Not recorded in macro: {}", skipped));
                                 }
+                                continue;
                             }
-                        }
-                        LayerResult::Blocked | LayerResult::Unresolved => {
-                            panes.active_mut().handle_raw_input(&event, &state)
-                        }
+                            GlobalResult::NotHandled => {
+                                ctx.panes.active_mut().handle_action(action, &event, ctx.state)
+                            }
+                        },
+                        LayerResult::Pending => continue,
+                        LayerResult::Blocked | LayerResult::Unresolved => match ctx.app_frame.macro_name.as_mut() {
+                            Some(input) => {
+                                input.handle_input(&event);
+                                Action::None
+                            }
+                            None => ctx.panes.active_mut().handle_raw_input(&event, ctx.state),
+                        },
                     }
                 }
             };
 
-            // Process layer management actions
-            match &pane_action {
-                Action::PushLayer(name) => {
-                    layer_stack.push(name);
-                }
-                Action::PopLayer(name) => {
-                    layer_stack.pop(name);
-                }
-                Action::ExitPerformanceMode => {
-                    layer_stack.pop("piano_mode");
-                    layer_stack.pop("pad_mode");
-                    panes.active_mut().deactivate_performance();
-                }
-                _ => {}
+            if let Some(skipped) = ctx.macros.record(MacroStep::Dispatch(pane_action.clone())) {
+                ctx.app_frame.show_toast(format!("Not recorded in macro: {}", skipped));
             }
-
-            // Auto-pop text_edit layer when pane is no longer editing
-            if layer_stack.has_layer("text_edit") {
-                let still_editing = match panes.active().id() {
-                    "instrument_edit" => {
-                        panes.get_pane_mut::<InstrumentEditPane>("instrument_edit")
-                            .map_or(false, |p| p.is_editing())
-                    }
-                    "frame_edit" => {
-                        panes.get_pane_mut::<FrameEditPane>("frame_edit")
-                            .map_or(false, |p| p.is_editing())
-                    }
-                    _ => false,
-                };
-                if !still_editing {
-                    layer_stack.pop("text_edit");
-                }
+            if apply_action(&pane_action, &mut ctx) {
+                break;
             }
+        }
 
-            // Process navigation
-            panes.process_nav(&pane_action, &state);
-
-            // Sync pane layer after navigation
-            if matches!(&pane_action, Action::Nav(_)) {
-                sync_pane_layer(&mut panes, &mut layer_stack);
+        // Drain incoming MIDI
+        let midi_events = midi_input.poll_events();
+        let had_input = had_input || !midi_events.is_empty();
+        for event in midi_events {
+            // Soft thru goes straight out; internal routing below is unaffected by it
+            if let Some(thru) = state.session.midi_recording.thru_event(&event) {
+                midi_output.send(&thru);
             }
-
-            if dispatch::dispatch_action(&pane_action, &mut state, &mut panes, &mut audio_engine, &mut app_frame, &mut active_notes) {
-                break;
+            if let midi::MidiEvent::ProgramChange { channel, program } = event {
+                handle_program_change(channel, program, &mut state, &mut panes);
             }
+            dispatch::dispatch_midi_event(&event, &mut state, &mut panes, &mut audio_engine);
         }
 
+        // Retrigger held notes and pads in note-repeat mode
+        dispatch::dispatch_note_repeat(&mut state, &mut panes, &mut audio_engine);
+
         // Poll for background compile completion
         if let Some(result) = audio_engine.poll_compile_result() {
             if let Some(server) = panes.get_pane_mut::<ServerPane>("server") {
@@ -207,15 +239,57 @@ fn run(backend: &mut RatatuiBackend) -> std::io::Result<()> {
                 }
             }
         }
+        dispatch::forget_failed_synthdefs(&mut state, &mut audio_engine);
+
+        // Poll for background save/load completion
+        if let Some(result) = state.project_io.poll() {
+            dispatch::finish_project_io(result, &mut state, &mut panes, &audio_engine, &mut app_frame);
+        }
+        app_frame.project_busy = state.project_io.busy().map(|job| job.label());
+        app_frame.macro_recording = macros.is_recording();
+
+        if let Some(report) = audio_engine.take_import_report() {
+            app_frame.show_toast(report.summary());
+        }
+
+        // Per-instrument voice counts for the instrument list and mixer
+        state.voice_counts = state.instruments.instruments.iter()
+            .map(|inst| (inst.id, audio_engine.voice_count(inst.id)))
+            .filter(|&(_, count)| count > 0)
+            .collect();
+
+        // Poll for background sample loads
+        for (buffer_id, result) in audio_engine.poll_sample_loads() {
+            let status = match result {
+                Ok(()) => state::SampleLoadStatus::Ready,
+                Err(e) => state::SampleLoadStatus::Failed(e),
+            };
+            state.sample_loads.insert(buffer_id, status);
+        }
 
         // Check scsynth process health
         if let Some(msg) = audio_engine.check_server_health() {
             if let Some(server) = panes.get_pane_mut::<ServerPane>("server") {
                 server.set_status(audio_engine.status(), &msg);
+                server.set_log_tail(audio_engine.server_log_tail(panes::SERVER_LOG_TAIL));
                 server.set_server_running(false);
             }
         }
 
+        // Server load: keep the server pane current and warn once per overload
+        audio_engine.poll_server_status();
+        let server_stats = audio_engine.server_stats();
+        if let Some(server) = panes.get_pane_mut::<ServerPane>("server") {
+            server.set_server_stats(server_stats);
+        }
+        let overloaded = server_stats.is_some_and(|s| s.is_overloaded());
+        if overloaded && !cpu_warned {
+            if let Some(stats) = server_stats {
+                app_frame.show_toast(format!("scsynth CPU high: {:.0}% peak", stats.peak_cpu));
+            }
+        }
+        cpu_warned = overloaded;
+
         // Piano roll playback tick
         {
             let now = Instant::now();
@@ -226,15 +300,13 @@ fn run(backend: &mut RatatuiBackend) -> std::io::Result<()> {
         }
 
         // Update master meter from real audio peak
-        {
-            let peak = if audio_engine.is_running() {
-                audio_engine.master_peak()
-            } else {
-                0.0
-            };
-            let mute = state.session.master_mute;
-            app_frame.set_master_peak(peak, mute);
-        }
+        let peak = if audio_engine.is_running() {
+            audio_engine.master_peak()
+        } else {
+            0.0
+        };
+        app_frame.set_master_peak(peak, state.session.master_mute);
+        app_frame.audio_warning = audio_engine.availability().warning();
 
         // Update recording state
         state.recording = audio_engine.is_recording();
@@ -247,7 +319,7 @@ fn run(backend: &mut RatatuiBackend) -> std::io::Result<()> {
         // Wait for scsynth to flush the WAV file before reading it
         if audio_engine.poll_pending_buffer_free() {
             if let Some(path) = state.pending_recording_path.take() {
-                let peaks = dispatch::compute_waveform_peaks(&path.to_string_lossy()).0;
+                let peaks = audio::peak_cache::compute_waveform_peaks(&path.to_string_lossy()).0;
                 if !peaks.is_empty() {
                     state.recorded_waveform = Some(peaks);
                     panes.switch_to("waveform", &state);
@@ -267,18 +339,141 @@ fn run(backend: &mut RatatuiBackend) -> std::io::Result<()> {
             state.recorded_waveform = None;
         }
 
+        // Back off the input wait only when nothing needs frequent frames
+        let busy = had_input
+            || midi_input.is_connected()
+            || state.session.piano_roll.playing
+            || state.instruments.instruments.iter().any(|i| i.drum_sequencer.as_ref().is_some_and(|s| s.playing))
+            || state.note_repeat.is_active()
+            || !active_notes.is_empty()
+            || peak > 0.0
+            || audio_engine.has_pending_work()
+            || state.project_io.busy().is_some()
+            || state.sample_loads.values().any(|s| *s == state::SampleLoadStatus::Loading)
+            || state.audio_in_waveform.is_some()
+            || !layer_stack.pending_display().is_empty();
+        poll.update(busy);
+
         // Render
         let mut frame = backend.begin_frame()?;
         let area = frame.area();
         last_area = area;
         app_frame.render_buf(area, frame.buffer_mut(), &state);
         panes.render(area, frame.buffer_mut(), &state);
+        panes.render_which_key(area, frame.buffer_mut(), &layer_stack.pending_display(), &layer_stack.pending_continuations());
         backend.end_frame(frame)?;
     }
 
+    // Let a save in progress finish rather than leave a half-written project
+    if let Some(state::project_io::ProjectIoResult::Failed(_, e)) = state.project_io.wait() {
+        eprintln!("Failed to save: {}", e);
+    }
+
     Ok(())
 }
 
+/// What global actions, pane actions and macro playback act on, borrowed from the main loop
+struct GlobalContext<'a> {
+    state: &'a mut AppState,
+    panes: &'a mut PaneManager,
+    audio_engine: &'a mut AudioEngine,
+    midi_input: &'a mut midi::MidiInputManager,
+    midi_output: &'a mut midi::MidiOutputManager,
+    app_frame: &'a mut Frame,
+    active_notes: &'a mut Vec<(u32, u8, u32)>,
+    select_mode: &'a mut InstrumentSelectMode,
+    layer_stack: &'a mut LayerStack,
+    macros: &'a mut MacroRecorder,
+}
+
+/// Apply an action a pane returned (or a macro replays): layer changes, then
+/// navigation, then dispatch. Returns true to quit.
+fn apply_action(action: &Action, ctx: &mut GlobalContext) -> bool {
+    let GlobalContext { state, panes, layer_stack, app_frame, .. } = ctx;
+
+    // Process layer management actions
+    match action {
+        Action::PushLayer(name) => {
+            layer_stack.push(name);
+        }
+        Action::PopLayer(name) => {
+            layer_stack.pop(name);
+        }
+        Action::ExitPerformanceMode => {
+            layer_stack.pop("piano_mode");
+            layer_stack.pop("pad_mode");
+            panes.active_mut().deactivate_performance();
+        }
+        _ => {}
+    }
+
+    // Auto-pop text_edit layer when neither the pane nor the macro name prompt is editing
+    if layer_stack.has_layer("text_edit") {
+        let still_editing = app_frame.macro_name.is_some() || match panes.active().id() {
+            "instrument_edit" => {
+                panes.get_pane_mut::<InstrumentEditPane>("instrument_edit")
+                    .map_or(false, |p| p.is_editing())
+            }
+            "frame_edit" => {
+                panes.get_pane_mut::<FrameEditPane>("frame_edit")
+                    .map_or(false, |p| p.is_editing())
+            }
+            "instrument" => {
+                panes.get_pane_mut::<InstrumentPane>("instrument")
+                    .is_some_and(|p| p.is_editing())
+            }
+            "piano_roll" => {
+                panes.get_pane_mut::<PianoRollPane>("piano_roll")
+                    .is_some_and(|p| p.is_editing())
+            }
+            "mixer" => {
+                panes.get_pane_mut::<MixerPane>("mixer")
+                    .is_some_and(|p| p.is_editing())
+            }
+            _ => false,
+        };
+        if !still_editing {
+            layer_stack.pop("text_edit");
+        }
+    }
+
+    // Process navigation
+    panes.process_nav(action, state);
+
+    // Sync pane layer after navigation
+    if matches!(action, Action::Nav(_)) {
+        sync_pane_layer(panes, layer_stack);
+    }
+
+    dispatch::dispatch_action(action, ctx.state, ctx.panes, ctx.audio_engine, ctx.midi_input, ctx.midi_output, ctx.app_frame, ctx.active_notes)
+}
+
+/// Dispatch a macro's steps again: the named one, or the newest
+fn play_macro(name: Option<&str>, ctx: &mut GlobalContext) {
+    if ctx.macros.is_recording() {
+        ctx.app_frame.show_toast("Stop recording before playing a macro");
+        return;
+    }
+    let Some(steps) = ctx.macros.steps(name).map(<[MacroStep]>::to_vec) else {
+        ctx.app_frame.show_toast(match name {
+            Some(name) => format!("No macro named \"{}\"", name),
+            None => "No macros recorded".to_string(),
+        });
+        return;
+    };
+    // Quit is never recorded, so a step can't end the session
+    for step in &steps {
+        match step {
+            MacroStep::Global(action) => {
+                handle_global_action(action, ctx);
+            }
+            MacroStep::Dispatch(action) => {
+                apply_action(action, ctx);
+            }
+        }
+    }
+}
+
 enum GlobalResult {
     Quit,
     Handled,
@@ -294,28 +489,62 @@ fn select_instrument(number: usize, state: &mut AppState, panes: &mut PaneManage
     }
 }
 
+/// Apply an incoming Program Change according to the configured mode.
+/// Out-of-range program numbers are ignored. The event itself is still passed
+/// on to `dispatch_midi_event`, so selecting here never swallows it.
+fn handle_program_change(channel: u8, program: u8, state: &mut AppState, panes: &mut PaneManager) {
+    let midi = &state.session.midi_recording;
+    if !midi.should_process_channel(channel) {
+        return;
+    }
+    match midi.program_change_mode {
+        ProgramChangeMode::Off => {}
+        ProgramChangeMode::SelectInstrument => {
+            // select_instrument is 1-based and ignores numbers past the end
+            select_instrument(program as usize + 1, state, panes);
+        }
+        ProgramChangeMode::SelectPattern => {
+            let selected = state.instruments.selected_instrument().map(|s| s.id);
+            if let Some(target) = midi.route_channel(channel, selected) {
+                if let Some(seq) = state.instruments.instrument_mut(target)
+                    .and_then(|inst| inst.drum_sequencer.as_mut())
+                {
+                    seq.select_pattern(program as usize);
+                }
+            }
+        }
+    }
+}
+
+/// Sync piano roll's current track and the mixer's instrument strip to the
+/// globally selected instrument, leaving the active pane as it is
+fn sync_tracks_to_selection(state: &mut AppState, panes: &mut PaneManager) {
+    let Some(selected_idx) = state.instruments.selected else { return };
+    let Some(inst_id) = state.instruments.instruments.get(selected_idx).map(|i| i.id) else { return };
+    // Find which track index corresponds to this instrument
+    if let Some(track_idx) = state.session.piano_roll.track_order.iter()
+        .position(|&id| id == inst_id)
+    {
+        if let Some(pr_pane) = panes.get_pane_mut::<PianoRollPane>("piano_roll") {
+            pr_pane.set_current_track(track_idx);
+        }
+    }
+
+    // Sync mixer selection
+    if panes.active().id() == "mixer" {
+        if let state::MixerSelection::Instrument(_) = state.session.mixer_selection {
+            state.session.mixer_selection = state::MixerSelection::Instrument(selected_idx);
+        }
+    }
+}
+
 /// Sync piano roll's current track to match the globally selected instrument,
 /// and re-route the active pane if on a F2-family pane (piano_roll/sequencer/waveform).
 fn sync_piano_roll_to_selection(state: &mut AppState, panes: &mut PaneManager) {
+    sync_tracks_to_selection(state, panes);
     if let Some(selected_idx) = state.instruments.selected {
         if let Some(inst) = state.instruments.instruments.get(selected_idx) {
-            let inst_id = inst.id;
-            // Find which track index corresponds to this instrument
-            if let Some(track_idx) = state.session.piano_roll.track_order.iter()
-                .position(|&id| id == inst_id)
-            {
-                if let Some(pr_pane) = panes.get_pane_mut::<PianoRollPane>("piano_roll") {
-                    pr_pane.set_current_track(track_idx);
-                }
-            }
-
-            // Sync mixer selection
             let active = panes.active().id();
-            if active == "mixer" {
-                if let state::MixerSelection::Instrument(_) = state.session.mixer_selection {
-                    state.session.mixer_selection = state::MixerSelection::Instrument(selected_idx);
-                }
-            }
 
             // Re-route if currently on a F2-family pane
             if active == "piano_roll" || active == "sequencer" || active == "waveform" {
@@ -347,60 +576,24 @@ fn sync_pane_layer(panes: &mut PaneManager, layer_stack: &mut LayerStack) {
     }
 }
 
-fn handle_global_action(
-    action: &str,
-    state: &mut AppState,
-    panes: &mut PaneManager,
-    audio_engine: &mut AudioEngine,
-    app_frame: &mut Frame,
-    active_notes: &mut Vec<(u32, u8, u32)>,
-    select_mode: &mut InstrumentSelectMode,
-    layer_stack: &mut LayerStack,
-) -> GlobalResult {
-    // Helper to capture current view state
-    let capture_view = |panes: &mut PaneManager, state: &AppState| -> ViewState {
-        let pane_id = panes.active().id().to_string();
-        let inst_selection = state.instruments.selected;
-        let edit_tab = panes.get_pane_mut::<InstrumentEditPane>("instrument_edit")
-            .map(|ep| ep.tab_index())
-            .unwrap_or(0);
-        ViewState { pane_id, inst_selection, edit_tab }
-    };
-
-    // Helper to restore view state
-    let restore_view = |panes: &mut PaneManager, state: &mut AppState, view: &ViewState| {
-        state.instruments.selected = view.inst_selection;
-        if let Some(edit_pane) = panes.get_pane_mut::<InstrumentEditPane>("instrument_edit") {
-            edit_pane.set_tab_index(view.edit_tab);
-        }
-        panes.switch_to(&view.pane_id, &*state);
-    };
+fn handle_global_action(action: &str, ctx: &mut GlobalContext) -> GlobalResult {
+    let GlobalContext {
+        state, panes, audio_engine, midi_input, midi_output, app_frame, active_notes, select_mode, layer_stack, macros,
+    } = ctx;
 
-    // Helper for pane switching with view history
-    let switch_to_pane = |target: &str, panes: &mut PaneManager, state: &mut AppState, app_frame: &mut Frame, layer_stack: &mut LayerStack| {
-        let current = capture_view(panes, state);
-        if app_frame.view_history.is_empty() {
-            app_frame.view_history.push(current);
-        } else {
-            app_frame.view_history[app_frame.history_cursor] = current;
-        }
-        // Truncate forward history
-        app_frame.view_history.truncate(app_frame.history_cursor + 1);
-        // Switch and record new view
+    // Helper for pane switching (PaneManager records navigation history)
+    let switch_to_pane = |target: &str, panes: &mut PaneManager, state: &mut AppState, layer_stack: &mut LayerStack| {
         panes.switch_to(target, &*state);
         sync_pane_layer(panes, layer_stack);
-        let new_view = capture_view(panes, state);
-        app_frame.view_history.push(new_view);
-        app_frame.history_cursor = app_frame.view_history.len() - 1;
     };
 
     match action {
         "quit" => return GlobalResult::Quit,
         "save" => {
-            dispatch::dispatch_action(&Action::Session(SessionAction::Save), state, panes, audio_engine, app_frame, active_notes);
+            dispatch::dispatch_action(&Action::Session(SessionAction::Save), state, panes, audio_engine, midi_input, midi_output, app_frame, active_notes);
         }
         "load" => {
-            dispatch::dispatch_action(&Action::Session(SessionAction::Load), state, panes, audio_engine, app_frame, active_notes);
+            dispatch::dispatch_action(&Action::Session(SessionAction::Load), state, panes, audio_engine, midi_input, midi_output, app_frame, active_notes);
         }
         "master_mute" => {
             state.session.master_mute = !state.session.master_mute;
@@ -409,10 +602,10 @@ fn handle_global_action(
             }
         }
         "record_master" => {
-            dispatch::dispatch_action(&Action::Server(ui::ServerAction::RecordMaster), state, panes, audio_engine, app_frame, active_notes);
+            dispatch::dispatch_action(&Action::Server(ui::ServerAction::RecordMaster), state, panes, audio_engine, midi_input, midi_output, app_frame, active_notes);
         }
         "switch:instrument" => {
-            switch_to_pane("instrument", panes, state, app_frame, layer_stack);
+            switch_to_pane("instrument", panes, state, layer_stack);
         }
         "switch:piano_roll_or_sequencer" => {
             let target = if let Some(inst) = state.instruments.selected_instrument() {
@@ -426,75 +619,56 @@ fn handle_global_action(
             } else {
                 "piano_roll"
             };
-            switch_to_pane(target, panes, state, app_frame, layer_stack);
+            switch_to_pane(target, panes, state, layer_stack);
         }
         "switch:track" => {
-            switch_to_pane("track", panes, state, app_frame, layer_stack);
+            switch_to_pane("track", panes, state, layer_stack);
         }
         "switch:mixer" => {
-            switch_to_pane("mixer", panes, state, app_frame, layer_stack);
+            switch_to_pane("mixer", panes, state, layer_stack);
         }
         "switch:server" => {
-            switch_to_pane("server", panes, state, app_frame, layer_stack);
+            switch_to_pane("server", panes, state, layer_stack);
         }
         "switch:logo" => {
-            switch_to_pane("logo", panes, state, app_frame, layer_stack);
+            switch_to_pane("logo", panes, state, layer_stack);
+        }
+        "switch:midi" => {
+            switch_to_pane("midi", panes, state, layer_stack);
         }
         "switch:frame_edit" => {
             if panes.active().id() == "frame_edit" {
-                panes.pop(&*state);
+                panes.pop(state);
             } else {
-                panes.push_to("frame_edit", &*state);
+                panes.push_to("frame_edit", state);
+            }
+        }
+        "cycle_theme" => {
+            ui::set_theme(ui::theme().next_builtin());
+        }
+        "toggle_split" => {
+            panes.toggle_split();
+        }
+        "split_focus" => {
+            if panes.focus_other_split(state) {
+                sync_pane_layer(panes, layer_stack);
             }
         }
+        "split_swap" => {
+            panes.swap_split();
+        }
         "nav_back" => {
-            let history = &mut app_frame.view_history;
-            if !history.is_empty() {
-                let current = capture_view(panes, state);
-                history[app_frame.history_cursor] = current;
-
-                let at_front = app_frame.history_cursor == history.len() - 1;
-                if at_front {
-                    if app_frame.history_cursor > 0 {
-                        app_frame.history_cursor -= 1;
-                        let view = history[app_frame.history_cursor].clone();
-                        restore_view(panes, state, &view);
-                        sync_pane_layer(panes, layer_stack);
-                    }
-                } else {
-                    if app_frame.history_cursor < history.len() - 1 {
-                        app_frame.history_cursor += 1;
-                        let view = history[app_frame.history_cursor].clone();
-                        restore_view(panes, state, &view);
-                        sync_pane_layer(panes, layer_stack);
-                    }
-                }
+            // History restores the pane it recorded, so the selection is synced without
+            // re-routing the pane
+            if panes.back(state) {
+                sync_tracks_to_selection(state, panes);
+                sync_pane_layer(panes, layer_stack);
             }
         }
         "nav_forward" => {
-            let history = &mut app_frame.view_history;
-            if !history.is_empty() {
-                let current = capture_view(panes, state);
-                history[app_frame.history_cursor] = current;
-
-                let at_front = app_frame.history_cursor == history.len() - 1;
-                if at_front {
-                    let target = app_frame.history_cursor.saturating_sub(2);
-                    if target != app_frame.history_cursor {
-                        app_frame.history_cursor = target;
-                        let view = history[app_frame.history_cursor].clone();
-                        restore_view(panes, state, &view);
-                        sync_pane_layer(panes, layer_stack);
-                    }
-                } else {
-                    let target = (app_frame.history_cursor + 2).min(history.len() - 1);
-                    if target != app_frame.history_cursor {
-                        app_frame.history_cursor = target;
-                        let view = history[app_frame.history_cursor].clone();
-                        restore_view(panes, state, &view);
-                        sync_pane_layer(panes, layer_stack);
-                    }
-                }
+            if panes.forward(state) {
+                sync_tracks_to_selection(state, panes);
+                sync_pane_layer(panes, layer_stack);
             }
         }
         "help" => {
@@ -511,12 +685,14 @@ fn handle_global_action(
                     "instrument_edit" => "Edit Instrument",
                     "track" => "Track",
                     "waveform" => "Waveform",
+                    "midi" => "MIDI Input",
+                    "phrase_browser" => "Phrases",
                     _ => current_id,
                 };
                 if let Some(help) = panes.get_pane_mut::<HelpPane>("help") {
                     help.set_context(current_id, title, &current_keymap);
                 }
-                panes.push_to("help", &*state);
+                panes.push_to("help", state);
             }
         }
         // Instrument selection by number (1-9 select instruments 1-9, 0 selects 10)
@@ -534,7 +710,7 @@ fn handle_global_action(
             sync_piano_roll_to_selection(state, panes);
         }
         "select_two_digit" => {
-            *select_mode = InstrumentSelectMode::WaitingFirstDigit;
+            **select_mode = InstrumentSelectMode::WaitingFirstDigit;
         }
         "toggle_piano_mode" => {
             let result = panes.active_mut().toggle_performance_mode(state);
@@ -552,6 +728,49 @@ fn handle_global_action(
                 ToggleResult::CycledLayout | ToggleResult::NotSupported => {}
             }
         }
+        "note_repeat" => {
+            state.note_repeat.toggle();
+        }
+        "note_repeat_rate" => {
+            state.note_repeat.cycle_rate();
+        }
+        "sustain" => {
+            state.sustain = !state.sustain;
+            audio_engine.set_sustain(state.sustain, &state.instruments);
+        }
+        "macro_record" => {
+            if !macros.is_recording() {
+                macros.start();
+            } else if let Some(count) = macros.stop() {
+                let mut input = TextInput::new(&format!("Name macro ({} steps):", count)).with_value(&macros.next_name());
+                input.set_focused(true);
+                app_frame.macro_name = Some(input);
+                layer_stack.push("text_edit");
+            } else {
+                app_frame.show_toast("Macro empty, nothing recorded");
+            }
+        }
+        "text:confirm" if app_frame.macro_name.is_some() => {
+            let name = app_frame.macro_name.take().map(|input| input.value().to_string()).unwrap_or_default();
+            layer_stack.pop("text_edit");
+            if let Some(recorded) = macros.name(&name) {
+                let message = format!("Recorded \"{}\" ({} steps)", recorded.name, recorded.steps.len());
+                match macros.save() {
+                    Ok(()) => app_frame.show_toast(message),
+                    Err(e) => app_frame.show_toast(format!("{}, not saved: {}", message, e)),
+                }
+            }
+        }
+        "text:cancel" if app_frame.macro_name.is_some() => {
+            app_frame.macro_name = None;
+            layer_stack.pop("text_edit");
+            macros.discard();
+            app_frame.show_toast("Macro discarded");
+        }
+        "macro_play" => play_macro(None, ctx),
+        _ if action.starts_with("macro:") => {
+            play_macro(action.strip_prefix("macro:"), ctx);
+        }
         "escape" => {
             // Global escape — falls through to pane when no mode layer handles it
             return GlobalResult::NotHandled;
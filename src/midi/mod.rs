@@ -1,10 +1,10 @@
 #![allow(dead_code)]
 
 use std::sync::mpsc::{self, Receiver, Sender};
-use midir::{MidiInput, MidiInputConnection};
+use midir::{MidiInput, MidiInputConnection, MidiOutput, MidiOutputConnection};
 
 /// MIDI event types
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum MidiEvent {
     NoteOn {
         channel: u8,
@@ -47,6 +47,32 @@ pub struct MidiPortInfo {
     pub name: String,
 }
 
+/// List the currently available MIDI input ports
+pub fn enumerate_ports() -> Vec<MidiPortInfo> {
+    let mut ports = Vec::new();
+    if let Ok(midi_in) = MidiInput::new("ilex-enum") {
+        for (index, port) in midi_in.ports().iter().enumerate() {
+            if let Ok(name) = midi_in.port_name(port) {
+                ports.push(MidiPortInfo { index, name });
+            }
+        }
+    }
+    ports
+}
+
+/// List the currently available MIDI output ports
+pub fn enumerate_output_ports() -> Vec<MidiPortInfo> {
+    let mut ports = Vec::new();
+    if let Ok(midi_out) = MidiOutput::new("ilex-enum") {
+        for (index, port) in midi_out.ports().iter().enumerate() {
+            if let Ok(name) = midi_out.port_name(port) {
+                ports.push(MidiPortInfo { index, name });
+            }
+        }
+    }
+    ports
+}
+
 /// MIDI input manager
 pub struct MidiInputManager {
     midi_in: Option<MidiInput>,
@@ -180,6 +206,99 @@ impl Drop for MidiInputManager {
     }
 }
 
+/// MIDI output manager: one connection to an external port
+pub struct MidiOutputManager {
+    connection: Option<MidiOutputConnection>,
+    connected_port_name: Option<String>,
+}
+
+impl MidiOutputManager {
+    pub fn new() -> Self {
+        Self {
+            connection: None,
+            connected_port_name: None,
+        }
+    }
+
+    /// Check if connected to a MIDI output port
+    pub fn is_connected(&self) -> bool {
+        self.connection.is_some()
+    }
+
+    /// Get the name of the connected port
+    pub fn connected_port_name(&self) -> Option<&str> {
+        self.connected_port_name.as_deref()
+    }
+
+    /// Connect to a MIDI output port by index
+    pub fn connect(&mut self, port_index: usize) -> Result<(), String> {
+        self.disconnect();
+
+        let midi_out = MidiOutput::new("ilex").map_err(|e| e.to_string())?;
+        let ports = midi_out.ports();
+        let port = ports
+            .get(port_index)
+            .ok_or_else(|| format!("Invalid port index: {}", port_index))?;
+        let port_name = midi_out.port_name(port).unwrap_or_else(|_| "Unknown".to_string());
+
+        let connection = midi_out.connect(port, "ilex-output").map_err(|e| e.to_string())?;
+        self.connection = Some(connection);
+        self.connected_port_name = Some(port_name);
+        Ok(())
+    }
+
+    /// Disconnect from the current MIDI output port
+    pub fn disconnect(&mut self) {
+        if let Some(conn) = self.connection.take() {
+            conn.close();
+        }
+        self.connected_port_name = None;
+    }
+
+    /// Send an event to the connected port; does nothing when disconnected
+    pub fn send(&mut self, event: &MidiEvent) {
+        if let Some(ref mut conn) = self.connection {
+            let _ = conn.send(&encode_midi_message(event));
+        }
+    }
+
+    /// All Notes Off on every channel, for when thru stops or moves while keys are held
+    pub fn all_notes_off(&mut self) {
+        for channel in 0..16 {
+            self.send(&MidiEvent::ControlChange { channel, controller: 123, value: 0 });
+        }
+    }
+}
+
+impl Default for MidiOutputManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Drop for MidiOutputManager {
+    fn drop(&mut self) {
+        self.disconnect();
+    }
+}
+
+/// Raw bytes for a MidiEvent, the inverse of `parse_midi_message`. Note-offs are
+/// sent as real 0x80 messages.
+pub fn encode_midi_message(event: &MidiEvent) -> Vec<u8> {
+    match *event {
+        MidiEvent::NoteOn { channel, note, velocity } => vec![0x90 | (channel & 0x0F), note & 0x7F, velocity & 0x7F],
+        MidiEvent::NoteOff { channel, note } => vec![0x80 | (channel & 0x0F), note & 0x7F, 0],
+        MidiEvent::PolyAftertouch { channel, note, pressure } => vec![0xA0 | (channel & 0x0F), note & 0x7F, pressure & 0x7F],
+        MidiEvent::ControlChange { channel, controller, value } => vec![0xB0 | (channel & 0x0F), controller & 0x7F, value & 0x7F],
+        MidiEvent::ProgramChange { channel, program } => vec![0xC0 | (channel & 0x0F), program & 0x7F],
+        MidiEvent::Aftertouch { channel, pressure } => vec![0xD0 | (channel & 0x0F), pressure & 0x7F],
+        MidiEvent::PitchBend { channel, value } => {
+            let raw = (value.clamp(-8192, 8191) + 8192) as u16;
+            vec![0xE0 | (channel & 0x0F), (raw & 0x7F) as u8, (raw >> 7) as u8]
+        }
+    }
+}
+
 /// Parse a raw MIDI message into a MidiEvent
 fn parse_midi_message(data: &[u8]) -> Option<MidiEvent> {
     if data.is_empty() {
@@ -368,4 +487,21 @@ mod tests {
             _ => panic!("Expected ControlChange"),
         }
     }
+
+    #[test]
+    fn test_encode_round_trips_through_parse() {
+        let events = [
+            MidiEvent::NoteOn { channel: 3, note: 64, velocity: 90 },
+            MidiEvent::ControlChange { channel: 15, controller: 74, value: 12 },
+            MidiEvent::PitchBend { channel: 1, value: -8192 },
+            MidiEvent::PitchBend { channel: 1, value: 8191 },
+            MidiEvent::ProgramChange { channel: 9, program: 5 },
+        ];
+        for event in events {
+            let bytes = encode_midi_message(&event);
+            assert_eq!(parse_midi_message(&bytes), Some(event));
+        }
+        let off = encode_midi_message(&MidiEvent::NoteOff { channel: 2, note: 60 });
+        assert_eq!(off, vec![0x82, 60, 0]);
+    }
 }
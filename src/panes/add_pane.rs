@@ -5,16 +5,19 @@ use ratatui::layout::Rect as RatatuiRect;
 use ratatui::text::{Line, Span};
 use ratatui::widgets::{Block, Borders, Paragraph, Widget};
 
+use crate::state::preset::factory_presets;
 use crate::state::{AppState, CustomSynthDefRegistry, SourceType};
-use crate::ui::layout_helpers::center_rect;
-use crate::ui::{Action, Color, FileSelectAction, InputEvent, InstrumentAction, Keymap, MouseEvent, MouseEventKind, MouseButton, NavAction, Pane, SessionAction, Style};
+use crate::ui::layout_helpers::fit_rect;
+use crate::ui::{Action, Color, FileSelectAction, InputEvent, InstrumentAction, Keymap, MouseEvent, MouseEventKind, MouseButton, NavAction, Pane, SessionAction, Style, theme};
 
 /// Options available in the Add Instrument menu
 #[derive(Debug, Clone)]
 pub enum AddOption {
     Source(SourceType),
+    FactoryPreset(usize),
     Separator(&'static str),
     ImportCustom,
+    ImportMidi,
 }
 
 pub struct AddPane {
@@ -42,9 +45,16 @@ impl AddPane {
             options.push(AddOption::Source(source));
         }
 
+        // Factory presets (read-only, built-in sources)
+        options.push(AddOption::Separator("── Factory Presets ──"));
+        for index in 0..factory_presets().len() {
+            options.push(AddOption::FactoryPreset(index));
+        }
+
         // Custom section
         options.push(AddOption::Separator("── Custom ──"));
         options.push(AddOption::ImportCustom);
+        options.push(AddOption::ImportMidi);
 
         options
     }
@@ -58,6 +68,12 @@ impl AddPane {
             options.push(AddOption::Source(source));
         }
 
+        // Factory presets (read-only, built-in sources)
+        options.push(AddOption::Separator("── Factory Presets ──"));
+        for index in 0..factory_presets().len() {
+            options.push(AddOption::FactoryPreset(index));
+        }
+
         // Custom section
         options.push(AddOption::Separator("── Custom ──"));
 
@@ -68,6 +84,7 @@ impl AddPane {
 
         // Import option
         options.push(AddOption::ImportCustom);
+        options.push(AddOption::ImportMidi);
 
         options
     }
@@ -117,7 +134,7 @@ impl AddPane {
 
     /// Render with registry for custom synthdef names (ratatui buffer path)
     fn render_buf_with_registry(&self, area: RatatuiRect, buf: &mut Buffer, registry: &CustomSynthDefRegistry) {
-        let rect = center_rect(area, 97, 29);
+        let rect = fit_rect(area, 97, 29);
 
         let block = Block::default()
             .borders(Borders::ALL)
@@ -137,7 +154,7 @@ impl AddPane {
         ))).render(RatatuiRect::new(content_x, content_y, inner.width.saturating_sub(2), 1), buf);
 
         let list_y = content_y + 2;
-        let sel_bg = ratatui::style::Style::from(Style::new().bg(Color::SELECTION_BG));
+        let sel_bg = ratatui::style::Style::from(Style::new().bg(theme().selection));
 
         for (i, option) in self.cached_options.iter().enumerate() {
             let y = list_y + i as u16;
@@ -150,7 +167,7 @@ impl AddPane {
                 AddOption::Separator(label) => {
                     Paragraph::new(Line::from(Span::styled(
                         *label,
-                        ratatui::style::Style::from(Style::new().fg(Color::DARK_GRAY)),
+                        ratatui::style::Style::from(Style::new().fg(theme().muted)),
                     ))).render(RatatuiRect::new(content_x, y, inner.width.saturating_sub(2), 1), buf);
                 }
                 AddOption::Source(source) => {
@@ -158,7 +175,7 @@ impl AddPane {
                     if is_selected {
                         if let Some(cell) = buf.cell_mut((content_x, y)) {
                             cell.set_char('>').set_style(
-                                ratatui::style::Style::from(Style::new().fg(Color::WHITE).bg(Color::SELECTION_BG).bold()),
+                                ratatui::style::Style::from(Style::new().fg(theme().foreground).bg(theme().selection).bold()),
                             );
                         }
                     }
@@ -175,14 +192,14 @@ impl AddPane {
                     let name = source.display_name(registry);
 
                     let short_style = if is_selected {
-                        ratatui::style::Style::from(Style::new().fg(color).bg(Color::SELECTION_BG))
+                        ratatui::style::Style::from(Style::new().fg(color).bg(theme().selection))
                     } else {
                         ratatui::style::Style::from(Style::new().fg(color))
                     };
                     let name_style = if is_selected {
-                        ratatui::style::Style::from(Style::new().fg(Color::WHITE).bg(Color::SELECTION_BG))
+                        ratatui::style::Style::from(Style::new().fg(theme().foreground).bg(theme().selection))
                     } else {
-                        ratatui::style::Style::from(Style::new().fg(Color::DARK_GRAY))
+                        ratatui::style::Style::from(Style::new().fg(theme().muted))
                     };
 
                     let line = Line::from(vec![
@@ -204,27 +221,74 @@ impl AddPane {
                         }
                     }
                 }
-                AddOption::ImportCustom => {
+                AddOption::FactoryPreset(index) => {
+                    let Some(preset) = factory_presets().get(*index) else {
+                        continue;
+                    };
+                    if is_selected {
+                        if let Some(cell) = buf.cell_mut((content_x, y)) {
+                            cell.set_char('>').set_style(
+                                ratatui::style::Style::from(Style::new().fg(theme().foreground).bg(theme().selection).bold()),
+                            );
+                        }
+                    }
+
+                    let (name_style, source_style) = if is_selected {
+                        (
+                            ratatui::style::Style::from(Style::new().fg(Color::GOLD).bg(theme().selection)),
+                            ratatui::style::Style::from(Style::new().fg(theme().foreground).bg(theme().selection)),
+                        )
+                    } else {
+                        (
+                            ratatui::style::Style::from(Style::new().fg(Color::GOLD)),
+                            ratatui::style::Style::from(Style::new().fg(theme().muted)),
+                        )
+                    };
+                    let source_name = preset.source_type().map(|s| s.name()).unwrap_or("");
+                    let line = Line::from(vec![
+                        Span::styled(format!("{:12}", preset.name), name_style),
+                        Span::styled(format!("  {}", source_name), source_style),
+                    ]);
+                    Paragraph::new(line).render(
+                        RatatuiRect::new(content_x + 2, y, inner.width.saturating_sub(4), 1), buf,
+                    );
+
+                    if is_selected {
+                        let fill_start = content_x + 2 + 14 + source_name.len() as u16;
+                        let fill_end = inner.x + inner.width;
+                        for x in fill_start..fill_end {
+                            if let Some(cell) = buf.cell_mut((x, y)) {
+                                cell.set_char(' ').set_style(sel_bg);
+                            }
+                        }
+                    }
+                }
+                AddOption::ImportCustom | AddOption::ImportMidi => {
+                    let label = if matches!(option, AddOption::ImportMidi) {
+                        "+ Import MIDI File..."
+                    } else {
+                        "+ Import Custom SynthDef..."
+                    };
                     if is_selected {
                         if let Some(cell) = buf.cell_mut((content_x, y)) {
                             cell.set_char('>').set_style(
-                                ratatui::style::Style::from(Style::new().fg(Color::WHITE).bg(Color::SELECTION_BG).bold()),
+                                ratatui::style::Style::from(Style::new().fg(theme().foreground).bg(theme().selection).bold()),
                             );
                         }
                     }
 
                     let text_style = if is_selected {
-                        ratatui::style::Style::from(Style::new().fg(Color::PURPLE).bg(Color::SELECTION_BG))
+                        ratatui::style::Style::from(Style::new().fg(Color::PURPLE).bg(theme().selection))
                     } else {
                         ratatui::style::Style::from(Style::new().fg(Color::PURPLE))
                     };
                     Paragraph::new(Line::from(Span::styled(
-                        "+ Import Custom SynthDef...",
+                        label,
                         text_style,
                     ))).render(RatatuiRect::new(content_x + 2, y, inner.width.saturating_sub(4), 1), buf);
 
                     if is_selected {
-                        let fill_start = content_x + 2 + 27;
+                        let fill_start = content_x + 2 + label.len() as u16;
                         let fill_end = inner.x + inner.width;
                         for x in fill_start..fill_end {
                             if let Some(cell) = buf.cell_mut((x, y)) {
@@ -239,10 +303,11 @@ impl AddPane {
         // Help text
         let help_y = rect.y + rect.height - 2;
         if help_y < area.y + area.height {
+            let help_width = inner.width.saturating_sub(2);
             Paragraph::new(Line::from(Span::styled(
-                "Enter: add | Escape: cancel | Up/Down: navigate",
-                ratatui::style::Style::from(Style::new().fg(Color::DARK_GRAY)),
-            ))).render(RatatuiRect::new(content_x, help_y, inner.width.saturating_sub(2), 1), buf);
+                self.keymap.footer_hint(help_width as usize),
+                ratatui::style::Style::from(Style::new().fg(theme().muted)),
+            ))).render(RatatuiRect::new(content_x, help_y, help_width, 1), buf);
         }
     }
 
@@ -265,9 +330,13 @@ impl Pane for AddPane {
                 if let Some(option) = self.cached_options.get(self.selected) {
                     match option {
                         AddOption::Source(source) => Action::Instrument(InstrumentAction::Add(*source)),
+                        AddOption::FactoryPreset(index) => Action::Instrument(InstrumentAction::AddFactoryPreset(*index)),
                         AddOption::ImportCustom => {
                             Action::Session(SessionAction::OpenFileBrowser(FileSelectAction::ImportCustomSynthDef))
                         }
+                        AddOption::ImportMidi => {
+                            Action::Session(SessionAction::OpenFileBrowser(FileSelectAction::ImportMidiFile))
+                        }
                         AddOption::Separator(_) => Action::None,
                     }
                 } else {
@@ -288,7 +357,7 @@ impl Pane for AddPane {
     }
 
     fn handle_mouse(&mut self, event: &MouseEvent, area: RatatuiRect, _state: &AppState) -> Action {
-        let rect = center_rect(area, 97, 29);
+        let rect = fit_rect(area, 97, 29);
         let inner_y = rect.y + 2;
         let content_y = inner_y + 1;
         let list_y = content_y + 2;
@@ -307,9 +376,15 @@ impl Pane for AddPane {
                         // Confirm selection
                         match &self.cached_options[idx] {
                             AddOption::Source(source) => return Action::Instrument(InstrumentAction::Add(*source)),
+                            AddOption::FactoryPreset(index) => {
+                                return Action::Instrument(InstrumentAction::AddFactoryPreset(*index));
+                            }
                             AddOption::ImportCustom => {
                                 return Action::Session(SessionAction::OpenFileBrowser(FileSelectAction::ImportCustomSynthDef));
                             }
+                            AddOption::ImportMidi => {
+                                return Action::Session(SessionAction::OpenFileBrowser(FileSelectAction::ImportMidiFile));
+                            }
                             AddOption::Separator(_) => {}
                         }
                     }
@@ -8,10 +8,10 @@ use ratatui::text::{Line, Span};
 use ratatui::widgets::{Block, Borders, Paragraph, Widget};
 
 use crate::state::AppState;
-use crate::ui::layout_helpers::center_rect;
+use crate::ui::layout_helpers::fit_rect;
 use crate::ui::{
     Action, ChopperAction, Color, FileSelectAction, InputEvent, InstrumentAction, Keymap, MouseEvent,
-    MouseEventKind, MouseButton, NavAction, Pane, SequencerAction, SessionAction, Style,
+    MouseEventKind, MouseButton, NavAction, Pane, SequencerAction, SessionAction, Style, theme,
 };
 
 struct DirEntry {
@@ -55,8 +55,14 @@ impl FileBrowserPane {
         self.on_select_action = action.clone();
         self.filter_extensions = match action {
             FileSelectAction::ImportCustomSynthDef => Some(vec!["scd".to_string()]),
-            FileSelectAction::LoadDrumSample(_) | FileSelectAction::LoadChopperSample | FileSelectAction::LoadPitchedSample(_) => {
-                Some(vec!["wav".to_string(), "aiff".to_string(), "aif".to_string()])
+            FileSelectAction::ImportMidiFile => Some(vec!["mid".to_string(), "midi".to_string()]),
+            FileSelectAction::LoadDrumSample(_)
+            | FileSelectAction::LoadPadLayer(_)
+            | FileSelectAction::LoadPadRoundRobin(_)
+            | FileSelectAction::LoadChopperSample
+            | FileSelectAction::LoadPitchedSample(_)
+            | FileSelectAction::LoadZoneLayer(_, _) => {
+                Some(["wav", "aiff", "aif", "flac", "mp3", "ogg"].iter().map(|e| e.to_string()).collect())
             }
         };
         self.current_dir = start_dir.unwrap_or_else(|| {
@@ -153,18 +159,41 @@ impl Pane for FileBrowserPane {
                             FileSelectAction::LoadDrumSample(pad_idx) => {
                                 Action::Sequencer(SequencerAction::LoadSampleResult(pad_idx, entry.path.clone()))
                             }
+                            FileSelectAction::LoadPadLayer(pad_idx) => {
+                                Action::Sequencer(SequencerAction::LoadLayerResult(pad_idx, entry.path.clone()))
+                            }
+                            FileSelectAction::LoadPadRoundRobin(pad_idx) => {
+                                Action::Sequencer(SequencerAction::LoadRoundRobinResult(pad_idx, entry.path.clone()))
+                            }
                             FileSelectAction::LoadChopperSample => {
                                 Action::Chopper(ChopperAction::LoadSampleResult(entry.path.clone()))
                             }
                             FileSelectAction::LoadPitchedSample(id) => {
                                 Action::Instrument(InstrumentAction::LoadSampleResult(id, entry.path.clone()))
                             }
+                            FileSelectAction::LoadZoneLayer(id, slice_id) => {
+                                Action::Instrument(InstrumentAction::LoadZoneLayerResult(id, slice_id, entry.path.clone()))
+                            }
+                            FileSelectAction::ImportMidiFile => {
+                                Action::Session(SessionAction::ImportMidiFile(entry.path.clone()))
+                            }
                         }
                     }
                 } else {
                     Action::None
                 }
             }
+            "import_folder" => {
+                if self.on_select_action != FileSelectAction::ImportCustomSynthDef {
+                    return Action::None;
+                }
+                // The highlighted folder, or the one being browsed
+                let dir = match self.entries.get(self.selected) {
+                    Some(entry) if entry.is_dir => entry.path.clone(),
+                    _ => self.current_dir.clone(),
+                };
+                Action::Session(SessionAction::ImportSynthDefFolder(dir))
+            }
             "cancel" => Action::Nav(NavAction::PopPane),
             "parent" => {
                 if let Some(parent) = self.current_dir.parent() {
@@ -215,12 +244,15 @@ impl Pane for FileBrowserPane {
     }
 
     fn render(&self, area: RatatuiRect, buf: &mut Buffer, _state: &AppState) {
-        let rect = center_rect(area, 97, 29);
+        let rect = fit_rect(area, 97, 29);
 
         let title = match self.on_select_action {
             FileSelectAction::ImportCustomSynthDef => " Import Custom SynthDef ",
             FileSelectAction::LoadDrumSample(_) | FileSelectAction::LoadChopperSample => " Load Sample ",
+            FileSelectAction::LoadPadLayer(_) | FileSelectAction::LoadZoneLayer(_, _) => " Load Velocity Layer ",
+            FileSelectAction::LoadPadRoundRobin(_) => " Load Round-Robin Sample ",
             FileSelectAction::LoadPitchedSample(_) => " Load Sample ",
+            FileSelectAction::ImportMidiFile => " Import MIDI File ",
         };
         let block = Block::default()
             .borders(Borders::ALL)
@@ -243,7 +275,7 @@ impl Pane for FileBrowserPane {
         };
         Paragraph::new(Line::from(Span::styled(
             display_path,
-            ratatui::style::Style::from(Style::new().fg(Color::CYAN).bold()),
+            ratatui::style::Style::from(Style::new().fg(theme().accent).bold()),
         ))).render(RatatuiRect::new(content_x, content_y, inner.width.saturating_sub(2), 1), buf);
 
         // File list
@@ -261,7 +293,7 @@ impl Pane for FileBrowserPane {
             eff_scroll = selected - visible_height + 1;
         }
 
-        let sel_bg = ratatui::style::Style::from(Style::new().bg(Color::SELECTION_BG));
+        let sel_bg = ratatui::style::Style::from(Style::new().bg(theme().selection));
 
         if entries.is_empty() {
             let ext_label = self
@@ -271,7 +303,7 @@ impl Pane for FileBrowserPane {
                 .unwrap_or_default();
             Paragraph::new(Line::from(Span::styled(
                 format!("(no .{} files found)", ext_label),
-                ratatui::style::Style::from(Style::new().fg(Color::DARK_GRAY)),
+                ratatui::style::Style::from(Style::new().fg(theme().muted)),
             ))).render(RatatuiRect::new(content_x, list_y, inner.width.saturating_sub(2), 1), buf);
         } else {
             for (i, entry) in entries.iter().skip(eff_scroll).take(visible_height).enumerate() {
@@ -290,19 +322,19 @@ impl Pane for FileBrowserPane {
                     }
                     if let Some(cell) = buf.cell_mut((content_x, y)) {
                         cell.set_char('>').set_style(
-                            ratatui::style::Style::from(Style::new().fg(Color::WHITE).bg(Color::SELECTION_BG).bold()),
+                            ratatui::style::Style::from(Style::new().fg(theme().foreground).bg(theme().selection).bold()),
                         );
                     }
                 }
 
                 let (icon, icon_color) = if entry.is_dir {
-                    ("/", Color::CYAN)
+                    ("/", theme().accent)
                 } else {
                     (" ", Color::CUSTOM_COLOR)
                 };
 
                 let icon_style = if is_selected {
-                    ratatui::style::Style::from(Style::new().fg(icon_color).bg(Color::SELECTION_BG))
+                    ratatui::style::Style::from(Style::new().fg(icon_color).bg(theme().selection))
                 } else {
                     ratatui::style::Style::from(Style::new().fg(icon_color))
                 };
@@ -314,9 +346,9 @@ impl Pane for FileBrowserPane {
                     entry.name.clone()
                 };
 
-                let name_color = if entry.is_dir { Color::CYAN } else { Color::WHITE };
+                let name_color = if entry.is_dir { theme().accent } else { theme().foreground };
                 let name_style = if is_selected {
-                    ratatui::style::Style::from(Style::new().fg(Color::WHITE).bg(Color::SELECTION_BG))
+                    ratatui::style::Style::from(Style::new().fg(theme().foreground).bg(theme().selection))
                 } else {
                     ratatui::style::Style::from(Style::new().fg(name_color))
                 };
@@ -331,7 +363,7 @@ impl Pane for FileBrowserPane {
             }
 
             // Scroll indicators
-            let scroll_style = ratatui::style::Style::from(Style::new().fg(Color::DARK_GRAY));
+            let scroll_style = ratatui::style::Style::from(Style::new().fg(theme().muted));
             if eff_scroll > 0 {
                 Paragraph::new(Line::from(Span::styled("...", scroll_style)))
                     .render(RatatuiRect::new(rect.x + rect.width - 5, list_y, 3, 1), buf);
@@ -345,15 +377,16 @@ impl Pane for FileBrowserPane {
         // Help text
         let help_y = rect.y + rect.height - 2;
         if help_y < area.y + area.height {
+            let help_width = inner.width.saturating_sub(2);
             Paragraph::new(Line::from(Span::styled(
-                "Enter: select | Backspace: parent | ~: home | &: hidden | Esc: cancel",
-                ratatui::style::Style::from(Style::new().fg(Color::DARK_GRAY)),
-            ))).render(RatatuiRect::new(content_x, help_y, inner.width.saturating_sub(2), 1), buf);
+                self.keymap.footer_hint(help_width as usize),
+                ratatui::style::Style::from(Style::new().fg(theme().muted)),
+            ))).render(RatatuiRect::new(content_x, help_y, help_width, 1), buf);
         }
     }
 
     fn handle_mouse(&mut self, event: &MouseEvent, area: RatatuiRect, _state: &AppState) -> Action {
-        let rect = center_rect(area, 97, 29);
+        let rect = fit_rect(area, 97, 29);
         let inner_y = rect.y + 2;
         let content_y = inner_y + 1;
         let list_y = content_y + 2;
@@ -394,6 +427,18 @@ impl Pane for FileBrowserPane {
                                             self.entries[clicked_idx].path.clone(),
                                         ));
                                     }
+                                    FileSelectAction::LoadPadLayer(pad_idx) => {
+                                        return Action::Sequencer(SequencerAction::LoadLayerResult(
+                                            pad_idx,
+                                            self.entries[clicked_idx].path.clone(),
+                                        ));
+                                    }
+                                    FileSelectAction::LoadPadRoundRobin(pad_idx) => {
+                                        return Action::Sequencer(SequencerAction::LoadRoundRobinResult(
+                                            pad_idx,
+                                            self.entries[clicked_idx].path.clone(),
+                                        ));
+                                    }
                                     FileSelectAction::LoadChopperSample => {
                                         return Action::Chopper(ChopperAction::LoadSampleResult(
                                             self.entries[clicked_idx].path.clone(),
@@ -405,6 +450,18 @@ impl Pane for FileBrowserPane {
                                             self.entries[clicked_idx].path.clone(),
                                         ));
                                     }
+                                    FileSelectAction::LoadZoneLayer(id, slice_id) => {
+                                        return Action::Instrument(InstrumentAction::LoadZoneLayerResult(
+                                            id,
+                                            slice_id,
+                                            self.entries[clicked_idx].path.clone(),
+                                        ));
+                                    }
+                                    FileSelectAction::ImportMidiFile => {
+                                        return Action::Session(SessionAction::ImportMidiFile(
+                                            self.entries[clicked_idx].path.clone(),
+                                        ));
+                                    }
                                 }
                             }
                         } else {
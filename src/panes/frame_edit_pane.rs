@@ -1,4 +1,5 @@
 use std::any::Any;
+use std::path::PathBuf;
 
 use ratatui::buffer::Buffer;
 use ratatui::layout::Rect as RatatuiRect;
@@ -6,9 +7,11 @@ use ratatui::text::{Line, Span};
 use ratatui::widgets::{Block, Borders, Paragraph, Widget};
 
 use crate::state::music::{Key, Scale};
+use crate::state::rng::{format_seed, parse_seed, time_seed};
 use crate::state::{AppState, MusicalSettings};
-use crate::ui::layout_helpers::center_rect;
-use crate::ui::{Action, Color, InputEvent, Keymap, NavAction, Pane, SessionAction, Style};
+use crate::state::session::{MAX_BPM, MAX_TRANSPOSE, MAX_TUNING_A4, MIN_BPM, MIN_TUNING_A4, TICKS_PER_BEAT_CHOICES};
+use crate::ui::layout_helpers::fit_rect;
+use crate::ui::{Action, InputEvent, Keymap, NavAction, Pane, SessionAction, Style, theme};
 use crate::ui::widgets::TextInput;
 
 /// Fields editable in the frame editor
@@ -20,13 +23,24 @@ enum Field {
     Key,
     Scale,
     Snap,
+    Transpose,
+    Resolution,
+    SynthDefs,
+    Seed,
 }
 
-const FIELDS: [Field; 6] = [Field::Bpm, Field::TimeSig, Field::Tuning, Field::Key, Field::Scale, Field::Snap];
+const FIELDS: [Field; 10] = [
+    Field::Bpm, Field::TimeSig, Field::Tuning, Field::Key, Field::Scale, Field::Snap, Field::Transpose, Field::Resolution,
+    Field::SynthDefs, Field::Seed,
+];
 
 pub struct FrameEditPane {
     keymap: Keymap,
     settings: MusicalSettings,
+    /// The project's synthdef directory as typed; empty for none
+    synthdef_dir: String,
+    /// The project's random seed
+    rng_seed: u64,
     selected: usize,
     editing: bool,
     edit_input: TextInput,
@@ -37,6 +51,8 @@ impl FrameEditPane {
         Self {
             keymap,
             settings: MusicalSettings::default(),
+            synthdef_dir: String::new(),
+            rng_seed: 0,
             selected: 0,
             editing: false,
             edit_input: TextInput::new(""),
@@ -86,20 +102,40 @@ impl FrameEditPane {
         };
     }
 
+    /// Step to the next or previous listed resolution; a custom value from the
+    /// config steps to its nearest neighbour
+    fn cycle_resolution(&mut self, forward: bool) {
+        let current = self.settings.ticks_per_beat;
+        let next = if forward {
+            TICKS_PER_BEAT_CHOICES.iter().find(|&&tpb| tpb > current)
+        } else {
+            TICKS_PER_BEAT_CHOICES.iter().rev().find(|&&tpb| tpb < current)
+        };
+        if let Some(&tpb) = next {
+            self.settings.ticks_per_beat = tpb;
+        }
+    }
+
     fn adjust(&mut self, increase: bool) {
         match self.current_field() {
             Field::Bpm => {
                 let delta: i16 = if increase { 1 } else { -1 };
-                self.settings.bpm = (self.settings.bpm as i16 + delta).clamp(20, 300) as u16;
+                self.settings.bpm = (self.settings.bpm as i16 + delta).clamp(MIN_BPM as i16, MAX_BPM as i16) as u16;
             }
             Field::TimeSig => self.cycle_time_sig(increase),
             Field::Tuning => {
                 let delta: f32 = if increase { 1.0 } else { -1.0 };
-                self.settings.tuning_a4 = (self.settings.tuning_a4 + delta).clamp(400.0, 480.0);
+                self.settings.tuning_a4 = (self.settings.tuning_a4 + delta).clamp(MIN_TUNING_A4, MAX_TUNING_A4);
             }
             Field::Key => self.cycle_key(increase),
             Field::Scale => self.cycle_scale(increase),
             Field::Snap => self.settings.snap = !self.settings.snap,
+            Field::Transpose => {
+                let delta: i8 = if increase { 1 } else { -1 };
+                self.settings.transpose = (self.settings.transpose + delta).clamp(-MAX_TRANSPOSE, MAX_TRANSPOSE);
+            }
+            Field::Resolution => self.cycle_resolution(increase),
+            Field::SynthDefs | Field::Seed => {}
         }
     }
 
@@ -111,6 +147,10 @@ impl FrameEditPane {
             Field::Key => "Key",
             Field::Scale => "Scale",
             Field::Snap => "Snap",
+            Field::Transpose => "Transpose",
+            Field::Resolution => "Resolution",
+            Field::SynthDefs => "Synthdefs",
+            Field::Seed => "Random seed",
         }
     }
 
@@ -122,6 +162,11 @@ impl FrameEditPane {
             Field::Key => self.settings.key.name().to_string(),
             Field::Scale => self.settings.scale.name().to_string(),
             Field::Snap => if self.settings.snap { "ON".into() } else { "OFF".into() },
+            Field::Transpose => format_transpose(self.settings.transpose),
+            Field::Resolution => format!("{} PPQ", self.settings.ticks_per_beat),
+            Field::SynthDefs if self.synthdef_dir.is_empty() => "(none)".into(),
+            Field::SynthDefs => self.synthdef_dir.clone(),
+            Field::Seed => format_seed(self.rng_seed),
         }
     }
 
@@ -130,6 +175,15 @@ impl FrameEditPane {
     }
 }
 
+/// Transpose in semitones, "0 (concert)" when off
+fn format_transpose(semitones: i8) -> String {
+    if semitones == 0 {
+        "0 (concert)".to_string()
+    } else {
+        format!("{:+} st", semitones)
+    }
+}
+
 impl Default for FrameEditPane {
     fn default() -> Self {
         Self::new(Keymap::new())
@@ -149,14 +203,37 @@ impl Pane for FrameEditPane {
                 match self.current_field() {
                     Field::Bpm => {
                         if let Ok(v) = text.parse::<u16>() {
-                            self.settings.bpm = v.clamp(20, 300);
+                            self.settings.bpm = v.clamp(MIN_BPM, MAX_BPM);
                         }
                     }
                     Field::Tuning => {
                         if let Ok(v) = text.parse::<f32>() {
-                            self.settings.tuning_a4 = v.clamp(400.0, 480.0);
+                            self.settings.tuning_a4 = v.clamp(MIN_TUNING_A4, MAX_TUNING_A4);
                         }
                     }
+                    Field::Transpose => {
+                        if let Ok(v) = text.trim().trim_start_matches('+').parse::<i8>() {
+                            self.settings.transpose = v.clamp(-MAX_TRANSPOSE, MAX_TRANSPOSE);
+                        }
+                    }
+                    Field::SynthDefs => {
+                        self.synthdef_dir = text.trim().to_string();
+                        self.editing = false;
+                        self.edit_input.set_focused(false);
+                        let dir = (!self.synthdef_dir.is_empty()).then(|| PathBuf::from(&self.synthdef_dir));
+                        return Action::Session(SessionAction::SetSynthDefDir(dir));
+                    }
+                    Field::Seed => {
+                        self.editing = false;
+                        self.edit_input.set_focused(false);
+                        return match parse_seed(&text) {
+                            Some(seed) => {
+                                self.rng_seed = seed;
+                                Action::Session(SessionAction::SetRngSeed(seed))
+                            }
+                            None => Action::None,
+                        };
+                    }
                     _ => {}
                 }
                 self.editing = false;
@@ -181,6 +258,11 @@ impl Pane for FrameEditPane {
                 }
                 Action::None
             }
+            // Either direction rerolls the seed
+            "decrease" | "increase" if self.current_field() == Field::Seed => {
+                self.rng_seed = time_seed();
+                Action::Session(SessionAction::SetRngSeed(self.rng_seed))
+            }
             "decrease" => {
                 self.adjust(false);
                 Action::Session(SessionAction::UpdateSessionLive(self.settings.clone()))
@@ -191,10 +273,13 @@ impl Pane for FrameEditPane {
             }
             "confirm" => {
                 let field = self.current_field();
-                if matches!(field, Field::Bpm | Field::Tuning) {
+                if matches!(field, Field::Bpm | Field::Tuning | Field::Transpose | Field::SynthDefs | Field::Seed) {
                     let val = match field {
                         Field::Bpm => format!("{}", self.settings.bpm),
                         Field::Tuning => format!("{:.1}", self.settings.tuning_a4),
+                        Field::Transpose => format!("{}", self.settings.transpose),
+                        Field::SynthDefs => self.synthdef_dir.clone(),
+                        Field::Seed => format_seed(self.rng_seed),
                         _ => unreachable!(),
                     };
                     self.edit_input.set_value(&val);
@@ -219,14 +304,14 @@ impl Pane for FrameEditPane {
         Action::None
     }
 
-    fn render(&self, area: RatatuiRect, buf: &mut Buffer, _state: &AppState) {
-        let rect = center_rect(area, 50, 13);
+    fn render(&self, area: RatatuiRect, buf: &mut Buffer, state: &AppState) {
+        let rect = fit_rect(area, 50, 14);
 
         let block = Block::default()
             .borders(Borders::ALL)
             .title(" Session ")
-            .border_style(ratatui::style::Style::from(Style::new().fg(Color::CYAN)))
-            .title_style(ratatui::style::Style::from(Style::new().fg(Color::CYAN)));
+            .border_style(ratatui::style::Style::from(Style::new().fg(theme().accent)))
+            .title_style(ratatui::style::Style::from(Style::new().fg(theme().accent)));
         let inner = block.inner(rect);
         block.render(rect, buf);
 
@@ -239,11 +324,11 @@ impl Pane for FrameEditPane {
                 break;
             }
             let is_selected = i == self.selected;
-            let sel_bg = ratatui::style::Style::from(Style::new().bg(Color::SELECTION_BG));
+            let sel_bg = ratatui::style::Style::from(Style::new().bg(theme().selection));
 
             // Indicator
             if is_selected {
-                let ind_style = ratatui::style::Style::from(Style::new().fg(Color::WHITE).bg(Color::SELECTION_BG).bold());
+                let ind_style = ratatui::style::Style::from(Style::new().fg(theme().foreground).bg(theme().selection).bold());
                 if let Some(cell) = buf.cell_mut((label_col, y)) {
                     cell.set_char('>').set_style(ind_style);
                 }
@@ -251,9 +336,9 @@ impl Pane for FrameEditPane {
 
             // Label
             let label_style = if is_selected {
-                ratatui::style::Style::from(Style::new().fg(Color::CYAN).bg(Color::SELECTION_BG))
+                ratatui::style::Style::from(Style::new().fg(theme().accent).bg(theme().selection))
             } else {
-                ratatui::style::Style::from(Style::new().fg(Color::CYAN))
+                ratatui::style::Style::from(Style::new().fg(theme().accent))
             };
             let label = format!("{:14}", Self::field_label(*field));
             Paragraph::new(Line::from(Span::styled(label, label_style)))
@@ -265,9 +350,9 @@ impl Pane for FrameEditPane {
                 self.edit_input.render_buf(buf, value_col, y, inner.width.saturating_sub(18));
             } else {
                 let val_style = if is_selected {
-                    ratatui::style::Style::from(Style::new().fg(Color::WHITE).bg(Color::SELECTION_BG))
+                    ratatui::style::Style::from(Style::new().fg(theme().foreground).bg(theme().selection))
                 } else {
-                    ratatui::style::Style::from(Style::new().fg(Color::WHITE))
+                    ratatui::style::Style::from(Style::new().fg(theme().foreground))
                 };
                 let val = self.field_value(*field);
                 Paragraph::new(Line::from(Span::styled(&val, val_style)))
@@ -289,14 +374,22 @@ impl Pane for FrameEditPane {
         // Help
         let help_y = rect.y + rect.height - 2;
         if help_y < area.y + area.height {
+            let pending_resolution = self.current_field() == Field::Resolution
+                && self.settings.ticks_per_beat != state.session.piano_roll.ticks_per_beat;
             let help = if self.editing {
-                "Enter: confirm | Esc: cancel"
+                "Enter: confirm | Esc: cancel".to_string()
+            } else if pending_resolution {
+                format!("Enter: rescale timeline to {} PPQ | Esc: cancel", self.settings.ticks_per_beat)
+            } else if self.current_field() == Field::SynthDefs {
+                "Enter: type folder (empty for none) | Esc: cancel".to_string()
+            } else if self.current_field() == Field::Seed {
+                "Left/Right: reroll | Enter: type seed (decimal or 0x hex)".to_string()
             } else {
-                "Left/Right: adjust | Enter: type/confirm | Esc: cancel"
+                "Left/Right: adjust | Enter: type/confirm | Esc: cancel".to_string()
             };
             Paragraph::new(Line::from(Span::styled(
                 help,
-                ratatui::style::Style::from(Style::new().fg(Color::DARK_GRAY)),
+                ratatui::style::Style::from(Style::new().fg(theme().muted)),
             ))).render(RatatuiRect::new(inner.x + 2, help_y, inner.width.saturating_sub(2), 1), buf);
         }
     }
@@ -307,6 +400,8 @@ impl Pane for FrameEditPane {
 
     fn on_enter(&mut self, state: &AppState) {
         self.set_settings(state.session.musical_settings());
+        self.synthdef_dir = state.session.synthdef_dir.as_ref().map(|d| d.display().to_string()).unwrap_or_default();
+        self.rng_seed = state.session.rng_seed;
     }
 
 
@@ -314,3 +409,34 @@ impl Pane for FrameEditPane {
         self
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_seed_rerolls_and_takes_typed_values() {
+        let mut state = AppState::new();
+        state.session.rng_seed = 99;
+        let mut pane = FrameEditPane::new(Keymap::new());
+        pane.on_enter(&state);
+        pane.selected = FIELDS.iter().position(|f| *f == Field::Seed).unwrap();
+        let key = InputEvent::key(crate::ui::KeyCode::Enter);
+        assert_eq!(pane.field_value(Field::Seed), "0x0000000000000063");
+
+        let Action::Session(SessionAction::SetRngSeed(rerolled)) = pane.handle_action("increase", &key, &state) else {
+            panic!("expected a reroll");
+        };
+        assert_eq!(pane.rng_seed, rerolled);
+
+        assert_eq!(pane.handle_action("confirm", &key, &state), Action::PushLayer("text_edit"));
+        pane.edit_input.set_value("0x2a");
+        assert_eq!(pane.handle_action("text:confirm", &key, &state), Action::Session(SessionAction::SetRngSeed(42)));
+        // Text that isn't a seed leaves it alone
+        pane.handle_action("confirm", &key, &state);
+        pane.edit_input.set_value("soon");
+        assert_eq!(pane.handle_action("text:confirm", &key, &state), Action::None);
+        assert_eq!(pane.rng_seed, 42);
+        assert!(!pane.is_editing());
+    }
+}
@@ -6,8 +6,8 @@ use ratatui::text::{Line, Span};
 use ratatui::widgets::{Block, Borders, Paragraph, Widget};
 
 use crate::state::AppState;
-use crate::ui::layout_helpers::center_rect;
-use crate::ui::{Action, Color, InputEvent, Keymap, MouseEvent, MouseEventKind, MouseButton, NavAction, Pane, Style};
+use crate::ui::layout_helpers::fit_rect;
+use crate::ui::{Action, Color, InputEvent, Keymap, MouseEvent, MouseEventKind, MouseButton, NavAction, Pane, Style, theme};
 
 pub struct HelpPane {
     keymap: Keymap,
@@ -84,7 +84,7 @@ impl Pane for HelpPane {
     }
 
     fn render(&self, area: RatatuiRect, buf: &mut Buffer, _state: &AppState) {
-        let rect = center_rect(area, 60, 20);
+        let rect = fit_rect(area, 60, 20);
         let title = format!(" Help: {} ", self.title);
 
         let block = Block::default()
@@ -99,8 +99,8 @@ impl Pane for HelpPane {
         let max_scroll = self.display_keymap.len().saturating_sub(visible_lines);
         let scroll = self.scroll.min(max_scroll);
 
-        let key_style = ratatui::style::Style::from(Style::new().fg(Color::CYAN).bold());
-        let desc_style = ratatui::style::Style::from(Style::new().fg(Color::WHITE));
+        let key_style = ratatui::style::Style::from(Style::new().fg(theme().accent).bold());
+        let desc_style = ratatui::style::Style::from(Style::new().fg(theme().foreground));
 
         for (i, (key, desc)) in self.display_keymap.iter().skip(scroll).take(visible_lines).enumerate() {
             let y = inner.y + 1 + i as u16;
@@ -132,7 +132,7 @@ impl Pane for HelpPane {
                 let ind_area = RatatuiRect::new(inner.x + 1, indicator_y, inner.width.saturating_sub(1), 1);
                 Paragraph::new(Line::from(Span::styled(
                     indicator,
-                    ratatui::style::Style::from(Style::new().fg(Color::DARK_GRAY)),
+                    ratatui::style::Style::from(Style::new().fg(theme().muted)),
                 ))).render(ind_area, buf);
             }
         }
@@ -142,8 +142,8 @@ impl Pane for HelpPane {
         if help_y < area.y + area.height {
             let help_area = RatatuiRect::new(inner.x + 1, help_y, inner.width.saturating_sub(1), 1);
             Paragraph::new(Line::from(Span::styled(
-                "[ESC/F1] Close  [Up/Down] Scroll",
-                ratatui::style::Style::from(Style::new().fg(Color::DARK_GRAY)),
+                self.keymap.footer_hint(help_area.width as usize),
+                ratatui::style::Style::from(Style::new().fg(theme().muted)),
             ))).render(help_area, buf);
         }
     }
@@ -6,8 +6,8 @@ use ratatui::text::{Line, Span};
 use ratatui::widgets::{Block, Borders, Paragraph, Widget};
 
 use crate::state::AppState;
-use crate::ui::layout_helpers::center_rect;
-use crate::ui::{Action, Color, InputEvent, Keymap, MouseEvent, MouseEventKind, MouseButton, NavAction, Pane, Style};
+use crate::ui::layout_helpers::fit_rect;
+use crate::ui::{Action, Color, InputEvent, Keymap, MouseEvent, MouseEventKind, MouseButton, NavAction, Pane, Style, theme};
 
 /// Menu item for the home screen
 struct MenuItem {
@@ -82,7 +82,7 @@ impl Pane for HomePane {
     }
 
     fn render(&self, area: RatatuiRect, buf: &mut Buffer, _state: &AppState) {
-        let rect = center_rect(area, 50, 12);
+        let rect = fit_rect(area, 50, 12);
 
         let block = Block::default()
             .borders(Borders::ALL)
@@ -92,19 +92,19 @@ impl Pane for HomePane {
         let inner = block.inner(rect);
         block.render(rect, buf);
 
-        let item_colors = [Color::CYAN, Color::PURPLE, Color::GOLD];
+        let item_colors = [theme().accent, Color::PURPLE, Color::GOLD];
 
         for (i, item) in self.items.iter().enumerate() {
             let y = inner.y + 1 + (i as u16 * 2);
             let is_selected = i == self.selected;
-            let item_color = item_colors.get(i).copied().unwrap_or(Color::WHITE);
+            let item_color = item_colors.get(i).copied().unwrap_or(theme().foreground);
 
             let label_text = format!(" [{}] {} ", i + 1, item.label);
 
             let label_line = if is_selected {
                 Line::from(Span::styled(
                     label_text,
-                    ratatui::style::Style::from(Style::new().fg(Color::WHITE).bg(Color::SELECTION_BG).bold()),
+                    ratatui::style::Style::from(Style::new().fg(theme().foreground).bg(theme().selection).bold()),
                 ))
             } else {
                 Line::from(Span::styled(
@@ -116,7 +116,7 @@ impl Pane for HomePane {
             let desc_style = if is_selected {
                 ratatui::style::Style::from(Style::new().fg(Color::SKY_BLUE))
             } else {
-                ratatui::style::Style::from(Style::new().fg(Color::DARK_GRAY))
+                ratatui::style::Style::from(Style::new().fg(theme().muted))
             };
             let desc_line = Line::from(Span::styled(format!("  {}", item.description), desc_style));
 
@@ -135,15 +135,15 @@ impl Pane for HomePane {
         if help_y < area.y + area.height {
             let help_area = RatatuiRect::new(inner.x + 2, help_y, inner.width.saturating_sub(2), 1);
             let help = Paragraph::new(Line::from(Span::styled(
-                "[1-3] Jump  [Enter] Select  [q] Quit",
-                ratatui::style::Style::from(Style::new().fg(Color::DARK_GRAY)),
+                self.keymap.footer_hint(help_area.width as usize),
+                ratatui::style::Style::from(Style::new().fg(theme().muted)),
             )));
             help.render(help_area, buf);
         }
     }
 
     fn handle_mouse(&mut self, event: &MouseEvent, area: RatatuiRect, _state: &AppState) -> Action {
-        let rect = center_rect(area, 50, 12);
+        let rect = fit_rect(area, 50, 12);
         let inner_x = rect.x + 1;
         let inner_y = rect.y + 1;
 
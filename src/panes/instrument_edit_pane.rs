@@ -5,22 +5,31 @@ use ratatui::layout::Rect as RatatuiRect;
 use ratatui::text::{Line, Span};
 use ratatui::widgets::{Block, Borders, Paragraph, Widget};
 
+use crate::state::instrument::{AudioInputMode, VoiceSpreadMode, GLIDE_RANGE, MAX_DETUNE_CENTS, MAX_UNISON_VOICES, TRIM_RANGE_DB};
+use crate::state::modulation::{
+    available_sources, remove_source, ModRouteSource, ModRouting, MAX_LFOS, MAX_MOD_ENVELOPES, MOD_AMOUNT_RANGE,
+};
+use crate::state::music::pitch_name;
+use crate::state::sampler::{parse_note, parse_zone, SamplerConfig};
 use crate::state::{
-    AppState, EffectSlot, EffectType, EnvConfig, FilterConfig, FilterType, LfoConfig,
+    AppState, EffectSlot, EffectType, EnvConfig, FilterConfig, FilterType, LfoConfig, LfoTarget, KEYTRACK_RANGE,
     SourceType, Param, ParamValue, InstrumentId, Instrument,
 };
-use crate::ui::layout_helpers::center_rect;
-use crate::ui::widgets::TextInput;
-use crate::ui::{Action, Color, FileSelectAction, InputEvent, KeyCode, Keymap, MouseEvent, MouseEventKind, Pane, PianoKeyboard, InstrumentAction, SessionAction, Style, ToggleResult, translate_key};
+use crate::ui::layout_helpers::fit_rect;
+use crate::ui::widgets::{parse_number, TextInput};
+use crate::ui::{Action, Color, FileSelectAction, InputEvent, KeyCode, Keymap, MouseEvent, MouseEventKind, Pane, PianoKeyboard, InstrumentAction, SessionAction, Style, theme, ToggleResult, translate_key};
 
 /// Which section a row belongs to
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum Section {
     Source,
+    Zones,
     Filter,
     Effects,
     Lfo,
     Envelope,
+    Mod,
+    Output,
 }
 
 pub struct InstrumentEditPane {
@@ -31,13 +40,36 @@ pub struct InstrumentEditPane {
     source_params: Vec<Param>,
     filter: Option<FilterConfig>,
     effects: Vec<EffectSlot>,
-    lfo: LfoConfig,
+    lfos: Vec<LfoConfig>,
+    /// LFO shown in the LFO section
+    lfo_index: usize,
     amp_envelope: EnvConfig,
+    mod_envelopes: Vec<EnvConfig>,
+    /// Envelope shown in the envelope section: 0 is the amp envelope, then mod envelopes
+    env_index: usize,
+    mod_routings: Vec<ModRouting>,
     polyphonic: bool,
     active: bool,
+    /// Pre-fader trim in dB
+    trim: f32,
+    /// Mono portamento time in seconds
+    glide: f32,
+    unison_voices: u8,
+    /// Unison spread in cents
+    detune: f32,
+    /// Stereo spread of polyphonic voices (0-1)
+    voice_spread: f32,
+    voice_spread_mode: VoiceSpreadMode,
+    /// Live notes the instrument responds to, ends included
+    key_low: u8,
+    key_high: u8,
+    /// Keyboard zones (sampler instruments only)
+    sampler: Option<SamplerConfig>,
     selected_row: usize,
     editing: bool,
     edit_input: TextInput,
+    /// Why the last typed entry was rejected, shown until it's edited or cancelled
+    edit_error: Option<String>,
     piano: PianoKeyboard,
 }
 
@@ -51,13 +83,27 @@ impl InstrumentEditPane {
             source_params: Vec::new(),
             filter: None,
             effects: Vec::new(),
-            lfo: LfoConfig::default(),
+            lfos: vec![LfoConfig::default()],
+            lfo_index: 0,
             amp_envelope: EnvConfig::default(),
+            mod_envelopes: Vec::new(),
+            env_index: 0,
+            mod_routings: Vec::new(),
             polyphonic: true,
             active: true,
+            trim: 0.0,
+            glide: 0.0,
+            unison_voices: 1,
+            detune: 0.0,
+            voice_spread: 0.0,
+            voice_spread_mode: VoiceSpreadMode::default(),
+            key_low: 0,
+            key_high: 127,
+            sampler: None,
             selected_row: 0,
             editing: false,
             edit_input: TextInput::new(""),
+            edit_error: None,
             piano: PianoKeyboard::new(),
         }
     }
@@ -69,47 +115,38 @@ impl InstrumentEditPane {
         self.source_params = instrument.source_params.clone();
         self.filter = instrument.filter.clone();
         self.effects = instrument.effects.clone();
-        self.lfo = instrument.lfo.clone();
+        self.lfos = instrument.lfos.clone();
+        if self.lfos.is_empty() {
+            self.lfos.push(LfoConfig::default());
+        }
+        self.lfo_index = self.lfo_index.min(self.lfos.len() - 1);
         self.amp_envelope = instrument.amp_envelope.clone();
+        self.mod_envelopes = instrument.mod_envelopes.clone();
+        self.env_index = self.env_index.min(self.mod_envelopes.len());
+        self.mod_routings = instrument.mod_routings.clone();
         self.polyphonic = instrument.polyphonic;
         self.active = instrument.active;
+        self.trim = instrument.trim;
+        self.glide = instrument.glide;
+        self.unison_voices = instrument.unison_voices;
+        self.detune = instrument.detune;
+        self.voice_spread = instrument.voice_spread;
+        self.voice_spread_mode = instrument.voice_spread_mode;
+        self.key_low = instrument.key_low;
+        self.key_high = instrument.key_high;
+        self.sampler = instrument.sampler_config.clone();
         self.selected_row = 0;
     }
 
-    #[allow(dead_code)]
-    pub fn instrument_id(&self) -> Option<InstrumentId> {
-        self.instrument_id
-    }
-
-    /// Get current tab as index (for view state - now section based)
-    pub fn tab_index(&self) -> u8 {
-        match self.current_section() {
-            Section::Source => 0,
-            Section::Filter => 1,
-            Section::Effects => 2,
-            Section::Lfo => 3,
-            Section::Envelope => 4,
-        }
+    /// Reload values changed outside the editor, keeping the cursor where it is
+    pub fn refresh_instrument(&mut self, instrument: &Instrument) {
+        let row = self.selected_row;
+        self.set_instrument(instrument);
+        self.selected_row = row.min(self.total_rows().saturating_sub(1));
     }
 
-    /// Set tab from index (for view state restoration)
-    pub fn set_tab_index(&mut self, idx: u8) {
-        // Jump to first row of the section
-        let target_section = match idx {
-            0 => Section::Source,
-            1 => Section::Filter,
-            2 => Section::Effects,
-            3 => Section::Lfo,
-            4 => Section::Envelope,
-            _ => Section::Source,
-        };
-        // Find first row of that section
-        for i in 0..self.total_rows() {
-            if self.section_for_row(i) == target_section {
-                self.selected_row = i;
-                break;
-            }
-        }
+    pub fn instrument_id(&self) -> Option<InstrumentId> {
+        self.instrument_id
     }
 
     /// Apply edits back to an instrument
@@ -118,60 +155,119 @@ impl InstrumentEditPane {
         instrument.source_params = self.source_params.clone();
         instrument.filter = self.filter.clone();
         instrument.effects = self.effects.clone();
-        instrument.lfo = self.lfo.clone();
+        instrument.lfos = self.lfos.clone();
         instrument.amp_envelope = self.amp_envelope.clone();
+        instrument.mod_envelopes = self.mod_envelopes.clone();
+        instrument.mod_routings = self.mod_routings.clone();
         instrument.polyphonic = self.polyphonic;
         instrument.active = self.active;
+        instrument.trim = self.trim;
+        instrument.glide = self.glide;
+        instrument.unison_voices = self.unison_voices;
+        instrument.detune = self.detune;
+        instrument.voice_spread = self.voice_spread;
+        instrument.voice_spread_mode = self.voice_spread_mode;
+        instrument.key_low = self.key_low;
+        instrument.key_high = self.key_high;
+    }
+
+    /// Edited zone layout, for sampler instruments
+    pub fn sampler_zones(&self) -> Option<&SamplerConfig> {
+        self.sampler.as_ref()
+    }
+
+    fn lfo(&self) -> &LfoConfig {
+        &self.lfos[self.lfo_index]
+    }
+
+    fn lfo_mut(&mut self) -> &mut LfoConfig {
+        &mut self.lfos[self.lfo_index]
+    }
+
+    fn envelope(&self) -> &EnvConfig {
+        match self.env_index {
+            0 => &self.amp_envelope,
+            i => &self.mod_envelopes[i - 1],
+        }
+    }
+
+    fn envelope_mut(&mut self) -> &mut EnvConfig {
+        match self.env_index {
+            0 => &mut self.amp_envelope,
+            i => &mut self.mod_envelopes[i - 1],
+        }
+    }
+
+    /// Source the envelope section is showing, if it's a mod envelope
+    fn shown_mod_envelope(&self) -> Option<ModRouteSource> {
+        self.env_index.checked_sub(1).map(ModRouteSource::Envelope)
+    }
+
+    /// Selectable rows per section, in display order. Sections with no rows are skipped.
+    fn section_rows(&self) -> [(Section, usize); 8] {
+        [
+            (Section::Source, self.source_params.len().max(1)), // At least 1 for empty message
+            (Section::Zones, self.sampler.as_ref().map_or(0, |s| s.slices.len().max(1))),
+            (Section::Filter, if self.filter.is_some() { 4 } else { 1 }), // type/cutoff/res/keytrack or "off"
+            (Section::Effects, self.effects.len().max(1)), // At least 1 for empty message
+            (Section::Lfo, 4), // enabled, rate, depth, shape/target
+            (Section::Envelope, 4), // A, D, S, R
+            (Section::Mod, self.mod_routings.len().max(1)), // At least 1 for empty message
+            (Section::Output, 8), // trim, glide, unison, detune, voice spread, spread mode, key low, key high
+        ]
     }
 
     /// Total number of selectable rows across all sections
     fn total_rows(&self) -> usize {
-        let source_rows = self.source_params.len().max(1); // At least 1 for empty message
-        let filter_rows = if self.filter.is_some() { 3 } else { 1 }; // type/cutoff/res or "off"
-        let effect_rows = self.effects.len().max(1); // At least 1 for empty message
-        let lfo_rows = 4; // enabled, rate, depth, shape/target
-        let env_rows = 4; // A, D, S, R
-        source_rows + filter_rows + effect_rows + lfo_rows + env_rows
+        self.section_rows().iter().map(|(_, rows)| rows).sum()
     }
 
     /// Which section does a given row belong to?
     fn section_for_row(&self, row: usize) -> Section {
-        let source_rows = self.source_params.len().max(1);
-        let filter_rows = if self.filter.is_some() { 3 } else { 1 };
-        let effect_rows = self.effects.len().max(1);
-        let lfo_rows = 4;
-
-        if row < source_rows {
-            Section::Source
-        } else if row < source_rows + filter_rows {
-            Section::Filter
-        } else if row < source_rows + filter_rows + effect_rows {
-            Section::Effects
-        } else if row < source_rows + filter_rows + effect_rows + lfo_rows {
-            Section::Lfo
-        } else {
-            Section::Envelope
-        }
+        self.row_info(row).0
     }
 
     /// Get section and local index for a row
     fn row_info(&self, row: usize) -> (Section, usize) {
-        let source_rows = self.source_params.len().max(1);
-        let filter_rows = if self.filter.is_some() { 3 } else { 1 };
-        let effect_rows = self.effects.len().max(1);
-        let lfo_rows = 4;
-
-        if row < source_rows {
-            (Section::Source, row)
-        } else if row < source_rows + filter_rows {
-            (Section::Filter, row - source_rows)
-        } else if row < source_rows + filter_rows + effect_rows {
-            (Section::Effects, row - source_rows - filter_rows)
-        } else if row < source_rows + filter_rows + effect_rows + lfo_rows {
-            (Section::Lfo, row - source_rows - filter_rows - effect_rows)
-        } else {
-            (Section::Envelope, row - source_rows - filter_rows - effect_rows - lfo_rows)
+        let mut start = 0;
+        for (section, rows) in self.section_rows() {
+            if row < start + rows {
+                return (section, row - start);
+            }
+            start += rows;
         }
+        // Past the end: an output index that matches no row
+        (Section::Output, 8 + row - start)
+    }
+
+    /// First row and row count of a section
+    fn section_range(&self, section: Section) -> (usize, usize) {
+        let mut start = 0;
+        for (s, rows) in self.section_rows() {
+            if s == section {
+                return (start, rows);
+            }
+            start += rows;
+        }
+        (start, 0)
+    }
+
+    /// First row of the next (or previous) section that has rows
+    fn section_start(&self, forward: bool) -> usize {
+        let sections: Vec<(Section, usize)> = self.section_rows()
+            .into_iter()
+            .scan(0, |start, (section, rows)| {
+                let first = *start;
+                *start += rows;
+                Some((section, first, rows))
+            })
+            .filter(|(_, _, rows)| *rows > 0)
+            .map(|(section, first, _)| (section, first))
+            .collect();
+        let current = sections.iter().position(|(s, _)| *s == self.current_section()).unwrap_or(0);
+        let len = sections.len();
+        let target = if forward { (current + 1) % len } else { (current + len - 1) % len };
+        sections[target].1
     }
 
     fn current_section(&self) -> Section {
@@ -188,6 +284,19 @@ impl InstrumentEditPane {
                     adjust_param(param, increase, fraction);
                 }
             }
+            Section::Zones => {
+                // Shift the whole zone (range and root) by a semitone, or an octave
+                let Some(zone) = self.sampler.as_mut().and_then(|s| s.slices.get_mut(local_idx)) else {
+                    return;
+                };
+                let step: i16 = if big { 12 } else { 1 };
+                let step = if increase { step } else { -step };
+                let (lo, hi) = (zone.key_range.0 as i16 + step, zone.key_range.1 as i16 + step);
+                if lo >= 0 && hi <= 127 {
+                    zone.key_range = (lo as u8, hi as u8);
+                    zone.root_note = (zone.root_note as i16 + step).clamp(0, 127) as u8;
+                }
+            }
             Section::Filter => {
                 if let Some(ref mut f) = self.filter {
                     match local_idx {
@@ -204,31 +313,44 @@ impl InstrumentEditPane {
                             if increase { f.resonance.value = (f.resonance.value + delta).min(f.resonance.max); }
                             else { f.resonance.value = (f.resonance.value - delta).max(f.resonance.min); }
                         }
+                        3 => {
+                            let (min, max) = KEYTRACK_RANGE;
+                            let delta = (max - min) * fraction;
+                            if increase { f.keytrack = (f.keytrack + delta).min(max); }
+                            else { f.keytrack = (f.keytrack - delta).max(min); }
+                        }
                         _ => {}
                     }
                 }
             }
             Section::Effects => {
                 if let Some(effect) = self.effects.get_mut(local_idx) {
-                    if let Some(param) = effect.params.first_mut() {
+                    if effect.sync {
+                        effect.division = effect.division.step(if increase { 1 } else { -1 });
+                    } else if let Some(param) = effect.params.first_mut() {
                         adjust_param(param, increase, fraction);
                     }
                 }
             }
             Section::Lfo => {
+                let lfo = self.lfo_mut();
                 match local_idx {
                     0 => {} // enabled - use 'l' to toggle
+                    1 if lfo.sync => {
+                        // Faster rate = shorter division
+                        lfo.division = lfo.division.step(if increase { -1 } else { 1 });
+                    }
                     1 => {
                         // rate: 0.1 to 32 Hz
                         let delta = if big { 2.0 } else { 0.5 };
-                        if increase { self.lfo.rate = (self.lfo.rate + delta).min(32.0); }
-                        else { self.lfo.rate = (self.lfo.rate - delta).max(0.1); }
+                        if increase { lfo.rate = (lfo.rate + delta).min(32.0); }
+                        else { lfo.rate = (lfo.rate - delta).max(0.1); }
                     }
                     2 => {
                         // depth: 0 to 1
                         let delta = fraction;
-                        if increase { self.lfo.depth = (self.lfo.depth + delta).min(1.0); }
-                        else { self.lfo.depth = (self.lfo.depth - delta).max(0.0); }
+                        if increase { lfo.depth = (lfo.depth + delta).min(1.0); }
+                        else { lfo.depth = (lfo.depth - delta).max(0.0); }
                     }
                     3 => {} // shape/target - use 's'/'m' to cycle
                     _ => {}
@@ -236,16 +358,60 @@ impl InstrumentEditPane {
             }
             Section::Envelope => {
                 let delta = if big { 0.1 } else { 0.05 };
+                let env = self.envelope_mut();
                 let val = match local_idx {
-                    0 => &mut self.amp_envelope.attack,
-                    1 => &mut self.amp_envelope.decay,
-                    2 => &mut self.amp_envelope.sustain,
-                    3 => &mut self.amp_envelope.release,
+                    0 => &mut env.attack,
+                    1 => &mut env.decay,
+                    2 => &mut env.sustain,
+                    3 => &mut env.release,
                     _ => return,
                 };
                 if increase { *val = (*val + delta).min(if local_idx == 2 { 1.0 } else { 5.0 }); }
                 else { *val = (*val - delta).max(0.0); }
             }
+            Section::Mod => {
+                if let Some(routing) = self.mod_routings.get_mut(local_idx) {
+                    let delta = if big { 0.10 } else { 0.05 };
+                    let delta = if increase { delta } else { -delta };
+                    routing.amount = (routing.amount + delta).clamp(MOD_AMOUNT_RANGE.0, MOD_AMOUNT_RANGE.1);
+                }
+            }
+            Section::Output if local_idx == 0 => {
+                let delta = if big { 3.0 } else { 0.5 };
+                let delta = if increase { delta } else { -delta };
+                self.trim = (self.trim + delta).clamp(TRIM_RANGE_DB.0, TRIM_RANGE_DB.1);
+            }
+            Section::Output if local_idx == 1 => {
+                let delta = if big { 0.1 } else { 0.01 };
+                let delta = if increase { delta } else { -delta };
+                self.glide = (self.glide + delta).clamp(GLIDE_RANGE.0, GLIDE_RANGE.1);
+            }
+            Section::Output if local_idx == 2 => {
+                self.unison_voices = if increase {
+                    (self.unison_voices + 1).min(MAX_UNISON_VOICES)
+                } else {
+                    self.unison_voices.saturating_sub(1).max(1)
+                };
+            }
+            Section::Output if local_idx == 3 => {
+                let delta = if big { 5.0 } else { 1.0 };
+                let delta = if increase { delta } else { -delta };
+                self.detune = (self.detune + delta).clamp(0.0, MAX_DETUNE_CENTS);
+            }
+            Section::Output if local_idx == 4 => {
+                let delta = if big { 0.1 } else { 0.05 };
+                let delta = if increase { delta } else { -delta };
+                self.voice_spread = (self.voice_spread + delta).clamp(0.0, 1.0);
+            }
+            Section::Output if local_idx == 5 => self.voice_spread_mode = self.voice_spread_mode.next(),
+            // Key range: a semitone or an octave at a time, the ends never crossing
+            Section::Output if local_idx == 6 => {
+                self.key_low = step_note(self.key_low, increase, big).min(self.key_high);
+            }
+            Section::Output if local_idx == 7 => {
+                self.key_high = step_note(self.key_high, increase, big).max(self.key_low);
+            }
+            Section::Output => {}
         }
     }
 
@@ -267,12 +433,19 @@ impl InstrumentEditPane {
                     zero_param(param);
                 }
             }
+            Section::Zones => {
+                // Open the zone back up to the full keyboard
+                if let Some(zone) = self.sampler.as_mut().and_then(|s| s.slices.get_mut(local_idx)) {
+                    zone.key_range = (0, 127);
+                }
+            }
             Section::Filter => {
                 if let Some(ref mut f) = self.filter {
                     match local_idx {
                         0 => {} // type - can't zero
                         1 => f.cutoff.value = f.cutoff.min,
                         2 => f.resonance.value = f.resonance.min,
+                        3 => f.keytrack = 0.0,
                         _ => {}
                     }
                 }
@@ -285,23 +458,40 @@ impl InstrumentEditPane {
                 }
             }
             Section::Lfo => {
+                let lfo = self.lfo_mut();
                 match local_idx {
-                    0 => self.lfo.enabled = false,
-                    1 => self.lfo.rate = 0.1,
-                    2 => self.lfo.depth = 0.0,
+                    0 => lfo.enabled = false,
+                    1 => lfo.rate = 0.1,
+                    2 => lfo.depth = 0.0,
                     3 => {} // shape/target - can't zero
                     _ => {}
                 }
             }
             Section::Envelope => {
+                let env = self.envelope_mut();
                 match local_idx {
-                    0 => self.amp_envelope.attack = 0.0,
-                    1 => self.amp_envelope.decay = 0.0,
-                    2 => self.amp_envelope.sustain = 0.0,
-                    3 => self.amp_envelope.release = 0.0,
+                    0 => env.attack = 0.0,
+                    1 => env.decay = 0.0,
+                    2 => env.sustain = 0.0,
+                    3 => env.release = 0.0,
                     _ => {}
                 }
             }
+            Section::Mod => {
+                if let Some(routing) = self.mod_routings.get_mut(local_idx) {
+                    routing.amount = 0.0;
+                }
+            }
+            // 0 dB: unity gain
+            Section::Output if local_idx == 0 => self.trim = 0.0,
+            Section::Output if local_idx == 1 => self.glide = 0.0,
+            Section::Output if local_idx == 2 => self.unison_voices = 1,
+            Section::Output if local_idx == 3 => self.detune = 0.0,
+            Section::Output if local_idx == 4 => self.voice_spread = 0.0,
+            Section::Output if local_idx == 5 => self.voice_spread_mode = VoiceSpreadMode::default(),
+            Section::Output if local_idx == 6 => self.key_low = 0,
+            Section::Output if local_idx == 7 => self.key_high = 127,
+            Section::Output => {}
         }
     }
 
@@ -315,10 +505,16 @@ impl InstrumentEditPane {
                     zero_param(param);
                 }
             }
+            Section::Zones => {
+                for zone in self.sampler.iter_mut().flat_map(|s| s.slices.iter_mut()) {
+                    zone.key_range = (0, 127);
+                }
+            }
             Section::Filter => {
                 if let Some(ref mut f) = self.filter {
                     f.cutoff.value = f.cutoff.min;
                     f.resonance.value = f.resonance.min;
+                    f.keytrack = 0.0;
                 }
             }
             Section::Effects => {
@@ -329,15 +525,31 @@ impl InstrumentEditPane {
                 }
             }
             Section::Lfo => {
-                self.lfo.enabled = false;
-                self.lfo.rate = 0.1;
-                self.lfo.depth = 0.0;
+                let lfo = self.lfo_mut();
+                lfo.enabled = false;
+                lfo.rate = 0.1;
+                lfo.depth = 0.0;
             }
             Section::Envelope => {
-                self.amp_envelope.attack = 0.0;
-                self.amp_envelope.decay = 0.0;
-                self.amp_envelope.sustain = 0.0;
-                self.amp_envelope.release = 0.0;
+                let env = self.envelope_mut();
+                env.attack = 0.0;
+                env.decay = 0.0;
+                env.sustain = 0.0;
+                env.release = 0.0;
+            }
+            Section::Mod => {
+                for routing in &mut self.mod_routings {
+                    routing.amount = 0.0;
+                }
+            }
+            Section::Output => {
+                self.trim = 0.0;
+                self.glide = 0.0;
+                self.unison_voices = 1;
+                self.detune = 0.0;
+                self.voice_spread = 0.0;
+                self.key_low = 0;
+                self.key_high = 127;
             }
         }
     }
@@ -348,6 +560,88 @@ impl InstrumentEditPane {
     }
 
     /// Get current parameter value as a string for pre-filling text edit
+    /// Apply typed entry to the selected row, clamped to the row's range.
+    /// Unparseable text leaves the value unchanged and returns why.
+    fn apply_edit(&mut self, text: &str) -> Result<(), String> {
+        let (section, local_idx) = self.row_info(self.selected_row);
+        match section {
+            Section::Source => {
+                if let Some(param) = self.source_params.get_mut(local_idx) {
+                    param.value = param.parse_value(text)?;
+                }
+            }
+            Section::Zones => {
+                if let Some(zone) = self.sampler.as_mut().and_then(|s| s.slices.get_mut(local_idx)) {
+                    let spec = parse_zone(text).ok_or_else(|| format!("Not a zone: {}", text.trim()))?;
+                    zone.key_range = spec.key_range;
+                    zone.root_note = spec.root_note;
+                    if let Some((start, end)) = spec.bounds {
+                        zone.start = start;
+                        zone.end = end;
+                    }
+                }
+            }
+            Section::Filter => {
+                if let Some(ref mut f) = self.filter {
+                    match local_idx {
+                        1 => f.cutoff.value = parse_number(text, "Hz")?.clamp(f.cutoff.min, f.cutoff.max),
+                        2 => f.resonance.value = parse_number(text, "")?.clamp(f.resonance.min, f.resonance.max),
+                        3 => f.keytrack = parse_number(text, "")?.clamp(KEYTRACK_RANGE.0, KEYTRACK_RANGE.1),
+                        _ => {}
+                    }
+                }
+            }
+            Section::Envelope => {
+                let max = if local_idx == 2 { 1.0 } else { 5.0 };
+                let val = parse_number(text, if local_idx == 2 { "" } else { "s" })?.clamp(0.0, max);
+                let env = self.envelope_mut();
+                match local_idx {
+                    0 => env.attack = val,
+                    1 => env.decay = val,
+                    2 => env.sustain = val,
+                    3 => env.release = val,
+                    _ => {}
+                }
+            }
+            Section::Mod => {
+                if let Some(routing) = self.mod_routings.get_mut(local_idx) {
+                    routing.amount = parse_number(text, "")?.clamp(MOD_AMOUNT_RANGE.0, MOD_AMOUNT_RANGE.1);
+                }
+            }
+            Section::Output if local_idx == 0 => {
+                self.trim = parse_number(text, "dB")?.clamp(TRIM_RANGE_DB.0, TRIM_RANGE_DB.1);
+            }
+            Section::Output if local_idx == 1 => {
+                self.glide = parse_number(text, "s")?.clamp(GLIDE_RANGE.0, GLIDE_RANGE.1);
+            }
+            Section::Output if local_idx == 2 => {
+                let voices = text.trim().parse::<u8>().map_err(|_| format!("Not a whole number: {}", text.trim()))?;
+                self.unison_voices = voices.clamp(1, MAX_UNISON_VOICES);
+            }
+            Section::Output if local_idx == 3 => {
+                self.detune = parse_number(text, "ct")?.clamp(0.0, MAX_DETUNE_CENTS);
+            }
+            Section::Output if local_idx == 4 => {
+                self.voice_spread = parse_number(text, "")?.clamp(0.0, 1.0);
+            }
+            Section::Output if local_idx == 6 => {
+                self.key_low = parse_key(text)?.min(self.key_high);
+            }
+            Section::Output if local_idx == 7 => {
+                self.key_high = parse_key(text)?.max(self.key_low);
+            }
+            Section::Output => {
+                self.voice_spread_mode = match text.trim().to_lowercase().as_str() {
+                    "pitch" => VoiceSpreadMode::Pitch,
+                    "order" => VoiceSpreadMode::Order,
+                    _ => return Err(format!("Expected pitch or order: {}", text.trim())),
+                };
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
     fn current_value_string(&self) -> String {
         let (section, local_idx) = self.row_info(self.selected_row);
         match section {
@@ -362,11 +656,21 @@ impl InstrumentEditPane {
                     String::new()
                 }
             }
+            Section::Zones => {
+                match self.sampler.as_ref().and_then(|s| s.slices.get(local_idx)) {
+                    Some(zone) if zone.start > 0.0 || zone.end < 1.0 => {
+                        format!("{} {:.2}-{:.2}", zone.zone_label(), zone.start, zone.end)
+                    }
+                    Some(zone) => zone.zone_label(),
+                    None => String::new(),
+                }
+            }
             Section::Filter => {
                 if let Some(ref f) = self.filter {
                     match local_idx {
                         1 => format!("{:.2}", f.cutoff.value),
                         2 => format!("{:.2}", f.resonance.value),
+                        3 => format!("{:.2}", f.keytrack),
                         _ => String::new(),
                     }
                 } else {
@@ -374,20 +678,43 @@ impl InstrumentEditPane {
                 }
             }
             Section::Envelope => {
+                let env = self.envelope();
                 match local_idx {
-                    0 => format!("{:.2}", self.amp_envelope.attack),
-                    1 => format!("{:.2}", self.amp_envelope.decay),
-                    2 => format!("{:.2}", self.amp_envelope.sustain),
-                    3 => format!("{:.2}", self.amp_envelope.release),
+                    0 => format!("{:.2}", env.attack),
+                    1 => format!("{:.2}", env.decay),
+                    2 => format!("{:.2}", env.sustain),
+                    3 => format!("{:.2}", env.release),
                     _ => String::new(),
                 }
             }
+            Section::Mod => self.mod_routings.get(local_idx)
+                .map(|r| format!("{:.2}", r.amount))
+                .unwrap_or_default(),
+            Section::Output if local_idx == 0 => format!("{:.1}", self.trim),
+            Section::Output if local_idx == 1 => format!("{:.2}", self.glide),
+            Section::Output if local_idx == 2 => self.unison_voices.to_string(),
+            Section::Output if local_idx == 3 => format!("{:.0}", self.detune),
+            Section::Output if local_idx == 4 => format!("{:.2}", self.voice_spread),
+            Section::Output if local_idx == 5 => self.voice_spread_mode.name().to_string(),
+            Section::Output if local_idx == 6 => pitch_name(self.key_low),
+            Section::Output if local_idx == 7 => pitch_name(self.key_high),
             _ => String::new(),
         }
     }
 
 }
 
+/// A note a semitone (or, big, an octave) up or down, within MIDI range
+fn step_note(note: u8, increase: bool, big: bool) -> u8 {
+    let delta = if big { 12 } else { 1 };
+    if increase { note.saturating_add(delta).min(127) } else { note.saturating_sub(delta) }
+}
+
+/// A typed key range end: a note name (`C3`, `F#2`) or MIDI number
+fn parse_key(text: &str) -> Result<u8, String> {
+    parse_note(text.trim()).ok_or_else(|| format!("Not a note: {}", text.trim()))
+}
+
 fn adjust_param(param: &mut Param, increase: bool, fraction: f32) {
     let range = param.max - param.min;
     match &mut param.value {
@@ -428,11 +755,92 @@ fn render_slider(value: f32, min: f32, max: f32, width: usize) -> String {
     s
 }
 
+/// How a stage of the signal chain passes audio
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ChainStage {
+    Source,
+    Filter,
+    Effect,
+    /// Built, but crossfaded to dry
+    Bypassed,
+    /// Disabled; the engine leaves it out of the chain
+    Off,
+    Output,
+}
+
+/// The instrument's stages in the order `rebuild_instrument_routing` chains them:
+/// source, filter, effects, output. `afl_dry` shows every effect bypassed, as it
+/// is while the instrument is heard after-fader solo.
+fn signal_chain(
+    source: SourceType,
+    filter: Option<&FilterConfig>,
+    effects: &[EffectSlot],
+    afl_dry: bool,
+) -> Vec<(String, ChainStage)> {
+    let mut chain = vec![(source.name().to_string(), ChainStage::Source)];
+    if let Some(f) = filter {
+        chain.push((f.filter_type.name().to_string(), ChainStage::Filter));
+    }
+    for effect in effects {
+        let name = effect.effect_type.name();
+        chain.push(if !effect.enabled {
+            (format!("{} off", name), ChainStage::Off)
+        } else if effect.bypassed || afl_dry {
+            (format!("{} byp", name), ChainStage::Bypassed)
+        } else {
+            (name.to_string(), ChainStage::Effect)
+        });
+    }
+    chain.push(("Out".to_string(), ChainStage::Output));
+    chain
+}
+
+/// Arrow drawn between chain stages, and the width of an overflow mark
+const CHAIN_ARROW: &str = " \u{2192} ";
+const CHAIN_MARK_WIDTH: usize = 2;
+
+/// The stages `start..end` of a chain with labels `widths` wide that fit in `width`
+/// columns, arrows and overflow marks included, always keeping `focus` in view
+fn chain_window(widths: &[usize], focus: usize, width: usize) -> (usize, usize) {
+    let arrow = CHAIN_ARROW.chars().count();
+    let span = |start: usize, end: usize| {
+        let stages = widths[start..end].iter().sum::<usize>() + arrow * (end - start - 1);
+        let marks = [start > 0, end < widths.len()].iter().filter(|&&m| m).count();
+        stages + marks * CHAIN_MARK_WIDTH
+    };
+    let focus = focus.min(widths.len().saturating_sub(1));
+    let (mut start, mut end) = (focus, (focus + 1).min(widths.len()));
+    loop {
+        if end < widths.len() && span(start, end + 1) <= width {
+            end += 1;
+        } else if start > 0 && span(start - 1, end) <= width {
+            start -= 1;
+        } else {
+            return (start, end);
+        }
+    }
+}
+
 impl Pane for InstrumentEditPane {
     fn id(&self) -> &'static str {
         "instrument_edit"
     }
 
+    /// The section being edited, by its place in `section_rows`
+    fn nav_position(&self) -> usize {
+        let section = self.current_section();
+        self.section_rows().iter().position(|(s, _)| *s == section).unwrap_or(0)
+    }
+
+    /// Jump to the first row of the section
+    fn restore_nav_position(&mut self, position: usize) {
+        if let Some(&(section, rows)) = self.section_rows().get(position) {
+            if rows > 0 {
+                self.selected_row = self.section_range(section).0;
+            }
+        }
+    }
+
     fn handle_action(&mut self, action: &str, event: &InputEvent, state: &AppState) -> Action {
         match action {
             // Piano mode actions
@@ -463,45 +871,19 @@ impl Pane for InstrumentEditPane {
             // Text edit layer actions
             "text:confirm" => {
                 let text = self.edit_input.value().to_string();
-                let (section, local_idx) = self.row_info(self.selected_row);
-                match section {
-                    Section::Source => {
-                        if let Some(param) = self.source_params.get_mut(local_idx) {
-                            if let Ok(v) = text.parse::<f32>() {
-                                param.value = ParamValue::Float(v.clamp(param.min, param.max));
-                            }
-                        }
-                    }
-                    Section::Filter => {
-                        if let Some(ref mut f) = self.filter {
-                            match local_idx {
-                                1 => if let Ok(v) = text.parse::<f32>() { f.cutoff.value = v.clamp(f.cutoff.min, f.cutoff.max); },
-                                2 => if let Ok(v) = text.parse::<f32>() { f.resonance.value = v.clamp(f.resonance.min, f.resonance.max); },
-                                _ => {}
-                            }
-                        }
-                    }
-                    Section::Envelope => {
-                        if let Ok(v) = text.parse::<f32>() {
-                            let max = if local_idx == 2 { 1.0 } else { 5.0 };
-                            let val = v.clamp(0.0, max);
-                            match local_idx {
-                                0 => self.amp_envelope.attack = val,
-                                1 => self.amp_envelope.decay = val,
-                                2 => self.amp_envelope.sustain = val,
-                                3 => self.amp_envelope.release = val,
-                                _ => {}
-                            }
-                        }
-                    }
-                    _ => {}
+                if let Err(e) = self.apply_edit(&text) {
+                    // Stay in the input so the entry can be corrected
+                    self.edit_error = Some(e);
+                    return Action::None;
                 }
                 self.editing = false;
+                self.edit_error = None;
                 self.edit_input.set_focused(false);
                 self.emit_update()
             }
             "text:cancel" => {
                 self.editing = false;
+                self.edit_error = None;
                 self.edit_input.set_focused(false);
                 Action::None
             }
@@ -541,16 +923,21 @@ impl Pane for InstrumentEditPane {
             }
             "enter_edit" => {
                 self.editing = true;
+                self.edit_error = None;
                 let current_val = self.current_value_string();
                 self.edit_input.set_value(&current_val);
                 self.edit_input.set_focused(true);
                 Action::PushLayer("text_edit")
             }
+            "audition" => match self.instrument_id {
+                Some(id) => Action::Instrument(InstrumentAction::Audition(id)),
+                None => Action::None,
+            },
             "toggle_filter" => {
                 if self.filter.is_some() {
                     self.filter = None;
                 } else {
-                    self.filter = Some(FilterConfig::new(FilterType::Lpf));
+                    self.filter = Some(FilterConfig::with_defaults(FilterType::Lpf, &state.param_defaults));
                 }
                 self.emit_update()
             }
@@ -565,23 +952,94 @@ impl Pane for InstrumentEditPane {
                 }
                 Action::None
             }
+            "add_effect" if self.current_section() == Section::Mod => {
+                let source = ModRouteSource::Lfo(self.lfo_index);
+                self.mod_routings.push(ModRouting::new(source, LfoTarget::FilterCutoff));
+                let (start, _) = self.section_range(Section::Mod);
+                self.selected_row = start + self.mod_routings.len() - 1;
+                self.emit_update()
+            }
             "add_effect" => {
-                let next_type = if self.effects.is_empty() {
-                    EffectType::Delay
-                } else {
-                    match self.effects.last().unwrap().effect_type {
-                        EffectType::Delay => EffectType::Reverb,
-                        EffectType::Reverb => EffectType::Gate,
-                        EffectType::Gate => EffectType::TapeComp,
-                        EffectType::TapeComp => EffectType::SidechainComp,
-                        EffectType::SidechainComp => EffectType::Delay,
-                    }
-                };
-                self.effects.push(EffectSlot::new(next_type));
+                let next_type = self.effects.last().map_or(EffectType::Delay, |e| e.effect_type.next());
+                self.effects.push(EffectSlot::with_defaults(next_type, &state.param_defaults));
                 self.emit_update()
             }
+            "add_zone" => match self.sampler.as_mut() {
+                Some(sampler) => {
+                    sampler.add_zone();
+                    self.emit_update()
+                }
+                None => Action::None,
+            },
+            "toggle_layer_zones" => match self.sampler.as_mut() {
+                Some(sampler) => {
+                    sampler.layer_zones = !sampler.layer_zones;
+                    self.emit_update()
+                }
+                None => Action::None,
+            },
+            "add_zone_layer" => {
+                let (section, local_idx) = self.row_info(self.selected_row);
+                match (self.instrument_id, self.sampler.as_ref()) {
+                    (Some(id), Some(sampler)) if section == Section::Zones && !sampler.slices.is_empty() => {
+                        let slice_id = sampler.slices[local_idx.min(sampler.slices.len() - 1)].id;
+                        Action::Session(SessionAction::OpenFileBrowser(FileSelectAction::LoadZoneLayer(id, slice_id)))
+                    }
+                    _ => Action::None,
+                }
+            }
+            "clear_zone_layers" => {
+                let (section, local_idx) = self.row_info(self.selected_row);
+                match self.sampler.as_mut() {
+                    Some(sampler) if section == Section::Zones && !sampler.slices.is_empty() => {
+                        let idx = local_idx.min(sampler.slices.len() - 1);
+                        sampler.slices[idx].layers.clear();
+                        self.emit_update()
+                    }
+                    _ => Action::None,
+                }
+            }
+            "toggle_crossfade" => match self.sampler.as_mut() {
+                Some(sampler) => {
+                    sampler.crossfade_layers = !sampler.crossfade_layers;
+                    self.emit_update()
+                }
+                None => Action::None,
+            },
             "remove_effect" => {
                 let (section, local_idx) = self.row_info(self.selected_row);
+                match section {
+                    Section::Mod if local_idx < self.mod_routings.len() => {
+                        self.mod_routings.remove(local_idx);
+                        self.selected_row = self.selected_row.min(self.total_rows() - 1);
+                        return self.emit_update();
+                    }
+                    // Removing a source drops its routings; LFO 1 always stays
+                    Section::Lfo if self.lfos.len() > 1 => {
+                        self.lfos.remove(self.lfo_index);
+                        remove_source(&mut self.mod_routings, ModRouteSource::Lfo(self.lfo_index));
+                        self.lfo_index = self.lfo_index.min(self.lfos.len() - 1);
+                        return self.emit_update();
+                    }
+                    Section::Envelope if self.env_index > 0 => {
+                        let removed = self.env_index - 1;
+                        self.mod_envelopes.remove(removed);
+                        remove_source(&mut self.mod_routings, ModRouteSource::Envelope(removed));
+                        self.env_index -= 1;
+                        return self.emit_update();
+                    }
+                    _ => {}
+                }
+                if section == Section::Zones {
+                    // Keep at least one zone so the sampler isn't silent everywhere
+                    if let Some(sampler) = self.sampler.as_mut().filter(|s| s.slices.len() > 1) {
+                        let id = sampler.slices[local_idx.min(sampler.slices.len() - 1)].id;
+                        sampler.remove_slice(id);
+                        self.selected_row = self.selected_row.min(self.total_rows() - 1);
+                        return self.emit_update();
+                    }
+                    return Action::None;
+                }
                 if section == Section::Effects && !self.effects.is_empty() {
                     let idx = local_idx.min(self.effects.len() - 1);
                     self.effects.remove(idx);
@@ -589,6 +1047,49 @@ impl Pane for InstrumentEditPane {
                 }
                 Action::None
             }
+            "mix_down" | "mix_up" => {
+                let (section, local_idx) = self.row_info(self.selected_row);
+                let delta = if action == "mix_up" { 0.05 } else { -0.05 };
+                match self.effects.get_mut(local_idx).filter(|_| section == Section::Effects) {
+                    Some(effect) => {
+                        effect.adjust_mix(delta);
+                        self.emit_update()
+                    }
+                    None => Action::None,
+                }
+            }
+            "toggle_bypass" => {
+                let (section, local_idx) = self.row_info(self.selected_row);
+                let (Some(id), Some(effect)) = (
+                    self.instrument_id,
+                    self.effects.get_mut(local_idx).filter(|_| section == Section::Effects),
+                ) else {
+                    return Action::None;
+                };
+                effect.bypassed = !effect.bypassed;
+                Action::Instrument(InstrumentAction::SetEffectBypass(id, local_idx, effect.bypassed))
+            }
+            "toggle_sync" => {
+                let (section, local_idx) = self.row_info(self.selected_row);
+                if section != Section::Effects {
+                    return Action::None;
+                }
+                match self.effects.get_mut(local_idx).filter(|e| e.supports_sync()) {
+                    Some(effect) => {
+                        effect.set_sync(!effect.sync, state.session.bpm as f32);
+                        self.emit_update()
+                    }
+                    None => Action::None,
+                }
+            }
+            "randomize" => match self.instrument_id {
+                Some(id) => Action::Instrument(InstrumentAction::RandomizeParams(id)),
+                None => Action::None,
+            },
+            "mutate" => match self.instrument_id {
+                Some(id) => Action::Instrument(InstrumentAction::MutateParams(id)),
+                None => Action::None,
+            },
             "toggle_poly" => {
                 self.polyphonic = !self.polyphonic;
                 self.emit_update()
@@ -612,6 +1113,20 @@ impl Pane for InstrumentEditPane {
                     Action::None
                 }
             }
+            "trim_sample" | "normalize_sample" | "revert_sample" => match self.instrument_id {
+                Some(id) if self.source.is_sample() => Action::Instrument(match action {
+                    "trim_sample" => InstrumentAction::TrimSample(id),
+                    "normalize_sample" => InstrumentAction::NormalizeSample(id),
+                    _ => InstrumentAction::RevertSample(id),
+                }),
+                _ => Action::None,
+            },
+            "toggle_reverse" => match self.instrument_id {
+                Some(id) if self.source.is_sample() => {
+                    Action::Instrument(InstrumentAction::ToggleSampleReverse(id))
+                }
+                _ => Action::None,
+            },
             "zero_param" => {
                 self.zero_current_param();
                 self.emit_update()
@@ -621,51 +1136,88 @@ impl Pane for InstrumentEditPane {
                 self.emit_update()
             }
             "toggle_lfo" => {
-                self.lfo.enabled = !self.lfo.enabled;
+                let lfo = self.lfo_mut();
+                lfo.enabled = !lfo.enabled;
+                self.emit_update()
+            }
+            "toggle_lfo_sync" => {
+                let bpm = state.session.bpm as f32;
+                let lfo = self.lfo_mut();
+                lfo.set_sync(!lfo.sync, bpm);
+                self.emit_update()
+            }
+            "toggle_lfo_retrigger" => {
+                let lfo = self.lfo_mut();
+                lfo.retrigger = !lfo.retrigger;
                 self.emit_update()
             }
             "cycle_lfo_shape" => {
-                self.lfo.shape = self.lfo.shape.next();
+                let (section, local_idx) = self.row_info(self.selected_row);
+                if section == Section::Mod {
+                    // Step the selected routing to the next source the instrument has
+                    let sources = available_sources(&self.lfos, &self.mod_envelopes);
+                    if let Some(routing) = self.mod_routings.get_mut(local_idx) {
+                        let pos = sources.iter().position(|s| *s == routing.source).unwrap_or(0);
+                        routing.source = sources[(pos + 1) % sources.len()];
+                    }
+                } else {
+                    let lfo = self.lfo_mut();
+                    lfo.shape = lfo.shape.next();
+                }
                 self.emit_update()
             }
             "cycle_lfo_target" => {
-                self.lfo.target = self.lfo.target.next();
+                let (section, local_idx) = self.row_info(self.selected_row);
+                let source = ModRouteSource::Lfo(self.lfo_index);
+                let routing = if section == Section::Mod {
+                    self.mod_routings.get_mut(local_idx)
+                } else {
+                    // Outside the matrix this retargets the shown LFO's first routing
+                    self.mod_routings.iter_mut().find(|r| r.source == source)
+                };
+                match routing {
+                    Some(routing) => routing.destination = routing.destination.next(),
+                    None if section != Section::Mod => {
+                        self.mod_routings.push(ModRouting::new(source, LfoTarget::FilterCutoff));
+                    }
+                    None => return Action::None,
+                }
                 self.emit_update()
             }
-            "next_section" => {
-                // Jump to first row of next section
-                let current = self.current_section();
-                let next = match current {
-                    Section::Source => Section::Filter,
-                    Section::Filter => Section::Effects,
-                    Section::Effects => Section::Lfo,
-                    Section::Lfo => Section::Envelope,
-                    Section::Envelope => Section::Source,
-                };
-                for i in 0..self.total_rows() {
-                    if self.section_for_row(i) == next {
-                        self.selected_row = i;
-                        break;
+            "next_mod_source" => {
+                match self.current_section() {
+                    Section::Lfo => self.lfo_index = (self.lfo_index + 1) % self.lfos.len(),
+                    Section::Envelope => self.env_index = (self.env_index + 1) % (self.mod_envelopes.len() + 1),
+                    _ => {}
+                }
+                Action::None
+            }
+            "add_mod_source" => {
+                match self.current_section() {
+                    Section::Lfo if self.lfos.len() < MAX_LFOS => {
+                        self.lfos.push(LfoConfig { enabled: true, ..LfoConfig::default() });
+                        self.lfo_index = self.lfos.len() - 1;
+                        let source = ModRouteSource::Lfo(self.lfo_index);
+                        self.mod_routings.push(ModRouting::new(source, LfoTarget::FilterCutoff));
+                    }
+                    Section::Envelope if self.mod_envelopes.len() < MAX_MOD_ENVELOPES => {
+                        self.mod_envelopes.push(EnvConfig { attack: 0.01, decay: 0.3, sustain: 0.0, release: 0.3 });
+                        self.env_index = self.mod_envelopes.len();
+                        let source = ModRouteSource::Envelope(self.mod_envelopes.len() - 1);
+                        self.mod_routings.push(ModRouting::new(source, LfoTarget::Pitch));
                     }
+                    _ => return Action::None,
                 }
+                self.emit_update()
+            }
+            "next_section" => {
+                // Jump to first row of next section
+                self.selected_row = self.section_start(true);
                 Action::None
             }
             "prev_section" => {
                 // Jump to first row of previous section
-                let current = self.current_section();
-                let prev = match current {
-                    Section::Source => Section::Envelope,
-                    Section::Filter => Section::Source,
-                    Section::Effects => Section::Filter,
-                    Section::Lfo => Section::Effects,
-                    Section::Envelope => Section::Lfo,
-                };
-                for i in 0..self.total_rows() {
-                    if self.section_for_row(i) == prev {
-                        self.selected_row = i;
-                        break;
-                    }
-                }
+                self.selected_row = self.section_start(false);
                 Action::None
             }
             _ => Action::None,
@@ -673,14 +1225,14 @@ impl Pane for InstrumentEditPane {
     }
 
     fn handle_raw_input(&mut self, event: &InputEvent, _state: &AppState) -> Action {
-        if self.editing {
-            self.edit_input.handle_input(event);
+        if self.editing && self.edit_input.handle_input(event) {
+            self.edit_error = None;
         }
         Action::None
     }
 
-    fn render(&self, area: RatatuiRect, buf: &mut Buffer, _state: &AppState) {
-        let rect = center_rect(area, 97, 29);
+    fn render(&self, area: RatatuiRect, buf: &mut Buffer, state: &AppState) {
+        let rect = fit_rect(area, 97, 29);
 
         let title = format!(" Edit: {} ({}) ", self.instrument_name, self.source.name());
         let block = Block::default()
@@ -696,11 +1248,22 @@ impl Pane for InstrumentEditPane {
 
         // Mode indicators in header
         let mode_x = rect.x + rect.width - 18;
-        let poly_style = ratatui::style::Style::from(Style::new().fg(if self.polyphonic { Color::LIME } else { Color::DARK_GRAY }));
+        let poly_style = ratatui::style::Style::from(Style::new().fg(if self.polyphonic { Color::LIME } else { theme().muted }));
         let poly_str = if self.polyphonic { " POLY " } else { " MONO " };
         Paragraph::new(Line::from(Span::styled(poly_str, poly_style)))
             .render(RatatuiRect::new(mode_x, rect.y, 6, 1), buf);
 
+        // Reverse indicator for samplers (the flag lives on the instrument's sampler config)
+        let reversed = self.instrument_id
+            .and_then(|id| state.instruments.instrument(id))
+            .and_then(|i| i.sampler_config.as_ref())
+            .is_some_and(|c| c.reverse);
+        if reversed {
+            let rev_x = mode_x.saturating_sub(6);
+            Paragraph::new(Line::from(Span::styled(" REV ", ratatui::style::Style::from(Style::new().fg(Color::ORANGE)))))
+                .render(RatatuiRect::new(rev_x, rect.y, 5, 1), buf);
+        }
+
         // Active/Inactive indicator for AudioIn instruments
         if self.source.is_audio_input() {
             let active_style = ratatui::style::Style::from(Style::new().fg(
@@ -720,21 +1283,74 @@ impl Pane for InstrumentEditPane {
                 .render(RatatuiRect::new(rect.x + 1, rect.y, piano_str.len() as u16, 1), buf);
         }
 
+        // Signal chain on the top margin, scrolled to keep the selected stage in view
+        {
+            let afl_dry = self.instrument_id
+                .and_then(|id| state.instruments.instrument(id))
+                .is_some_and(|i| i.solo && state.session.afl_listening(&state.instruments));
+            let chain = signal_chain(self.source, self.filter.as_ref(), &self.effects, afl_dry);
+            let focus = match self.row_info(self.selected_row) {
+                (Section::Filter, _) if self.filter.is_some() => 1,
+                (Section::Effects, idx) if !self.effects.is_empty() => {
+                    1 + self.filter.is_some() as usize + idx.min(self.effects.len() - 1)
+                }
+                (Section::Source | Section::Zones | Section::Filter | Section::Effects, _) => 0,
+                _ => chain.len() - 1,
+            };
+            let widths: Vec<usize> = chain.iter().map(|(label, _)| label.chars().count()).collect();
+            let (start, end) = chain_window(&widths, focus, inner.width.saturating_sub(2) as usize);
+
+            let muted = ratatui::style::Style::from(Style::new().fg(theme().muted));
+            let mut spans = Vec::new();
+            if start > 0 {
+                spans.push(Span::styled("\u{2039} ", muted));
+            }
+            for (i, (label, stage)) in chain[start..end].iter().enumerate() {
+                if i > 0 {
+                    spans.push(Span::styled(CHAIN_ARROW, muted));
+                }
+                let color = match stage {
+                    ChainStage::Source => theme().accent,
+                    ChainStage::Filter => Color::FILTER_COLOR,
+                    ChainStage::Effect => Color::FX_COLOR,
+                    ChainStage::Bypassed | ChainStage::Off => theme().muted,
+                    ChainStage::Output => theme().foreground,
+                };
+                let style = if start + i == focus { Style::new().fg(color).bold() } else { Style::new().fg(color) };
+                spans.push(Span::styled(label.as_str(), ratatui::style::Style::from(style)));
+            }
+            if end < chain.len() {
+                spans.push(Span::styled(" \u{203a}", muted));
+            }
+            Paragraph::new(Line::from(spans))
+                .render(RatatuiRect::new(content_x, inner.y, inner.width.saturating_sub(2), 1), buf);
+        }
+
+        // Sections are laid out on a scratch buffer tall enough for all of them, then the
+        // window around the selected row is copied in, so long instruments scroll
+        let content_top = y;
+        let mut content = Buffer::empty(RatatuiRect::new(
+            inner.x, content_top, inner.width, self.total_rows() as u16 + 24,
+        ));
+        let screen = buf;
+        let buf = &mut content;
+        let mut selected_y = content_top;
         let mut global_row = 0;
 
         // === SOURCE SECTION ===
         Paragraph::new(Line::from(Span::styled(
             format!("SOURCE: {}", self.source.name()),
-            ratatui::style::Style::from(Style::new().fg(Color::CYAN).bold()),
+            ratatui::style::Style::from(Style::new().fg(theme().accent).bold()),
         ))).render(RatatuiRect::new(content_x, y, inner.width.saturating_sub(2), 1), buf);
         y += 1;
 
         if self.source_params.is_empty() {
             let is_sel = self.selected_row == global_row;
+            if is_sel { selected_y = y; }
             let style = if is_sel {
-                ratatui::style::Style::from(Style::new().fg(Color::DARK_GRAY).bg(Color::SELECTION_BG))
+                ratatui::style::Style::from(Style::new().fg(theme().muted).bg(theme().selection))
             } else {
-                ratatui::style::Style::from(Style::new().fg(Color::DARK_GRAY))
+                ratatui::style::Style::from(Style::new().fg(theme().muted))
             };
             Paragraph::new(Line::from(Span::styled("(no parameters)", style)))
                 .render(RatatuiRect::new(content_x + 2, y, inner.width.saturating_sub(4), 1), buf);
@@ -742,6 +1358,7 @@ impl Pane for InstrumentEditPane {
         } else {
             for param in &self.source_params {
                 let is_sel = self.selected_row == global_row;
+                if is_sel { selected_y = y; }
                 render_param_row_buf(buf, content_x, y, param, is_sel, self.editing && is_sel, &self.edit_input);
                 y += 1;
                 global_row += 1;
@@ -749,6 +1366,46 @@ impl Pane for InstrumentEditPane {
         }
         y += 1;
 
+        // === ZONES SECTION (sampler only) ===
+        if let Some(ref sampler) = self.sampler {
+            let overlap = if sampler.layer_zones { "layer" } else { "first" };
+            let xfade = if sampler.crossfade_layers { "on" } else { "off" };
+            Paragraph::new(Line::from(Span::styled(
+                format!(
+                    "ZONES [overlap: {}, xfade: {}]  (z: add, d: remove, Z: layer, L/X: vel layers, F: xfade)",
+                    overlap, xfade
+                ),
+                ratatui::style::Style::from(Style::new().fg(theme().accent).bold()),
+            ))).render(RatatuiRect::new(content_x, y, inner.width.saturating_sub(2), 1), buf);
+            y += 1;
+
+            if sampler.slices.is_empty() {
+                let is_sel = self.selected_row == global_row;
+                if is_sel { selected_y = y; }
+                render_label_value_row_buf(buf, content_x, y, "(no zones)", "", theme().muted, is_sel);
+                y += 1;
+                global_row += 1;
+            }
+            for (i, zone) in sampler.slices.iter().enumerate() {
+                let is_sel = self.selected_row == global_row;
+                if is_sel { selected_y = y; }
+                let label = format!("Zone {}", i + 1);
+                if self.editing && is_sel {
+                    render_label_value_row_buf(buf, content_x, y, &label, "", theme().accent, is_sel);
+                    self.edit_input.render_buf(buf, content_x + 16, y, 30);
+                } else {
+                    let mut value = format!("{:14} {:.2}-{:.2}", zone.zone_label(), zone.start, zone.end);
+                    if !zone.layers.is_empty() {
+                        value.push_str(&format!("  {} vel layers", zone.layers.len() + 1));
+                    }
+                    render_label_value_row_buf(buf, content_x, y, &label, &value, theme().accent, is_sel);
+                }
+                y += 1;
+                global_row += 1;
+            }
+            y += 1;
+        }
+
         // === FILTER SECTION ===
         let filter_label = if let Some(ref f) = self.filter {
             format!("FILTER: {}  (f: off, t: cycle)", f.filter_type.name())
@@ -765,6 +1422,7 @@ impl Pane for InstrumentEditPane {
             // Type row
             {
                 let is_sel = self.selected_row == global_row;
+                if is_sel { selected_y = y; }
                 render_label_value_row_buf(buf, content_x, y, "Type", &f.filter_type.name(), Color::FILTER_COLOR, is_sel);
                 y += 1;
                 global_row += 1;
@@ -772,6 +1430,7 @@ impl Pane for InstrumentEditPane {
             // Cutoff row
             {
                 let is_sel = self.selected_row == global_row;
+                if is_sel { selected_y = y; }
                 render_value_row_buf(buf, content_x, y, "Cutoff", f.cutoff.value, f.cutoff.min, f.cutoff.max, is_sel, self.editing && is_sel, &self.edit_input);
                 y += 1;
                 global_row += 1;
@@ -779,16 +1438,26 @@ impl Pane for InstrumentEditPane {
             // Resonance row
             {
                 let is_sel = self.selected_row == global_row;
+                if is_sel { selected_y = y; }
                 render_value_row_buf(buf, content_x, y, "Resonance", f.resonance.value, f.resonance.min, f.resonance.max, is_sel, self.editing && is_sel, &self.edit_input);
                 y += 1;
                 global_row += 1;
             }
+            // Key-tracking row
+            {
+                let is_sel = self.selected_row == global_row;
+                if is_sel { selected_y = y; }
+                render_value_row_buf(buf, content_x, y, "KeyTrack", f.keytrack, KEYTRACK_RANGE.0, KEYTRACK_RANGE.1, is_sel, self.editing && is_sel, &self.edit_input);
+                y += 1;
+                global_row += 1;
+            }
         } else {
             let is_sel = self.selected_row == global_row;
+            if is_sel { selected_y = y; }
             let style = if is_sel {
-                ratatui::style::Style::from(Style::new().fg(Color::DARK_GRAY).bg(Color::SELECTION_BG))
+                ratatui::style::Style::from(Style::new().fg(theme().muted).bg(theme().selection))
             } else {
-                ratatui::style::Style::from(Style::new().fg(Color::DARK_GRAY))
+                ratatui::style::Style::from(Style::new().fg(theme().muted))
             };
             Paragraph::new(Line::from(Span::styled("(disabled)", style)))
                 .render(RatatuiRect::new(content_x + 2, y, inner.width.saturating_sub(4), 1), buf);
@@ -799,17 +1468,18 @@ impl Pane for InstrumentEditPane {
 
         // === EFFECTS SECTION ===
         Paragraph::new(Line::from(Span::styled(
-            "EFFECTS  (a: add, d: remove)",
+            "EFFECTS  (a: add, d: remove, [/]: dry/wet, b: bypass)",
             ratatui::style::Style::from(Style::new().fg(Color::FX_COLOR).bold()),
         ))).render(RatatuiRect::new(content_x, y, inner.width.saturating_sub(2), 1), buf);
         y += 1;
 
         if self.effects.is_empty() {
             let is_sel = self.selected_row == global_row;
+            if is_sel { selected_y = y; }
             let style = if is_sel {
-                ratatui::style::Style::from(Style::new().fg(Color::DARK_GRAY).bg(Color::SELECTION_BG))
+                ratatui::style::Style::from(Style::new().fg(theme().muted).bg(theme().selection))
             } else {
-                ratatui::style::Style::from(Style::new().fg(Color::DARK_GRAY))
+                ratatui::style::Style::from(Style::new().fg(theme().muted))
             };
             Paragraph::new(Line::from(Span::styled("(no effects)", style)))
                 .render(RatatuiRect::new(content_x + 2, y, inner.width.saturating_sub(4), 1), buf);
@@ -817,37 +1487,48 @@ impl Pane for InstrumentEditPane {
         } else {
             for effect in &self.effects {
                 let is_sel = self.selected_row == global_row;
+                if is_sel { selected_y = y; }
                 // Selection indicator
                 if is_sel {
                     if let Some(cell) = buf.cell_mut((content_x, y)) {
                         cell.set_char('>').set_style(
-                            ratatui::style::Style::from(Style::new().fg(Color::WHITE).bg(Color::SELECTION_BG).bold()),
+                            ratatui::style::Style::from(Style::new().fg(theme().foreground).bg(theme().selection).bold()),
                         );
                     }
                 }
 
-                let enabled_str = if effect.enabled { "ON " } else { "OFF" };
+                let enabled_str = if !effect.enabled {
+                    "OFF"
+                } else if effect.bypassed {
+                    "BYP"
+                } else {
+                    "ON "
+                };
                 let effect_text = format!("{:10} [{}]", effect.effect_type.name(), enabled_str);
                 let effect_style = if is_sel {
-                    ratatui::style::Style::from(Style::new().fg(Color::FX_COLOR).bg(Color::SELECTION_BG))
+                    ratatui::style::Style::from(Style::new().fg(Color::FX_COLOR).bg(theme().selection))
                 } else {
                     ratatui::style::Style::from(Style::new().fg(Color::FX_COLOR))
                 };
                 Paragraph::new(Line::from(Span::styled(effect_text, effect_style)))
                     .render(RatatuiRect::new(content_x + 2, y, 18, 1), buf);
 
-                // Params inline
-                let params_str: String = effect.params.iter().take(3).map(|p| {
+                // Dry/wet first, then the effect's own params inline
+                let others: String = effect.params.iter().filter(|p| p.name != "mix").take(3).map(|p| {
+                    if effect.sync && p.name == "time" {
+                        return format!("time:{} sync", effect.division.name());
+                    }
                     match &p.value {
                         ParamValue::Float(v) => format!("{}:{:.2}", p.name, v),
                         ParamValue::Int(v) => format!("{}:{}", p.name, v),
                         ParamValue::Bool(v) => format!("{}:{}", p.name, v),
                     }
                 }).collect::<Vec<_>>().join("  ");
+                let params_str = format!("wet:{:>3.0}%  {}", effect.mix() * 100.0, others);
                 let params_style = if is_sel {
-                    ratatui::style::Style::from(Style::new().fg(Color::SKY_BLUE).bg(Color::SELECTION_BG))
+                    ratatui::style::Style::from(Style::new().fg(Color::SKY_BLUE).bg(theme().selection))
                 } else {
-                    ratatui::style::Style::from(Style::new().fg(Color::DARK_GRAY))
+                    ratatui::style::Style::from(Style::new().fg(theme().muted))
                 };
                 Paragraph::new(Line::from(Span::styled(params_str, params_style)))
                     .render(RatatuiRect::new(content_x + 20, y, inner.width.saturating_sub(22), 1), buf);
@@ -859,9 +1540,13 @@ impl Pane for InstrumentEditPane {
         y += 1;
 
         // === LFO SECTION ===
-        let lfo_status = if self.lfo.enabled { "ON" } else { "OFF" };
+        let lfo = self.lfo();
+        let lfo_status = if lfo.enabled { "ON" } else { "OFF" };
         Paragraph::new(Line::from(Span::styled(
-            format!("LFO [{}]  (l: toggle, s: shape, m: target)", lfo_status),
+            format!(
+                "LFO {}/{} [{}]  (l: toggle, s: shape, m: target, k: sync, n: retrig, j/J: next/add)",
+                self.lfo_index + 1, self.lfos.len(), lfo_status
+            ),
             ratatui::style::Style::from(Style::new().fg(Color::PINK).bold()),
         ))).render(RatatuiRect::new(content_x, y, inner.width.saturating_sub(2), 1), buf);
         y += 1;
@@ -869,21 +1554,34 @@ impl Pane for InstrumentEditPane {
         // Row 0: Enabled
         {
             let is_sel = self.selected_row == global_row;
-            let enabled_val = if self.lfo.enabled { "ON" } else { "OFF" };
+            if is_sel { selected_y = y; }
+            let enabled_val = match (lfo.enabled, lfo.retrigger) {
+                (true, true) => "ON  retrig",
+                (true, false) => "ON",
+                (false, _) => "OFF",
+            };
             render_label_value_row_buf(buf, content_x, y, "Enabled", enabled_val, Color::PINK, is_sel);
             y += 1;
             global_row += 1;
         }
 
         // Row 1: Rate
-        {
+        if lfo.sync {
+            let is_sel = self.selected_row == global_row;
+            if is_sel { selected_y = y; }
+            let rate_val = format!("{} sync", lfo.division.name());
+            render_label_value_row_buf(buf, content_x, y, "Rate", &rate_val, Color::PINK, is_sel);
+            y += 1;
+            global_row += 1;
+        } else {
             let is_sel = self.selected_row == global_row;
-            render_value_row_buf(buf, content_x, y, "Rate", self.lfo.rate, 0.1, 32.0, is_sel, self.editing && is_sel, &self.edit_input);
+            if is_sel { selected_y = y; }
+            render_value_row_buf(buf, content_x, y, "Rate", lfo.rate, 0.1, 32.0, is_sel, self.editing && is_sel, &self.edit_input);
             // Hz label
             let hz_style = if is_sel {
-                ratatui::style::Style::from(Style::new().fg(Color::DARK_GRAY).bg(Color::SELECTION_BG))
+                ratatui::style::Style::from(Style::new().fg(theme().muted).bg(theme().selection))
             } else {
-                ratatui::style::Style::from(Style::new().fg(Color::DARK_GRAY))
+                ratatui::style::Style::from(Style::new().fg(theme().muted))
             };
             for (j, ch) in "Hz".chars().enumerate() {
                 if let Some(cell) = buf.cell_mut((content_x + 44 + j as u16, y)) {
@@ -897,7 +1595,8 @@ impl Pane for InstrumentEditPane {
         // Row 2: Depth
         {
             let is_sel = self.selected_row == global_row;
-            render_value_row_buf(buf, content_x, y, "Depth", self.lfo.depth, 0.0, 1.0, is_sel, self.editing && is_sel, &self.edit_input);
+            if is_sel { selected_y = y; }
+            render_value_row_buf(buf, content_x, y, "Depth", lfo.depth, 0.0, 1.0, is_sel, self.editing && is_sel, &self.edit_input);
             y += 1;
             global_row += 1;
         }
@@ -905,7 +1604,14 @@ impl Pane for InstrumentEditPane {
         // Row 3: Shape and Target
         {
             let is_sel = self.selected_row == global_row;
-            let shape_val = format!("{} → {}", self.lfo.shape.name(), self.lfo.target.name());
+            if is_sel { selected_y = y; }
+            let source = ModRouteSource::Lfo(self.lfo_index);
+            let targets: Vec<&str> = self.mod_routings.iter()
+                .filter(|r| r.source == source)
+                .map(|r| r.destination.name())
+                .collect();
+            let targets = if targets.is_empty() { "-".to_string() } else { targets.join(", ") };
+            let shape_val = format!("{} → {}", lfo.shape.name(), targets);
             render_label_value_row_buf(buf, content_x, y, "Shape/Dest", &shape_val, Color::PINK, is_sel);
             y += 1;
             global_row += 1;
@@ -913,31 +1619,162 @@ impl Pane for InstrumentEditPane {
         y += 1;
 
         // === ENVELOPE SECTION ===
+        let env_name = match self.shown_mod_envelope() {
+            Some(source) => source.name(),
+            None => "Amp".to_string(),
+        };
         Paragraph::new(Line::from(Span::styled(
-            "ENVELOPE (ADSR)  (p: poly, r: track)",
+            format!("ENVELOPE: {} (ADSR)  (p: poly, r: track, j/J: next/add)", env_name),
             ratatui::style::Style::from(Style::new().fg(Color::ENV_COLOR).bold()),
         ))).render(RatatuiRect::new(content_x, y, inner.width.saturating_sub(2), 1), buf);
         y += 1;
 
         let env_labels = ["Attack", "Decay", "Sustain", "Release"];
-        let env_values = [
-            self.amp_envelope.attack,
-            self.amp_envelope.decay,
-            self.amp_envelope.sustain,
-            self.amp_envelope.release,
-        ];
+        let env = self.envelope();
+        let env_values = [env.attack, env.decay, env.sustain, env.release];
         let env_maxes = [5.0, 5.0, 1.0, 5.0];
 
         for (label, (val, max)) in env_labels.iter().zip(env_values.iter().zip(env_maxes.iter())) {
             let is_sel = self.selected_row == global_row;
+            if is_sel { selected_y = y; }
             render_value_row_buf(buf, content_x, y, label, *val, 0.0, *max, is_sel, self.editing && is_sel, &self.edit_input);
             y += 1;
             global_row += 1;
         }
+        y += 1;
+
+        // === MOD MATRIX ===
+        Paragraph::new(Line::from(Span::styled(
+            "MOD MATRIX  (a: add, d: remove, s: source, m: dest, \u{2190}/\u{2192}: amount)",
+            ratatui::style::Style::from(Style::new().fg(Color::PINK).bold()),
+        ))).render(RatatuiRect::new(content_x, y, inner.width.saturating_sub(2), 1), buf);
+        y += 1;
+
+        if self.mod_routings.is_empty() {
+            let is_sel = self.selected_row == global_row;
+            if is_sel { selected_y = y; }
+            render_label_value_row_buf(buf, content_x, y, "(no routings)", "", theme().muted, is_sel);
+            y += 1;
+            global_row += 1;
+        }
+        for routing in &self.mod_routings {
+            let is_sel = self.selected_row == global_row;
+            if is_sel { selected_y = y; }
+            let label = routing.source.name();
+            if self.editing && is_sel {
+                render_label_value_row_buf(buf, content_x, y, &label, "", Color::PINK, is_sel);
+                self.edit_input.render_buf(buf, content_x + 16, y, 10);
+            } else {
+                let value = format!("→ {:8} {:+.2}", routing.destination.name(), routing.amount);
+                render_label_value_row_buf(buf, content_x, y, &label, &value, Color::PINK, is_sel);
+            }
+            y += 1;
+            global_row += 1;
+        }
+
+        // === OUTPUT ===
+        let is_sel = self.selected_row == global_row;
+        if is_sel { selected_y = y; }
+        render_value_row_buf(
+            buf, content_x, y, "Trim (dB)", self.trim, TRIM_RANGE_DB.0, TRIM_RANGE_DB.1,
+            is_sel, self.editing && is_sel, &self.edit_input,
+        );
+        y += 1;
+        global_row += 1;
+
+        // Glide only acts on monophonic instruments
+        let is_sel = self.selected_row == global_row;
+        if is_sel { selected_y = y; }
+        let glide_label = if self.polyphonic { "Glide (mono)" } else { "Glide (s)" };
+        render_value_row_buf(
+            buf, content_x, y, glide_label, self.glide, GLIDE_RANGE.0, GLIDE_RANGE.1,
+            is_sel, self.editing && is_sel, &self.edit_input,
+        );
+        y += 1;
+        global_row += 1;
+
+        // Unison stacks only on the built-in oscillators
+        let is_sel = self.selected_row == global_row;
+        if is_sel { selected_y = y; }
+        let unison = Param {
+            name: if self.source.is_oscillator() { "Unison" } else { "Unison (osc)" }.to_string(),
+            value: ParamValue::Int(self.unison_voices as i32),
+            min: 1.0,
+            max: MAX_UNISON_VOICES as f32,
+        };
+        render_param_row_buf(buf, content_x, y, &unison, is_sel, self.editing && is_sel, &self.edit_input);
+        y += 1;
+        global_row += 1;
+
+        let is_sel = self.selected_row == global_row;
+        if is_sel { selected_y = y; }
+        render_value_row_buf(
+            buf, content_x, y, "Detune (ct)", self.detune, 0.0, MAX_DETUNE_CENTS,
+            is_sel, self.editing && is_sel, &self.edit_input,
+        );
+        y += 1;
+        global_row += 1;
+
+        // Voice spread places polyphonic notes around the instrument's pan
+        let is_sel = self.selected_row == global_row;
+        if is_sel { selected_y = y; }
+        let spread_label = if self.polyphonic { "Spread" } else { "Spread (poly)" };
+        render_value_row_buf(
+            buf, content_x, y, spread_label, self.voice_spread, 0.0, 1.0,
+            is_sel, self.editing && is_sel, &self.edit_input,
+        );
+        y += 1;
+        global_row += 1;
+
+        let is_sel = self.selected_row == global_row;
+        if is_sel { selected_y = y; }
+        if self.editing && is_sel {
+            render_label_value_row_buf(buf, content_x, y, "Spread by", "", theme().foreground, is_sel);
+            self.edit_input.render_buf(buf, content_x + 16, y, 10);
+        } else {
+            render_label_value_row_buf(
+                buf, content_x, y, "Spread by", self.voice_spread_mode.name(), theme().foreground, is_sel,
+            );
+        }
+        y += 1;
+        global_row += 1;
+
+        // Key range limits live notes, for splits
+        for (label, note) in [("Key low", self.key_low), ("Key high", self.key_high)] {
+            let is_sel = self.selected_row == global_row;
+            if is_sel { selected_y = y; }
+            if self.editing && is_sel {
+                render_label_value_row_buf(buf, content_x, y, label, "", theme().foreground, is_sel);
+                self.edit_input.render_buf(buf, content_x + 16, y, 10);
+            } else {
+                let value = format!("{} ({})", pitch_name(note), note);
+                render_label_value_row_buf(buf, content_x, y, label, &value, theme().foreground, is_sel);
+            }
+            y += 1;
+            global_row += 1;
+        }
 
         // Suppress unused variable warning
         let _ = global_row;
 
+        let content_len = y - content_top;
+        let visible = inner.height.saturating_sub(3); // top margin and help line
+        let scroll = selected_y
+            .saturating_sub(content_top)
+            .saturating_sub(visible / 2)
+            .min(content_len.saturating_sub(visible));
+        for row in 0..visible.min(content_len) {
+            for col in inner.x..inner.x + inner.width {
+                if let (Some(src), Some(dst)) = (
+                    content.cell((col, content_top + scroll + row)),
+                    screen.cell_mut((col, content_top + row)),
+                ) {
+                    *dst = src.clone();
+                }
+            }
+        }
+        let buf = screen;
+
         // Help text
         let help_y = rect.y + rect.height - 2;
         let help_text = if self.piano.is_active() {
@@ -945,10 +1782,12 @@ impl Pane for InstrumentEditPane {
         } else {
             "\u{2191}/\u{2193}: move | Tab/S-Tab: section | \u{2190}/\u{2192}: adjust | \\: zero | /: piano | Esc: done"
         };
-        Paragraph::new(Line::from(Span::styled(
-            help_text,
-            ratatui::style::Style::from(Style::new().fg(Color::DARK_GRAY)),
-        ))).render(RatatuiRect::new(content_x, help_y, inner.width.saturating_sub(2), 1), buf);
+        let help_line = match &self.edit_error {
+            Some(error) => Span::styled(error.as_str(), ratatui::style::Style::from(Style::new().fg(Color::BLACK).bg(Color::ORANGE).bold())),
+            None => Span::styled(help_text, ratatui::style::Style::from(Style::new().fg(theme().muted))),
+        };
+        Paragraph::new(Line::from(help_line))
+            .render(RatatuiRect::new(content_x, help_y, inner.width.saturating_sub(2), 1), buf);
     }
 
     fn handle_mouse(&mut self, event: &MouseEvent, _area: RatatuiRect, _state: &AppState) -> Action {
@@ -1017,16 +1856,16 @@ fn render_param_row_buf(
     if is_selected {
         if let Some(cell) = buf.cell_mut((x, y)) {
             cell.set_char('>').set_style(
-                ratatui::style::Style::from(Style::new().fg(Color::WHITE).bg(Color::SELECTION_BG).bold()),
+                ratatui::style::Style::from(Style::new().fg(theme().foreground).bg(theme().selection).bold()),
             );
         }
     }
 
     // Param name
     let name_style = if is_selected {
-        ratatui::style::Style::from(Style::new().fg(Color::CYAN).bg(Color::SELECTION_BG))
+        ratatui::style::Style::from(Style::new().fg(theme().accent).bg(theme().selection))
     } else {
-        ratatui::style::Style::from(Style::new().fg(Color::CYAN))
+        ratatui::style::Style::from(Style::new().fg(theme().accent))
     };
     let name_str = format!("{:12}", param.name);
     for (j, ch) in name_str.chars().enumerate() {
@@ -1043,7 +1882,7 @@ fn render_param_row_buf(
     };
     let slider = render_slider(val, min, max, 16);
     let slider_style = if is_selected {
-        ratatui::style::Style::from(Style::new().fg(Color::LIME).bg(Color::SELECTION_BG))
+        ratatui::style::Style::from(Style::new().fg(Color::LIME).bg(theme().selection))
     } else {
         ratatui::style::Style::from(Style::new().fg(Color::LIME))
     };
@@ -1058,14 +1897,15 @@ fn render_param_row_buf(
         edit_input.render_buf(buf, x + 34, y, 10);
     } else {
         let value_str = match &param.value {
+            ParamValue::Int(v) if param.name == "input" => AudioInputMode::from_index(*v).name().to_string(),
             ParamValue::Float(v) => format!("{:.2}", v),
             ParamValue::Int(v) => format!("{}", v),
             ParamValue::Bool(v) => format!("{}", v),
         };
         let val_style = if is_selected {
-            ratatui::style::Style::from(Style::new().fg(Color::WHITE).bg(Color::SELECTION_BG))
+            ratatui::style::Style::from(Style::new().fg(theme().foreground).bg(theme().selection))
         } else {
-            ratatui::style::Style::from(Style::new().fg(Color::WHITE))
+            ratatui::style::Style::from(Style::new().fg(theme().foreground))
         };
         let formatted = format!("{:10}", value_str);
         for (j, ch) in formatted.chars().enumerate() {
@@ -1089,16 +1929,16 @@ fn render_value_row_buf(
     if is_selected {
         if let Some(cell) = buf.cell_mut((x, y)) {
             cell.set_char('>').set_style(
-                ratatui::style::Style::from(Style::new().fg(Color::WHITE).bg(Color::SELECTION_BG).bold()),
+                ratatui::style::Style::from(Style::new().fg(theme().foreground).bg(theme().selection).bold()),
             );
         }
     }
 
     // Label
     let name_style = if is_selected {
-        ratatui::style::Style::from(Style::new().fg(Color::CYAN).bg(Color::SELECTION_BG))
+        ratatui::style::Style::from(Style::new().fg(theme().accent).bg(theme().selection))
     } else {
-        ratatui::style::Style::from(Style::new().fg(Color::CYAN))
+        ratatui::style::Style::from(Style::new().fg(theme().accent))
     };
     let name_str = format!("{:12}", name);
     for (j, ch) in name_str.chars().enumerate() {
@@ -1110,7 +1950,7 @@ fn render_value_row_buf(
     // Slider
     let slider = render_slider(value, min, max, 16);
     let slider_style = if is_selected {
-        ratatui::style::Style::from(Style::new().fg(Color::LIME).bg(Color::SELECTION_BG))
+        ratatui::style::Style::from(Style::new().fg(Color::LIME).bg(theme().selection))
     } else {
         ratatui::style::Style::from(Style::new().fg(Color::LIME))
     };
@@ -1125,9 +1965,9 @@ fn render_value_row_buf(
         edit_input.render_buf(buf, x + 34, y, 10);
     } else {
         let val_style = if is_selected {
-            ratatui::style::Style::from(Style::new().fg(Color::WHITE).bg(Color::SELECTION_BG))
+            ratatui::style::Style::from(Style::new().fg(theme().foreground).bg(theme().selection))
         } else {
-            ratatui::style::Style::from(Style::new().fg(Color::WHITE))
+            ratatui::style::Style::from(Style::new().fg(theme().foreground))
         };
         let formatted = format!("{:.2}", value);
         for (j, ch) in formatted.chars().enumerate() {
@@ -1151,14 +1991,14 @@ fn render_label_value_row_buf(
     if is_selected {
         if let Some(cell) = buf.cell_mut((x, y)) {
             cell.set_char('>').set_style(
-                ratatui::style::Style::from(Style::new().fg(Color::WHITE).bg(Color::SELECTION_BG).bold()),
+                ratatui::style::Style::from(Style::new().fg(theme().foreground).bg(theme().selection).bold()),
             );
         }
     }
 
     let text = format!("{:12}  {}", label, value);
     let style = if is_selected {
-        ratatui::style::Style::from(Style::new().fg(color).bg(Color::SELECTION_BG))
+        ratatui::style::Style::from(Style::new().fg(color).bg(theme().selection))
     } else {
         ratatui::style::Style::from(Style::new().fg(color))
     };
@@ -1168,3 +2008,57 @@ fn render_label_value_row_buf(
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_signal_chain_follows_routing_order() {
+        let mut effects = vec![EffectSlot::new(EffectType::Delay), EffectSlot::new(EffectType::Reverb)];
+        effects[0].bypassed = true;
+        effects[1].enabled = false;
+        let filter = FilterConfig::new(FilterType::Hpf);
+        let chain = signal_chain(SourceType::Saw, Some(&filter), &effects, false);
+        let stages: Vec<ChainStage> = chain.iter().map(|(_, s)| *s).collect();
+        assert_eq!(
+            stages,
+            [ChainStage::Source, ChainStage::Filter, ChainStage::Bypassed, ChainStage::Off, ChainStage::Output],
+        );
+        assert_eq!(chain[2].0, "Delay byp");
+
+        // Reordering shows straight away; AFL solo hears every effect dry
+        effects.swap(0, 1);
+        effects[1].bypassed = false;
+        let chain = signal_chain(SourceType::Saw, None, &effects, true);
+        assert_eq!(chain[1], ("Reverb off".to_string(), ChainStage::Off));
+        assert_eq!(chain[2], ("Delay byp".to_string(), ChainStage::Bypassed));
+    }
+
+    #[test]
+    fn test_nav_position_returns_to_the_section() {
+        let mut pane = InstrumentEditPane::new(Keymap::new());
+        pane.selected_row = pane.section_range(Section::Envelope).0 + 2;
+        let position = pane.nav_position();
+        pane.selected_row = 0;
+        pane.restore_nav_position(position);
+        assert_eq!(pane.current_section(), Section::Envelope);
+        assert_eq!(pane.selected_row, pane.section_range(Section::Envelope).0);
+        // Unknown positions leave the row alone
+        pane.restore_nav_position(99);
+        assert_eq!(pane.current_section(), Section::Envelope);
+    }
+
+    #[test]
+    fn test_long_chain_scrolls_to_focus() {
+        let widths = [5; 8];
+        assert_eq!(chain_window(&widths, 3, 100), (0, 8));
+        // Two stages and an arrow between both overflow marks
+        let (start, end) = chain_window(&widths, 6, 17);
+        assert!(start <= 6 && 6 < end);
+        assert_eq!(end - start, 2);
+        assert_eq!(chain_window(&widths, 0, 15), (0, 2));
+        // A stage wider than the pane still shows on its own
+        assert_eq!(chain_window(&[40, 5], 0, 10), (0, 1));
+    }
+}
@@ -5,9 +5,11 @@ use ratatui::layout::Rect as RatatuiRect;
 use ratatui::text::{Line, Span};
 use ratatui::widgets::{Block, Borders, Paragraph, Widget};
 
-use crate::state::{AppState, SourceType};
-use crate::ui::layout_helpers::center_rect;
-use crate::ui::{Action, NavAction, InstrumentAction, SessionAction, Color, InputEvent, KeyCode, Keymap, MouseEvent, MouseEventKind, MouseButton, PadKeyboard, Pane, PianoKeyboard, Style, ToggleResult, translate_key};
+use crate::audio::engine::MAX_VOICES_PER_INSTRUMENT;
+use crate::state::{AppState, InstrumentId, SourceType};
+use crate::ui::layout_helpers::fit_rect;
+use crate::ui::widgets::TextInput;
+use crate::ui::{Action, NavAction, InstrumentAction, SessionAction, Color, InputEvent, KeyCode, Keymap, MouseEvent, MouseEventKind, MouseButton, PadKeyboard, Pane, PianoKeyboard, Style, theme, ToggleResult, translate_key};
 
 fn source_color(source: SourceType) -> Color {
     match source {
@@ -27,6 +29,8 @@ pub struct InstrumentPane {
     keymap: Keymap,
     piano: PianoKeyboard,
     pad_keyboard: PadKeyboard,
+    renaming: Option<InstrumentId>,
+    rename_input: TextInput,
 }
 
 impl InstrumentPane {
@@ -35,9 +39,15 @@ impl InstrumentPane {
             keymap,
             piano: PianoKeyboard::new(),
             pad_keyboard: PadKeyboard::new(),
+            renaming: None,
+            rename_input: TextInput::new(""),
         }
     }
 
+    pub fn is_editing(&self) -> bool {
+        self.renaming.is_some()
+    }
+
     fn format_filter(instrument: &crate::state::instrument::Instrument) -> String {
         match &instrument.filter {
             Some(f) => format!("[{}]", f.filter_type.name()),
@@ -95,8 +105,57 @@ impl Pane for InstrumentPane {
                     Action::None
                 }
             }
+            "audition" => {
+                if let Some(instrument) = state.instruments.selected_instrument() {
+                    Action::Instrument(InstrumentAction::Audition(instrument.id))
+                } else {
+                    Action::None
+                }
+            }
             "save" => Action::Session(SessionAction::Save),
             "load" => Action::Session(SessionAction::Load),
+            "rename" => {
+                if let Some(instrument) = state.instruments.selected_instrument() {
+                    self.rename_input.set_value(&instrument.name);
+                    self.rename_input.set_focused(true);
+                    self.renaming = Some(instrument.id);
+                    Action::PushLayer("text_edit")
+                } else {
+                    Action::None
+                }
+            }
+            "move_up" | "move_down" => {
+                if let Some(instrument) = state.instruments.selected_instrument() {
+                    if action == "move_up" {
+                        Action::Instrument(InstrumentAction::MoveUp(instrument.id))
+                    } else {
+                        Action::Instrument(InstrumentAction::MoveDown(instrument.id))
+                    }
+                } else {
+                    Action::None
+                }
+            }
+            "color" => {
+                if let Some(instrument) = state.instruments.selected_instrument() {
+                    Action::Instrument(InstrumentAction::CycleColor(instrument.id))
+                } else {
+                    Action::None
+                }
+            }
+
+            // Text edit layer actions
+            "text:confirm" => {
+                self.rename_input.set_focused(false);
+                match self.renaming.take() {
+                    Some(id) => Action::Instrument(InstrumentAction::Rename(id, self.rename_input.value().to_string())),
+                    None => Action::None,
+                }
+            }
+            "text:cancel" => {
+                self.rename_input.set_focused(false);
+                self.renaming = None;
+                Action::None
+            }
 
             // Piano layer actions
             "piano:escape" => {
@@ -143,14 +202,21 @@ impl Pane for InstrumentPane {
         }
     }
 
+    fn handle_raw_input(&mut self, event: &InputEvent, _state: &AppState) -> Action {
+        if self.renaming.is_some() {
+            self.rename_input.handle_input(event);
+        }
+        Action::None
+    }
+
     fn render(&self, area: RatatuiRect, buf: &mut Buffer, state: &AppState) {
-        let rect = center_rect(area, 97, 29);
+        let rect = fit_rect(area, 97, 29);
 
         let block = Block::default()
             .borders(Borders::ALL)
             .title(" Instruments ")
-            .border_style(ratatui::style::Style::from(Style::new().fg(Color::CYAN)))
-            .title_style(ratatui::style::Style::from(Style::new().fg(Color::CYAN)));
+            .border_style(ratatui::style::Style::from(Style::new().fg(theme().accent)))
+            .title_style(ratatui::style::Style::from(Style::new().fg(theme().accent)));
         let inner = block.inner(rect);
         block.render(rect, buf);
 
@@ -159,7 +225,7 @@ impl Pane for InstrumentPane {
 
         Paragraph::new(Line::from(Span::styled(
             "Instruments:",
-            ratatui::style::Style::from(Style::new().fg(Color::CYAN).bold()),
+            ratatui::style::Style::from(Style::new().fg(theme().accent).bold()),
         ))).render(RatatuiRect::new(content_x, content_y, inner.width.saturating_sub(2), 1), buf);
 
         let list_y = content_y + 2;
@@ -168,14 +234,14 @@ impl Pane for InstrumentPane {
         if state.instruments.instruments.is_empty() {
             Paragraph::new(Line::from(Span::styled(
                 "(no instruments — press 'a' to add)",
-                ratatui::style::Style::from(Style::new().fg(Color::DARK_GRAY)),
+                ratatui::style::Style::from(Style::new().fg(theme().muted)),
             ))).render(RatatuiRect::new(content_x + 2, list_y, inner.width.saturating_sub(4), 1), buf);
         }
 
         let scroll_offset = state.instruments.selected
             .map(|s| if s >= max_visible { s - max_visible + 1 } else { 0 })
             .unwrap_or(0);
-        let sel_bg = ratatui::style::Style::from(Style::new().bg(Color::SELECTION_BG));
+        let sel_bg = ratatui::style::Style::from(Style::new().bg(theme().selection));
 
         for (i, instrument) in state.instruments.instruments.iter().enumerate().skip(scroll_offset) {
             let row = i - scroll_offset;
@@ -192,41 +258,55 @@ impl Pane for InstrumentPane {
             if is_selected {
                 if let Some(cell) = buf.cell_mut((content_x, y)) {
                     cell.set_char('>').set_style(
-                        ratatui::style::Style::from(Style::new().fg(Color::WHITE).bg(Color::SELECTION_BG).bold()),
+                        ratatui::style::Style::from(Style::new().fg(theme().foreground).bg(theme().selection).bold()),
                     );
                 }
             }
 
             let mk_style = |fg: Color| -> ratatui::style::Style {
                 if is_selected {
-                    ratatui::style::Style::from(Style::new().fg(fg).bg(Color::SELECTION_BG))
+                    ratatui::style::Style::from(Style::new().fg(fg).bg(theme().selection))
                 } else {
                     ratatui::style::Style::from(Style::new().fg(fg))
                 }
             };
 
             // Build row as a Line with multiple spans
-            let name_str = format!("{:14}", &instrument.name[..instrument.name.len().min(14)]);
+            let name_str = format!("{:14}", instrument.name.chars().take(14).collect::<String>());
             let source_str = format!(" {:10}", instrument.source.name());
             let filter_str = format!(" {:12}", Self::format_filter(instrument));
             let fx_raw = Self::format_effects(instrument);
             let fx_str = format!(" {:18}", &fx_raw[..fx_raw.len().min(18)]);
             let level_str = format!(" {}", Self::format_level(instrument.level));
+            let voices = state.voice_count(instrument.id);
+            let voice_str = if voices > 0 {
+                format!("  {:>2}/{} voices", voices, MAX_VOICES_PER_INSTRUMENT)
+            } else {
+                String::new()
+            };
+            // At the limit, the next note steals the oldest
+            let voice_c = if voices >= MAX_VOICES_PER_INSTRUMENT { Color::METER_HIGH } else { theme().muted };
 
             let source_c = source_color(instrument.source);
+            let name_c = instrument.color.map(Color::from_tag).unwrap_or(theme().foreground);
 
             let line = Line::from(vec![
-                Span::styled(name_str, mk_style(Color::WHITE)),
+                Span::styled(name_str, mk_style(name_c)),
                 Span::styled(source_str, mk_style(source_c)),
                 Span::styled(filter_str, mk_style(Color::FILTER_COLOR)),
                 Span::styled(fx_str, mk_style(Color::FX_COLOR)),
                 Span::styled(level_str, mk_style(Color::LIME)),
+                Span::styled(voice_str, mk_style(voice_c)),
             ]);
             let line_width = inner.width.saturating_sub(3);
             Paragraph::new(line).render(
                 RatatuiRect::new(content_x + 2, y, line_width, 1), buf,
             );
 
+            if self.renaming == Some(instrument.id) {
+                self.rename_input.render_buf(buf, content_x + 2, y, 14);
+            }
+
             // Fill rest of line with selection bg
             if is_selected {
                 let fill_start = content_x + 2 + line_width;
@@ -269,21 +349,22 @@ impl Pane for InstrumentPane {
 
         // Help text
         let help_y = rect.y + rect.height - 2;
+        let help_width = inner.width.saturating_sub(2);
         let help_text = if self.pad_keyboard.is_active() {
-            "R T Y U / F G H J / V B N M: trigger pads | /: cycle | Esc: exit"
+            "R T Y U / F G H J / V B N M: trigger pads | /: cycle | Esc: exit".to_string()
         } else if self.piano.is_active() {
-            "Play keys | [/]: octave | \u{2191}/\u{2193}: select instrument | /: cycle | Esc: exit"
+            "Play keys | [/]: octave | \u{2191}/\u{2193}: select instrument | /: cycle | Esc: exit".to_string()
         } else {
-            "a: add | d: delete | Enter: edit | /: piano | w: save | o: load"
+            self.keymap.footer_hint(help_width as usize)
         };
         Paragraph::new(Line::from(Span::styled(
             help_text,
-            ratatui::style::Style::from(Style::new().fg(Color::DARK_GRAY)),
-        ))).render(RatatuiRect::new(content_x, help_y, inner.width.saturating_sub(2), 1), buf);
+            ratatui::style::Style::from(Style::new().fg(theme().muted)),
+        ))).render(RatatuiRect::new(content_x, help_y, help_width, 1), buf);
     }
 
     fn handle_mouse(&mut self, event: &MouseEvent, area: RatatuiRect, state: &AppState) -> Action {
-        let rect = center_rect(area, 97, 29);
+        let rect = fit_rect(area, 97, 29);
         let inner_x = rect.x + 2;
         let inner_y = rect.y + 2;
         let content_y = inner_y + 1;
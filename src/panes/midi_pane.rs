@@ -0,0 +1,422 @@
+use std::any::Any;
+
+use ratatui::buffer::Buffer;
+use ratatui::layout::Rect as RatatuiRect;
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, Paragraph, Widget};
+
+use crate::midi::{self, MidiPortInfo};
+use crate::state::midi_recording::{ProgramChangeMode, MIDI_CHANNELS};
+use crate::state::{AppState, AutomationTarget, InstrumentId};
+use crate::ui::layout_helpers::fit_rect;
+use crate::ui::{Action, Color, InputEvent, Keymap, MidiAction, Pane, Style, theme};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum MidiPaneFocus {
+    Ports,
+    Channels,
+}
+
+pub struct MidiPane {
+    keymap: Keymap,
+    ports: Vec<MidiPortInfo>,
+    selected_port: usize, // 0 = "None" (disconnected), 1+ = index into ports
+    selected_channel: usize,
+    connected_port: Option<String>,
+    output_ports: Vec<MidiPortInfo>,
+    selected_output: usize, // 0 = "None" (disconnected), 1+ = index into output_ports
+    connected_output: Option<String>,
+    /// Index into the selected instrument's automation targets for CC learn
+    learn_index: usize,
+    message: String,
+    focus: MidiPaneFocus,
+}
+
+impl MidiPane {
+    pub fn new(keymap: Keymap) -> Self {
+        Self {
+            keymap,
+            ports: midi::enumerate_ports(),
+            selected_port: 0,
+            selected_channel: 0,
+            connected_port: None,
+            output_ports: midi::enumerate_output_ports(),
+            selected_output: 0,
+            connected_output: None,
+            learn_index: 0,
+            message: String::new(),
+            focus: MidiPaneFocus::Ports,
+        }
+    }
+
+    /// Update the connection status shown in the pane
+    pub fn set_connection(&mut self, port_name: Option<String>, message: &str) {
+        self.connected_port = port_name;
+        self.message = message.to_string();
+    }
+
+    /// Show a status message, e.g. a learned CC mapping
+    pub fn set_message(&mut self, message: &str) {
+        self.message = message.to_string();
+    }
+
+    /// The selected instrument's target that CC learn maps to
+    fn learn_target(&self, state: &AppState) -> Option<AutomationTarget> {
+        let targets = AutomationTarget::all_for(state.instruments.selected_instrument()?);
+        targets.get(self.learn_index % targets.len().max(1)).cloned()
+    }
+
+    /// Update the output connection status shown in the pane
+    pub fn set_output_connection(&mut self, port_name: Option<String>, message: &str) {
+        self.connected_output = port_name;
+        self.message = message.to_string();
+    }
+
+    fn refresh_ports(&mut self) {
+        self.ports = midi::enumerate_ports();
+        if self.selected_port > self.ports.len() {
+            self.selected_port = 0;
+        }
+        self.output_ports = midi::enumerate_output_ports();
+        if self.selected_output > self.output_ports.len() {
+            self.selected_output = 0;
+        }
+    }
+
+    /// Step the selected channel's route through [selected instrument, instrument 1..n]
+    fn cycle_route(&self, state: &AppState, delta: i32) -> Option<InstrumentId> {
+        let ids: Vec<InstrumentId> = state.instruments.instruments.iter().map(|i| i.id).collect();
+        let current = state.session.midi_recording.channel_routes[self.selected_channel];
+        // Position 0 = follow selection, 1+ = instrument index + 1
+        let pos = current
+            .and_then(|id| ids.iter().position(|&i| i == id))
+            .map(|i| i + 1)
+            .unwrap_or(0) as i32;
+        let count = ids.len() as i32 + 1;
+        let new_pos = (pos + delta).rem_euclid(count);
+        if new_pos == 0 {
+            None
+        } else {
+            ids.get(new_pos as usize - 1).copied()
+        }
+    }
+}
+
+impl Default for MidiPane {
+    fn default() -> Self {
+        Self::new(Keymap::new())
+    }
+}
+
+impl Pane for MidiPane {
+    fn id(&self) -> &'static str {
+        "midi"
+    }
+
+    fn handle_action(&mut self, action: &str, _event: &InputEvent, state: &AppState) -> Action {
+        match action {
+            "next_section" => {
+                self.focus = match self.focus {
+                    MidiPaneFocus::Ports => MidiPaneFocus::Channels,
+                    MidiPaneFocus::Channels => MidiPaneFocus::Ports,
+                };
+                Action::None
+            }
+            "cycle_program_change" => Action::Midi(MidiAction::CycleProgramChangeMode),
+            "cycle_record_quantize" => Action::Midi(MidiAction::CycleRecordQuantize),
+            "toggle_record_automation" => Action::Midi(MidiAction::ToggleRecordAutomation),
+            "cycle_automation_freeze" => Action::Midi(MidiAction::CycleAutomationFreeze),
+            "cycle_output" => {
+                self.selected_output = (self.selected_output + 1) % (self.output_ports.len() + 1);
+                match self.selected_output.checked_sub(1).and_then(|i| self.output_ports.get(i)) {
+                    Some(port) => Action::Midi(MidiAction::ConnectOutput(port.index)),
+                    None => Action::Midi(MidiAction::DisconnectOutput),
+                }
+            }
+            "next_learn_target" => {
+                self.learn_index += 1;
+                Action::None
+            }
+            "learn_cc" => {
+                if state.session.midi_recording.learn_target.is_some() {
+                    Action::Midi(MidiAction::LearnCc(None))
+                } else {
+                    match self.learn_target(state) {
+                        Some(target) => Action::Midi(MidiAction::LearnCc(Some(target))),
+                        None => Action::None,
+                    }
+                }
+            }
+            "forget_cc" => match self.learn_target(state) {
+                Some(target) => Action::Midi(MidiAction::ForgetCc(target)),
+                None => Action::None,
+            },
+            "toggle_thru" => Action::Midi(MidiAction::ToggleThru),
+            "cycle_thru_channel" => Action::Midi(MidiAction::CycleThruChannel),
+            "thru_velocity_up" => Action::Midi(MidiAction::AdjustThruVelocity(0.05)),
+            "thru_velocity_down" => Action::Midi(MidiAction::AdjustThruVelocity(-0.05)),
+            "refresh_ports" => {
+                self.refresh_ports();
+                Action::None
+            }
+            "prev" => {
+                match self.focus {
+                    MidiPaneFocus::Ports => {
+                        let count = self.ports.len() + 1;
+                        self.selected_port = (self.selected_port + count - 1) % count;
+                    }
+                    MidiPaneFocus::Channels => {
+                        self.selected_channel = (self.selected_channel + MIDI_CHANNELS - 1) % MIDI_CHANNELS;
+                    }
+                }
+                Action::None
+            }
+            "next" => {
+                match self.focus {
+                    MidiPaneFocus::Ports => {
+                        self.selected_port = (self.selected_port + 1) % (self.ports.len() + 1);
+                    }
+                    MidiPaneFocus::Channels => {
+                        self.selected_channel = (self.selected_channel + 1) % MIDI_CHANNELS;
+                    }
+                }
+                Action::None
+            }
+            "confirm" => match self.focus {
+                MidiPaneFocus::Ports => {
+                    if self.selected_port == 0 {
+                        Action::Midi(MidiAction::Disconnect)
+                    } else {
+                        match self.ports.get(self.selected_port - 1) {
+                            Some(port) => Action::Midi(MidiAction::ConnectPort(port.index)),
+                            None => Action::None,
+                        }
+                    }
+                }
+                MidiPaneFocus::Channels => Action::None,
+            },
+            "route_next" | "route_prev" if self.focus == MidiPaneFocus::Channels => {
+                let delta = if action == "route_next" { 1 } else { -1 };
+                let route = self.cycle_route(state, delta);
+                Action::Midi(MidiAction::SetChannelRoute(self.selected_channel as u8, route))
+            }
+            "clear_route" if self.focus == MidiPaneFocus::Channels => {
+                Action::Midi(MidiAction::SetChannelRoute(self.selected_channel as u8, None))
+            }
+            _ => Action::None,
+        }
+    }
+
+    fn render(&self, area: RatatuiRect, buf: &mut Buffer, state: &AppState) {
+        let rect = fit_rect(area, 70, 30);
+
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .title(" MIDI Input ")
+            .border_style(ratatui::style::Style::from(Style::new().fg(Color::MIDI_COLOR)))
+            .title_style(ratatui::style::Style::from(Style::new().fg(Color::MIDI_COLOR)));
+        let inner = block.inner(rect);
+        block.render(rect, buf);
+
+        let x = inner.x + 1;
+        let w = inner.width.saturating_sub(2);
+        let bottom = inner.y + inner.height;
+        let label_style = ratatui::style::Style::from(Style::new().fg(theme().accent));
+        let normal_style = ratatui::style::Style::from(Style::new().fg(theme().foreground));
+        let dim_style = ratatui::style::Style::from(Style::new().fg(theme().muted));
+        let mut y = inner.y + 1;
+
+        // Connection status
+        let (status_text, status_color) = match &self.connected_port {
+            Some(name) => (name.as_str(), Color::METER_LOW),
+            None => ("Not connected", theme().muted),
+        };
+        let status_line = Line::from(vec![
+            Span::styled("Input: ", label_style),
+            Span::styled(status_text, ratatui::style::Style::from(Style::new().fg(status_color).bold())),
+        ]);
+        Paragraph::new(status_line).render(RatatuiRect::new(x, y, w, 1), buf);
+        y += 1;
+
+        let (output_text, output_color) = match &self.connected_output {
+            Some(name) => (name.as_str(), Color::METER_LOW),
+            None => ("Not connected", theme().muted),
+        };
+        let thru = &state.session.midi_recording.thru;
+        let thru_text = if thru.enabled {
+            let channel = thru.channel.map_or("in".to_string(), |ch| (ch + 1).to_string());
+            format!("  Thru: ch {}, vel {:.0}%", channel, thru.velocity_scale * 100.0)
+        } else {
+            "  Thru: off".to_string()
+        };
+        Paragraph::new(Line::from(vec![
+            Span::styled("Output: ", label_style),
+            Span::styled(output_text, ratatui::style::Style::from(Style::new().fg(output_color).bold())),
+            Span::styled(thru_text, normal_style),
+        ]))
+        .render(RatatuiRect::new(x, y, w, 1), buf);
+        y += 1;
+
+        let pc_mode = match state.session.midi_recording.program_change_mode {
+            ProgramChangeMode::Off => "Off",
+            ProgramChangeMode::SelectInstrument => "Select instrument",
+            ProgramChangeMode::SelectPattern => "Select drum pattern",
+        };
+        Paragraph::new(Line::from(vec![
+            Span::styled("Program Change: ", label_style),
+            Span::styled(pc_mode, normal_style),
+        ]))
+        .render(RatatuiRect::new(x, y, w, 1), buf);
+        y += 1;
+
+        let midi = &state.session.midi_recording;
+        let quantize = match midi.record_quantize {
+            Some(div) => format!("1/{}", div * 4),
+            None => "Off".to_string(),
+        };
+        let capture = if midi.record_automation { "CC/bend → automation" } else { "notes only" };
+        Paragraph::new(Line::from(vec![
+            Span::styled("Record: ", label_style),
+            Span::styled(format!("quantize {}, {}, offset {} ms", quantize, capture, state.record_offset_ms), normal_style),
+        ]))
+        .render(RatatuiRect::new(x, y, w, 1), buf);
+        y += 1;
+
+        let learn_text = match (&midi.learn_target, self.learn_target(state)) {
+            (Some(target), _) => format!("{} (move a control...)", target.name()),
+            (None, Some(target)) => {
+                let mapped: Vec<String> = midi.cc_mappings.iter()
+                    .filter(|m| m.target == target)
+                    .map(|m| match m.channel {
+                        Some(ch) => format!("CC {} ch {}", m.cc_number, ch + 1),
+                        None => format!("CC {}", m.cc_number),
+                    })
+                    .collect();
+                let mapped = if mapped.is_empty() { "unmapped".to_string() } else { mapped.join(", ") };
+                format!("{}: {}", target.name(), mapped)
+            }
+            (None, None) => "-".to_string(),
+        };
+        Paragraph::new(Line::from(vec![
+            Span::styled("CC learn: ", label_style),
+            Span::styled(learn_text, normal_style),
+        ]))
+        .render(RatatuiRect::new(x, y, w, 1), buf);
+        y += 1;
+
+        let freeze = match state.session.automation.freeze_division {
+            Some(div) => format!("every 1/{}", div * 4),
+            None => "Off".to_string(),
+        };
+        Paragraph::new(Line::from(vec![
+            Span::styled("Freeze automation on save: ", label_style),
+            Span::styled(freeze, normal_style),
+        ]))
+        .render(RatatuiRect::new(x, y, w, 1), buf);
+        y += 1;
+
+        if !self.message.is_empty() {
+            let msg: String = self.message.chars().take(w as usize).collect();
+            Paragraph::new(Line::from(Span::styled(
+                msg,
+                ratatui::style::Style::from(Style::new().fg(Color::SKY_BLUE)),
+            )))
+            .render(RatatuiRect::new(x, y, w, 1), buf);
+        }
+        y += 2;
+
+        // Port list
+        let ports_focused = self.focus == MidiPaneFocus::Ports;
+        let header_style = if ports_focused {
+            ratatui::style::Style::from(Style::new().fg(Color::GOLD))
+        } else {
+            dim_style
+        };
+        Paragraph::new(Line::from(Span::styled("── Input Port ──", header_style)))
+            .render(RatatuiRect::new(x, y, w, 1), buf);
+        y += 1;
+
+        let port_names = std::iter::once("None").chain(self.ports.iter().map(|p| p.name.as_str()));
+        for (i, name) in port_names.enumerate() {
+            if y >= bottom {
+                break;
+            }
+            let is_selected = i == self.selected_port;
+            let style = if is_selected && ports_focused {
+                ratatui::style::Style::from(Style::new().fg(Color::GOLD).bold())
+            } else {
+                normal_style
+            };
+            let marker = if is_selected { "> " } else { "  " };
+            Paragraph::new(Line::from(vec![
+                Span::styled(marker, style),
+                Span::styled(name, style),
+            ]))
+            .render(RatatuiRect::new(x, y, w, 1), buf);
+            y += 1;
+        }
+        y += 1;
+
+        // Channel routing
+        let channels_focused = self.focus == MidiPaneFocus::Channels;
+        let header_style = if channels_focused {
+            ratatui::style::Style::from(Style::new().fg(Color::GOLD))
+        } else {
+            dim_style
+        };
+        if y < bottom {
+            Paragraph::new(Line::from(Span::styled("── Channel Routing ──", header_style)))
+                .render(RatatuiRect::new(x, y, w, 1), buf);
+            y += 1;
+        }
+
+        let routes = &state.session.midi_recording.channel_routes;
+        for (ch, route) in routes.iter().enumerate() {
+            if y >= bottom.saturating_sub(1) {
+                break;
+            }
+            let is_selected = ch == self.selected_channel;
+            let target = match route {
+                Some(id) => state
+                    .instruments
+                    .instrument(*id)
+                    .map(|inst| inst.name.clone())
+                    .unwrap_or_else(|| format!("#{}", id)),
+                None => "(selected instrument)".to_string(),
+            };
+            let style = if is_selected && channels_focused {
+                ratatui::style::Style::from(Style::new().fg(Color::GOLD).bold())
+            } else if route.is_some() {
+                normal_style
+            } else {
+                dim_style
+            };
+            let marker = if is_selected { "> " } else { "  " };
+            Paragraph::new(Line::from(vec![
+                Span::styled(marker, style),
+                Span::styled(format!("Ch {:>2}  ", ch + 1), label_style),
+                Span::styled(target, style),
+            ]))
+            .render(RatatuiRect::new(x, y, w, 1), buf);
+            y += 1;
+        }
+
+        // Help text
+        let help_y = rect.y + rect.height - 2;
+        if help_y > inner.y {
+            Paragraph::new(Line::from(Span::styled(
+                self.keymap.footer_hint(w as usize),
+                dim_style,
+            )))
+            .render(RatatuiRect::new(x, help_y, w, 1), buf);
+        }
+    }
+
+    fn keymap(&self) -> &Keymap {
+        &self.keymap
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
@@ -3,7 +3,9 @@ mod file_browser_pane;
 mod frame_edit_pane;
 mod help_pane;
 mod home_pane;
+mod midi_pane;
 mod mixer_pane;
+mod phrase_browser_pane;
 mod piano_roll_pane;
 mod sequencer_pane;
 mod server_pane;
@@ -19,13 +21,54 @@ pub use file_browser_pane::FileBrowserPane;
 pub use frame_edit_pane::FrameEditPane;
 pub use help_pane::HelpPane;
 pub use home_pane::HomePane;
+pub use midi_pane::MidiPane;
 pub use mixer_pane::MixerPane;
+pub use phrase_browser_pane::PhraseBrowserPane;
 pub use piano_roll_pane::PianoRollPane;
 pub use sequencer_pane::SequencerPane;
-pub use server_pane::ServerPane;
+pub use server_pane::{ServerPane, SERVER_LOG_TAIL};
 pub use instrument_edit_pane::InstrumentEditPane;
 pub use instrument_pane::InstrumentPane;
 pub use sample_chopper_pane::SampleChopperPane;
 pub use logo_pane::LogoPane;
 pub use track_pane::TrackPane;
-pub use waveform_pane::WaveformPane;
\ No newline at end of file
+pub use waveform_pane::WaveformPane;
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::{AppState, SourceType};
+    use crate::ui::layout_helpers::{MIN_HEIGHT, MIN_WIDTH};
+    use crate::ui::{Keymap, Pane};
+    use ratatui::buffer::Buffer;
+    use ratatui::layout::Rect;
+
+    #[test]
+    fn test_panes_render_at_supported_sizes() {
+        let mut state = AppState::new();
+        state.add_instrument(SourceType::Saw);
+        let panes: Vec<Box<dyn Pane>> = vec![
+            Box::new(InstrumentPane::new(Keymap::new())),
+            Box::new(HomePane::new(Keymap::new())),
+            Box::new(AddPane::new(Keymap::new())),
+            Box::new(InstrumentEditPane::new(Keymap::new())),
+            Box::new(ServerPane::new(Keymap::new())),
+            Box::new(MixerPane::new(Keymap::new())),
+            Box::new(HelpPane::new(Keymap::new())),
+            Box::new(PianoRollPane::new(Keymap::new())),
+            Box::new(SequencerPane::new(Keymap::new())),
+            Box::new(FrameEditPane::new(Keymap::new())),
+            Box::new(SampleChopperPane::new(Keymap::new(), Keymap::new())),
+            Box::new(FileBrowserPane::new(Keymap::new())),
+            Box::new(TrackPane::new(Keymap::new())),
+            Box::new(WaveformPane::new(Keymap::new())),
+            Box::new(PhraseBrowserPane::new(Keymap::new())),
+        ];
+        for (width, height) in [(MIN_WIDTH, MIN_HEIGHT), (80, 24), (MIN_WIDTH, 60), (200, MIN_HEIGHT), (200, 60)] {
+            let area = Rect::new(0, 0, width, height);
+            for pane in &panes {
+                let mut buf = Buffer::empty(area);
+                pane.render(area, &mut buf, &state);
+            }
+        }
+    }
+}
@@ -0,0 +1,137 @@
+use std::any::Any;
+
+use ratatui::buffer::Buffer;
+use ratatui::layout::Rect as RatatuiRect;
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, Paragraph, Widget};
+
+use crate::state::music::pitch_name;
+use crate::state::AppState;
+use crate::ui::layout_helpers::fit_rect;
+use crate::ui::{Action, Color, InputEvent, Keymap, MouseEvent, MouseEventKind, NavAction, Pane, PianoRollAction, Style, theme};
+
+/// Phrase library, opened from the piano roll to insert a saved phrase at its cursor
+pub struct PhraseBrowserPane {
+    keymap: Keymap,
+    selected: usize,
+}
+
+impl PhraseBrowserPane {
+    pub fn new(keymap: Keymap) -> Self {
+        Self { keymap, selected: 0 }
+    }
+
+    fn move_selection(&mut self, delta: i32, count: usize) {
+        if count == 0 {
+            self.selected = 0;
+        } else {
+            self.selected = (self.selected as i32 + delta).clamp(0, count as i32 - 1) as usize;
+        }
+    }
+}
+
+impl Pane for PhraseBrowserPane {
+    fn id(&self) -> &'static str {
+        "phrase_browser"
+    }
+
+    fn handle_action(&mut self, action: &str, _event: &InputEvent, state: &AppState) -> Action {
+        let count = state.phrases.phrases.len();
+        self.selected = self.selected.min(count.saturating_sub(1));
+        match action {
+            "close" => Action::Nav(NavAction::PopPane),
+            "up" => {
+                self.move_selection(-1, count);
+                Action::None
+            }
+            "down" => {
+                self.move_selection(1, count);
+                Action::None
+            }
+            "insert" if count > 0 => Action::PianoRoll(PianoRollAction::InsertPhrase(self.selected, true)),
+            "insert_original" if count > 0 => Action::PianoRoll(PianoRollAction::InsertPhrase(self.selected, false)),
+            "delete" if count > 0 => {
+                let idx = self.selected;
+                self.selected = self.selected.min(count.saturating_sub(2));
+                Action::PianoRoll(PianoRollAction::DeletePhrase(idx))
+            }
+            _ => Action::None,
+        }
+    }
+
+    fn render(&self, area: RatatuiRect, buf: &mut Buffer, state: &AppState) {
+        let rect = fit_rect(area, 60, 20);
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .title(" Phrases ")
+            .border_style(ratatui::style::Style::from(Style::new().fg(Color::GOLD)))
+            .title_style(ratatui::style::Style::from(Style::new().fg(Color::GOLD)));
+        let inner = block.inner(rect);
+        block.render(rect, buf);
+
+        let phrases = &state.phrases.phrases;
+        let visible = inner.height.saturating_sub(3) as usize;
+        if phrases.is_empty() {
+            Paragraph::new(Line::from(Span::styled(
+                "(no phrases: select notes in the piano roll and save them)",
+                ratatui::style::Style::from(Style::new().fg(theme().muted)),
+            )))
+            .render(RatatuiRect::new(inner.x + 1, inner.y + 1, inner.width.saturating_sub(2), 1), buf);
+        }
+
+        let selected = self.selected.min(phrases.len().saturating_sub(1));
+        let scroll = selected.saturating_sub(visible.saturating_sub(1));
+        for (i, phrase) in phrases.iter().enumerate().skip(scroll).take(visible) {
+            let y = inner.y + 1 + (i - scroll) as u16;
+            let (low, high) = phrase.span();
+            let detail = format!(
+                "{:>3} notes {:>5.1} beats  {} to {}",
+                phrase.notes.len(),
+                phrase.beats(),
+                pitch_name((phrase.root as i16 + low as i16).clamp(0, 127) as u8),
+                pitch_name((phrase.root as i16 + high as i16).clamp(0, 127) as u8),
+            );
+            let name_width = (inner.width as usize).saturating_sub(detail.len() + 4).max(8);
+            let name: String = phrase.name.chars().take(name_width).collect();
+            let (name_style, detail_style) = if i == selected {
+                (
+                    Style::new().fg(theme().foreground).bg(theme().selection).bold(),
+                    Style::new().fg(Color::SKY_BLUE).bg(theme().selection),
+                )
+            } else {
+                (Style::new().fg(theme().foreground), Style::new().fg(theme().muted))
+            };
+            let line = Line::from(vec![
+                Span::styled(format!("{:<width$}  ", name, width = name_width), ratatui::style::Style::from(name_style)),
+                Span::styled(detail, ratatui::style::Style::from(detail_style)),
+            ]);
+            Paragraph::new(line).render(RatatuiRect::new(inner.x + 1, y, inner.width.saturating_sub(2), 1), buf);
+        }
+
+        let help_y = rect.y + rect.height - 2;
+        let help_area = RatatuiRect::new(inner.x + 1, help_y, inner.width.saturating_sub(2), 1);
+        Paragraph::new(Line::from(Span::styled(
+            self.keymap.footer_hint(help_area.width as usize),
+            ratatui::style::Style::from(Style::new().fg(theme().muted)),
+        )))
+        .render(help_area, buf);
+    }
+
+    fn handle_mouse(&mut self, event: &MouseEvent, _area: RatatuiRect, state: &AppState) -> Action {
+        let count = state.phrases.phrases.len();
+        match event.kind {
+            MouseEventKind::ScrollUp => self.move_selection(-1, count),
+            MouseEventKind::ScrollDown => self.move_selection(1, count),
+            _ => {}
+        }
+        Action::None
+    }
+
+    fn keymap(&self) -> &Keymap {
+        &self.keymap
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
@@ -5,17 +5,18 @@ use ratatui::layout::Rect as RatatuiRect;
 use ratatui::text::{Line, Span};
 use ratatui::widgets::{Block, Borders, Paragraph, Widget};
 
-use crate::state::piano_roll::PianoRollState;
-use crate::state::AppState;
-use crate::ui::layout_helpers::center_rect;
-use crate::ui::{Action, Color, InputEvent, KeyCode, Keymap, MouseEvent, MouseEventKind, MouseButton, Pane, PianoKeyboard, PianoRollAction, Style, ToggleResult, translate_key};
-
-/// MIDI note name for a given pitch (0-127)
-fn note_name(pitch: u8) -> String {
-    let names = ["C", "C#", "D", "D#", "E", "F", "F#", "G", "G#", "A", "A#", "B"];
-    let octave = (pitch / 12) as i8 - 1;
-    let name = names[(pitch % 12) as usize];
-    format!("{}{}", name, octave)
+use crate::state::piano_roll::{rescale_tick, Note, PianoRollState};
+use crate::state::music::{pitch_freq, pitch_name_in_key};
+use crate::state::ui_state::{PianoRollView, MAX_PIANO_ZOOM, MIN_PIANO_ZOOM};
+use crate::state::{AppState, InstrumentState, SessionState};
+use crate::ui::layout_helpers::fit_rect;
+use crate::ui::widgets::TextInput;
+use crate::ui::{Action, Color, InputEvent, KeyCode, Keymap, MouseEvent, MouseEventKind, MouseButton, NavAction, Pane, PianoKeyboard, PianoRollAction, SessionAction, Style, theme, ToggleResult, translate_key};
+
+/// Position as `bar:beat:tick` (bar and beat 1-based)
+fn format_bbt(piano_roll: &PianoRollState, tick: u32) -> String {
+    let (bar, beat, tick) = piano_roll.tick_to_bbt(tick);
+    format!("{}:{}:{:03}", bar, beat, tick)
 }
 
 /// Check if a pitch is a black key
@@ -23,6 +24,39 @@ fn is_black_key(pitch: u8) -> bool {
     matches!(pitch % 12, 1 | 3 | 6 | 8 | 10)
 }
 
+/// Rows of the velocity lane under the grid
+const VELOCITY_LANE_HEIGHT: u16 = 2;
+
+/// Velocity change per press when editing notes' velocities
+const NOTE_VELOCITY_STEP: i8 = 8;
+
+/// Character for one row of a velocity bar, `row` counted up from the lane's
+/// bottom, in eighth-block steps
+fn velocity_bar(velocity: u8, row: u16, rows: u16) -> char {
+    const BLOCKS: [char; 9] = [' ', '▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+    let levels = rows as u32 * 8;
+    let filled = (velocity as u32 * levels).div_ceil(127);
+    BLOCKS[filled.saturating_sub(row as u32 * 8).min(8) as usize]
+}
+
+/// Text prompt shown on the status line
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Prompt {
+    GotoBar,
+    RenameMarker(u32),
+    PhraseName,
+}
+
+/// One step written in step-record mode (no pitches = rest)
+#[derive(Debug, Clone)]
+pub struct StepEntry {
+    pub tick: u32,
+    pub track: usize,
+    pub pitches: Vec<u8>,
+    /// Notes the step wrote over, put back when it is undone
+    pub replaced: Vec<Note>,
+}
+
 pub struct PianoRollPane {
     keymap: Keymap,
     // Cursor state
@@ -33,12 +67,27 @@ pub struct PianoRollPane {
     view_bottom_pitch: u8,  // Lowest visible pitch
     view_start_tick: u32,   // Leftmost visible tick
     zoom_level: u8,         // 1=finest, higher=wider beats. Ticks per cell.
+    ticks_per_beat: u32,    // Project resolution the grid and ticks below are in
     // Note placement defaults
     default_duration: u32,
     default_velocity: u8,
     // Piano keyboard mode
     piano: PianoKeyboard,
     recording: bool,            // True when recording notes from piano keyboard
+    live_quantize: bool,        // Snap live-recorded notes to the grid as they're played
+    // Step-record mode
+    step_record: bool,
+    step_history: Vec<StepEntry>,
+    step_held: usize,           // MIDI notes still held in the current step chord
+    // Jump-to-bar / marker-name prompt
+    prompt: Option<Prompt>,
+    prompt_input: TextInput,
+    // Marquee selection: the fixed corner while the box is being stretched, and the
+    // indices of the current track's loose notes it encloses. Edits that add, remove
+    // or reorder notes clear the selection, since they would shift the indices.
+    marquee_anchor: Option<(u32, u8)>,
+    selected_notes: Vec<usize>,
+    clipboard: Vec<Note>,
 }
 
 impl PianoRollPane {
@@ -50,11 +99,21 @@ impl PianoRollPane {
             current_track: 0,
             view_bottom_pitch: 48, // C3
             view_start_tick: 0,
-            zoom_level: 3, // Each cell = 120 ticks (1/4 beat at 480 tpb)
+            zoom_level: 3, // Each cell = 1/2 beat
+            ticks_per_beat: 480,
             default_duration: 480, // One beat
             default_velocity: 100,
             piano: PianoKeyboard::new(),
             recording: false,
+            live_quantize: false,
+            step_record: false,
+            step_history: Vec::new(),
+            step_held: 0,
+            prompt: None,
+            prompt_input: TextInput::new(""),
+            marquee_anchor: None,
+            selected_notes: Vec::new(),
+            clipboard: Vec::new(),
         }
     }
 
@@ -65,7 +124,153 @@ impl PianoRollPane {
     pub fn default_velocity(&self) -> u8 { self.default_velocity }
     pub fn current_track(&self) -> usize { self.current_track }
     pub fn is_recording(&self) -> bool { self.recording }
-    pub fn set_recording(&mut self, recording: bool) { self.recording = recording; }
+    pub fn set_recording(&mut self, recording: bool) {
+        if recording {
+            self.clear_selection();
+        }
+        self.recording = recording;
+    }
+    /// Grid that live-recorded notes snap to, when input quantize is on
+    pub fn live_quantize_grid(&self) -> Option<u32> {
+        self.live_quantize.then(|| self.ticks_per_cell())
+    }
+    pub fn is_step_recording(&self) -> bool { self.step_record }
+    pub fn is_editing(&self) -> bool { self.prompt.is_some() }
+    /// Indices of the selected notes in the current track's `notes`
+    #[allow(dead_code)]
+    pub fn selected_notes(&self) -> &[usize] { &self.selected_notes }
+
+    /// Cursor, track and zoom, for saving with the project
+    pub fn view(&self) -> PianoRollView {
+        PianoRollView {
+            cursor_tick: self.cursor_tick,
+            cursor_pitch: self.cursor_pitch,
+            track: self.current_track,
+            zoom_level: self.zoom_level,
+        }
+    }
+
+    /// Follow a change of project resolution, keeping the cursor, note length and
+    /// clipboard at the same musical position and length
+    pub fn set_ticks_per_beat(&mut self, ticks_per_beat: u32) {
+        let from = self.ticks_per_beat;
+        if ticks_per_beat == from {
+            return;
+        }
+        let scale = |tick: u32| rescale_tick(tick, from, ticks_per_beat);
+        self.ticks_per_beat = ticks_per_beat;
+        self.clear_selection();
+        self.cursor_tick = self.snap_tick(scale(self.cursor_tick));
+        self.view_start_tick = self.snap_tick(scale(self.view_start_tick));
+        self.default_duration = scale(self.default_duration).max(self.ticks_per_cell());
+        for note in &mut self.clipboard {
+            note.tick = scale(note.tick);
+            note.duration = scale(note.duration).max(1);
+        }
+        for entry in &mut self.step_history {
+            entry.tick = scale(entry.tick);
+            for note in &mut entry.replaced {
+                note.tick = scale(note.tick);
+                note.duration = scale(note.duration).max(1);
+            }
+        }
+        self.scroll_to_cursor();
+    }
+
+    /// Reopen a saved view, clamped to the loaded piano roll's tracks
+    pub fn restore_view(&mut self, view: &PianoRollView, piano_roll: &PianoRollState) {
+        let view = view.clamped(piano_roll.track_order.len());
+        self.set_ticks_per_beat(piano_roll.ticks_per_beat);
+        self.clear_selection();
+        self.current_track = view.track;
+        self.zoom_level = view.zoom_level;
+        self.cursor_pitch = view.cursor_pitch;
+        self.cursor_tick = self.snap_tick(view.cursor_tick);
+        self.scroll_to_cursor();
+    }
+
+    pub fn clear_selection(&mut self) {
+        self.marquee_anchor = None;
+        self.selected_notes.clear();
+    }
+
+    /// The marquee as (start tick, end tick, low pitch, high pitch), covering the
+    /// anchor and cursor cells
+    fn marquee_box(&self) -> Option<(u32, u32, u8, u8)> {
+        let (tick, pitch) = self.marquee_anchor?;
+        let start = tick.min(self.cursor_tick);
+        let end = tick.max(self.cursor_tick) + self.ticks_per_cell();
+        Some((start, end, pitch.min(self.cursor_pitch), pitch.max(self.cursor_pitch)))
+    }
+
+    /// Reselect the notes under the marquee after the cursor moves
+    fn extend_marquee(&mut self, state: &AppState) {
+        if let Some((start, end, low, high)) = self.marquee_box() {
+            self.selected_notes = state.session.piano_roll.notes_in_rect(self.current_track, start, end, low, high);
+        }
+    }
+
+    /// Notes a velocity edit applies to: the selection, else the note under the cursor
+    fn velocity_targets(&self, piano_roll: &PianoRollState) -> Vec<usize> {
+        if !self.selected_notes.is_empty() {
+            return self.selected_notes.clone();
+        }
+        piano_roll.note_under(self.current_track, self.cursor_pitch, self.cursor_tick).into_iter().collect()
+    }
+
+    pub fn set_cursor_tick(&mut self, tick: u32) {
+        self.cursor_tick = tick;
+        self.scroll_to_cursor();
+    }
+
+    fn open_prompt(&mut self, prompt: Prompt, label: &str, value: &str) -> Action {
+        self.prompt_input = TextInput::new(label).with_value(value);
+        self.prompt_input.set_focused(true);
+        self.prompt = Some(prompt);
+        Action::PushLayer("text_edit")
+    }
+
+    /// Write a new step at the cursor and advance by the note length. Returns the step tick.
+    pub fn begin_step(&mut self, pitches: &[u8]) -> u32 {
+        let tick = self.cursor_tick;
+        self.step_history.push(StepEntry { tick, track: self.current_track, pitches: pitches.to_vec(), replaced: Vec::new() });
+        self.cursor_tick += self.default_duration;
+        self.scroll_to_cursor();
+        tick
+    }
+
+    /// A MIDI note-on in step mode: joins the current chord while other step notes
+    /// are held, otherwise starts a new step. Returns (track, tick) to write at.
+    pub fn step_note_on(&mut self, pitch: u8) -> (usize, u32) {
+        self.step_held += 1;
+        if self.step_held > 1 {
+            if let Some(entry) = self.step_history.last_mut() {
+                entry.pitches.push(pitch);
+                return (entry.track, entry.tick);
+            }
+        }
+        (self.current_track, self.begin_step(&[pitch]))
+    }
+
+    /// Keep a note the current step wrote over, so stepping back restores it
+    pub fn step_replaced(&mut self, note: Option<Note>) {
+        if let (Some(entry), Some(note)) = (self.step_history.last_mut(), note) {
+            entry.replaced.push(note);
+        }
+    }
+
+    pub fn step_note_off(&mut self) {
+        self.step_held = self.step_held.saturating_sub(1);
+    }
+
+    /// Remove the last step and rewind the cursor to it
+    pub fn step_back(&mut self) -> Option<StepEntry> {
+        let entry = self.step_history.pop()?;
+        self.cursor_tick = entry.tick;
+        self.step_held = 0;
+        self.scroll_to_cursor();
+        Some(entry)
+    }
 
     pub fn adjust_default_duration(&mut self, delta: i32) {
         let new_dur = (self.default_duration as i32 + delta).max(self.ticks_per_cell() as i32);
@@ -80,30 +285,41 @@ impl PianoRollPane {
     pub fn change_track(&mut self, delta: i8, track_count: usize) {
         if track_count == 0 { return; }
         let new_idx = (self.current_track as i32 + delta as i32).clamp(0, track_count as i32 - 1);
-        self.current_track = new_idx as usize;
+        self.set_current_track(new_idx as usize);
     }
 
     /// Set current track index directly (for external syncing from global instrument selection)
     pub fn set_current_track(&mut self, idx: usize) {
+        if idx != self.current_track {
+            self.clear_selection();
+        }
         self.current_track = idx;
     }
 
-    pub fn jump_to_end(&mut self) {
-        // Jump to a reasonable far position (e.g., 16 bars worth)
-        self.cursor_tick = 480 * 4 * 16; // 16 bars at 4/4
+    /// Move the cursor to the start of a bar, clamped to the bars that have content
+    pub fn jump_to_bar(&mut self, bar: u32, piano_roll: &PianoRollState) {
+        let bar = bar.clamp(1, piano_roll.last_bar());
+        self.cursor_tick = piano_roll.bbt_to_tick(bar, 1, 0);
         self.scroll_to_cursor();
     }
 
+    /// Jump to the start of the last bar with content
+    pub fn jump_to_end(&mut self, piano_roll: &PianoRollState) {
+        self.jump_to_bar(piano_roll.last_bar(), piano_roll);
+    }
+
     /// Ticks per grid cell based on zoom level
-    fn ticks_per_cell(&self) -> u32 {
-        match self.zoom_level {
-            1 => 60,   // 1/8 beat
-            2 => 120,  // 1/4 beat
-            3 => 240,  // 1/2 beat
-            4 => 480,  // 1 beat
-            5 => 960,  // 2 beats
-            _ => 240,
-        }
+    pub fn ticks_per_cell(&self) -> u32 {
+        let tpb = self.ticks_per_beat;
+        let ticks = match self.zoom_level {
+            1 => tpb / 8,  // 1/8 beat
+            2 => tpb / 4,  // 1/4 beat
+            3 => tpb / 2,  // 1/2 beat
+            4 => tpb,      // 1 beat
+            5 => tpb * 2,  // 2 beats
+            _ => tpb / 2,
+        };
+        ticks.max(1)
     }
 
     /// Snap cursor tick to grid
@@ -114,8 +330,8 @@ impl PianoRollPane {
 
     /// Ensure cursor is visible by adjusting view
     fn scroll_to_cursor(&mut self) {
-        // Vertical: keep cursor within visible range
-        let visible_rows = 24u8;
+        // Vertical: keep cursor within visible range (the grid rows above the velocity lane)
+        let visible_rows = 22u8;
         if self.cursor_pitch < self.view_bottom_pitch {
             self.view_bottom_pitch = self.cursor_pitch;
         } else if self.cursor_pitch >= self.view_bottom_pitch.saturating_add(visible_rows) {
@@ -137,16 +353,18 @@ impl PianoRollPane {
         // Piano octave base note: octave 4 = C4 = MIDI 60
         let base_pitch = ((self.piano.octave() as i16 + 1) * 12).clamp(0, 127) as u8;
         // Center the view so the octave is roughly in the middle
-        // visible_rows is about 24, so offset by ~12 to center
-        let visible_rows = 24u8;
+        // visible_rows is about 22, so offset by ~11 to center
+        let visible_rows = 22u8;
         self.view_bottom_pitch = base_pitch.saturating_sub(visible_rows / 2);
         // Also move cursor to the base note of this octave
         self.cursor_pitch = base_pitch;
     }
 
     /// Render notes grid (buffer version)
-    fn render_notes_buf(&self, buf: &mut Buffer, area: RatatuiRect, piano_roll: &PianoRollState) {
-        let rect = center_rect(area, 97, 29);
+    fn render_notes_buf(&self, buf: &mut Buffer, area: RatatuiRect, session: &SessionState, instruments: &InstrumentState, groove: &str) {
+        let piano_roll = &session.piano_roll;
+        let note_name = |pitch: u8| pitch_name_in_key(pitch, session.key, session.scale);
+        let rect = fit_rect(area, 97, 29);
 
         // Layout constants
         let key_col_width: u16 = 5;
@@ -155,14 +373,23 @@ impl PianoRollPane {
         let grid_x = rect.x + key_col_width;
         let grid_y = rect.y + header_height;
         let grid_width = rect.width.saturating_sub(key_col_width + 1);
-        let grid_height = rect.height.saturating_sub(header_height + footer_height + 1);
+        let grid_height = rect.height.saturating_sub(header_height + VELOCITY_LANE_HEIGHT + footer_height + 1);
 
-        // Border
+        // Border, tinted with the track's instrument color tag
+        let track_instrument = piano_roll.track_at(self.current_track)
+            .and_then(|track| instruments.instrument(track.module_id));
+        let border_color = track_instrument
+            .and_then(|inst| inst.color)
+            .map(Color::from_tag)
+            .unwrap_or(Color::PINK);
         let track_label = if let Some(track) = piano_roll.track_at(self.current_track) {
             let mode = if track.polyphonic { "POLY" } else { "MONO" };
+            let name = track_instrument
+                .map(|inst| inst.name.clone())
+                .unwrap_or_else(|| format!("midi-{}", track.module_id));
             format!(
-                " Piano Roll: midi-{} [{}/{}] {} ",
-                track.module_id,
+                " Piano Roll: {} [{}/{}] {} ",
+                name,
                 self.current_track + 1,
                 piano_roll.track_order.len(),
                 mode,
@@ -173,8 +400,8 @@ impl PianoRollPane {
         let block = Block::default()
             .borders(Borders::ALL)
             .title(track_label.as_str())
-            .border_style(ratatui::style::Style::from(Style::new().fg(Color::PINK)))
-            .title_style(ratatui::style::Style::from(Style::new().fg(Color::PINK)));
+            .border_style(ratatui::style::Style::from(Style::new().fg(border_color)))
+            .title_style(ratatui::style::Style::from(Style::new().fg(border_color)));
         block.render(rect, buf);
 
         // Header: transport info
@@ -183,13 +410,14 @@ impl PianoRollPane {
         let loop_icon = if piano_roll.looping { "L" } else { " " };
         let (ts_num, ts_den) = piano_roll.time_signature;
         let header_text = format!(
-            " BPM:{:.0}  {}/{}  {}  {}  Beat:{:.1}",
+            " BPM:{:.0}  {}/{}  {}  {}  Pos:{}  Groove:{}",
             piano_roll.bpm, ts_num, ts_den, play_icon, loop_icon,
-            piano_roll.tick_to_beat(piano_roll.playhead),
+            format_bbt(piano_roll, piano_roll.playhead),
+            groove,
         );
         Paragraph::new(Line::from(Span::styled(
             header_text,
-            ratatui::style::Style::from(Style::new().fg(Color::WHITE)),
+            ratatui::style::Style::from(Style::new().fg(theme().foreground)),
         ))).render(RatatuiRect::new(rect.x + 1, header_y, rect.width.saturating_sub(2), 1), buf);
 
         // Loop range indicator
@@ -206,6 +434,16 @@ impl PianoRollPane {
             ))).render(RatatuiRect::new(loop_x, header_y, rect.width.saturating_sub(loop_x - rect.x), 1), buf);
         }
 
+        // Clip playback in view (notes sounding from before the view are included)
+        let view_end_tick = self.view_start_tick + grid_width as u32 * self.ticks_per_cell();
+        let current_track = piano_roll.track_at(self.current_track);
+        let clips = current_track.map_or(&[][..], |t| t.clips.as_slice());
+        let clip_notes = current_track.map_or_else(Vec::new, |t| t.clip_notes_between(0, view_end_tick));
+        let selected: Vec<&Note> = current_track.map_or_else(Vec::new, |t| {
+            self.selected_notes.iter().filter_map(|&i| t.notes.get(i)).collect()
+        });
+        let marquee = self.marquee_box();
+
         // Piano keys column + grid rows
         for row in 0..grid_height {
             let pitch = self.view_bottom_pitch.saturating_add((grid_height - 1 - row) as u8);
@@ -218,11 +456,11 @@ impl PianoRollPane {
             let name = note_name(pitch);
             let is_black = is_black_key(pitch);
             let key_style = if pitch == self.cursor_pitch {
-                ratatui::style::Style::from(Style::new().fg(Color::WHITE).bg(Color::SELECTION_BG))
+                ratatui::style::Style::from(Style::new().fg(theme().foreground).bg(theme().selection))
             } else if is_black {
-                ratatui::style::Style::from(Style::new().fg(Color::GRAY))
+                ratatui::style::Style::from(Style::new().fg(theme().border))
             } else {
-                ratatui::style::Style::from(Style::new().fg(Color::WHITE))
+                ratatui::style::Style::from(Style::new().fg(theme().foreground))
             };
             let key_str = format!("{:>3}", name);
             for (j, ch) in key_str.chars().enumerate() {
@@ -232,7 +470,7 @@ impl PianoRollPane {
             }
 
             // Separator
-            let sep_style = ratatui::style::Style::from(Style::new().fg(Color::GRAY));
+            let sep_style = ratatui::style::Style::from(Style::new().fg(theme().border));
             if let Some(cell) = buf.cell_mut((rect.x + key_col_width - 1, y)) {
                 cell.set_char('|').set_style(sep_style);
             }
@@ -252,12 +490,23 @@ impl PianoRollPane {
                     track.notes.iter().any(|n| n.pitch == pitch && n.tick == tick)
                 });
 
+                let has_clip_note = clip_notes.iter().any(|n| {
+                    n.pitch == pitch && tick >= n.tick && tick < n.tick + n.duration
+                });
+                let in_clip = clips.iter().any(|c| c.contains(tick));
+                let is_selected = selected.iter().any(|n| {
+                    n.pitch == pitch && tick >= n.tick && tick < n.tick + n.duration
+                });
+                let in_marquee = marquee.is_some_and(|(start, end, low, high)| {
+                    tick >= start && tick < end && (low..=high).contains(&pitch)
+                });
+
                 let is_cursor = pitch == self.cursor_pitch && tick == self.cursor_tick;
                 let is_playhead = piano_roll.playing
                     && tick <= piano_roll.playhead
                     && piano_roll.playhead < tick + self.ticks_per_cell();
 
-                let tpb = piano_roll.ticks_per_beat;
+                let tpb = piano_roll.ticks_per_meter_beat();
                 let tpbar = piano_roll.ticks_per_bar();
                 let is_bar_line = tick % tpbar == 0;
                 let is_beat_line = tick % tpb == 0;
@@ -266,18 +515,28 @@ impl PianoRollPane {
                     if has_note {
                         ('█', ratatui::style::Style::from(Style::new().fg(Color::BLACK).bg(Color::WHITE)))
                     } else {
-                        ('▒', ratatui::style::Style::from(Style::new().fg(Color::WHITE).bg(Color::SELECTION_BG)))
+                        ('▒', ratatui::style::Style::from(Style::new().fg(theme().foreground).bg(theme().selection)))
                     }
+                } else if has_note && is_selected {
+                    ('█', ratatui::style::Style::from(Style::new().fg(Color::GOLD)))
                 } else if has_note {
                     if is_note_start {
                         ('█', ratatui::style::Style::from(Style::new().fg(Color::PINK)))
                     } else {
                         ('█', ratatui::style::Style::from(Style::new().fg(Color::MAGENTA)))
                     }
+                } else if has_clip_note {
+                    ('█', ratatui::style::Style::from(Style::new().fg(Color::SKY_BLUE)))
+                } else if in_marquee {
+                    ('░', ratatui::style::Style::from(Style::new().fg(Color::GOLD)))
                 } else if is_playhead {
                     ('│', ratatui::style::Style::from(Style::new().fg(Color::GREEN)))
+                } else if in_clip && is_bar_line {
+                    ('┊', ratatui::style::Style::from(Style::new().fg(theme().border).bg(Color::new(20, 30, 45))))
+                } else if in_clip {
+                    (' ', ratatui::style::Style::from(Style::new().bg(Color::new(20, 30, 45))))
                 } else if is_bar_line {
-                    ('┊', ratatui::style::Style::from(Style::new().fg(Color::GRAY)))
+                    ('┊', ratatui::style::Style::from(Style::new().fg(theme().border)))
                 } else if is_beat_line {
                     ('·', ratatui::style::Style::from(Style::new().fg(Color::new(40, 40, 40))))
                 } else if is_black {
@@ -292,54 +551,132 @@ impl PianoRollPane {
             }
         }
 
+        // Velocity lane: a bar per note start, the loudest when several share a cell
+        let lane_y = grid_y + grid_height;
+        let targets = self.velocity_targets(piano_roll);
+        let label_style = ratatui::style::Style::from(Style::new().fg(theme().border));
+        let target_velocity = current_track
+            .and_then(|t| targets.first().and_then(|&i| t.notes.get(i)))
+            .map_or(String::new(), |n| format!("{:>3}", n.velocity));
+        for (row, label) in [(0, "Vel".to_string()), (1, target_velocity)] {
+            for (j, ch) in label.chars().enumerate() {
+                if let Some(cell) = buf.cell_mut((rect.x + 1 + j as u16, lane_y + row)) {
+                    cell.set_char(ch).set_style(label_style);
+                }
+            }
+        }
+        for col in 0..grid_width {
+            let tick = self.view_start_tick + col as u32 * self.ticks_per_cell();
+            let cell_end = tick + self.ticks_per_cell();
+            let loose = current_track.into_iter()
+                .flat_map(|t| t.notes.iter().enumerate())
+                .filter(|(_, n)| n.tick >= tick && n.tick < cell_end)
+                .max_by_key(|(_, n)| n.velocity);
+            let (velocity, color) = match loose {
+                Some((i, n)) if targets.contains(&i) => (n.velocity, Color::GOLD),
+                Some((_, n)) => (n.velocity, Color::PINK),
+                None => match clip_notes.iter().filter(|n| n.tick >= tick && n.tick < cell_end).map(|n| n.velocity).max() {
+                    Some(velocity) => (velocity, Color::SKY_BLUE),
+                    None => continue,
+                },
+            };
+            let style = ratatui::style::Style::from(Style::new().fg(color));
+            for row in 0..VELOCITY_LANE_HEIGHT {
+                let ch = velocity_bar(velocity, VELOCITY_LANE_HEIGHT - 1 - row, VELOCITY_LANE_HEIGHT);
+                if let Some(cell) = buf.cell_mut((grid_x + col, lane_y + row)) {
+                    cell.set_char(ch).set_style(style);
+                }
+            }
+        }
+
         // Footer: beat markers
-        let footer_y = grid_y + grid_height;
+        let footer_y = lane_y + VELOCITY_LANE_HEIGHT;
         for col in 0..grid_width {
             let tick = self.view_start_tick + col as u32 * self.ticks_per_cell();
-            let tpb = piano_roll.ticks_per_beat;
+            let tpb = piano_roll.ticks_per_meter_beat();
             let tpbar = piano_roll.ticks_per_bar();
             let x = grid_x + col;
 
             if tick % tpbar == 0 {
                 let bar = tick / tpbar + 1;
                 let label = format!("{}", bar);
-                let white = ratatui::style::Style::from(Style::new().fg(Color::WHITE));
+                let white = ratatui::style::Style::from(Style::new().fg(theme().foreground));
                 for (j, ch) in label.chars().enumerate() {
                     if let Some(cell) = buf.cell_mut((x + j as u16, footer_y)) {
                         cell.set_char(ch).set_style(white);
                     }
                 }
             } else if tick % tpb == 0 {
-                let gray = ratatui::style::Style::from(Style::new().fg(Color::GRAY));
+                let gray = ratatui::style::Style::from(Style::new().fg(theme().border));
                 if let Some(cell) = buf.cell_mut((x, footer_y)) {
                     cell.set_char('·').set_style(gray);
                 }
             }
         }
 
+        // Timeline markers (drawn over the bar numbers)
+        let marker_style = ratatui::style::Style::from(Style::new().fg(Color::GOLD).bold());
+        for marker in &piano_roll.markers {
+            if marker.tick < self.view_start_tick || marker.tick >= view_end_tick {
+                continue;
+            }
+            let x = grid_x + ((marker.tick - self.view_start_tick) / self.ticks_per_cell()) as u16;
+            let label = format!("\u{25BC}{}", marker.name);
+            for (j, ch) in label.chars().enumerate() {
+                let cx = x + j as u16;
+                if cx >= grid_x + grid_width {
+                    break;
+                }
+                if let Some(cell) = buf.cell_mut((cx, footer_y)) {
+                    cell.set_char(ch).set_style(marker_style);
+                }
+            }
+        }
+
         // Status line
         let status_y = footer_y + 1;
-        let vel_str = format!(
-            "Note:{} Tick:{} Vel:{} Dur:{}",
-            note_name(self.cursor_pitch),
-            self.cursor_tick,
-            self.default_velocity,
-            self.default_duration,
-        );
-        Paragraph::new(Line::from(Span::styled(
-            vel_str,
-            ratatui::style::Style::from(Style::new().fg(Color::GRAY)),
-        ))).render(RatatuiRect::new(rect.x + 1, status_y, rect.width.saturating_sub(2), 1), buf);
+        if self.prompt.is_some() {
+            self.prompt_input.render_buf(buf, rect.x + 1, status_y, 40);
+        } else {
+            let clip_str = match piano_roll.clip_at(self.current_track, self.cursor_tick) {
+                Some(idx) if clips[idx].loops > 1 => format!(" Clip:{} x{}", clips[idx].name, clips[idx].loops),
+                Some(idx) => format!(" Clip:{}", clips[idx].name),
+                None => String::new(),
+            };
+            let sel_str = match (self.marquee_anchor, self.selected_notes.len()) {
+                (Some(_), n) => format!(" Marquee:{}", n),
+                (None, 0) => String::new(),
+                (None, n) => format!(" Sel:{}", n),
+            };
+            let vel_str = format!(
+                "Note:{} {:.1}Hz Pos:{} Vel:{} Dur:{}{}{}",
+                note_name(self.cursor_pitch),
+                pitch_freq(self.cursor_pitch, session.tuning_a4),
+                format_bbt(piano_roll, self.cursor_tick),
+                self.default_velocity,
+                self.default_duration,
+                clip_str,
+                sel_str,
+            );
+            Paragraph::new(Line::from(Span::styled(
+                vel_str,
+                ratatui::style::Style::from(Style::new().fg(theme().border)),
+            ))).render(RatatuiRect::new(rect.x + 1, status_y, rect.width.saturating_sub(2), 1), buf);
+        }
 
         // Piano mode indicator
         if self.piano.is_active() {
             let piano_str = self.piano.status_label();
             let mut indicator_x = rect.x + rect.width - piano_str.len() as u16 - 1;
 
-            if self.recording {
-                let rec_str = " REC ";
+            if self.recording || self.step_record {
+                let rec_str = match (self.recording, self.live_quantize) {
+                    (true, true) => " REC Q ",
+                    (true, false) => " REC ",
+                    (false, _) => " STEP ",
+                };
                 indicator_x -= rec_str.len() as u16;
-                let rec_style = ratatui::style::Style::from(Style::new().fg(Color::WHITE).bg(Color::RED));
+                let rec_style = ratatui::style::Style::from(Style::new().fg(theme().foreground).bg(Color::RED));
                 for (j, ch) in rec_str.chars().enumerate() {
                     if let Some(cell) = buf.cell_mut((indicator_x + j as u16, status_y)) {
                         cell.set_char(ch).set_style(rec_style);
@@ -355,11 +692,11 @@ impl PianoRollPane {
                 }
             }
         } else {
-            let hint_str = "/=piano";
+            let hint_str = if self.step_record { "STEP  /=piano" } else { "/=piano" };
             let hint_x = rect.x + rect.width - hint_str.len() as u16 - 2;
             Paragraph::new(Line::from(Span::styled(
                 hint_str,
-                ratatui::style::Style::from(Style::new().fg(Color::GRAY)),
+                ratatui::style::Style::from(Style::new().fg(theme().border)),
             ))).render(RatatuiRect::new(hint_x, status_y, hint_str.len() as u16, 1), buf);
         }
     }
@@ -420,6 +757,7 @@ impl Pane for PianoRollPane {
                     self.cursor_pitch += 1;
                     self.scroll_to_cursor();
                 }
+                self.extend_marquee(state);
                 Action::None
             }
             "down" => {
@@ -427,22 +765,108 @@ impl Pane for PianoRollPane {
                     self.cursor_pitch -= 1;
                     self.scroll_to_cursor();
                 }
+                self.extend_marquee(state);
                 Action::None
             }
             "right" => {
                 self.cursor_tick += self.ticks_per_cell();
                 self.scroll_to_cursor();
+                self.extend_marquee(state);
                 Action::None
             }
             "left" => {
                 let step = self.ticks_per_cell();
                 self.cursor_tick = self.cursor_tick.saturating_sub(step);
                 self.scroll_to_cursor();
+                self.extend_marquee(state);
+                Action::None
+            }
+            "toggle_note" => {
+                self.clear_selection();
+                Action::PianoRoll(PianoRollAction::ToggleNote)
+            }
+            "marquee" => {
+                if self.marquee_anchor.is_some() {
+                    // Second press fixes the box; the selection stays
+                    self.marquee_anchor = None;
+                } else {
+                    self.marquee_anchor = Some((self.cursor_tick, self.cursor_pitch));
+                    self.extend_marquee(state);
+                }
+                Action::None
+            }
+            "clear_selection" => {
+                self.clear_selection();
+                Action::None
+            }
+            "copy_selection" => {
+                self.marquee_anchor = None;
+                if !self.selected_notes.is_empty() {
+                    self.clipboard = state.session.piano_roll.copy_notes(self.current_track, &self.selected_notes);
+                }
                 Action::None
             }
-            "toggle_note" => Action::PianoRoll(PianoRollAction::ToggleNote),
+            "save_phrase" if !self.selected_notes.is_empty() => {
+                self.marquee_anchor = None;
+                let name = state.phrases.next_name();
+                self.open_prompt(Prompt::PhraseName, "Phrase name:", &name)
+            }
+            "phrase_browser" => Action::Nav(NavAction::PushPane("phrase_browser")),
+            "paste" if !self.clipboard.is_empty() => {
+                self.clear_selection();
+                Action::PianoRoll(PianoRollAction::PasteNotes(self.clipboard.clone()))
+            }
+            "transpose_up" | "transpose_down" if !self.selected_notes.is_empty() => {
+                self.marquee_anchor = None;
+                let semitones = if action == "transpose_up" { 1 } else { -1 };
+                Action::PianoRoll(PianoRollAction::TransposeNotes(self.selected_notes.clone(), semitones))
+            }
+            "delete_note" if !self.selected_notes.is_empty() => {
+                let indices = std::mem::take(&mut self.selected_notes);
+                self.marquee_anchor = None;
+                Action::PianoRoll(PianoRollAction::DeleteNotes(indices))
+            }
+            "delete_note" => Action::PianoRoll(PianoRollAction::DeleteAtCursor),
+            "delete_loop_range" => {
+                // The loop range doubles as the selection, as for grooves
+                self.clear_selection();
+                let pr = &state.session.piano_roll;
+                Action::PianoRoll(PianoRollAction::DeleteRange(pr.loop_start, pr.loop_end))
+            }
+            "quantize_selection" if !self.selected_notes.is_empty() => {
+                self.marquee_anchor = None;
+                Action::PianoRoll(PianoRollAction::QuantizeNotes(self.selected_notes.clone(), self.ticks_per_cell()))
+            }
+            "loop_selection" if !self.selected_notes.is_empty() => {
+                Action::PianoRoll(PianoRollAction::LoopToSelection(self.selected_notes.clone(), self.ticks_per_cell()))
+            }
+            "toggle_live_quantize" => {
+                self.live_quantize = !self.live_quantize;
+                Action::None
+            }
+            "toggle_step_record" => {
+                self.clear_selection();
+                self.step_record = !self.step_record;
+                self.step_history.clear();
+                self.step_held = 0;
+                Action::None
+            }
+            "step_rest" if self.step_record => {
+                self.begin_step(&[]);
+                Action::None
+            }
+            "step_back" if self.step_record => Action::PianoRoll(PianoRollAction::StepBack),
             "grow_duration" => Action::PianoRoll(PianoRollAction::AdjustDuration(self.ticks_per_cell() as i32)),
             "shrink_duration" => Action::PianoRoll(PianoRollAction::AdjustDuration(-(self.ticks_per_cell() as i32))),
+            "note_vel_up" | "note_vel_down" => {
+                let targets = self.velocity_targets(&state.session.piano_roll);
+                if targets.is_empty() {
+                    return Action::None;
+                }
+                self.marquee_anchor = None;
+                let delta = if action == "note_vel_up" { NOTE_VELOCITY_STEP } else { -NOTE_VELOCITY_STEP };
+                Action::PianoRoll(PianoRollAction::AdjustNoteVelocities(targets, delta))
+            }
             "vel_up" => Action::PianoRoll(PianoRollAction::AdjustVelocity(10)),
             "vel_down" => Action::PianoRoll(PianoRollAction::AdjustVelocity(-10)),
             "play_stop" => Action::PianoRoll(PianoRollAction::PlayStop),
@@ -452,21 +876,119 @@ impl Pane for PianoRollPane {
             "octave_up" => {
                 self.cursor_pitch = (self.cursor_pitch as i16 + 12).min(127) as u8;
                 self.scroll_to_cursor();
+                self.extend_marquee(state);
                 Action::None
             }
             "octave_down" => {
                 self.cursor_pitch = (self.cursor_pitch as i16 - 12).max(0) as u8;
                 self.scroll_to_cursor();
+                self.extend_marquee(state);
                 Action::None
             }
             "home" => {
                 self.cursor_tick = 0;
                 self.view_start_tick = 0;
+                self.extend_marquee(state);
                 Action::None
             }
             "end" => Action::PianoRoll(PianoRollAction::Jump(1)),
+            "next_bar" | "prev_bar" => {
+                let pr = &state.session.piano_roll;
+                let per_bar = pr.ticks_per_bar();
+                let bar_start = self.cursor_tick - self.cursor_tick % per_bar;
+                self.cursor_tick = if action == "next_bar" {
+                    bar_start + per_bar
+                } else if bar_start == self.cursor_tick {
+                    bar_start.saturating_sub(per_bar)
+                } else {
+                    bar_start
+                };
+                self.scroll_to_cursor();
+                self.extend_marquee(state);
+                Action::None
+            }
+            "next_note" | "prev_note" | "nearest_note" => {
+                let pr = &state.session.piano_roll;
+                let found = match action {
+                    "nearest_note" => pr.nearest_note(self.current_track, self.cursor_tick, self.cursor_pitch),
+                    _ => pr.adjacent_note(self.current_track, self.cursor_tick, self.cursor_pitch, action == "next_note"),
+                };
+                // Past the first or last note the cursor stays put rather than wrapping
+                let track = pr.track_at(self.current_track);
+                if let Some((index, note)) = found.and_then(|i| Some((i, *track?.notes.get(i)?))) {
+                    // Land on the note itself, on or off the grid, selected for editing
+                    self.cursor_tick = note.tick;
+                    self.cursor_pitch = note.pitch;
+                    self.marquee_anchor = None;
+                    self.selected_notes = vec![index];
+                    self.scroll_to_cursor();
+                }
+                Action::None
+            }
+            "goto_bar" => self.open_prompt(Prompt::GotoBar, "Go to bar:", ""),
+            "add_marker" => Action::PianoRoll(PianoRollAction::AddMarker),
+            // Grooves and clip edits reorder or remove loose notes, staling the selection
+            "apply_groove" => {
+                self.clear_selection();
+                Action::PianoRoll(PianoRollAction::ApplyGroove)
+            }
+            "extract_groove" => Action::PianoRoll(PianoRollAction::ExtractGroove),
+            "make_clip" => {
+                self.clear_selection();
+                Action::PianoRoll(PianoRollAction::MakeClip)
+            }
+            "duplicate_clip" => Action::PianoRoll(PianoRollAction::DuplicateClip),
+            "clip_earlier" => Action::PianoRoll(PianoRollAction::MoveClip(-1)),
+            "clip_later" => Action::PianoRoll(PianoRollAction::MoveClip(1)),
+            "clip_loops_down" => Action::PianoRoll(PianoRollAction::AdjustClipLoops(-1)),
+            "clip_loops_up" => Action::PianoRoll(PianoRollAction::AdjustClipLoops(1)),
+            "unpack_clip" => {
+                self.clear_selection();
+                Action::PianoRoll(PianoRollAction::UnpackClip)
+            }
+            "delete_clip" => Action::PianoRoll(PianoRollAction::DeleteClip),
+            "consolidate_clips" => {
+                self.clear_selection();
+                Action::PianoRoll(PianoRollAction::ConsolidateClips)
+            }
+            "cycle_groove" => Action::Session(SessionAction::CycleGroove(1)),
+            "remove_marker" => Action::PianoRoll(PianoRollAction::RemoveMarker),
+            "rename_marker" => {
+                match state.session.piano_roll.marker_at(self.cursor_tick) {
+                    Some(marker) => {
+                        let name = marker.name.clone();
+                        self.open_prompt(Prompt::RenameMarker(self.cursor_tick), "Marker:", &name)
+                    }
+                    None => Action::None,
+                }
+            }
+            "next_marker" => Action::PianoRoll(PianoRollAction::JumpMarker(1)),
+            "prev_marker" => Action::PianoRoll(PianoRollAction::JumpMarker(-1)),
+            "text:confirm" => {
+                self.prompt_input.set_focused(false);
+                let text = self.prompt_input.value().to_string();
+                match self.prompt.take() {
+                    Some(Prompt::GotoBar) => {
+                        if let Ok(bar) = text.trim().parse::<u32>() {
+                            self.jump_to_bar(bar, &state.session.piano_roll);
+                        }
+                        Action::None
+                    }
+                    Some(Prompt::RenameMarker(tick)) => Action::PianoRoll(PianoRollAction::RenameMarker(tick, text)),
+                    Some(Prompt::PhraseName) => {
+                        let notes = state.session.piano_roll.copy_notes(self.current_track, &self.selected_notes);
+                        Action::PianoRoll(PianoRollAction::SavePhrase(text, notes))
+                    }
+                    None => Action::None,
+                }
+            }
+            "text:cancel" => {
+                self.prompt = None;
+                self.prompt_input.set_focused(false);
+                Action::None
+            }
             "zoom_in" => {
-                if self.zoom_level > 1 {
+                if self.zoom_level > MIN_PIANO_ZOOM {
                     self.zoom_level -= 1;
                     self.cursor_tick = self.snap_tick(self.cursor_tick);
                     self.scroll_to_cursor();
@@ -474,7 +996,7 @@ impl Pane for PianoRollPane {
                 Action::None
             }
             "zoom_out" => {
-                if self.zoom_level < 5 {
+                if self.zoom_level < MAX_PIANO_ZOOM {
                     self.zoom_level += 1;
                     self.cursor_tick = self.snap_tick(self.cursor_tick);
                     self.scroll_to_cursor();
@@ -487,15 +1009,22 @@ impl Pane for PianoRollPane {
         }
     }
 
+    fn handle_raw_input(&mut self, event: &InputEvent, _state: &AppState) -> Action {
+        if self.prompt.is_some() {
+            self.prompt_input.handle_input(event);
+        }
+        Action::None
+    }
+
     fn handle_mouse(&mut self, event: &MouseEvent, area: RatatuiRect, _state: &AppState) -> Action {
-        let rect = center_rect(area, 97, 29);
+        let rect = fit_rect(area, 97, 29);
         let key_col_width: u16 = 5;
         let header_height: u16 = 2;
         let footer_height: u16 = 2;
         let grid_x = rect.x + key_col_width;
         let grid_y = rect.y + header_height;
         let grid_width = rect.width.saturating_sub(key_col_width + 1);
-        let grid_height = rect.height.saturating_sub(header_height + footer_height + 1);
+        let grid_height = rect.height.saturating_sub(header_height + VELOCITY_LANE_HEIGHT + footer_height + 1);
 
         let col = event.column;
         let row = event.row;
@@ -514,6 +1043,7 @@ impl Pane for PianoRollPane {
                     if pitch <= 127 {
                         self.cursor_pitch = pitch;
                         self.cursor_tick = tick;
+                        self.clear_selection();
                         return Action::PianoRoll(PianoRollAction::ToggleNote);
                     }
                 }
@@ -570,7 +1100,8 @@ impl Pane for PianoRollPane {
     }
 
     fn render(&self, area: RatatuiRect, buf: &mut Buffer, state: &AppState) {
-        self.render_notes_buf(buf, area, &state.session.piano_roll);
+        let groove = state.session.selected_groove().map_or("-", |g| g.name.as_str());
+        self.render_notes_buf(buf, area, &state.session, &state.instruments, groove);
     }
 
     fn keymap(&self) -> &Keymap {
@@ -603,3 +1134,42 @@ impl Pane for PianoRollPane {
         self
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_velocity_bar_fills_from_the_bottom() {
+        let bar = |velocity| [velocity_bar(velocity, 1, 2), velocity_bar(velocity, 0, 2)];
+        assert_eq!(bar(127), ['█', '█']);
+        assert_eq!(bar(64), ['▁', '█']);
+        assert_eq!(bar(63), [' ', '█']);
+        assert_eq!(bar(100), ['▅', '█']);
+        assert_eq!(bar(1), [' ', '▁']);
+        assert_eq!(bar(0), [' ', ' ']);
+    }
+
+    #[test]
+    fn test_velocity_edits_target_selection_or_note_under_cursor() {
+        let mut state = AppState::new();
+        state.session.piano_roll.add_track(1);
+        state.session.piano_roll.add_note(0, 60, 0, 480, 100);
+        state.session.piano_roll.add_note(0, 64, 0, 480, 90);
+        let mut pane = PianoRollPane::default();
+        let event = InputEvent::new(KeyCode::Up, crate::ui::Modifiers::ctrl());
+
+        pane.cursor_pitch = 62;
+        assert!(matches!(pane.handle_action("note_vel_up", &event, &state), Action::None));
+        pane.cursor_pitch = 60;
+        assert!(matches!(
+            pane.handle_action("note_vel_up", &event, &state),
+            Action::PianoRoll(PianoRollAction::AdjustNoteVelocities(ref i, 8)) if i == &[0]
+        ));
+        pane.selected_notes = vec![0, 1];
+        assert!(matches!(
+            pane.handle_action("note_vel_down", &event, &state),
+            Action::PianoRoll(PianoRollAction::AdjustNoteVelocities(ref i, -8)) if i == &[0, 1]
+        ));
+    }
+}
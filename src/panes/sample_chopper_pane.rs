@@ -7,9 +7,9 @@ use ratatui::widgets::{Block, Borders, Paragraph, Widget};
 
 use crate::panes::FileBrowserPane;
 use crate::state::AppState;
-use crate::ui::layout_helpers::center_rect;
+use crate::ui::layout_helpers::fit_rect;
 use crate::ui::{
-    Action, ChopperAction, Color, FileSelectAction, InputEvent, Keymap, NavAction, Pane, Style,
+    Action, ChopperAction, Color, FileSelectAction, InputEvent, Keymap, NavAction, Pane, Style, theme,
 };
 
 pub struct SampleChopperPane {
@@ -95,6 +95,9 @@ impl Pane for SampleChopperPane {
             "commit" => Action::Chopper(ChopperAction::CommitAll),
             "load_sample" => Action::Chopper(ChopperAction::LoadSample),
             "preview" => Action::Chopper(ChopperAction::PreviewSlice),
+            "trim" => Action::Chopper(ChopperAction::TrimSilence),
+            "normalize" => Action::Chopper(ChopperAction::Normalize),
+            "revert" => Action::Chopper(ChopperAction::RevertSample),
             "back" => Action::Nav(NavAction::PopPane),
             a if a.starts_with("assign_") => {
                 if let Ok(idx) = a[7..].parse::<usize>() {
@@ -108,7 +111,7 @@ impl Pane for SampleChopperPane {
     }
 
     fn render(&self, area: RatatuiRect, buf: &mut Buffer, state: &AppState) {
-        let rect = center_rect(area, 97, 29);
+        let rect = fit_rect(area, 97, 29);
 
         if let Some(drum_seq) = self.selected_drum_sequencer(state) {
             if drum_seq.chopper.is_none() {
@@ -119,12 +122,12 @@ impl Pane for SampleChopperPane {
             let block = Block::default()
                 .borders(Borders::ALL)
                 .title(" Sample Chopper ")
-                .border_style(ratatui::style::Style::from(Style::new().fg(Color::GRAY)))
-                .title_style(ratatui::style::Style::from(Style::new().fg(Color::GRAY)));
+                .border_style(ratatui::style::Style::from(Style::new().fg(theme().border)))
+                .title_style(ratatui::style::Style::from(Style::new().fg(theme().border)));
             block.render(rect, buf);
             Paragraph::new(Line::from(Span::styled(
                 "No drum machine instrument selected. Press 1 to add one.",
-                ratatui::style::Style::from(Style::new().fg(Color::DARK_GRAY)),
+                ratatui::style::Style::from(Style::new().fg(theme().muted)),
             ))).render(RatatuiRect::new(rect.x + 2, rect.y + 2, rect.width.saturating_sub(4), 1), buf);
             return;
         }
@@ -132,8 +135,8 @@ impl Pane for SampleChopperPane {
         let block = Block::default()
             .borders(Borders::ALL)
             .title(" Sample Chopper ")
-            .border_style(ratatui::style::Style::from(Style::new().fg(Color::GRAY)))
-            .title_style(ratatui::style::Style::from(Style::new().fg(Color::GRAY)));
+            .border_style(ratatui::style::Style::from(Style::new().fg(theme().border)))
+            .title_style(ratatui::style::Style::from(Style::new().fg(theme().border)));
         block.render(rect, buf);
 
         // Get chopper state
@@ -142,7 +145,7 @@ impl Pane for SampleChopperPane {
             None => {
                 Paragraph::new(Line::from(Span::styled(
                     "No sample loaded.",
-                    ratatui::style::Style::from(Style::new().fg(Color::DARK_GRAY)),
+                    ratatui::style::Style::from(Style::new().fg(theme().muted)),
                 ))).render(RatatuiRect::new(rect.x + 2, rect.y + 2, rect.width.saturating_sub(4), 1), buf);
                 return;
             }
@@ -157,16 +160,28 @@ impl Pane for SampleChopperPane {
             .unwrap_or_else(|| "No Sample".to_string());
         Paragraph::new(Line::from(Span::styled(
             filename,
-            ratatui::style::Style::from(Style::new().fg(Color::CYAN).bold()),
+            ratatui::style::Style::from(Style::new().fg(theme().accent).bold()),
         ))).render(RatatuiRect::new(content_x, content_y, rect.width.saturating_sub(4), 1), buf);
 
         let info = format!("{:.1}s   {} slices", chopper.duration_secs, chopper.slices.len());
         let info_x = rect.x + rect.width - 2 - info.len() as u16;
         Paragraph::new(Line::from(Span::styled(
             info,
-            ratatui::style::Style::from(Style::new().fg(Color::DARK_GRAY)),
+            ratatui::style::Style::from(Style::new().fg(theme().muted)),
         ))).render(RatatuiRect::new(info_x, content_y, rect.width.saturating_sub(info_x - rect.x), 1), buf);
 
+        // Last trim/normalize result, and whether the original can be restored
+        let edit_line = match (&chopper.status, chopper.original_path.is_some()) {
+            (Some(status), true) => format!("{} (u: revert)", status),
+            (Some(status), false) => status.clone(),
+            (None, true) => "Edited (u: revert)".to_string(),
+            (None, false) => String::new(),
+        };
+        Paragraph::new(Line::from(Span::styled(
+            edit_line,
+            ratatui::style::Style::from(Style::new().fg(theme().muted)),
+        ))).render(RatatuiRect::new(content_x, content_y + 1, rect.width.saturating_sub(4), 1), buf);
+
         // Waveform
         let wave_y = content_y + 2;
         let wave_height: u16 = 8;
@@ -192,16 +207,16 @@ impl Pane for SampleChopperPane {
         } else {
             Paragraph::new(Line::from(Span::styled(
                 "(No waveform data)",
-                ratatui::style::Style::from(Style::new().fg(Color::DARK_GRAY)),
+                ratatui::style::Style::from(Style::new().fg(theme().muted)),
             ))).render(RatatuiRect::new(content_x, wave_y + wave_height / 2, 20, 1), buf);
         }
 
         // Draw slices
         let slice_y_start = wave_y;
         let slice_y_end = wave_y + wave_height;
-        let dark_gray_style = ratatui::style::Style::from(Style::new().fg(Color::DARK_GRAY));
-        let sel_bg_style = ratatui::style::Style::from(Style::new().bg(Color::SELECTION_BG));
-        let sel_white_style = ratatui::style::Style::from(Style::new().fg(Color::WHITE).bg(Color::SELECTION_BG));
+        let dark_gray_style = ratatui::style::Style::from(Style::new().fg(theme().muted));
+        let sel_bg_style = ratatui::style::Style::from(Style::new().bg(theme().selection));
+        let sel_white_style = ratatui::style::Style::from(Style::new().fg(theme().foreground).bg(theme().selection));
 
         for (i, slice) in chopper.slices.iter().enumerate() {
             let start_x = (slice.start * wave_width as f32) as u16;
@@ -267,14 +282,14 @@ impl Pane for SampleChopperPane {
             if i == chopper.selected_slice {
                 if let Some(cell) = buf.cell_mut((content_x, y)) {
                     cell.set_char('>').set_style(
-                        ratatui::style::Style::from(Style::new().fg(Color::WHITE).bold()),
+                        ratatui::style::Style::from(Style::new().fg(theme().foreground).bold()),
                     );
                 }
             }
 
             let text = format!("{:<2} {:.3}-{:.3}", i + 1, slice.start, slice.end);
             let style = ratatui::style::Style::from(Style::new().fg(
-                if i == chopper.selected_slice { Color::WHITE } else { Color::GRAY }
+                if i == chopper.selected_slice { theme().foreground } else { theme().border }
             ));
             for (j, ch) in text.chars().enumerate() {
                 if let Some(cell) = buf.cell_mut((content_x + 2 + j as u16, y)) {
@@ -303,10 +318,11 @@ impl Pane for SampleChopperPane {
 
         // Footer help
         let help_y = rect.y + rect.height - 2;
+        let help_width = rect.width.saturating_sub(4);
         Paragraph::new(Line::from(Span::styled(
-            "Enter:chop ,:commit x:del n:auto 1-0:assign Space:preview s:load Esc:back",
-            ratatui::style::Style::from(Style::new().fg(Color::DARK_GRAY)),
-        ))).render(RatatuiRect::new(content_x, help_y, rect.width.saturating_sub(4), 1), buf);
+            self.keymap.footer_hint(help_width as usize),
+            ratatui::style::Style::from(Style::new().fg(theme().muted)),
+        ))).render(RatatuiRect::new(content_x, help_y, help_width, 1), buf);
     }
 
     fn keymap(&self) -> &Keymap {
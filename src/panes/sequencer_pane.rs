@@ -1,20 +1,41 @@
 use std::any::Any;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
 
 use ratatui::buffer::Buffer;
 use ratatui::layout::Rect as RatatuiRect;
 use ratatui::text::{Line, Span};
 use ratatui::widgets::{Block, Borders, Paragraph, Widget};
 
-use crate::state::drum_sequencer::NUM_PADS;
-use crate::state::AppState;
-use crate::ui::layout_helpers::center_rect;
-use crate::ui::{Action, Color, InputEvent, Keymap, MouseEvent, MouseEventKind, MouseButton, NavAction, Pane, SequencerAction, Style};
+use crate::state::drum_sequencer::{LockParam, NUM_PADS};
+use crate::state::{AppState, SampleLoadStatus};
+use crate::ui::layout_helpers::fit_rect;
+use crate::ui::{Action, Color, InputEvent, Keymap, MouseEvent, MouseEventKind, MouseButton, NavAction, Pane, SequencerAction, SessionAction, Style, theme};
+
+/// Letter shown for a pattern index
+fn pattern_name(index: usize) -> &'static str {
+    match index {
+        0 => "A", 1 => "B", 2 => "C", 3 => "D", _ => "?",
+    }
+}
+
+/// Timestamped file in the current directory for an exported pattern loop
+fn export_path(pattern: usize) -> PathBuf {
+    let dir = std::env::current_dir().unwrap_or_else(|_| PathBuf::from("."));
+    let secs = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    dir.join(format!("pattern_{}_{}.wav", pattern_name(pattern), secs))
+}
 
 pub struct SequencerPane {
     keymap: Keymap,
     cursor_pad: usize,
     cursor_step: usize,
     view_start_step: usize,
+    /// Setting the lock keys edit on the cursor step
+    lock_param: LockParam,
 }
 
 impl SequencerPane {
@@ -24,6 +45,7 @@ impl SequencerPane {
             cursor_pad: 0,
             cursor_step: 0,
             view_start_step: 0,
+            lock_param: LockParam::Pitch,
         }
     }
 
@@ -74,6 +96,12 @@ impl Pane for SequencerPane {
                     -0.05,
                 ));
             }
+            "toggle_reverse" => Action::Sequencer(SequencerAction::ToggleReverse(self.cursor_pad)),
+            "add_layer" => Action::Sequencer(SequencerAction::LoadLayer(self.cursor_pad)),
+            "clear_layers" => Action::Sequencer(SequencerAction::ClearLayers(self.cursor_pad)),
+            "add_round_robin" => Action::Sequencer(SequencerAction::LoadRoundRobin(self.cursor_pad)),
+            "clear_round_robin" => Action::Sequencer(SequencerAction::ClearRoundRobin(self.cursor_pad)),
+            "toggle_crossfade" => Action::Sequencer(SequencerAction::ToggleLayerCrossfade(self.cursor_pad)),
             "pad_level_up" => {
                 return Action::Sequencer(SequencerAction::AdjustPadLevel(
                     self.cursor_pad,
@@ -110,13 +138,43 @@ impl Pane for SequencerPane {
             "prev_pattern" => Action::Sequencer(SequencerAction::PrevPattern),
             "next_pattern" => Action::Sequencer(SequencerAction::NextPattern),
             "cycle_length" => Action::Sequencer(SequencerAction::CyclePatternLength),
+            "apply_groove" => Action::Sequencer(SequencerAction::ApplyGroove),
+            "extract_groove" => Action::Sequencer(SequencerAction::ExtractGroove),
+            "cycle_groove" => Action::Session(SessionAction::CycleGroove(1)),
+            "cycle_follow" => Action::Sequencer(SequencerAction::CycleFollowTarget),
+            "follow_repeats_down" => Action::Sequencer(SequencerAction::AdjustFollowRepeats(-1)),
+            "follow_repeats_up" => Action::Sequencer(SequencerAction::AdjustFollowRepeats(1)),
+            "export_loop" => Action::Sequencer(SequencerAction::ExportLoop(export_path(seq.current_pattern))),
+            "bounce_to_pad" => Action::Sequencer(SequencerAction::BounceToPad(self.cursor_pad, export_path(seq.current_pattern))),
+            "cycle_lock_param" => {
+                self.lock_param = self.lock_param.next();
+                Action::None
+            }
+            "lock_up" | "lock_down" => Action::Sequencer(SequencerAction::AdjustLock(
+                self.cursor_pad,
+                self.cursor_step,
+                self.lock_param,
+                if action == "lock_up" { 1 } else { -1 },
+            )),
+            "clear_locks" => Action::Sequencer(SequencerAction::ClearLocks(self.cursor_pad, self.cursor_step)),
+            "euclid_more" | "euclid_fewer" | "euclid_rotate" | "euclid_rotate_back" => {
+                // Step from the row's last generated rhythm, or from an empty one
+                let (pulses, rotation) = seq.pattern().euclid[self.cursor_pad].unwrap_or((0, 0));
+                let (pulses, rotation) = match action {
+                    "euclid_more" => (pulses + 1, rotation),
+                    "euclid_fewer" => (pulses.saturating_sub(1), rotation),
+                    "euclid_rotate" => (pulses, rotation + 1),
+                    _ => (pulses, rotation - 1),
+                };
+                Action::Sequencer(SequencerAction::Euclid(self.cursor_pad, pulses, rotation))
+            }
             _ => Action::None,
         }
     }
 
     fn render(&self, area: RatatuiRect, buf: &mut Buffer, state: &AppState) {
         let box_width: u16 = 97;
-        let rect = center_rect(area, box_width, 29);
+        let rect = fit_rect(area, box_width, 29);
 
         let seq = match state.instruments.selected_drum_sequencer() {
             Some(s) => s,
@@ -130,7 +188,7 @@ impl Pane for SequencerPane {
                 let cy = rect.y + rect.height / 2;
                 Paragraph::new(Line::from(Span::styled(
                     "No drum machine instrument selected. Press 1 to add one.",
-                    ratatui::style::Style::from(Style::new().fg(Color::DARK_GRAY)),
+                    ratatui::style::Style::from(Style::new().fg(theme().muted)),
                 ))).render(RatatuiRect::new(rect.x + 12, cy, rect.width.saturating_sub(14), 1), buf);
                 return;
             }
@@ -163,29 +221,44 @@ impl Pane for SequencerPane {
         let cy = rect.y + 1;
 
         // Header line
-        let pattern_label = match seq.current_pattern {
-            0 => "A", 1 => "B", 2 => "C", 3 => "D", _ => "?",
+        let pattern_label = pattern_name(seq.current_pattern);
+        let follow_label = match pattern.follow_action {
+            Some((target, _)) if target == seq.current_pattern => "  Follow: loop".to_string(),
+            Some((target, repeats)) if seq.playing => format!(
+                "  Follow: {} after {}/{}",
+                pattern_name(target), (seq.pattern_passes + 1).min(repeats), repeats,
+            ),
+            Some((target, repeats)) => format!("  Follow: {} after {}", pattern_name(target), repeats),
+            None => String::new(),
         };
         let play_label = if seq.playing { "PLAY" } else { "STOP" };
-        let play_color = if seq.playing { Color::GREEN } else { Color::GRAY };
+        let play_color = if seq.playing { Color::GREEN } else { theme().border };
 
         let header = Line::from(vec![
             Span::styled(
                 format!("Pattern {}", pattern_label),
-                ratatui::style::Style::from(Style::new().fg(Color::WHITE).bold()),
+                ratatui::style::Style::from(Style::new().fg(theme().foreground).bold()),
             ),
             Span::styled(
                 format!("  Length: {}", pattern.length),
-                ratatui::style::Style::from(Style::new().fg(Color::DARK_GRAY)),
+                ratatui::style::Style::from(Style::new().fg(theme().muted)),
+            ),
+            Span::styled(
+                follow_label,
+                ratatui::style::Style::from(Style::new().fg(Color::ORANGE)),
             ),
             Span::styled(
                 format!("  BPM: {:.0}", state.session.piano_roll.bpm),
-                ratatui::style::Style::from(Style::new().fg(Color::DARK_GRAY)),
+                ratatui::style::Style::from(Style::new().fg(theme().muted)),
             ),
             Span::styled(
                 format!("  {}", play_label),
                 ratatui::style::Style::from(Style::new().fg(play_color).bold()),
             ),
+            Span::styled(
+                format!("  Groove: {}", state.session.selected_groove().map_or("-", |g| g.name.as_str())),
+                ratatui::style::Style::from(Style::new().fg(theme().muted)),
+            ),
         ]);
         Paragraph::new(header).render(RatatuiRect::new(cx, cy, rect.width.saturating_sub(4), 1), buf);
 
@@ -194,7 +267,7 @@ impl Pane for SequencerPane {
         let label_width: u16 = 11;
         let step_col_start = cx + label_width;
 
-        let dark_gray = ratatui::style::Style::from(Style::new().fg(Color::DARK_GRAY));
+        let dark_gray = ratatui::style::Style::from(Style::new().fg(theme().muted));
         for i in 0..steps_shown {
             let step_num = view_start + i + 1;
             let x = step_col_start + (i as u16) * 3;
@@ -226,16 +299,24 @@ impl Pane for SequencerPane {
                 format!("{:>2} {:<6} ", pad_idx + 1, name)
             };
 
-            let label_style = if is_cursor_row {
-                ratatui::style::Style::from(Style::new().fg(Color::WHITE).bold())
-            } else {
-                ratatui::style::Style::from(Style::new().fg(Color::GRAY))
+            let load_status = pad.buffer_id.and_then(|id| state.sample_loads.get(&id));
+            let label_style = match load_status {
+                Some(SampleLoadStatus::Loading) => ratatui::style::Style::from(Style::new().fg(theme().muted)),
+                Some(SampleLoadStatus::Failed(_)) => ratatui::style::Style::from(Style::new().fg(Color::RED)),
+                _ if is_cursor_row => ratatui::style::Style::from(Style::new().fg(theme().foreground).bold()),
+                _ => ratatui::style::Style::from(Style::new().fg(theme().border)),
             };
             for (j, ch) in label.chars().enumerate() {
                 if let Some(cell) = buf.cell_mut((cx + j as u16, y)) {
                     cell.set_char(ch).set_style(label_style);
                 }
             }
+            // Rows still as generated by the Euclidean rhythm
+            if pattern.euclid[pad_idx].is_some() {
+                if let Some(cell) = buf.cell_mut((cx + label_width - 1, y)) {
+                    cell.set_char('E').set_style(ratatui::style::Style::from(Style::new().fg(Color::TEAL).bold()));
+                }
+            }
 
             // Steps
             for i in 0..steps_shown {
@@ -252,16 +333,22 @@ impl Pane for SequencerPane {
                 } else if is_playhead {
                     if step.active { (Color::BLACK, Color::GREEN) } else { (Color::GREEN, Color::new(20, 50, 20)) }
                 } else if step.active {
-                    let intensity = (step.velocity as f32 / 127.0 * 200.0) as u8 + 55;
-                    (Color::new(intensity, intensity / 3, 0), Color::BLACK)
+                    let intensity = (step.played_velocity() as f32 / 127.0 * 200.0) as u8 + 55;
+                    (Color::new(intensity, intensity / 3, 0), theme().background)
                 } else if is_beat {
-                    (Color::new(60, 60, 60), Color::BLACK)
+                    (Color::new(60, 60, 60), theme().background)
                 } else {
-                    (Color::new(40, 40, 40), Color::BLACK)
+                    (Color::new(40, 40, 40), theme().background)
                 };
 
                 let style = ratatui::style::Style::from(Style::new().fg(fg).bg(bg));
-                let chars: Vec<char> = if step.active { " █ " } else { " · " }.chars().collect();
+                let chars: Vec<char> = if !step.active {
+                    " · "
+                } else if step.locks.is_empty() {
+                    " █ "
+                } else {
+                    " ◆ "
+                }.chars().collect();
                 for (j, ch) in chars.iter().enumerate() {
                     if let Some(cell) = buf.cell_mut((x + j as u16, y)) {
                         cell.set_char(*ch).set_style(style);
@@ -287,9 +374,14 @@ impl Pane for SequencerPane {
         } else {
             &pad.name
         };
+        let (name_display, name_color) = match pad.buffer_id.and_then(|id| state.sample_loads.get(&id)) {
+            Some(SampleLoadStatus::Loading) => ("(loading...)", theme().muted),
+            Some(SampleLoadStatus::Failed(_)) => ("(load failed)", Color::RED),
+            _ => (name_display, theme().foreground),
+        };
         Paragraph::new(Line::from(Span::styled(
             name_display,
-            ratatui::style::Style::from(Style::new().fg(Color::WHITE)),
+            ratatui::style::Style::from(Style::new().fg(name_color)),
         ))).render(RatatuiRect::new(cx + 8, detail_y, 22, 1), buf);
 
         // Level bar
@@ -317,12 +409,68 @@ impl Pane for SequencerPane {
         // Velocity
         let step = &pattern.steps[self.cursor_pad][self.cursor_step];
         let vel_str = format!("Vel: {}", step.velocity);
+        let vel_x = bar_x + bar_width as u16 + 2;
         for (j, ch) in vel_str.chars().enumerate() {
-            if let Some(cell) = buf.cell_mut((bar_x + bar_width as u16 + 2 + j as u16, detail_y)) {
+            if let Some(cell) = buf.cell_mut((vel_x + j as u16, detail_y)) {
                 cell.set_char(ch).set_style(dark_gray);
             }
         }
 
+        if pad.reverse {
+            let rev_style = ratatui::style::Style::from(Style::new().fg(Color::ORANGE));
+            for (j, ch) in "REV".chars().enumerate() {
+                if let Some(cell) = buf.cell_mut((vel_x + 10 + j as u16, detail_y)) {
+                    cell.set_char(ch).set_style(rev_style);
+                }
+            }
+        }
+
+        if !pad.layers.is_empty() || !pad.round_robin.is_empty() {
+            let mut parts = Vec::new();
+            if !pad.layers.is_empty() {
+                parts.push(format!("{}L{}", pad.layers.len() + 1, if pad.crossfade { " XF" } else { "" }));
+            }
+            if !pad.round_robin.is_empty() {
+                parts.push(format!("RR{}", pad.round_robin.len() + 1));
+            }
+            let layer_str = parts.join(" ");
+            for (j, ch) in layer_str.chars().enumerate() {
+                if let Some(cell) = buf.cell_mut((vel_x + 14 + j as u16, detail_y)) {
+                    cell.set_char(ch).set_style(dark_gray);
+                }
+            }
+        }
+
+        if let Some((pulses, rotation)) = pattern.euclid[self.cursor_pad] {
+            let euclid_str = format!("E({},{}) +{}", pulses, pattern.length, rotation);
+            let euclid_style = ratatui::style::Style::from(Style::new().fg(Color::TEAL));
+            for (j, ch) in euclid_str.chars().enumerate() {
+                if let Some(cell) = buf.cell_mut((vel_x + 28 + j as u16, detail_y)) {
+                    cell.set_char(ch).set_style(euclid_style);
+                }
+            }
+        }
+
+        // Parameter locks on the cursor step; the one the lock keys edit is highlighted
+        let lock_y = detail_y + 1;
+        let mut lock_spans = vec![Span::styled("Locks:", dark_gray)];
+        for param in LockParam::ALL {
+            let value = match step.lock(param) {
+                Some(v) => param.format(v),
+                None => "-".to_string(),
+            };
+            let style = if param == self.lock_param {
+                ratatui::style::Style::from(Style::new().fg(Color::TEAL).bold())
+            } else if step.lock(param).is_some() {
+                ratatui::style::Style::from(Style::new().fg(theme().foreground))
+            } else {
+                dark_gray
+            };
+            lock_spans.push(Span::styled(format!("  {} {}", param.name(), value), style));
+        }
+        Paragraph::new(Line::from(lock_spans))
+            .render(RatatuiRect::new(cx, lock_y, rect.width.saturating_sub(4), 1), buf);
+
         // Scroll indicator
         if pattern.length > visible {
             let scroll_str = format!("{}-{}/{}", view_start + 1, view_start + steps_shown, pattern.length);
@@ -336,15 +484,16 @@ impl Pane for SequencerPane {
 
         // Help line
         let help_y = rect.y + rect.height - 2;
+        let help_width = rect.width.saturating_sub(4);
         Paragraph::new(Line::from(Span::styled(
-            "Enter:toggle  Space:play/stop  s:sample  c:chopper  x:clear  []:pattern  {:length",
-            ratatui::style::Style::from(Style::new().fg(Color::DARK_GRAY)),
-        ))).render(RatatuiRect::new(cx, help_y, rect.width.saturating_sub(4), 1), buf);
+            self.keymap.footer_hint(help_width as usize),
+            ratatui::style::Style::from(Style::new().fg(theme().muted)),
+        ))).render(RatatuiRect::new(cx, help_y, help_width, 1), buf);
     }
 
     fn handle_mouse(&mut self, event: &MouseEvent, area: RatatuiRect, state: &AppState) -> Action {
         let box_width: u16 = 97;
-        let rect = center_rect(area, box_width, 29);
+        let rect = fit_rect(area, box_width, 29);
         let cx = rect.x + 2;
         let header_y = rect.y + 3;
         let label_width: u16 = 11;
@@ -6,10 +6,13 @@ use ratatui::text::{Line, Span};
 use ratatui::widgets::{Block, Borders, Paragraph, Widget};
 
 use crate::audio::devices::{self, AudioDevice, AudioDeviceConfig};
-use crate::audio::ServerStatus;
+use crate::audio::{ServerStats, ServerStatus};
 use crate::state::AppState;
-use crate::ui::layout_helpers::center_rect;
-use crate::ui::{Action, Color, InputEvent, KeyCode, Keymap, Pane, ServerAction, Style};
+use crate::ui::layout_helpers::fit_rect;
+use crate::ui::{Action, Color, InputEvent, KeyCode, Keymap, Pane, ServerAction, SessionAction, Style, theme};
+
+/// scsynth output lines shown under an error
+pub const SERVER_LOG_TAIL: usize = 6;
 
 #[derive(Debug, Clone, Copy, PartialEq)]
 enum ServerPaneFocus {
@@ -29,6 +32,10 @@ pub struct ServerPane {
     focus: ServerPaneFocus,
     /// Whether device selection changed since last server start
     device_config_dirty: bool,
+    /// Latest load figures while connected
+    stats: Option<ServerStats>,
+    /// Last lines scsynth printed before failing
+    log_tail: Vec<String>,
 }
 
 impl ServerPane {
@@ -70,12 +77,26 @@ impl ServerPane {
             selected_input,
             focus: ServerPaneFocus::Controls,
             device_config_dirty: false,
+            stats: None,
+            log_tail: Vec::new(),
         }
     }
 
     pub fn set_status(&mut self, status: ServerStatus, message: &str) {
         self.status = status;
         self.message = message.to_string();
+        if status != ServerStatus::Error {
+            self.log_tail.clear();
+        }
+    }
+
+    /// Server output to show with the current error
+    pub fn set_log_tail(&mut self, lines: Vec<String>) {
+        self.log_tail = lines;
+    }
+
+    pub fn set_server_stats(&mut self, stats: Option<ServerStats>) {
+        self.stats = stats;
     }
 
     pub fn set_server_running(&mut self, running: bool) {
@@ -174,6 +195,7 @@ impl Pane for ServerPane {
             "compile" => Action::Server(ServerAction::CompileSynthDefs),
             "load_synthdefs" => Action::Server(ServerAction::LoadSynthDefs),
             "record_master" => Action::Server(ServerAction::RecordMaster),
+            "render_click" => Action::Session(SessionAction::RenderClick),
             "refresh_devices" => {
                 self.refresh_devices();
                 if self.server_running {
@@ -259,13 +281,13 @@ impl Pane for ServerPane {
         let output_devs = self.output_devices();
         let input_devs = self.input_devices();
 
-        // Calculate height: status(4) + output header(1) + output items + gap(1) + input header(1) + input items + gap(1) + help(2) + borders(2)
+        // Calculate height: status(5) + output header(1) + output items + gap(1) + input header(1) + input items + gap(1) + help(2) + borders(2)
         let output_list_h = output_devs.len() + 1; // +1 for "System Default"
         let input_list_h = input_devs.len() + 1;
-        let content_h = 4 + 1 + output_list_h + 1 + 1 + input_list_h + 1 + 2;
+        let content_h = 5 + self.log_tail.len() + 1 + output_list_h + 1 + 1 + input_list_h + 1 + 2;
         let total_h = (content_h + 2).min(area.height as usize).max(15) as u16;
 
-        let rect = center_rect(area, 70, total_h);
+        let rect = fit_rect(area, 70, total_h);
 
         let block = Block::default()
             .borders(Borders::ALL)
@@ -277,7 +299,7 @@ impl Pane for ServerPane {
 
         let x = inner.x + 1;
         let w = inner.width.saturating_sub(2);
-        let label_style = ratatui::style::Style::from(Style::new().fg(Color::CYAN));
+        let label_style = ratatui::style::Style::from(Style::new().fg(theme().accent));
         let mut y = inner.y + 1;
 
         // Server process status
@@ -295,7 +317,7 @@ impl Pane for ServerPane {
 
         // Connection status
         let (status_text, status_color) = match self.status {
-            ServerStatus::Stopped => ("Not connected", Color::DARK_GRAY),
+            ServerStatus::Stopped => ("Not connected", theme().muted),
             ServerStatus::Starting => ("Starting...", Color::ORANGE),
             ServerStatus::Running => ("Ready (not connected)", Color::SOLO_COLOR),
             ServerStatus::Connected => ("Connected", Color::METER_LOW),
@@ -308,6 +330,30 @@ impl Pane for ServerPane {
         Paragraph::new(conn_line).render(RatatuiRect::new(x, y, w, 1), buf);
         y += 1;
 
+        // Server load
+        if let Some(stats) = self.stats {
+            let cpu_color = if stats.is_overloaded() {
+                Color::MUTE_COLOR
+            } else if stats.peak_cpu >= 50.0 {
+                Color::ORANGE
+            } else {
+                Color::METER_LOW
+            };
+            let load_line = Line::from(vec![
+                Span::styled("CPU:        ", label_style),
+                Span::styled(
+                    format!("{:.1}% avg  {:.1}% peak", stats.avg_cpu, stats.peak_cpu),
+                    ratatui::style::Style::from(Style::new().fg(cpu_color).bold()),
+                ),
+                Span::styled(
+                    format!("   {} synths  {} ugens  {} defs", stats.synths, stats.ugens, stats.synthdefs),
+                    ratatui::style::Style::from(Style::new().fg(theme().muted)),
+                ),
+            ]);
+            Paragraph::new(load_line).render(RatatuiRect::new(x, y, w, 1), buf);
+        }
+        y += 1;
+
         // Message
         if !self.message.is_empty() {
             let max_len = w as usize;
@@ -320,6 +366,15 @@ impl Pane for ServerPane {
         }
         y += 1;
 
+        // scsynth's last words after a failure
+        let log_style = ratatui::style::Style::from(Style::new().fg(theme().muted));
+        for line in &self.log_tail {
+            let text: String = line.chars().take(w as usize).collect();
+            Paragraph::new(Line::from(Span::styled(text, log_style)))
+                .render(RatatuiRect::new(x, y, w, 1), buf);
+            y += 1;
+        }
+
         // Recording status
         if state.recording {
             let mins = state.recording_secs / 60;
@@ -337,7 +392,7 @@ impl Pane for ServerPane {
 
         // Output Device section
         let output_focused = self.focus == ServerPaneFocus::OutputDevice;
-        let section_color = if output_focused { Color::GOLD } else { Color::DARK_GRAY };
+        let section_color = if output_focused { Color::GOLD } else { theme().muted };
         let section_style = ratatui::style::Style::from(Style::new().fg(section_color));
         let header = Line::from(Span::styled("── Output Device ──", section_style));
         Paragraph::new(header).render(RatatuiRect::new(x, y, w, 1), buf);
@@ -349,7 +404,7 @@ impl Pane for ServerPane {
 
         // Input Device section
         let input_focused = self.focus == ServerPaneFocus::InputDevice;
-        let section_color = if input_focused { Color::GOLD } else { Color::DARK_GRAY };
+        let section_color = if input_focused { Color::GOLD } else { theme().muted };
         let section_style = ratatui::style::Style::from(Style::new().fg(section_color));
         let header = Line::from(Span::styled("── Input Device ──", section_style));
         Paragraph::new(header).render(RatatuiRect::new(x, y, w, 1), buf);
@@ -371,7 +426,7 @@ impl Pane for ServerPane {
 
         // Help text at bottom
         let _ = y;
-        let help_style = ratatui::style::Style::from(Style::new().fg(Color::DARK_GRAY));
+        let help_style = ratatui::style::Style::from(Style::new().fg(theme().muted));
         let help_lines = [
             "s: start  k: kill  c: connect  d: disconnect  b: build  l: load",
             "r: refresh devices  Tab: next section",
@@ -407,16 +462,16 @@ impl ServerPane {
         selected: usize,
         focused: bool,
     ) -> u16 {
-        let normal_style = ratatui::style::Style::from(Style::new().fg(Color::WHITE));
+        let normal_style = ratatui::style::Style::from(Style::new().fg(theme().foreground));
         let selected_style = if focused {
             ratatui::style::Style::from(Style::new().fg(Color::GOLD).bold())
         } else {
-            ratatui::style::Style::from(Style::new().fg(Color::WHITE).bold())
+            ratatui::style::Style::from(Style::new().fg(theme().foreground).bold())
         };
         let marker_style = if focused {
             ratatui::style::Style::from(Style::new().fg(Color::GOLD))
         } else {
-            ratatui::style::Style::from(Style::new().fg(Color::WHITE))
+            ratatui::style::Style::from(Style::new().fg(theme().foreground))
         };
 
         // "System Default" entry (index 0)
@@ -458,7 +513,7 @@ impl ServerPane {
                 format!("  ({})", info_parts.join(", "))
             };
 
-            let info_style = ratatui::style::Style::from(Style::new().fg(Color::DARK_GRAY));
+            let info_style = ratatui::style::Style::from(Style::new().fg(theme().muted));
 
             let line = Line::from(vec![
                 Span::styled(marker, marker_style),
@@ -6,8 +6,8 @@ use ratatui::text::{Line, Span};
 use ratatui::widgets::{Block, Borders, Paragraph, Widget};
 
 use crate::state::{AppState, SourceType};
-use crate::ui::layout_helpers::center_rect;
-use crate::ui::{Action, Color, InputEvent, Keymap, Pane, Style};
+use crate::ui::layout_helpers::fit_rect;
+use crate::ui::{Action, Color, InputEvent, Keymap, Pane, Style, theme};
 
 fn source_color(source: SourceType) -> Color {
     match source {
@@ -49,13 +49,13 @@ impl Pane for TrackPane {
     }
 
     fn render(&self, area: RatatuiRect, buf: &mut Buffer, state: &AppState) {
-        let rect = center_rect(area, 97, 29);
+        let rect = fit_rect(area, 97, 29);
 
         let block = Block::default()
             .borders(Borders::ALL)
             .title(" Track ")
-            .border_style(ratatui::style::Style::from(Style::new().fg(Color::CYAN)))
-            .title_style(ratatui::style::Style::from(Style::new().fg(Color::CYAN)));
+            .border_style(ratatui::style::Style::from(Style::new().fg(theme().accent)))
+            .title_style(ratatui::style::Style::from(Style::new().fg(theme().accent)));
         let inner = block.inner(rect);
         block.render(rect, buf);
 
@@ -65,7 +65,7 @@ impl Pane for TrackPane {
             let y = inner.y + inner.height / 2;
             Paragraph::new(Line::from(Span::styled(
                 text,
-                ratatui::style::Style::from(Style::new().fg(Color::DARK_GRAY)),
+                ratatui::style::Style::from(Style::new().fg(theme().muted)),
             )))
             .render(RatatuiRect::new(x, y, text.len() as u16, 1), buf);
             return;
@@ -86,7 +86,7 @@ impl Pane for TrackPane {
             .map(|s| if s >= max_visible { s - max_visible + 1 } else { 0 })
             .unwrap_or(0);
 
-        let sel_bg = ratatui::style::Style::from(Style::new().bg(Color::SELECTION_BG));
+        let sel_bg = ratatui::style::Style::from(Style::new().bg(theme().selection));
         let separator_style = ratatui::style::Style::from(Style::new().fg(Color::new(40, 40, 40)));
 
         for (vi, i) in (scroll_offset..num_instruments).enumerate() {
@@ -107,7 +107,7 @@ impl Pane for TrackPane {
             if is_selected {
                 if let Some(cell) = buf.cell_mut((inner.x, lane_y)) {
                     cell.set_char('>').set_style(
-                        ratatui::style::Style::from(Style::new().fg(Color::WHITE).bg(Color::SELECTION_BG).bold()),
+                        ratatui::style::Style::from(Style::new().fg(theme().foreground).bg(theme().selection).bold()),
                     );
                 }
             }
@@ -117,14 +117,14 @@ impl Pane for TrackPane {
             let name_str = format!("{}", &instrument.name[..instrument.name.len().min(14)]);
 
             let num_style = if is_selected {
-                ratatui::style::Style::from(Style::new().fg(Color::DARK_GRAY).bg(Color::SELECTION_BG))
+                ratatui::style::Style::from(Style::new().fg(theme().muted).bg(theme().selection))
             } else {
-                ratatui::style::Style::from(Style::new().fg(Color::DARK_GRAY))
+                ratatui::style::Style::from(Style::new().fg(theme().muted))
             };
             let name_style = if is_selected {
-                ratatui::style::Style::from(Style::new().fg(Color::WHITE).bg(Color::SELECTION_BG).bold())
+                ratatui::style::Style::from(Style::new().fg(theme().foreground).bg(theme().selection).bold())
             } else {
-                ratatui::style::Style::from(Style::new().fg(Color::WHITE))
+                ratatui::style::Style::from(Style::new().fg(theme().foreground))
             };
 
             let label_line = Line::from(vec![
@@ -139,7 +139,7 @@ impl Pane for TrackPane {
             if lane_height > 2 {
                 let src_str = format!("   {}", instrument.source.name());
                 let src_style = if is_selected {
-                    ratatui::style::Style::from(Style::new().fg(source_c).bg(Color::SELECTION_BG))
+                    ratatui::style::Style::from(Style::new().fg(source_c).bg(theme().selection))
                 } else {
                     ratatui::style::Style::from(Style::new().fg(source_c))
                 };
@@ -169,7 +169,7 @@ impl Pane for TrackPane {
                 if y >= inner.y + inner.height { break; }
                 if let Some(cell) = buf.cell_mut((inner.x + label_width, y)) {
                     cell.set_char('│').set_style(
-                        ratatui::style::Style::from(Style::new().fg(Color::GRAY)),
+                        ratatui::style::Style::from(Style::new().fg(theme().border)),
                     );
                 }
             }
@@ -228,7 +228,7 @@ impl Pane for TrackPane {
                 let bar_num = (col / 16) + 1;
                 let label = format!("{}", bar_num);
                 let x = timeline_x + col;
-                let label_style = ratatui::style::Style::from(Style::new().fg(Color::DARK_GRAY));
+                let label_style = ratatui::style::Style::from(Style::new().fg(theme().muted));
                 for (j, ch) in label.chars().enumerate() {
                     if x + (j as u16) < inner.x + inner.width {
                         if let Some(cell) = buf.cell_mut((x + j as u16, footer_y)) {
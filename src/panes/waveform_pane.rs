@@ -6,14 +6,25 @@ use ratatui::text::{Line, Span};
 use ratatui::widgets::{Block, Borders, Paragraph, Widget};
 
 use crate::state::AppState;
-use crate::ui::layout_helpers::center_rect;
-use crate::ui::{Action, Color, InputEvent, Keymap, Pane, Style};
+use crate::ui::layout_helpers::fit_rect;
+use crate::ui::{color_depth, Action, Color, InputEvent, Keymap, Pane, Style, theme};
 
 /// Waveform display characters (8 levels)
 const WAVEFORM_CHARS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
 
+/// Smooth center-to-edge colors for terminals with enough colors
+const WAVEFORM_GRADIENT: [Color; 4] = [
+    Color::new(60, 200, 80),
+    Color::new(200, 200, 40),
+    Color::new(220, 120, 30),
+    Color::new(220, 40, 40),
+];
+
 /// Color a waveform row by its distance from center (0.0=center, 1.0=edge)
 fn waveform_color(frac: f32) -> Color {
+    if color_depth().supports_gradients() {
+        return Color::gradient(&WAVEFORM_GRADIENT, frac);
+    }
     if frac > 0.85 {
         Color::new(220, 40, 40)   // red
     } else if frac > 0.7 {
@@ -56,7 +67,7 @@ impl Pane for WaveformPane {
             .or(state.audio_in_waveform.as_deref())
             .unwrap_or(&[]);
 
-        let rect = center_rect(area, 97, 29);
+        let rect = fit_rect(area, 97, 29);
 
         let header_height: u16 = 2;
         let footer_height: u16 = 2;
@@ -91,7 +102,7 @@ impl Pane for WaveformPane {
         );
         Paragraph::new(Line::from(Span::styled(
             header_text,
-            ratatui::style::Style::from(Style::new().fg(Color::WHITE)),
+            ratatui::style::Style::from(Style::new().fg(theme().foreground)),
         ))).render(RatatuiRect::new(rect.x + 1, header_y, rect.width.saturating_sub(2), 1), buf);
 
         // Waveform display area
@@ -99,7 +110,7 @@ impl Pane for WaveformPane {
         let half_height = (grid_height / 2) as f32;
 
         // Draw center line
-        let dark_gray = ratatui::style::Style::from(Style::new().fg(Color::DARK_GRAY));
+        let dark_gray = ratatui::style::Style::from(Style::new().fg(theme().muted));
         for x in 0..grid_width {
             if let Some(cell) = buf.cell_mut((grid_x + x, center_y)) {
                 cell.set_char('─').set_style(dark_gray);
@@ -156,7 +167,7 @@ impl Pane for WaveformPane {
         let status = format!("Samples: {}", waveform_len);
         Paragraph::new(Line::from(Span::styled(
             status,
-            ratatui::style::Style::from(Style::new().fg(Color::GRAY)),
+            ratatui::style::Style::from(Style::new().fg(theme().border)),
         ))).render(RatatuiRect::new(rect.x + 1, status_y, rect.width.saturating_sub(2), 1), buf);
     }
 
@@ -42,10 +42,8 @@ pub fn tick_playback(
                 let mut note_ons: Vec<(u32, u8, u8, u32, u32)> = Vec::new();
                 for &instrument_id in &pr.track_order {
                     if let Some(track) = pr.tracks.get(&instrument_id) {
-                        for note in &track.notes {
-                            if note.tick >= scan_start && note.tick < scan_end {
-                                note_ons.push((instrument_id, note.pitch, note.velocity, note.duration, note.tick));
-                            }
+                        for note in track.notes_between(scan_start, scan_end) {
+                            note_ons.push((instrument_id, note.pitch, note.played_velocity(), note.duration, note.tick));
                         }
                     }
                 }
@@ -73,7 +71,8 @@ pub fn tick_playback(
 
             // Process automation
             for lane in &state.session.automation.lanes {
-                if !lane.enabled {
+                // Lanes being recorded from a controller follow the controller, not the old points
+                if !lane.enabled || state.session.midi_recording.is_writing(&lane.target) {
                     continue;
                 }
                 if let Some(value) = lane.value_at(new_playhead) {
@@ -82,27 +81,69 @@ pub fn tick_playback(
             }
         }
 
-        // Process active notes: decrement remaining ticks, send note-offs
-        let mut note_offs: Vec<(u32, u8, u32)> = Vec::new();
-        for note in active_notes.iter_mut() {
-            if note.2 <= tick_delta {
-                note_offs.push((note.0, note.1, note.2));
-                note.2 = 0;
-            } else {
-                note.2 -= tick_delta;
-            }
+        release_due_notes(state, audio_engine, active_notes, tick_delta, secs_per_tick);
+    } else if !state.session.piano_roll.playing && !active_notes.is_empty() {
+        // Live-played notes still auto-release with the transport stopped
+        let pr = &state.session.piano_roll;
+        let tick_delta = (elapsed.as_secs_f32() * (pr.bpm / 60.0) * pr.ticks_per_beat as f32) as u32;
+        if tick_delta > 0 {
+            let secs_per_tick = 60.0 / (pr.bpm as f64 * pr.ticks_per_beat as f64);
+            release_due_notes(state, audio_engine, active_notes, tick_delta, secs_per_tick);
         }
-        active_notes.retain(|n| n.2 > 0);
+    }
+}
 
-        if audio_engine.is_running() {
-            for (instrument_id, pitch, remaining) in &note_offs {
-                let offset = *remaining as f64 * secs_per_tick;
-                let _ = audio_engine.release_voice(*instrument_id, *pitch, offset, &state.instruments);
-            }
+/// Count down active notes by `tick_delta` and release those that run out, offset to
+/// where in the elapsed span they ended.
+fn release_due_notes(
+    state: &AppState,
+    audio_engine: &mut AudioEngine,
+    active_notes: &mut Vec<(u32, u8, u32)>,
+    tick_delta: u32,
+    secs_per_tick: f64,
+) {
+    let note_offs = count_down_notes(active_notes, tick_delta);
+    if audio_engine.is_running() {
+        for (instrument_id, pitch, remaining) in &note_offs {
+            let offset = *remaining as f64 * secs_per_tick;
+            let _ = audio_engine.release_voice(*instrument_id, *pitch, offset, &state.instruments);
         }
     }
 }
 
+/// Keep a held key's notes sounding. When every pitch is still in `active_notes` for
+/// the instrument, all of them are given the same time left, at least `ticks`, so a
+/// chord released together ends together. Returns false, changing nothing, when any
+/// pitch has already been released (the press is then a new note, not a repeat).
+pub fn hold_live_notes(active_notes: &mut [(u32, u8, u32)], instrument_id: u32, pitches: &[u8], ticks: u32) -> bool {
+    let sounding = |pitch: u8| active_notes.iter().any(|n| n.0 == instrument_id && n.1 == pitch);
+    if pitches.is_empty() || !pitches.iter().all(|&p| sounding(p)) {
+        return false;
+    }
+    let held = |n: &(u32, u8, u32)| n.0 == instrument_id && pitches.contains(&n.1);
+    let left = active_notes.iter().filter(|n| held(n)).map(|n| n.2).max().unwrap_or(0).max(ticks);
+    for note in active_notes.iter_mut().filter(|n| held(n)) {
+        note.2 = left;
+    }
+    true
+}
+
+/// Decrement remaining ticks, removing and returning the notes that end within `tick_delta`
+/// along with the ticks they had left.
+fn count_down_notes(active_notes: &mut Vec<(u32, u8, u32)>, tick_delta: u32) -> Vec<(u32, u8, u32)> {
+    let mut note_offs: Vec<(u32, u8, u32)> = Vec::new();
+    for note in active_notes.iter_mut() {
+        if note.2 <= tick_delta {
+            note_offs.push((note.0, note.1, note.2));
+            note.2 = 0;
+        } else {
+            note.2 -= tick_delta;
+        }
+    }
+    active_notes.retain(|n| n.2 > 0);
+    note_offs
+}
+
 /// Advance the drum sequencer for each drum machine instrument and trigger pad hits.
 pub fn tick_drum_sequencer(
     state: &mut AppState,
@@ -117,11 +158,11 @@ pub fn tick_drum_sequencer(
             None => continue,
         };
         if !seq.playing {
-            seq.last_played_step = None;
+            seq.play_position = None;
             continue;
         }
 
-        let pattern_length = seq.pattern().length;
+        let mut pattern_length = seq.pattern().length;
         let steps_per_beat = 4.0_f32;
         let steps_per_second = (bpm / 60.0) * steps_per_beat;
 
@@ -130,32 +171,76 @@ pub fn tick_drum_sequencer(
         while seq.step_accumulator >= 1.0 {
             seq.step_accumulator -= 1.0;
             seq.current_step = (seq.current_step + 1) % pattern_length;
+            if seq.current_step == 0 {
+                seq.finish_pass();
+                pattern_length = seq.pattern().length;
+            }
         }
 
-        if seq.last_played_step != Some(seq.current_step) {
-            if audio_engine.is_running() && !instrument.mute {
-                let current_step = seq.current_step;
-                let current_pattern = seq.current_pattern;
-                let pattern = &seq.patterns[current_pattern];
-                for (pad_idx, pad) in seq.pads.iter().enumerate() {
-                    if let Some(buffer_id) = pad.buffer_id {
-                        if let Some(step) = pattern
-                            .steps
-                            .get(pad_idx)
-                            .and_then(|s| s.get(current_step))
-                        {
-                            if step.active {
-                                let amp = (step.velocity as f32 / 127.0) * pad.level;
-                                let _ = audio_engine.play_drum_hit_to_instrument(
-                                    buffer_id, amp, instrument.id,
-                                    pad.slice_start, pad.slice_end,
-                                );
-                            }
-                        }
-                    }
+        // Hits are due when their nudged time falls between the last tick and now.
+        // Times are unwrapped across the loop seam, so an early step one can fire at
+        // the end of the previous pass.
+        let now = seq.current_step as f32 + seq.step_accumulator;
+        let len = pattern_length as f32;
+        let (from, to) = match seq.play_position.replace(now) {
+            Some(prev) if now >= prev => (prev, now),
+            Some(prev) => (prev, now + len),
+            // Just started: include step one even if it's pushed early
+            None => (-1.0, now),
+        };
+        if to > from && audio_engine.is_running() && !instrument.mute {
+            let pattern = &seq.patterns[seq.current_pattern];
+            let hits: Vec<(usize, usize, u8)> = pattern.steps.iter()
+                .enumerate()
+                .flat_map(|(pad_idx, steps)| {
+                    steps.iter().take(pattern_length).enumerate().filter_map(move |(i, step)| {
+                        let t = i as f32 + step.nudge;
+                        let due = [t, t + len].iter().any(|&c| from < c && c <= to);
+                        (step.active && due).then_some((pad_idx, i, step.played_velocity()))
+                    })
+                })
+                .collect();
+            for (pad_idx, step_idx, velocity) in hits {
+                if pad_idx >= seq.pads.len() {
+                    continue;
+                }
+                let buffers = seq.next_hit(pad_idx, velocity);
+                let hit = seq.pads[pad_idx].hit(&seq.pattern().steps[pad_idx][step_idx].locks);
+                let amp = (velocity as f32 / 127.0) * hit.level;
+                for (buffer_id, gain) in buffers {
+                    let _ = audio_engine.play_drum_hit_to_instrument(buffer_id, amp * gain, instrument.id, &hit);
                 }
             }
-            seq.last_played_step = Some(seq.current_step);
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_count_down_notes_releases_expired() {
+        let mut active = vec![(1, 60, 100), (1, 64, 30), (2, 67, 50)];
+        let offs = count_down_notes(&mut active, 50);
+        assert_eq!(offs, vec![(1, 64, 30), (2, 67, 50)]);
+        assert_eq!(active, vec![(1, 60, 50)]);
+        assert!(count_down_notes(&mut active, 10).is_empty());
+        assert_eq!(active, vec![(1, 60, 40)]);
+    }
+
+    #[test]
+    fn test_hold_live_notes_extends_chords_together() {
+        let mut active = vec![(1, 60, 30), (1, 64, 80), (2, 60, 10)];
+        // A chord holds as one: both notes get the longer time left
+        assert!(hold_live_notes(&mut active, 1, &[60, 64], 50));
+        assert_eq!(active, vec![(1, 60, 80), (1, 64, 80), (2, 60, 10)]);
+        assert!(hold_live_notes(&mut active, 1, &[60, 64], 100));
+        assert_eq!(active, vec![(1, 60, 100), (1, 64, 100), (2, 60, 10)]);
+        // A released pitch makes the press a new note
+        assert!(!hold_live_notes(&mut active, 1, &[60, 67], 100));
+        assert!(!hold_live_notes(&mut active, 3, &[60], 100));
+        assert!(!hold_live_notes(&mut active, 1, &[], 100));
+        assert_eq!(active, vec![(1, 60, 100), (1, 64, 100), (2, 60, 10)]);
+    }
+}
@@ -1,6 +1,6 @@
 use crate::audio::devices;
 use crate::audio::{self, AudioEngine};
-use crate::panes::ServerPane;
+use crate::panes::{ServerPane, SERVER_LOG_TAIL};
 use crate::state::AppState;
 use crate::ui::PaneManager;
 
@@ -49,8 +49,11 @@ pub fn auto_start_sc(
                 }
             }
         }
-        Err(_e) => {
-            // Server start failed — status remains Stopped
+        Err(e) => {
+            if let Some(server) = panes.get_pane_mut::<ServerPane>("server") {
+                server.set_status(audio::ServerStatus::Error, &e);
+                server.set_log_tail(audio_engine.server_log_tail(SERVER_LOG_TAIL));
+            }
         }
     }
 }
@@ -1,9 +1,18 @@
 #![allow(dead_code)]
 
-use super::instrument::InstrumentId;
+use serde::{Deserialize, Serialize};
+
+use super::instrument::{Instrument, InstrumentId, TRIM_RANGE_DB};
+use super::piano_roll::rescale_tick;
 
 pub type AutomationLaneId = u32;
 
+/// Most points `render_points` returns for one lane; finer grids are coarsened to fit
+pub const MAX_RENDERED_POINTS: usize = 100_000;
+
+/// How far (normalized) thinning lets a recorded curve drift: half a CC step
+pub const RECORD_THIN_TOLERANCE: f32 = 0.5 / 127.0;
+
 /// Interpolation curve type between automation points
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum CurveType {
@@ -53,12 +62,14 @@ impl AutomationPoint {
 }
 
 /// What parameter is being automated
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum AutomationTarget {
     /// Instrument output level
     InstrumentLevel(InstrumentId),
     /// Instrument pan
     InstrumentPan(InstrumentId),
+    /// Instrument pre-fader trim, in dB
+    InstrumentTrim(InstrumentId),
     /// Filter cutoff frequency
     FilterCutoff(InstrumentId),
     /// Filter resonance
@@ -72,11 +83,35 @@ pub enum AutomationTarget {
 }
 
 impl AutomationTarget {
+    /// Every target an instrument has: mixer controls, then its filter, sampler and
+    /// effect parameters when present
+    pub fn all_for(instrument: &Instrument) -> Vec<AutomationTarget> {
+        let id = instrument.id;
+        let mut targets = vec![
+            AutomationTarget::InstrumentLevel(id),
+            AutomationTarget::InstrumentPan(id),
+            AutomationTarget::InstrumentTrim(id),
+        ];
+        if instrument.filter.is_some() {
+            targets.push(AutomationTarget::FilterCutoff(id));
+            targets.push(AutomationTarget::FilterResonance(id));
+        }
+        if instrument.sampler_config.is_some() {
+            targets.push(AutomationTarget::SampleRate(id));
+            targets.push(AutomationTarget::SampleAmp(id));
+        }
+        for (fx_idx, slot) in instrument.effects.iter().enumerate() {
+            targets.extend((0..slot.params.len()).map(|param_idx| AutomationTarget::EffectParam(id, fx_idx, param_idx)));
+        }
+        targets
+    }
+
     /// Get the instrument ID associated with this target
     pub fn instrument_id(&self) -> InstrumentId {
         match self {
             AutomationTarget::InstrumentLevel(id) => *id,
             AutomationTarget::InstrumentPan(id) => *id,
+            AutomationTarget::InstrumentTrim(id) => *id,
             AutomationTarget::FilterCutoff(id) => *id,
             AutomationTarget::FilterResonance(id) => *id,
             AutomationTarget::EffectParam(id, _, _) => *id,
@@ -90,6 +125,7 @@ impl AutomationTarget {
         match self {
             AutomationTarget::InstrumentLevel(_) => "Level".to_string(),
             AutomationTarget::InstrumentPan(_) => "Pan".to_string(),
+            AutomationTarget::InstrumentTrim(_) => "Trim".to_string(),
             AutomationTarget::FilterCutoff(_) => "Filter Cutoff".to_string(),
             AutomationTarget::FilterResonance(_) => "Filter Resonance".to_string(),
             AutomationTarget::EffectParam(_, fx_idx, param_idx) => {
@@ -105,6 +141,7 @@ impl AutomationTarget {
         match self {
             AutomationTarget::InstrumentLevel(_) => (0.0, 1.0),
             AutomationTarget::InstrumentPan(_) => (-1.0, 1.0),
+            AutomationTarget::InstrumentTrim(_) => TRIM_RANGE_DB,
             AutomationTarget::FilterCutoff(_) => (20.0, 20000.0),
             AutomationTarget::FilterResonance(_) => (0.0, 1.0),
             AutomationTarget::EffectParam(_, _, _) => (0.0, 1.0),
@@ -150,6 +187,69 @@ impl AutomationLane {
         self.points.insert(pos, point);
     }
 
+    /// A value in the parameter's range as a normalized point value
+    pub fn normalize(&self, value: f32) -> f32 {
+        let range = self.max_value - self.min_value;
+        if range.abs() > f32::EPSILON {
+            ((value - self.min_value) / range).clamp(0.0, 1.0)
+        } else {
+            0.0
+        }
+    }
+
+    /// Record a point while writing live: anything between the previous write at
+    /// `since` and `tick` is replaced, so a pass over old automation overwrites it
+    pub fn write_point(&mut self, since: Option<u32>, tick: u32, value: f32) {
+        if let Some(since) = since {
+            self.points.retain(|p| p.tick <= since || p.tick >= tick);
+        }
+        self.add_point(tick, value);
+    }
+
+    /// Drop points between `start` and `end` that the curve doesn't need: the
+    /// lane stays within `tolerance` (normalized) of the original everywhere.
+    /// Points outside the span and the span's end points are kept.
+    pub fn thin(&mut self, start: u32, end: u32, tolerance: f32) {
+        let (Some(first), Some(last)) = (
+            self.points.iter().position(|p| p.tick >= start),
+            self.points.iter().rposition(|p| p.tick <= end),
+        ) else {
+            return;
+        };
+        if last <= first + 1 {
+            return;
+        }
+        let mut keep = vec![false; last - first + 1];
+        keep[0] = true;
+        keep[last - first] = true;
+        // Douglas-Peucker on (tick, value), with the error measured vertically
+        let span = &self.points[first..=last];
+        let mut stack = vec![(0, span.len() - 1)];
+        while let Some((a, b)) = stack.pop() {
+            let (pa, pb) = (&span[a], &span[b]);
+            let width = (pb.tick - pa.tick).max(1) as f32;
+            let worst = (a + 1..b)
+                .map(|i| {
+                    let t = (span[i].tick - pa.tick) as f32 / width;
+                    (i, (span[i].value - (pa.value + (pb.value - pa.value) * t)).abs())
+                })
+                .max_by(|x, y| x.1.total_cmp(&y.1));
+            if let Some((i, err)) = worst {
+                if err > tolerance {
+                    keep[i] = true;
+                    stack.push((a, i));
+                    stack.push((i, b));
+                }
+            }
+        }
+        let mut idx = 0;
+        self.points.retain(|_| {
+            let kept = idx < first || idx > last || keep[idx - first];
+            idx += 1;
+            kept
+        });
+    }
+
     /// Remove point at or near the given tick
     pub fn remove_point(&mut self, tick: u32) {
         self.points.retain(|p| p.tick != tick);
@@ -208,6 +308,45 @@ impl AutomationLane {
         }
     }
 
+    /// Grid `render_points` actually uses for a requested resolution: the
+    /// resolution itself, or a coarser one when it would exceed `MAX_RENDERED_POINTS`
+    pub fn render_resolution(&self, resolution_ticks: u32) -> u32 {
+        let resolution = resolution_ticks.max(1);
+        let (Some(first), Some(last)) = (self.points.first(), self.points.last()) else {
+            return resolution;
+        };
+        let span = (last.tick - first.tick) as usize;
+        // Room left for grid lines once every authored point is kept
+        let budget = MAX_RENDERED_POINTS.saturating_sub(self.points.len() + 1).max(1);
+        if span / resolution as usize <= budget {
+            resolution
+        } else {
+            span.div_ceil(budget) as u32
+        }
+    }
+
+    /// Sample the interpolated curve on a fixed grid from the first point to the
+    /// last, as (tick, value) pairs in the parameter's range. Each value holds until
+    /// the next pair. Authored points are always included so Step jumps land on
+    /// their exact tick. Empty for disabled lanes.
+    pub fn render_points(&self, resolution_ticks: u32) -> Vec<(u32, f32)> {
+        let (Some(first), Some(last)) = (self.points.first(), self.points.last()) else {
+            return Vec::new();
+        };
+        if !self.enabled {
+            return Vec::new();
+        }
+        let resolution = self.render_resolution(resolution_ticks);
+        let mut ticks: Vec<u32> = (first.tick..=last.tick).step_by(resolution as usize).collect();
+        ticks.extend(self.points.iter().map(|p| p.tick));
+        ticks.sort_unstable();
+        ticks.dedup();
+        ticks
+            .into_iter()
+            .filter_map(|tick| self.value_at(tick).map(|value| (tick, value)))
+            .collect()
+    }
+
     /// Get the first point at or after the given tick
     pub fn point_at_or_after(&self, tick: u32) -> Option<&AutomationPoint> {
         self.points.iter().find(|p| p.tick >= tick)
@@ -234,6 +373,9 @@ impl AutomationLane {
 pub struct AutomationState {
     pub lanes: Vec<AutomationLane>,
     pub selected_lane: Option<usize>,
+    /// Grid (divisions per beat) lanes are flattened to in the save file; None saves
+    /// only the authored points
+    pub freeze_division: Option<u32>,
     next_lane_id: AutomationLaneId,
 }
 
@@ -242,10 +384,47 @@ impl AutomationState {
         Self {
             lanes: Vec::new(),
             selected_lane: None,
+            freeze_division: None,
             next_lane_id: 0,
         }
     }
 
+    /// Cycle the freeze grid: off, 1/16, 1/32, 1/64, 1/128
+    pub fn cycle_freeze_division(&mut self) {
+        self.freeze_division = match self.freeze_division {
+            None => Some(4),
+            Some(4) => Some(8),
+            Some(8) => Some(16),
+            Some(16) => Some(32),
+            Some(_) => None,
+        };
+    }
+
+    /// Freeze grid in ticks, if freezing is on
+    pub fn freeze_resolution(&self, ticks_per_beat: u32) -> Option<u32> {
+        self.freeze_division.map(|div| (ticks_per_beat / div.max(1)).max(1))
+    }
+
+    /// Move every point from one tick resolution to another (see
+    /// `PianoRollState::set_ticks_per_beat`). Points that land on the same tick
+    /// keep the first.
+    pub fn rescale_ticks(&mut self, from_tpb: u32, to_tpb: u32) {
+        for lane in &mut self.lanes {
+            for point in &mut lane.points {
+                point.tick = rescale_tick(point.tick, from_tpb, to_tpb);
+            }
+            lane.points.dedup_by_key(|p| p.tick);
+        }
+    }
+
+    /// Lanes whose span is too long to freeze at `resolution_ticks` without coarsening
+    pub fn coarsened_lanes(&self, resolution_ticks: u32) -> Vec<&AutomationLane> {
+        self.lanes
+            .iter()
+            .filter(|lane| lane.render_resolution(resolution_ticks) > resolution_ticks.max(1))
+            .collect()
+    }
+
     /// Recalculate next_lane_id from existing lanes (used after loading from DB)
     pub fn recalculate_next_lane_id(&mut self) {
         self.next_lane_id = self.lanes.iter().map(|l| l.id).max().map_or(0, |m| m + 1);
@@ -401,6 +580,43 @@ mod tests {
         assert!((lane.value_at(50).unwrap() - 0.0).abs() < 0.01);
     }
 
+    #[test]
+    fn test_render_points() {
+        let mut lane = AutomationLane::new(0, AutomationTarget::InstrumentLevel(0));
+        lane.points.push(AutomationPoint::with_curve(0, 0.0, CurveType::Step));
+        lane.points.push(AutomationPoint::with_curve(250, 1.0, CurveType::SCurve));
+        lane.points.push(AutomationPoint::new(500, 0.0));
+        lane.min_value = 0.0;
+        lane.max_value = 1.0;
+
+        let points = lane.render_points(100);
+        let ticks: Vec<u32> = points.iter().map(|&(t, _)| t).collect();
+        assert_eq!(ticks, [0, 100, 200, 250, 300, 400, 500]);
+        // Step holds right up to the authored jump
+        assert_eq!(points[2].1, 0.0);
+        assert_eq!(points[3].1, 1.0);
+        // SCurve is sampled through its shape: 1/5 of the way down is 0.104 lower
+        assert!((points[4].1 - (1.0 - 0.104)).abs() < 0.001);
+
+        lane.enabled = false;
+        assert!(lane.render_points(100).is_empty());
+    }
+
+    #[test]
+    fn test_render_points_capped() {
+        let mut lane = AutomationLane::new(0, AutomationTarget::InstrumentLevel(0));
+        lane.add_point(0, 0.0);
+        lane.add_point(10_000_000, 1.0);
+        assert!(lane.render_resolution(1) > 1);
+        assert!(lane.render_points(1).len() <= MAX_RENDERED_POINTS);
+        assert_eq!(lane.render_resolution(1_000), 1_000);
+
+        let mut state = AutomationState::new();
+        state.lanes.push(lane);
+        assert_eq!(state.coarsened_lanes(1).len(), 1);
+        assert!(state.coarsened_lanes(1_000).is_empty());
+    }
+
     #[test]
     fn test_automation_state() {
         let mut state = AutomationState::new();
@@ -434,4 +650,38 @@ mod tests {
         assert!((val_at_0 - 20.0).abs() < 1.0);
         assert!((val_at_100 - 20000.0).abs() < 1.0);
     }
+
+    #[test]
+    fn test_thin_keeps_shape_within_tolerance() {
+        let mut lane = AutomationLane::new(0, AutomationTarget::InstrumentLevel(0));
+        // A ramp up then a hold, one point per tick, plus a point outside the span
+        for tick in 0..=100 {
+            lane.add_point(tick, tick as f32 / 100.0);
+        }
+        for tick in 101..=200 {
+            lane.add_point(tick, 1.0);
+        }
+        lane.add_point(500, 0.2);
+        lane.thin(0, 200, RECORD_THIN_TOLERANCE);
+
+        let ticks: Vec<u32> = lane.points.iter().map(|p| p.tick).collect();
+        assert_eq!(ticks, vec![0, 100, 200, 500]);
+        for tick in (0..=200).step_by(7) {
+            let expected = (tick as f32 / 100.0).min(1.0);
+            assert!((lane.value_at(tick).unwrap() - expected).abs() <= RECORD_THIN_TOLERANCE);
+        }
+    }
+
+    #[test]
+    fn test_write_point_overwrites_since_last_write() {
+        let mut lane = AutomationLane::new(0, AutomationTarget::InstrumentLevel(0));
+        for tick in [0, 50, 100, 150] {
+            lane.add_point(tick, 0.5);
+        }
+        lane.write_point(None, 40, 0.1);
+        lane.write_point(Some(40), 120, 0.9);
+        let ticks: Vec<u32> = lane.points.iter().map(|p| p.tick).collect();
+        assert_eq!(ticks, vec![0, 40, 120, 150]);
+        assert_eq!(lane.normalize(0.25), 0.25);
+    }
 }
@@ -58,6 +58,10 @@ impl CustomSynthDefRegistry {
         self.synthdefs.iter().find(|s| s.synthdef_name == name)
     }
 
+    pub fn remove_by_name(&mut self, name: &str) {
+        self.synthdefs.retain(|s| s.synthdef_name != name);
+    }
+
     pub fn is_empty(&self) -> bool {
         self.synthdefs.is_empty()
     }
@@ -66,3 +70,48 @@ impl CustomSynthDefRegistry {
         self.synthdefs.len()
     }
 }
+
+/// Per-file outcome of importing a folder of .scd files
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SynthDefImportReport {
+    pub loaded: Vec<String>,
+    /// Synthdef names already registered (or repeated within the folder)
+    pub skipped: Vec<String>,
+    /// (file or synthdef name, error)
+    pub failed: Vec<(String, String)>,
+}
+
+impl SynthDefImportReport {
+    /// One-line summary for a toast
+    pub fn summary(&self) -> String {
+        let total = self.loaded.len() + self.skipped.len() + self.failed.len();
+        let mut msg = format!("Imported {}/{} synthdefs", self.loaded.len(), total);
+        if !self.skipped.is_empty() {
+            msg.push_str(&format!(" | skipped (duplicate): {}", self.skipped.join(", ")));
+        }
+        if !self.failed.is_empty() {
+            let names: Vec<&str> = self.failed.iter().map(|(name, _)| name.as_str()).collect();
+            msg.push_str(&format!(" | failed: {}", names.join(", ")));
+        }
+        msg
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_import_report_summary() {
+        let report = SynthDefImportReport {
+            loaded: vec!["bass".into(), "lead".into()],
+            skipped: vec!["pad".into()],
+            failed: vec![("broken.scd".into(), "parse error".into())],
+        };
+        assert_eq!(
+            report.summary(),
+            "Imported 2/4 synthdefs | skipped (duplicate): pad | failed: broken.scd"
+        );
+        assert_eq!(SynthDefImportReport::default().summary(), "Imported 0/0 synthdefs");
+    }
+}
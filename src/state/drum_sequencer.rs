@@ -1,4 +1,6 @@
-use super::sampler::{BufferId, Slice, SliceId};
+use serde::{Deserialize, Serialize};
+
+use super::sampler::{layers_for_velocity, BufferId, Slice, SliceId, VelocityLayer};
 
 pub const NUM_PADS: usize = 12;
 #[allow(dead_code)]
@@ -16,12 +18,22 @@ pub struct ChopperState {
     pub next_slice_id: SliceId,
     pub waveform_peaks: Vec<f32>,
     pub duration_secs: f32,
+    /// Sample as first loaded, kept while `path` points at a trimmed/normalized copy
+    pub original_path: Option<String>,
+    /// Outcome of the last sample edit, shown in the chopper header
+    pub status: Option<String>,
 }
 
 #[derive(Debug, Clone)]
 pub struct DrumStep {
     pub active: bool,
     pub velocity: u8, // 1-127, default 100
+    /// Timing offset from the grid, as a fraction of a step (set by grooves)
+    pub nudge: f32,
+    /// Velocity scale from the last groove applied, on top of `velocity`
+    pub groove_velocity: f32,
+    /// Parameter locks: pad settings overridden for this step's hits only
+    pub locks: Vec<(LockParam, f32)>,
 }
 
 impl Default for DrumStep {
@@ -29,10 +41,126 @@ impl Default for DrumStep {
         Self {
             active: false,
             velocity: 100,
+            nudge: 0.0,
+            groove_velocity: 1.0,
+            locks: Vec::new(),
+        }
+    }
+}
+
+impl DrumStep {
+    /// Velocity the step plays at, with the groove's scale applied
+    pub fn played_velocity(&self) -> u8 {
+        (self.velocity as f32 * self.groove_velocity).round().clamp(1.0, 127.0) as u8
+    }
+
+    pub fn lock(&self, param: LockParam) -> Option<f32> {
+        self.locks.iter().find(|(p, _)| *p == param).map(|(_, v)| *v)
+    }
+
+    /// Lock `param` to `value`, clamped to its range
+    pub fn set_lock(&mut self, param: LockParam, value: f32) {
+        let (min, max) = param.range();
+        let value = value.clamp(min, max);
+        match self.locks.iter_mut().find(|(p, _)| *p == param) {
+            Some(lock) => lock.1 = value,
+            None => self.locks.push((param, value)),
+        }
+    }
+}
+
+/// A pad setting a sequencer step can lock to its own value
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum LockParam {
+    /// Semitones from the sample's own pitch
+    Pitch,
+    /// Replaces the pad level
+    Level,
+    /// Replace the pad's slice points; pulling the end in shortens the hit
+    SliceStart,
+    SliceEnd,
+    /// 1 plays backwards, 0 forwards, whatever the pad is set to
+    Reverse,
+}
+
+impl LockParam {
+    pub const ALL: [LockParam; 5] = [
+        LockParam::Pitch, LockParam::Level, LockParam::SliceStart, LockParam::SliceEnd, LockParam::Reverse,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            LockParam::Pitch => "Pitch",
+            LockParam::Level => "Level",
+            LockParam::SliceStart => "Start",
+            LockParam::SliceEnd => "End",
+            LockParam::Reverse => "Rev",
+        }
+    }
+
+    /// Stored form, for persistence
+    pub fn key(&self) -> &'static str {
+        match self {
+            LockParam::Pitch => "pitch",
+            LockParam::Level => "level",
+            LockParam::SliceStart => "slice_start",
+            LockParam::SliceEnd => "slice_end",
+            LockParam::Reverse => "reverse",
+        }
+    }
+
+    pub fn from_key(key: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|p| p.key() == key)
+    }
+
+    pub fn next(&self) -> Self {
+        let idx = Self::ALL.iter().position(|p| p == self).unwrap_or(0);
+        Self::ALL[(idx + 1) % Self::ALL.len()]
+    }
+
+    pub fn range(&self) -> (f32, f32) {
+        match self {
+            LockParam::Pitch => (-24.0, 24.0),
+            _ => (0.0, 1.0),
+        }
+    }
+
+    /// How far one key press moves a lock
+    pub fn increment(&self) -> f32 {
+        match self {
+            LockParam::Pitch | LockParam::Reverse => 1.0,
+            _ => 0.05,
+        }
+    }
+
+    /// Lock value shown in the sequencer
+    pub fn format(&self, value: f32) -> String {
+        match self {
+            LockParam::Pitch => format!("{:+}st", value.round() as i32),
+            LockParam::Reverse => if value >= 0.5 { "on" } else { "off" }.to_string(),
+            _ => format!("{:.0}%", value * 100.0),
         }
     }
 }
 
+/// How one pad hit sounds once a step's locks are applied
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PadHit {
+    pub level: f32,
+    pub slice_start: f32,
+    pub slice_end: f32,
+    pub reverse: bool,
+    /// Playback rate; 1.0 is the sample's own pitch
+    pub rate: f32,
+}
+
+/// An alternate sample a pad cycles through on successive hits
+#[derive(Debug, Clone, PartialEq)]
+pub struct RoundRobinSample {
+    pub buffer_id: BufferId,
+    pub path: String,
+}
+
 #[derive(Debug, Clone)]
 pub struct DrumPad {
     pub buffer_id: Option<BufferId>,
@@ -41,6 +169,71 @@ pub struct DrumPad {
     pub level: f32, // 0.0-1.0, default 0.8
     pub slice_start: f32, // 0.0-1.0, default 0.0
     pub slice_end: f32,   // 0.0-1.0, default 1.0
+    /// Play the slice backwards
+    pub reverse: bool,
+    /// Samples swapped in by velocity; `buffer_id` covers velocities below them
+    pub layers: Vec<VelocityLayer>,
+    /// Blend adjacent velocity layers near their boundaries
+    pub crossfade: bool,
+    /// Alternates to `buffer_id`, taking turns with it hit by hit
+    pub round_robin: Vec<RoundRobinSample>,
+}
+
+impl DrumPad {
+    /// Buffers (with gains) a hit at this velocity should play. `round_robin` is the
+    /// pad's hit counter; it picks among the base sample and its alternates, while
+    /// velocity layers above the base band always play their own sample.
+    pub fn buffers_for_hit(&self, velocity: u8, round_robin: usize) -> Vec<(BufferId, f32)> {
+        let variants: Vec<BufferId> = self.buffer_id.into_iter()
+            .chain(self.round_robin.iter().map(|s| s.buffer_id))
+            .collect();
+        let base = variants.get(round_robin % variants.len().max(1)).copied();
+        layers_for_velocity(base, &self.layers, self.crossfade, velocity)
+    }
+
+    /// The pad's own value for a lockable setting, where a new lock starts from
+    pub fn lock_base(&self, param: LockParam) -> f32 {
+        match param {
+            LockParam::Pitch => 0.0,
+            LockParam::Level => self.level,
+            LockParam::SliceStart => self.slice_start,
+            LockParam::SliceEnd => self.slice_end,
+            LockParam::Reverse => if self.reverse { 1.0 } else { 0.0 },
+        }
+    }
+
+    /// This pad's settings with a step's `locks` laid over them. Anything unlocked
+    /// keeps the pad's value, so a step without locks plays the pad as set. Slice
+    /// locks that would leave the slice empty or backwards fall back to the pad's slice.
+    pub fn hit(&self, locks: &[(LockParam, f32)]) -> PadHit {
+        let mut hit = PadHit {
+            level: self.level,
+            slice_start: self.slice_start,
+            slice_end: self.slice_end,
+            reverse: self.reverse,
+            rate: 1.0,
+        };
+        for &(param, value) in locks {
+            match param {
+                LockParam::Pitch => hit.rate = 2f32.powf(value / 12.0),
+                LockParam::Level => hit.level = value,
+                LockParam::SliceStart => hit.slice_start = value,
+                LockParam::SliceEnd => hit.slice_end = value,
+                LockParam::Reverse => hit.reverse = value >= 0.5,
+            }
+        }
+        if hit.slice_start >= hit.slice_end {
+            hit.slice_start = self.slice_start;
+            hit.slice_end = self.slice_end;
+        }
+        hit
+    }
+
+    /// Play one whole sample, as a bounced loop needs: forwards from start to end at
+    /// full level, with no velocity layers or round-robin alternates left over
+    pub fn load_whole_sample(&mut self, buffer_id: BufferId, path: String, name: String) {
+        *self = Self { buffer_id: Some(buffer_id), path: Some(path), name, level: 1.0, ..Self::default() };
+    }
 }
 
 impl Default for DrumPad {
@@ -52,6 +245,10 @@ impl Default for DrumPad {
             level: 0.8,
             slice_start: 0.0,
             slice_end: 1.0,
+            reverse: false,
+            layers: Vec::new(),
+            crossfade: false,
+            round_robin: Vec::new(),
         }
     }
 }
@@ -60,8 +257,17 @@ impl Default for DrumPad {
 pub struct DrumPattern {
     pub steps: Vec<Vec<DrumStep>>, // [NUM_PADS][length]
     pub length: usize,
+    /// (target pattern, repeats): after playing `repeats` times, switch to the target.
+    /// Pointing at itself just loops.
+    pub follow_action: Option<(usize, u8)>,
+    /// Per pad, the (pulses, rotation) its row was generated from by `euclid`, until
+    /// a step is edited by hand (not saved)
+    pub euclid: Vec<Option<(usize, i32)>>,
 }
 
+/// Most passes a follow action can wait for
+pub const MAX_FOLLOW_REPEATS: u8 = 64;
+
 impl DrumPattern {
     pub fn new(length: usize) -> Self {
         Self {
@@ -69,10 +275,48 @@ impl DrumPattern {
                 .map(|_| (0..length).map(|_| DrumStep::default()).collect())
                 .collect(),
             length,
+            follow_action: None,
+            euclid: vec![None; NUM_PADS],
+        }
+    }
+
+    /// Fill a pad's row with a Euclidean rhythm over the pattern length and remember
+    /// how it was made. Pulses clamp to the length and rotation wraps; step
+    /// velocities are kept.
+    pub fn fill_euclid(&mut self, pad_idx: usize, pulses: usize, rotation: i32) {
+        let length = self.length;
+        let Some(row) = self.steps.get_mut(pad_idx) else { return };
+        let pulses = pulses.min(length);
+        let rotation = rotation.rem_euclid(length.max(1) as i32);
+        for (step, hit) in row.iter_mut().zip(euclid(pulses, length, rotation)) {
+            step.active = hit;
+            step.nudge = 0.0;
+        }
+        self.euclid[pad_idx] = Some((pulses, rotation));
+    }
+
+    /// A pad's row was edited by hand, so it's no longer a generated rhythm
+    pub fn clear_euclid(&mut self, pad_idx: usize) {
+        if let Some(generated) = self.euclid.get_mut(pad_idx) {
+            *generated = None;
         }
     }
 }
 
+/// Euclidean rhythm: `pulses` hits spread as evenly as possible over `steps`, the
+/// first on step one, then shifted later by `rotation` steps (wrapping either way).
+/// More pulses than steps fills every step.
+pub fn euclid(pulses: usize, steps: usize, rotation: i32) -> Vec<bool> {
+    if steps == 0 {
+        return Vec::new();
+    }
+    let pulses = pulses.min(steps);
+    let shift = rotation.rem_euclid(steps as i32) as usize;
+    (0..steps)
+        .map(|i| ((i + steps - shift) % steps) * pulses % steps < pulses)
+        .collect()
+}
+
 #[derive(Debug, Clone)]
 pub struct DrumSequencerState {
     pub pads: Vec<DrumPad>,
@@ -82,8 +326,14 @@ pub struct DrumSequencerState {
     pub current_step: usize,
     pub next_buffer_id: BufferId,
     pub step_accumulator: f32,
-    pub last_played_step: Option<usize>,
+    /// Position (step + fraction) at the last tick while playing, hits between
+    /// it and the current position are due (runtime only)
+    pub play_position: Option<f32>,
     pub chopper: Option<ChopperState>,
+    /// Hits per pad since the pattern (re)started, selecting round-robin samples (runtime only)
+    pub round_robin: Vec<usize>,
+    /// Completed passes of the current pattern, counted toward its follow action (runtime only)
+    pub pattern_passes: u8,
 }
 
 impl DrumSequencerState {
@@ -98,8 +348,10 @@ impl DrumSequencerState {
             current_step: 0,
             next_buffer_id: 10000,
             step_accumulator: 0.0,
-            last_played_step: None,
+            play_position: None,
             chopper: None,
+            round_robin: vec![0; NUM_PADS],
+            pattern_passes: 0,
         }
     }
 
@@ -110,6 +362,76 @@ impl DrumSequencerState {
     pub fn pattern_mut(&mut self) -> &mut DrumPattern {
         &mut self.patterns[self.current_pattern]
     }
+
+    /// Buffers (with gains) for the next hit on a pad, advancing its round-robin
+    pub fn next_hit(&mut self, pad_idx: usize, velocity: u8) -> Vec<(BufferId, f32)> {
+        let Some(pad) = self.pads.get(pad_idx) else {
+            return Vec::new();
+        };
+        let count = &mut self.round_robin[pad_idx];
+        let hit = pad.buffers_for_hit(velocity, *count);
+        *count = count.wrapping_add(1);
+        hit
+    }
+
+    /// Write a live hit into the playing pattern at the nearest step, keeping how far
+    /// off the step it landed as nudge so it plays back as played (not re-triggered
+    /// this pass, since its time has already gone by)
+    pub fn record_hit(&mut self, pad_idx: usize, velocity: u8) {
+        if !self.playing || pad_idx >= self.pads.len() {
+            return;
+        }
+        let pos = self.current_step as f32 + self.step_accumulator;
+        let nearest = pos.round();
+        let pattern = self.pattern_mut();
+        let len = pattern.length.max(1);
+        if let Some(step) = pattern.steps.get_mut(pad_idx).and_then(|s| s.get_mut(nearest as usize % len)) {
+            step.active = true;
+            step.velocity = velocity;
+            step.nudge = pos - nearest;
+            step.groove_velocity = 1.0;
+            pattern.clear_euclid(pad_idx);
+        }
+    }
+
+    /// Switch to a pattern, starting it fresh (round-robin and follow-action count).
+    /// Out-of-range indices are ignored.
+    pub fn select_pattern(&mut self, index: usize) {
+        if index < self.patterns.len() {
+            self.current_pattern = index;
+            self.pattern_passes = 0;
+            self.reset_round_robin();
+        }
+    }
+
+    /// The playhead wrapped to step one: count the pass and run the pattern's follow
+    /// action when it's due. There's no song mode to defer to, so this is the only
+    /// thing that changes patterns during playback.
+    pub fn finish_pass(&mut self) {
+        self.pattern_passes = self.pattern_passes.saturating_add(1);
+        let Some((target, repeats)) = self.pattern().follow_action else {
+            return;
+        };
+        if self.pattern_passes < repeats.max(1) {
+            return;
+        }
+        if target == self.current_pattern || target >= self.patterns.len() {
+            self.pattern_passes = 0;
+            return;
+        }
+        // Keep the last position relative to the new pattern's step one, so the
+        // playback loop doesn't read the wrap as a pass through the new pattern
+        let old_len = self.pattern().length as f32;
+        self.play_position = self.play_position.map(|p| p - old_len);
+        self.select_pattern(target);
+    }
+
+    /// Start every pad's round-robin from its base sample again, so a pattern
+    /// sounds the same each time it's started or switched to. Looping back to
+    /// step one doesn't reset, which would repeat a sample across the seam.
+    pub fn reset_round_robin(&mut self) {
+        self.round_robin.iter_mut().for_each(|c| *c = 0);
+    }
 }
 
 impl Default for DrumSequencerState {
@@ -148,6 +470,70 @@ mod tests {
         assert!(!seq.pattern().steps[0][0].active);
     }
 
+    #[test]
+    fn test_round_robin_cycles_and_resets() {
+        let mut seq = DrumSequencerState::new();
+        // A single sample pad plays the same buffer every hit
+        seq.pads[0].buffer_id = Some(1);
+        assert_eq!(seq.next_hit(0, 100), vec![(1, 1.0)]);
+        assert_eq!(seq.next_hit(0, 100), vec![(1, 1.0)]);
+
+        seq.reset_round_robin();
+        seq.pads[0].round_robin.push(RoundRobinSample { buffer_id: 2, path: "b.wav".into() });
+        seq.pads[0].layers.push(VelocityLayer { buffer_id: 3, path: "hard.wav".into(), vel_range: (100, 127) });
+        let bases: Vec<_> = (0..4).map(|_| seq.next_hit(0, 64)[0].0).collect();
+        assert_eq!(bases, vec![1, 2, 1, 2]);
+        // Velocity layers still take over at their velocities
+        assert_eq!(seq.next_hit(0, 120), vec![(3, 1.0)]);
+        assert_eq!(seq.next_hit(0, 64), vec![(2, 1.0)]);
+
+        seq.reset_round_robin();
+        assert_eq!(seq.next_hit(0, 64), vec![(1, 1.0)]);
+    }
+
+    #[test]
+    fn test_record_hit_at_nearest_step() {
+        let mut seq = DrumSequencerState::new();
+        seq.record_hit(0, 90);
+        assert!(!seq.pattern().steps[0][0].active);
+        seq.playing = true;
+        seq.current_step = 3;
+        seq.step_accumulator = 0.75;
+        seq.record_hit(2, 90);
+        let step = &seq.pattern().steps[2][4];
+        assert!(step.active);
+        assert_eq!(step.velocity, 90);
+        assert!((step.nudge + 0.25).abs() < 1e-6);
+        // Late in the last step wraps to step one
+        seq.current_step = DEFAULT_STEPS - 1;
+        seq.step_accumulator = 0.6;
+        seq.record_hit(1, 100);
+        assert!(seq.pattern().steps[1][0].active);
+    }
+
+    #[test]
+    fn test_follow_action_advances_after_repeats() {
+        let mut seq = DrumSequencerState::new();
+        seq.patterns[0].follow_action = Some((2, 2));
+        seq.patterns[2].follow_action = Some((2, 1));
+        seq.play_position = Some(15.9);
+        seq.finish_pass();
+        assert_eq!(seq.current_pattern, 0);
+        seq.finish_pass();
+        assert_eq!(seq.current_pattern, 2);
+        assert_eq!(seq.pattern_passes, 0);
+        assert!((seq.play_position.unwrap() + 0.1).abs() < 1e-4);
+        // Pointing at itself just loops
+        for _ in 0..5 {
+            seq.finish_pass();
+        }
+        assert_eq!(seq.current_pattern, 2);
+        // No follow action: passes just count
+        seq.select_pattern(1);
+        seq.finish_pass();
+        assert_eq!((seq.current_pattern, seq.pattern_passes), (1, 1));
+    }
+
     #[test]
     fn test_pattern_switching() {
         let mut seq = DrumSequencerState::new();
@@ -157,4 +543,83 @@ mod tests {
         seq.current_pattern = 0;
         assert!(seq.pattern().steps[0][0].active);
     }
+
+    fn hits(row: &[bool]) -> String {
+        row.iter().map(|&hit| if hit { 'x' } else { '.' }).collect()
+    }
+
+    #[test]
+    fn test_euclid_spreads_pulses() {
+        assert_eq!(hits(&euclid(3, 8, 0)), "x..x..x.");
+        assert_eq!(hits(&euclid(4, 16, 0)), "x...x...x...x...");
+        assert_eq!(hits(&euclid(0, 4, 0)), "....");
+        // More pulses than steps fills the row
+        assert_eq!(hits(&euclid(9, 5, 0)), "xxxxx");
+        // Rotation shifts later and wraps either way
+        assert_eq!(hits(&euclid(3, 8, 1)), ".x..x..x");
+        assert_eq!(euclid(3, 8, 9), euclid(3, 8, 1));
+        assert_eq!(euclid(3, 8, -7), euclid(3, 8, 1));
+        assert!(euclid(3, 0, 2).is_empty());
+    }
+
+    #[test]
+    fn test_fill_euclid_uses_pattern_length() {
+        let mut pattern = DrumPattern::new(7);
+        pattern.steps[2][6].velocity = 40;
+        pattern.fill_euclid(2, 3, -1);
+        let row: Vec<bool> = pattern.steps[2].iter().map(|s| s.active).collect();
+        assert_eq!(row, euclid(3, 7, 6));
+        assert_eq!(row.iter().filter(|&&hit| hit).count(), 3);
+        assert_eq!(pattern.euclid[2], Some((3, 6)));
+        assert_eq!(pattern.steps[2][6].velocity, 40);
+
+        pattern.fill_euclid(2, 12, 0);
+        assert!(pattern.steps[2].iter().all(|s| s.active));
+        assert_eq!(pattern.euclid[2], Some((7, 0)));
+
+        pattern.clear_euclid(2);
+        assert_eq!(pattern.euclid[2], None);
+    }
+
+    #[test]
+    fn test_unlocked_step_plays_pad_as_set() {
+        let pad = DrumPad { level: 0.6, slice_start: 0.25, reverse: true, ..Default::default() };
+        let hit = pad.hit(&DrumStep::default().locks);
+        assert_eq!(hit, PadHit { level: 0.6, slice_start: 0.25, slice_end: 1.0, reverse: true, rate: 1.0 });
+    }
+
+    #[test]
+    fn test_locks_override_pad_for_their_step() {
+        let pad = DrumPad { slice_start: 0.5, ..Default::default() };
+        let mut step = DrumStep::default();
+        step.set_lock(LockParam::Pitch, 12.0);
+        step.set_lock(LockParam::Level, 0.3);
+        step.set_lock(LockParam::SliceEnd, 0.75);
+        step.set_lock(LockParam::Pitch, 40.0);
+        assert_eq!(step.locks.len(), 3);
+        assert_eq!(step.lock(LockParam::Pitch), Some(24.0));
+
+        let hit = pad.hit(&step.locks);
+        assert!((hit.rate - 4.0).abs() < 1e-5);
+        assert_eq!((hit.level, hit.slice_start, hit.slice_end), (0.3, 0.5, 0.75));
+
+        // An end before the pad's start would empty the slice: keep the pad's
+        step.set_lock(LockParam::SliceEnd, 0.2);
+        let hit = pad.hit(&step.locks);
+        assert_eq!((hit.slice_start, hit.slice_end), (0.5, 1.0));
+        assert_eq!(pad.lock_base(LockParam::SliceEnd), 1.0);
+    }
+
+    #[test]
+    fn test_load_whole_sample_plays_full_loop() {
+        let mut pad = DrumPad { level: 0.5, slice_start: 0.25, slice_end: 0.5, reverse: true, crossfade: true, ..DrumPad::default() };
+        pad.round_robin.push(RoundRobinSample { buffer_id: 2, path: "b.wav".into() });
+        pad.layers.push(VelocityLayer { buffer_id: 3, path: "hard.wav".into(), vel_range: (100, 127) });
+
+        pad.load_whole_sample(7, "loop.wav".into(), "loop".into());
+        assert_eq!(pad.buffers_for_hit(127, 1), vec![(7, 1.0)]);
+        let hit = pad.hit(&[]);
+        assert_eq!((hit.slice_start, hit.slice_end, hit.reverse, hit.level), (0.0, 1.0, false, 1.0));
+        assert_eq!(pad.path.as_deref(), Some("loop.wav"));
+    }
 }
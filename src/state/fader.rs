@@ -0,0 +1,140 @@
+//! Fader and pan law. Mixer levels are stored as linear amplitude (0.0-1.0, unity at
+//! 1.0) and sent to synths as is, but shown, stepped and dragged in dB. Pan is stored
+//! as -1.0 (left) to 1.0 (right).
+
+/// Bottom of the fader travel above silence; the very bottom is -inf
+pub const FADER_MIN_DB: f32 = -60.0;
+
+/// Level step for a normal fader nudge
+pub const FADER_STEP_DB: f32 = 1.0;
+
+/// Level step for a big fader nudge
+pub const FADER_BIG_STEP_DB: f32 = 6.0;
+
+/// Pan step for a nudge left or right
+pub const PAN_STEP: f32 = 0.05;
+
+/// Decibels for a linear level; -inf for silence
+pub fn level_to_db(level: f32) -> f32 {
+    if level <= 0.0 {
+        f32::NEG_INFINITY
+    } else {
+        20.0 * level.log10()
+    }
+}
+
+/// Linear level for decibels, clamped to the stored range. -inf gives silence.
+pub fn db_to_level(db: f32) -> f32 {
+    if db == f32::NEG_INFINITY {
+        0.0
+    } else {
+        10f32.powf(db / 20.0).clamp(0.0, 1.0)
+    }
+}
+
+/// Fader position (0.0 bottom, 1.0 top) for a level: even in dB from `FADER_MIN_DB`
+/// to unity, with silence at the very bottom
+pub fn level_to_position(level: f32) -> f32 {
+    if level <= 0.0 {
+        return 0.0;
+    }
+    ((level_to_db(level) - FADER_MIN_DB) / -FADER_MIN_DB).clamp(0.0, 1.0)
+}
+
+/// Level for a fader position; the very bottom is silence and the top exactly unity
+pub fn position_to_level(position: f32) -> f32 {
+    if position <= 0.0 {
+        return 0.0;
+    }
+    db_to_level(FADER_MIN_DB * (1.0 - position.min(1.0)))
+}
+
+/// Nudge a level by `db` decibels. Stepping down past `FADER_MIN_DB` reaches silence,
+/// and stepping up from silence starts at `FADER_MIN_DB`.
+pub fn step_level(level: f32, db: f32) -> f32 {
+    if level <= 0.0 {
+        return if db > 0.0 { db_to_level(FADER_MIN_DB) } else { 0.0 };
+    }
+    let stepped = level_to_db(level) + db;
+    if stepped < FADER_MIN_DB {
+        0.0
+    } else {
+        db_to_level(stepped)
+    }
+}
+
+/// Nudge pan by `delta`, on a grid of hundredths. A step that crosses center stops
+/// exactly on it, so center is always reachable.
+pub fn step_pan(pan: f32, delta: f32) -> f32 {
+    let stepped = (((pan + delta) * 100.0).round() / 100.0).clamp(-1.0, 1.0);
+    if pan != 0.0 && stepped != 0.0 && stepped.signum() != pan.signum() {
+        0.0
+    } else {
+        stepped
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unity_and_silence_are_exact() {
+        assert_eq!(db_to_level(0.0), 1.0);
+        assert_eq!(level_to_db(1.0), 0.0);
+        assert_eq!(position_to_level(1.0), 1.0);
+        assert_eq!(level_to_position(1.0), 1.0);
+        assert_eq!(position_to_level(0.0), 0.0);
+        assert_eq!(level_to_position(0.0), 0.0);
+        assert_eq!(db_to_level(f32::NEG_INFINITY), 0.0);
+        assert_eq!(db_to_level(12.0), 1.0);
+    }
+
+    #[test]
+    fn test_steps_are_even_in_db() {
+        let level = step_level(1.0, -FADER_BIG_STEP_DB);
+        assert!((level_to_db(level) + 6.0).abs() < 1e-4);
+        let level = step_level(level, FADER_STEP_DB);
+        assert!((level_to_db(level) + 5.0).abs() < 1e-4);
+        // Can't go past unity
+        assert_eq!(step_level(1.0, FADER_STEP_DB), 1.0);
+    }
+
+    #[test]
+    fn test_stepping_reaches_and_leaves_silence() {
+        let mut level = 1.0;
+        for _ in 0..11 {
+            level = step_level(level, -FADER_BIG_STEP_DB);
+        }
+        assert_eq!(level, 0.0);
+        assert_eq!(step_level(0.0, -FADER_STEP_DB), 0.0);
+        let level = step_level(0.0, FADER_STEP_DB);
+        assert!((level_to_db(level) - FADER_MIN_DB).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_pan_steps_snap_to_center() {
+        assert_eq!(step_pan(0.0, PAN_STEP), 0.05);
+        assert_eq!(step_pan(0.03, -PAN_STEP), 0.0);
+        assert_eq!(step_pan(-0.03, PAN_STEP), 0.0);
+        assert_eq!(step_pan(0.0, -PAN_STEP), -0.05);
+        assert_eq!(step_pan(0.98, PAN_STEP), 1.0);
+
+        // Accumulated steps come back to exactly center
+        let mut pan = 0.0;
+        for _ in 0..7 {
+            pan = step_pan(pan, PAN_STEP);
+        }
+        for _ in 0..7 {
+            pan = step_pan(pan, -PAN_STEP);
+        }
+        assert_eq!(pan, 0.0);
+    }
+
+    #[test]
+    fn test_position_round_trip() {
+        for position in [0.1, 0.25, 0.5, 0.9] {
+            assert!((level_to_position(position_to_level(position)) - position).abs() < 1e-4);
+        }
+    }
+}
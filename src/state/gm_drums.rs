@@ -0,0 +1,131 @@
+//! General MIDI percussion map: which drum-machine pad a GM drum note belongs on.
+//! GM puts percussion on channel 10 (9 counted from 0), one sound per note from 35
+//! to 81. Twelve pads can't hold 47 sounds, so related sounds share a pad: every
+//! hi-hat that isn't open goes to the closed hat, all toms split low/mid/high, and
+//! the hand drums and small percussion get the last two pads. Notes outside the GM
+//! set land on the percussion pad rather than on the kick or snare.
+//!
+//! The MIDI file importer routes channel-10 notes through this table, with every
+//! other channel kept as pitched notes.
+
+use super::drum_sequencer::NUM_PADS;
+
+/// MIDI channel GM reserves for percussion, counted from 0
+pub const GM_DRUM_CHANNEL: u8 = 9;
+
+pub const PAD_KICK: usize = 0;
+pub const PAD_SNARE: usize = 1;
+pub const PAD_CLAP: usize = 2;
+pub const PAD_CLOSED_HAT: usize = 3;
+pub const PAD_OPEN_HAT: usize = 4;
+pub const PAD_LOW_TOM: usize = 5;
+pub const PAD_MID_TOM: usize = 6;
+pub const PAD_HIGH_TOM: usize = 7;
+pub const PAD_CRASH: usize = 8;
+pub const PAD_RIDE: usize = 9;
+/// Shakers, bells, blocks and anything outside the GM set
+pub const PAD_PERCUSSION: usize = 10;
+pub const PAD_HAND_DRUMS: usize = 11;
+
+/// Names for the pads above, for naming a kit's pads on import
+pub const PAD_NAMES: [&str; NUM_PADS] = [
+    "Kick", "Snare", "Clap", "Closed Hat", "Open Hat", "Low Tom",
+    "Mid Tom", "High Tom", "Crash", "Ride", "Percussion", "Hand Drums",
+];
+
+/// GM percussion notes 35-81: (note, sound, pad)
+const GM_DRUMS: [(u8, &str, usize); 47] = [
+    (35, "Acoustic Bass Drum", PAD_KICK),
+    (36, "Bass Drum 1", PAD_KICK),
+    (37, "Side Stick", PAD_SNARE),
+    (38, "Acoustic Snare", PAD_SNARE),
+    (39, "Hand Clap", PAD_CLAP),
+    (40, "Electric Snare", PAD_SNARE),
+    (41, "Low Floor Tom", PAD_LOW_TOM),
+    (42, "Closed Hi-Hat", PAD_CLOSED_HAT),
+    (43, "High Floor Tom", PAD_LOW_TOM),
+    (44, "Pedal Hi-Hat", PAD_CLOSED_HAT),
+    (45, "Low Tom", PAD_MID_TOM),
+    (46, "Open Hi-Hat", PAD_OPEN_HAT),
+    (47, "Low-Mid Tom", PAD_MID_TOM),
+    (48, "Hi-Mid Tom", PAD_HIGH_TOM),
+    (49, "Crash Cymbal 1", PAD_CRASH),
+    (50, "High Tom", PAD_HIGH_TOM),
+    (51, "Ride Cymbal 1", PAD_RIDE),
+    (52, "Chinese Cymbal", PAD_CRASH),
+    (53, "Ride Bell", PAD_RIDE),
+    (54, "Tambourine", PAD_PERCUSSION),
+    (55, "Splash Cymbal", PAD_CRASH),
+    (56, "Cowbell", PAD_PERCUSSION),
+    (57, "Crash Cymbal 2", PAD_CRASH),
+    (58, "Vibraslap", PAD_PERCUSSION),
+    (59, "Ride Cymbal 2", PAD_RIDE),
+    (60, "Hi Bongo", PAD_HAND_DRUMS),
+    (61, "Low Bongo", PAD_HAND_DRUMS),
+    (62, "Mute Hi Conga", PAD_HAND_DRUMS),
+    (63, "Open Hi Conga", PAD_HAND_DRUMS),
+    (64, "Low Conga", PAD_HAND_DRUMS),
+    (65, "High Timbale", PAD_HAND_DRUMS),
+    (66, "Low Timbale", PAD_HAND_DRUMS),
+    (67, "High Agogo", PAD_PERCUSSION),
+    (68, "Low Agogo", PAD_PERCUSSION),
+    (69, "Cabasa", PAD_PERCUSSION),
+    (70, "Maracas", PAD_PERCUSSION),
+    (71, "Short Whistle", PAD_PERCUSSION),
+    (72, "Long Whistle", PAD_PERCUSSION),
+    (73, "Short Guiro", PAD_PERCUSSION),
+    (74, "Long Guiro", PAD_PERCUSSION),
+    (75, "Claves", PAD_PERCUSSION),
+    (76, "Hi Wood Block", PAD_PERCUSSION),
+    (77, "Low Wood Block", PAD_PERCUSSION),
+    (78, "Mute Cuica", PAD_HAND_DRUMS),
+    (79, "Open Cuica", PAD_HAND_DRUMS),
+    (80, "Mute Triangle", PAD_PERCUSSION),
+    (81, "Open Triangle", PAD_PERCUSSION),
+];
+
+/// Whether notes on `channel` are GM drums rather than pitched notes
+pub fn is_drum_channel(channel: u8) -> bool {
+    channel == GM_DRUM_CHANNEL
+}
+
+/// GM name of a percussion note; None outside 35-81
+pub fn gm_drum_name(note: u8) -> Option<&'static str> {
+    GM_DRUMS.iter().find(|(n, ..)| *n == note).map(|(_, name, _)| *name)
+}
+
+/// Pad a GM percussion note plays on; notes outside the GM set go to the
+/// percussion pad
+pub fn gm_drum_pad(note: u8) -> usize {
+    GM_DRUMS.iter().find(|(n, ..)| *n == note).map_or(PAD_PERCUSSION, |(.., pad)| *pad)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_gm_notes_map_to_their_pads() {
+        assert!(GM_DRUMS.iter().enumerate().all(|(i, (note, ..))| *note as usize == 35 + i));
+        assert!(GM_DRUMS.iter().all(|(.., pad)| *pad < NUM_PADS));
+        assert_eq!(gm_drum_pad(36), PAD_KICK);
+        assert_eq!(gm_drum_pad(38), PAD_SNARE);
+        assert_eq!(gm_drum_pad(42), PAD_CLOSED_HAT);
+        assert_eq!(gm_drum_pad(44), PAD_CLOSED_HAT);
+        assert_eq!(gm_drum_pad(46), PAD_OPEN_HAT);
+        assert_eq!(gm_drum_pad(49), PAD_CRASH);
+        assert_eq!(gm_drum_pad(51), PAD_RIDE);
+        assert_eq!(PAD_NAMES[gm_drum_pad(63)], "Hand Drums");
+        assert_eq!(gm_drum_name(39), Some("Hand Clap"));
+
+        // Outside the GM set, including the GM2 extras at either end
+        for note in [0, 27, 34, 82, 87, 127] {
+            assert_eq!(gm_drum_pad(note), PAD_PERCUSSION, "{}", note);
+            assert_eq!(gm_drum_name(note), None);
+        }
+
+        assert!(is_drum_channel(9));
+        assert!(!is_drum_channel(0));
+        assert!(!is_drum_channel(10));
+    }
+}
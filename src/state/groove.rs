@@ -0,0 +1,270 @@
+use super::drum_sequencer::DrumPattern;
+use super::piano_roll::Note;
+
+/// Furthest a groove may push a hit off its step, as a fraction of a step
+pub const MAX_TIMING: f32 = 0.5;
+
+/// Velocity a groove's velocity factors are measured against when extracting
+pub const REFERENCE_VELOCITY: u8 = 100;
+
+/// Default length of an extracted groove (one bar of 16ths)
+pub const DEFAULT_GROOVE_STEPS: usize = 16;
+
+/// Feel of one step: how far off the grid it lands and how hard it's hit
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GrooveStep {
+    /// Offset from the step, as a fraction of a step (-0.5..=0.5, positive is late)
+    pub timing: f32,
+    /// Velocity multiplier
+    pub velocity: f32,
+}
+
+impl Default for GrooveStep {
+    fn default() -> Self {
+        Self { timing: 0.0, velocity: 1.0 }
+    }
+}
+
+/// Per-step timing and velocity offsets, tiled over patterns longer than the template
+#[derive(Debug, Clone, PartialEq)]
+pub struct GrooveTemplate {
+    pub name: String,
+    pub steps: Vec<GrooveStep>,
+}
+
+impl GrooveTemplate {
+    pub fn new(name: impl Into<String>, steps: Vec<GrooveStep>) -> Self {
+        Self { name: name.into(), steps }
+    }
+
+    /// MPC-style swing: every second 16th is delayed, 50% being straight and
+    /// 66% roughly a triplet feel
+    pub fn swing(percent: u8) -> Self {
+        let late = ((percent as f32 / 50.0) - 1.0).clamp(0.0, MAX_TIMING);
+        Self::new(
+            format!("Swing {}", percent),
+            vec![GrooveStep::default(), GrooveStep { timing: late, velocity: 1.0 }],
+        )
+    }
+
+    /// Built-in grooves a new session starts with
+    pub fn presets() -> Vec<Self> {
+        [54, 58, 62, 66].into_iter().map(Self::swing).collect()
+    }
+
+    /// Groove for a step index, wrapping so a short template tiles a long pattern
+    pub fn step(&self, index: usize) -> GrooveStep {
+        if self.steps.is_empty() {
+            return GrooveStep::default();
+        }
+        self.steps[index % self.steps.len()]
+    }
+
+    /// Set each hit's nudge and velocity scale from the groove (scaled by `amount`).
+    /// Both replace what a previous groove set, so re-applying doesn't drift further
+    /// off grid or compound the accents.
+    pub fn apply_to_pattern(&self, pattern: &mut DrumPattern, amount: f32) {
+        for pad_steps in &mut pattern.steps {
+            for (i, step) in pad_steps.iter_mut().take(pattern.length).enumerate() {
+                if !step.active {
+                    continue;
+                }
+                let groove = self.step(i);
+                step.nudge = (groove.timing * amount).clamp(-MAX_TIMING, MAX_TIMING);
+                step.groove_velocity = 1.0 + (groove.velocity - 1.0) * amount;
+            }
+        }
+    }
+
+    /// Average the nudges and velocities of a pattern's active steps into a template
+    /// `steps` long; steps with no hits are left neutral.
+    pub fn extract_from_pattern(name: impl Into<String>, pattern: &DrumPattern, steps: usize) -> Self {
+        let mut acc = Accumulator::new(steps);
+        for pad_steps in &pattern.steps {
+            for (i, step) in pad_steps.iter().take(pattern.length).enumerate() {
+                if step.active {
+                    acc.add(i, step.nudge, step.played_velocity());
+                }
+            }
+        }
+        Self::new(name, acc.finish())
+    }
+
+    /// Move notes starting in `range` onto the grid plus the groove's offset for their
+    /// step, and set their velocity scale. `ticks_per_step` is the groove's grid size.
+    /// Both replace what a previous groove set, as for patterns.
+    pub fn apply_to_notes<'a>(
+        &self,
+        notes: impl IntoIterator<Item = &'a mut Note>,
+        range: std::ops::Range<u32>,
+        ticks_per_step: u32,
+        amount: f32,
+    ) {
+        let tps = ticks_per_step.max(1) as f32;
+        for note in notes {
+            if !range.contains(&note.tick) {
+                continue;
+            }
+            let grid = (note.tick as f32 / tps).round();
+            let groove = self.step(grid as usize);
+            let offset = (groove.timing * amount).clamp(-MAX_TIMING, MAX_TIMING) * tps;
+            note.tick = (grid * tps + offset).round().max(0.0) as u32;
+            note.groove_velocity = 1.0 + (groove.velocity - 1.0) * amount;
+        }
+    }
+
+    /// Measure how far notes starting in `range` sit off the grid, and how hard they're hit
+    pub fn extract_from_notes<'a>(
+        name: impl Into<String>,
+        notes: impl IntoIterator<Item = &'a Note>,
+        range: std::ops::Range<u32>,
+        ticks_per_step: u32,
+        steps: usize,
+    ) -> Self {
+        let tps = ticks_per_step.max(1) as f32;
+        let mut acc = Accumulator::new(steps);
+        for note in notes {
+            if !range.contains(&note.tick) {
+                continue;
+            }
+            let grid = (note.tick as f32 / tps).round();
+            acc.add(grid as usize, note.tick as f32 / tps - grid, note.played_velocity());
+        }
+        Self::new(name, acc.finish())
+    }
+}
+
+/// Running per-step sums for extraction
+struct Accumulator {
+    sums: Vec<(f32, f32, u32)>,
+}
+
+impl Accumulator {
+    fn new(steps: usize) -> Self {
+        Self { sums: vec![(0.0, 0.0, 0); steps.max(1)] }
+    }
+
+    fn add(&mut self, index: usize, timing: f32, velocity: u8) {
+        let len = self.sums.len();
+        let sum = &mut self.sums[index % len];
+        sum.0 += timing;
+        sum.1 += velocity as f32 / REFERENCE_VELOCITY as f32;
+        sum.2 += 1;
+    }
+
+    fn finish(self) -> Vec<GrooveStep> {
+        self.sums
+            .into_iter()
+            .map(|(timing, velocity, count)| match count {
+                0 => GrooveStep::default(),
+                n => GrooveStep {
+                    timing: (timing / n as f32).clamp(-MAX_TIMING, MAX_TIMING),
+                    velocity: velocity / n as f32,
+                },
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_groove() -> GrooveTemplate {
+        let steps = (0..16)
+            .map(|i| GrooveStep {
+                timing: if i % 2 == 1 { 0.2 } else { -0.05 * (i % 4) as f32 },
+                velocity: if i % 4 == 0 { 1.2 } else { 0.8 },
+            })
+            .collect();
+        GrooveTemplate::new("test", steps)
+    }
+
+    #[test]
+    fn test_groove_tiles_over_longer_pattern() {
+        let groove = sample_groove();
+        let mut pattern = DrumPattern::new(32);
+        pattern.steps[0].iter_mut().for_each(|s| s.active = true);
+        groove.apply_to_pattern(&mut pattern, 1.0);
+        assert_eq!(pattern.steps[1][17].nudge, 0.0);
+        for i in 0..32 {
+            let step = &pattern.steps[0][i];
+            assert_eq!(step.nudge, groove.steps[i % 16].timing);
+            assert_eq!(step.played_velocity(), (100.0 * groove.steps[i % 16].velocity).round() as u8);
+        }
+    }
+
+    #[test]
+    fn test_extract_inverts_apply() {
+        let groove = sample_groove();
+        let mut pattern = DrumPattern::new(32);
+        for pad in pattern.steps.iter_mut().take(2) {
+            pad.iter_mut().for_each(|s| s.active = true);
+        }
+        groove.apply_to_pattern(&mut pattern, 1.0);
+        let extracted = GrooveTemplate::extract_from_pattern("x", &pattern, 16);
+        for (a, b) in groove.steps.iter().zip(&extracted.steps) {
+            assert!((a.timing - b.timing).abs() < 1e-5);
+            assert!((a.velocity - b.velocity).abs() < 0.01);
+        }
+
+        // Same for piano-roll notes on a 16th grid
+        let tps = 120;
+        let mut notes: Vec<Note> = (0..32)
+            .map(|i| Note::new(i * tps, tps, 60, 100))
+            .collect();
+        groove.apply_to_notes(notes.iter_mut(), 0..32 * tps, tps, 1.0);
+        let extracted = GrooveTemplate::extract_from_notes("x", notes.iter(), 0..32 * tps, tps, 16);
+        for (a, b) in groove.steps.iter().zip(&extracted.steps) {
+            assert!((a.timing - b.timing).abs() < 1.0 / tps as f32);
+            assert!((a.velocity - b.velocity).abs() < 0.01);
+        }
+    }
+
+    #[test]
+    fn test_apply_to_notes_respects_range() {
+        let groove = GrooveTemplate::swing(66);
+        let mut notes = [
+            Note::new(120, 60, 60, 90),
+            Note::new(600, 60, 60, 90),
+        ];
+        groove.apply_to_notes(notes.iter_mut(), 0..480, 120, 1.0);
+        assert_eq!(notes[0].tick, 120 + 38);
+        assert_eq!(notes[1].tick, 600);
+    }
+
+    #[test]
+    fn test_reapplying_a_groove_does_not_compound() {
+        let groove = sample_groove();
+        let mut pattern = DrumPattern::new(16);
+        pattern.steps[0].iter_mut().for_each(|s| s.active = true);
+        groove.apply_to_pattern(&mut pattern, 1.0);
+        let once: Vec<_> = pattern.steps[0].iter().map(|s| (s.played_velocity(), s.nudge)).collect();
+        groove.apply_to_pattern(&mut pattern, 1.0);
+        let twice: Vec<_> = pattern.steps[0].iter().map(|s| (s.played_velocity(), s.nudge)).collect();
+        assert_eq!(once, twice);
+        assert!(pattern.steps[0].iter().all(|s| s.velocity == 100));
+
+        // A neutral groove takes the accents back off
+        GrooveTemplate::new("flat", vec![GrooveStep::default(); 16]).apply_to_pattern(&mut pattern, 1.0);
+        assert!(pattern.steps[0].iter().all(|s| s.played_velocity() == 100));
+    }
+
+    #[test]
+    fn test_reapplying_a_groove_to_notes_does_not_compound() {
+        let groove = sample_groove();
+        let tps = 120;
+        let mut notes: Vec<Note> = (0..16).map(|i| Note::new(i * tps, tps, 60, 100)).collect();
+        groove.apply_to_notes(notes.iter_mut(), 0..16 * tps, tps, 1.0);
+        let once: Vec<_> = notes.iter().map(|n| (n.tick, n.played_velocity())).collect();
+        groove.apply_to_notes(notes.iter_mut(), 0..16 * tps, tps, 1.0);
+        let twice: Vec<_> = notes.iter().map(|n| (n.tick, n.played_velocity())).collect();
+        assert_eq!(once, twice);
+        assert_eq!(once[0].1, 120);
+        assert!(notes.iter().all(|n| n.velocity == 100));
+
+        // A neutral groove takes the accents back off
+        GrooveTemplate::new("flat", vec![GrooveStep::default(); 16]).apply_to_notes(notes.iter_mut(), 0..16 * tps, tps, 1.0);
+        assert!(notes.iter().all(|n| n.played_velocity() == 100));
+    }
+}
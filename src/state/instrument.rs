@@ -1,11 +1,16 @@
+use serde::{Deserialize, Serialize};
+
 use super::custom_synthdef::{CustomSynthDefId, CustomSynthDefRegistry};
 use super::drum_sequencer::DrumSequencerState;
+use super::modulation::{ModRouteSource, ModRouting};
+use super::music::NoteDivision;
 use super::param::{Param, ParamValue};
+use super::rng::Rng;
 use super::sampler::SamplerConfig;
 
 pub type InstrumentId = u32;
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum SourceType {
     Saw,
     Sin,
@@ -110,6 +115,12 @@ impl SourceType {
                     min: 0.0,
                     max: 7.0,
                 },
+                Param {
+                    name: "input".to_string(),
+                    value: ParamValue::Int(AudioInputMode::Left.index()),
+                    min: 0.0,
+                    max: (AudioInputMode::ALL.len() - 1) as f32,
+                },
                 Param {
                     name: "test_tone".to_string(),
                     value: ParamValue::Float(0.0),
@@ -214,6 +225,11 @@ impl SourceType {
         matches!(self, SourceType::BusIn)
     }
 
+    /// Built-in oscillators, the sources that can stack unison copies
+    pub fn is_oscillator(&self) -> bool {
+        matches!(self, SourceType::Saw | SourceType::Sin | SourceType::Sqr | SourceType::Tri)
+    }
+
     #[allow(dead_code)]
     pub fn is_custom(&self) -> bool {
         matches!(self, SourceType::Custom(_))
@@ -243,7 +259,7 @@ impl SourceType {
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum FilterType {
     Lpf,
     Hpf,
@@ -267,13 +283,12 @@ impl FilterType {
         }
     }
 
-    #[allow(dead_code)]
     pub fn all() -> Vec<FilterType> {
         vec![FilterType::Lpf, FilterType::Hpf, FilterType::Bpf]
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum EffectType {
     Delay,
     Reverb,
@@ -319,6 +334,7 @@ impl EffectType {
                 Param { name: "rate".to_string(), value: ParamValue::Float(4.0), min: 0.1, max: 32.0 },
                 Param { name: "depth".to_string(), value: ParamValue::Float(1.0), min: 0.0, max: 1.0 },
                 Param { name: "shape".to_string(), value: ParamValue::Int(1), min: 0.0, max: 2.0 }, // 0=sine, 1=square, 2=saw
+                Param { name: "mix".to_string(), value: ParamValue::Float(1.0), min: 0.0, max: 1.0 },
             ],
             EffectType::TapeComp => vec![
                 Param { name: "drive".to_string(), value: ParamValue::Float(1.5), min: 1.0, max: 8.0 },
@@ -338,6 +354,17 @@ impl EffectType {
         }
     }
 
+    /// The type offered after this one when adding effects
+    pub fn next(self) -> EffectType {
+        match self {
+            EffectType::Delay => EffectType::Reverb,
+            EffectType::Reverb => EffectType::Gate,
+            EffectType::Gate => EffectType::TapeComp,
+            EffectType::TapeComp => EffectType::SidechainComp,
+            EffectType::SidechainComp => EffectType::Delay,
+        }
+    }
+
     #[allow(dead_code)]
     pub fn all() -> Vec<EffectType> {
         vec![EffectType::Delay, EffectType::Reverb, EffectType::Gate, EffectType::TapeComp, EffectType::SidechainComp]
@@ -377,6 +404,10 @@ pub struct MixerBus {
     pub pan: f32,
     pub mute: bool,
     pub solo: bool,
+    /// First hardware output channel of the stereo pair this bus plays to (0 = out 1/2)
+    pub output_channel: u32,
+    /// Insert chain on the bus, between the sends feeding it and its fader
+    pub effects: Vec<EffectSlot>,
 }
 
 impl MixerBus {
@@ -388,6 +419,8 @@ impl MixerBus {
             pan: 0.0,
             mute: false,
             solo: false,
+            output_channel: 0,
+            effects: Vec::new(),
         }
     }
 }
@@ -463,23 +496,23 @@ impl LfoShape {
     }
 }
 
-// TODO: Currently only FilterCutoff is wired up in the audio engine.
-// To implement each target, add a `*_mod_in` param to the relevant SynthDef,
-// then wire it up in AudioEngine::rebuild_instrument_routing.
+// Mod matrix destinations. Each wired target has a `*_mod_in` param on the
+// SynthDef that owns it: per-voice targets are routed in AudioEngine::spawn_voice,
+// instrument-level ones in AudioEngine::rebuild_instrument_routing (see `mod_input`).
 //
-// Implementation notes per target:
 //   FilterCutoff   - DONE (filter SynthDefs have cutoff_mod_in)
-//   FilterResonance- Add res_mod_in to filter SynthDefs
-//   Amplitude      - Add amp_mod_in to oscillator SynthDefs, multiply with amp
-//   Pitch          - Add freq_mod_in to oscillators, multiply freq by 2^(mod) for semitones
-//   Pan            - Add pan_mod_in to ilex_output, add to pan and clip
+//   FilterResonance- DONE (filter SynthDefs have res_mod_in)
+//   Amplitude      - DONE per voice (oscillators have amp_mod_in)
+//   Pitch          - DONE per voice (oscillators have pitch_mod_in)
+//   Pan            - DONE (ilex_output has pan_mod_in)
+//   DelayTime      - DONE (ilex_delay has time_mod_in)
+//   DelayFeedback  - DONE (ilex_delay has feedback_mod_in)
+//   ReverbMix      - DONE (ilex_reverb has mix_mod_in)
+//   GateRate       - DONE (ilex_gate has rate_mod_in)
+//   SendLevel      - DONE (ilex_send has level_mod_in)
+// TODO:
 //   PulseWidth     - Add width_mod_in to ilex_sqr only, add to pulse width
 //   SampleRate     - Add rate_mod_in to ilex_sampler, multiply with rate
-//   DelayTime      - Add time_mod_in to ilex_delay
-//   DelayFeedback  - Add feedback_mod_in to ilex_delay
-//   ReverbMix      - Add mix_mod_in to ilex_reverb
-//   GateRate       - Add rate_mod_in to ilex_gate (meta-modulation!)
-//   SendLevel      - Add level_mod_in to ilex_send
 //   Detune         - Add detune_mod_in to oscillators, slight pitch offset
 //   Attack         - Add attack_mod_in to oscillators (unusual but possible)
 //   Release        - Add release_mod_in to oscillators
@@ -571,7 +604,11 @@ pub struct LfoConfig {
     pub rate: f32,
     pub depth: f32,
     pub shape: LfoShape,
-    pub target: LfoTarget,
+    /// Follow the session tempo: one cycle lasts `division` instead of running at `rate` Hz
+    pub sync: bool,
+    pub division: NoteDivision,
+    /// Restart the cycle at each note-on (per voice when the instrument is polyphonic)
+    pub retrigger: bool,
 }
 
 impl Default for LfoConfig {
@@ -581,16 +618,55 @@ impl Default for LfoConfig {
             rate: 2.0,
             depth: 0.5,
             shape: LfoShape::Sine,
-            target: LfoTarget::FilterCutoff,
+            sync: false,
+            division: NoteDivision::Quarter,
+            retrigger: false,
         }
     }
 }
 
+impl LfoConfig {
+    /// Rate sent to the LFO synth: the division's frequency at `bpm` when synced,
+    /// otherwise the free-running `rate`
+    pub fn rate_hz(&self, bpm: f32) -> f32 {
+        if self.sync {
+            1.0 / self.division.seconds(bpm)
+        } else {
+            self.rate
+        }
+    }
+
+    /// Switch tempo sync; turning it on snaps to the division nearest the current rate
+    pub fn set_sync(&mut self, sync: bool, bpm: f32) {
+        if sync && !self.sync {
+            self.division = NoteDivision::nearest(1.0 / self.rate.max(0.01), bpm);
+        }
+        self.sync = sync;
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct FilterConfig {
     pub filter_type: FilterType,
     pub cutoff: ModulatedParam,
     pub resonance: ModulatedParam,
+    /// How far the cutoff follows the played note: 1.0 moves it an octave per octave
+    pub keytrack: f32,
+}
+
+/// Lowest and highest key-tracking amount
+pub const KEYTRACK_RANGE: (f32, f32) = (-1.0, 2.0);
+
+/// Note key-tracking is measured from; it plays the cutoff unchanged (middle C)
+pub const KEYTRACK_CENTER_NOTE: u8 = 60;
+
+/// Preferred starting values for effects and filters added to an instrument, by type
+/// and param name (set in the user config). Applied only when a slot is added, so
+/// loaded projects and presets keep what they saved.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ParamDefaults {
+    pub effects: Vec<(EffectType, String, f32)>,
+    pub filters: Vec<(FilterType, String, f32)>,
 }
 
 impl FilterConfig {
@@ -599,7 +675,29 @@ impl FilterConfig {
             filter_type,
             cutoff: ModulatedParam { value: 1000.0, min: 20.0, max: 20000.0, mod_source: None },
             resonance: ModulatedParam { value: 0.5, min: 0.0, max: 1.0, mod_source: None },
+            keytrack: 0.0,
+        }
+    }
+
+    /// A new filter starting from the configured defaults for its type, clamped to
+    /// each param's range; unknown param names are ignored
+    pub fn with_defaults(filter_type: FilterType, defaults: &ParamDefaults) -> Self {
+        let mut config = Self::new(filter_type);
+        for (_, name, value) in defaults.filters.iter().filter(|(t, ..)| *t == filter_type) {
+            match name.as_str() {
+                "cutoff" => config.cutoff.value = value.clamp(config.cutoff.min, config.cutoff.max),
+                "resonance" => config.resonance.value = value.clamp(config.resonance.min, config.resonance.max),
+                "keytrack" => config.keytrack = value.clamp(KEYTRACK_RANGE.0, KEYTRACK_RANGE.1),
+                _ => {}
+            }
         }
+        config
+    }
+
+    /// Factor key-tracking scales the cutoff by for `pitch`; 1.0 when tracking is off
+    pub fn keytrack_ratio(&self, pitch: u8) -> f32 {
+        let octaves = (pitch as f32 - KEYTRACK_CENTER_NOTE as f32) / 12.0;
+        2f32.powf(octaves * self.keytrack)
     }
 }
 
@@ -608,6 +706,11 @@ pub struct EffectSlot {
     pub effect_type: EffectType,
     pub params: Vec<Param>,
     pub enabled: bool,
+    /// Follow the session tempo: the delay time is `division` instead of the time param
+    pub sync: bool,
+    pub division: NoteDivision,
+    /// Crossfaded to the dry signal for A/B listening; not saved
+    pub bypassed: bool,
 }
 
 impl EffectSlot {
@@ -616,23 +719,260 @@ impl EffectSlot {
             params: effect_type.default_params(),
             effect_type,
             enabled: true,
+            sync: false,
+            division: NoteDivision::Quarter,
+            bypassed: false,
         }
     }
+
+    /// A new effect starting from the configured defaults for its type, clamped to
+    /// each param's range; unknown param names are ignored
+    pub fn with_defaults(effect_type: EffectType, defaults: &ParamDefaults) -> Self {
+        let mut slot = Self::new(effect_type);
+        for (_, name, value) in defaults.effects.iter().filter(|(t, ..)| *t == effect_type) {
+            if let Some(param) = slot.params.iter_mut().find(|p| &p.name == name) {
+                param.set_f32(*value);
+            }
+        }
+        slot
+    }
+
+    /// A new effect for a bus, fully wet whatever the defaults say. The dry signal
+    /// already reaches master through each instrument's own output, so on a bus fed
+    /// by sends the send levels alone set how much of the effect is heard.
+    pub fn for_bus(effect_type: EffectType, defaults: &ParamDefaults) -> Self {
+        let mut slot = Self::with_defaults(effect_type, defaults);
+        if let Some(p) = slot.params.iter_mut().find(|p| p.name == "mix") {
+            p.set_f32(1.0);
+        }
+        slot
+    }
+
+    /// Dry/wet balance, 1.0 fully wet
+    pub fn mix(&self) -> f32 {
+        self.params.iter().find(|p| p.name == "mix").map_or(1.0, |p| p.value_f32())
+    }
+
+    /// Nudge the dry/wet balance, clamped to 0-1
+    pub fn adjust_mix(&mut self, delta: f32) {
+        if let Some(p) = self.params.iter_mut().find(|p| p.name == "mix") {
+            p.value = ParamValue::Float((p.value_f32() + delta).clamp(p.min, p.max));
+        }
+    }
+
+    pub fn supports_sync(&self) -> bool {
+        self.effect_type == EffectType::Delay
+    }
+
+    /// Delay time in seconds at `bpm` when synced, clamped to the time param's range;
+    /// None in free mode, where the time param is used as-is
+    pub fn synced_time(&self, bpm: f32) -> Option<f32> {
+        if !self.sync || !self.supports_sync() {
+            return None;
+        }
+        let range = self
+            .params
+            .iter()
+            .find(|p| p.name == "time")
+            .map(|p| (p.min, p.max))
+            .unwrap_or((0.0, 2.0));
+        Some(self.division.seconds(bpm).clamp(range.0, range.1))
+    }
+
+    /// Switch tempo sync; turning it on snaps the division to the one closest to the
+    /// current free time so the echo doesn't jump
+    pub fn set_sync(&mut self, sync: bool, bpm: f32) {
+        if sync && !self.sync {
+            let time = self
+                .params
+                .iter()
+                .find(|p| p.name == "time")
+                .map(|p| p.value_f32())
+                .unwrap_or(0.3);
+            self.division = NoteDivision::nearest(time, bpm);
+        }
+        self.sync = sync && self.supports_sync();
+    }
 }
 
 pub const MAX_BUSES: usize = 8;
 
+/// Source params that pick routing, a test signal or the overall level rather than
+/// the sound itself; randomize/mutate leave these alone (a random "amp" can silence the patch)
+const NON_PATCH_PARAMS: &[&str] = &["amp", "bus", "channel", "input", "test_tone"];
+
+/// Which hardware inputs an Audio In source reads, relative to its `channel` param
+/// (the left input of a pair). Stored as the `input` source param.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum AudioInputMode {
+    /// `channel` alone, on both sides
+    #[default]
+    Left,
+    /// `channel + 1` alone, on both sides
+    Right,
+    /// `channel` and `channel + 1` kept as left and right
+    Stereo,
+    /// `channel` and `channel + 1` mixed down to mono
+    Sum,
+}
+
+impl AudioInputMode {
+    pub const ALL: [AudioInputMode; 4] =
+        [AudioInputMode::Left, AudioInputMode::Right, AudioInputMode::Stereo, AudioInputMode::Sum];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            AudioInputMode::Left => "Left",
+            AudioInputMode::Right => "Right",
+            AudioInputMode::Stereo => "Stereo",
+            AudioInputMode::Sum => "Sum L+R",
+        }
+    }
+
+    /// Value of the `input` param (what `ilex_audio_in` switches on)
+    pub fn index(&self) -> i32 {
+        Self::ALL.iter().position(|m| m == self).unwrap_or(0) as i32
+    }
+
+    /// Mode for an `input` param value; out-of-range values are clamped
+    pub fn from_index(index: i32) -> Self {
+        Self::ALL[index.clamp(0, Self::ALL.len() as i32 - 1) as usize]
+    }
+}
+
+/// User-assigned color tag, shown in the mixer and piano roll track headers
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColorTag {
+    Red,
+    Orange,
+    Yellow,
+    Green,
+    Cyan,
+    Blue,
+    Purple,
+    Pink,
+}
+
+impl ColorTag {
+    pub const ALL: [ColorTag; 8] = [
+        ColorTag::Red,
+        ColorTag::Orange,
+        ColorTag::Yellow,
+        ColorTag::Green,
+        ColorTag::Cyan,
+        ColorTag::Blue,
+        ColorTag::Purple,
+        ColorTag::Pink,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ColorTag::Red => "red",
+            ColorTag::Orange => "orange",
+            ColorTag::Yellow => "yellow",
+            ColorTag::Green => "green",
+            ColorTag::Cyan => "cyan",
+            ColorTag::Blue => "blue",
+            ColorTag::Purple => "purple",
+            ColorTag::Pink => "pink",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.iter().copied().find(|t| t.name() == name)
+    }
+
+    /// Next tag in the cycle; untagged follows the last color
+    pub fn cycle(current: Option<Self>) -> Option<Self> {
+        match current {
+            None => Some(Self::ALL[0]),
+            Some(tag) => {
+                let idx = Self::ALL.iter().position(|t| *t == tag).unwrap_or(0);
+                Self::ALL.get(idx + 1).copied()
+            }
+        }
+    }
+}
+
+/// Lowest and highest instrument trim, in dB
+pub const TRIM_RANGE_DB: (f32, f32) = (-24.0, 24.0);
+
+/// Shortest and longest mono glide, in seconds
+pub const GLIDE_RANGE: (f32, f32) = (0.0, 2.0);
+
+/// Most stacked copies one note can play
+pub const MAX_UNISON_VOICES: u8 = 8;
+
+/// Widest unison detune, in cents either side of the note
+pub const MAX_DETUNE_CENTS: f32 = 100.0;
+
+/// Semitones either side of middle C at which pitch spread reaches full width
+const PITCH_SPREAD_SEMITONES: f32 = 24.0;
+
+/// Positions successive voices take under order spread: alternating sides, outside in
+const ORDER_SPREAD_POSITIONS: [f32; 4] = [-1.0, 1.0, -0.5, 0.5];
+
+/// How polyphonic voices are placed across the stereo field
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum VoiceSpreadMode {
+    /// Low notes left, high notes right
+    #[default]
+    Pitch,
+    /// Successive notes alternate sides
+    Order,
+}
+
+impl VoiceSpreadMode {
+    pub fn name(&self) -> &'static str {
+        match self {
+            VoiceSpreadMode::Pitch => "Pitch",
+            VoiceSpreadMode::Order => "Order",
+        }
+    }
+
+    pub fn next(&self) -> Self {
+        match self {
+            VoiceSpreadMode::Pitch => VoiceSpreadMode::Order,
+            VoiceSpreadMode::Order => VoiceSpreadMode::Pitch,
+        }
+    }
+}
+
+pub fn db_to_amp(db: f32) -> f32 {
+    10f32.powf(db / 20.0)
+}
+
 #[derive(Debug, Clone)]
 pub struct Instrument {
     pub id: InstrumentId,
     pub name: String,
+    pub color: Option<ColorTag>,
     pub source: SourceType,
     pub source_params: Vec<Param>,
     pub filter: Option<FilterConfig>,
     pub effects: Vec<EffectSlot>,
-    pub lfo: LfoConfig,
+    /// Modulation sources and the mod matrix wiring them to destinations. There is
+    /// always at least one LFO.
+    pub lfos: Vec<LfoConfig>,
+    pub mod_envelopes: Vec<EnvConfig>,
+    pub mod_routings: Vec<ModRouting>,
     pub amp_envelope: EnvConfig,
     pub polyphonic: bool,
+    /// Pre-fader gain in dB; 0 leaves the signal as the source made it
+    pub trim: f32,
+    /// Seconds a monophonic instrument takes to slide to each new note; 0 jumps
+    pub glide: f32,
+    /// Detuned copies each note stacks (built-in oscillators only); 1 plays a single voice
+    pub unison_voices: u8,
+    /// Cents the outermost unison copies sit either side of the note
+    pub detune: f32,
+    /// How far (0-1) polyphonic voices spread either side of `pan`; 0 keeps them centred
+    pub voice_spread: f32,
+    pub voice_spread_mode: VoiceSpreadMode,
+    /// Lowest and highest notes played live (MIDI and keyboard), ends included; others
+    /// are ignored, for splits. 0-127 takes every note.
+    pub key_low: u8,
+    pub key_high: u8,
     // Integrated mixer
     pub level: f32,
     pub pan: f32,
@@ -665,13 +1005,24 @@ impl Instrument {
         Self {
             id,
             name: format!("{}-{}", source.short_name(), id),
+            color: None,
             source,
             source_params: source.default_params(),
             filter: None,
             effects: Vec::new(),
-            lfo: LfoConfig::default(),
+            lfos: vec![LfoConfig::default()],
+            mod_envelopes: Vec::new(),
+            mod_routings: vec![ModRouting::new(ModRouteSource::Lfo(0), LfoTarget::FilterCutoff)],
             amp_envelope: EnvConfig::default(),
             polyphonic: true,
+            trim: 0.0,
+            glide: 0.0,
+            unison_voices: 1,
+            detune: 0.0,
+            voice_spread: 0.0,
+            voice_spread_mode: VoiceSpreadMode::default(),
+            key_low: 0,
+            key_high: 127,
             level: 0.8,
             pan: 0.0,
             mute: false,
@@ -683,4 +1034,345 @@ impl Instrument {
             drum_sequencer,
         }
     }
+
+    /// Whether a live note falls in the key range (both ends included)
+    pub fn accepts_note(&self, pitch: u8) -> bool {
+        (self.key_low..=self.key_high).contains(&pitch)
+    }
+
+    /// Note a test tone plays: middle C, or the first zone's root for samplers,
+    /// pulled inside the instrument's key range
+    pub fn audition_pitch(&self) -> u8 {
+        let pitch = self
+            .sampler_config
+            .as_ref()
+            .and_then(|c| c.slices.first())
+            .map_or(60, |zone| zone.root_note.clamp(zone.key_range.0, zone.key_range.1.max(zone.key_range.0)));
+        pitch.clamp(self.key_low, self.key_high.max(self.key_low))
+    }
+
+    /// Trim as a linear gain factor
+    pub fn trim_amp(&self) -> f32 {
+        db_to_amp(self.trim)
+    }
+
+    /// Detune (cents) and pan of each copy a note stacks, spread evenly from hard
+    /// left/flat to hard right/sharp. A single centred copy unless unison is on.
+    pub fn unison_spread(&self) -> Vec<(f32, f32)> {
+        let count = if self.source.is_oscillator() {
+            self.unison_voices.clamp(1, MAX_UNISON_VOICES) as usize
+        } else {
+            1
+        };
+        if count == 1 {
+            return vec![(0.0, 0.0)];
+        }
+        (0..count)
+            .map(|i| {
+                let position = i as f32 / (count - 1) as f32 * 2.0 - 1.0;
+                (position * self.detune, position)
+            })
+            .collect()
+    }
+
+    /// Stereo offset (-1 to 1) of one voice relative to the instrument's pan, whose
+    /// output stage centres the spread. `order` counts the notes spawned so far.
+    pub fn voice_pan(&self, pitch: u8, order: usize) -> f32 {
+        if !self.polyphonic || self.voice_spread <= 0.0 {
+            return 0.0;
+        }
+        let position = match self.voice_spread_mode {
+            VoiceSpreadMode::Pitch => ((pitch as f32 - 60.0) / PITCH_SPREAD_SEMITONES).clamp(-1.0, 1.0),
+            VoiceSpreadMode::Order => ORDER_SPREAD_POSITIONS[order % ORDER_SPREAD_POSITIONS.len()],
+        };
+        position * self.voice_spread.min(1.0)
+    }
+
+    /// Routings whose source exists and is switched on, i.e. the ones the engine wires up
+    pub fn active_routings(&self) -> impl Iterator<Item = &ModRouting> {
+        self.mod_routings.iter().filter(|r| match r.source {
+            ModRouteSource::Lfo(i) => self.lfos.get(i).is_some_and(|l| l.enabled),
+            ModRouteSource::Envelope(i) => i < self.mod_envelopes.len(),
+        })
+    }
+
+    /// Assign every source param (and the filter and amp envelope) a random value in range
+    pub fn randomize_params(&mut self, rng: &mut Rng) {
+        for param in self.patch_params_mut() {
+            param.randomize(rng);
+        }
+        if let Some(ref mut filter) = self.filter {
+            // Exponential cutoff so low frequencies are as likely as high ones
+            let (lo, hi) = (filter.cutoff.min.max(1.0).ln(), filter.cutoff.max.ln());
+            filter.cutoff.value = rng.range(lo, hi).exp();
+            filter.resonance.value = rng.range(filter.resonance.min, filter.resonance.max);
+        }
+        self.amp_envelope = EnvConfig {
+            attack: rng.range(0.001, 1.0),
+            decay: rng.range(0.01, 2.0),
+            sustain: rng.next_f32(),
+            release: rng.range(0.01, 3.0),
+        };
+    }
+
+    /// Perturb every source param by up to `amount` (fraction of its range)
+    pub fn mutate_params(&mut self, rng: &mut Rng, amount: f32) {
+        for param in self.patch_params_mut() {
+            param.mutate(rng, amount);
+        }
+    }
+
+    fn patch_params_mut(&mut self) -> impl Iterator<Item = &mut Param> {
+        self.source_params
+            .iter_mut()
+            .filter(|p| !NON_PATCH_PARAMS.contains(&p.name.as_str()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_key_range_includes_its_ends() {
+        let mut inst = Instrument::new(1, SourceType::Saw);
+        assert!(inst.accepts_note(0) && inst.accepts_note(127));
+        inst.key_low = 48;
+        inst.key_high = 60;
+        assert!(inst.accepts_note(48) && inst.accepts_note(60));
+        assert!(!inst.accepts_note(47) && !inst.accepts_note(61));
+    }
+
+    #[test]
+    fn test_color_tag_cycle_wraps_to_untagged() {
+        let mut tag = None;
+        for expected in ColorTag::ALL {
+            tag = ColorTag::cycle(tag);
+            assert_eq!(tag, Some(expected));
+            assert_eq!(ColorTag::from_name(expected.name()), Some(expected));
+        }
+        assert_eq!(ColorTag::cycle(tag), None);
+    }
+    #[test]
+    fn test_audio_input_mode_index_round_trip_and_clamp() {
+        for mode in AudioInputMode::ALL {
+            assert_eq!(AudioInputMode::from_index(mode.index()), mode);
+        }
+        assert_eq!(AudioInputMode::from_index(-1), AudioInputMode::Left);
+        assert_eq!(AudioInputMode::from_index(9), AudioInputMode::Sum);
+        let input = SourceType::AudioIn.default_params().into_iter().find(|p| p.name == "input").unwrap();
+        assert_eq!(input.max as i32, AudioInputMode::Sum.index());
+    }
+
+    #[test]
+    fn test_trim_defaults_to_unity_gain() {
+        let mut inst = Instrument::new(0, SourceType::Saw);
+        assert_eq!(inst.trim_amp(), 1.0);
+        inst.trim = -6.0;
+        assert!((inst.trim_amp() - 0.501).abs() < 1e-3);
+        inst.trim = 20.0;
+        assert!((inst.trim_amp() - 10.0).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_delay_sync_snaps_to_nearest_division() {
+        let mut delay = EffectSlot::new(EffectType::Delay);
+        assert_eq!(delay.synced_time(120.0), None);
+
+        // The default 0.3s at 120 BPM lands nearest a quarter triplet (0.333s)
+        delay.set_sync(true, 120.0);
+        assert_eq!(delay.division, NoteDivision::QuarterTriplet);
+        delay.division = NoteDivision::Eighth;
+        assert_eq!(delay.synced_time(120.0), Some(0.25));
+        assert_eq!(delay.synced_time(60.0), Some(0.5));
+
+        delay.division = NoteDivision::EighthDotted;
+        assert!((delay.synced_time(100.0).unwrap() - 0.45).abs() < 1e-6);
+        // Whole notes at slow tempos are limited to the delay line length
+        delay.division = NoteDivision::Whole;
+        assert_eq!(delay.synced_time(60.0), Some(2.0));
+
+        // Free mode keeps using the untouched time param
+        delay.set_sync(false, 120.0);
+        assert_eq!(delay.synced_time(120.0), None);
+        assert_eq!(delay.params[0].value_f32(), 0.3);
+
+        let mut reverb = EffectSlot::new(EffectType::Reverb);
+        reverb.set_sync(true, 120.0);
+        assert!(!reverb.sync);
+    }
+
+    #[test]
+    fn test_every_effect_has_dry_wet_mix() {
+        for effect_type in EffectType::all() {
+            let slot = EffectSlot::new(effect_type);
+            assert!(slot.params.iter().any(|p| p.name == "mix"), "{:?}", effect_type);
+        }
+        // Effects that had no blend before default fully wet
+        let mut gate = EffectSlot::new(EffectType::Gate);
+        assert_eq!(gate.mix(), 1.0);
+        gate.adjust_mix(0.5);
+        assert_eq!(gate.mix(), 1.0);
+        gate.adjust_mix(-0.25);
+        assert_eq!(gate.mix(), 0.75);
+    }
+
+    #[test]
+    fn test_lfo_sync_rate() {
+        let mut lfo = LfoConfig::default();
+        assert_eq!(lfo.rate_hz(90.0), 2.0);
+
+        // 2 Hz at 120 BPM is exactly one cycle per quarter
+        lfo.set_sync(true, 120.0);
+        assert_eq!(lfo.division, NoteDivision::Quarter);
+        assert_eq!(lfo.rate_hz(120.0), 2.0);
+        assert_eq!(lfo.rate_hz(60.0), 1.0);
+        lfo.division = NoteDivision::Sixteenth;
+        assert_eq!(lfo.rate_hz(120.0), 8.0);
+        lfo.division = NoteDivision::Whole;
+        assert_eq!(lfo.rate_hz(120.0), 0.5);
+
+        lfo.set_sync(false, 120.0);
+        assert_eq!(lfo.rate_hz(120.0), 2.0);
+    }
+
+    #[test]
+    fn test_voice_pan() {
+        let mut inst = Instrument::new(0, SourceType::Saw);
+        assert_eq!(inst.voice_pan(36, 0), 0.0);
+
+        inst.voice_spread = 0.5;
+        assert_eq!(inst.voice_pan(60, 0), 0.0);
+        assert_eq!(inst.voice_pan(84, 0), 0.5);
+        assert_eq!(inst.voice_pan(12, 0), -0.5);
+
+        inst.voice_spread_mode = VoiceSpreadMode::Order;
+        let pans: Vec<f32> = (0..5).map(|n| inst.voice_pan(60, n)).collect();
+        assert_eq!(pans, [-0.5, 0.5, -0.25, 0.25, -0.5]);
+
+        // Monophonic instruments have one voice; it stays at the base pan
+        inst.polyphonic = false;
+        assert_eq!(inst.voice_pan(84, 1), 0.0);
+    }
+
+    #[test]
+    fn test_unison_spread() {
+        let mut inst = Instrument::new(1, SourceType::Saw);
+        assert_eq!(inst.unison_spread(), [(0.0, 0.0)]);
+
+        inst.unison_voices = 3;
+        inst.detune = 20.0;
+        assert_eq!(inst.unison_spread(), [(-20.0, -1.0), (0.0, 0.0), (20.0, 1.0)]);
+
+        // Zero detune still stacks copies; they sum in phase
+        inst.detune = 0.0;
+        assert!(inst.unison_spread().iter().all(|&(cents, _)| cents == 0.0));
+
+        inst.unison_voices = 40;
+        assert_eq!(inst.unison_spread().len(), MAX_UNISON_VOICES as usize);
+
+        let mut custom = Instrument::new(2, SourceType::Custom(0));
+        custom.unison_voices = 4;
+        assert_eq!(custom.unison_spread().len(), 1);
+    }
+
+    #[test]
+    fn test_filter_keytrack_ratio() {
+        let mut filter = FilterConfig::new(FilterType::Lpf);
+        assert_eq!(filter.keytrack_ratio(24), 1.0);
+        assert_eq!(filter.keytrack_ratio(96), 1.0);
+
+        filter.keytrack = 1.0;
+        assert_eq!(filter.keytrack_ratio(60), 1.0);
+        assert_eq!(filter.keytrack_ratio(72), 2.0);
+        assert_eq!(filter.keytrack_ratio(48), 0.5);
+        filter.keytrack = 0.5;
+        assert_eq!(filter.keytrack_ratio(84), 2.0);
+    }
+
+    #[test]
+    fn test_configured_defaults_apply_to_new_slots() {
+        // No configured defaults: the built-in ones
+        let none = ParamDefaults::default();
+        let delay = EffectSlot::with_defaults(EffectType::Delay, &none);
+        assert_eq!(delay.params, EffectSlot::new(EffectType::Delay).params);
+        assert_eq!(FilterConfig::with_defaults(FilterType::Lpf, &none).cutoff.value, 1000.0);
+
+        let defaults = ParamDefaults {
+            effects: vec![
+                (EffectType::Delay, "feedback".into(), 0.25),
+                (EffectType::Delay, "time".into(), 9.0),
+                (EffectType::Delay, "bogus".into(), 1.0),
+                (EffectType::Gate, "shape".into(), 1.6),
+            ],
+            filters: vec![(FilterType::Hpf, "cutoff".into(), 200.0), (FilterType::Hpf, "keytrack".into(), 5.0)],
+        };
+        let delay = EffectSlot::with_defaults(EffectType::Delay, &defaults);
+        let value = |slot: &EffectSlot, name: &str| slot.params.iter().find(|p| p.name == name).unwrap().value.clone();
+        assert_eq!(value(&delay, "feedback"), ParamValue::Float(0.25));
+        assert_eq!(value(&delay, "time"), ParamValue::Float(2.0));
+        assert_eq!(value(&delay, "mix"), ParamValue::Float(0.3));
+        // Int params stay ints
+        let gate = EffectSlot::with_defaults(EffectType::Gate, &defaults);
+        assert_eq!(value(&gate, "shape"), ParamValue::Int(2));
+        // Defaults only reach their own type
+        assert_eq!(EffectSlot::with_defaults(EffectType::Reverb, &defaults).params, EffectType::Reverb.default_params());
+
+        let hpf = FilterConfig::with_defaults(FilterType::Hpf, &defaults);
+        assert_eq!((hpf.cutoff.value, hpf.keytrack), (200.0, KEYTRACK_RANGE.1));
+        assert_eq!(FilterConfig::with_defaults(FilterType::Lpf, &defaults).cutoff.value, 1000.0);
+    }
+
+    #[test]
+    fn test_audition_pitch() {
+        let mut inst = Instrument::new(0, SourceType::Saw);
+        assert_eq!(inst.audition_pitch(), 60);
+        // Splits sound inside their own range
+        inst.key_low = 72;
+        assert_eq!(inst.audition_pitch(), 72);
+
+        let mut sampler = Instrument::new(1, SourceType::PitchedSampler);
+        let zone = &mut sampler.sampler_config.as_mut().unwrap().slices[0];
+        zone.root_note = 40;
+        zone.key_range = (48, 60);
+        assert_eq!(sampler.audition_pitch(), 48);
+    }
+
+    #[test]
+    fn test_bus_effects_start_fully_wet() {
+        let defaults = ParamDefaults {
+            effects: vec![(EffectType::Reverb, "mix".to_string(), 0.2), (EffectType::Reverb, "room".to_string(), 0.9)],
+            filters: Vec::new(),
+        };
+        let reverb = EffectSlot::for_bus(EffectType::Reverb, &defaults);
+        assert_eq!(reverb.mix(), 1.0);
+        assert_eq!(reverb.params.iter().find(|p| p.name == "room").map(|p| p.value_f32()), Some(0.9));
+        // Adding keeps cycling through every type
+        let mut effect_type = EffectType::Delay;
+        for _ in 0..EffectType::all().len() {
+            effect_type = effect_type.next();
+        }
+        assert_eq!(effect_type, EffectType::Delay);
+    }
+
+    #[test]
+    fn test_randomize_leaves_routing_and_level_alone() {
+        let mut rng = Rng::new(7);
+        for source in [SourceType::Saw, SourceType::AudioIn] {
+            let mut inst = Instrument::new(1, source);
+            let fixed = |inst: &Instrument| {
+                inst.source_params.iter()
+                    .filter(|p| NON_PATCH_PARAMS.contains(&p.name.as_str()))
+                    .map(|p| (p.name.clone(), p.value_f32()))
+                    .collect::<Vec<_>>()
+            };
+            let before = fixed(&inst);
+            assert!(!before.is_empty());
+            for _ in 0..8 {
+                inst.randomize_params(&mut rng);
+                inst.mutate_params(&mut rng, 1.0);
+            }
+            assert_eq!(fixed(&inst), before);
+        }
+    }
 }
@@ -45,6 +45,37 @@ impl InstrumentState {
         }
     }
 
+    /// Move an instrument one slot earlier in the list; the selection follows it.
+    pub fn move_up(&mut self, id: InstrumentId) -> bool {
+        match self.instruments.iter().position(|s| s.id == id) {
+            Some(pos) if pos > 0 => {
+                self.swap(pos, pos - 1);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Move an instrument one slot later in the list; the selection follows it.
+    pub fn move_down(&mut self, id: InstrumentId) -> bool {
+        match self.instruments.iter().position(|s| s.id == id) {
+            Some(pos) if pos + 1 < self.instruments.len() => {
+                self.swap(pos, pos + 1);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn swap(&mut self, a: usize, b: usize) {
+        self.instruments.swap(a, b);
+        if self.selected == Some(a) {
+            self.selected = Some(b);
+        } else if self.selected == Some(b) {
+            self.selected = Some(a);
+        }
+    }
+
     pub fn instrument(&self, id: InstrumentId) -> Option<&Instrument> {
         self.instruments.iter().find(|s| s.id == id)
     }
@@ -188,4 +219,23 @@ mod tests {
         state.select_next();
         assert_eq!(state.selected, Some(2)); // stay at end
     }
+
+    #[test]
+    fn test_move_instrument_keeps_ids_and_selection() {
+        let mut state = InstrumentState::new();
+        let id1 = state.add_instrument(SourceType::Saw);
+        let id2 = state.add_instrument(SourceType::Sin);
+
+        // id2 is selected; moving it up keeps it selected at its new slot
+        assert!(state.move_up(id2));
+        assert_eq!(state.instruments[0].id, id2);
+        assert_eq!(state.instruments[1].id, id1);
+        assert_eq!(state.selected, Some(0));
+
+        assert!(!state.move_up(id2));
+        assert!(!state.move_down(id1));
+        assert!(state.move_down(id2));
+        assert_eq!(state.instruments[1].id, id2);
+        assert_eq!(state.selected, Some(1));
+    }
 }
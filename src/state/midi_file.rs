@@ -0,0 +1,380 @@
+//! Standard MIDI File import. Every melodic channel becomes a new instrument with
+//! its notes on the piano roll; the GM percussion channel becomes a kit whose step
+//! patterns are filled through the GM drum map, one 16th-note step per grid slot
+//! with the offset from the grid kept as the step's nudge.
+
+use super::drum_sequencer::{DrumPattern, DEFAULT_STEPS, MAX_STEPS, NUM_PATTERNS};
+use super::gm_drums::{gm_drum_name, gm_drum_pad, is_drum_channel, PAD_NAMES};
+use super::{AppState, InstrumentId, SourceType};
+
+/// A note read from the file, in the file's own ticks
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MidiFileNote {
+    pub channel: u8,
+    pub pitch: u8,
+    pub velocity: u8,
+    pub tick: u32,
+    pub duration: u32,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct MidiFile {
+    pub ticks_per_beat: u32,
+    /// Notes from every track, sorted by tick
+    pub notes: Vec<MidiFileNote>,
+}
+
+/// What an import added
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MidiImport {
+    pub instruments: Vec<InstrumentId>,
+    pub notes: usize,
+    /// Drum hits past the last step the kit's patterns can hold
+    pub dropped_hits: usize,
+}
+
+impl MidiImport {
+    pub fn summary(&self) -> String {
+        let mut msg = format!("Imported {} notes into {} instruments", self.notes, self.instruments.len());
+        if self.dropped_hits > 0 {
+            msg.push_str(&format!(", {} drum hits past the last pattern dropped", self.dropped_hits));
+        }
+        msg
+    }
+}
+
+/// Parse a format 0 or 1 Standard MIDI File. Notes still held at the end of their
+/// track end there; everything but notes is skipped.
+pub fn parse_midi_file(bytes: &[u8]) -> Result<MidiFile, String> {
+    let mut reader = Reader { bytes, pos: 0 };
+    let (id, header) = reader.chunk()?;
+    if id != b"MThd" || header.len() < 6 {
+        return Err("Not a MIDI file".to_string());
+    }
+    let division = u16::from_be_bytes([header[4], header[5]]);
+    if division & 0x8000 != 0 || division == 0 {
+        return Err("SMPTE-timed MIDI files aren't supported".to_string());
+    }
+
+    let mut notes = Vec::new();
+    while reader.pos < bytes.len() {
+        let (id, data) = reader.chunk()?;
+        if id == b"MTrk" {
+            parse_track(data, &mut notes)?;
+        }
+    }
+    notes.sort_by_key(|n| (n.tick, n.channel, n.pitch));
+    Ok(MidiFile { ticks_per_beat: division as u32, notes })
+}
+
+fn parse_track(data: &[u8], notes: &mut Vec<MidiFileNote>) -> Result<(), String> {
+    let mut reader = Reader { bytes: data, pos: 0 };
+    let mut tick: u32 = 0;
+    let mut running_status: Option<u8> = None;
+    // (channel, pitch, velocity, start tick) of notes waiting for their note-off
+    let mut held: Vec<(u8, u8, u8, u32)> = Vec::new();
+
+    while reader.pos < data.len() {
+        tick = tick.saturating_add(reader.var_len()?);
+        let mut status = reader.byte()?;
+        match status {
+            0xFF => {
+                reader.byte()?;
+                let len = reader.var_len()? as usize;
+                reader.take(len)?;
+                continue;
+            }
+            0xF0 | 0xF7 => {
+                let len = reader.var_len()? as usize;
+                reader.take(len)?;
+                continue;
+            }
+            _ if status < 0x80 => {
+                // Running status: this byte is the first data byte
+                status = running_status.ok_or("MIDI data byte without a status")?;
+                reader.pos -= 1;
+            }
+            _ => running_status = Some(status),
+        }
+
+        let channel = status & 0x0F;
+        match status & 0xF0 {
+            0x80 | 0x90 => {
+                let pitch = reader.byte()? & 0x7F;
+                let velocity = reader.byte()? & 0x7F;
+                if status & 0xF0 == 0x90 && velocity > 0 {
+                    held.push((channel, pitch, velocity, tick));
+                } else if let Some(i) = held.iter().position(|&(c, p, ..)| c == channel && p == pitch) {
+                    let (_, _, velocity, start) = held.remove(i);
+                    notes.push(MidiFileNote { channel, pitch, velocity, tick: start, duration: tick - start });
+                }
+            }
+            0xC0 | 0xD0 => {
+                reader.byte()?;
+            }
+            _ => {
+                reader.take(2)?;
+            }
+        }
+    }
+    for (channel, pitch, velocity, start) in held {
+        notes.push(MidiFileNote { channel, pitch, velocity, tick: start, duration: tick - start });
+    }
+    Ok(())
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], String> {
+        let end = self.pos.checked_add(len).filter(|&end| end <= self.bytes.len());
+        let end = end.ok_or("MIDI file is truncated")?;
+        let slice = &self.bytes[self.pos..end];
+        self.pos = end;
+        Ok(slice)
+    }
+
+    fn byte(&mut self) -> Result<u8, String> {
+        Ok(self.take(1)?[0])
+    }
+
+    /// Variable-length quantity: 7 bits per byte, high bit set on all but the last
+    fn var_len(&mut self) -> Result<u32, String> {
+        let mut value: u32 = 0;
+        for _ in 0..4 {
+            let byte = self.byte()?;
+            value = (value << 7) | (byte & 0x7F) as u32;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err("Bad variable-length value in MIDI file".to_string())
+    }
+
+    fn chunk(&mut self) -> Result<(&'a [u8], &'a [u8]), String> {
+        let id = self.take(4)?;
+        let len = self.take(4)?;
+        let len = u32::from_be_bytes([len[0], len[1], len[2], len[3]]) as usize;
+        Ok((id, self.take(len)?))
+    }
+}
+
+/// Add the file's channels to the project as new instruments: a pitched track per
+/// melodic channel, and a kit for the GM drum channel
+pub fn import_midi_file(state: &mut AppState, file: &MidiFile) -> MidiImport {
+    let mut import = MidiImport::default();
+    let ticks_per_beat = state.session.piano_roll.ticks_per_beat;
+    let scale = |tick: u32| {
+        ((tick as u64 * ticks_per_beat as u64 + file.ticks_per_beat as u64 / 2) / file.ticks_per_beat.max(1) as u64) as u32
+    };
+
+    let mut channels: Vec<u8> = file.notes.iter().map(|n| n.channel).collect();
+    channels.sort_unstable();
+    channels.dedup();
+    for channel in channels {
+        let notes = file.notes.iter().filter(|n| n.channel == channel);
+        if is_drum_channel(channel) {
+            let id = state.add_instrument(SourceType::Kit);
+            let ticks_per_step = (ticks_per_beat / 4).max(1);
+            let notes: Vec<&MidiFileNote> = notes.collect();
+            let hits: Vec<(f32, usize, u8)> = notes.iter()
+                .map(|n| (scale(n.tick) as f32 / ticks_per_step as f32, gm_drum_pad(n.pitch), n.velocity))
+                .collect();
+            let mut dropped = 0;
+            if let Some(inst) = state.instruments.instrument_mut(id) {
+                inst.name = format!("GM Drums-{}", id);
+                if let Some(seq) = inst.drum_sequencer.as_mut() {
+                    for (pad_idx, pad) in seq.pads.iter_mut().enumerate() {
+                        pad.name = pad_name(pad_idx, &notes);
+                    }
+                    dropped = fill_patterns(&mut seq.patterns, &hits);
+                }
+            }
+            import.notes += hits.len() - dropped;
+            import.dropped_hits += dropped;
+            import.instruments.push(id);
+        } else {
+            let id = state.add_instrument(SourceType::Saw);
+            if let Some(inst) = state.instruments.instrument_mut(id) {
+                inst.name = format!("MIDI {}-{}", channel + 1, id);
+            }
+            let pr = &mut state.session.piano_roll;
+            let Some(track_index) = pr.track_order.iter().position(|&t| t == id) else { continue };
+            for note in notes {
+                let tick = scale(note.tick);
+                let duration = scale(note.tick + note.duration).saturating_sub(tick).max(1);
+                pr.add_note(track_index, note.pitch, tick, duration, note.velocity.max(1));
+                import.notes += 1;
+            }
+            import.instruments.push(id);
+        }
+    }
+    import
+}
+
+/// A pad playing a single GM sound is named after it, one sharing several after
+/// its group
+fn pad_name(pad_idx: usize, notes: &[&MidiFileNote]) -> String {
+    let mut pitches = notes.iter().map(|n| n.pitch).filter(|&p| gm_drum_pad(p) == pad_idx);
+    let first = pitches.next();
+    match first.filter(|&p| pitches.all(|q| q == p)).and_then(gm_drum_name) {
+        Some(name) => name.to_string(),
+        None => PAD_NAMES[pad_idx].to_string(),
+    }
+}
+
+/// Lay hits (step position, pad, velocity) out across the patterns, chained by
+/// follow actions to play in order. Returns how many didn't fit.
+fn fill_patterns(patterns: &mut [DrumPattern], hits: &[(f32, usize, u8)]) -> usize {
+    let capacity = NUM_PATTERNS * MAX_STEPS;
+    let last_step = hits.iter().map(|&(pos, ..)| pos.round() as usize).filter(|&s| s < capacity).max();
+    let Some(last_step) = last_step else {
+        return hits.len();
+    };
+    let total = (last_step + 1).div_ceil(DEFAULT_STEPS) * DEFAULT_STEPS;
+    let used = total.div_ceil(MAX_STEPS);
+    for (i, pattern) in patterns.iter_mut().enumerate().take(used) {
+        *pattern = DrumPattern::new((total - i * MAX_STEPS).min(MAX_STEPS));
+        if used > 1 {
+            pattern.follow_action = Some(((i + 1) % used, 1));
+        }
+    }
+
+    let mut dropped = 0;
+    for &(pos, pad, velocity) in hits {
+        let step = pos.round() as usize;
+        if step >= capacity {
+            dropped += 1;
+            continue;
+        }
+        let slot = &mut patterns[step / MAX_STEPS].steps[pad][step % MAX_STEPS];
+        // Several sounds can share a pad; the loudest hit on a step wins
+        if !slot.active || velocity > slot.velocity {
+            slot.active = true;
+            slot.velocity = velocity.max(1);
+            slot.nudge = pos - step as f32;
+        }
+    }
+    dropped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::gm_drums::{PAD_CLAP, PAD_CLOSED_HAT, PAD_KICK, PAD_PERCUSSION, PAD_SNARE};
+
+    fn var_len(mut value: u32) -> Vec<u8> {
+        let mut bytes = vec![(value & 0x7F) as u8];
+        value >>= 7;
+        while value > 0 {
+            bytes.insert(0, (value & 0x7F) as u8 | 0x80);
+            value >>= 7;
+        }
+        bytes
+    }
+
+    /// A format 1 file from (delta, event bytes) tracks
+    fn smf(ticks_per_beat: u16, tracks: &[Vec<(u32, Vec<u8>)>]) -> Vec<u8> {
+        let mut bytes = b"MThd".to_vec();
+        bytes.extend(6u32.to_be_bytes());
+        bytes.extend(1u16.to_be_bytes());
+        bytes.extend((tracks.len() as u16).to_be_bytes());
+        bytes.extend(ticks_per_beat.to_be_bytes());
+        for track in tracks {
+            let mut data = Vec::new();
+            for (delta, event) in track {
+                data.extend(var_len(*delta));
+                data.extend(event);
+            }
+            data.extend([0x00, 0xFF, 0x2F, 0x00]);
+            bytes.extend(b"MTrk");
+            bytes.extend((data.len() as u32).to_be_bytes());
+            bytes.extend(data);
+        }
+        bytes
+    }
+
+    #[test]
+    fn test_parse_notes_with_running_status_and_meta() {
+        let bytes = smf(96, &[
+            vec![(0, vec![0xFF, 0x51, 0x03, 0x07, 0xA1, 0x20])],
+            vec![
+                (0, vec![0x90, 60, 100]),
+                // Running status, and note-on at velocity 0 as note-off
+                (48, vec![64, 90]),
+                (48, vec![60, 0]),
+                (200, vec![0x80, 64, 0]),
+                (0, vec![0xC0, 5]),
+                // Held to the end of the track
+                (0, vec![0x91, 67, 80]),
+            ],
+        ]);
+        let file = parse_midi_file(&bytes).unwrap();
+        assert_eq!(file.ticks_per_beat, 96);
+        let notes: Vec<_> = file.notes.iter().map(|n| (n.channel, n.pitch, n.velocity, n.tick, n.duration)).collect();
+        assert_eq!(notes, [(0, 60, 100, 0, 96), (0, 64, 90, 48, 248), (1, 67, 80, 296, 0)]);
+
+        assert!(parse_midi_file(b"RIFF").is_err());
+        assert!(parse_midi_file(&bytes[..bytes.len() - 3]).is_err());
+    }
+
+    #[test]
+    fn test_import_routes_drums_to_pads_and_keeps_melodic_channels_pitched() {
+        let mut state = AppState::new();
+        let tpb = state.session.piano_roll.ticks_per_beat;
+        let file = MidiFile {
+            ticks_per_beat: 96,
+            notes: vec![
+                MidiFileNote { channel: 0, pitch: 48, velocity: 100, tick: 0, duration: 96 },
+                MidiFileNote { channel: 9, pitch: 36, velocity: 110, tick: 0, duration: 10 },
+                // A little late for the second 16th
+                MidiFileNote { channel: 9, pitch: 38, velocity: 90, tick: 30, duration: 10 },
+                MidiFileNote { channel: 9, pitch: 42, velocity: 70, tick: 48, duration: 10 },
+                // Not a GM sound: the percussion pad
+                MidiFileNote { channel: 9, pitch: 90, velocity: 70, tick: 72, duration: 10 },
+                // Bar 5, past the first 64-step pattern
+                MidiFileNote { channel: 9, pitch: 36, velocity: 100, tick: 96 * 16, duration: 10 },
+            ],
+        };
+        let import = import_midi_file(&mut state, &file);
+        assert_eq!(import.instruments.len(), 2);
+        assert_eq!(import.notes, 6);
+
+        let melodic = import.instruments[0];
+        let track = state.session.piano_roll.track_at(0).unwrap();
+        assert_eq!(track.module_id, melodic);
+        assert_eq!((track.notes[0].pitch, track.notes[0].tick, track.notes[0].duration), (48, 0, tpb));
+
+        let kit = state.instruments.instrument(import.instruments[1]).unwrap();
+        assert!(kit.source.is_kit());
+        let seq = kit.drum_sequencer.as_ref().unwrap();
+        assert_eq!(seq.pads[PAD_SNARE].name, "Acoustic Snare");
+        assert_eq!(seq.pads[PAD_PERCUSSION].name, "Percussion");
+        assert_eq!(seq.pads[PAD_CLAP].name, "Clap");
+        let first = &seq.patterns[0];
+        assert_eq!(first.length, MAX_STEPS);
+        assert!(first.steps[PAD_KICK][0].active);
+        assert_eq!(first.steps[PAD_KICK][0].velocity, 110);
+        assert!(first.steps[PAD_SNARE][1].active);
+        assert!((first.steps[PAD_SNARE][1].nudge - 0.25).abs() < 1e-6);
+        assert!(first.steps[PAD_CLOSED_HAT][2].active);
+        assert!(first.steps[PAD_PERCUSSION][3].active);
+        assert!(seq.patterns[1].steps[PAD_KICK][0].active);
+        assert_eq!(seq.patterns[1].length, DEFAULT_STEPS);
+        assert_eq!(first.follow_action, Some((1, 1)));
+        assert_eq!(seq.patterns[1].follow_action, Some((0, 1)));
+    }
+
+    #[test]
+    fn test_drum_hits_past_the_patterns_are_counted() {
+        let mut patterns: Vec<DrumPattern> = (0..NUM_PATTERNS).map(|_| DrumPattern::new(DEFAULT_STEPS)).collect();
+        let capacity = (NUM_PATTERNS * MAX_STEPS) as f32;
+        let dropped = fill_patterns(&mut patterns, &[(0.0, PAD_KICK, 100), (capacity, PAD_KICK, 100)]);
+        assert_eq!(dropped, 1);
+        assert_eq!(patterns[0].length, DEFAULT_STEPS);
+        assert_eq!(patterns[0].follow_action, None);
+    }
+}
@@ -1,7 +1,14 @@
 #![allow(dead_code)]
 
+use std::collections::{HashMap, VecDeque};
+
 use super::automation::AutomationTarget;
 use super::instrument::InstrumentId;
+use super::piano_roll::Note;
+use crate::midi::MidiEvent;
+
+/// Number of MIDI channels
+pub const MIDI_CHANNELS: usize = 16;
 
 /// Recording mode for MIDI automation
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -20,6 +27,44 @@ impl Default for RecordMode {
     }
 }
 
+/// What an incoming Program Change selects
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ProgramChangeMode {
+    /// Ignore Program Change
+    #[default]
+    Off,
+    /// Select the instrument at the program number's index
+    SelectInstrument,
+    /// Switch the routed drum machine to the program number's pattern
+    SelectPattern,
+}
+
+impl ProgramChangeMode {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Off => "off",
+            Self::SelectInstrument => "instrument",
+            Self::SelectPattern => "pattern",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Self {
+        match name {
+            "instrument" => Self::SelectInstrument,
+            "pattern" => Self::SelectPattern,
+            _ => Self::Off,
+        }
+    }
+
+    pub fn next(&self) -> Self {
+        match self {
+            Self::Off => Self::SelectInstrument,
+            Self::SelectInstrument => Self::SelectPattern,
+            Self::SelectPattern => Self::Off,
+        }
+    }
+}
+
 /// Mapping of a MIDI CC to an automation target
 #[derive(Debug, Clone)]
 pub struct MidiCcMapping {
@@ -60,6 +105,16 @@ impl MidiCcMapping {
     }
 }
 
+/// A MIDI event captured while recording
+#[derive(Debug, Clone)]
+pub struct RecordedMidiEvent {
+    /// Playhead position when the event arrived
+    pub tick: u32,
+    /// Instrument the event was routed to (None if nothing was selected)
+    pub instrument_id: Option<InstrumentId>,
+    pub event: MidiEvent,
+}
+
 /// Pitch bend configuration for scratching
 #[derive(Debug, Clone)]
 pub struct PitchBendConfig {
@@ -90,6 +145,51 @@ impl PitchBendConfig {
     }
 }
 
+/// Ticks one automation target has been written over in the current recording
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WrittenSpan {
+    pub start: u32,
+    pub end: u32,
+    /// Latest write, where the next one overwrites from
+    pub last: u32,
+}
+
+/// Soft thru limits for the note-on velocity scale
+pub const MIN_THRU_VELOCITY_SCALE: f32 = 0.1;
+pub const MAX_THRU_VELOCITY_SCALE: f32 = 2.0;
+
+/// Soft thru: incoming MIDI echoed to the MIDI output for layering with hardware.
+/// It sits beside internal routing, never feeding it, so nothing plays twice here.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MidiThru {
+    pub enabled: bool,
+    /// Send everything on this channel (None = keep the incoming channel)
+    pub channel: Option<u8>,
+    /// Note-on velocity multiplier
+    pub velocity_scale: f32,
+}
+
+impl Default for MidiThru {
+    fn default() -> Self {
+        Self { enabled: false, channel: None, velocity_scale: 1.0 }
+    }
+}
+
+impl MidiThru {
+    /// Step the remap channel: incoming, then 0..15, then back to incoming
+    pub fn cycle_channel(&mut self) {
+        self.channel = match self.channel {
+            None => Some(0),
+            Some(ch) if (ch as usize) < MIDI_CHANNELS - 1 => Some(ch + 1),
+            Some(_) => None,
+        };
+    }
+
+    pub fn adjust_velocity_scale(&mut self, delta: f32) {
+        self.velocity_scale = (self.velocity_scale + delta).clamp(MIN_THRU_VELOCITY_SCALE, MAX_THRU_VELOCITY_SCALE);
+    }
+}
+
 /// State for MIDI recording and mapping
 #[derive(Debug, Clone, Default)]
 pub struct MidiRecordingState {
@@ -105,6 +205,26 @@ pub struct MidiRecordingState {
     pub note_passthrough: bool,
     /// MIDI channel filter (None = all channels)
     pub channel_filter: Option<u8>,
+    /// What incoming Program Change messages select
+    pub program_change_mode: ProgramChangeMode,
+    /// Quantize grid for recorded notes in steps per beat (None = unquantized)
+    pub record_quantize: Option<u32>,
+    /// Whether recorded CC/pitch bend becomes automation points
+    pub record_automation: bool,
+    /// Events captured during the current recording (runtime only)
+    pub recorded_events: Vec<RecordedMidiEvent>,
+    /// Per-channel instrument routing (None = follow the selected instrument)
+    pub channel_routes: [Option<InstrumentId>; MIDI_CHANNELS],
+    /// Sounding notes keyed by (channel, note), so a note-off reaches the
+    /// instrument that received the note-on even if routing changed since.
+    /// Runtime only, not persisted.
+    pub held_notes: HashMap<(u8, u8), InstrumentId>,
+    /// Soft thru to the MIDI output
+    pub thru: MidiThru,
+    /// Target the next incoming CC gets mapped to (runtime only)
+    pub learn_target: Option<AutomationTarget>,
+    /// Lanes written from CC and pitch bend during the current recording (runtime only)
+    pub automation_writes: HashMap<AutomationTarget, WrittenSpan>,
 }
 
 impl MidiRecordingState {
@@ -116,6 +236,15 @@ impl MidiRecordingState {
             live_input_instrument: None,
             note_passthrough: true,
             channel_filter: None,
+            program_change_mode: ProgramChangeMode::default(),
+            record_quantize: None,
+            record_automation: false,
+            recorded_events: Vec::new(),
+            channel_routes: [None; MIDI_CHANNELS],
+            held_notes: HashMap::new(),
+            thru: MidiThru::default(),
+            learn_target: None,
+            automation_writes: HashMap::new(),
         }
     }
 
@@ -168,9 +297,129 @@ impl MidiRecordingState {
         }
     }
 
-    /// Stop recording
+    /// Stop recording and discard any captured events
     pub fn stop_recording(&mut self) {
         self.record_mode = RecordMode::Off;
+        self.recorded_events.clear();
+        self.automation_writes.clear();
+    }
+
+    /// Map the CC that just arrived to the armed learn target, replacing whatever
+    /// that controller was mapped to on its channel. Returns the learned target.
+    pub fn learn_cc(&mut self, channel: u8, controller: u8) -> Option<AutomationTarget> {
+        let target = self.learn_target.take()?;
+        let mut mapping = MidiCcMapping::new(controller, target.clone());
+        mapping.channel = Some(channel);
+        self.add_cc_mapping(mapping);
+        Some(target)
+    }
+
+    /// Drop every CC mapping to `target`
+    pub fn forget_cc_mappings(&mut self, target: &AutomationTarget) {
+        self.cc_mappings.retain(|m| &m.target != target);
+    }
+
+    /// Note a live automation write at `tick`. Returns the previous write on the
+    /// same target this pass, which the new one overwrites from; after the
+    /// playhead loops back there is none.
+    pub fn track_automation_write(&mut self, target: &AutomationTarget, tick: u32) -> Option<u32> {
+        match self.automation_writes.get_mut(target) {
+            Some(span) => {
+                let since = (tick >= span.last).then_some(span.last);
+                span.start = span.start.min(tick);
+                span.end = span.end.max(tick);
+                span.last = tick;
+                since
+            }
+            None => {
+                self.automation_writes.insert(target.clone(), WrittenSpan { start: tick, end: tick, last: tick });
+                None
+            }
+        }
+    }
+
+    /// Whether a lane is being written live, so playback should leave it alone
+    pub fn is_writing(&self, target: &AutomationTarget) -> bool {
+        self.is_recording() && self.automation_writes.contains_key(target)
+    }
+
+    /// Capture an incoming event at the given tick (no-op unless recording)
+    pub fn capture(&mut self, tick: u32, instrument_id: Option<InstrumentId>, event: MidiEvent) {
+        if self.is_recording() {
+            self.recorded_events.push(RecordedMidiEvent { tick, instrument_id, event });
+        }
+    }
+
+    /// Cycle the record quantize grid: off, 1/4, 1/8, 1/16, 1/32
+    pub fn cycle_record_quantize(&mut self) {
+        self.record_quantize = match self.record_quantize {
+            None => Some(1),
+            Some(1) => Some(2),
+            Some(2) => Some(4),
+            Some(4) => Some(8),
+            Some(_) => None,
+        };
+    }
+
+    /// Pair captured note-ons with note-offs into piano roll notes.
+    ///
+    /// Repeated notes on the same channel/pitch are paired first-in first-out, so
+    /// overlapping strikes each keep their own length. Notes still held at
+    /// `end_tick` are closed there. When the playhead wrapped around a loop
+    /// (`loop_range`), a note-off earlier than its note-on is measured across the wrap.
+    pub fn recorded_notes(
+        &self,
+        end_tick: u32,
+        ticks_per_beat: u32,
+        loop_range: Option<(u32, u32)>,
+    ) -> Vec<(InstrumentId, Note)> {
+        let span = |start: u32, end: u32| -> u32 {
+            match loop_range {
+                Some((loop_start, loop_end)) if end < start => {
+                    loop_end.saturating_sub(start) + end.saturating_sub(loop_start)
+                }
+                _ => end.saturating_sub(start),
+            }
+        };
+
+        // (channel, pitch) -> queue of (start tick, velocity, instrument)
+        type OpenNotes = HashMap<(u8, u8), VecDeque<(u32, u8, InstrumentId)>>;
+        let mut open = OpenNotes::new();
+        let mut notes = Vec::new();
+        for rec in &self.recorded_events {
+            match rec.event {
+                MidiEvent::NoteOn { channel, note, velocity } => {
+                    if let Some(id) = rec.instrument_id {
+                        open.entry((channel, note)).or_default().push_back((rec.tick, velocity, id));
+                    }
+                }
+                MidiEvent::NoteOff { channel, note } => {
+                    if let Some((start, velocity, id)) = open.get_mut(&(channel, note)).and_then(|q| q.pop_front()) {
+                        notes.push((id, Note::new(start, span(start, rec.tick), note, velocity)));
+                    }
+                }
+                _ => {}
+            }
+        }
+        for ((_, note), queue) in open {
+            for (start, velocity, id) in queue {
+                notes.push((id, Note::new(start, span(start, end_tick), note, velocity)));
+            }
+        }
+        notes.sort_by_key(|(_, n)| (n.tick, n.pitch));
+
+        if let Some(grid) = self.record_quantize.map(|div| (ticks_per_beat / div.max(1)).max(1)) {
+            for (_, n) in &mut notes {
+                let start = (n.tick + grid / 2) / grid * grid;
+                let end = ((n.tick + n.duration + grid / 2) / grid * grid).max(start + grid);
+                n.tick = start;
+                n.duration = end - start;
+            }
+        }
+        for (_, n) in &mut notes {
+            n.duration = n.duration.max(1);
+        }
+        notes
     }
 
     /// Check if currently recording
@@ -192,6 +441,74 @@ impl MidiRecordingState {
     pub fn should_process_channel(&self, channel: u8) -> bool {
         self.channel_filter.map_or(true, |f| f == channel)
     }
+
+    /// Route a MIDI channel to an instrument (None = follow the selected instrument)
+    pub fn set_channel_route(&mut self, channel: u8, instrument_id: Option<InstrumentId>) {
+        if let Some(route) = self.channel_routes.get_mut(channel as usize) {
+            *route = instrument_id;
+        }
+    }
+
+    /// Resolve the target instrument for a MIDI channel
+    pub fn route_channel(&self, channel: u8, selected: Option<InstrumentId>) -> Option<InstrumentId> {
+        self.channel_routes
+            .get(channel as usize)
+            .copied()
+            .flatten()
+            .or(selected)
+    }
+
+    /// Resolve and remember the target instrument for a note-on
+    pub fn note_on_target(&mut self, channel: u8, note: u8, selected: Option<InstrumentId>) -> Option<InstrumentId> {
+        let target = self.route_channel(channel, selected)?;
+        self.held_notes.insert((channel, note), target);
+        Some(target)
+    }
+
+    /// Take the instrument that received the matching note-on
+    pub fn note_off_target(&mut self, channel: u8, note: u8) -> Option<InstrumentId> {
+        self.held_notes.remove(&(channel, note))
+    }
+
+    /// What soft thru sends for an incoming event, if anything. Thru follows the
+    /// input channel filter, but note-offs always pass so a note can't hang on the
+    /// hardware when the filter or routing changed while it was held. Scaled
+    /// velocities stay at least 1 so a note-on never turns into a note-off.
+    pub fn thru_event(&self, event: &MidiEvent) -> Option<MidiEvent> {
+        let thru = &self.thru;
+        if !thru.enabled {
+            return None;
+        }
+        let remap = |channel: u8| thru.channel.unwrap_or(channel);
+        let passes = |channel: u8| self.should_process_channel(channel);
+        match *event {
+            MidiEvent::NoteOff { channel, note } => Some(MidiEvent::NoteOff { channel: remap(channel), note }),
+            MidiEvent::NoteOn { channel, note, velocity } => passes(channel).then(|| MidiEvent::NoteOn {
+                channel: remap(channel),
+                note,
+                velocity: (velocity as f32 * thru.velocity_scale).round().clamp(1.0, 127.0) as u8,
+            }),
+            MidiEvent::ControlChange { channel, controller, value } => passes(channel)
+                .then(|| MidiEvent::ControlChange { channel: remap(channel), controller, value }),
+            MidiEvent::PitchBend { channel, value } => passes(channel)
+                .then(|| MidiEvent::PitchBend { channel: remap(channel), value }),
+            MidiEvent::ProgramChange { channel, program } => passes(channel)
+                .then(|| MidiEvent::ProgramChange { channel: remap(channel), program }),
+            MidiEvent::Aftertouch { channel, pressure } => passes(channel)
+                .then(|| MidiEvent::Aftertouch { channel: remap(channel), pressure }),
+            MidiEvent::PolyAftertouch { channel, note, pressure } => passes(channel)
+                .then(|| MidiEvent::PolyAftertouch { channel: remap(channel), note, pressure }),
+        }
+    }
+
+    /// Drop channel routes pointing at a removed instrument
+    pub fn clear_instrument_routes(&mut self, instrument_id: InstrumentId) {
+        for route in self.channel_routes.iter_mut() {
+            if *route == Some(instrument_id) {
+                *route = None;
+            }
+        }
+    }
 }
 
 /// Common CC numbers for reference
@@ -270,4 +587,149 @@ mod tests {
         state.stop_recording();
         assert_eq!(state.record_mode, RecordMode::Off);
     }
+
+    #[test]
+    fn test_channel_routing() {
+        let mut state = MidiRecordingState::new();
+
+        // Unrouted channels follow the selected instrument
+        assert_eq!(state.route_channel(0, Some(7)), Some(7));
+        assert_eq!(state.route_channel(0, None), None);
+
+        state.set_channel_route(1, Some(3));
+        assert_eq!(state.route_channel(1, Some(7)), Some(3));
+        assert_eq!(state.route_channel(0, Some(7)), Some(7));
+
+        state.clear_instrument_routes(3);
+        assert_eq!(state.route_channel(1, Some(7)), Some(7));
+    }
+
+    #[test]
+    fn test_note_off_follows_note_on() {
+        let mut state = MidiRecordingState::new();
+        state.set_channel_route(0, Some(1));
+        assert_eq!(state.note_on_target(0, 60, Some(9)), Some(1));
+
+        // Re-route mid-note: the note-off still reaches the original instrument
+        state.set_channel_route(0, Some(2));
+        assert_eq!(state.note_off_target(0, 60), Some(1));
+        assert_eq!(state.note_off_target(0, 60), None);
+    }
+
+    #[test]
+    fn test_program_change_mode_names() {
+        for mode in [ProgramChangeMode::Off, ProgramChangeMode::SelectInstrument, ProgramChangeMode::SelectPattern] {
+            assert_eq!(ProgramChangeMode::from_name(mode.name()), mode);
+        }
+        // Program Change is opt-in: unset or unknown means ignore it
+        assert_eq!(ProgramChangeMode::default(), ProgramChangeMode::Off);
+        assert_eq!(ProgramChangeMode::from_name("bogus"), ProgramChangeMode::Off);
+    }
+
+    fn note_on(tick: u32, note: u8) -> RecordedMidiEvent {
+        RecordedMidiEvent { tick, instrument_id: Some(1), event: MidiEvent::NoteOn { channel: 0, note, velocity: 100 } }
+    }
+
+    fn note_off(tick: u32, note: u8) -> RecordedMidiEvent {
+        RecordedMidiEvent { tick, instrument_id: Some(1), event: MidiEvent::NoteOff { channel: 0, note } }
+    }
+
+    #[test]
+    fn test_recorded_notes_overlapping() {
+        let mut state = MidiRecordingState::new();
+        state.recorded_events = vec![
+            note_on(0, 60),
+            note_on(100, 60), // same pitch struck again before release
+            note_off(200, 60),
+            note_off(300, 60),
+            note_on(400, 64), // never released
+        ];
+        let notes = state.recorded_notes(500, 480, None);
+        let spans: Vec<(u32, u32, u8)> = notes.iter().map(|(_, n)| (n.tick, n.duration, n.pitch)).collect();
+        assert_eq!(spans, vec![(0, 200, 60), (100, 200, 60), (400, 100, 64)]);
+    }
+
+    #[test]
+    fn test_recorded_notes_quantized_and_wrapped() {
+        let mut state = MidiRecordingState::new();
+        state.record_quantize = Some(4); // 1/16 = 120 ticks
+        state.recorded_events = vec![note_on(130, 60), note_off(170, 60)];
+        let notes = state.recorded_notes(1000, 480, None);
+        assert_eq!((notes[0].1.tick, notes[0].1.duration), (120, 120));
+
+        // Note-off after the playhead wrapped from 1920 back to 0
+        state.record_quantize = None;
+        state.recorded_events = vec![note_on(1800, 60), note_off(60, 60)];
+        let notes = state.recorded_notes(100, 480, Some((0, 1920)));
+        assert_eq!((notes[0].1.tick, notes[0].1.duration), (1800, 180));
+    }
+
+    #[test]
+    fn test_thru_remaps_scales_and_always_passes_note_off() {
+        let mut midi = MidiRecordingState::new();
+        let on = MidiEvent::NoteOn { channel: 0, note: 60, velocity: 100 };
+        assert_eq!(midi.thru_event(&on), None);
+
+        midi.thru.enabled = true;
+        assert_eq!(midi.thru_event(&on), Some(on));
+
+        midi.thru.channel = Some(9);
+        midi.thru.velocity_scale = 0.5;
+        assert_eq!(midi.thru_event(&on), Some(MidiEvent::NoteOn { channel: 9, note: 60, velocity: 50 }));
+        midi.thru.velocity_scale = 0.1;
+        let soft = MidiEvent::NoteOn { channel: 0, note: 60, velocity: 3 };
+        assert_eq!(midi.thru_event(&soft), Some(MidiEvent::NoteOn { channel: 9, note: 60, velocity: 1 }));
+
+        // Filtered channels drop note-ons but still release notes
+        midi.channel_filter = Some(4);
+        assert_eq!(midi.thru_event(&on), None);
+        let off = MidiEvent::NoteOff { channel: 0, note: 60 };
+        assert_eq!(midi.thru_event(&off), Some(MidiEvent::NoteOff { channel: 9, note: 60 }));
+    }
+
+    #[test]
+    fn test_thru_channel_cycles_back_to_incoming() {
+        let mut thru = MidiThru::default();
+        for _ in 0..MIDI_CHANNELS {
+            thru.cycle_channel();
+        }
+        assert_eq!(thru.channel, Some(15));
+        thru.cycle_channel();
+        assert_eq!(thru.channel, None);
+        thru.adjust_velocity_scale(5.0);
+        assert_eq!(thru.velocity_scale, MAX_THRU_VELOCITY_SCALE);
+    }
+
+    #[test]
+    fn test_learn_maps_next_cc_to_armed_target() {
+        let mut state = MidiRecordingState::new();
+        assert_eq!(state.learn_cc(0, 74), None);
+
+        state.cc_mappings.push(MidiCcMapping { channel: Some(2), ..MidiCcMapping::new(74, AutomationTarget::InstrumentPan(1)) });
+        state.learn_target = Some(AutomationTarget::FilterCutoff(1));
+        assert_eq!(state.learn_cc(2, 74), Some(AutomationTarget::FilterCutoff(1)));
+        assert_eq!(state.learn_target, None);
+        assert_eq!(state.cc_mappings.len(), 1);
+        assert_eq!(state.find_cc_mapping(74, 2).unwrap().target, AutomationTarget::FilterCutoff(1));
+        assert!(state.find_cc_mapping(74, 3).is_none());
+
+        state.forget_cc_mappings(&AutomationTarget::FilterCutoff(1));
+        assert!(state.cc_mappings.is_empty());
+    }
+
+    #[test]
+    fn test_automation_writes_track_span_and_loop_wrap() {
+        let mut state = MidiRecordingState::new();
+        let target = AutomationTarget::InstrumentLevel(0);
+        assert_eq!(state.track_automation_write(&target, 100), None);
+        assert_eq!(state.track_automation_write(&target, 160), Some(100));
+        // Looped back to the start: nothing to overwrite from
+        assert_eq!(state.track_automation_write(&target, 20), None);
+        assert_eq!(state.automation_writes[&target], WrittenSpan { start: 20, end: 160, last: 20 });
+        assert!(!state.is_writing(&target));
+        state.record_mode = RecordMode::Recording;
+        assert!(state.is_writing(&target));
+        state.stop_recording();
+        assert!(state.automation_writes.is_empty());
+    }
 }
@@ -1,27 +1,46 @@
 pub mod automation;
 pub mod custom_synthdef;
 pub mod drum_sequencer;
+pub mod fader;
+pub mod gm_drums;
+pub mod groove;
 pub mod instrument;
 pub mod instrument_state;
+pub mod midi_file;
 pub mod midi_recording;
+pub mod modulation;
 pub mod music;
+pub mod note_repeat;
 pub mod param;
 pub mod persistence;
+pub mod phrase;
 pub mod piano_roll;
+pub mod preset;
+pub mod project_io;
+pub mod rng;
 pub mod sampler;
 pub mod session;
+pub mod ui_state;
 
 pub use automation::AutomationTarget;
-pub use custom_synthdef::{CustomSynthDef, CustomSynthDefRegistry, ParamSpec};
+pub use custom_synthdef::{CustomSynthDef, CustomSynthDefRegistry, ParamSpec, SynthDefImportReport};
 pub use instrument::*;
 pub use instrument_state::InstrumentState;
 pub use param::{Param, ParamValue};
-pub use sampler::BufferId;
-pub use session::{MixerSelection, MusicalSettings, SessionState, MAX_BUSES};
+pub use sampler::{BufferId, SampleLoadStatus};
+pub use session::{MixerSelection, MusicalSettings, SessionState, SoloMode, MAX_BUSES};
 
+use std::collections::HashMap;
+
+use crate::audio::peak_cache::PeakCache;
 use crate::ui::KeyboardLayout;
 
 /// Top-level application state, owned by main.rs and passed to panes by reference.
+/// Beats a live-played note sounds before auto-release, unless configured otherwise
+pub const DEFAULT_LIVE_NOTE_LENGTH: f32 = 0.5;
+/// Largest record latency offset accepted, either way, in milliseconds
+pub const MAX_RECORD_OFFSET_MS: f32 = 500.0;
+
 pub struct AppState {
     pub session: SessionState,
     pub instruments: InstrumentState,
@@ -32,13 +51,54 @@ pub struct AppState {
     pub keyboard_layout: KeyboardLayout,
     pub recording: bool,
     pub recording_secs: u64,
+    /// Waveform peaks for sample files, keyed by path + mtime
+    pub peak_cache: PeakCache,
+    /// Background sample load progress per buffer
+    pub sample_loads: HashMap<BufferId, SampleLoadStatus>,
+    /// Voices each instrument is sounding, refreshed from the engine every frame
+    /// (runtime only; instruments with none are left out)
+    pub voice_counts: HashMap<InstrumentId, usize>,
+    /// Randomness for patch randomize/mutate, started from the session's seed
+    pub rng: rng::Rng,
+    /// Held-note repeat (runtime only)
+    pub note_repeat: note_repeat::NoteRepeatState,
+    /// Beats a live-played note sounds before auto-release
+    pub live_note_length: f32,
+    /// Computer-keyboard notes sound while their key auto-repeats, not a fixed length
+    pub live_note_hold: bool,
+    /// Milliseconds recorded MIDI is moved earlier to cancel input latency (negative
+    /// moves it later). Recording only; playback is untouched.
+    pub record_offset_ms: f32,
+    /// Configured starting values for effects and filters added in the instrument editor
+    pub param_defaults: ParamDefaults,
+    /// Saved note phrases, shared by every project
+    pub phrases: phrase::PhraseLibrary,
+    /// Save or load running in the background (runtime only)
+    pub project_io: project_io::ProjectIo,
+    /// Sustain pedal or hold toggle is down (runtime only)
+    pub sustain: bool,
+    /// Master output summed to mono for checking (runtime only; the mix is untouched)
+    pub mono_check: bool,
 }
 
 impl AppState {
+    /// Use `seed` for the project and restart randomness from it
+    pub fn set_rng_seed(&mut self, seed: u64) {
+        self.session.rng_seed = seed;
+        self.rng = rng::Rng::new(seed);
+    }
+
+    /// Voices an instrument is sounding
+    pub fn voice_count(&self, id: InstrumentId) -> usize {
+        self.voice_counts.get(&id).copied().unwrap_or(0)
+    }
+
     #[allow(dead_code)]
     pub fn new() -> Self {
+        let session = SessionState::new();
         Self {
-            session: SessionState::new(),
+            rng: rng::Rng::new(session.rng_seed),
+            session,
             instruments: InstrumentState::new(),
             audio_in_waveform: None,
             recorded_waveform: None,
@@ -46,12 +106,28 @@ impl AppState {
             keyboard_layout: KeyboardLayout::default(),
             recording: false,
             recording_secs: 0,
+            peak_cache: PeakCache::default_disk_dir()
+                .map(PeakCache::with_disk_dir)
+                .unwrap_or_default(),
+            sample_loads: HashMap::new(),
+            voice_counts: HashMap::new(),
+            note_repeat: note_repeat::NoteRepeatState::default(),
+            live_note_length: DEFAULT_LIVE_NOTE_LENGTH,
+            live_note_hold: false,
+            record_offset_ms: 0.0,
+            param_defaults: ParamDefaults::default(),
+            phrases: phrase::PhraseLibrary::default(),
+            project_io: project_io::ProjectIo::default(),
+            sustain: false,
+            mono_check: false,
         }
     }
 
     pub fn new_with_defaults(defaults: MusicalSettings) -> Self {
+        let session = SessionState::new_with_defaults(defaults);
         Self {
-            session: SessionState::new_with_defaults(defaults),
+            rng: rng::Rng::new(session.rng_seed),
+            session,
             instruments: InstrumentState::new(),
             audio_in_waveform: None,
             recorded_waveform: None,
@@ -59,9 +135,66 @@ impl AppState {
             keyboard_layout: KeyboardLayout::default(),
             recording: false,
             recording_secs: 0,
+            peak_cache: PeakCache::default_disk_dir()
+                .map(PeakCache::with_disk_dir)
+                .unwrap_or_default(),
+            sample_loads: HashMap::new(),
+            voice_counts: HashMap::new(),
+            note_repeat: note_repeat::NoteRepeatState::default(),
+            live_note_length: DEFAULT_LIVE_NOTE_LENGTH,
+            live_note_hold: false,
+            record_offset_ms: 0.0,
+            param_defaults: ParamDefaults::default(),
+            phrases: phrase::PhraseLibrary::default(),
+            project_io: project_io::ProjectIo::default(),
+            sustain: false,
+            mono_check: false,
+        }
+    }
+
+    /// Playhead position incoming MIDI is recorded at: the playhead less
+    /// `record_offset_ms` at the current tempo. While looping, a position pulled
+    /// before the loop start wraps to the loop's end; otherwise it stops at zero.
+    pub fn record_tick(&self) -> u32 {
+        let pr = &self.session.piano_roll;
+        let offset = (self.record_offset_ms as f64 / 1000.0 * pr.bpm as f64 / 60.0 * pr.ticks_per_beat as f64).round() as i64;
+        let tick = pr.playhead as i64 - offset;
+        if pr.looping && pr.loop_end > pr.loop_start && (pr.loop_start..pr.loop_end).contains(&pr.playhead) {
+            let (start, len) = (pr.loop_start as i64, (pr.loop_end - pr.loop_start) as i64);
+            return (start + (tick - start).rem_euclid(len)) as u32;
+        }
+        tick.clamp(0, u32::MAX as i64) as u32
+    }
+
+    /// Ticks a live-played note sounds before auto-release, at the current resolution
+    pub fn live_note_ticks(&self) -> u32 {
+        let ticks = self.live_note_length * self.session.piano_roll.ticks_per_beat as f32;
+        (ticks.round() as u32).max(1)
+    }
+
+    /// Ticks a note played from the computer keyboard sounds before auto-release. In
+    /// hold mode a tap is stretched past the keyboard's repeat delay, so a held key's
+    /// first auto-repeat still finds its note sounding.
+    pub fn key_note_ticks(&self) -> u32 {
+        if self.live_note_hold {
+            self.live_note_ticks().max(self.ticks_in(note_repeat::KEY_REPEAT_DELAY))
+        } else {
+            self.live_note_ticks()
         }
     }
 
+    /// Ticks a held key's note keeps sounding after each auto-repeat: once repeats
+    /// stop for this long the key counts as up
+    pub fn key_release_ticks(&self) -> u32 {
+        self.ticks_in(note_repeat::KEY_RELEASE_GAP)
+    }
+
+    fn ticks_in(&self, duration: std::time::Duration) -> u32 {
+        let pr = &self.session.piano_roll;
+        let ticks = duration.as_secs_f64() * pr.bpm as f64 / 60.0 * pr.ticks_per_beat as f64;
+        (ticks.ceil() as u32).max(1)
+    }
+
     /// Add an instrument, with custom synthdef param setup and piano roll track auto-creation.
     pub fn add_instrument(&mut self, source: SourceType) -> InstrumentId {
         let id = self.instruments.add_instrument(source);
@@ -91,19 +224,45 @@ impl AppState {
         id
     }
 
-    /// Remove an instrument and its piano roll track.
+    /// Add an instrument built from a factory preset
+    pub fn add_instrument_from_preset(&mut self, preset: &preset::Preset) -> Option<InstrumentId> {
+        let id = self.add_instrument(preset.source_type()?);
+        if let Some(inst) = self.instruments.instrument_mut(id) {
+            preset.apply_to(inst);
+        }
+        Some(id)
+    }
+
+    /// Remove an instrument, its piano roll track, and any MIDI channel routes to it.
     pub fn remove_instrument(&mut self, id: InstrumentId) {
         self.instruments.remove_instrument(id);
         self.session.piano_roll.remove_track(id);
+        self.session.midi_recording.clear_instrument_routes(id);
     }
 
-    /// Compute effective mute for an instrument, considering solo state and master mute.
-    pub fn effective_instrument_mute(&self, inst: &Instrument) -> bool {
-        if self.instruments.any_instrument_solo() {
-            !inst.solo
+    /// Move an instrument one slot up (earlier) or down, keeping the piano roll track
+    /// order and mixer selection in step. Ids don't change, so automation stays valid.
+    pub fn move_instrument(&mut self, id: InstrumentId, up: bool) -> bool {
+        let Some(from) = self.instruments.instruments.iter().position(|s| s.id == id) else {
+            return false;
+        };
+        let moved = if up {
+            self.instruments.move_up(id)
         } else {
-            inst.mute || self.session.master_mute
+            self.instruments.move_down(id)
+        };
+        if !moved {
+            return false;
         }
+        let to = if up { from - 1 } else { from + 1 };
+        let other = self.instruments.instruments[from].id;
+        self.session.piano_roll.swap_tracks(id, other);
+        self.session.mixer_selection = match self.session.mixer_selection {
+            MixerSelection::Instrument(idx) if idx == from => MixerSelection::Instrument(to),
+            MixerSelection::Instrument(idx) if idx == to => MixerSelection::Instrument(from),
+            selection => selection,
+        };
+        true
     }
 
     /// Collect mixer updates for all instruments (instrument_id, level, mute)
@@ -116,7 +275,7 @@ impl AppState {
                 (
                     s.id,
                     s.level * self.session.master_level,
-                    self.effective_instrument_mute(s),
+                    self.session.effective_instrument_mute(&self.instruments, s),
                 )
             })
             .collect()
@@ -186,3 +345,97 @@ impl AppState {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_move_instrument_syncs_tracks_and_mixer_selection() {
+        let mut state = AppState::new();
+        let id1 = state.add_instrument(SourceType::Saw);
+        let id2 = state.add_instrument(SourceType::Sin);
+        state.session.mixer_selection = MixerSelection::Instrument(0);
+
+        assert!(state.move_instrument(id1, false));
+        assert_eq!(state.session.piano_roll.track_order, vec![id2, id1]);
+        assert_eq!(state.session.mixer_selection, MixerSelection::Instrument(1));
+    }
+
+    #[test]
+    fn test_live_note_ticks_follow_resolution() {
+        let mut state = AppState::new();
+        state.session.piano_roll.ticks_per_beat = 480;
+        assert_eq!(state.live_note_ticks(), 240);
+        state.live_note_length = 2.0;
+        assert_eq!(state.live_note_ticks(), 960);
+        state.session.piano_roll.ticks_per_beat = 96;
+        assert_eq!(state.live_note_ticks(), 192);
+        state.live_note_length = 0.001;
+        assert_eq!(state.live_note_ticks(), 1);
+    }
+
+    #[test]
+    fn test_key_note_ticks_outlast_repeat_delay_when_holding() {
+        let mut state = AppState::new();
+        let pr = &mut state.session.piano_roll;
+        pr.ticks_per_beat = 480;
+        pr.bpm = 120.0;
+        assert_eq!(state.key_note_ticks(), 240);
+        state.live_note_hold = true;
+        // 700ms at 120 BPM is 1.4 beats
+        assert_eq!(state.key_note_ticks(), 672);
+        state.live_note_length = 2.0;
+        assert_eq!(state.key_note_ticks(), 960);
+        // 120ms
+        assert_eq!(state.key_release_ticks(), 116);
+    }
+
+    #[test]
+    fn test_record_tick_offsets_by_tempo_and_wraps_loop() {
+        let mut state = AppState::new();
+        let pr = &mut state.session.piano_roll;
+        pr.bpm = 120.0;
+        pr.ticks_per_beat = 480;
+        pr.playhead = 1000;
+        assert_eq!(state.record_tick(), 1000);
+
+        // 50 ms at 120 bpm is a tenth of a beat
+        state.record_offset_ms = 50.0;
+        assert_eq!(state.record_tick(), 952);
+        state.record_offset_ms = -50.0;
+        assert_eq!(state.record_tick(), 1048);
+
+        state.record_offset_ms = 50.0;
+        state.session.piano_roll.playhead = 10;
+        assert_eq!(state.record_tick(), 0);
+        let pr = &mut state.session.piano_roll;
+        pr.looping = true;
+        pr.loop_start = 0;
+        pr.loop_end = 1920;
+        assert_eq!(state.record_tick(), 1882);
+    }
+
+    #[test]
+    fn test_same_seed_randomizes_the_same() {
+        let mut state = AppState::new();
+        let id = state.add_instrument(SourceType::Saw);
+        let randomized = |state: &mut AppState| {
+            let rng = &mut state.rng;
+            let inst = state.instruments.instrument_mut(id).unwrap();
+            inst.randomize_params(rng);
+            inst.mutate_params(rng, 0.3);
+            inst.source_params.iter().map(|p| p.value_f32()).collect::<Vec<_>>()
+        };
+
+        state.set_rng_seed(1234);
+        let first = randomized(&mut state);
+        // The stream moves on within a session...
+        assert_ne!(randomized(&mut state), first);
+        // ...and starts over from the seed, as on reopening the project
+        state.set_rng_seed(state.session.rng_seed);
+        assert_eq!(randomized(&mut state), first);
+        state.set_rng_seed(4321);
+        assert_ne!(randomized(&mut state), first);
+    }
+}
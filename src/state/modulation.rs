@@ -0,0 +1,90 @@
+use super::instrument::{EnvConfig, LfoConfig, LfoTarget};
+
+/// Most LFOs an instrument can run
+pub const MAX_LFOS: usize = 4;
+
+/// Most modulation envelopes an instrument can run (the amp envelope is separate)
+pub const MAX_MOD_ENVELOPES: usize = 4;
+
+/// Lowest and highest routing amount; negative inverts the source
+pub const MOD_AMOUNT_RANGE: (f32, f32) = (-1.0, 1.0);
+
+/// Where a routing's signal comes from, as an index into the instrument's LFOs or
+/// modulation envelopes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ModRouteSource {
+    Lfo(usize),
+    Envelope(usize),
+}
+
+impl ModRouteSource {
+    pub fn name(&self) -> String {
+        match self {
+            ModRouteSource::Lfo(i) => format!("LFO {}", i + 1),
+            ModRouteSource::Envelope(i) => format!("Env {}", i + 1),
+        }
+    }
+}
+
+/// One row of the mod matrix: `source` scaled by `amount` drives `destination`
+#[derive(Debug, Clone, PartialEq)]
+pub struct ModRouting {
+    pub source: ModRouteSource,
+    pub destination: LfoTarget,
+    pub amount: f32,
+}
+
+impl ModRouting {
+    pub fn new(source: ModRouteSource, destination: LfoTarget) -> Self {
+        Self { source, destination, amount: 1.0 }
+    }
+}
+
+/// Whether a destination lives on each voice (and so can take per-note envelopes)
+/// rather than on the instrument's shared processing chain
+pub fn is_voice_destination(target: LfoTarget) -> bool {
+    matches!(target, LfoTarget::Pitch | LfoTarget::Amplitude)
+}
+
+/// Every source that exists on an instrument, LFOs first
+pub fn available_sources(lfos: &[LfoConfig], envelopes: &[EnvConfig]) -> Vec<ModRouteSource> {
+    (0..lfos.len())
+        .map(ModRouteSource::Lfo)
+        .chain((0..envelopes.len()).map(ModRouteSource::Envelope))
+        .collect()
+}
+
+/// Fix up routings after a source was removed: drop routings from it and shift the
+/// indices of later sources of the same kind down
+pub fn remove_source(routings: &mut Vec<ModRouting>, removed: ModRouteSource) {
+    routings.retain(|r| r.source != removed);
+    for routing in routings.iter_mut() {
+        routing.source = match (routing.source, removed) {
+            (ModRouteSource::Lfo(i), ModRouteSource::Lfo(r)) if i > r => ModRouteSource::Lfo(i - 1),
+            (ModRouteSource::Envelope(i), ModRouteSource::Envelope(r)) if i > r => ModRouteSource::Envelope(i - 1),
+            (source, _) => source,
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_remove_source_reindexes_routings() {
+        let mut routings = vec![
+            ModRouting::new(ModRouteSource::Lfo(0), LfoTarget::FilterCutoff),
+            ModRouting::new(ModRouteSource::Lfo(1), LfoTarget::Pitch),
+            ModRouting::new(ModRouteSource::Lfo(2), LfoTarget::Amplitude),
+            ModRouting::new(ModRouteSource::Envelope(1), LfoTarget::Pitch),
+        ];
+        remove_source(&mut routings, ModRouteSource::Lfo(1));
+        let sources: Vec<_> = routings.iter().map(|r| r.source).collect();
+        assert_eq!(
+            sources,
+            [ModRouteSource::Lfo(0), ModRouteSource::Lfo(1), ModRouteSource::Envelope(1)]
+        );
+        assert_eq!(routings[1].destination, LfoTarget::Amplitude);
+    }
+}
@@ -1,7 +1,9 @@
 #![allow(dead_code)]
 
+use serde::{Deserialize, Serialize};
+
 /// Musical key (pitch class)
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum Key {
     C, Cs, D, Ds, E, F, Fs, G, Gs, A, As, B,
 }
@@ -31,7 +33,7 @@ impl Key {
 }
 
 /// Scale definition as intervals from root
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum Scale {
     Major,
     Minor,
@@ -87,6 +89,46 @@ impl Scale {
     }
 }
 
+const SHARP_NAMES: [&str; 12] = ["C", "C#", "D", "D#", "E", "F", "F#", "G", "G#", "A", "A#", "B"];
+const FLAT_NAMES: [&str; 12] = ["C", "Db", "D", "Eb", "E", "F", "Gb", "G", "Ab", "A", "Bb", "B"];
+
+/// Note name with octave, spelled with sharps. Octaves follow scientific pitch
+/// notation: middle C (MIDI 60) is C4, so MIDI 0 is C-1 and A4 is MIDI 69.
+pub fn pitch_name(pitch: u8) -> String {
+    format!("{}{}", SHARP_NAMES[(pitch % 12) as usize], (pitch / 12) as i8 - 1)
+}
+
+/// Like `pitch_name`, but spelled with flats when the key signature has flats
+pub fn pitch_name_in_key(pitch: u8, key: Key, scale: Scale) -> String {
+    let names = if key.uses_flats(scale) { &FLAT_NAMES } else { &SHARP_NAMES };
+    format!("{}{}", names[(pitch % 12) as usize], (pitch / 12) as i8 - 1)
+}
+
+/// Frequency of a MIDI pitch in equal temperament, with A4 (MIDI 69) at `tuning_a4`
+pub fn pitch_freq(pitch: u8, tuning_a4: f32) -> f32 {
+    tuning_a4 * 2.0_f32.powf((pitch as f32 - 69.0) / 12.0)
+}
+
+impl Key {
+    /// Whether a key signature for this root and scale is written with flats. Modes
+    /// borrow the signature of their parent major key (D dorian reads like C major,
+    /// D minor like F major); F, Bb, Eb, Ab and Db majors use flats, the rest sharps.
+    /// Pentatonic counts as major and blues as minor.
+    pub fn uses_flats(&self, scale: Scale) -> bool {
+        let major_offset = match scale {
+            Scale::Major | Scale::Pentatonic | Scale::Chromatic => 0,
+            Scale::Dorian => 2,
+            Scale::Phrygian => 4,
+            Scale::Lydian => 5,
+            Scale::Mixolydian => 7,
+            Scale::Minor | Scale::Aeolian | Scale::Blues => 9,
+            Scale::Locrian => 11,
+        };
+        let parent_major = (self.semitone() - major_offset).rem_euclid(12);
+        matches!(parent_major, 1 | 3 | 5 | 8 | 10)
+    }
+}
+
 /// Snap a frequency to the nearest scale degree
 /// `tuning_a4` is the reference frequency for A4 (default 440.0)
 pub fn snap_freq_to_scale(freq: f32, key: Key, scale: Scale, tuning_a4: f32) -> f32 {
@@ -122,3 +164,104 @@ pub fn snap_freq_to_scale(freq: f32, key: Key, scale: Scale, tuning_a4: f32) ->
     // Convert back to frequency
     tuning_a4 * (2.0_f32).powf((best_note as f32 - 69.0) / 12.0)
 }
+
+/// Note length used for tempo-synced times, ordered shortest to longest
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NoteDivision {
+    ThirtySecond, SixteenthTriplet, Sixteenth, EighthTriplet, SixteenthDotted,
+    Eighth, QuarterTriplet, EighthDotted, Quarter, HalfTriplet, QuarterDotted,
+    Half, HalfDotted, Whole,
+}
+
+impl NoteDivision {
+    pub const ALL: [NoteDivision; 14] = [
+        NoteDivision::ThirtySecond, NoteDivision::SixteenthTriplet, NoteDivision::Sixteenth,
+        NoteDivision::EighthTriplet, NoteDivision::SixteenthDotted, NoteDivision::Eighth,
+        NoteDivision::QuarterTriplet, NoteDivision::EighthDotted, NoteDivision::Quarter,
+        NoteDivision::HalfTriplet, NoteDivision::QuarterDotted, NoteDivision::Half,
+        NoteDivision::HalfDotted, NoteDivision::Whole,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            NoteDivision::ThirtySecond => "1/32", NoteDivision::SixteenthTriplet => "1/16t",
+            NoteDivision::Sixteenth => "1/16", NoteDivision::EighthTriplet => "1/8t",
+            NoteDivision::SixteenthDotted => "1/16d", NoteDivision::Eighth => "1/8",
+            NoteDivision::QuarterTriplet => "1/4t", NoteDivision::EighthDotted => "1/8d",
+            NoteDivision::Quarter => "1/4", NoteDivision::HalfTriplet => "1/2t",
+            NoteDivision::QuarterDotted => "1/4d", NoteDivision::Half => "1/2",
+            NoteDivision::HalfDotted => "1/2d", NoteDivision::Whole => "1/1",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.iter().copied().find(|d| d.name() == name)
+    }
+
+    /// Length in quarter-note beats
+    pub fn beats(&self) -> f32 {
+        match self {
+            NoteDivision::ThirtySecond => 0.125,
+            NoteDivision::SixteenthTriplet => 1.0 / 6.0,
+            NoteDivision::Sixteenth => 0.25,
+            NoteDivision::EighthTriplet => 1.0 / 3.0,
+            NoteDivision::SixteenthDotted => 0.375,
+            NoteDivision::Eighth => 0.5,
+            NoteDivision::QuarterTriplet => 2.0 / 3.0,
+            NoteDivision::EighthDotted => 0.75,
+            NoteDivision::Quarter => 1.0,
+            NoteDivision::HalfTriplet => 4.0 / 3.0,
+            NoteDivision::QuarterDotted => 1.5,
+            NoteDivision::Half => 2.0,
+            NoteDivision::HalfDotted => 3.0,
+            NoteDivision::Whole => 4.0,
+        }
+    }
+
+    pub fn seconds(&self, bpm: f32) -> f32 {
+        self.beats() * 60.0 / bpm.max(1.0)
+    }
+
+    /// Division whose length at `bpm` is closest to `seconds`
+    pub fn nearest(seconds: f32, bpm: f32) -> Self {
+        Self::ALL
+            .iter()
+            .copied()
+            .min_by(|a, b| {
+                let da = (a.seconds(bpm) - seconds).abs();
+                let db = (b.seconds(bpm) - seconds).abs();
+                da.total_cmp(&db)
+            })
+            .unwrap_or(NoteDivision::Quarter)
+    }
+
+    /// Step `delta` divisions longer (positive) or shorter, stopping at the ends
+    pub fn step(&self, delta: i32) -> Self {
+        let idx = Self::ALL.iter().position(|d| d == self).unwrap_or(0) as i32;
+        Self::ALL[(idx + delta).clamp(0, Self::ALL.len() as i32 - 1) as usize]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pitch_names_follow_key_signature() {
+        assert_eq!(pitch_name(60), "C4");
+        assert_eq!(pitch_name(0), "C-1");
+        assert_eq!(pitch_name(70), "A#4");
+        assert_eq!(pitch_name_in_key(70, Key::F, Scale::Major), "Bb4");
+        assert_eq!(pitch_name_in_key(70, Key::D, Scale::Minor), "Bb4");
+        assert_eq!(pitch_name_in_key(70, Key::D, Scale::Dorian), "A#4");
+        assert_eq!(pitch_name_in_key(70, Key::G, Scale::Major), "A#4");
+        assert_eq!(pitch_name_in_key(61, Key::C, Scale::Phrygian), "Db4");
+    }
+
+    #[test]
+    fn test_pitch_freq() {
+        assert_eq!(pitch_freq(69, 440.0), 440.0);
+        assert!((pitch_freq(60, 440.0) - 261.626).abs() < 0.01);
+        assert_eq!(pitch_freq(81, 432.0), 864.0);
+    }
+}
@@ -0,0 +1,214 @@
+use std::time::{Duration, Instant};
+
+use super::music::NoteDivision;
+use super::InstrumentId;
+
+/// Rates the repeat selector cycles through
+pub const REPEAT_RATES: [NoteDivision; 6] = [
+    NoteDivision::Quarter,
+    NoteDivision::Eighth,
+    NoteDivision::EighthTriplet,
+    NoteDivision::Sixteenth,
+    NoteDivision::SixteenthTriplet,
+    NoteDivision::ThirtySecond,
+];
+
+/// Terminals only report key presses, so a held key is seen through its auto-repeat.
+/// The first auto-repeat arrives after the keyboard's repeat delay; repeats start once
+/// it proves the key is held, so a tap plays a single hit.
+pub const KEY_REPEAT_DELAY: Duration = Duration::from_millis(700);
+
+/// Longest gap between auto-repeats before the key counts as released
+pub const KEY_RELEASE_GAP: Duration = Duration::from_millis(120);
+
+/// What a held input retriggers
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RepeatTarget {
+    /// A note; `channel` is set for MIDI input, whose note-off ends the hold
+    Note { instrument_id: InstrumentId, pitch: u8, velocity: u8, channel: Option<u8> },
+    /// A kit pad played from the pad keyboard
+    Pad { instrument_id: InstrumentId, pad: usize },
+}
+
+impl RepeatTarget {
+    fn same_input(&self, other: &RepeatTarget) -> bool {
+        match (self, other) {
+            (
+                RepeatTarget::Note { instrument_id: a, pitch: p, channel: c, .. },
+                RepeatTarget::Note { instrument_id: b, pitch: q, channel: d, .. },
+            ) => a == b && p == q && c == d,
+            (a, b) => a == b,
+        }
+    }
+
+    fn is_key(&self) -> bool {
+        !matches!(self, RepeatTarget::Note { channel: Some(_), .. })
+    }
+}
+
+#[derive(Debug, Clone)]
+struct HeldInput {
+    target: RepeatTarget,
+    /// Last press or auto-repeat seen (keys only)
+    last_seen: Instant,
+    /// An auto-repeat arrived, so the key is known to be held
+    confirmed: bool,
+    next_hit: Instant,
+}
+
+/// Held-note repeat: inputs held while enabled retrigger every `division`
+#[derive(Debug, Clone)]
+pub struct NoteRepeatState {
+    pub enabled: bool,
+    pub division: NoteDivision,
+    held: Vec<HeldInput>,
+}
+
+impl Default for NoteRepeatState {
+    fn default() -> Self {
+        Self { enabled: false, division: NoteDivision::Sixteenth, held: Vec::new() }
+    }
+}
+
+impl NoteRepeatState {
+    pub fn toggle(&mut self) {
+        self.enabled = !self.enabled;
+        self.held.clear();
+    }
+
+    /// Step to the next rate in `REPEAT_RATES`, wrapping
+    pub fn cycle_rate(&mut self) {
+        let idx = REPEAT_RATES.iter().position(|d| *d == self.division).map_or(0, |i| i + 1);
+        self.division = REPEAT_RATES[idx % REPEAT_RATES.len()];
+    }
+
+    /// Time between repeats at `bpm`
+    pub fn interval(&self, bpm: f32) -> Duration {
+        Duration::from_secs_f32(self.division.seconds(bpm))
+    }
+
+    /// Register a press. True when it starts a new hold and should sound now; false when
+    /// it's the auto-repeat of a key already held, which the repeats replace.
+    pub fn press(&mut self, target: RepeatTarget, now: Instant, bpm: f32) -> bool {
+        if let Some(held) = self.held.iter_mut().find(|h| h.target.same_input(&target)) {
+            if target.is_key() {
+                held.last_seen = now;
+                held.confirmed = true;
+                return false;
+            }
+        }
+        let next_hit = now + self.interval(bpm);
+        self.held.retain(|h| !h.target.same_input(&target));
+        self.held.push(HeldInput { target, last_seen: now, confirmed: false, next_hit });
+        true
+    }
+
+    /// A MIDI note-off: stop its repeats at once
+    pub fn release_midi(&mut self, channel: u8, pitch: u8) {
+        self.held.retain(|h| {
+            !matches!(h.target, RepeatTarget::Note { pitch: p, channel: Some(c), .. } if p == pitch && c == channel)
+        });
+    }
+
+    /// Whether anything is held
+    pub fn is_active(&self) -> bool {
+        !self.held.is_empty()
+    }
+
+    /// Drop keys that stopped auto-repeating, returning the ones that had been repeating
+    /// so their last hit can be released
+    pub fn released_keys(&mut self, now: Instant) -> Vec<RepeatTarget> {
+        let mut released = Vec::new();
+        self.held.retain(|h| {
+            if !h.target.is_key() {
+                return true;
+            }
+            let timeout = if h.confirmed { KEY_RELEASE_GAP } else { KEY_REPEAT_DELAY };
+            let held = now.duration_since(h.last_seen) <= timeout;
+            if !held && h.confirmed {
+                released.push(h.target);
+            }
+            held
+        });
+        released
+    }
+
+    /// Return the targets due to retrigger, scheduling each one's next hit. Call after
+    /// `released_keys`. Hits missed while a key waited for its first auto-repeat are skipped.
+    pub fn due(&mut self, now: Instant, bpm: f32) -> Vec<RepeatTarget> {
+        let interval = self.interval(bpm);
+        let mut due = Vec::new();
+        for held in &mut self.held {
+            if (held.target.is_key() && !held.confirmed) || now < held.next_hit {
+                continue;
+            }
+            due.push(held.target);
+            while held.next_hit <= now {
+                held.next_hit += interval;
+            }
+        }
+        due
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KEY: RepeatTarget = RepeatTarget::Pad { instrument_id: 1, pad: 0 };
+
+    #[test]
+    fn test_held_key_repeats_until_released() {
+        let mut repeat = NoteRepeatState { enabled: true, ..Default::default() };
+        let start = Instant::now();
+        let at = |ms: u64| start + Duration::from_millis(ms);
+        // 1/16 at 120 BPM is 125ms
+        assert!(repeat.press(KEY, start, 120.0));
+        // Waiting for the first auto-repeat: no repeats yet
+        assert!(repeat.released_keys(at(200)).is_empty());
+        assert!(repeat.due(at(200), 120.0).is_empty());
+        assert!(!repeat.press(KEY, at(500), 120.0));
+        assert_eq!(repeat.due(at(510), 120.0), [KEY]);
+        assert!(repeat.due(at(560), 120.0).is_empty());
+        assert!(!repeat.press(KEY, at(580), 120.0));
+        assert_eq!(repeat.due(at(640), 120.0), [KEY]);
+        // Auto-repeats stop: released within the gap, no more hits
+        assert_eq!(repeat.released_keys(at(580 + 200)), [KEY]);
+        assert!(repeat.due(at(580 + 200), 120.0).is_empty());
+        assert!(!repeat.is_active());
+    }
+
+    #[test]
+    fn test_tap_plays_once() {
+        let mut repeat = NoteRepeatState { enabled: true, ..Default::default() };
+        let start = Instant::now();
+        assert!(repeat.press(KEY, start, 120.0));
+        for ms in (0..1000).step_by(16) {
+            let now = start + Duration::from_millis(ms);
+            assert!(repeat.released_keys(now).is_empty());
+            assert!(repeat.due(now, 120.0).is_empty());
+        }
+        assert!(!repeat.is_active());
+    }
+
+    #[test]
+    fn test_midi_note_repeats_until_note_off() {
+        let mut repeat = NoteRepeatState { enabled: true, division: NoteDivision::Eighth, ..Default::default() };
+        let note = RepeatTarget::Note { instrument_id: 2, pitch: 42, velocity: 90, channel: Some(9) };
+        let start = Instant::now();
+        assert!(repeat.press(note, start, 120.0));
+        assert!(repeat.due(start + Duration::from_millis(100), 120.0).is_empty());
+        assert_eq!(repeat.due(start + Duration::from_millis(250), 120.0), [note]);
+        repeat.release_midi(9, 42);
+        assert!(repeat.due(start + Duration::from_millis(500), 120.0).is_empty());
+    }
+
+    #[test]
+    fn test_cycle_rate_wraps() {
+        let mut repeat = NoteRepeatState::default();
+        for _ in 0..REPEAT_RATES.len() {
+            repeat.cycle_rate();
+        }
+        assert_eq!(repeat.division, NoteDivision::Sixteenth);
+    }
+}
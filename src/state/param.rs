@@ -1,3 +1,5 @@
+use super::rng::Rng;
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct Param {
     pub name: String,
@@ -12,3 +14,123 @@ pub enum ParamValue {
     Int(i32),
     Bool(bool),
 }
+
+impl Param {
+    /// Current value as the float sent to SuperCollider
+    pub fn value_f32(&self) -> f32 {
+        match self.value {
+            ParamValue::Float(v) => v,
+            ParamValue::Int(v) => v as f32,
+            ParamValue::Bool(v) => if v { 1.0 } else { 0.0 },
+        }
+    }
+
+    /// Parse typed entry for this param, keeping its type and clamping to `min..max`.
+    /// The error names the rejected text.
+    pub fn parse_value(&self, text: &str) -> Result<ParamValue, String> {
+        let text = text.trim();
+        let parsed = match self.value {
+            ParamValue::Float(_) => text
+                .parse::<f32>()
+                .ok()
+                .filter(|v| v.is_finite())
+                .map(|v| ParamValue::Float(v.clamp(self.min, self.max))),
+            ParamValue::Int(_) => text
+                .parse::<i32>()
+                .ok()
+                .map(|v| ParamValue::Int(v.clamp(self.min as i32, self.max as i32))),
+            ParamValue::Bool(_) => match text.to_lowercase().as_str() {
+                "true" | "on" | "yes" | "1" => Some(ParamValue::Bool(true)),
+                "false" | "off" | "no" | "0" => Some(ParamValue::Bool(false)),
+                _ => None,
+            },
+        };
+        parsed.ok_or_else(|| match self.value {
+            ParamValue::Float(_) => format!("Not a number: {}", text),
+            ParamValue::Int(_) => format!("Not a whole number: {}", text),
+            ParamValue::Bool(_) => format!("Expected on or off: {}", text),
+        })
+    }
+
+    /// Set from a float, keeping the value's type and clamping to `min..max`;
+    /// bools are on above one half
+    pub fn set_f32(&mut self, value: f32) {
+        let value = value.clamp(self.min, self.max);
+        self.value = match self.value {
+            ParamValue::Float(_) => ParamValue::Float(value),
+            ParamValue::Int(_) => ParamValue::Int(value.round() as i32),
+            ParamValue::Bool(_) => ParamValue::Bool(value > 0.5),
+        };
+    }
+
+    /// Assign a random value within `min..max`, respecting the value's type
+    pub fn randomize(&mut self, rng: &mut Rng) {
+        match &mut self.value {
+            ParamValue::Float(v) => *v = rng.range(self.min, self.max),
+            ParamValue::Int(v) => *v = rng.range(self.min, self.max + 1.0).floor().min(self.max) as i32,
+            ParamValue::Bool(v) => *v = rng.next_bool(),
+        }
+    }
+
+    /// Nudge the value by up to `amount` (fraction of the range) in either direction.
+    /// Ints move by at least one step; bools flip with probability `amount`.
+    pub fn mutate(&mut self, rng: &mut Rng, amount: f32) {
+        let span = (self.max - self.min) * amount;
+        match &mut self.value {
+            ParamValue::Float(v) => *v = (*v + rng.range(-span, span)).clamp(self.min, self.max),
+            ParamValue::Int(v) => {
+                let step = rng.range(-span, span).round().clamp(-span.max(1.0), span.max(1.0)) as i32;
+                let step = if step == 0 { if rng.next_bool() { 1 } else { -1 } } else { step };
+                *v = (*v + step).clamp(self.min as i32, self.max as i32);
+            }
+            ParamValue::Bool(v) => {
+                if rng.next_f32() < amount {
+                    *v = !*v;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_value_keeps_type_and_clamps() {
+        let float_param = Param { name: "amp".into(), value: ParamValue::Float(0.5), min: 0.0, max: 1.0 };
+        assert_eq!(float_param.parse_value(" 0.25"), Ok(ParamValue::Float(0.25)));
+        assert_eq!(float_param.parse_value("3"), Ok(ParamValue::Float(1.0)));
+        assert!(float_param.parse_value("NaN").is_err());
+
+        let int_param = Param { name: "channel".into(), value: ParamValue::Int(0), min: 0.0, max: 7.0 };
+        assert_eq!(int_param.parse_value("9"), Ok(ParamValue::Int(7)));
+        assert_eq!(int_param.parse_value("2.5"), Err("Not a whole number: 2.5".to_string()));
+
+        let bool_param = Param { name: "loop".into(), value: ParamValue::Bool(false), min: 0.0, max: 1.0 };
+        assert_eq!(bool_param.parse_value("On"), Ok(ParamValue::Bool(true)));
+        assert!(bool_param.parse_value("maybe").is_err());
+    }
+
+    #[test]
+    fn test_randomize_respects_type_and_range() {
+        let mut rng = Rng::new(7);
+        let mut int_param = Param { name: "channel".into(), value: ParamValue::Int(0), min: 0.0, max: 7.0 };
+        let mut float_param = Param { name: "amp".into(), value: ParamValue::Float(0.5), min: 0.0, max: 1.0 };
+        let mut seen_max = false;
+        for _ in 0..200 {
+            int_param.randomize(&mut rng);
+            float_param.randomize(&mut rng);
+            let ParamValue::Int(i) = int_param.value else { panic!("type changed") };
+            assert!((0..=7).contains(&i));
+            seen_max |= i == 7;
+            let ParamValue::Float(f) = float_param.value else { panic!("type changed") };
+            assert!((0.0..=1.0).contains(&f));
+        }
+        assert!(seen_max);
+
+        int_param.value = ParamValue::Int(3);
+        int_param.mutate(&mut rng, 0.05);
+        assert!(matches!(int_param.value, ParamValue::Int(2) | ParamValue::Int(4)));
+    }
+}
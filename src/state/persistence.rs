@@ -3,12 +3,18 @@ use std::path::{Path, PathBuf};
 use rusqlite::{Connection as SqlConnection, Result as SqlResult};
 
 use super::custom_synthdef::{CustomSynthDef, CustomSynthDefRegistry, ParamSpec};
-use super::music::{Key, Scale};
+use super::groove::{GrooveStep, GrooveTemplate};
+use super::music::{Key, NoteDivision, Scale};
 use super::param::{Param, ParamValue};
 use super::piano_roll::PianoRollState;
-use super::session::{SessionState, MAX_BUSES};
+use super::session::{MixerSelection, SessionState, SoloMode, MAX_BUSES};
+use super::ui_state::{
+    clamp_mixer_selection, mixer_selection_key, parse_mixer_selection, PianoRollView, UiState, MAX_PIANO_ZOOM,
+    MIN_PIANO_ZOOM,
+};
 use super::instrument::*;
 use super::instrument_state::InstrumentState;
+use super::modulation::{ModRouteSource, ModRouting};
 
 // --- AutomationTarget serialization helpers ---
 
@@ -22,6 +28,9 @@ fn serialize_automation_target(
         super::automation::AutomationTarget::InstrumentPan(id) => {
             ("instrument_pan", *id, None, None)
         }
+        super::automation::AutomationTarget::InstrumentTrim(id) => {
+            ("instrument_trim", *id, None, None)
+        }
         super::automation::AutomationTarget::FilterCutoff(id) => {
             ("filter_cutoff", *id, None, None)
         }
@@ -46,6 +55,7 @@ fn deserialize_automation_target(
     match target_type {
         "instrument_level" => Some(AutomationTarget::InstrumentLevel(instrument_id)),
         "instrument_pan" => Some(AutomationTarget::InstrumentPan(instrument_id)),
+        "instrument_trim" => Some(AutomationTarget::InstrumentTrim(instrument_id)),
         "filter_cutoff" => Some(AutomationTarget::FilterCutoff(instrument_id)),
         "filter_resonance" => Some(AutomationTarget::FilterResonance(instrument_id)),
         "effect_param" => {
@@ -59,9 +69,99 @@ fn deserialize_automation_target(
     }
 }
 
+/// Columns added after a table's original schema: (table, column, definition).
+/// `CREATE TABLE IF NOT EXISTS` leaves tables from older files untouched, so these
+/// are backfilled with `ALTER TABLE` before anything reads or writes them.
+const ADDED_COLUMNS: &[(&str, &str, &str)] = &[
+    ("midi_recording_settings", "program_change_mode", "TEXT NOT NULL DEFAULT 'off'"),
+    ("midi_recording_settings", "record_quantize", "INTEGER"),
+    ("midi_recording_settings", "record_automation", "INTEGER NOT NULL DEFAULT 0"),
+    ("midi_recording_settings", "thru_enabled", "INTEGER NOT NULL DEFAULT 0"),
+    ("midi_recording_settings", "thru_channel", "INTEGER"),
+    ("midi_recording_settings", "thru_velocity_scale", "REAL NOT NULL DEFAULT 1"),
+    ("instruments", "color", "TEXT"),
+    ("instruments", "trim", "REAL NOT NULL DEFAULT 0"),
+    ("chopper_states", "original_path", "TEXT"),
+    ("drum_pads", "reverse", "INTEGER NOT NULL DEFAULT 0"),
+    ("sampler_configs", "reverse", "INTEGER NOT NULL DEFAULT 0"),
+    ("sampler_configs", "layer_zones", "INTEGER NOT NULL DEFAULT 0"),
+    ("sampler_slices", "key_low", "INTEGER NOT NULL DEFAULT 0"),
+    ("sampler_slices", "key_high", "INTEGER NOT NULL DEFAULT 127"),
+    ("drum_pads", "crossfade", "INTEGER NOT NULL DEFAULT 0"),
+    ("drum_steps", "nudge", "REAL NOT NULL DEFAULT 0"),
+    ("drum_steps", "groove_velocity", "REAL NOT NULL DEFAULT 1"),
+    ("sampler_configs", "crossfade_layers", "INTEGER NOT NULL DEFAULT 0"),
+    ("instrument_effects", "sync", "INTEGER NOT NULL DEFAULT 0"),
+    ("instrument_effects", "division", "TEXT"),
+    ("instruments", "lfo_sync", "INTEGER NOT NULL DEFAULT 0"),
+    ("instruments", "lfo_division", "TEXT"),
+    ("instruments", "lfo_retrigger", "INTEGER NOT NULL DEFAULT 0"),
+    ("instruments", "filter_keytrack", "REAL NOT NULL DEFAULT 0"),
+    ("instruments", "glide", "REAL NOT NULL DEFAULT 0"),
+    ("instruments", "unison_voices", "INTEGER NOT NULL DEFAULT 1"),
+    ("instruments", "detune", "REAL NOT NULL DEFAULT 0"),
+    ("instruments", "voice_spread", "REAL NOT NULL DEFAULT 0"),
+    ("instruments", "voice_spread_mode", "TEXT NOT NULL DEFAULT 'pitch'"),
+    ("drum_patterns", "follow_target", "INTEGER"),
+    ("drum_patterns", "follow_repeats", "INTEGER NOT NULL DEFAULT 1"),
+    ("session", "automation_freeze_division", "INTEGER"),
+    ("mixer_buses", "output_channel", "INTEGER NOT NULL DEFAULT 0"),
+    ("instruments", "key_low", "INTEGER NOT NULL DEFAULT 0"),
+    ("musical_settings", "transpose", "INTEGER NOT NULL DEFAULT 0"),
+    ("instruments", "key_high", "INTEGER NOT NULL DEFAULT 127"),
+    ("mixer_master", "output_channel", "INTEGER NOT NULL DEFAULT 0"),
+    ("mixer_master", "solo_mode", "TEXT NOT NULL DEFAULT 'in_place'"),
+    ("session", "synthdef_dir", "TEXT"),
+    ("session", "rng_seed", "INTEGER"),
+    ("sampler_configs", "path", "TEXT"),
+    ("sampler_configs", "original_path", "TEXT"),
+    ("instrument_modulations", "position", "INTEGER"),
+    ("instrument_modulations", "enabled", "INTEGER"),
+    ("instrument_modulations", "lfo_shape", "TEXT"),
+    ("instrument_modulations", "lfo_sync", "INTEGER"),
+    ("instrument_modulations", "lfo_division", "TEXT"),
+    ("instrument_modulations", "lfo_retrigger", "INTEGER"),
+    ("instrument_modulations", "source_index", "INTEGER"),
+    ("instrument_modulations", "destination", "TEXT"),
+    ("instrument_modulations", "amount", "REAL"),
+    ("piano_roll_notes", "groove_velocity", "REAL NOT NULL DEFAULT 1"),
+    ("piano_roll_clip_notes", "groove_velocity", "REAL NOT NULL DEFAULT 1"),
+    ("sampler_configs", "legacy_mapping", "INTEGER NOT NULL DEFAULT 0"),
+];
+
+/// Backfill columns missing from tables created by older versions
+fn migrate_columns(conn: &SqlConnection) -> SqlResult<()> {
+    let table_columns = |table: &str| -> SqlResult<Vec<String>> {
+        Ok(conn
+            .prepare(&format!("PRAGMA table_info({})", table))?
+            .query_map([], |row| row.get::<_, String>(1))?
+            .flatten()
+            .collect())
+    };
+    // Samplers saved before keyboard zones keep their old slice mapping
+    let slice_columns = table_columns("sampler_slices")?;
+    let pre_zone = !slice_columns.is_empty() && !slice_columns.iter().any(|c| c == "key_low");
+
+    for (table, column, definition) in ADDED_COLUMNS {
+        let columns = table_columns(table)?;
+        // Empty means the table doesn't exist yet; it will be created with the column
+        if !columns.is_empty() && !columns.iter().any(|c| c == column) {
+            conn.execute(
+                &format!("ALTER TABLE {} ADD COLUMN {} {}", table, column, definition),
+                [],
+            )?;
+        }
+    }
+    if pre_zone {
+        conn.execute("UPDATE sampler_configs SET legacy_mapping = 1", [])?;
+    }
+    Ok(())
+}
+
 /// Save to SQLite
 pub fn save_project(path: &Path, session: &SessionState, instruments: &InstrumentState) -> SqlResult<()> {
     let conn = SqlConnection::open(path)?;
+    migrate_columns(&conn)?;
 
     conn.execute_batch(
         "
@@ -77,7 +177,10 @@ pub fn save_project(path: &Path, session: &SessionState, instruments: &Instrumen
                 modified_at TEXT NOT NULL,
                 next_instrument_id INTEGER NOT NULL,
                 selected_instrument INTEGER,
-                selected_automation_lane INTEGER
+                selected_automation_lane INTEGER,
+                automation_freeze_division INTEGER,
+                synthdef_dir TEXT,
+                rng_seed INTEGER
             );
 
             CREATE TABLE IF NOT EXISTS instruments (
@@ -88,11 +191,15 @@ pub fn save_project(path: &Path, session: &SessionState, instruments: &Instrumen
                 filter_type TEXT,
                 filter_cutoff REAL,
                 filter_resonance REAL,
+                filter_keytrack REAL NOT NULL DEFAULT 0,
                 lfo_enabled INTEGER NOT NULL DEFAULT 0,
                 lfo_rate REAL NOT NULL DEFAULT 2.0,
                 lfo_depth REAL NOT NULL DEFAULT 0.5,
                 lfo_shape TEXT NOT NULL DEFAULT 'sine',
                 lfo_target TEXT NOT NULL DEFAULT 'filter',
+                lfo_sync INTEGER NOT NULL DEFAULT 0,
+                lfo_division TEXT,
+                lfo_retrigger INTEGER NOT NULL DEFAULT 0,
                 amp_attack REAL NOT NULL,
                 amp_decay REAL NOT NULL,
                 amp_sustain REAL NOT NULL,
@@ -103,7 +210,16 @@ pub fn save_project(path: &Path, session: &SessionState, instruments: &Instrumen
                 mute INTEGER NOT NULL,
                 solo INTEGER NOT NULL,
                 active INTEGER NOT NULL DEFAULT 1,
-                output_target TEXT NOT NULL
+                output_target TEXT NOT NULL,
+                color TEXT,
+                trim REAL NOT NULL DEFAULT 0,
+                glide REAL NOT NULL DEFAULT 0,
+                unison_voices INTEGER NOT NULL DEFAULT 1,
+                detune REAL NOT NULL DEFAULT 0,
+                voice_spread REAL NOT NULL DEFAULT 0,
+                voice_spread_mode TEXT NOT NULL DEFAULT 'pitch',
+                key_low INTEGER NOT NULL DEFAULT 0,
+                key_high INTEGER NOT NULL DEFAULT 127
             );
 
             CREATE TABLE IF NOT EXISTS instrument_source_params (
@@ -121,6 +237,8 @@ pub fn save_project(path: &Path, session: &SessionState, instruments: &Instrumen
                 position INTEGER NOT NULL,
                 effect_type TEXT NOT NULL,
                 enabled INTEGER NOT NULL,
+                sync INTEGER NOT NULL DEFAULT 0,
+                division TEXT,
                 PRIMARY KEY (instrument_id, position)
             );
 
@@ -132,6 +250,24 @@ pub fn save_project(path: &Path, session: &SessionState, instruments: &Instrumen
                 PRIMARY KEY (instrument_id, effect_position, param_name)
             );
 
+            CREATE TABLE IF NOT EXISTS bus_effects (
+                bus_id INTEGER NOT NULL,
+                position INTEGER NOT NULL,
+                effect_type TEXT NOT NULL,
+                enabled INTEGER NOT NULL,
+                sync INTEGER NOT NULL DEFAULT 0,
+                division TEXT,
+                PRIMARY KEY (bus_id, position)
+            );
+
+            CREATE TABLE IF NOT EXISTS bus_effect_params (
+                bus_id INTEGER NOT NULL,
+                effect_position INTEGER NOT NULL,
+                param_name TEXT NOT NULL,
+                param_value REAL NOT NULL,
+                PRIMARY KEY (bus_id, effect_position, param_name)
+            );
+
             CREATE TABLE IF NOT EXISTS instrument_sends (
                 instrument_id INTEGER NOT NULL,
                 bus_id INTEGER NOT NULL,
@@ -152,6 +288,15 @@ pub fn save_project(path: &Path, session: &SessionState, instruments: &Instrumen
                 env_release REAL,
                 source_instrument_id INTEGER,
                 source_param_name TEXT,
+                position INTEGER,
+                enabled INTEGER,
+                lfo_shape TEXT,
+                lfo_sync INTEGER,
+                lfo_division TEXT,
+                lfo_retrigger INTEGER,
+                source_index INTEGER,
+                destination TEXT,
+                amount REAL,
                 PRIMARY KEY (instrument_id, target_param)
             );
 
@@ -161,13 +306,26 @@ pub fn save_project(path: &Path, session: &SessionState, instruments: &Instrumen
                 level REAL NOT NULL,
                 pan REAL NOT NULL,
                 mute INTEGER NOT NULL,
-                solo INTEGER NOT NULL
+                solo INTEGER NOT NULL,
+                output_channel INTEGER NOT NULL DEFAULT 0
             );
 
             CREATE TABLE IF NOT EXISTS mixer_master (
                 id INTEGER PRIMARY KEY CHECK (id = 1),
                 level REAL NOT NULL,
-                mute INTEGER NOT NULL
+                mute INTEGER NOT NULL,
+                output_channel INTEGER NOT NULL DEFAULT 0,
+                solo_mode TEXT NOT NULL DEFAULT 'in_place'
+            );
+
+            CREATE TABLE IF NOT EXISTS ui_state (
+                id INTEGER PRIMARY KEY CHECK (id = 1),
+                active_pane TEXT,
+                mixer_selection TEXT NOT NULL,
+                piano_cursor_tick INTEGER NOT NULL,
+                piano_cursor_pitch INTEGER NOT NULL,
+                piano_track INTEGER NOT NULL,
+                piano_zoom INTEGER NOT NULL
             );
 
             CREATE TABLE IF NOT EXISTS piano_roll_tracks (
@@ -182,7 +340,48 @@ pub fn save_project(path: &Path, session: &SessionState, instruments: &Instrumen
                 tick INTEGER NOT NULL,
                 duration INTEGER NOT NULL,
                 pitch INTEGER NOT NULL,
-                velocity INTEGER NOT NULL
+                velocity INTEGER NOT NULL,
+                groove_velocity REAL NOT NULL DEFAULT 1
+            );
+
+            CREATE TABLE IF NOT EXISTS piano_roll_clips (
+                track_instrument_id INTEGER NOT NULL,
+                position INTEGER NOT NULL,
+                name TEXT NOT NULL,
+                start_tick INTEGER NOT NULL,
+                length INTEGER NOT NULL,
+                loops INTEGER NOT NULL,
+                PRIMARY KEY (track_instrument_id, position)
+            );
+
+            CREATE TABLE IF NOT EXISTS piano_roll_clip_notes (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                track_instrument_id INTEGER NOT NULL,
+                clip_position INTEGER NOT NULL,
+                tick INTEGER NOT NULL,
+                duration INTEGER NOT NULL,
+                pitch INTEGER NOT NULL,
+                velocity INTEGER NOT NULL,
+                groove_velocity REAL NOT NULL DEFAULT 1
+            );
+
+            CREATE TABLE IF NOT EXISTS markers (
+                tick INTEGER PRIMARY KEY,
+                name TEXT NOT NULL
+            );
+
+            CREATE TABLE IF NOT EXISTS grooves (
+                position INTEGER PRIMARY KEY,
+                name TEXT NOT NULL,
+                selected INTEGER NOT NULL DEFAULT 0
+            );
+
+            CREATE TABLE IF NOT EXISTS groove_steps (
+                groove INTEGER NOT NULL,
+                step INTEGER NOT NULL,
+                timing REAL NOT NULL,
+                velocity REAL NOT NULL,
+                PRIMARY KEY (groove, step)
             );
 
             CREATE TABLE IF NOT EXISTS musical_settings (
@@ -197,7 +396,8 @@ pub fn save_project(path: &Path, session: &SessionState, instruments: &Instrumen
                 key TEXT NOT NULL DEFAULT 'C',
                 scale TEXT NOT NULL DEFAULT 'Major',
                 tuning_a4 REAL NOT NULL DEFAULT 440.0,
-                snap INTEGER NOT NULL DEFAULT 0
+                snap INTEGER NOT NULL DEFAULT 0,
+                transpose INTEGER NOT NULL DEFAULT 0
             );
 
             CREATE TABLE IF NOT EXISTS sampler_configs (
@@ -206,7 +406,13 @@ pub fn save_project(path: &Path, session: &SessionState, instruments: &Instrumen
                 loop_mode INTEGER NOT NULL,
                 pitch_tracking INTEGER NOT NULL,
                 next_slice_id INTEGER NOT NULL,
-                selected_slice INTEGER NOT NULL DEFAULT 0
+                selected_slice INTEGER NOT NULL DEFAULT 0,
+                reverse INTEGER NOT NULL DEFAULT 0,
+                layer_zones INTEGER NOT NULL DEFAULT 0,
+                crossfade_layers INTEGER NOT NULL DEFAULT 0,
+                path TEXT,
+                original_path TEXT,
+                legacy_mapping INTEGER NOT NULL DEFAULT 0
             );
 
             CREATE TABLE IF NOT EXISTS sampler_slices (
@@ -217,9 +423,32 @@ pub fn save_project(path: &Path, session: &SessionState, instruments: &Instrumen
                 end_pos REAL NOT NULL,
                 name TEXT NOT NULL,
                 root_note INTEGER NOT NULL,
+                key_low INTEGER NOT NULL DEFAULT 0,
+                key_high INTEGER NOT NULL DEFAULT 127,
                 PRIMARY KEY (instrument_id, slice_id)
             );
 
+            CREATE TABLE IF NOT EXISTS velocity_layers (
+                instrument_id INTEGER NOT NULL,
+                owner TEXT NOT NULL,
+                owner_index INTEGER NOT NULL,
+                position INTEGER NOT NULL,
+                buffer_id INTEGER NOT NULL,
+                path TEXT NOT NULL,
+                vel_low INTEGER NOT NULL,
+                vel_high INTEGER NOT NULL,
+                PRIMARY KEY (instrument_id, owner, owner_index, position)
+            );
+
+            CREATE TABLE IF NOT EXISTS round_robin_samples (
+                instrument_id INTEGER NOT NULL,
+                pad_index INTEGER NOT NULL,
+                position INTEGER NOT NULL,
+                buffer_id INTEGER NOT NULL,
+                path TEXT NOT NULL,
+                PRIMARY KEY (instrument_id, pad_index, position)
+            );
+
             CREATE TABLE IF NOT EXISTS automation_lanes (
                 id INTEGER PRIMARY KEY,
                 target_type TEXT NOT NULL,
@@ -239,6 +468,13 @@ pub fn save_project(path: &Path, session: &SessionState, instruments: &Instrumen
                 PRIMARY KEY (lane_id, tick)
             );
 
+            CREATE TABLE IF NOT EXISTS automation_frozen_points (
+                lane_id INTEGER NOT NULL,
+                tick INTEGER NOT NULL,
+                value REAL NOT NULL,
+                PRIMARY KEY (lane_id, tick)
+            );
+
             CREATE TABLE IF NOT EXISTS custom_synthdefs (
                 id INTEGER PRIMARY KEY,
                 name TEXT NOT NULL,
@@ -264,6 +500,8 @@ pub fn save_project(path: &Path, session: &SessionState, instruments: &Instrumen
                 path TEXT,
                 name TEXT NOT NULL DEFAULT '',
                 level REAL NOT NULL DEFAULT 0.8,
+                reverse INTEGER NOT NULL DEFAULT 0,
+                crossfade INTEGER NOT NULL DEFAULT 0,
                 PRIMARY KEY (instrument_id, pad_index)
             );
 
@@ -271,6 +509,8 @@ pub fn save_project(path: &Path, session: &SessionState, instruments: &Instrumen
                 instrument_id INTEGER NOT NULL,
                 pattern_index INTEGER NOT NULL,
                 length INTEGER NOT NULL DEFAULT 16,
+                follow_target INTEGER,
+                follow_repeats INTEGER NOT NULL DEFAULT 1,
                 PRIMARY KEY (instrument_id, pattern_index)
             );
 
@@ -280,9 +520,21 @@ pub fn save_project(path: &Path, session: &SessionState, instruments: &Instrumen
                 pad_index INTEGER NOT NULL,
                 step_index INTEGER NOT NULL,
                 velocity INTEGER NOT NULL DEFAULT 100,
+                nudge REAL NOT NULL DEFAULT 0,
+                groove_velocity REAL NOT NULL DEFAULT 1,
                 PRIMARY KEY (instrument_id, pattern_index, pad_index, step_index)
             );
 
+            CREATE TABLE IF NOT EXISTS drum_step_locks (
+                instrument_id INTEGER NOT NULL,
+                pattern_index INTEGER NOT NULL,
+                pad_index INTEGER NOT NULL,
+                step_index INTEGER NOT NULL,
+                param TEXT NOT NULL,
+                value REAL NOT NULL,
+                PRIMARY KEY (instrument_id, pattern_index, pad_index, step_index, param)
+            );
+
             CREATE TABLE IF NOT EXISTS chopper_states (
                 instrument_id INTEGER PRIMARY KEY,
                 buffer_id INTEGER,
@@ -290,7 +542,8 @@ pub fn save_project(path: &Path, session: &SessionState, instruments: &Instrumen
                 name TEXT NOT NULL,
                 selected_slice INTEGER NOT NULL,
                 next_slice_id INTEGER NOT NULL,
-                duration_secs REAL NOT NULL
+                duration_secs REAL NOT NULL,
+                original_path TEXT
             );
 
             CREATE TABLE IF NOT EXISTS chopper_slices (
@@ -308,7 +561,13 @@ pub fn save_project(path: &Path, session: &SessionState, instruments: &Instrumen
                 id INTEGER PRIMARY KEY CHECK (id = 1),
                 live_input_instrument INTEGER,
                 note_passthrough INTEGER NOT NULL,
-                channel_filter INTEGER
+                channel_filter INTEGER,
+                program_change_mode TEXT NOT NULL DEFAULT 'off',
+                record_quantize INTEGER,
+                record_automation INTEGER NOT NULL DEFAULT 0,
+                thru_enabled INTEGER NOT NULL DEFAULT 0,
+                thru_channel INTEGER,
+                thru_velocity_scale REAL NOT NULL DEFAULT 1
             );
 
             CREATE TABLE IF NOT EXISTS midi_cc_mappings (
@@ -334,21 +593,36 @@ pub fn save_project(path: &Path, session: &SessionState, instruments: &Instrumen
                 sensitivity REAL NOT NULL
             );
 
+            CREATE TABLE IF NOT EXISTS midi_channel_routes (
+                channel INTEGER PRIMARY KEY,
+                instrument_id INTEGER NOT NULL
+            );
+
             -- Clear existing data
+            DELETE FROM midi_channel_routes;
             DELETE FROM midi_pitch_bend_configs;
             DELETE FROM midi_cc_mappings;
             DELETE FROM midi_recording_settings;
             DELETE FROM chopper_slices;
             DELETE FROM chopper_states;
+            DELETE FROM drum_step_locks;
             DELETE FROM drum_steps;
             DELETE FROM drum_patterns;
             DELETE FROM drum_pads;
             DELETE FROM custom_synthdef_params;
             DELETE FROM custom_synthdefs;
+            DELETE FROM automation_frozen_points;
             DELETE FROM automation_points;
             DELETE FROM automation_lanes;
+            DELETE FROM velocity_layers;
+            DELETE FROM round_robin_samples;
             DELETE FROM sampler_slices;
             DELETE FROM sampler_configs;
+            DELETE FROM markers;
+            DELETE FROM grooves;
+            DELETE FROM groove_steps;
+            DELETE FROM piano_roll_clip_notes;
+            DELETE FROM piano_roll_clips;
             DELETE FROM piano_roll_notes;
             DELETE FROM piano_roll_tracks;
             DELETE FROM musical_settings;
@@ -358,24 +632,33 @@ pub fn save_project(path: &Path, session: &SessionState, instruments: &Instrumen
             DELETE FROM instrument_effects;
             DELETE FROM instrument_source_params;
             DELETE FROM instruments;
+            DELETE FROM bus_effect_params;
+            DELETE FROM bus_effects;
             DELETE FROM mixer_buses;
             DELETE FROM mixer_master;
+            DELETE FROM ui_state;
             DELETE FROM session;
             ",
     )?;
 
+    // The *_at timestamp columns are left empty so that saving the same state twice
+    // writes identical bytes; the file's own mtime records when it was saved
     conn.execute(
-        "INSERT OR REPLACE INTO schema_version (version, applied_at) VALUES (5, datetime('now'))",
+        "INSERT OR REPLACE INTO schema_version (version, applied_at) VALUES (5, '')",
         [],
     )?;
 
     conn.execute(
-        "INSERT INTO session (id, name, created_at, modified_at, next_instrument_id, selected_instrument, selected_automation_lane)
-             VALUES (1, 'default', datetime('now'), datetime('now'), ?1, ?2, ?3)",
+        "INSERT INTO session (id, name, created_at, modified_at, next_instrument_id, selected_instrument, selected_automation_lane, automation_freeze_division, synthdef_dir, rng_seed)
+             VALUES (1, 'default', '', '', ?1, ?2, ?3, ?4, ?5, ?6)",
         rusqlite::params![
             &instruments.next_id,
             instruments.selected.map(|s| s as i32),
             session.automation.selected_lane.map(|s| s as i32),
+            session.automation.freeze_division,
+            session.synthdef_dir.as_ref().map(|d| d.to_string_lossy().to_string()),
+            // SQLite integers are signed; the bits round-trip unchanged
+            session.rng_seed as i64,
         ],
     )?;
 
@@ -386,38 +669,51 @@ pub fn save_project(path: &Path, session: &SessionState, instruments: &Instrumen
     save_modulations(&conn, instruments)?;
     save_mixer(&conn, session)?;
     save_piano_roll(&conn, session)?;
+    save_grooves(&conn, session)?;
     save_sampler_configs(&conn, instruments)?;
+    save_velocity_layers(&conn, instruments)?;
+    save_round_robin_samples(&conn, instruments)?;
     save_automation(&conn, session)?;
     save_custom_synthdefs(&conn, session)?;
     save_drum_sequencers(&conn, instruments)?;
     save_chopper_states(&conn, instruments)?;
     save_midi_recording(&conn, session)?;
+    save_ui_state(&conn, session)?;
 
     Ok(())
 }
 
-/// Load from SQLite
-pub fn load_project(path: &Path) -> SqlResult<(SessionState, InstrumentState)> {
+/// The `session` table's row: next instrument id, selected instrument, selected
+/// automation lane, freeze division, synthdef dir, random seed
+type SessionRow = (InstrumentId, Option<i32>, Option<i32>, Option<u32>, Option<String>, Option<i64>);
+
+/// Load from SQLite. References to instruments, buses or parameters that no longer
+/// exist are dropped or repaired on the way in; the last element describes each fix,
+/// and is empty for a consistent file.
+pub fn load_project(path: &Path) -> SqlResult<(SessionState, InstrumentState, Vec<String>)> {
     let conn = SqlConnection::open(path)?;
+    migrate_columns(&conn)?;
 
-    let (next_id, selected_instrument, selected_automation_lane): (InstrumentId, Option<i32>, Option<i32>) = conn.query_row(
-        "SELECT next_instrument_id, selected_instrument, selected_automation_lane FROM session WHERE id = 1",
+    let (next_id, selected_instrument, selected_automation_lane, freeze_division, synthdef_dir, rng_seed): SessionRow = conn.query_row(
+        "SELECT next_instrument_id, selected_instrument, selected_automation_lane, automation_freeze_division, synthdef_dir, rng_seed FROM session WHERE id = 1",
         [],
-        |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+        |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?, row.get(4)?, row.get(5)?)),
     )?;
 
     let mut instruments = load_instruments(&conn)?;
     load_source_params(&conn, &mut instruments)?;
     load_effects(&conn, &mut instruments)?;
-    load_sends(&conn, &mut instruments)?;
+    let orphan_sends = load_sends(&conn, &mut instruments)?;
     load_modulations(&conn, &mut instruments)?;
     load_sampler_configs(&conn, &mut instruments)?;
     let buses = load_buses(&conn)?;
-    let (master_level, master_mute) = load_master(&conn);
+    let (master_level, master_mute, master_output_channel, solo_mode) = load_master(&conn);
     let (piano_roll, musical) = load_piano_roll(&conn)?;
     let mut automation = load_automation(&conn)?;
     let custom_synthdefs = load_custom_synthdefs(&conn)?;
     load_drum_sequencers(&conn, &mut instruments)?;
+    load_velocity_layers(&conn, &mut instruments);
+    load_round_robin_samples(&conn, &mut instruments);
     load_chopper_states(&conn, &mut instruments)?;
     let midi_recording = load_midi_recording(&conn)?;
 
@@ -427,15 +723,27 @@ pub fn load_project(path: &Path) -> SqlResult<(SessionState, InstrumentState)> {
         _ if !automation.lanes.is_empty() => Some(0),
         _ => None,
     };
+    automation.freeze_division = freeze_division;
 
     let mut session = SessionState::new();
     session.buses = buses;
     session.master_level = master_level;
+    session.master_output_channel = master_output_channel;
+    session.solo_mode = solo_mode;
     session.master_mute = master_mute;
     session.piano_roll = piano_roll;
     session.automation = automation;
     session.midi_recording = midi_recording;
+    if let Some((grooves, selected)) = load_grooves(&conn) {
+        session.grooves = grooves;
+        session.selected_groove = selected;
+    }
     session.custom_synthdefs = custom_synthdefs;
+    session.synthdef_dir = synthdef_dir.map(PathBuf::from);
+    // Projects saved before seeds keep the fresh one from SessionState::new
+    if let Some(seed) = rng_seed {
+        session.rng_seed = seed as u64;
+    }
     // Apply musical settings from load_piano_roll
     session.bpm = musical.bpm;
     session.time_signature = musical.time_signature;
@@ -443,30 +751,182 @@ pub fn load_project(path: &Path) -> SqlResult<(SessionState, InstrumentState)> {
     session.scale = musical.scale;
     session.tuning_a4 = musical.tuning_a4;
     session.snap = musical.snap;
+    session.transpose = musical.transpose;
 
-    let instrument_state = InstrumentState {
+    let mut instrument_state = InstrumentState {
         instruments,
         selected: selected_instrument.map(|s| s as usize),
         next_id,
         next_sampler_buffer_id: 20000,
     };
+    instrument_state.next_sampler_buffer_id = instrument_state.instruments.iter()
+        .filter_map(|i| i.sampler_config.as_ref())
+        .flat_map(|c| c.buffer_id.into_iter().chain(c.slices.iter().flat_map(|s| s.layers.iter().map(|l| l.buffer_id))))
+        .map(|id| id + 1)
+        .max()
+        .unwrap_or(20000)
+        .max(20000);
+
+    let repairs = repair_orphans(&mut session, &mut instrument_state, orphan_sends);
+
+    if let Some((ui, mixer_selection)) = load_ui_state(&conn) {
+        session.mixer_selection = clamp_mixer_selection(mixer_selection, &instrument_state);
+        session.ui = ui;
+    }
+
+    Ok((session, instrument_state, repairs))
+}
+
+/// "1 thing" / "3 things"
+fn count_of(n: usize, what: &str) -> String {
+    format!("{} {}{}", n, what, if n == 1 { "" } else { "s" })
+}
+
+/// Drop or repair what a damaged or hand-edited file can leave pointing at nothing:
+/// automation lanes and MIDI mappings for missing instruments or parameters, piano
+/// roll tracks without an instrument, and outputs to missing buses (moved to master).
+/// `orphan_sends` counts send rows already skipped on load. Returns one line per
+/// kind of fix.
+fn repair_orphans(session: &mut SessionState, instruments: &mut InstrumentState, orphan_sends: usize) -> Vec<String> {
+    use super::automation::AutomationTarget;
+
+    let mut repairs = Vec::new();
+    let valid = |target: &AutomationTarget| {
+        instruments.instrument(target.instrument_id()).is_some_and(|inst| AutomationTarget::all_for(inst).contains(target))
+    };
+
+    let automation = &mut session.automation;
+    let before = automation.lanes.len();
+    automation.lanes.retain(|lane| valid(&lane.target));
+    let dropped = before - automation.lanes.len();
+    if dropped > 0 {
+        automation.selected_lane = automation.selected_lane.map(|i| i.min(automation.lanes.len().saturating_sub(1)));
+        if automation.lanes.is_empty() {
+            automation.selected_lane = None;
+        }
+        repairs.push(format!("dropped {} for missing targets", count_of(dropped, "automation lane")));
+    }
+
+    let midi = &mut session.midi_recording;
+    let before = midi.cc_mappings.len() + midi.pitch_bend_configs.len();
+    midi.cc_mappings.retain(|m| valid(&m.target));
+    midi.pitch_bend_configs.retain(|c| valid(&c.target));
+    let dropped = before - midi.cc_mappings.len() - midi.pitch_bend_configs.len();
+    if dropped > 0 {
+        repairs.push(format!("dropped {} for missing targets", count_of(dropped, "MIDI mapping")));
+    }
+
+    let piano_roll = &mut session.piano_roll;
+    let before = piano_roll.tracks.len();
+    piano_roll.tracks.retain(|id, _| instruments.instrument(*id).is_some());
+    piano_roll.track_order.retain(|id| piano_roll.tracks.contains_key(id));
+    let dropped = before - piano_roll.tracks.len();
+    if dropped > 0 {
+        repairs.push(format!("dropped {} without an instrument", count_of(dropped, "piano roll track")));
+    }
+
+    if orphan_sends > 0 {
+        repairs.push(format!("dropped {} to missing buses or instruments", count_of(orphan_sends, "send")));
+    }
+
+    let mut rerouted = 0;
+    for inst in &mut instruments.instruments {
+        if let OutputTarget::Bus(id) = inst.output_target {
+            if session.bus(id).is_none() {
+                inst.output_target = OutputTarget::Master;
+                rerouted += 1;
+            }
+        }
+    }
+    if rerouted > 0 {
+        repairs.push(format!("moved {} from missing buses to master", count_of(rerouted, "output")));
+    }
 
-    Ok((session, instrument_state))
+    repairs
 }
 
 // --- Save helpers ---
 
+fn save_grooves(conn: &SqlConnection, session: &SessionState) -> SqlResult<()> {
+    let mut groove_stmt = conn.prepare("INSERT INTO grooves (position, name, selected) VALUES (?1, ?2, ?3)")?;
+    let mut step_stmt = conn.prepare(
+        "INSERT INTO groove_steps (groove, step, timing, velocity) VALUES (?1, ?2, ?3, ?4)",
+    )?;
+    for (pos, groove) in session.grooves.iter().enumerate() {
+        groove_stmt.execute(rusqlite::params![pos as i32, groove.name, pos == session.selected_groove])?;
+        for (i, step) in groove.steps.iter().enumerate() {
+            step_stmt.execute(rusqlite::params![pos as i32, i as i32, step.timing as f64, step.velocity as f64])?;
+        }
+    }
+    Ok(())
+}
+
+fn save_round_robin_samples(conn: &SqlConnection, instruments: &InstrumentState) -> SqlResult<()> {
+    let mut stmt = conn.prepare(
+        "INSERT INTO round_robin_samples (instrument_id, pad_index, position, buffer_id, path)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+    )?;
+    for inst in &instruments.instruments {
+        let Some(seq) = &inst.drum_sequencer else {
+            continue;
+        };
+        for (pad_idx, pad) in seq.pads.iter().enumerate() {
+            for (pos, sample) in pad.round_robin.iter().enumerate() {
+                stmt.execute(rusqlite::params![inst.id, pad_idx as i32, pos as i32, sample.buffer_id, sample.path])?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Velocity layers of drum pads (`owner` "pad", indexed by pad) and sampler zones
+/// ("slice", indexed by slice id)
+fn save_velocity_layers(conn: &SqlConnection, instruments: &InstrumentState) -> SqlResult<()> {
+    let mut stmt = conn.prepare(
+        "INSERT INTO velocity_layers (instrument_id, owner, owner_index, position, buffer_id, path, vel_low, vel_high)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+    )?;
+    for inst in &instruments.instruments {
+        let pads = inst.drum_sequencer.iter()
+            .flat_map(|seq| seq.pads.iter().enumerate())
+            .map(|(i, pad)| ("pad", i as u32, &pad.layers));
+        let slices = inst.sampler_config.iter()
+            .flat_map(|config| config.slices.iter())
+            .map(|slice| ("slice", slice.id, &slice.layers));
+        for (owner, owner_index, layers) in pads.chain(slices) {
+            for (pos, layer) in layers.iter().enumerate() {
+                stmt.execute(rusqlite::params![
+                    inst.id,
+                    owner,
+                    owner_index,
+                    pos as i32,
+                    layer.buffer_id,
+                    layer.path,
+                    layer.vel_range.0,
+                    layer.vel_range.1,
+                ])?;
+            }
+        }
+    }
+    Ok(())
+}
+
 fn save_drum_sequencers(conn: &SqlConnection, instruments: &InstrumentState) -> SqlResult<()> {
     let mut pad_stmt = conn.prepare(
-        "INSERT INTO drum_pads (instrument_id, pad_index, buffer_id, path, name, level)
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        "INSERT INTO drum_pads (instrument_id, pad_index, buffer_id, path, name, level, reverse, crossfade)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
     )?;
     let mut pattern_stmt = conn.prepare(
-        "INSERT INTO drum_patterns (instrument_id, pattern_index, length) VALUES (?1, ?2, ?3)",
+        "INSERT INTO drum_patterns (instrument_id, pattern_index, length, follow_target, follow_repeats)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
     )?;
     let mut step_stmt = conn.prepare(
-        "INSERT INTO drum_steps (instrument_id, pattern_index, pad_index, step_index, velocity)
-             VALUES (?1, ?2, ?3, ?4, ?5)",
+        "INSERT INTO drum_steps (instrument_id, pattern_index, pad_index, step_index, velocity, nudge, groove_velocity)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+    )?;
+    let mut lock_stmt = conn.prepare(
+        "INSERT INTO drum_step_locks (instrument_id, pattern_index, pad_index, step_index, param, value)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
     )?;
 
     for inst in &instruments.instruments {
@@ -482,20 +942,34 @@ fn save_drum_sequencers(conn: &SqlConnection, instruments: &InstrumentState) ->
                     pad.path,
                     pad.name,
                     pad.level as f64,
+                    pad.reverse,
+                    pad.crossfade,
                 ])?;
             }
 
             // Save patterns
             for (pi, pattern) in seq.patterns.iter().enumerate() {
-                pattern_stmt.execute(rusqlite::params![instrument_id, pi, pattern.length])?;
+                pattern_stmt.execute(rusqlite::params![
+                    instrument_id,
+                    pi,
+                    pattern.length,
+                    pattern.follow_action.map(|(target, _)| target as i32),
+                    pattern.follow_action.map_or(1, |(_, repeats)| repeats),
+                ])?;
 
                 // Save only active steps
                 for (pad_idx, pad_steps) in pattern.steps.iter().enumerate() {
                     for (step_idx, step) in pad_steps.iter().enumerate() {
                         if step.active {
                             step_stmt.execute(rusqlite::params![
-                                instrument_id, pi, pad_idx, step_idx, step.velocity as i32
+                                instrument_id, pi, pad_idx, step_idx, step.velocity as i32, step.nudge as f64,
+                                step.groove_velocity as f64
                             ])?;
+                            for (param, value) in &step.locks {
+                                lock_stmt.execute(rusqlite::params![
+                                    instrument_id, pi, pad_idx, step_idx, param.key(), *value as f64
+                                ])?;
+                            }
                         }
                     }
                 }
@@ -507,8 +981,8 @@ fn save_drum_sequencers(conn: &SqlConnection, instruments: &InstrumentState) ->
 
 fn save_chopper_states(conn: &SqlConnection, instruments: &InstrumentState) -> SqlResult<()> {
     let mut header_stmt = conn.prepare(
-        "INSERT INTO chopper_states (instrument_id, buffer_id, path, name, selected_slice, next_slice_id, duration_secs)
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+        "INSERT INTO chopper_states (instrument_id, buffer_id, path, name, selected_slice, next_slice_id, duration_secs, original_path)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
     )?;
     let mut slice_stmt = conn.prepare(
         "INSERT INTO chopper_slices (instrument_id, slice_id, position, start_pos, end_pos, name, root_note)
@@ -528,6 +1002,7 @@ fn save_chopper_states(conn: &SqlConnection, instruments: &InstrumentState) -> S
                     chopper.selected_slice as i32,
                     chopper.next_slice_id as i32,
                     chopper.duration_secs as f64,
+                    chopper.original_path,
                 ])?;
 
                 for (pos, slice) in chopper.slices.iter().enumerate() {
@@ -585,8 +1060,11 @@ fn save_instruments(conn: &SqlConnection, instruments: &InstrumentState) -> SqlR
         "INSERT INTO instruments (id, name, position, source_type, filter_type, filter_cutoff, filter_resonance,
              lfo_enabled, lfo_rate, lfo_depth, lfo_shape, lfo_target,
              amp_attack, amp_decay, amp_sustain, amp_release, polyphonic,
-             level, pan, mute, solo, active, output_target)
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19, ?20, ?21, ?22, ?23)",
+             level, pan, mute, solo, active, output_target, color, trim,
+             lfo_sync, lfo_division, lfo_retrigger, filter_keytrack, glide, unison_voices, detune,
+             voice_spread, voice_spread_mode, key_low, key_high)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19, ?20, ?21, ?22, ?23, ?24, ?25,
+                     ?26, ?27, ?28, ?29, ?30, ?31, ?32, ?33, ?34, ?35, ?36)",
     )?;
     for (pos, inst) in instruments.instruments.iter().enumerate() {
         let source_str = match inst.source {
@@ -603,28 +1081,16 @@ fn save_instruments(conn: &SqlConnection, instruments: &InstrumentState) -> SqlR
             } else {
                 (None, None, None)
             };
-        let lfo_shape_str = match inst.lfo.shape {
-            LfoShape::Sine => "sine",
-            LfoShape::Square => "square",
-            LfoShape::Saw => "saw",
-            LfoShape::Triangle => "triangle",
-        };
-        let lfo_target_str = match inst.lfo.target {
-            LfoTarget::FilterCutoff => "filter_cutoff",
-            LfoTarget::FilterResonance => "filter_res",
-            LfoTarget::Amplitude => "amp",
-            LfoTarget::Pitch => "pitch",
-            LfoTarget::Pan => "pan",
-            LfoTarget::PulseWidth => "pulse_width",
-            LfoTarget::SampleRate => "sample_rate",
-            LfoTarget::DelayTime => "delay_time",
-            LfoTarget::DelayFeedback => "delay_feedback",
-            LfoTarget::ReverbMix => "reverb_mix",
-            LfoTarget::GateRate => "gate_rate",
-            LfoTarget::SendLevel => "send_level",
-            LfoTarget::Detune => "detune",
-            LfoTarget::Attack => "attack",
-            LfoTarget::Release => "release",
+        // LFO 1 and its first routing keep the original columns; the rest of the
+        // mod matrix is saved by save_modulations. 'none' marks an instrument with no
+        // routings, which would otherwise load as an older project's single routing.
+        let lfo = inst.lfos.first().cloned().unwrap_or_default();
+        let lfo_target = if inst.mod_routings.is_empty() {
+            "none"
+        } else {
+            inst.mod_routings.iter()
+                .find(|r| r.source == ModRouteSource::Lfo(0))
+                .map_or("filter_cutoff", |r| lfo_target_key(r.destination))
         };
         let output_str = match inst.output_target {
             OutputTarget::Master => "master".to_string(),
@@ -638,11 +1104,11 @@ fn save_instruments(conn: &SqlConnection, instruments: &InstrumentState) -> SqlR
             filter_type,
             filter_cutoff,
             filter_res,
-            inst.lfo.enabled,
-            inst.lfo.rate as f64,
-            inst.lfo.depth as f64,
-            lfo_shape_str,
-            lfo_target_str,
+            lfo.enabled,
+            lfo.rate as f64,
+            lfo.depth as f64,
+            lfo_shape_key(lfo.shape),
+            lfo_target,
             inst.amp_envelope.attack as f64,
             inst.amp_envelope.decay as f64,
             inst.amp_envelope.sustain as f64,
@@ -654,6 +1120,19 @@ fn save_instruments(conn: &SqlConnection, instruments: &InstrumentState) -> SqlR
             inst.solo,
             inst.active,
             output_str,
+            inst.color.map(|c| c.name()),
+            inst.trim as f64,
+            lfo.sync,
+            lfo.division.name(),
+            lfo.retrigger,
+            inst.filter.as_ref().map_or(0.0, |f| f.keytrack as f64),
+            inst.glide as f64,
+            inst.unison_voices,
+            inst.detune as f64,
+            inst.voice_spread as f64,
+            voice_spread_mode_key(inst.voice_spread_mode),
+            inst.key_low,
+            inst.key_high,
         ])?;
     }
     Ok(())
@@ -686,41 +1165,55 @@ fn save_source_params(conn: &SqlConnection, instruments: &InstrumentState) -> Sq
 
 fn save_effects(conn: &SqlConnection, instruments: &InstrumentState) -> SqlResult<()> {
     let mut effect_stmt = conn.prepare(
-        "INSERT INTO instrument_effects (instrument_id, position, effect_type, enabled)
-             VALUES (?1, ?2, ?3, ?4)",
+        "INSERT INTO instrument_effects (instrument_id, position, effect_type, enabled, sync, division)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
     )?;
     let mut param_stmt = conn.prepare(
         "INSERT INTO instrument_effect_params (instrument_id, effect_position, param_name, param_value)
              VALUES (?1, ?2, ?3, ?4)",
     )?;
     for inst in &instruments.instruments {
-        for (pos, effect) in inst.effects.iter().enumerate() {
-            let type_str = format!("{:?}", effect.effect_type).to_lowercase();
-            effect_stmt.execute(rusqlite::params![
-                inst.id,
+        save_effect_chain(&mut effect_stmt, &mut param_stmt, inst.id, &inst.effects)?;
+    }
+    Ok(())
+}
+
+/// Write one owner's effect chain through statements shaped like the
+/// `instrument_effects` / `instrument_effect_params` inserts
+fn save_effect_chain(
+    effect_stmt: &mut rusqlite::Statement,
+    param_stmt: &mut rusqlite::Statement,
+    owner_id: u32,
+    effects: &[EffectSlot],
+) -> SqlResult<()> {
+    for (pos, effect) in effects.iter().enumerate() {
+        let type_str = format!("{:?}", effect.effect_type).to_lowercase();
+        effect_stmt.execute(rusqlite::params![
+            owner_id,
+            pos as i32,
+            type_str,
+            effect.enabled,
+            effect.sync,
+            effect.division.name()
+        ])?;
+        for param in &effect.params {
+            let value = match &param.value {
+                ParamValue::Float(v) => *v as f64,
+                ParamValue::Int(v) => *v as f64,
+                ParamValue::Bool(v) => {
+                    if *v {
+                        1.0
+                    } else {
+                        0.0
+                    }
+                }
+            };
+            param_stmt.execute(rusqlite::params![
+                owner_id,
                 pos as i32,
-                type_str,
-                effect.enabled
+                param.name,
+                value
             ])?;
-            for param in &effect.params {
-                let value = match &param.value {
-                    ParamValue::Float(v) => *v as f64,
-                    ParamValue::Int(v) => *v as f64,
-                    ParamValue::Bool(v) => {
-                        if *v {
-                            1.0
-                        } else {
-                            0.0
-                        }
-                    }
-                };
-                param_stmt.execute(rusqlite::params![
-                    inst.id,
-                    pos as i32,
-                    param.name,
-                    value
-                ])?;
-            }
         }
     }
     Ok(())
@@ -762,13 +1255,55 @@ fn save_modulations(conn: &SqlConnection, instruments: &InstrumentState) -> SqlR
             }
         }
     }
+
+    // Mod matrix: LFOs after the first (which lives on the instruments row),
+    // modulation envelopes, and the full routing list, keyed by kind and position
+    let mut matrix_stmt = conn.prepare(
+        "INSERT INTO instrument_modulations (instrument_id, target_param, mod_type, position,
+             enabled, lfo_rate, lfo_depth, lfo_shape, lfo_sync, lfo_division, lfo_retrigger,
+             env_attack, env_decay, env_sustain, env_release, source_index, destination, amount)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18)",
+    )?;
+    for inst in &instruments.instruments {
+        for (pos, lfo) in inst.lfos.iter().enumerate().skip(1) {
+            matrix_stmt.execute(rusqlite::params![
+                inst.id, format!("lfo:{}", pos), "matrix_lfo", pos as i32,
+                lfo.enabled, lfo.rate as f64, lfo.depth as f64,
+                lfo_shape_key(lfo.shape), lfo.sync, lfo.division.name(), lfo.retrigger,
+                None::<f64>, None::<f64>, None::<f64>, None::<f64>,
+                None::<i32>, None::<String>, None::<f64>
+            ])?;
+        }
+        for (pos, env) in inst.mod_envelopes.iter().enumerate() {
+            matrix_stmt.execute(rusqlite::params![
+                inst.id, format!("env:{}", pos), "matrix_env", pos as i32,
+                None::<bool>, None::<f64>, None::<f64>,
+                None::<String>, None::<bool>, None::<String>, None::<bool>,
+                env.attack as f64, env.decay as f64, env.sustain as f64, env.release as f64,
+                None::<i32>, None::<String>, None::<f64>
+            ])?;
+        }
+        for (pos, routing) in inst.mod_routings.iter().enumerate() {
+            let (mod_type, index) = match routing.source {
+                ModRouteSource::Lfo(i) => ("route_lfo", i),
+                ModRouteSource::Envelope(i) => ("route_env", i),
+            };
+            matrix_stmt.execute(rusqlite::params![
+                inst.id, format!("route:{}", pos), mod_type, pos as i32,
+                None::<bool>, None::<f64>, None::<f64>,
+                None::<String>, None::<bool>, None::<String>, None::<bool>,
+                None::<f64>, None::<f64>, None::<f64>, None::<f64>,
+                index as i32, lfo_target_key(routing.destination), routing.amount as f64
+            ])?;
+        }
+    }
     Ok(())
 }
 
 fn save_mixer(conn: &SqlConnection, session: &SessionState) -> SqlResult<()> {
     let mut stmt = conn.prepare(
-        "INSERT INTO mixer_buses (id, name, level, pan, mute, solo)
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        "INSERT INTO mixer_buses (id, name, level, pan, mute, solo, output_channel)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
     )?;
     for bus in &session.buses {
         stmt.execute(rusqlite::params![
@@ -777,25 +1312,43 @@ fn save_mixer(conn: &SqlConnection, session: &SessionState) -> SqlResult<()> {
             bus.level as f64,
             bus.pan as f64,
             bus.mute,
-            bus.solo
+            bus.solo,
+            bus.output_channel
         ])?;
     }
 
+    let mut effect_stmt = conn.prepare(
+        "INSERT INTO bus_effects (bus_id, position, effect_type, enabled, sync, division)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+    )?;
+    let mut param_stmt = conn.prepare(
+        "INSERT INTO bus_effect_params (bus_id, effect_position, param_name, param_value)
+             VALUES (?1, ?2, ?3, ?4)",
+    )?;
+    for bus in &session.buses {
+        save_effect_chain(&mut effect_stmt, &mut param_stmt, bus.id as u32, &bus.effects)?;
+    }
+
     conn.execute(
-        "INSERT INTO mixer_master (id, level, mute) VALUES (1, ?1, ?2)",
-        rusqlite::params![session.master_level as f64, session.master_mute],
+        "INSERT INTO mixer_master (id, level, mute, output_channel, solo_mode) VALUES (1, ?1, ?2, ?3, ?4)",
+        rusqlite::params![
+            session.master_level as f64,
+            session.master_mute,
+            session.master_output_channel,
+            solo_mode_key(session.solo_mode),
+        ],
     )?;
     Ok(())
 }
 
 fn save_sampler_configs(conn: &SqlConnection, instruments: &InstrumentState) -> SqlResult<()> {
     let mut config_stmt = conn.prepare(
-        "INSERT INTO sampler_configs (instrument_id, buffer_id, loop_mode, pitch_tracking, next_slice_id, selected_slice)
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        "INSERT INTO sampler_configs (instrument_id, buffer_id, loop_mode, pitch_tracking, next_slice_id, selected_slice, reverse, layer_zones, crossfade_layers, path, original_path, legacy_mapping)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)",
     )?;
     let mut slice_stmt = conn.prepare(
-        "INSERT INTO sampler_slices (instrument_id, slice_id, position, start_pos, end_pos, name, root_note)
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+        "INSERT INTO sampler_slices (instrument_id, slice_id, position, start_pos, end_pos, name, root_note, key_low, key_high)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
     )?;
 
     for inst in &instruments.instruments {
@@ -807,6 +1360,12 @@ fn save_sampler_configs(conn: &SqlConnection, instruments: &InstrumentState) ->
                 config.pitch_tracking,
                 config.next_slice_id() as i32,
                 config.selected_slice as i32,
+                config.reverse,
+                config.layer_zones,
+                config.crossfade_layers,
+                config.path,
+                config.original_path,
+                config.legacy_mapping,
             ])?;
 
             for (pos, slice) in config.slices.iter().enumerate() {
@@ -818,6 +1377,8 @@ fn save_sampler_configs(conn: &SqlConnection, instruments: &InstrumentState) ->
                     slice.end as f64,
                     &slice.name,
                     slice.root_note as i32,
+                    slice.key_range.0 as i32,
+                    slice.key_range.1 as i32,
                 ])?;
             }
         }
@@ -834,6 +1395,14 @@ fn save_automation(conn: &SqlConnection, session: &SessionState) -> SqlResult<()
         "INSERT INTO automation_points (lane_id, tick, value, curve_type)
              VALUES (?1, ?2, ?3, ?4)",
     )?;
+    let mut frozen_stmt = conn.prepare(
+        "INSERT INTO automation_frozen_points (lane_id, tick, value)
+             VALUES (?1, ?2, ?3)",
+    )?;
+    // Flattened curves are written for external tools; loading reads only the authored points
+    let freeze_resolution = session
+        .automation
+        .freeze_resolution(session.piano_roll.ticks_per_beat);
 
     for lane in &session.automation.lanes {
         let (target_type, instrument_id, effect_idx, param_idx) =
@@ -864,11 +1433,22 @@ fn save_automation(conn: &SqlConnection, session: &SessionState) -> SqlResult<()
                 curve_str,
             ])?;
         }
+
+        if let Some(resolution) = freeze_resolution {
+            for (tick, value) in lane.render_points(resolution) {
+                frozen_stmt.execute(rusqlite::params![lane.id as i32, tick as i32, value as f64])?;
+            }
+        }
     }
     Ok(())
 }
 
 fn save_piano_roll(conn: &SqlConnection, session: &SessionState) -> SqlResult<()> {
+    // `tracks` is a HashMap; walk it in track order so the file is the same every save
+    let ordered_tracks = || {
+        session.piano_roll.track_order.iter().filter_map(|id| session.piano_roll.tracks.get(id))
+    };
+
     // Tracks
     {
         let mut stmt = conn.prepare(
@@ -882,29 +1462,63 @@ fn save_piano_roll(conn: &SqlConnection, session: &SessionState) -> SqlResult<()
         }
     }
 
+    // Markers
+    {
+        let mut stmt = conn.prepare("INSERT INTO markers (tick, name) VALUES (?1, ?2)")?;
+        for marker in &session.piano_roll.markers {
+            stmt.execute(rusqlite::params![marker.tick, &marker.name])?;
+        }
+    }
+
     // Notes
     {
         let mut stmt = conn.prepare(
-            "INSERT INTO piano_roll_notes (track_instrument_id, tick, duration, pitch, velocity)
-                 VALUES (?1, ?2, ?3, ?4, ?5)",
+            "INSERT INTO piano_roll_notes (track_instrument_id, tick, duration, pitch, velocity, groove_velocity)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
         )?;
-        for track in session.piano_roll.tracks.values() {
+        for track in ordered_tracks() {
             for note in &track.notes {
                 stmt.execute(rusqlite::params![
                     track.module_id,
                     note.tick,
                     note.duration,
                     note.pitch,
-                    note.velocity
+                    note.velocity,
+                    note.groove_velocity as f64
                 ])?;
             }
         }
     }
 
+    // Clips
+    {
+        let mut clip_stmt = conn.prepare(
+            "INSERT INTO piano_roll_clips (track_instrument_id, position, name, start_tick, length, loops)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        )?;
+        let mut note_stmt = conn.prepare(
+            "INSERT INTO piano_roll_clip_notes (track_instrument_id, clip_position, tick, duration, pitch, velocity, groove_velocity)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+        )?;
+        for track in ordered_tracks() {
+            for (pos, clip) in track.clips.iter().enumerate() {
+                clip_stmt.execute(rusqlite::params![
+                    track.module_id, pos as i32, &clip.name, clip.start, clip.length, clip.loops
+                ])?;
+                for note in &clip.notes {
+                    note_stmt.execute(rusqlite::params![
+                        track.module_id, pos as i32, note.tick, note.duration, note.pitch, note.velocity,
+                        note.groove_velocity as f64
+                    ])?;
+                }
+            }
+        }
+    }
+
     // Musical settings
     conn.execute(
-        "INSERT INTO musical_settings (id, bpm, time_sig_num, time_sig_denom, ticks_per_beat, loop_start, loop_end, looping, key, scale, tuning_a4, snap)
-             VALUES (1, ?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+        "INSERT INTO musical_settings (id, bpm, time_sig_num, time_sig_denom, ticks_per_beat, loop_start, loop_end, looping, key, scale, tuning_a4, snap, transpose)
+             VALUES (1, ?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)",
         rusqlite::params![
             session.bpm as f64,
             session.time_signature.0,
@@ -917,6 +1531,7 @@ fn save_piano_roll(conn: &SqlConnection, session: &SessionState) -> SqlResult<()
             session.scale.name(),
             session.tuning_a4 as f64,
             session.snap,
+            session.transpose,
         ],
     )?;
     Ok(())
@@ -927,12 +1542,18 @@ fn save_midi_recording(conn: &SqlConnection, session: &SessionState) -> SqlResul
 
     // Settings
     conn.execute(
-        "INSERT INTO midi_recording_settings (id, live_input_instrument, note_passthrough, channel_filter)
-             VALUES (1, ?1, ?2, ?3)",
+        "INSERT INTO midi_recording_settings (id, live_input_instrument, note_passthrough, channel_filter, program_change_mode, record_quantize, record_automation, thru_enabled, thru_channel, thru_velocity_scale)
+             VALUES (1, ?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
         rusqlite::params![
             midi.live_input_instrument.map(|id| id as i32),
             midi.note_passthrough,
             midi.channel_filter.map(|c| c as i32),
+            midi.program_change_mode.name(),
+            midi.record_quantize.map(|q| q as i32),
+            midi.record_automation,
+            midi.thru.enabled,
+            midi.thru.channel.map(|c| c as i32),
+            midi.thru.velocity_scale as f64,
         ],
     )?;
 
@@ -975,6 +1596,16 @@ fn save_midi_recording(conn: &SqlConnection, session: &SessionState) -> SqlResul
         ])?;
     }
 
+    // Channel routes
+    let mut route_stmt = conn.prepare(
+        "INSERT INTO midi_channel_routes (channel, instrument_id) VALUES (?1, ?2)",
+    )?;
+    for (channel, route) in midi.channel_routes.iter().enumerate() {
+        if let Some(instrument_id) = route {
+            route_stmt.execute(rusqlite::params![channel as i32, instrument_id])?;
+        }
+    }
+
     Ok(())
 }
 
@@ -988,6 +1619,7 @@ struct MusicalSettingsLoaded {
     scale: Scale,
     tuning_a4: f32,
     snap: bool,
+    transpose: i8,
 }
 
 impl Default for MusicalSettingsLoaded {
@@ -999,6 +1631,7 @@ impl Default for MusicalSettingsLoaded {
             scale: Scale::Major,
             tuning_a4: 440.0,
             snap: false,
+            transpose: 0,
         }
     }
 }
@@ -1013,7 +1646,9 @@ fn load_instruments(conn: &SqlConnection) -> SqlResult<Vec<Instrument>> {
          COALESCE(lfo_shape, 'sine') as lfo_shape,
          COALESCE(lfo_target, 'filter') as lfo_target,
          amp_attack, amp_decay, amp_sustain, amp_release, polyphonic,
-         level, pan, mute, solo, COALESCE(active, 1) as active, output_target
+         level, pan, mute, solo, COALESCE(active, 1) as active, output_target, color, trim,
+         lfo_sync, lfo_division, lfo_retrigger, filter_keytrack, glide, unison_voices, detune,
+         voice_spread, voice_spread_mode, key_low, key_high
          FROM instruments ORDER BY position",
     )?;
     let rows = stmt.query_map([], |row| {
@@ -1039,6 +1674,19 @@ fn load_instruments(conn: &SqlConnection) -> SqlResult<Vec<Instrument>> {
         let solo: bool = row.get(19)?;
         let active: bool = row.get(20)?;
         let output_str: String = row.get(21)?;
+        let color_str: Option<String> = row.get(22)?;
+        let trim: f64 = row.get(23)?;
+        let lfo_sync: bool = row.get(24)?;
+        let lfo_division: Option<String> = row.get(25)?;
+        let lfo_retrigger: bool = row.get(26)?;
+        let filter_keytrack: f64 = row.get(27)?;
+        let glide: f64 = row.get(28)?;
+        let unison_voices: u8 = row.get(29)?;
+        let detune: f64 = row.get(30)?;
+        let voice_spread: f64 = row.get(31)?;
+        let voice_spread_mode: String = row.get(32)?;
+        let key_low: u8 = row.get(33)?;
+        let key_high: u8 = row.get(34)?;
         Ok((
             id,
             name,
@@ -1062,6 +1710,19 @@ fn load_instruments(conn: &SqlConnection) -> SqlResult<Vec<Instrument>> {
             solo,
             active,
             output_str,
+            color_str,
+            trim,
+            lfo_sync,
+            lfo_division,
+            lfo_retrigger,
+            filter_keytrack,
+            glide,
+            unison_voices,
+            detune,
+            voice_spread,
+            voice_spread_mode,
+            key_low,
+            key_high,
         ))
     })?;
 
@@ -1089,6 +1750,19 @@ fn load_instruments(conn: &SqlConnection) -> SqlResult<Vec<Instrument>> {
             solo,
             active,
             output_str,
+            color_str,
+            trim,
+            lfo_sync,
+            lfo_division,
+            lfo_retrigger,
+            filter_keytrack,
+            glide,
+            unison_voices,
+            detune,
+            voice_spread,
+            voice_spread_mode,
+            key_low,
+            key_high,
         ) = result?;
 
         let source = parse_source_type(&source_str);
@@ -1101,31 +1775,15 @@ fn load_instruments(conn: &SqlConnection) -> SqlResult<Vec<Instrument>> {
             if let Some(r) = filter_res {
                 config.resonance.value = r as f32;
             }
+            config.keytrack = filter_keytrack as f32;
             config
         });
-        let lfo_shape = match lfo_shape_str.as_str() {
-            "square" => LfoShape::Square,
-            "saw" => LfoShape::Saw,
-            "triangle" => LfoShape::Triangle,
-            _ => LfoShape::Sine,
-        };
-        let lfo_target = match lfo_target_str.as_str() {
-            "filter_cutoff" | "filter" => LfoTarget::FilterCutoff,
-            "filter_res" => LfoTarget::FilterResonance,
-            "amp" => LfoTarget::Amplitude,
-            "pitch" => LfoTarget::Pitch,
-            "pan" => LfoTarget::Pan,
-            "pulse_width" => LfoTarget::PulseWidth,
-            "sample_rate" => LfoTarget::SampleRate,
-            "delay_time" => LfoTarget::DelayTime,
-            "delay_feedback" => LfoTarget::DelayFeedback,
-            "reverb_mix" => LfoTarget::ReverbMix,
-            "gate_rate" => LfoTarget::GateRate,
-            "send_level" => LfoTarget::SendLevel,
-            "detune" => LfoTarget::Detune,
-            "attack" => LfoTarget::Attack,
-            "release" => LfoTarget::Release,
-            _ => LfoTarget::FilterCutoff,
+        let lfo_shape = parse_lfo_shape(&lfo_shape_str);
+        let mod_routings = match lfo_target_str.as_str() {
+            "none" => Vec::new(),
+            // Projects from before the mod matrix had one LFO with one target; route
+            // rows in instrument_modulations (if any) replace this in load_modulations
+            target => vec![ModRouting::new(ModRouteSource::Lfo(0), parse_lfo_target(target))],
         };
         let output_target = if output_str == "master" {
             OutputTarget::Master
@@ -1153,17 +1811,22 @@ fn load_instruments(conn: &SqlConnection) -> SqlResult<Vec<Instrument>> {
         instruments.push(Instrument {
             id,
             name,
+            color: color_str.as_deref().and_then(ColorTag::from_name),
             source,
             source_params: source.default_params(),
             filter,
             effects: Vec::new(),
-            lfo: LfoConfig {
+            lfos: vec![LfoConfig {
                 enabled: lfo_enabled,
                 rate: lfo_rate as f32,
                 depth: lfo_depth as f32,
                 shape: lfo_shape,
-                target: lfo_target,
-            },
+                sync: lfo_sync,
+                division: parse_division(lfo_division.as_deref()),
+                retrigger: lfo_retrigger,
+            }],
+            mod_envelopes: Vec::new(),
+            mod_routings,
             amp_envelope: EnvConfig {
                 attack: attack as f32,
                 decay: decay as f32,
@@ -1171,6 +1834,14 @@ fn load_instruments(conn: &SqlConnection) -> SqlResult<Vec<Instrument>> {
                 release: release as f32,
             },
             polyphonic,
+            trim: trim as f32,
+            glide: glide as f32,
+            unison_voices,
+            detune: detune as f32,
+            voice_spread: voice_spread as f32,
+            voice_spread_mode: parse_voice_spread_mode(&voice_spread_mode),
+            key_low,
+            key_high,
             level: level as f32,
             pan: pan as f32,
             mute,
@@ -1188,7 +1859,7 @@ fn load_instruments(conn: &SqlConnection) -> SqlResult<Vec<Instrument>> {
 fn load_source_params(conn: &SqlConnection, instruments: &mut [Instrument]) -> SqlResult<()> {
     let mut stmt = conn.prepare(
         "SELECT param_name, param_value, param_min, param_max, param_type
-         FROM instrument_source_params WHERE instrument_id = ?1",
+         FROM instrument_source_params WHERE instrument_id = ?1 ORDER BY rowid",
     )?;
     for inst in instruments {
         let params: Vec<Param> = stmt
@@ -1217,6 +1888,12 @@ fn load_source_params(conn: &SqlConnection, instruments: &mut [Instrument]) -> S
             .collect();
         if !params.is_empty() {
             inst.source_params = params;
+            // Params added to the source since the file was saved start at their defaults
+            for default in inst.source.default_params() {
+                if !inst.source_params.iter().any(|p| p.name == default.name) {
+                    inst.source_params.push(default);
+                }
+            }
         }
     }
     Ok(())
@@ -1224,48 +1901,66 @@ fn load_source_params(conn: &SqlConnection, instruments: &mut [Instrument]) -> S
 
 fn load_effects(conn: &SqlConnection, instruments: &mut [Instrument]) -> SqlResult<()> {
     let mut effect_stmt = conn.prepare(
-        "SELECT position, effect_type, enabled FROM instrument_effects WHERE instrument_id = ?1 ORDER BY position",
+        "SELECT position, effect_type, enabled, sync, division FROM instrument_effects WHERE instrument_id = ?1 ORDER BY position",
     )?;
     let mut param_stmt = conn.prepare(
         "SELECT param_name, param_value FROM instrument_effect_params WHERE instrument_id = ?1 AND effect_position = ?2",
     )?;
     for inst in instruments {
-        let effects: Vec<(i32, String, bool)> = effect_stmt
-            .query_map([&inst.id], |row| {
-                Ok((row.get(0)?, row.get(1)?, row.get(2)?))
+        inst.effects = load_effect_chain(&mut effect_stmt, &mut param_stmt, inst.id)?;
+    }
+    Ok(())
+}
+
+/// Read one owner's effect chain through statements shaped like the
+/// `instrument_effects` / `instrument_effect_params` selects
+fn load_effect_chain(
+    effect_stmt: &mut rusqlite::Statement,
+    param_stmt: &mut rusqlite::Statement,
+    owner_id: u32,
+) -> SqlResult<Vec<EffectSlot>> {
+    let effects: Vec<(i32, String, bool, bool, Option<String>)> = effect_stmt
+        .query_map([&owner_id], |row| {
+            Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?, row.get(4)?))
+        })?
+        .filter_map(|r| r.ok())
+        .collect();
+
+    let mut chain = Vec::new();
+    for (pos, type_str, enabled, sync, division) in effects {
+        let effect_type = parse_effect_type(&type_str);
+        let mut slot = EffectSlot::new(effect_type);
+        slot.enabled = enabled;
+        slot.sync = sync && slot.supports_sync();
+        if let Some(division) = division.as_deref().and_then(NoteDivision::from_name) {
+            slot.division = division;
+        }
+
+        let params: Vec<(String, f64)> = param_stmt
+            .query_map(rusqlite::params![owner_id, pos], |row| {
+                Ok((row.get(0)?, row.get(1)?))
             })?
             .filter_map(|r| r.ok())
             .collect();
 
-        for (pos, type_str, enabled) in effects {
-            let effect_type = parse_effect_type(&type_str);
-            let mut slot = EffectSlot::new(effect_type);
-            slot.enabled = enabled;
-
-            let params: Vec<(String, f64)> = param_stmt
-                .query_map(rusqlite::params![inst.id, pos], |row| {
-                    Ok((row.get(0)?, row.get(1)?))
-                })?
-                .filter_map(|r| r.ok())
-                .collect();
-
-            for (name, value) in params {
-                if let Some(p) = slot.params.iter_mut().find(|p| p.name == name) {
-                    p.value = match &p.value {
-                        ParamValue::Int(_) => ParamValue::Int(value as i32),
-                        ParamValue::Bool(_) => ParamValue::Bool(value != 0.0),
-                        _ => ParamValue::Float(value as f32),
-                    };
-                }
+        for (name, value) in params {
+            if let Some(p) = slot.params.iter_mut().find(|p| p.name == name) {
+                p.value = match &p.value {
+                    ParamValue::Int(_) => ParamValue::Int(value as i32),
+                    ParamValue::Bool(_) => ParamValue::Bool(value != 0.0),
+                    _ => ParamValue::Float(value as f32),
+                };
             }
-
-            inst.effects.push(slot);
         }
+
+        chain.push(slot);
     }
-    Ok(())
+    Ok(chain)
 }
 
-fn load_sends(conn: &SqlConnection, instruments: &mut [Instrument]) -> SqlResult<()> {
+/// Returns how many saved sends matched no instrument or bus and were skipped
+fn load_sends(conn: &SqlConnection, instruments: &mut [Instrument]) -> SqlResult<usize> {
+    let mut orphans = 0;
     if let Ok(mut stmt) = conn.prepare(
         "SELECT instrument_id, bus_id, level, enabled FROM instrument_sends",
     ) {
@@ -1278,17 +1973,21 @@ fn load_sends(conn: &SqlConnection, instruments: &mut [Instrument]) -> SqlResult
         }) {
             for result in rows {
                 if let Ok((instrument_id, bus_id, level, enabled)) = result {
-                    if let Some(inst) = instruments.iter_mut().find(|s| s.id == instrument_id) {
-                        if let Some(send) = inst.sends.iter_mut().find(|s| s.bus_id == bus_id) {
+                    let send = instruments.iter_mut()
+                        .find(|s| s.id == instrument_id)
+                        .and_then(|inst| inst.sends.iter_mut().find(|s| s.bus_id == bus_id));
+                    match send {
+                        Some(send) => {
                             send.level = level as f32;
                             send.enabled = enabled;
                         }
+                        None => orphans += 1,
                     }
                 }
             }
         }
     }
-    Ok(())
+    Ok(orphans)
 }
 
 fn insert_mod_source(
@@ -1362,8 +2061,7 @@ fn load_modulations(conn: &SqlConnection, instruments: &mut [Instrument]) -> Sql
                             enabled: true,
                             rate: lfo_rate.unwrap_or(1.0) as f32,
                             depth: lfo_depth.unwrap_or(0.5) as f32,
-                            shape: LfoShape::Sine,
-                            target: LfoTarget::FilterCutoff,
+                            ..LfoConfig::default()
                         })),
                         "envelope" => Some(ModSource::Envelope(EnvConfig {
                             attack: env_a.unwrap_or(0.01) as f32,
@@ -1392,13 +2090,89 @@ fn load_modulations(conn: &SqlConnection, instruments: &mut [Instrument]) -> Sql
             }
         }
     }
+    load_mod_matrix(conn, instruments);
     Ok(())
 }
 
+/// The mod matrix rows of instrument_modulations. An instrument with route rows
+/// gets exactly those routings; without any it keeps what load_instruments gave it.
+fn load_mod_matrix(conn: &SqlConnection, instruments: &mut [Instrument]) {
+    let Ok(mut stmt) = conn.prepare(
+        "SELECT instrument_id, mod_type, enabled, lfo_rate, lfo_depth, lfo_shape, lfo_sync,
+         lfo_division, lfo_retrigger, env_attack, env_decay, env_sustain, env_release,
+         source_index, destination, amount
+         FROM instrument_modulations
+         WHERE mod_type IN ('matrix_lfo', 'matrix_env', 'route_lfo', 'route_env')
+         ORDER BY instrument_id, position",
+    ) else {
+        return;
+    };
+    let Ok(rows) = stmt.query_map([], |row| {
+        let instrument_id = row.get::<_, InstrumentId>(0)?;
+        let mod_type = row.get::<_, String>(1)?;
+        let matrix_row = match mod_type.as_str() {
+            "matrix_lfo" => MatrixRow::Lfo(LfoConfig {
+                enabled: row.get::<_, Option<bool>>(2)?.unwrap_or(true),
+                rate: row.get::<_, Option<f64>>(3)?.unwrap_or(2.0) as f32,
+                depth: row.get::<_, Option<f64>>(4)?.unwrap_or(0.5) as f32,
+                shape: parse_lfo_shape(&row.get::<_, Option<String>>(5)?.unwrap_or_default()),
+                sync: row.get::<_, Option<bool>>(6)?.unwrap_or(false),
+                division: parse_division(row.get::<_, Option<String>>(7)?.as_deref()),
+                retrigger: row.get::<_, Option<bool>>(8)?.unwrap_or(false),
+            }),
+            "matrix_env" => MatrixRow::Envelope(EnvConfig {
+                attack: row.get::<_, Option<f64>>(9)?.unwrap_or(0.01) as f32,
+                decay: row.get::<_, Option<f64>>(10)?.unwrap_or(0.1) as f32,
+                sustain: row.get::<_, Option<f64>>(11)?.unwrap_or(0.0) as f32,
+                release: row.get::<_, Option<f64>>(12)?.unwrap_or(0.3) as f32,
+            }),
+            _ => {
+                let index = row.get::<_, Option<i32>>(13)?.unwrap_or(0).max(0) as usize;
+                let source = if mod_type == "route_env" {
+                    ModRouteSource::Envelope(index)
+                } else {
+                    ModRouteSource::Lfo(index)
+                };
+                MatrixRow::Routing(ModRouting {
+                    source,
+                    destination: parse_lfo_target(&row.get::<_, Option<String>>(14)?.unwrap_or_default()),
+                    amount: row.get::<_, Option<f64>>(15)?.unwrap_or(1.0) as f32,
+                })
+            }
+        };
+        Ok((instrument_id, matrix_row))
+    }) else {
+        return;
+    };
+
+    let mut routed: Vec<InstrumentId> = Vec::new();
+    for (instrument_id, matrix_row) in rows.flatten() {
+        let Some(inst) = instruments.iter_mut().find(|i| i.id == instrument_id) else { continue };
+        match matrix_row {
+            MatrixRow::Lfo(lfo) => inst.lfos.push(lfo),
+            MatrixRow::Envelope(env) => inst.mod_envelopes.push(env),
+            MatrixRow::Routing(routing) => {
+                if !routed.contains(&instrument_id) {
+                    routed.push(instrument_id);
+                    inst.mod_routings.clear();
+                }
+                inst.mod_routings.push(routing);
+            }
+        }
+    }
+}
+
+/// One mod matrix row of instrument_modulations
+enum MatrixRow {
+    Lfo(LfoConfig),
+    Envelope(EnvConfig),
+    Routing(ModRouting),
+}
+
 fn load_buses(conn: &SqlConnection) -> SqlResult<Vec<MixerBus>> {
     let mut buses: Vec<MixerBus> = (1..=MAX_BUSES as u8).map(MixerBus::new).collect();
     if let Ok(mut stmt) = conn.prepare(
-        "SELECT id, name, level, pan, mute, solo FROM mixer_buses ORDER BY id",
+        "SELECT id, name, level, pan, mute, solo, output_channel FROM mixer_buses ORDER BY id",
     ) {
         if let Ok(rows) = stmt.query_map([], |row| {
             Ok((
@@ -1408,33 +2182,83 @@ fn load_buses(conn: &SqlConnection) -> SqlResult<Vec<MixerBus>> {
                 row.get::<_, f64>(3)?,
                 row.get::<_, bool>(4)?,
                 row.get::<_, bool>(5)?,
+                row.get::<_, u32>(6)?,
             ))
         }) {
             for result in rows {
-                if let Ok((id, name, level, pan, mute, solo)) = result {
+                if let Ok((id, name, level, pan, mute, solo, output_channel)) = result {
                     if let Some(bus) = buses.get_mut((id - 1) as usize) {
                         bus.name = name;
                         bus.level = level as f32;
                         bus.pan = pan as f32;
                         bus.mute = mute;
                         bus.solo = solo;
+                        bus.output_channel = output_channel;
                     }
                 }
             }
         }
     }
+    // Files from before bus effects have no such tables
+    if let (Ok(mut effect_stmt), Ok(mut param_stmt)) = (
+        conn.prepare("SELECT position, effect_type, enabled, sync, division FROM bus_effects WHERE bus_id = ?1 ORDER BY position"),
+        conn.prepare("SELECT param_name, param_value FROM bus_effect_params WHERE bus_id = ?1 AND effect_position = ?2"),
+    ) {
+        for bus in &mut buses {
+            bus.effects = load_effect_chain(&mut effect_stmt, &mut param_stmt, bus.id as u32)?;
+        }
+    }
     Ok(buses)
 }
 
-fn load_master(conn: &SqlConnection) -> (f32, bool) {
+fn save_ui_state(conn: &SqlConnection, session: &SessionState) -> SqlResult<()> {
+    let view = &session.ui.piano_roll;
+    conn.execute(
+        "INSERT INTO ui_state (id, active_pane, mixer_selection, piano_cursor_tick, piano_cursor_pitch, piano_track, piano_zoom)
+             VALUES (1, ?1, ?2, ?3, ?4, ?5, ?6)",
+        rusqlite::params![
+            session.ui.active_pane,
+            mixer_selection_key(session.mixer_selection),
+            view.cursor_tick,
+            view.cursor_pitch,
+            view.track as i64,
+            view.zoom_level,
+        ],
+    )?;
+    Ok(())
+}
+
+/// Saved view and mixer selection, or None for files from before the table existed.
+/// The piano roll view is clamped when applied to the pane.
+fn load_ui_state(conn: &SqlConnection) -> Option<(UiState, MixerSelection)> {
+    conn.query_row(
+        "SELECT active_pane, mixer_selection, piano_cursor_tick, piano_cursor_pitch, piano_track, piano_zoom
+             FROM ui_state WHERE id = 1",
+        [],
+        |row| {
+            let ui = UiState {
+                active_pane: row.get(0)?,
+                piano_roll: PianoRollView {
+                    cursor_tick: row.get(2)?,
+                    cursor_pitch: row.get::<_, i64>(3)?.clamp(0, 127) as u8,
+                    track: row.get::<_, i64>(4)?.max(0) as usize,
+                    zoom_level: row.get::<_, i64>(5)?.clamp(MIN_PIANO_ZOOM as i64, MAX_PIANO_ZOOM as i64) as u8,
+                },
+            };
+            Ok((ui, parse_mixer_selection(&row.get::<_, String>(1)?)))
+        },
+    ).ok()
+}
+
+fn load_master(conn: &SqlConnection) -> (f32, bool, u32, SoloMode) {
     if let Ok(row) = conn.query_row(
-        "SELECT level, mute FROM mixer_master WHERE id = 1",
+        "SELECT level, mute, output_channel, solo_mode FROM mixer_master WHERE id = 1",
         [],
-        |row| Ok((row.get::<_, f64>(0)?, row.get::<_, bool>(1)?)),
+        |row| Ok((row.get::<_, f64>(0)?, row.get::<_, bool>(1)?, row.get::<_, u32>(2)?, row.get::<_, String>(3)?)),
     ) {
-        (row.0 as f32, row.1)
+        (row.0 as f32, row.1, row.2, parse_solo_mode(&row.3))
     } else {
-        (1.0, false)
+        (1.0, false, 0, SoloMode::default())
     }
 }
 
@@ -1443,7 +2267,8 @@ fn load_piano_roll(conn: &SqlConnection) -> SqlResult<(PianoRollState, MusicalSe
     let mut musical = MusicalSettingsLoaded::default();
 
     if let Ok(row) = conn.query_row(
-        "SELECT bpm, time_sig_num, time_sig_denom, ticks_per_beat, loop_start, loop_end, looping, key, scale, tuning_a4, snap
+        "SELECT bpm, time_sig_num, time_sig_denom, ticks_per_beat, loop_start, loop_end, looping, key, scale, tuning_a4, snap,
+         transpose
          FROM musical_settings WHERE id = 1",
         [],
         |row| {
@@ -1451,7 +2276,7 @@ fn load_piano_roll(conn: &SqlConnection) -> SqlResult<(PianoRollState, MusicalSe
                 row.get::<_, f64>(0)?, row.get::<_, u8>(1)?, row.get::<_, u8>(2)?,
                 row.get::<_, u32>(3)?, row.get::<_, u32>(4)?, row.get::<_, u32>(5)?,
                 row.get::<_, bool>(6)?, row.get::<_, String>(7)?, row.get::<_, String>(8)?,
-                row.get::<_, f64>(9)?, row.get::<_, bool>(10)?,
+                row.get::<_, f64>(9)?, row.get::<_, bool>(10)?, row.get::<_, i8>(11)?,
             ))
         },
     ) {
@@ -1461,6 +2286,7 @@ fn load_piano_roll(conn: &SqlConnection) -> SqlResult<(PianoRollState, MusicalSe
         musical.scale = parse_scale(&row.8);
         musical.tuning_a4 = row.9 as f32;
         musical.snap = row.10;
+        musical.transpose = row.11;
         piano_roll.bpm = row.0 as f32;
         piano_roll.time_signature = (row.1, row.2);
         piano_roll.ticks_per_beat = row.3;
@@ -1479,14 +2305,9 @@ fn load_piano_roll(conn: &SqlConnection) -> SqlResult<(PianoRollState, MusicalSe
             for result in rows {
                 if let Ok((instrument_id, polyphonic)) = result {
                     piano_roll.track_order.push(instrument_id);
-                    piano_roll.tracks.insert(
-                        instrument_id,
-                        super::piano_roll::Track {
-                            module_id: instrument_id,
-                            notes: Vec::new(),
-                            polyphonic,
-                        },
-                    );
+                    let mut track = super::piano_roll::Track::new(instrument_id);
+                    track.polyphonic = polyphonic;
+                    piano_roll.tracks.insert(instrument_id, track);
                 }
             }
         }
@@ -1494,7 +2315,7 @@ fn load_piano_roll(conn: &SqlConnection) -> SqlResult<(PianoRollState, MusicalSe
 
     // Load notes
     if let Ok(mut stmt) = conn.prepare(
-        "SELECT track_instrument_id, tick, duration, pitch, velocity FROM piano_roll_notes",
+        "SELECT track_instrument_id, tick, duration, pitch, velocity, groove_velocity FROM piano_roll_notes",
     ) {
         if let Ok(rows) = stmt.query_map([], |row| {
             Ok((
@@ -1503,27 +2324,86 @@ fn load_piano_roll(conn: &SqlConnection) -> SqlResult<(PianoRollState, MusicalSe
                 row.get::<_, u32>(2)?,
                 row.get::<_, u8>(3)?,
                 row.get::<_, u8>(4)?,
+                row.get::<_, f64>(5)?,
             ))
         }) {
             for result in rows {
-                if let Ok((instrument_id, tick, duration, pitch, velocity)) = result {
+                if let Ok((instrument_id, tick, duration, pitch, velocity, groove_velocity)) = result {
                     if let Some(track) = piano_roll.tracks.get_mut(&instrument_id) {
-                        track
-                            .notes
-                            .push(super::piano_roll::Note { tick, duration, pitch, velocity });
+                        let mut note = super::piano_roll::Note::new(tick, duration, pitch, velocity);
+                        note.groove_velocity = groove_velocity as f32;
+                        track.notes.push(note);
                     }
                 }
             }
         }
     }
 
+    // Load clips (tables are absent in older files, whose notes all stay loose)
+    if let Ok(mut stmt) = conn.prepare(
+        "SELECT track_instrument_id, name, start_tick, length, loops
+         FROM piano_roll_clips ORDER BY track_instrument_id, position",
+    ) {
+        if let Ok(rows) = stmt.query_map([], |row| {
+            Ok((
+                row.get::<_, InstrumentId>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, u32>(2)?,
+                row.get::<_, u32>(3)?,
+                row.get::<_, u32>(4)?,
+            ))
+        }) {
+            for (instrument_id, name, start, length, loops) in rows.flatten() {
+                if let Some(track) = piano_roll.tracks.get_mut(&instrument_id) {
+                    let mut clip = super::piano_roll::Clip::new(name, start, length);
+                    clip.loops = loops.max(1);
+                    track.clips.push(clip);
+                }
+            }
+        }
+    }
+    if let Ok(mut stmt) = conn.prepare(
+        "SELECT track_instrument_id, clip_position, tick, duration, pitch, velocity, groove_velocity
+         FROM piano_roll_clip_notes ORDER BY id",
+    ) {
+        if let Ok(rows) = stmt.query_map([], |row| {
+            Ok((
+                row.get::<_, InstrumentId>(0)?,
+                row.get::<_, usize>(1)?,
+                row.get::<_, u32>(2)?,
+                row.get::<_, u32>(3)?,
+                row.get::<_, u8>(4)?,
+                row.get::<_, u8>(5)?,
+                row.get::<_, f64>(6)?,
+            ))
+        }) {
+            for (instrument_id, pos, tick, duration, pitch, velocity, groove_velocity) in rows.flatten() {
+                if let Some(clip) = piano_roll.tracks.get_mut(&instrument_id).and_then(|t| t.clips.get_mut(pos)) {
+                    let mut note = super::piano_roll::Note::new(tick, duration, pitch, velocity);
+                    note.groove_velocity = groove_velocity as f32;
+                    clip.notes.push(note);
+                }
+            }
+        }
+    }
+
+    // Load markers (table is absent in older files)
+    if let Ok(mut stmt) = conn.prepare("SELECT tick, name FROM markers ORDER BY tick") {
+        if let Ok(rows) = stmt.query_map([], |row| Ok((row.get::<_, u32>(0)?, row.get::<_, String>(1)?))) {
+            for (tick, name) in rows.flatten() {
+                piano_roll.add_marker(tick, name);
+            }
+        }
+    }
+
     Ok((piano_roll, musical))
 }
 
 fn load_sampler_configs(conn: &SqlConnection, instruments: &mut [Instrument]) -> SqlResult<()> {
     // Load sampler configs
     if let Ok(mut config_stmt) = conn.prepare(
-        "SELECT instrument_id, buffer_id, loop_mode, pitch_tracking, next_slice_id, COALESCE(selected_slice, 0)
+        "SELECT instrument_id, buffer_id, loop_mode, pitch_tracking, next_slice_id, COALESCE(selected_slice, 0), reverse, layer_zones, crossfade_layers,
+                path, original_path, legacy_mapping
          FROM sampler_configs",
     ) {
         if let Ok(rows) = config_stmt.query_map([], |row| {
@@ -1534,10 +2414,16 @@ fn load_sampler_configs(conn: &SqlConnection, instruments: &mut [Instrument]) ->
                 row.get::<_, bool>(3)?,
                 row.get::<_, i32>(4)?,
                 row.get::<_, i32>(5)?,
+                row.get::<_, bool>(6)?,
+                row.get::<_, bool>(7)?,
+                row.get::<_, bool>(8)?,
+                row.get::<_, Option<String>>(9)?,
+                row.get::<_, Option<String>>(10)?,
+                row.get::<_, bool>(11)?,
             ))
         }) {
             for result in rows {
-                if let Ok((instrument_id, buffer_id, loop_mode, pitch_tracking, next_slice_id, selected_slice)) = result
+                if let Ok((instrument_id, buffer_id, loop_mode, pitch_tracking, next_slice_id, selected_slice, reverse, layer_zones, crossfade_layers, path, original_path, legacy_mapping)) = result
                 {
                     if let Some(inst) = instruments.iter_mut().find(|s| s.id == instrument_id) {
                         if let Some(ref mut config) = inst.sampler_config {
@@ -1545,6 +2431,12 @@ fn load_sampler_configs(conn: &SqlConnection, instruments: &mut [Instrument]) ->
                                 buffer_id.map(|id| id as super::sampler::BufferId);
                             config.loop_mode = loop_mode;
                             config.pitch_tracking = pitch_tracking;
+                            config.reverse = reverse;
+                            config.layer_zones = layer_zones;
+                            config.crossfade_layers = crossfade_layers;
+                            config.path = path;
+                            config.original_path = original_path;
+                            config.legacy_mapping = legacy_mapping;
                             config
                                 .set_next_slice_id(next_slice_id as super::sampler::SliceId);
                             config.selected_slice = selected_slice as usize;
@@ -1559,7 +2451,8 @@ fn load_sampler_configs(conn: &SqlConnection, instruments: &mut [Instrument]) ->
 
     // Load slices
     if let Ok(mut slice_stmt) = conn.prepare(
-        "SELECT instrument_id, slice_id, start_pos, end_pos, name, root_note FROM sampler_slices ORDER BY instrument_id, position",
+        "SELECT instrument_id, slice_id, start_pos, end_pos, name, root_note, key_low, key_high
+         FROM sampler_slices ORDER BY instrument_id, position",
     ) {
         if let Ok(rows) = slice_stmt.query_map([], |row| {
             Ok((
@@ -1569,10 +2462,12 @@ fn load_sampler_configs(conn: &SqlConnection, instruments: &mut [Instrument]) ->
                 row.get::<_, f64>(3)?,
                 row.get::<_, String>(4)?,
                 row.get::<_, i32>(5)?,
+                row.get::<_, i32>(6)?,
+                row.get::<_, i32>(7)?,
             ))
         }) {
             for result in rows {
-                if let Ok((instrument_id, slice_id, start, end, name, root_note)) = result {
+                if let Ok((instrument_id, slice_id, start, end, name, root_note, key_low, key_high)) = result {
                     if let Some(inst) = instruments.iter_mut().find(|s| s.id == instrument_id) {
                         if let Some(ref mut config) = inst.sampler_config {
                             config.slices.push(super::sampler::Slice {
@@ -1581,6 +2476,8 @@ fn load_sampler_configs(conn: &SqlConnection, instruments: &mut [Instrument]) ->
                                 end: end as f32,
                                 name,
                                 root_note: root_note as u8,
+                                key_range: (key_low.clamp(0, 127) as u8, key_high.clamp(0, 127) as u8),
+                                layers: Vec::new(),
                             });
                         }
                     }
@@ -1681,12 +2578,219 @@ fn load_automation(conn: &SqlConnection) -> SqlResult<super::automation::Automat
     Ok(state)
 }
 
+/// Groove templates and the selected index; None for files saved before grooves
+/// existed, which keep the built-in presets
+fn load_grooves(conn: &SqlConnection) -> Option<(Vec<GrooveTemplate>, usize)> {
+    let mut stmt = conn.prepare("SELECT name, selected FROM grooves ORDER BY position").ok()?;
+    let mut grooves = Vec::new();
+    let mut selected = 0;
+    for (i, (name, is_selected)) in stmt
+        .query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, bool>(1)?)))
+        .ok()?
+        .flatten()
+        .enumerate()
+    {
+        grooves.push(GrooveTemplate::new(name, Vec::new()));
+        if is_selected {
+            selected = i;
+        }
+    }
+    if grooves.is_empty() {
+        return None;
+    }
+
+    let mut stmt = conn.prepare("SELECT groove, timing, velocity FROM groove_steps ORDER BY groove, step").ok()?;
+    let rows = stmt.query_map([], |row| {
+        Ok((row.get::<_, usize>(0)?, row.get::<_, f64>(1)?, row.get::<_, f64>(2)?))
+    }).ok()?;
+    for (groove, timing, velocity) in rows.flatten() {
+        if let Some(g) = grooves.get_mut(groove) {
+            g.steps.push(GrooveStep { timing: timing as f32, velocity: velocity as f32 });
+        }
+    }
+    Some((grooves, selected))
+}
+
+fn lfo_shape_key(shape: LfoShape) -> &'static str {
+    match shape {
+        LfoShape::Sine => "sine",
+        LfoShape::Square => "square",
+        LfoShape::Saw => "saw",
+        LfoShape::Triangle => "triangle",
+    }
+}
+
+fn parse_lfo_shape(s: &str) -> LfoShape {
+    match s {
+        "square" => LfoShape::Square,
+        "saw" => LfoShape::Saw,
+        "triangle" => LfoShape::Triangle,
+        _ => LfoShape::Sine,
+    }
+}
+
+fn solo_mode_key(mode: SoloMode) -> &'static str {
+    match mode {
+        SoloMode::InPlace => "in_place",
+        SoloMode::Afl => "afl",
+    }
+}
+
+fn parse_solo_mode(s: &str) -> SoloMode {
+    match s {
+        "afl" => SoloMode::Afl,
+        _ => SoloMode::InPlace,
+    }
+}
+
+fn voice_spread_mode_key(mode: VoiceSpreadMode) -> &'static str {
+    match mode {
+        VoiceSpreadMode::Pitch => "pitch",
+        VoiceSpreadMode::Order => "order",
+    }
+}
+
+fn parse_voice_spread_mode(s: &str) -> VoiceSpreadMode {
+    match s {
+        "order" => VoiceSpreadMode::Order,
+        _ => VoiceSpreadMode::Pitch,
+    }
+}
+
+fn lfo_target_key(target: LfoTarget) -> &'static str {
+    match target {
+        LfoTarget::FilterCutoff => "filter_cutoff",
+        LfoTarget::FilterResonance => "filter_res",
+        LfoTarget::Amplitude => "amp",
+        LfoTarget::Pitch => "pitch",
+        LfoTarget::Pan => "pan",
+        LfoTarget::PulseWidth => "pulse_width",
+        LfoTarget::SampleRate => "sample_rate",
+        LfoTarget::DelayTime => "delay_time",
+        LfoTarget::DelayFeedback => "delay_feedback",
+        LfoTarget::ReverbMix => "reverb_mix",
+        LfoTarget::GateRate => "gate_rate",
+        LfoTarget::SendLevel => "send_level",
+        LfoTarget::Detune => "detune",
+        LfoTarget::Attack => "attack",
+        LfoTarget::Release => "release",
+    }
+}
+
+fn parse_lfo_target(s: &str) -> LfoTarget {
+    match s {
+        "filter_cutoff" | "filter" => LfoTarget::FilterCutoff,
+        "filter_res" => LfoTarget::FilterResonance,
+        "amp" => LfoTarget::Amplitude,
+        "pitch" => LfoTarget::Pitch,
+        "pan" => LfoTarget::Pan,
+        "pulse_width" => LfoTarget::PulseWidth,
+        "sample_rate" => LfoTarget::SampleRate,
+        "delay_time" => LfoTarget::DelayTime,
+        "delay_feedback" => LfoTarget::DelayFeedback,
+        "reverb_mix" => LfoTarget::ReverbMix,
+        "gate_rate" => LfoTarget::GateRate,
+        "send_level" => LfoTarget::SendLevel,
+        "detune" => LfoTarget::Detune,
+        "attack" => LfoTarget::Attack,
+        "release" => LfoTarget::Release,
+        _ => LfoTarget::FilterCutoff,
+    }
+}
+
+fn parse_division(s: Option<&str>) -> NoteDivision {
+    s.and_then(NoteDivision::from_name).unwrap_or(NoteDivision::Quarter)
+}
+
+fn load_round_robin_samples(conn: &SqlConnection, instruments: &mut [Instrument]) {
+    use super::drum_sequencer::RoundRobinSample;
+
+    let Ok(mut stmt) = conn.prepare(
+        "SELECT instrument_id, pad_index, buffer_id, path
+         FROM round_robin_samples ORDER BY instrument_id, pad_index, position",
+    ) else {
+        return;
+    };
+    let Ok(rows) = stmt.query_map([], |row| {
+        Ok((
+            row.get::<_, InstrumentId>(0)?,
+            row.get::<_, usize>(1)?,
+            RoundRobinSample {
+                buffer_id: row.get(2)?,
+                path: row.get(3)?,
+            },
+        ))
+    }) else {
+        return;
+    };
+    for (instrument_id, pad_index, sample) in rows.flatten() {
+        let seq = instruments.iter_mut()
+            .find(|s| s.id == instrument_id)
+            .and_then(|s| s.drum_sequencer.as_mut());
+        if let Some(seq) = seq {
+            seq.next_buffer_id = seq.next_buffer_id.max(sample.buffer_id + 1);
+            if let Some(pad) = seq.pads.get_mut(pad_index) {
+                pad.round_robin.push(sample);
+            }
+        }
+    }
+}
+
+fn load_velocity_layers(conn: &SqlConnection, instruments: &mut [Instrument]) {
+    use super::sampler::VelocityLayer;
+
+    let Ok(mut stmt) = conn.prepare(
+        "SELECT instrument_id, owner, owner_index, buffer_id, path, vel_low, vel_high
+         FROM velocity_layers ORDER BY instrument_id, owner, owner_index, position",
+    ) else {
+        return;
+    };
+    let Ok(rows) = stmt.query_map([], |row| {
+        Ok((
+            row.get::<_, InstrumentId>(0)?,
+            row.get::<_, String>(1)?,
+            row.get::<_, u32>(2)?,
+            VelocityLayer {
+                buffer_id: row.get(3)?,
+                path: row.get(4)?,
+                vel_range: (row.get(5)?, row.get(6)?),
+            },
+        ))
+    }) else {
+        return;
+    };
+    for (instrument_id, owner, owner_index, layer) in rows.flatten() {
+        let Some(inst) = instruments.iter_mut().find(|s| s.id == instrument_id) else {
+            continue;
+        };
+        match owner.as_str() {
+            "pad" => {
+                if let Some(seq) = &mut inst.drum_sequencer {
+                    // Layer buffers share the pad id space, so keep new ids past them
+                    seq.next_buffer_id = seq.next_buffer_id.max(layer.buffer_id + 1);
+                    if let Some(pad) = seq.pads.get_mut(owner_index as usize) {
+                        pad.layers.push(layer);
+                    }
+                }
+            }
+            "slice" => {
+                let slice = inst.sampler_config.as_mut()
+                    .and_then(|c| c.slices.iter_mut().find(|s| s.id == owner_index));
+                if let Some(slice) = slice {
+                    slice.layers.push(layer);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
 fn load_drum_sequencers(conn: &SqlConnection, instruments: &mut [Instrument]) -> SqlResult<()> {
-    use super::drum_sequencer::DrumPattern;
+    use super::drum_sequencer::{DrumPattern, LockParam};
 
     // Load pads per instrument
     if let Ok(mut stmt) = conn.prepare(
-        "SELECT instrument_id, pad_index, buffer_id, path, name, level FROM drum_pads",
+        "SELECT instrument_id, pad_index, buffer_id, path, name, level, reverse, crossfade FROM drum_pads",
     ) {
         if let Ok(rows) = stmt.query_map([], |row| {
             Ok((
@@ -1696,10 +2800,12 @@ fn load_drum_sequencers(conn: &SqlConnection, instruments: &mut [Instrument]) ->
                 row.get::<_, Option<String>>(3)?,
                 row.get::<_, String>(4)?,
                 row.get::<_, f64>(5)?,
+                row.get::<_, bool>(6)?,
+                row.get::<_, bool>(7)?,
             ))
         }) {
             for row in rows {
-                if let Ok((instrument_id, idx, buffer_id, path, name, level)) = row {
+                if let Ok((instrument_id, idx, buffer_id, path, name, level, reverse, crossfade)) = row {
                     if let Some(inst) = instruments.iter_mut().find(|s| s.id == instrument_id) {
                         if let Some(seq) = &mut inst.drum_sequencer {
                             if let Some(pad) = seq.pads.get_mut(idx) {
@@ -1707,6 +2813,8 @@ fn load_drum_sequencers(conn: &SqlConnection, instruments: &mut [Instrument]) ->
                                 pad.path = path;
                                 pad.name = name;
                                 pad.level = level as f32;
+                                pad.reverse = reverse;
+                                pad.crossfade = crossfade;
                             }
                         }
                     }
@@ -1730,21 +2838,25 @@ fn load_drum_sequencers(conn: &SqlConnection, instruments: &mut [Instrument]) ->
 
     // Load patterns per instrument
     if let Ok(mut stmt) = conn.prepare(
-        "SELECT instrument_id, pattern_index, length FROM drum_patterns ORDER BY instrument_id, pattern_index",
+        "SELECT instrument_id, pattern_index, length, follow_target, follow_repeats
+         FROM drum_patterns ORDER BY instrument_id, pattern_index",
     ) {
         if let Ok(rows) = stmt.query_map([], |row| {
             Ok((
                 row.get::<_, InstrumentId>(0)?,
                 row.get::<_, usize>(1)?,
                 row.get::<_, usize>(2)?,
+                row.get::<_, Option<usize>>(3)?,
+                row.get::<_, u8>(4)?,
             ))
         }) {
             for row in rows {
-                if let Ok((instrument_id, idx, length)) = row {
+                if let Ok((instrument_id, idx, length, follow_target, follow_repeats)) = row {
                     if let Some(inst) = instruments.iter_mut().find(|s| s.id == instrument_id) {
                         if let Some(seq) = &mut inst.drum_sequencer {
                             if let Some(pattern) = seq.patterns.get_mut(idx) {
                                 *pattern = DrumPattern::new(length);
+                                pattern.follow_action = follow_target.map(|t| (t, follow_repeats.max(1)));
                             }
                         }
                     }
@@ -1755,7 +2867,7 @@ fn load_drum_sequencers(conn: &SqlConnection, instruments: &mut [Instrument]) ->
 
     // Load active steps per instrument
     if let Ok(mut stmt) = conn.prepare(
-        "SELECT instrument_id, pattern_index, pad_index, step_index, velocity FROM drum_steps",
+        "SELECT instrument_id, pattern_index, pad_index, step_index, velocity, nudge, groove_velocity FROM drum_steps",
     ) {
         if let Ok(rows) = stmt.query_map([], |row| {
             Ok((
@@ -1764,10 +2876,12 @@ fn load_drum_sequencers(conn: &SqlConnection, instruments: &mut [Instrument]) ->
                 row.get::<_, usize>(2)?,
                 row.get::<_, usize>(3)?,
                 row.get::<_, u8>(4)?,
+                row.get::<_, f64>(5)?,
+                row.get::<_, f64>(6)?,
             ))
         }) {
             for row in rows {
-                if let Ok((instrument_id, pi, pad_idx, step_idx, velocity)) = row {
+                if let Ok((instrument_id, pi, pad_idx, step_idx, velocity, nudge, groove_velocity)) = row {
                     if let Some(inst) = instruments.iter_mut().find(|s| s.id == instrument_id) {
                         if let Some(seq) = &mut inst.drum_sequencer {
                             if let Some(pattern) = seq.patterns.get_mut(pi) {
@@ -1778,6 +2892,8 @@ fn load_drum_sequencers(conn: &SqlConnection, instruments: &mut [Instrument]) ->
                                 {
                                     step.active = true;
                                     step.velocity = velocity;
+                                    step.nudge = nudge as f32;
+                                    step.groove_velocity = groove_velocity as f32;
                                 }
                             }
                         }
@@ -1787,6 +2903,35 @@ fn load_drum_sequencers(conn: &SqlConnection, instruments: &mut [Instrument]) ->
         }
     }
 
+    // Load parameter locks onto their steps (older files have no table)
+    if let Ok(mut stmt) = conn.prepare(
+        "SELECT instrument_id, pattern_index, pad_index, step_index, param, value FROM drum_step_locks",
+    ) {
+        if let Ok(rows) = stmt.query_map([], |row| {
+            Ok((
+                row.get::<_, InstrumentId>(0)?,
+                row.get::<_, usize>(1)?,
+                row.get::<_, usize>(2)?,
+                row.get::<_, usize>(3)?,
+                row.get::<_, String>(4)?,
+                row.get::<_, f64>(5)?,
+            ))
+        }) {
+            for (instrument_id, pi, pad_idx, step_idx, param, value) in rows.flatten() {
+                let Some(param) = LockParam::from_key(&param) else { continue };
+                let step = instruments.iter_mut()
+                    .find(|s| s.id == instrument_id)
+                    .and_then(|inst| inst.drum_sequencer.as_mut())
+                    .and_then(|seq| seq.patterns.get_mut(pi))
+                    .and_then(|pattern| pattern.steps.get_mut(pad_idx))
+                    .and_then(|s| s.get_mut(step_idx));
+                if let Some(step) = step {
+                    step.set_lock(param, value as f32);
+                }
+            }
+        }
+    }
+
     Ok(())
 }
 
@@ -1796,7 +2941,7 @@ fn load_chopper_states(conn: &SqlConnection, instruments: &mut [Instrument]) ->
 
     // Load chopper headers
     if let Ok(mut stmt) = conn.prepare(
-        "SELECT instrument_id, buffer_id, path, name, selected_slice, next_slice_id, duration_secs
+        "SELECT instrument_id, buffer_id, path, name, selected_slice, next_slice_id, duration_secs, original_path
          FROM chopper_states",
     ) {
         if let Ok(rows) = stmt.query_map([], |row| {
@@ -1808,10 +2953,11 @@ fn load_chopper_states(conn: &SqlConnection, instruments: &mut [Instrument]) ->
                 row.get::<_, usize>(4)?,
                 row.get::<_, u32>(5)?,
                 row.get::<_, f64>(6)?,
+                row.get::<_, Option<String>>(7)?,
             ))
         }) {
             for result in rows {
-                if let Ok((instrument_id, buffer_id, path, name, selected_slice, next_slice_id, duration_secs)) = result {
+                if let Ok((instrument_id, buffer_id, path, name, selected_slice, next_slice_id, duration_secs, original_path)) = result {
                     if let Some(inst) = instruments.iter_mut().find(|s| s.id == instrument_id) {
                         if let Some(seq) = &mut inst.drum_sequencer {
                             seq.chopper = Some(ChopperState {
@@ -1823,6 +2969,8 @@ fn load_chopper_states(conn: &SqlConnection, instruments: &mut [Instrument]) ->
                                 next_slice_id,
                                 waveform_peaks: Vec::new(),
                                 duration_secs: duration_secs as f32,
+                                original_path,
+                                status: None,
                             });
                         }
                     }
@@ -1857,6 +3005,8 @@ fn load_chopper_states(conn: &SqlConnection, instruments: &mut [Instrument]) ->
                                     end: end as f32,
                                     name,
                                     root_note: root_note as u8,
+                                    key_range: (0, 127),
+                                    layers: Vec::new(),
                                 });
                             }
                         }
@@ -1870,13 +3020,17 @@ fn load_chopper_states(conn: &SqlConnection, instruments: &mut [Instrument]) ->
 }
 
 fn load_midi_recording(conn: &SqlConnection) -> SqlResult<super::midi_recording::MidiRecordingState> {
-    use super::midi_recording::{MidiCcMapping, MidiRecordingState, PitchBendConfig};
+    use super::midi_recording::{
+        MidiCcMapping, MidiRecordingState, MidiThru, PitchBendConfig, ProgramChangeMode, MAX_THRU_VELOCITY_SCALE,
+        MIDI_CHANNELS, MIN_THRU_VELOCITY_SCALE,
+    };
 
     let mut state = MidiRecordingState::new();
 
     // Load settings
     if let Ok(row) = conn.query_row(
-        "SELECT live_input_instrument, note_passthrough, channel_filter
+        "SELECT live_input_instrument, note_passthrough, channel_filter, program_change_mode, record_quantize, record_automation,
+                thru_enabled, thru_channel, thru_velocity_scale
          FROM midi_recording_settings WHERE id = 1",
         [],
         |row| {
@@ -1884,12 +3038,26 @@ fn load_midi_recording(conn: &SqlConnection) -> SqlResult<super::midi_recording:
                 row.get::<_, Option<i32>>(0)?,
                 row.get::<_, bool>(1)?,
                 row.get::<_, Option<i32>>(2)?,
+                row.get::<_, String>(3)?,
+                row.get::<_, Option<i32>>(4)?,
+                row.get::<_, bool>(5)?,
+                row.get::<_, bool>(6)?,
+                row.get::<_, Option<i32>>(7)?,
+                row.get::<_, f64>(8)?,
             ))
         },
     ) {
         state.live_input_instrument = row.0.map(|id| id as InstrumentId);
         state.note_passthrough = row.1;
         state.channel_filter = row.2.map(|c| c as u8);
+        state.program_change_mode = ProgramChangeMode::from_name(&row.3);
+        state.record_quantize = row.4.map(|q| q as u32);
+        state.record_automation = row.5;
+        state.thru = MidiThru {
+            enabled: row.6,
+            channel: row.7.and_then(|c| u8::try_from(c).ok()).filter(|&c| (c as usize) < MIDI_CHANNELS),
+            velocity_scale: (row.8 as f32).clamp(MIN_THRU_VELOCITY_SCALE, MAX_THRU_VELOCITY_SCALE),
+        };
     }
 
     // Load CC mappings
@@ -1958,6 +3126,19 @@ fn load_midi_recording(conn: &SqlConnection) -> SqlResult<super::midi_recording:
         }
     }
 
+    // Load channel routes
+    if let Ok(mut stmt) = conn.prepare(
+        "SELECT channel, instrument_id FROM midi_channel_routes",
+    ) {
+        if let Ok(rows) = stmt.query_map([], |row| {
+            Ok((row.get::<_, i32>(0)?, row.get::<_, InstrumentId>(1)?))
+        }) {
+            for (channel, instrument_id) in rows.flatten() {
+                state.set_channel_route(channel as u8, Some(instrument_id));
+            }
+        }
+    }
+
     // Always start with recording off
     state.record_mode = super::midi_recording::RecordMode::Off;
 
@@ -2088,3 +3269,668 @@ fn parse_effect_type(s: &str) -> EffectType {
         _ => EffectType::Delay,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::AppState;
+
+    /// Save `state` to a fresh project file and load it back
+    fn round_trip(state: &AppState) -> (SessionState, InstrumentState) {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("project.ilex");
+        save_project(&path, &state.session, &state.instruments).unwrap();
+        let (session, instruments, _) = load_project(&path).unwrap();
+        (session, instruments)
+    }
+
+    #[test]
+    fn test_instrument_name_and_color_round_trip() {
+        let mut state = AppState::new();
+        let synthdef_id = state.session.custom_synthdefs.add(CustomSynthDef {
+            id: 0,
+            name: "My Bass".to_string(),
+            synthdef_name: "my_bass".to_string(),
+            source_path: PathBuf::from("my_bass.scd"),
+            params: Vec::new(),
+        });
+        let custom = state.add_instrument(SourceType::Custom(synthdef_id));
+        let saw = state.add_instrument(SourceType::Saw);
+        state.instruments.instrument_mut(custom).unwrap().name = "lead".to_string();
+        state.instruments.instrument_mut(custom).unwrap().color = Some(ColorTag::Cyan);
+        state.instruments.instrument_mut(custom).unwrap().trim = -6.5;
+        // Duplicate names are allowed
+        state.instruments.instrument_mut(saw).unwrap().name = "lead".to_string();
+
+        let (_, loaded) = round_trip(&state);
+
+        let custom = loaded.instrument(custom).unwrap();
+        assert_eq!(custom.name, "lead");
+        assert_eq!(custom.color, Some(ColorTag::Cyan));
+        assert_eq!(custom.trim, -6.5);
+        assert_eq!(custom.source, SourceType::Custom(synthdef_id));
+        let saw = loaded.instrument(saw).unwrap();
+        assert_eq!(saw.name, "lead");
+        assert_eq!(saw.color, None);
+        assert_eq!(saw.trim, 0.0);
+    }
+
+    /// Every row of every table in storage order, minus the save timestamps
+    fn dump_tables(path: &Path) -> Vec<String> {
+        let conn = SqlConnection::open(path).unwrap();
+        let tables: Vec<String> = conn
+            .prepare("SELECT name FROM sqlite_master WHERE type = 'table' ORDER BY name")
+            .unwrap()
+            .query_map([], |row| row.get(0))
+            .unwrap()
+            .flatten()
+            .collect();
+        let mut rows = Vec::new();
+        for table in tables {
+            let mut stmt = conn.prepare(&format!("SELECT * FROM {}", table)).unwrap();
+            let columns: Vec<String> = stmt.column_names().iter().map(|c| c.to_string()).collect();
+            let mut query = stmt.query([]).unwrap();
+            while let Some(row) = query.next().unwrap() {
+                let values: Vec<String> = (0..columns.len())
+                    .map(|i| format!("{:?}", row.get_ref(i).unwrap()))
+                    .collect();
+                rows.push(format!("{}: {}", table, values.join(", ")));
+            }
+        }
+        rows
+    }
+
+    #[test]
+    fn test_resave_writes_rows_in_the_same_order() {
+        let mut state = AppState::new();
+        for _ in 0..6 {
+            state.add_instrument(SourceType::Saw);
+        }
+        for track in 0..6 {
+            state.session.piano_roll.add_note(track, 60 + track as u8, 0, 120, 100);
+            state.session.piano_roll.add_note(track, 48, 480, 120, 90);
+            state.session.piano_roll.add_note(track, 50, 960, 120, 90);
+            state.session.piano_roll.make_clip(track, 960, 1920);
+        }
+
+        let dir = tempfile::tempdir().unwrap();
+        let first = dir.path().join("first.ilex");
+        let second = dir.path().join("second.ilex");
+        save_project(&first, &state.session, &state.instruments).unwrap();
+        // Loading rebuilds the track map, so iterating it would come out in a new order
+        let (session, instruments, _) = load_project(&first).unwrap();
+        save_project(&second, &session, &instruments).unwrap();
+
+        // Rows first, for a readable failure, then the files themselves
+        assert_eq!(dump_tables(&first), dump_tables(&second));
+        assert!(std::fs::read(&first).unwrap() == std::fs::read(&second).unwrap());
+        assert_eq!(session.piano_roll.track_order, state.session.piano_roll.track_order);
+    }
+
+    #[test]
+    fn test_output_channels_round_trip() {
+        let mut state = AppState::new();
+        state.session.master_output_channel = 2;
+        state.session.bus_mut(3).unwrap().output_channel = 4;
+
+        let (session, _) = round_trip(&state);
+        assert_eq!(session.master_output_channel, 2);
+        assert_eq!(session.bus(3).unwrap().output_channel, 4);
+        // Unassigned buses keep plain stereo
+        assert_eq!(session.bus(1).unwrap().output_channel, 0);
+    }
+
+    #[test]
+    fn test_ui_state_round_trip_and_clamp() {
+        let mut state = AppState::new();
+        state.add_instrument(SourceType::Saw);
+        state.add_instrument(SourceType::Saw);
+        state.session.mixer_selection = MixerSelection::Instrument(1);
+        state.session.ui = UiState {
+            active_pane: Some("mixer".into()),
+            piano_roll: PianoRollView { cursor_tick: 960, cursor_pitch: 64, track: 1, zoom_level: 4 },
+        };
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("project.ilex");
+        save_project(&path, &state.session, &state.instruments).unwrap();
+        let (session, _, _) = load_project(&path).unwrap();
+        assert_eq!(session.ui, state.session.ui);
+        assert_eq!(session.mixer_selection, MixerSelection::Instrument(1));
+
+        // A selection past the remaining instruments clamps to the last one
+        let first = state.instruments.instruments[0].id;
+        state.instruments.instruments.retain(|i| i.id == first);
+        save_project(&path, &state.session, &state.instruments).unwrap();
+        let (session, _, _) = load_project(&path).unwrap();
+        assert_eq!(session.mixer_selection, MixerSelection::Instrument(0));
+
+        // Files from before the table load with the defaults
+        let conn = SqlConnection::open(&path).unwrap();
+        conn.execute_batch("DROP TABLE ui_state;").unwrap();
+        drop(conn);
+        let (session, _, _) = load_project(&path).unwrap();
+        assert_eq!(session.ui, UiState::default());
+        assert_eq!(session.mixer_selection, MixerSelection::default());
+    }
+
+    #[test]
+    fn test_solo_mode_round_trip() {
+        let mut state = AppState::new();
+        let (session, _) = round_trip(&state);
+        assert_eq!(session.solo_mode, SoloMode::InPlace);
+
+        state.session.solo_mode = SoloMode::Afl;
+        let (session, _) = round_trip(&state);
+        assert_eq!(session.solo_mode, SoloMode::Afl);
+    }
+
+    #[test]
+    fn test_midi_thru_round_trip() {
+        let mut state = AppState::new();
+        let thru = &mut state.session.midi_recording.thru;
+        thru.enabled = true;
+        thru.channel = Some(9);
+        thru.velocity_scale = 0.75;
+
+        let (loaded, _) = round_trip(&state);
+        assert_eq!(loaded.midi_recording.thru, state.session.midi_recording.thru);
+    }
+
+    #[test]
+    fn test_automation_freeze_round_trip() {
+        use crate::state::automation::{AutomationPoint, AutomationTarget, CurveType};
+
+        let mut state = AppState::new();
+        let id = state.add_instrument(SourceType::Saw);
+        let lane_id = state.session.automation.add_lane(AutomationTarget::InstrumentLevel(id));
+        let lane = state.session.automation.lane_mut(lane_id).unwrap();
+        lane.points.push(AutomationPoint::with_curve(0, 0.2, CurveType::Step));
+        lane.points.push(AutomationPoint::new(480, 0.8));
+        state.session.automation.freeze_division = Some(4);
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("project.ilex");
+        save_project(&path, &state.session, &state.instruments).unwrap();
+        let (session, _, _) = load_project(&path).unwrap();
+        assert_eq!(session.automation.freeze_division, Some(4));
+        // Frozen points are export-only; the lane comes back with its authored points
+        assert_eq!(session.automation.lanes[0].points.len(), 2);
+
+        let conn = SqlConnection::open(&path).unwrap();
+        let frozen: Vec<(u32, f64)> = conn
+            .prepare("SELECT tick, value FROM automation_frozen_points ORDER BY tick")
+            .unwrap()
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+            .unwrap()
+            .flatten()
+            .collect();
+        let ticks: Vec<u32> = frozen.iter().map(|&(t, _)| t).collect();
+        assert_eq!(ticks, [0, 120, 240, 360, 480]);
+        assert!(frozen[..4].iter().all(|&(_, v)| (v - 0.2).abs() < 1e-6));
+    }
+
+    #[test]
+    fn test_sampler_reverse_and_zones_round_trip() {
+        let mut state = AppState::new();
+        let sampler = state.add_instrument(SourceType::PitchedSampler);
+        let kit = state.add_instrument(SourceType::Kit);
+        let config = state.instruments.instrument_mut(sampler).unwrap().sampler_config.as_mut().unwrap();
+        config.path = Some("/samples/pad_trim.wav".to_string());
+        config.original_path = Some("/samples/pad.wav".to_string());
+        config.reverse = true;
+        config.layer_zones = true;
+        config.slices[0].key_range = (36, 59);
+        config.add_zone();
+        state.instruments.instrument_mut(kit).unwrap().drum_sequencer.as_mut().unwrap().pads[2].reverse = true;
+
+        let (_, loaded) = round_trip(&state);
+
+        let config = loaded.instrument(sampler).unwrap().sampler_config.as_ref().unwrap();
+        assert_eq!(config.path.as_deref(), Some("/samples/pad_trim.wav"));
+        assert_eq!(config.original_path.as_deref(), Some("/samples/pad.wav"));
+        assert!(config.reverse);
+        assert!(config.layer_zones);
+        let ranges: Vec<_> = config.slices.iter().map(|s| s.key_range).collect();
+        assert_eq!(ranges, vec![(36, 59), (60, 127)]);
+        let pads = &loaded.instrument(kit).unwrap().drum_sequencer.as_ref().unwrap().pads;
+        assert!(pads[2].reverse);
+        assert!(!pads[0].reverse);
+    }
+
+    #[test]
+    fn test_pre_zone_sampler_keeps_its_slice_mapping() {
+        let mut state = AppState::new();
+        let sampler = state.add_instrument(SourceType::PitchedSampler);
+        let config = state.instruments.instrument_mut(sampler).unwrap().sampler_config.as_mut().unwrap();
+        config.add_slice(0.5, 1.0);
+        config.add_slice(0.75, 1.0);
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("project.ilex");
+        save_project(&path, &state.session, &state.instruments).unwrap();
+        // Rewind the file to how projects looked before keyboard zones
+        let conn = SqlConnection::open(&path).unwrap();
+        conn.execute_batch(
+            "ALTER TABLE sampler_slices DROP COLUMN key_low;
+             ALTER TABLE sampler_slices DROP COLUMN key_high;
+             ALTER TABLE sampler_configs DROP COLUMN layer_zones;
+             ALTER TABLE sampler_configs DROP COLUMN legacy_mapping;",
+        ).unwrap();
+        drop(conn);
+
+        let (session, loaded, _) = load_project(&path).unwrap();
+        let config = loaded.instrument(sampler).unwrap().sampler_config.as_ref().unwrap();
+        assert!(config.legacy_mapping);
+        let played: Vec<_> = (60..63).map(|note| config.zones_for_note(note)[0].id).collect();
+        assert_eq!(played, vec![0, 1, 2]);
+        assert!(config.slices.iter().all(|s| config.zone_root(s) == 60));
+
+        // The flag is saved, so the project keeps the mapping from then on
+        save_project(&path, &session, &loaded).unwrap();
+        let (_, reloaded, _) = load_project(&path).unwrap();
+        assert!(reloaded.instrument(sampler).unwrap().sampler_config.as_ref().unwrap().legacy_mapping);
+    }
+
+    #[test]
+    fn test_new_sampler_never_gets_the_legacy_mapping() {
+        let mut state = AppState::new();
+        let sampler = state.add_instrument(SourceType::PitchedSampler);
+        let config = state.instruments.instrument_mut(sampler).unwrap().sampler_config.as_mut().unwrap();
+        // Full-range slices look like a pre-zone project but are plain overlapping zones
+        config.add_slice(0.5, 1.0);
+        config.add_slice(0.75, 1.0);
+
+        let (_, loaded) = round_trip(&state);
+        let config = loaded.instrument(sampler).unwrap().sampler_config.as_ref().unwrap();
+        assert!(!config.legacy_mapping);
+        assert_eq!(config.zones_for_note(62)[0].id, 0);
+    }
+
+    #[test]
+    fn test_velocity_layers_round_trip() {
+        use crate::state::sampler::VelocityLayer;
+
+        let mut state = AppState::new();
+        let sampler = state.add_instrument(SourceType::PitchedSampler);
+        let kit = state.add_instrument(SourceType::Kit);
+        let config = state.instruments.instrument_mut(sampler).unwrap().sampler_config.as_mut().unwrap();
+        config.crossfade_layers = true;
+        let slice_id = config.slices[0].id;
+        config.slices[0].layers.push(VelocityLayer { buffer_id: 20005, path: "soft.wav".into(), vel_range: (1, 40) });
+        let seq = state.instruments.instrument_mut(kit).unwrap().drum_sequencer.as_mut().unwrap();
+        seq.pads[1].crossfade = true;
+        seq.pads[1].layers.push(VelocityLayer { buffer_id: 10003, path: "a.wav".into(), vel_range: (43, 84) });
+        seq.pads[1].layers.push(VelocityLayer { buffer_id: 10004, path: "b.wav".into(), vel_range: (85, 127) });
+
+        let (_, loaded) = round_trip(&state);
+
+        let config = loaded.instrument(sampler).unwrap().sampler_config.as_ref().unwrap();
+        assert!(config.crossfade_layers);
+        let slice = config.slices.iter().find(|s| s.id == slice_id).unwrap();
+        assert_eq!(slice.layers.len(), 1);
+        assert_eq!(slice.layers[0].vel_range, (1, 40));
+        assert!(loaded.next_sampler_buffer_id > 20005);
+
+        let seq = loaded.instrument(kit).unwrap().drum_sequencer.as_ref().unwrap();
+        assert!(seq.pads[1].crossfade);
+        let ids: Vec<_> = seq.pads[1].layers.iter().map(|l| l.buffer_id).collect();
+        assert_eq!(ids, vec![10003, 10004]);
+        assert_eq!(seq.pads[1].layers[1].path, "b.wav");
+        assert!(seq.pads[0].layers.is_empty());
+        assert!(seq.next_buffer_id > 10004);
+    }
+
+    #[test]
+    fn test_round_robin_samples_round_trip() {
+        use crate::state::drum_sequencer::RoundRobinSample;
+
+        let mut state = AppState::new();
+        let kit = state.add_instrument(SourceType::Kit);
+        let seq = state.instruments.instrument_mut(kit).unwrap().drum_sequencer.as_mut().unwrap();
+        seq.pads[3].buffer_id = Some(10000);
+        seq.pads[3].path = Some("a.wav".into());
+        seq.pads[3].round_robin.push(RoundRobinSample { buffer_id: 10007, path: "b.wav".into() });
+        seq.pads[3].round_robin.push(RoundRobinSample { buffer_id: 10002, path: "c.wav".into() });
+
+        let (_, loaded) = round_trip(&state);
+
+        let seq = loaded.instrument(kit).unwrap().drum_sequencer.as_ref().unwrap();
+        let paths: Vec<_> = seq.pads[3].round_robin.iter().map(|s| s.path.as_str()).collect();
+        assert_eq!(paths, vec!["b.wav", "c.wav"]);
+        assert!(seq.next_buffer_id > 10007);
+        assert!(seq.round_robin.iter().all(|&c| c == 0));
+    }
+
+    #[test]
+    fn test_grooves_and_nudges_round_trip() {
+        let mut state = AppState::new();
+        let kit = state.add_instrument(SourceType::Kit);
+        let seq = state.instruments.instrument_mut(kit).unwrap().drum_sequencer.as_mut().unwrap();
+        seq.pattern_mut().steps[0][1].active = true;
+        GrooveTemplate::swing(62).apply_to_pattern(seq.pattern_mut(), 1.0);
+        seq.pattern_mut().steps[0][1].groove_velocity = 1.25;
+        let extracted = GrooveTemplate::extract_from_pattern("", seq.pattern(), 16);
+        state.session.add_groove(extracted.clone());
+
+        let (session, loaded) = round_trip(&state);
+
+        assert_eq!(session.grooves, state.session.grooves);
+        assert_eq!(session.selected_groove, 4);
+        assert_eq!(session.selected_groove().unwrap().steps, extracted.steps);
+        let seq = loaded.instrument(kit).unwrap().drum_sequencer.as_ref().unwrap();
+        assert!((seq.pattern().steps[0][1].nudge - 0.24).abs() < 1e-5);
+        assert_eq!(seq.pattern().steps[0][1].groove_velocity, 1.25);
+    }
+
+    #[test]
+    fn test_step_locks_round_trip() {
+        use crate::state::drum_sequencer::LockParam;
+
+        let mut state = AppState::new();
+        let kit = state.add_instrument(SourceType::Kit);
+        let seq = state.instruments.instrument_mut(kit).unwrap().drum_sequencer.as_mut().unwrap();
+        seq.patterns[2].steps[3][5].active = true;
+        seq.patterns[2].steps[3][5].set_lock(LockParam::Pitch, -5.0);
+        seq.patterns[2].steps[3][5].set_lock(LockParam::SliceEnd, 0.4);
+        seq.patterns[2].steps[3][6].active = true;
+
+        let (_, loaded) = round_trip(&state);
+
+        let seq = loaded.instrument(kit).unwrap().drum_sequencer.as_ref().unwrap();
+        let step = &seq.patterns[2].steps[3][5];
+        assert_eq!(step.lock(LockParam::Pitch), Some(-5.0));
+        assert!((step.lock(LockParam::SliceEnd).unwrap() - 0.4).abs() < 1e-6);
+        assert_eq!(step.lock(LockParam::Level), None);
+        assert!(seq.patterns[2].steps[3][6].locks.is_empty());
+    }
+
+    #[test]
+    fn test_follow_actions_round_trip() {
+        let mut state = AppState::new();
+        let kit = state.add_instrument(SourceType::Kit);
+        let seq = state.instruments.instrument_mut(kit).unwrap().drum_sequencer.as_mut().unwrap();
+        seq.patterns[0].follow_action = Some((1, 4));
+        seq.patterns[3].follow_action = Some((3, 1));
+
+        let (_, loaded) = round_trip(&state);
+
+        let seq = loaded.instrument(kit).unwrap().drum_sequencer.as_ref().unwrap();
+        let follows: Vec<_> = seq.patterns.iter().map(|p| p.follow_action).collect();
+        assert_eq!(follows, [Some((1, 4)), None, None, Some((3, 1))]);
+    }
+
+    #[test]
+    fn test_tempo_sync_round_trip() {
+        let mut state = AppState::new();
+        let id = state.add_instrument(SourceType::Saw);
+        let inst = state.instruments.instrument_mut(id).unwrap();
+        inst.effects = vec![EffectSlot::new(EffectType::Delay), EffectSlot::new(EffectType::Delay)];
+        inst.effects[0].sync = true;
+        inst.effects[0].division = NoteDivision::EighthDotted;
+        inst.lfos[0].sync = true;
+        inst.lfos[0].division = NoteDivision::HalfTriplet;
+        inst.lfos[0].retrigger = true;
+        inst.lfos[0].rate = 5.5;
+
+        let (_, loaded) = round_trip(&state);
+
+        let lfo = &loaded.instrument(id).unwrap().lfos[0];
+        assert!(lfo.sync && lfo.retrigger);
+        assert_eq!(lfo.division, NoteDivision::HalfTriplet);
+        assert_eq!(lfo.rate, 5.5);
+        let effects = &loaded.instrument(id).unwrap().effects;
+        assert!(effects[0].sync);
+        assert_eq!(effects[0].division, NoteDivision::EighthDotted);
+        assert!(!effects[1].sync);
+        assert_eq!(effects[1].division, NoteDivision::Quarter);
+    }
+
+    #[test]
+    fn test_transpose_round_trip() {
+        let mut state = AppState::new();
+        let id = state.add_instrument(SourceType::Saw);
+        state.session.piano_roll.add_note(0, 60, 0, 480, 100);
+        state.session.transpose = -5;
+
+        let (session, _) = round_trip(&state);
+        assert_eq!(session.transpose, -5);
+        // Stored pitches are untouched
+        assert_eq!(session.piano_roll.tracks[&id].notes[0].pitch, 60);
+    }
+
+    #[test]
+    fn test_key_range_round_trip() {
+        let mut state = AppState::new();
+        let split = state.add_instrument(SourceType::Saw);
+        let inst = state.instruments.instrument_mut(split).unwrap();
+        inst.key_low = 36;
+        inst.key_high = 59;
+        let full = state.add_instrument(SourceType::Sin);
+
+        let (_, loaded) = round_trip(&state);
+
+        let inst = loaded.instrument(split).unwrap();
+        assert_eq!((inst.key_low, inst.key_high), (36, 59));
+        let inst = loaded.instrument(full).unwrap();
+        assert_eq!((inst.key_low, inst.key_high), (0, 127));
+    }
+
+    #[test]
+    fn test_glide_and_keytrack_round_trip() {
+        let mut state = AppState::new();
+        let id = state.add_instrument(SourceType::Saw);
+        let inst = state.instruments.instrument_mut(id).unwrap();
+        inst.polyphonic = false;
+        inst.glide = 0.15;
+        let mut filter = FilterConfig::new(FilterType::Lpf);
+        filter.keytrack = 0.75;
+        inst.filter = Some(filter);
+        let other = state.add_instrument(SourceType::Sin);
+
+        let (_, loaded) = round_trip(&state);
+
+        let inst = loaded.instrument(id).unwrap();
+        assert_eq!(inst.glide, 0.15);
+        assert_eq!(inst.filter.as_ref().unwrap().keytrack, 0.75);
+        assert_eq!(loaded.instrument(other).unwrap().glide, 0.0);
+    }
+
+    #[test]
+    fn test_unison_round_trip() {
+        let mut state = AppState::new();
+        let id = state.add_instrument(SourceType::Saw);
+        let inst = state.instruments.instrument_mut(id).unwrap();
+        inst.unison_voices = 5;
+        inst.detune = 18.5;
+        inst.voice_spread = 0.75;
+        inst.voice_spread_mode = VoiceSpreadMode::Order;
+
+        let (_, loaded) = round_trip(&state);
+
+        let inst = loaded.instrument(id).unwrap();
+        assert_eq!((inst.unison_voices, inst.detune), (5, 18.5));
+        assert_eq!((inst.voice_spread, inst.voice_spread_mode), (0.75, VoiceSpreadMode::Order));
+    }
+
+    #[test]
+    fn test_mod_matrix_round_trip() {
+        let mut state = AppState::new();
+        let id = state.add_instrument(SourceType::Saw);
+        let inst = state.instruments.instrument_mut(id).unwrap();
+        inst.lfos.push(LfoConfig { enabled: true, rate: 0.25, shape: LfoShape::Saw, ..LfoConfig::default() });
+        inst.mod_envelopes.push(EnvConfig { attack: 0.2, decay: 0.4, sustain: 0.5, release: 1.0 });
+        inst.mod_routings.push(ModRouting { amount: -0.5, ..ModRouting::new(ModRouteSource::Lfo(1), LfoTarget::Pitch) });
+        inst.mod_routings.push(ModRouting::new(ModRouteSource::Envelope(0), LfoTarget::Amplitude));
+        let expected = inst.mod_routings.clone();
+
+        let (_, loaded) = round_trip(&state);
+
+        let inst = loaded.instrument(id).unwrap();
+        assert_eq!(inst.mod_routings, expected);
+        assert_eq!(inst.lfos.len(), 2);
+        assert_eq!(inst.lfos[1].rate, 0.25);
+        assert_eq!(inst.lfos[1].shape, LfoShape::Saw);
+        assert_eq!(inst.mod_envelopes[0].release, 1.0);
+    }
+
+    #[test]
+    fn test_single_lfo_project_loads_as_one_routing() {
+        let mut state = AppState::new();
+        let id = state.add_instrument(SourceType::Saw);
+        state.instruments.instrument_mut(id).unwrap().lfos[0].enabled = true;
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("project.ilex");
+        save_project(&path, &state.session, &state.instruments).unwrap();
+        // Rewind the file to how projects looked before the mod matrix
+        let conn = SqlConnection::open(&path).unwrap();
+        conn.execute_batch(
+            "DELETE FROM instrument_modulations WHERE position IS NOT NULL;
+             ALTER TABLE instrument_modulations DROP COLUMN source_index;
+             ALTER TABLE instrument_modulations DROP COLUMN destination;
+             ALTER TABLE instrument_modulations DROP COLUMN amount;
+             UPDATE instruments SET lfo_target = 'pitch';",
+        ).unwrap();
+        drop(conn);
+
+        let (_, loaded, _) = load_project(&path).unwrap();
+        let inst = loaded.instrument(id).unwrap();
+        assert_eq!(inst.lfos.len(), 1);
+        assert!(inst.lfos[0].enabled);
+        assert_eq!(
+            inst.mod_routings,
+            [ModRouting::new(ModRouteSource::Lfo(0), LfoTarget::Pitch)]
+        );
+    }
+
+    #[test]
+    fn test_instrument_without_routings_stays_unrouted() {
+        let mut state = AppState::new();
+        let id = state.add_instrument(SourceType::Saw);
+        let routed = state.add_instrument(SourceType::Saw);
+        state.instruments.instrument_mut(id).unwrap().mod_routings.clear();
+        let inst = state.instruments.instrument_mut(routed).unwrap();
+        inst.mod_envelopes.push(EnvConfig::default());
+        inst.mod_routings = vec![ModRouting::new(ModRouteSource::Envelope(0), LfoTarget::Pitch)];
+
+        let (_, loaded) = round_trip(&state);
+
+        assert!(loaded.instrument(id).unwrap().mod_routings.is_empty());
+        assert_eq!(
+            loaded.instrument(routed).unwrap().mod_routings,
+            [ModRouting::new(ModRouteSource::Envelope(0), LfoTarget::Pitch)]
+        );
+    }
+
+    #[test]
+    fn test_clips_round_trip() {
+        let mut state = AppState::new();
+        state.add_instrument(SourceType::Saw);
+        let pr = &mut state.session.piano_roll;
+        pr.add_note(0, 60, 0, 240, 100);
+        pr.add_note(0, 67, 480, 120, 80);
+        pr.add_note(0, 72, 3840, 240, 100);
+        pr.make_clip(0, 0, 1920);
+        pr.duplicate_clip(0, 0);
+        pr.track_at_mut(0).unwrap().clips[1].loops = 4;
+        pr.track_at_mut(0).unwrap().clips[1].notes[1].groove_velocity = 1.25;
+        pr.track_at_mut(0).unwrap().notes[0].groove_velocity = 0.75;
+
+        let (session, _) = round_trip(&state);
+
+        let track = session.piano_roll.track_at(0).unwrap();
+        assert_eq!(track.notes.len(), 1);
+        let clips: Vec<_> = track.clips.iter().map(|c| (c.name.as_str(), c.start, c.length, c.loops, c.notes.len())).collect();
+        assert_eq!(clips, [("Clip 1", 0, 1920, 1, 2), ("Clip 1", 1920, 1920, 4, 2)]);
+        assert_eq!(track.clips[1].notes[1].velocity, 80);
+        assert_eq!(track.clips[1].notes[1].groove_velocity, 1.25);
+        assert_eq!(track.notes[0].groove_velocity, 0.75);
+    }
+
+    #[test]
+    fn test_markers_round_trip() {
+        let mut state = AppState::new();
+        state.session.piano_roll.add_marker(1920, "chorus".to_string());
+        state.session.piano_roll.add_marker(0, "intro".to_string());
+
+        let (session, _) = round_trip(&state);
+
+        assert_eq!(session.piano_roll.markers, state.session.piano_roll.markers);
+    }
+
+    #[test]
+    fn test_synthdef_dir_round_trip() {
+        let mut state = AppState::new();
+        assert_eq!(round_trip(&state).0.synthdef_dir, None);
+
+        state.session.synthdef_dir = Some(PathBuf::from("/music/defs"));
+        let (session, _) = round_trip(&state);
+        assert_eq!(session.synthdef_dir, Some(PathBuf::from("/music/defs")));
+    }
+
+    #[test]
+    fn test_load_repairs_orphaned_references() {
+        use crate::state::automation::AutomationTarget;
+
+        let mut state = AppState::new();
+        let id = state.add_instrument(SourceType::Saw);
+        state.session.automation.add_lane(AutomationTarget::InstrumentLevel(id));
+        state.session.automation.add_lane(AutomationTarget::InstrumentLevel(99));
+        state.session.piano_roll.add_track(99);
+        state.instruments.instrument_mut(id).unwrap().output_target = OutputTarget::Bus(99);
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("project.ilex");
+        save_project(&path, &state.session, &state.instruments).unwrap();
+        let conn = SqlConnection::open(&path).unwrap();
+        conn.execute("INSERT INTO instrument_sends (instrument_id, bus_id, level, enabled) VALUES (99, 1, 0.5, 1)", []).unwrap();
+        drop(conn);
+
+        let (session, instruments, repairs) = load_project(&path).unwrap();
+        let targets: Vec<_> = session.automation.lanes.iter().map(|l| l.target.clone()).collect();
+        assert_eq!(targets, [AutomationTarget::InstrumentLevel(id)]);
+        assert_eq!(session.piano_roll.track_order, [id]);
+        assert!(!session.piano_roll.tracks.contains_key(&99));
+        assert_eq!(instruments.instrument(id).unwrap().output_target, OutputTarget::Master);
+        assert_eq!(repairs, [
+            "dropped 1 automation lane for missing targets",
+            "dropped 1 piano roll track without an instrument",
+            "dropped 1 send to missing buses or instruments",
+            "moved 1 output from missing buses to master",
+        ]);
+
+        // Once saved repaired, the project loads clean
+        save_project(&path, &session, &instruments).unwrap();
+        assert!(load_project(&path).unwrap().2.is_empty());
+    }
+
+    #[test]
+    fn test_bus_effects_round_trip() {
+        let mut state = AppState::new();
+        let id = state.add_instrument(SourceType::Saw);
+        let bus = state.session.bus_mut(2).unwrap();
+        bus.effects.push(EffectSlot::for_bus(EffectType::Reverb, &ParamDefaults::default()));
+        bus.effects.push(EffectSlot::new(EffectType::Delay));
+        bus.effects[1].enabled = false;
+        // An instrument effect keeps to its own table
+        state.instruments.instrument_mut(id).unwrap().effects.push(EffectSlot::new(EffectType::Gate));
+
+        let (session, instruments) = round_trip(&state);
+
+        let effects = &session.bus(2).unwrap().effects;
+        let types: Vec<_> = effects.iter().map(|e| (e.effect_type, e.enabled)).collect();
+        assert_eq!(types, [(EffectType::Reverb, true), (EffectType::Delay, false)]);
+        assert_eq!(effects[0].mix(), 1.0);
+        assert!(session.bus(1).unwrap().effects.is_empty());
+        assert_eq!(instruments.instrument(id).unwrap().effects.len(), 1);
+    }
+
+    #[test]
+    fn test_rng_seed_round_trip() {
+        let mut state = AppState::new();
+        // Seeds use all 64 bits, past what a signed SQLite integer holds as positive
+        state.session.rng_seed = u64::MAX - 6;
+        assert_eq!(round_trip(&state).0.rng_seed, u64::MAX - 6);
+    }
+}
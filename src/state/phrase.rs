@@ -0,0 +1,223 @@
+//! Phrase library: named note snippets saved from a piano roll selection and
+//! inserted into any project later. Kept in one file under the config dir, apart
+//! from projects, so riffs carry over between them.
+
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use super::piano_roll::{rescale_tick, Note};
+
+/// One note of a phrase, relative to the phrase's start and root
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct PhraseNote {
+    /// Ticks after the first note
+    pub tick: u32,
+    /// Semitones from the root
+    pub pitch: i8,
+    pub duration: u32,
+    pub velocity: u8,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Phrase {
+    pub name: String,
+    /// Pitch the phrase was saved at (its first note), for inserting untransposed
+    pub root: u8,
+    /// Resolution `tick` and `duration` are in
+    pub ticks_per_beat: u32,
+    pub notes: Vec<PhraseNote>,
+}
+
+impl Phrase {
+    /// Normalize notes to start at tick 0, pitched relative to the first note (the
+    /// lowest one where several start together)
+    pub fn from_notes(name: impl Into<String>, notes: &[Note], ticks_per_beat: u32) -> Self {
+        let mut sorted = notes.to_vec();
+        sorted.sort_by_key(|n| (n.tick, n.pitch));
+        let first_tick = sorted.first().map_or(0, |n| n.tick);
+        let root = sorted.first().map_or(60, |n| n.pitch);
+        Self {
+            name: name.into(),
+            root,
+            ticks_per_beat,
+            notes: sorted
+                .iter()
+                .map(|n| PhraseNote {
+                    tick: n.tick - first_tick,
+                    pitch: (n.pitch as i16 - root as i16) as i8,
+                    duration: n.duration,
+                    velocity: n.played_velocity(),
+                })
+                .collect(),
+        }
+    }
+
+    /// The notes with the root at `root`, rescaled to `ticks_per_beat` and starting
+    /// at tick 0. Notes transposed off the keyboard are left out.
+    pub fn notes_at(&self, root: u8, ticks_per_beat: u32) -> Vec<Note> {
+        self.notes
+            .iter()
+            .filter_map(|n| {
+                let pitch = u8::try_from(root as i16 + n.pitch as i16).ok().filter(|&p| p <= 127)?;
+                Some(Note::new(
+                    rescale_tick(n.tick, self.ticks_per_beat, ticks_per_beat),
+                    rescale_tick(n.duration, self.ticks_per_beat, ticks_per_beat).max(1),
+                    pitch,
+                    n.velocity,
+                ))
+            })
+            .collect()
+    }
+
+    /// Length in beats, from the first note's start to the last note's end
+    pub fn beats(&self) -> f32 {
+        let end = self.notes.iter().map(|n| n.tick + n.duration).max().unwrap_or(0);
+        end as f32 / self.ticks_per_beat.max(1) as f32
+    }
+
+    /// Lowest and highest note, in semitones from the root
+    pub fn span(&self) -> (i8, i8) {
+        let low = self.notes.iter().map(|n| n.pitch).min().unwrap_or(0);
+        let high = self.notes.iter().map(|n| n.pitch).max().unwrap_or(0);
+        (low, high)
+    }
+}
+
+#[derive(Default, Serialize, Deserialize)]
+struct PhraseFile {
+    #[serde(default, rename = "phrase")]
+    phrases: Vec<Phrase>,
+}
+
+/// Saved phrases and the file they live in. Without a file (as in tests) the
+/// library only lasts the session.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PhraseLibrary {
+    pub phrases: Vec<Phrase>,
+    path: Option<PathBuf>,
+}
+
+impl PhraseLibrary {
+    pub fn default_path() -> Option<PathBuf> {
+        dirs::config_dir().map(|d| d.join("ilex").join("phrases.toml"))
+    }
+
+    /// Read the library at `path`; a missing file gives an empty one that is
+    /// written there on the first save. A file that can't be read or parsed is an
+    /// error, so it is never overwritten.
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let text = match std::fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+            Err(e) => return Err(format!("Couldn't read {}: {}", path.display(), e)),
+        };
+        let file: PhraseFile = toml::from_str(&text)
+            .map_err(|e| format!("Couldn't parse {}: {}", path.display(), e))?;
+        Ok(Self { phrases: file.phrases, path: Some(path.to_path_buf()) })
+    }
+
+    pub fn save(&self) -> Result<(), String> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+        }
+        let file = PhraseFile { phrases: self.phrases.clone() };
+        let text = toml::to_string(&file).map_err(|e| e.to_string())?;
+        std::fs::write(path, text).map_err(|e| e.to_string())
+    }
+
+    /// Add a phrase, replacing any with the same name. Returns its index.
+    pub fn add(&mut self, phrase: Phrase) -> usize {
+        match self.phrases.iter().position(|p| p.name == phrase.name) {
+            Some(idx) => {
+                self.phrases[idx] = phrase;
+                idx
+            }
+            None => {
+                self.phrases.push(phrase);
+                self.phrases.len() - 1
+            }
+        }
+    }
+
+    pub fn remove(&mut self, idx: usize) -> Option<Phrase> {
+        (idx < self.phrases.len()).then(|| self.phrases.remove(idx))
+    }
+
+    /// "Phrase N" for the first N not already taken
+    pub fn next_name(&self) -> String {
+        (1..)
+            .map(|n| format!("Phrase {}", n))
+            .find(|name| !self.phrases.iter().any(|p| &p.name == name))
+            .unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn note(tick: u32, pitch: u8) -> Note {
+        Note::new(tick, 240, pitch, 100)
+    }
+
+    #[test]
+    fn test_phrase_inserts_transposed_and_rescaled() {
+        let phrase = Phrase::from_notes("riff", &[note(1200, 67), note(960, 64), note(960, 60)], 480);
+        assert_eq!(phrase.root, 60);
+        assert_eq!(phrase.notes[0], PhraseNote { tick: 0, pitch: 0, duration: 240, velocity: 100 });
+        assert_eq!((phrase.notes[2].tick, phrase.notes[2].pitch), (240, 7));
+        assert_eq!(phrase.span(), (0, 7));
+        assert_eq!(phrase.beats(), 1.0);
+
+        // Placed a fifth down, in a project at a quarter of the resolution
+        let notes = phrase.notes_at(55, 120);
+        let placed: Vec<(u32, u8, u32)> = notes.iter().map(|n| (n.tick, n.pitch, n.duration)).collect();
+        assert_eq!(placed, [(0, 55, 60), (0, 59, 60), (60, 62, 60)]);
+
+        // Notes pushed off the keyboard are dropped
+        assert_eq!(phrase.notes_at(121, 480).len(), 2);
+    }
+
+    #[test]
+    fn test_library_persists_and_replaces_by_name() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("ilex").join("phrases.toml");
+        let mut library = PhraseLibrary::load(&path).unwrap();
+        assert!(library.phrases.is_empty());
+        assert_eq!(library.next_name(), "Phrase 1");
+
+        library.add(Phrase::from_notes("Phrase 1", &[note(0, 60)], 480));
+        library.add(Phrase::from_notes("bass", &[note(0, 36), note(480, 43)], 960));
+        assert_eq!(library.add(Phrase::from_notes("Phrase 1", &[note(0, 62), note(0, 65)], 480)), 0);
+        assert_eq!(library.phrases.len(), 2);
+        assert_eq!(library.next_name(), "Phrase 2");
+        library.save().unwrap();
+
+        let reloaded = PhraseLibrary::load(&path).unwrap();
+        assert_eq!(reloaded, library);
+        assert_eq!(reloaded.phrases[0].notes.len(), 2);
+
+        let mut reloaded = reloaded;
+        assert_eq!(reloaded.remove(1).map(|p| p.name), Some("bass".to_string()));
+        assert_eq!(reloaded.remove(5), None);
+    }
+
+    #[test]
+    fn test_unreadable_library_is_never_overwritten() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("phrases.toml");
+        std::fs::write(&path, "[[phrase]\nname = ").unwrap();
+        let error = PhraseLibrary::load(&path).unwrap_err();
+        assert!(error.contains("phrases.toml"), "{}", error);
+
+        // What the app falls back to keeps the session's phrases off disk
+        let mut library = PhraseLibrary::default();
+        library.add(Phrase::from_notes("riff", &[note(0, 60)], 480));
+        library.save().unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "[[phrase]\nname = ");
+    }
+}
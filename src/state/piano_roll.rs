@@ -1,22 +1,175 @@
 use std::collections::HashMap;
 
+use serde::{Deserialize, Serialize};
+
 use super::instrument::InstrumentId;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
 pub struct Note {
     pub tick: u32,
     pub duration: u32,
     pub pitch: u8,
     pub velocity: u8,
+    /// Velocity scale from the last groove applied, on top of `velocity`
+    pub groove_velocity: f32,
+}
+
+impl Note {
+    pub fn new(tick: u32, duration: u32, pitch: u8, velocity: u8) -> Self {
+        Self { tick, duration, pitch, velocity, groove_velocity: 1.0 }
+    }
+
+    /// Velocity the note plays at, with the groove's scale applied
+    pub fn played_velocity(&self) -> u8 {
+        (self.velocity as f32 * self.groove_velocity).round().clamp(1.0, 127.0) as u8
+    }
 }
 
+/// Rescale a tick position from one resolution to another, rounding to nearest
+pub fn rescale_tick(tick: u32, from_tpb: u32, to_tpb: u32) -> u32 {
+    let from = from_tpb.max(1) as u64;
+    ((tick as u64 * to_tpb as u64 + from / 2) / from).min(u32::MAX as u64) as u32
+}
+
+/// Round a tick to the nearest line of a grid `grid` ticks apart
+pub fn snap_to_grid(tick: u32, grid: u32) -> u32 {
+    let grid = grid.max(1) as u64;
+    (((tick as u64 + grid / 2) / grid) * grid).min(u32::MAX as u64) as u32
+}
+
+/// Most passes a looped clip can play
+pub const MAX_CLIP_LOOPS: u32 = 64;
+
+/// Named bundle of notes placed on a track. Note ticks are relative to `start`; the
+/// clip plays `loops` passes back to back, each `length` ticks long.
+#[derive(Debug, Clone)]
+pub struct Clip {
+    pub name: String,
+    pub start: u32,
+    pub length: u32,
+    pub loops: u32,
+    pub notes: Vec<Note>,
+}
+
+impl Clip {
+    pub fn new(name: impl Into<String>, start: u32, length: u32) -> Self {
+        Self { name: name.into(), start, length: length.max(1), loops: 1, notes: Vec::new() }
+    }
+
+    /// Tick where the last pass ends
+    pub fn end(&self) -> u32 {
+        self.start + self.length * self.loops.max(1)
+    }
+
+    pub fn contains(&self, tick: u32) -> bool {
+        tick >= self.start && tick < self.end()
+    }
+
+    /// Notes starting in `start..end` on the timeline, one per pass they fall in. Notes
+    /// past `length` are cut off, and each is trimmed to its pass so a loop never runs
+    /// into the next repeat.
+    pub fn notes_between(&self, start: u32, end: u32) -> Vec<Note> {
+        let mut notes = Vec::new();
+        for pass in 0..self.loops.max(1) {
+            let pass_start = self.start + pass * self.length;
+            if pass_start >= end {
+                break;
+            }
+            if pass_start + self.length <= start {
+                continue;
+            }
+            for note in self.notes.iter().filter(|n| n.tick < self.length) {
+                let tick = pass_start + note.tick;
+                if tick >= start && tick < end {
+                    let duration = note.duration.min(self.length - note.tick);
+                    notes.push(Note { tick, duration, ..*note });
+                }
+            }
+        }
+        notes
+    }
+}
+
+/// Note edits in the piano roll (toggle, move, transpose, quantize, velocity, ...)
+/// work on a track's loose notes; clip contents are shown but not edited in place.
+/// Unpacking a clip turns its playback back into loose notes. Loose notes are never
+/// moved into clips on their own, whether written, recorded or loaded from a project
+/// saved before clips: only making a clip or consolidating does that.
 #[derive(Debug, Clone)]
 pub struct Track {
     pub module_id: InstrumentId,
+    /// Notes placed directly on the timeline, outside any clip
     pub notes: Vec<Note>,
+    /// Clips, kept sorted by start
+    pub clips: Vec<Clip>,
     pub polyphonic: bool,
 }
 
+impl Track {
+    pub fn new(module_id: InstrumentId) -> Self {
+        Self { module_id, notes: Vec::new(), clips: Vec::new(), polyphonic: true }
+    }
+
+    /// Every note starting in `start..end`, loose notes and clip playback alike
+    pub fn notes_between(&self, start: u32, end: u32) -> Vec<Note> {
+        let mut notes: Vec<Note> = self.notes.iter()
+            .filter(|n| n.tick >= start && n.tick < end)
+            .cloned()
+            .collect();
+        notes.extend(self.clip_notes_between(start, end));
+        notes
+    }
+
+    /// Notes played by clips starting in `start..end`
+    pub fn clip_notes_between(&self, start: u32, end: u32) -> Vec<Note> {
+        self.clips.iter()
+            .filter(|c| c.start < end && c.end() > start)
+            .flat_map(|c| c.notes_between(start, end))
+            .collect()
+    }
+
+    /// Run `edit` over the loose notes and then each clip's notes, the clip notes at
+    /// their timeline position in the clip's first pass, so timeline-based edits
+    /// (grooves) land in clips too. Clip notes pulled before the clip start stay at
+    /// its start.
+    pub fn edit_on_timeline(&mut self, mut edit: impl FnMut(&mut Vec<Note>)) {
+        edit(&mut self.notes);
+        self.notes.sort_by_key(|n| (n.tick, n.pitch));
+        for clip in &mut self.clips {
+            for note in &mut clip.notes {
+                note.tick += clip.start;
+            }
+            edit(&mut clip.notes);
+            for note in &mut clip.notes {
+                note.tick = note.tick.saturating_sub(clip.start);
+            }
+            clip.notes.sort_by_key(|n| (n.tick, n.pitch));
+        }
+    }
+
+    /// Insert a clip keeping clips sorted by start; returns its index
+    pub fn insert_clip(&mut self, clip: Clip) -> usize {
+        let idx = self.clips.partition_point(|c| c.start <= clip.start);
+        self.clips.insert(idx, clip);
+        idx
+    }
+
+    /// Name for a new clip that doesn't clash with existing ones
+    fn next_clip_name(&self) -> String {
+        (1..)
+            .map(|n| format!("Clip {}", n))
+            .find(|name| !self.clips.iter().any(|c| &c.name == name))
+            .unwrap_or_default()
+    }
+}
+
+/// Named cue point on the timeline
+#[derive(Debug, Clone, PartialEq)]
+pub struct Marker {
+    pub tick: u32,
+    pub name: String,
+}
+
 #[derive(Debug, Clone)]
 pub struct PianoRollState {
     pub tracks: HashMap<InstrumentId, Track>,
@@ -29,6 +182,8 @@ pub struct PianoRollState {
     pub loop_end: u32,
     pub playhead: u32,
     pub ticks_per_beat: u32,
+    /// Timeline markers, kept sorted by tick (at most one per tick)
+    pub markers: Vec<Marker>,
 }
 
 impl PianoRollState {
@@ -44,19 +199,13 @@ impl PianoRollState {
             loop_end: 480 * 4, // 4 beats
             playhead: 0,
             ticks_per_beat: 480,
+            markers: Vec::new(),
         }
     }
 
     pub fn add_track(&mut self, instrument_id: InstrumentId) {
         if !self.tracks.contains_key(&instrument_id) {
-            self.tracks.insert(
-                instrument_id,
-                Track {
-                    module_id: instrument_id,
-                    notes: Vec::new(),
-                    polyphonic: true,
-                },
-            );
+            self.tracks.insert(instrument_id, Track::new(instrument_id));
             self.track_order.push(instrument_id);
         }
     }
@@ -66,6 +215,47 @@ impl PianoRollState {
         self.track_order.retain(|&id| id != instrument_id);
     }
 
+    /// Swap two tracks' positions in track_order
+    pub fn swap_tracks(&mut self, a: InstrumentId, b: InstrumentId) {
+        let pos_a = self.track_order.iter().position(|&id| id == a);
+        let pos_b = self.track_order.iter().position(|&id| id == b);
+        if let (Some(pos_a), Some(pos_b)) = (pos_a, pos_b) {
+            self.track_order.swap(pos_a, pos_b);
+        }
+    }
+
+    /// Add a marker at `tick`, replacing the name of any marker already there
+    pub fn add_marker(&mut self, tick: u32, name: String) {
+        match self.markers.binary_search_by_key(&tick, |m| m.tick) {
+            Ok(idx) => self.markers[idx].name = name,
+            Err(idx) => self.markers.insert(idx, Marker { tick, name }),
+        }
+    }
+
+    pub fn remove_marker(&mut self, tick: u32) -> bool {
+        match self.markers.binary_search_by_key(&tick, |m| m.tick) {
+            Ok(idx) => {
+                self.markers.remove(idx);
+                true
+            }
+            Err(_) => false,
+        }
+    }
+
+    pub fn marker_at(&self, tick: u32) -> Option<&Marker> {
+        self.markers.iter().find(|m| m.tick == tick)
+    }
+
+    /// First marker strictly after `tick`
+    pub fn next_marker(&self, tick: u32) -> Option<&Marker> {
+        self.markers.iter().find(|m| m.tick > tick)
+    }
+
+    /// Last marker strictly before `tick`
+    pub fn prev_marker(&self, tick: u32) -> Option<&Marker> {
+        self.markers.iter().rev().find(|m| m.tick < tick)
+    }
+
     /// Get the track at the given index in track_order
     pub fn track_at(&self, index: usize) -> Option<&Track> {
         self.track_order
@@ -86,16 +276,300 @@ impl PianoRollState {
             if let Some(pos) = track.notes.iter().position(|n| n.pitch == pitch && n.tick == tick) {
                 track.notes.remove(pos);
             } else {
-                track.notes.push(Note {
-                    tick,
-                    duration,
-                    pitch,
-                    velocity,
-                });
+                track.notes.push(Note::new(tick, duration, pitch, velocity));
             }
         }
     }
 
+    /// Add a note, replacing any existing note at the same pitch and tick. Returns
+    /// the note it replaced.
+    pub fn add_note(&mut self, track_index: usize, pitch: u8, tick: u32, duration: u32, velocity: u8) -> Option<Note> {
+        let track = self.track_at_mut(track_index)?;
+        let replaced = track.notes.iter().position(|n| n.pitch == pitch && n.tick == tick).map(|i| track.notes.remove(i));
+        track.notes.push(Note::new(tick, duration, pitch, velocity));
+        replaced
+    }
+
+    /// Where a live-recorded note played at `tick` lands with input quantize on: the
+    /// nearest `grid` line, or the other neighbouring line when a note of the same pitch
+    /// already starts there, so fast repeats stay distinct notes rather than toggling
+    /// each other off. Falls back to `tick` itself when both lines are taken.
+    pub fn live_quantize_tick(&self, track_index: usize, pitch: u8, tick: u32, grid: u32) -> u32 {
+        let grid = grid.max(1);
+        let below = tick / grid * grid;
+        let above = below + grid;
+        let (nearest, other) = if tick - below < above - tick { (below, above) } else { (above, below) };
+        // A note snapped onto the loop end would never play; it belongs at the loop start
+        let wrap = |t: u32| if self.looping && t >= self.loop_end { self.loop_start } else { t };
+        let taken = |t: u32| {
+            self.track_at(track_index)
+                .is_some_and(|track| track.notes.iter().any(|n| n.pitch == pitch && n.tick == t))
+        };
+        [wrap(nearest), wrap(other)].into_iter().find(|&t| !taken(t)).unwrap_or(tick)
+    }
+
+    /// Loop between two points given in either order. A loop that would be shorter
+    /// than `min_length` (at least one tick) is stretched past its start to that length.
+    pub fn set_loop(&mut self, a: u32, b: u32, min_length: u32) {
+        let (start, end) = (a.min(b), a.max(b));
+        self.loop_start = start;
+        self.loop_end = end.max(start.saturating_add(min_length.max(1)));
+    }
+
+    /// Start of the earliest and end of the latest of a track's loose notes at these indices
+    pub fn notes_span(&self, track_index: usize, indices: &[usize]) -> Option<(u32, u32)> {
+        let track = self.track_at(track_index)?;
+        let notes: Vec<&Note> = indices.iter().filter_map(|&i| track.notes.get(i)).collect();
+        let start = notes.iter().map(|n| n.tick).min()?;
+        let end = notes.iter().map(|n| n.tick + n.duration).max()?;
+        Some((start, end))
+    }
+
+    /// Loose note to land on stepping from the cursor: the next (or previous) by start
+    /// tick, with pitch ordering the notes of a chord. None past the first or last note.
+    pub fn adjacent_note(&self, track_index: usize, tick: u32, pitch: u8, forward: bool) -> Option<usize> {
+        let track = self.track_at(track_index)?;
+        let key = |i: usize| (track.notes[i].tick, track.notes[i].pitch);
+        let candidates = (0..track.notes.len()).filter(|&i| {
+            if forward { key(i) > (tick, pitch) } else { key(i) < (tick, pitch) }
+        });
+        if forward { candidates.min_by_key(|&i| key(i)) } else { candidates.max_by_key(|&i| key(i)) }
+    }
+
+    /// Loose note closest to the cursor: ticks away from the note's span (zero inside
+    /// it), then semitones away
+    pub fn nearest_note(&self, track_index: usize, tick: u32, pitch: u8) -> Option<usize> {
+        let track = self.track_at(track_index)?;
+        (0..track.notes.len()).min_by_key(|&i| {
+            let note = &track.notes[i];
+            let end = note.tick + note.duration.max(1) - 1;
+            let ticks = note.tick.saturating_sub(tick).max(tick.saturating_sub(end));
+            (ticks, note.pitch.abs_diff(pitch), note.tick)
+        })
+    }
+
+    /// Remove the note at the given pitch and tick, if any
+    pub fn remove_note(&mut self, track_index: usize, pitch: u8, tick: u32) {
+        if let Some(track) = self.track_at_mut(track_index) {
+            track.notes.retain(|n| !(n.pitch == pitch && n.tick == tick));
+        }
+    }
+
+    /// Indices of a track's loose notes enclosed by a marquee: pitch in `low..=high`
+    /// and start in `start..end`. Notes count by their start alone: one sounding into
+    /// the box from before it is left out, one running past its end is taken whole.
+    /// Clip notes are never included; unpack the clip to edit them.
+    pub fn notes_in_rect(&self, track_index: usize, start: u32, end: u32, low: u8, high: u8) -> Vec<usize> {
+        self.track_at(track_index).map_or_else(Vec::new, |track| {
+            track.notes.iter()
+                .enumerate()
+                .filter(|(_, n)| n.tick >= start && n.tick < end && (low..=high).contains(&n.pitch))
+                .map(|(i, _)| i)
+                .collect()
+        })
+    }
+
+    /// Shift notes by `semitones`. Nothing moves (false) if any note would leave the
+    /// MIDI range, so chords keep their shape.
+    pub fn transpose_notes(&mut self, track_index: usize, indices: &[usize], semitones: i8) -> bool {
+        let Some(track) = self.track_at_mut(track_index) else {
+            return false;
+        };
+        let fits = indices.iter()
+            .filter_map(|&i| track.notes.get(i))
+            .all(|n| (0..=127).contains(&(n.pitch as i16 + semitones as i16)));
+        if !fits {
+            return false;
+        }
+        for (i, note) in track.notes.iter_mut().enumerate() {
+            if indices.contains(&i) {
+                note.pitch = (note.pitch as i16 + semitones as i16) as u8;
+            }
+        }
+        true
+    }
+
+    /// Change the velocity of the notes at these indices by `delta`. The notes move
+    /// together: the delta is cut short where it would push any of them past 1 or
+    /// 127, so the differences between them survive. Returns the delta applied.
+    pub fn adjust_velocities(&mut self, track_index: usize, indices: &[usize], delta: i8) -> i8 {
+        let Some(track) = self.track_at_mut(track_index) else {
+            return 0;
+        };
+        let velocities = || indices.iter().filter_map(|&i| track.notes.get(i)).map(|n| n.velocity as i16);
+        let (Some(low), Some(high)) = (velocities().min(), velocities().max()) else {
+            return 0;
+        };
+        let delta = (delta as i16).clamp((1 - low).min(0), (127 - high).max(0));
+        for (i, note) in track.notes.iter_mut().enumerate() {
+            if indices.contains(&i) {
+                note.velocity = (note.velocity as i16 + delta) as u8;
+            }
+        }
+        delta as i8
+    }
+
+    /// Move note starts to the nearest `grid` line, keeping their lengths
+    pub fn quantize_notes(&mut self, track_index: usize, indices: &[usize], grid: u32) {
+        let grid = grid.max(1);
+        if let Some(track) = self.track_at_mut(track_index) {
+            for (i, note) in track.notes.iter_mut().enumerate() {
+                if indices.contains(&i) {
+                    note.tick = (note.tick + grid / 2) / grid * grid;
+                }
+            }
+        }
+    }
+
+    /// Copies of notes with ticks relative to the earliest, for `paste_notes`
+    pub fn copy_notes(&self, track_index: usize, indices: &[usize]) -> Vec<Note> {
+        let Some(track) = self.track_at(track_index) else {
+            return Vec::new();
+        };
+        let mut notes: Vec<Note> = indices.iter().filter_map(|&i| track.notes.get(i)).copied().collect();
+        let first = notes.iter().map(|n| n.tick).min().unwrap_or(0);
+        for note in &mut notes {
+            note.tick -= first;
+        }
+        notes.sort_by_key(|n| (n.tick, n.pitch));
+        notes
+    }
+
+    /// Write copied notes starting at `tick`, replacing any at the same pitch and tick.
+    /// They keep the velocity they played at, groove included.
+    pub fn paste_notes(&mut self, track_index: usize, tick: u32, notes: &[Note]) {
+        for note in notes {
+            self.add_note(track_index, note.pitch, tick + note.tick, note.duration, note.played_velocity());
+        }
+    }
+
+    /// Index of the loose note sounding at `pitch` and `tick` (the latest-starting one
+    /// where notes overlap)
+    pub fn note_under(&self, track_index: usize, pitch: u8, tick: u32) -> Option<usize> {
+        self.track_at(track_index)?.notes.iter()
+            .enumerate()
+            .filter(|(_, n)| n.pitch == pitch && tick >= n.tick && tick < n.tick + n.duration)
+            .max_by_key(|(_, n)| n.tick)
+            .map(|(i, _)| i)
+    }
+
+    /// Remove the loose notes at these indices
+    pub fn remove_notes(&mut self, track_index: usize, indices: &[usize]) {
+        if let Some(track) = self.track_at_mut(track_index) {
+            let mut i = 0;
+            track.notes.retain(|_| {
+                let keep = !indices.contains(&i);
+                i += 1;
+                keep
+            });
+        }
+    }
+
+    /// Clear `start..end` on a track's loose notes. Notes starting inside the range
+    /// are removed whole, even if they ring on past its end; notes starting before it
+    /// and sounding into it are cut off at `start`. Returns how many notes were removed.
+    pub fn delete_range(&mut self, track_index: usize, start: u32, end: u32) -> usize {
+        let Some(track) = self.track_at_mut(track_index) else {
+            return 0;
+        };
+        let before = track.notes.len();
+        track.notes.retain(|n| !(n.tick >= start && n.tick < end));
+        for note in &mut track.notes {
+            if note.tick < start && note.tick + note.duration > start {
+                note.duration = start - note.tick;
+            }
+        }
+        before - track.notes.len()
+    }
+
+    /// Name of the clip playing a note at `pitch`/`tick` on a track that has no loose
+    /// note there. Clip notes aren't edited in place, so a note edit there has
+    /// nothing to act on.
+    pub fn clip_note_at(&self, track_index: usize, pitch: u8, tick: u32) -> Option<&str> {
+        let track = self.track_at(track_index)?;
+        if track.notes.iter().any(|n| n.pitch == pitch && n.tick == tick) {
+            return None;
+        }
+        track.clips.iter()
+            .find(|c| c.notes_between(tick, tick + 1).iter().any(|n| n.pitch == pitch))
+            .map(|c| c.name.as_str())
+    }
+
+    /// Index of the clip under `tick` on a track (the latest-starting one where clips overlap)
+    pub fn clip_at(&self, track_index: usize, tick: u32) -> Option<usize> {
+        self.track_at(track_index)?.clips.iter().rposition(|c| c.contains(tick))
+    }
+
+    /// Move a track's loose notes starting in `start..end` into a new clip spanning the
+    /// range. Returns the clip's index.
+    pub fn make_clip(&mut self, track_index: usize, start: u32, end: u32) -> Option<usize> {
+        if end <= start {
+            return None;
+        }
+        let track = self.track_at_mut(track_index)?;
+        let mut clip = Clip::new(track.next_clip_name(), start, end - start);
+        track.notes.retain(|n| {
+            let inside = n.tick >= start && n.tick < end;
+            if inside {
+                clip.notes.push(Note { tick: n.tick - start, ..*n });
+            }
+            !inside
+        });
+        clip.notes.sort_by_key(|n| (n.tick, n.pitch));
+        Some(track.insert_clip(clip))
+    }
+
+    /// Copy a clip to start right where it ends. Returns the copy's index.
+    pub fn duplicate_clip(&mut self, track_index: usize, clip_index: usize) -> Option<usize> {
+        let track = self.track_at_mut(track_index)?;
+        let mut copy = track.clips.get(clip_index)?.clone();
+        copy.start = copy.end();
+        Some(track.insert_clip(copy))
+    }
+
+    /// Shift a clip by `delta` ticks (stopping at zero). Returns its new index.
+    pub fn move_clip(&mut self, track_index: usize, clip_index: usize, delta: i64) -> Option<usize> {
+        let track = self.track_at_mut(track_index)?;
+        if clip_index >= track.clips.len() {
+            return None;
+        }
+        let mut clip = track.clips.remove(clip_index);
+        clip.start = (clip.start as i64 + delta).max(0) as u32;
+        Some(track.insert_clip(clip))
+    }
+
+    /// Write a clip's playback back onto the track as loose notes (loops expanded)
+    /// and remove the clip
+    pub fn unpack_clip(&mut self, track_index: usize, clip_index: usize) {
+        let Some(track) = self.track_at_mut(track_index) else {
+            return;
+        };
+        if clip_index >= track.clips.len() {
+            return;
+        }
+        let clip = track.clips.remove(clip_index);
+        for note in clip.notes_between(clip.start, clip.end()) {
+            track.notes.retain(|n| !(n.pitch == note.pitch && n.tick == note.tick));
+            track.notes.push(note);
+        }
+        track.notes.sort_by_key(|n| (n.tick, n.pitch));
+    }
+
+    /// Migrate flat timelines to clips: each track's loose notes become one clip from
+    /// the start of the song, rounded up to whole bars
+    pub fn consolidate_into_clips(&mut self) {
+        let bar = self.ticks_per_bar();
+        for track in self.tracks.values_mut() {
+            let Some(end) = track.notes.iter().map(|n| n.tick + n.duration).max() else {
+                continue;
+            };
+            let mut clip = Clip::new(track.next_clip_name(), 0, end.div_ceil(bar) * bar);
+            clip.notes = std::mem::take(&mut track.notes);
+            clip.notes.sort_by_key(|n| (n.tick, n.pitch));
+            track.insert_clip(clip);
+        }
+    }
+
     /// Find a note at the given pitch and tick (exact match on tick start)
     #[allow(dead_code)]
     pub fn find_note(&self, track_index: usize, pitch: u8, tick: u32) -> Option<&Note> {
@@ -139,9 +613,82 @@ impl PianoRollState {
         tick as f32 / self.ticks_per_beat as f32
     }
 
+    /// Ticks per beat of the meter. `ticks_per_beat` counts quarter notes, so in
+    /// 7/8 a meter beat is an eighth note (half a quarter).
+    pub fn ticks_per_meter_beat(&self) -> u32 {
+        (self.ticks_per_beat * 4 / self.time_signature.1.max(1) as u32).max(1)
+    }
+
     /// Total ticks per bar
     pub fn ticks_per_bar(&self) -> u32 {
-        self.ticks_per_beat * self.time_signature.0 as u32
+        self.ticks_per_meter_beat() * self.time_signature.0.max(1) as u32
+    }
+
+    /// Convert a tick to (bar, beat, tick-within-beat); bar and beat are 1-based
+    pub fn tick_to_bbt(&self, tick: u32) -> (u32, u32, u32) {
+        let per_bar = self.ticks_per_bar();
+        let per_beat = self.ticks_per_meter_beat();
+        let in_bar = tick % per_bar;
+        (tick / per_bar + 1, in_bar / per_beat + 1, in_bar % per_beat)
+    }
+
+    /// Convert (bar, beat, tick-within-beat) back to ticks; bar and beat are 1-based
+    pub fn bbt_to_tick(&self, bar: u32, beat: u32, tick: u32) -> u32 {
+        bar.saturating_sub(1) * self.ticks_per_bar()
+            + beat.saturating_sub(1) * self.ticks_per_meter_beat()
+            + tick
+    }
+
+    /// Tick where the last note or clip across all tracks ends
+    pub fn content_end(&self) -> u32 {
+        self.tracks
+            .values()
+            .flat_map(|t| {
+                t.notes.iter().map(|n| n.tick + n.duration).chain(t.clips.iter().map(Clip::end))
+            })
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Last bar that contains any notes or clips (1 when empty)
+    pub fn last_bar(&self) -> u32 {
+        self.tick_to_bbt(self.content_end().saturating_sub(1)).0
+    }
+
+    /// Change the tick resolution, rescaling notes, clips, loop points, markers and
+    /// the playhead so everything stays at the same musical position. Lengths that
+    /// round to zero are kept at one tick. Automation lives outside the piano roll;
+    /// use `SessionState::set_ticks_per_beat` to rescale both.
+    pub fn set_ticks_per_beat(&mut self, ticks_per_beat: u32) {
+        let ticks_per_beat = ticks_per_beat.max(1);
+        let from = self.ticks_per_beat;
+        if ticks_per_beat == from {
+            return;
+        }
+        let scale = |tick: u32| rescale_tick(tick, from, ticks_per_beat);
+        let rescale_notes = |notes: &mut Vec<Note>| {
+            for note in notes {
+                note.tick = scale(note.tick);
+                note.duration = scale(note.duration).max(1);
+            }
+        };
+        for track in self.tracks.values_mut() {
+            rescale_notes(&mut track.notes);
+            for clip in &mut track.clips {
+                clip.start = scale(clip.start);
+                clip.length = scale(clip.length).max(1);
+                rescale_notes(&mut clip.notes);
+            }
+        }
+        self.loop_start = scale(self.loop_start);
+        self.loop_end = scale(self.loop_end).max(self.loop_start + 1);
+        self.playhead = scale(self.playhead);
+        for marker in &mut self.markers {
+            marker.tick = scale(marker.tick);
+        }
+        // Markers squeezed onto one tick by a coarser grid keep the first name
+        self.markers.dedup_by_key(|m| m.tick);
+        self.ticks_per_beat = ticks_per_beat;
     }
 }
 
@@ -150,3 +697,319 @@ impl Default for PianoRollState {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_live_quantize_keeps_fast_notes_distinct() {
+        let mut pr = PianoRollState::new();
+        pr.add_track(1);
+        // 1/16 grid at 480 tpb
+        assert_eq!(pr.live_quantize_tick(0, 60, 130, 120), 120);
+        assert_eq!(pr.live_quantize_tick(0, 60, 190, 120), 240);
+        pr.toggle_note(0, 60, 120, 60, 100);
+        // A second hit snapping to the same line takes the next one over
+        assert_eq!(pr.live_quantize_tick(0, 60, 150, 120), 240);
+        // Other pitches can share the line (chords)
+        assert_eq!(pr.live_quantize_tick(0, 64, 150, 120), 120);
+        // Both neighbours taken: keep the played position
+        pr.toggle_note(0, 60, 240, 60, 100);
+        assert_eq!(pr.live_quantize_tick(0, 60, 150, 120), 150);
+
+        // Snapping onto the loop end wraps to the loop start
+        pr.looping = true;
+        assert_eq!(pr.live_quantize_tick(0, 62, 1900, 120), 0);
+    }
+
+    #[test]
+    fn test_set_ticks_per_beat_rescales_and_round_trips() {
+        let mut pr = PianoRollState::new();
+        pr.add_track(1);
+        pr.add_note(0, 60, 240, 120, 100);
+        pr.add_note(0, 62, 1000, 1, 100);
+        pr.make_clip(0, 960, 1920);
+        pr.add_marker(1920, "B".to_string());
+        pr.playhead = 720;
+
+        pr.set_ticks_per_beat(960);
+        assert_eq!(pr.ticks_per_beat, 960);
+        assert_eq!((pr.loop_start, pr.loop_end, pr.playhead), (0, 3840, 1440));
+        assert_eq!(pr.markers[0].tick, 3840);
+        let track = pr.track_at(0).unwrap();
+        assert_eq!((track.notes[0].tick, track.notes[0].duration), (480, 240));
+        assert_eq!((track.clips[0].start, track.clips[0].length), (1920, 1920));
+        assert_eq!((track.clips[0].notes[0].tick, track.clips[0].notes[0].duration), (80, 2));
+
+        pr.set_ticks_per_beat(480);
+        let track = pr.track_at(0).unwrap();
+        assert_eq!((track.notes[0].tick, track.notes[0].duration), (240, 120));
+        assert_eq!((track.clips[0].notes[0].tick, track.clips[0].notes[0].duration), (40, 1));
+        assert_eq!(pr.playhead, 720);
+
+        // Too short for the coarser grid: clamped to a tick rather than lost
+        pr.set_ticks_per_beat(24);
+        let note = pr.track_at(0).unwrap().clips[0].notes[0];
+        assert_eq!((note.tick, note.duration), (2, 1));
+    }
+
+    #[test]
+    fn test_marquee_selection_and_edits() {
+        let mut pr = PianoRollState::new();
+        pr.add_track(1);
+        pr.add_note(0, 60, 0, 960, 100); // starts before the box, sounds into it
+        pr.add_note(0, 62, 480, 960, 100); // starts inside, runs past the end
+        pr.add_note(0, 64, 530, 120, 100);
+        pr.add_note(0, 72, 600, 120, 100); // above the box
+        let selected = pr.notes_in_rect(0, 480, 960, 60, 67);
+        assert_eq!(selected, [1, 2]);
+
+        pr.quantize_notes(0, &selected, 120);
+        let notes = &pr.track_at(0).unwrap().notes;
+        assert_eq!((notes[1].tick, notes[2].tick, notes[0].tick), (480, 480, 0));
+
+        assert!(pr.transpose_notes(0, &selected, 12));
+        let pitches: Vec<u8> = pr.track_at(0).unwrap().notes.iter().map(|n| n.pitch).collect();
+        assert_eq!(pitches, [60, 74, 76, 72]);
+        // Out of range for one note: nothing moves
+        assert!(!pr.transpose_notes(0, &selected, 60));
+        assert_eq!(pr.track_at(0).unwrap().notes[1].pitch, 74);
+
+        pr.track_at_mut(0).unwrap().notes[2].velocity = 80;
+        assert_eq!(pr.adjust_velocities(0, &selected, 20), 20);
+        // Near the top the whole selection stops together, keeping the gap
+        assert_eq!(pr.adjust_velocities(0, &selected, 20), 7);
+        let velocities: Vec<u8> = pr.track_at(0).unwrap().notes.iter().map(|n| n.velocity).collect();
+        assert_eq!(velocities, [100, 127, 107, 100]);
+        assert_eq!(pr.adjust_velocities(0, &selected, 10), 0);
+        assert_eq!(pr.adjust_velocities(0, &selected, -127), -106);
+        assert_eq!(pr.track_at(0).unwrap().notes[2].velocity, 1);
+        assert_eq!(pr.adjust_velocities(0, &[], 10), 0);
+
+        let copied = pr.copy_notes(0, &selected);
+        assert_eq!(copied.iter().map(|n| (n.tick, n.pitch)).collect::<Vec<_>>(), [(0, 74), (0, 76)]);
+        pr.paste_notes(0, 1920, &copied);
+        assert_eq!(pr.notes_in_rect(0, 1920, 2400, 0, 127).len(), 2);
+    }
+
+    #[test]
+    fn test_delete_notes() {
+        let mut pr = PianoRollState::new();
+        pr.add_track(1);
+        pr.add_note(0, 60, 0, 960, 100); // sounds into the range
+        pr.add_note(0, 60, 480, 960, 100); // starts inside, runs past the end
+        pr.add_note(0, 64, 600, 60, 100);
+        pr.add_note(0, 67, 1200, 120, 100); // after the range
+
+        assert_eq!(pr.note_under(0, 60, 500), Some(1));
+        assert_eq!(pr.note_under(0, 64, 700), None);
+
+        assert_eq!(pr.delete_range(0, 480, 960), 2);
+        let notes: Vec<(u32, u32, u8)> = pr.track_at(0).unwrap().notes.iter()
+            .map(|n| (n.tick, n.duration, n.pitch))
+            .collect();
+        assert_eq!(notes, [(0, 480, 60), (1200, 120, 67)]);
+
+        pr.remove_notes(0, &[1]);
+        assert_eq!(pr.track_at(0).unwrap().notes.len(), 1);
+    }
+
+    #[test]
+    fn test_bbt_round_trip_in_odd_meter() {
+        let mut pr = PianoRollState::new();
+        pr.time_signature = (7, 8);
+        // 7 eighth notes of 240 ticks each
+        assert_eq!(pr.ticks_per_bar(), 1680);
+        assert_eq!(pr.tick_to_bbt(0), (1, 1, 0));
+        assert_eq!(pr.tick_to_bbt(1679), (1, 7, 239));
+        assert_eq!(pr.tick_to_bbt(1680 + 250), (2, 2, 10));
+        assert_eq!(pr.bbt_to_tick(2, 2, 10), 1680 + 250);
+    }
+
+    #[test]
+    fn test_last_bar_from_content() {
+        let mut pr = PianoRollState::new();
+        assert_eq!(pr.last_bar(), 1);
+        pr.add_track(0);
+        // Ends exactly on the bar 3 line, so bar 2 is the last with content
+        pr.add_note(0, 60, 1920 + 960, 960, 100);
+        assert_eq!(pr.last_bar(), 2);
+    }
+
+    #[test]
+    fn test_looped_clip_repeats_and_trims() {
+        let mut clip = Clip::new("c", 1920, 960);
+        clip.loops = 3;
+        clip.notes = vec![
+            Note::new(0, 240, 60, 100),
+            // Runs past the clip end: trimmed to the pass
+            Note::new(720, 480, 64, 90),
+            // Beyond the clip length: never plays
+            Note::new(960, 120, 67, 90),
+        ];
+        assert_eq!(clip.end(), 1920 + 2880);
+        let notes = clip.notes_between(0, 10_000);
+        let ticks: Vec<_> = notes.iter().map(|n| (n.tick, n.pitch, n.duration)).collect();
+        assert_eq!(
+            ticks,
+            [
+                (1920, 60, 240), (2640, 64, 240),
+                (2880, 60, 240), (3600, 64, 240),
+                (3840, 60, 240), (4560, 64, 240),
+            ]
+        );
+        // A window in the middle of the second pass
+        let window: Vec<_> = clip.notes_between(2880, 3700).iter().map(|n| n.tick).collect();
+        assert_eq!(window, [2880, 3600]);
+    }
+
+    #[test]
+    fn test_clip_make_duplicate_unpack() {
+        let mut pr = PianoRollState::new();
+        pr.add_track(0);
+        pr.add_note(0, 60, 0, 240, 100);
+        pr.add_note(0, 62, 480, 240, 100);
+        pr.add_note(0, 64, 1920, 240, 100);
+
+        let idx = pr.make_clip(0, 0, 1920).unwrap();
+        let track = pr.track_at(0).unwrap();
+        assert_eq!(track.notes.len(), 1);
+        assert_eq!(track.clips[idx].notes.len(), 2);
+        assert_eq!(pr.clip_at(0, 1000), Some(idx));
+        assert_eq!(pr.clip_at(0, 1920), None);
+        assert_eq!(pr.clip_note_at(0, 62, 480), Some("Clip 1"));
+        assert_eq!(pr.clip_note_at(0, 62, 0), None);
+        // A loose note over the clip is what gets edited
+        pr.add_note(0, 62, 480, 240, 100);
+        assert_eq!(pr.clip_note_at(0, 62, 480), None);
+        pr.toggle_note(0, 62, 480, 240, 100);
+
+        let copy = pr.duplicate_clip(0, idx).unwrap();
+        let track = pr.track_at(0).unwrap();
+        assert_eq!(track.clips[copy].start, 1920);
+        let starts: Vec<_> = track.notes_between(0, 3840).iter().map(|n| (n.tick, n.pitch)).collect();
+        assert_eq!(starts.len(), 5);
+        assert!(starts.contains(&(2400, 62)));
+
+        let moved = pr.move_clip(0, copy, 1920).unwrap();
+        assert_eq!(pr.track_at(0).unwrap().clips[moved].start, 3840);
+
+        pr.track_at_mut(0).unwrap().clips[0].loops = 2;
+        pr.unpack_clip(0, 0);
+        let track = pr.track_at(0).unwrap();
+        assert_eq!(track.clips.len(), 1);
+        let loose: Vec<_> = track.notes.iter().map(|n| n.tick).collect();
+        assert_eq!(loose, [0, 480, 1920, 1920, 2400]);
+    }
+
+    #[test]
+    fn test_timeline_edits_reach_clip_notes() {
+        let mut pr = PianoRollState::new();
+        pr.add_track(0);
+        pr.add_note(0, 60, 480, 240, 100);
+        pr.add_note(0, 62, 2400, 240, 100);
+        pr.make_clip(0, 1920, 3840);
+        // Nudge everything in 2000..3000 later; the clip note sits at 2400 on the timeline
+        pr.track_at_mut(0).unwrap().edit_on_timeline(|notes| {
+            for note in notes.iter_mut().filter(|n| (2000..3000).contains(&n.tick)) {
+                note.tick += 10;
+            }
+        });
+        let track = pr.track_at(0).unwrap();
+        assert_eq!(track.notes[0].tick, 480);
+        assert_eq!(track.clips[0].notes[0].tick, 490);
+        let played: Vec<_> = track.notes_between(0, 3840).iter().map(|n| n.tick).collect();
+        assert_eq!(played, [480, 2410]);
+    }
+
+    #[test]
+    fn test_consolidate_into_clips() {
+        let mut pr = PianoRollState::new();
+        pr.add_track(0);
+        pr.add_track(1);
+        pr.add_note(0, 60, 2000, 240, 100);
+        pr.consolidate_into_clips();
+        let track = pr.track_at(0).unwrap();
+        assert!(track.notes.is_empty());
+        assert_eq!(track.clips.len(), 1);
+        assert_eq!((track.clips[0].start, track.clips[0].length), (0, 3840));
+        assert!(pr.track_at(1).unwrap().clips.is_empty());
+        assert_eq!(pr.content_end(), 3840);
+    }
+
+    #[test]
+    fn test_markers_stay_sorted_and_navigate() {
+        let mut pr = PianoRollState::new();
+        pr.add_marker(1920, "chorus".into());
+        pr.add_marker(0, "intro".into());
+        pr.add_marker(1920, "hook".into());
+        assert_eq!(pr.markers.len(), 2);
+        assert_eq!(pr.markers[0].name, "intro");
+        assert_eq!(pr.next_marker(0).map(|m| m.name.as_str()), Some("hook"));
+        assert_eq!(pr.prev_marker(1920).map(|m| m.tick), Some(0));
+        assert!(pr.next_marker(1920).is_none());
+        assert!(pr.remove_marker(0));
+        assert!(!pr.remove_marker(0));
+    }
+
+    #[test]
+    fn test_loop_points_snap_and_stay_ordered() {
+        assert_eq!(snap_to_grid(130, 120), 120);
+        assert_eq!(snap_to_grid(180, 120), 240);
+        // A triplet grid works the same way
+        assert_eq!(snap_to_grid(170, 160), 160);
+
+        let mut pr = PianoRollState::new();
+        pr.set_loop(960, 1920, 240);
+        assert_eq!((pr.loop_start, pr.loop_end), (960, 1920));
+        // A start moved past the end swaps the points
+        pr.set_loop(2400, pr.loop_end, 240);
+        assert_eq!((pr.loop_start, pr.loop_end), (1920, 2400));
+        // An empty loop is stretched to the minimum length
+        pr.set_loop(pr.loop_start, 1920, 240);
+        assert_eq!((pr.loop_start, pr.loop_end), (1920, 2160));
+        pr.set_loop(0, 0, 0);
+        assert_eq!((pr.loop_start, pr.loop_end), (0, 1));
+    }
+
+    #[test]
+    fn test_notes_span() {
+        let mut pr = PianoRollState::new();
+        pr.add_track(1);
+        pr.add_note(0, 60, 480, 240, 100);
+        pr.add_note(0, 64, 100, 120, 100);
+        pr.add_note(0, 67, 1000, 500, 100);
+        assert_eq!(pr.notes_span(0, &[0, 1]), Some((100, 720)));
+        assert_eq!(pr.notes_span(0, &[0, 1, 2]), Some((100, 1500)));
+        assert_eq!(pr.notes_span(0, &[]), None);
+        assert_eq!(pr.notes_span(0, &[9]), None);
+    }
+
+    #[test]
+    fn test_adjacent_and_nearest_note() {
+        let mut pr = PianoRollState::new();
+        pr.add_track(1);
+        // Off-grid notes, stored out of order, with a two-note chord at 500
+        pr.add_note(0, 64, 500, 100, 100);
+        pr.add_note(0, 60, 37, 100, 100);
+        pr.add_note(0, 60, 500, 100, 100);
+        pr.add_note(0, 72, 2000, 100, 100);
+
+        assert_eq!(pr.adjacent_note(0, 0, 60, true), Some(1));
+        assert_eq!(pr.adjacent_note(0, 37, 60, true), Some(2));
+        assert_eq!(pr.adjacent_note(0, 500, 60, true), Some(0));
+        assert_eq!(pr.adjacent_note(0, 500, 64, true), Some(3));
+        // No wrapping at either end
+        assert_eq!(pr.adjacent_note(0, 2000, 72, true), None);
+        assert_eq!(pr.adjacent_note(0, 37, 60, false), None);
+        assert_eq!(pr.adjacent_note(0, 2000, 72, false), Some(0));
+        assert_eq!(pr.adjacent_note(0, 500, 64, false), Some(2));
+
+        // Inside a note's span counts as on it; pitch breaks ties
+        assert_eq!(pr.nearest_note(0, 90, 40), Some(1));
+        assert_eq!(pr.nearest_note(0, 550, 63), Some(0));
+        assert_eq!(pr.nearest_note(0, 1500, 0), Some(3));
+        assert_eq!(pr.nearest_note(1, 0, 60), None);
+    }
+}
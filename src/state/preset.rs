@@ -0,0 +1,154 @@
+//! Factory presets embedded in the binary (`presets/factory.toml`).
+//!
+//! These are read-only and never touch disk; they only use built-in sources so they
+//! work without any custom synthdefs.
+
+use std::collections::BTreeMap;
+use std::sync::OnceLock;
+
+use serde::Deserialize;
+
+use super::instrument::{EffectSlot, EffectType, EnvConfig, FilterConfig, FilterType, Instrument, SourceType, GLIDE_RANGE};
+use super::param::ParamValue;
+
+const FACTORY_PRESETS: &str = include_str!("../../presets/factory.toml");
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Preset {
+    pub name: String,
+    pub source: String,
+    #[serde(default = "default_polyphonic")]
+    pub polyphonic: bool,
+    /// Mono glide time in seconds
+    #[serde(default)]
+    pub glide: f32,
+    #[serde(default)]
+    pub params: BTreeMap<String, f32>,
+    pub filter: Option<PresetFilter>,
+    pub envelope: Option<PresetEnvelope>,
+    #[serde(default)]
+    pub effects: Vec<PresetEffect>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct PresetFilter {
+    #[serde(rename = "type")]
+    pub filter_type: String,
+    pub cutoff: f32,
+    pub resonance: f32,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct PresetEnvelope {
+    pub attack: f32,
+    pub decay: f32,
+    pub sustain: f32,
+    pub release: f32,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct PresetEffect {
+    #[serde(rename = "type")]
+    pub effect_type: String,
+    #[serde(default)]
+    pub params: BTreeMap<String, f32>,
+}
+
+#[derive(Deserialize)]
+struct PresetFile {
+    preset: Vec<Preset>,
+}
+
+fn default_polyphonic() -> bool {
+    true
+}
+
+/// The factory presets, parsed once. Presets with a non-built-in source are dropped.
+pub fn factory_presets() -> &'static [Preset] {
+    static PRESETS: OnceLock<Vec<Preset>> = OnceLock::new();
+    PRESETS.get_or_init(|| {
+        toml::from_str::<PresetFile>(FACTORY_PRESETS)
+            .map(|file| file.preset)
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|p| p.source_type().is_some())
+            .collect()
+    })
+}
+
+impl Preset {
+    /// Built-in oscillator source named by this preset
+    pub fn source_type(&self) -> Option<SourceType> {
+        SourceType::all()
+            .into_iter()
+            .filter(|s| !s.is_audio_input() && !s.is_bus_in() && !s.is_sample() && !s.is_kit())
+            .find(|s| s.short_name() == self.source)
+    }
+
+    /// Apply this preset's sound to an instrument created with `source_type()`
+    pub fn apply_to(&self, instrument: &mut Instrument) {
+        instrument.name = format!("{}-{}", self.name.to_lowercase(), instrument.id);
+        instrument.polyphonic = self.polyphonic;
+        instrument.glide = self.glide.clamp(GLIDE_RANGE.0, GLIDE_RANGE.1);
+        for param in &mut instrument.source_params {
+            if let Some(&value) = self.params.get(&param.name) {
+                param.value = ParamValue::Float(value.clamp(param.min, param.max));
+            }
+        }
+        instrument.filter = self.filter.as_ref().map(|f| {
+            let filter_type = FilterType::all()
+                .into_iter()
+                .find(|t| format!("{:?}", t).to_lowercase() == f.filter_type)
+                .unwrap_or(FilterType::Lpf);
+            let mut config = FilterConfig::new(filter_type);
+            config.cutoff.value = f.cutoff.clamp(config.cutoff.min, config.cutoff.max);
+            config.resonance.value = f.resonance.clamp(config.resonance.min, config.resonance.max);
+            config
+        });
+        if let Some(env) = &self.envelope {
+            instrument.amp_envelope = EnvConfig {
+                attack: env.attack,
+                decay: env.decay,
+                sustain: env.sustain,
+                release: env.release,
+            };
+        }
+        instrument.effects = self
+            .effects
+            .iter()
+            .filter_map(|e| {
+                let effect_type = EffectType::all()
+                    .into_iter()
+                    .find(|t| format!("{:?}", t).to_lowercase() == e.effect_type)?;
+                let mut slot = EffectSlot::new(effect_type);
+                for param in &mut slot.params {
+                    if let Some(&value) = e.params.get(&param.name) {
+                        param.value = ParamValue::Float(value.clamp(param.min, param.max));
+                    }
+                }
+                Some(slot)
+            })
+            .collect();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_factory_presets_parse_with_built_in_sources() {
+        let presets = factory_presets();
+        let names: Vec<&str> = presets.iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(names, ["Bass", "Lead", "Pad", "Pluck"]);
+
+        for preset in presets {
+            let source = preset.source_type().unwrap();
+            assert!(!source.is_custom());
+            let mut instrument = Instrument::new(3, source);
+            preset.apply_to(&mut instrument);
+            assert!(instrument.filter.is_some());
+            assert_eq!(instrument.effects.len(), preset.effects.len());
+        }
+    }
+}
@@ -0,0 +1,139 @@
+//! Background project saves and loads. Writing a large project takes long enough to
+//! stall the UI, so the work runs on a worker thread and the main loop polls for the
+//! result, like synthdef compiles. A save writes a snapshot taken when it starts, so
+//! edits made while it runs can't leave the file half old, half new.
+
+use std::path::PathBuf;
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::thread;
+
+use super::instrument_state::InstrumentState;
+use super::persistence::{load_project, save_project};
+use super::session::SessionState;
+
+/// What the worker is doing
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProjectJob {
+    Saving,
+    Loading,
+}
+
+impl ProjectJob {
+    /// Header indicator while the job runs
+    pub fn label(self) -> &'static str {
+        match self {
+            ProjectJob::Saving => "Saving...",
+            ProjectJob::Loading => "Loading...",
+        }
+    }
+}
+
+pub enum ProjectIoResult {
+    Saved(PathBuf),
+    /// The loaded project, with any repairs made to orphaned references
+    Loaded(PathBuf, Box<SessionState>, Box<InstrumentState>, Vec<String>),
+    Failed(ProjectJob, String),
+}
+
+/// One save or load at a time, run off the main thread
+#[derive(Default)]
+pub struct ProjectIo {
+    job: Option<(ProjectJob, Receiver<ProjectIoResult>)>,
+}
+
+impl ProjectIo {
+    pub fn busy(&self) -> Option<ProjectJob> {
+        self.job.as_ref().map(|(job, _)| *job)
+    }
+
+    pub fn is_loading(&self) -> bool {
+        self.busy() == Some(ProjectJob::Loading)
+    }
+
+    /// Write a snapshot of the project to `path`
+    pub fn start_save(&mut self, path: PathBuf, session: SessionState, instruments: InstrumentState) -> Result<(), String> {
+        self.start(ProjectJob::Saving, move || {
+            if let Some(parent) = path.parent() {
+                std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+            }
+            save_project(&path, &session, &instruments).map_err(|e| e.to_string())?;
+            Ok(ProjectIoResult::Saved(path))
+        })
+    }
+
+    pub fn start_load(&mut self, path: PathBuf) -> Result<(), String> {
+        self.start(ProjectJob::Loading, move || {
+            let (session, instruments, repairs) = load_project(&path).map_err(|e| e.to_string())?;
+            Ok(ProjectIoResult::Loaded(path, Box::new(session), Box::new(instruments), repairs))
+        })
+    }
+
+    fn start<F>(&mut self, job: ProjectJob, work: F) -> Result<(), String>
+    where
+        F: FnOnce() -> Result<ProjectIoResult, String> + Send + 'static,
+    {
+        if let Some(running) = self.busy() {
+            return Err(format!("Still {}", running.label().to_lowercase()));
+        }
+        let (tx, rx) = mpsc::channel();
+        self.job = Some((job, rx));
+        thread::spawn(move || {
+            let result = work().unwrap_or_else(|e| ProjectIoResult::Failed(job, e));
+            let _ = tx.send(result);
+        });
+        Ok(())
+    }
+
+    /// The finished job's result, if it is done
+    pub fn poll(&mut self) -> Option<ProjectIoResult> {
+        let (job, rx) = self.job.as_ref()?;
+        let result = match rx.try_recv() {
+            Ok(result) => result,
+            Err(TryRecvError::Empty) => return None,
+            Err(TryRecvError::Disconnected) => ProjectIoResult::Failed(*job, "worker thread stopped".to_string()),
+        };
+        self.job = None;
+        Some(result)
+    }
+
+    /// Block until the running job finishes, so quitting never cuts a save short
+    pub fn wait(&mut self) -> Option<ProjectIoResult> {
+        let (job, rx) = self.job.take()?;
+        Some(rx.recv().unwrap_or_else(|_| ProjectIoResult::Failed(job, "worker thread stopped".to_string())))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_save_then_load_in_background() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("projects").join("song.sqlite");
+        let mut io = ProjectIo::default();
+        let mut session = SessionState::new();
+        session.bpm = 97;
+
+        io.start_save(path.clone(), session, InstrumentState::new()).unwrap();
+        assert_eq!(io.busy(), Some(ProjectJob::Saving));
+        // Only one job at a time
+        assert!(io.start_load(path.clone()).is_err());
+        assert!(matches!(io.wait(), Some(ProjectIoResult::Saved(p)) if p == path));
+        assert_eq!(io.busy(), None);
+
+        io.start_load(path.clone()).unwrap();
+        assert!(io.is_loading());
+        match io.wait() {
+            Some(ProjectIoResult::Loaded(_, session, _, repairs)) => {
+                assert_eq!(session.bpm, 97);
+                assert!(repairs.is_empty());
+            }
+            _ => panic!("load failed"),
+        }
+
+        io.start_load(dir.path().join("missing").join("none.sqlite")).unwrap();
+        assert!(matches!(io.wait(), Some(ProjectIoResult::Failed(ProjectJob::Loading, _))));
+        assert!(io.poll().is_none());
+    }
+}
@@ -1,8 +1,18 @@
 #![allow(dead_code)]
 
+use super::music::pitch_name;
+
 pub type BufferId = u32;
 pub type SliceId = u32;
 
+/// Progress of a background sample load
+#[derive(Debug, Clone, PartialEq)]
+pub enum SampleLoadStatus {
+    Loading,
+    Ready,
+    Failed(String),
+}
+
 /// A loaded sample buffer
 #[derive(Debug, Clone)]
 pub struct SampleBuffer {
@@ -40,8 +50,86 @@ impl SampleBuffer {
     }
 }
 
-/// A slice within a sample buffer
-#[derive(Debug, Clone)]
+/// Velocity span (in steps) blended on each side of a layer boundary when crossfading
+pub const LAYER_CROSSFADE_WIDTH: u8 = 8;
+
+/// An extra sample played instead of the base sample for a range of velocities
+#[derive(Debug, Clone, PartialEq)]
+pub struct VelocityLayer {
+    pub buffer_id: BufferId,
+    pub path: String,
+    /// Lowest and highest velocity (inclusive, 1-127) that select this layer
+    pub vel_range: (u8, u8),
+}
+
+/// Spread `count` layers evenly over the top of the velocity range, leaving the
+/// lowest band to the base sample: with one layer the base plays 1-63 and the layer 64-127.
+pub fn spread_layer_ranges(layers: &mut [VelocityLayer]) {
+    let bands = layers.len() as u32 + 1;
+    for (i, layer) in layers.iter_mut().enumerate() {
+        let band = i as u32 + 1;
+        let lo = 1 + band * 127 / bands;
+        let hi = (band + 1) * 127 / bands;
+        layer.vel_range = (lo as u8, hi as u8);
+    }
+}
+
+/// Buffers to trigger for a velocity, with a gain for each. Velocities no layer
+/// covers play `base`, so with no layers this is always just `base` at full gain.
+/// With `crossfade`, velocities within `LAYER_CROSSFADE_WIDTH` of the boundary
+/// between two adjacent bands play both with an equal-power blend.
+pub fn layers_for_velocity(
+    base: Option<BufferId>,
+    layers: &[VelocityLayer],
+    crossfade: bool,
+    velocity: u8,
+) -> Vec<(BufferId, f32)> {
+    // Bands in velocity order, the base sample filling the space below the first layer
+    let mut bands: Vec<(Option<BufferId>, u8, u8)> = layers.iter()
+        .map(|l| (Some(l.buffer_id), l.vel_range.0, l.vel_range.1))
+        .collect();
+    bands.sort_by_key(|b| b.1);
+    let below = bands.first().map_or(127, |b| b.1.saturating_sub(1));
+    if below > 0 {
+        bands.insert(0, (base, 0, below));
+    }
+
+    let Some(idx) = bands.iter().position(|b| (b.1..=b.2).contains(&velocity)) else {
+        return base.map(|b| vec![(b, 1.0)]).unwrap_or_default();
+    };
+    let single = |buffer: Option<BufferId>| buffer.map(|b| vec![(b, 1.0)]).unwrap_or_default();
+    if !crossfade {
+        return single(bands[idx].0);
+    }
+
+    // Find an adjacent band whose boundary is within the crossfade width
+    let w = LAYER_CROSSFADE_WIDTH as f32;
+    let v = velocity as f32;
+    let neighbor = if idx + 1 < bands.len() && bands[idx + 1].1 == bands[idx].2 + 1 && v > bands[idx].2 as f32 + 0.5 - w {
+        Some((idx, idx + 1))
+    } else if idx > 0 && bands[idx - 1].2 + 1 == bands[idx].1 && v < bands[idx].1 as f32 - 0.5 + w {
+        Some((idx - 1, idx))
+    } else {
+        None
+    };
+    let Some((lower, upper)) = neighbor else {
+        return single(bands[idx].0);
+    };
+
+    // Position across the 2w-wide window centred on the boundary, 0 = all lower
+    let boundary = bands[upper].1 as f32 - 0.5;
+    let t = ((v - (boundary - w)) / (2.0 * w)).clamp(0.0, 1.0);
+    let angle = t * std::f32::consts::FRAC_PI_2;
+    [(bands[lower].0, angle.cos()), (bands[upper].0, angle.sin())]
+        .into_iter()
+        .filter_map(|(buffer, gain)| buffer.map(|b| (b, gain)))
+        .filter(|(_, gain)| *gain > 1e-3)
+        .collect()
+}
+
+/// A slice within a sample buffer. On the pitched sampler each slice is also a
+/// keyboard zone: notes in `key_range` play it, pitched relative to `root_note`.
+#[derive(Debug, Clone, PartialEq)]
 pub struct Slice {
     pub id: SliceId,
     /// Start position as a fraction of the buffer (0.0-1.0)
@@ -51,6 +139,10 @@ pub struct Slice {
     pub name: String,
     /// MIDI note this slice maps to (for chromatic/mapped mode)
     pub root_note: u8,
+    /// Lowest and highest MIDI notes (inclusive) that trigger this slice
+    pub key_range: (u8, u8),
+    /// Samples swapped in by velocity; the sampler's own buffer covers the rest
+    pub layers: Vec<VelocityLayer>,
 }
 
 impl Slice {
@@ -61,6 +153,8 @@ impl Slice {
             end: end.clamp(0.0, 1.0),
             name: format!("Slice {}", id),
             root_note: 60, // Middle C
+            key_range: (0, 127),
+            layers: Vec::new(),
         }
     }
 
@@ -73,17 +167,111 @@ impl Slice {
     pub fn duration(&self) -> f32 {
         (self.end - self.start).abs()
     }
+
+    /// Move the bounds onto a copy of the buffer that keeps only `kept` of it (start
+    /// and end fractions of the old buffer), as after trimming. A slice lying wholly
+    /// in the cut part collapses onto the nearest edge.
+    pub fn rescale_to(&mut self, kept: (f32, f32)) {
+        let span = (kept.1 - kept.0).max(f32::EPSILON);
+        self.start = ((self.start - kept.0) / span).clamp(0.0, 1.0);
+        self.end = ((self.end - kept.0) / span).clamp(0.0, 1.0);
+    }
+
+    /// Whether a MIDI note falls in this slice's key range
+    pub fn contains_note(&self, note: u8) -> bool {
+        (self.key_range.0..=self.key_range.1).contains(&note)
+    }
+
+    /// Zone as `low-high root`, in note names (the format `parse_zone` reads)
+    pub fn zone_label(&self) -> String {
+        format!(
+            "{}-{} {}",
+            pitch_name(self.key_range.0),
+            pitch_name(self.key_range.1),
+            pitch_name(self.root_note)
+        )
+    }
+}
+
+/// A zone typed as `low-high root`, optionally followed by `start-end` slice bounds
+/// (fractions of the buffer). Notes are names (`C3`, `F#2`, `Bb4`) or MIDI numbers.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ZoneSpec {
+    pub key_range: (u8, u8),
+    pub root_note: u8,
+    pub bounds: Option<(f32, f32)>,
+}
+
+/// Parse a zone such as `C2-B3 C3` or `36-59 48 0.5-1.0`. The range may be given
+/// high-to-low; it is stored low-to-high.
+pub fn parse_zone(text: &str) -> Option<ZoneSpec> {
+    let mut parts = text.split_whitespace();
+    // Octave -1 has its own '-', so try each dash until both sides are notes
+    let range = parts.next()?;
+    let (lo, hi) = range.match_indices('-')
+        .find_map(|(i, _)| Some((parse_note(&range[..i])?, parse_note(&range[i + 1..])?)))?;
+    let root_note = parse_note(parts.next()?)?;
+    let bounds = match parts.next() {
+        Some(b) => {
+            let (start, end) = b.split_once('-')?;
+            let (start, end) = (start.parse::<f32>().ok()?, end.parse::<f32>().ok()?);
+            if !(0.0..=1.0).contains(&start) || !(0.0..=1.0).contains(&end) || start >= end {
+                return None;
+            }
+            Some((start, end))
+        }
+        None => None,
+    };
+    if parts.next().is_some() {
+        return None;
+    }
+    Some(ZoneSpec { key_range: (lo.min(hi), lo.max(hi)), root_note, bounds })
+}
+
+/// Parse a MIDI note number or a note name like `C4`, `F#2`, `Bb-1`
+pub fn parse_note(text: &str) -> Option<u8> {
+    if let Ok(n) = text.parse::<u8>() {
+        return (n <= 127).then_some(n);
+    }
+    let mut chars = text.chars();
+    let base = match chars.next()?.to_ascii_uppercase() {
+        'C' => 0, 'D' => 2, 'E' => 4, 'F' => 5, 'G' => 7, 'A' => 9, 'B' => 11,
+        _ => return None,
+    };
+    let rest = chars.as_str();
+    let (accidental, octave) = match rest.chars().next()? {
+        '#' => (1, &rest[1..]),
+        'b' => (-1, &rest[1..]),
+        _ => (0, rest),
+    };
+    let octave: i32 = octave.parse().ok()?;
+    let note = (octave + 1) * 12 + base + accidental;
+    u8::try_from(note).ok().filter(|n| *n <= 127)
 }
 
 /// Sampler configuration for an instrument
 #[derive(Debug, Clone)]
 pub struct SamplerConfig {
     pub buffer_id: Option<BufferId>,
+    /// File the buffer was loaded from
+    pub path: Option<String>,
+    /// Sample as first loaded, kept while `path` points at a trimmed/normalized copy
+    pub original_path: Option<String>,
     pub slices: Vec<Slice>,
     pub selected_slice: usize,
     pub loop_mode: bool,
     /// Whether to change playback rate based on MIDI note (pitch tracking)
     pub pitch_tracking: bool,
+    /// Play every zone covering a note instead of only the first
+    pub layer_zones: bool,
+    /// Blend adjacent velocity layers near their boundaries
+    pub crossfade_layers: bool,
+    /// Play slices backwards (from slice end to start)
+    pub reverse: bool,
+    /// Keep the slice mapping of projects saved before keyboard zones: the slice
+    /// rooted on the note, else note modulo count, all pitched from middle C. Set only
+    /// when such a project is loaded, and cleared once its zones are edited.
+    pub legacy_mapping: bool,
     /// Next slice ID for auto-increment
     next_slice_id: SliceId,
 }
@@ -93,10 +281,16 @@ impl SamplerConfig {
         // Create a default full-buffer slice
         let mut config = Self {
             buffer_id: None,
+            path: None,
+            original_path: None,
             slices: Vec::new(),
             selected_slice: 0,
             loop_mode: false,
             pitch_tracking: true,
+            layer_zones: false,
+            crossfade_layers: false,
+            reverse: false,
+            legacy_mapping: false,
             next_slice_id: 0,
         };
         // Add initial full-buffer slice
@@ -155,15 +349,42 @@ impl SamplerConfig {
         self.next_slice_id = id;
     }
 
-    /// Find which slice to play for a given MIDI note (in mapped mode)
-    pub fn slice_for_note(&self, note: u8) -> Option<&Slice> {
-        // Simple mapping: notes 0-127 map to slices modulo slice count
-        if self.slices.is_empty() {
-            return None;
+    /// Slices whose key range covers a note. Where zones overlap, only the first
+    /// (lowest-index) one plays unless `layer_zones` is set. Empty means silence.
+    /// Projects from before zones keep the old mapping (see `legacy_mapping`).
+    pub fn zones_for_note(&self, note: u8) -> Vec<&Slice> {
+        if self.legacy_mapping && !self.slices.is_empty() {
+            return self.slices.iter().find(|s| s.root_note == note)
+                .or_else(|| self.slices.get(note as usize % self.slices.len()))
+                .into_iter()
+                .collect();
         }
-        // Find slice with matching root_note, or fall back to modulo mapping
-        self.slices.iter().find(|s| s.root_note == note)
-            .or_else(|| self.slices.get(note as usize % self.slices.len()))
+        let mut zones = self.slices.iter().filter(|s| s.contains_note(note));
+        if self.layer_zones {
+            zones.collect()
+        } else {
+            zones.next().into_iter().collect()
+        }
+    }
+
+    /// Note a zone plays at its recorded pitch. Under the old slice mapping every
+    /// slice was pitched from middle C, whatever its root note.
+    pub fn zone_root(&self, zone: &Slice) -> u8 {
+        if self.legacy_mapping { 60 } else { zone.root_note }
+    }
+
+    /// Add a full-buffer zone starting just above the highest existing zone
+    pub fn add_zone(&mut self) -> SliceId {
+        let low = self.slices.iter()
+            .map(|s| s.key_range.1)
+            .max()
+            .map_or(0, |hi| if hi >= 127 { 0 } else { hi + 1 });
+        let id = self.add_slice(0.0, 1.0);
+        if let Some(slice) = self.slices.last_mut() {
+            slice.key_range = (low, 127);
+            slice.root_note = if low == 0 { 60 } else { low };
+        }
+        id
     }
 }
 
@@ -243,6 +464,98 @@ mod tests {
         assert_eq!(config.slices.len(), 1);
     }
 
+    #[test]
+    fn test_zones_for_note() {
+        let mut config = SamplerConfig::new();
+        config.slices[0].key_range = (36, 59);
+        let upper = config.add_zone();
+        assert_eq!(config.slices[1].key_range, (60, 127));
+        assert_eq!(config.slices[1].root_note, 60);
+        // Overlap 55-59 with the low zone
+        config.slices[1].key_range = (55, 127);
+
+        assert_eq!(config.zones_for_note(40)[0].id, 0);
+        assert_eq!(config.zones_for_note(70)[0].id, upper);
+        // Overlap picks the first zone unless layering
+        let ids: Vec<_> = config.zones_for_note(57).iter().map(|s| s.id).collect();
+        assert_eq!(ids, vec![0]);
+        config.layer_zones = true;
+        let ids: Vec<_> = config.zones_for_note(57).iter().map(|s| s.id).collect();
+        assert_eq!(ids, vec![0, upper]);
+        // Below every zone: silent
+        assert!(config.zones_for_note(20).is_empty());
+    }
+
+    #[test]
+    fn test_legacy_mapping_keeps_the_old_slice_mapping() {
+        let mut config = SamplerConfig::new();
+        config.add_slice(0.5, 1.0);
+        config.add_slice(0.75, 1.0);
+        config.slices[2].root_note = 64;
+        let ids = |config: &SamplerConfig, note| config.zones_for_note(note).iter().map(|s| s.id).collect::<Vec<_>>();
+
+        // Full-range slices are overlapping zones; the first one plays
+        assert_eq!(ids(&config, 62), vec![0]);
+        assert_eq!(config.zone_root(&config.slices[2]), 64);
+
+        // Rooted slice first, then note modulo slice count, all pitched from middle C
+        config.legacy_mapping = true;
+        assert_eq!(ids(&config, 64), vec![2]);
+        assert_eq!(ids(&config, 61), vec![1]);
+        assert_eq!(ids(&config, 62), vec![2]);
+        assert_eq!(config.zone_root(&config.slices[2]), 60);
+    }
+
+    #[test]
+    fn test_parse_zone() {
+        let spec = parse_zone("C2-B3 C3").unwrap();
+        assert_eq!(spec.key_range, (36, 59));
+        assert_eq!(spec.root_note, 48);
+        assert_eq!(spec.bounds, None);
+
+        let spec = parse_zone("72-60 F#4 0.5-1").unwrap();
+        assert_eq!(spec.key_range, (60, 72));
+        assert_eq!(spec.root_note, 66);
+        assert_eq!(spec.bounds, Some((0.5, 1.0)));
+
+        assert_eq!(parse_zone("C-1-G9 Bb-1").unwrap().key_range, (0, 127));
+        assert_eq!(parse_zone("C-1-G9 Bb-1").unwrap().root_note, 10);
+        assert!(parse_zone("C2-B3").is_none());
+        assert!(parse_zone("C2-H3 C3").is_none());
+        assert!(parse_zone("C2-B3 C3 0.8-0.2").is_none());
+        assert_eq!(Slice::new(0, 0.0, 1.0).zone_label(), "C-1-G9 C4");
+    }
+
+    #[test]
+    fn test_layers_for_velocity() {
+        // No layers: always the base sample at full gain, as before layering existed
+        for vel in [1, 64, 127] {
+            assert_eq!(layers_for_velocity(Some(7), &[], true, vel), vec![(7, 1.0)]);
+        }
+        assert!(layers_for_velocity(None, &[], false, 100).is_empty());
+
+        let mut layers = vec![
+            VelocityLayer { buffer_id: 8, path: "mid.wav".into(), vel_range: (0, 127) },
+            VelocityLayer { buffer_id: 9, path: "hard.wav".into(), vel_range: (0, 127) },
+        ];
+        spread_layer_ranges(&mut layers);
+        assert_eq!(layers[0].vel_range, (43, 84));
+        assert_eq!(layers[1].vel_range, (85, 127));
+        assert_eq!(layers_for_velocity(Some(7), &layers, false, 20), vec![(7, 1.0)]);
+        assert_eq!(layers_for_velocity(Some(7), &layers, false, 42), vec![(7, 1.0)]);
+        assert_eq!(layers_for_velocity(Some(7), &layers, false, 43), vec![(8, 1.0)]);
+        assert_eq!(layers_for_velocity(Some(7), &layers, false, 127), vec![(9, 1.0)]);
+
+        // Crossfade blends both sides of a boundary with equal power
+        let blend = layers_for_velocity(Some(7), &layers, true, 42);
+        assert_eq!(blend.iter().map(|b| b.0).collect::<Vec<_>>(), vec![7, 8]);
+        let power: f32 = blend.iter().map(|b| b.1 * b.1).sum();
+        assert!((power - 1.0).abs() < 1e-4);
+        // Away from boundaries a single layer plays
+        assert_eq!(layers_for_velocity(Some(7), &layers, true, 64), vec![(8, 1.0)]);
+        assert_eq!(layers_for_velocity(Some(7), &layers, true, 10), vec![(7, 1.0)]);
+    }
+
     #[test]
     fn test_sample_registry() {
         let mut registry = SampleRegistry::new();
@@ -254,4 +567,19 @@ mod tests {
         registry.remove_buffer(id);
         assert!(registry.buffer(id).is_none());
     }
+
+    #[test]
+    fn test_trim_rescales_slices() {
+        let mut slice = Slice::new(0, 0.5, 0.75);
+        // Trimming the first quarter and last half leaves 0.25-0.5 of the old buffer
+        slice.rescale_to((0.25, 0.5));
+        assert_eq!((slice.start, slice.end), (1.0, 1.0));
+
+        let mut slice = Slice::new(0, 0.3, 0.6);
+        slice.rescale_to((0.2, 0.7));
+        assert!((slice.start - 0.2).abs() < 1e-6 && (slice.end - 0.8).abs() < 1e-6);
+        // Keeping everything (a normalize) leaves the bounds alone
+        slice.rescale_to((0.0, 1.0));
+        assert!((slice.start - 0.2).abs() < 1e-6 && (slice.end - 0.8).abs() < 1e-6);
+    }
 }
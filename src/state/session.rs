@@ -1,13 +1,88 @@
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+
 use super::automation::AutomationState;
 use super::custom_synthdef::CustomSynthDefRegistry;
+use super::groove::GrooveTemplate;
 use super::midi_recording::MidiRecordingState;
 use super::music::{Key, Scale};
 use super::piano_roll::PianoRollState;
-use super::instrument::MixerBus;
+use super::rng::time_seed;
+use super::instrument::{EffectSlot, Instrument, MixerBus, OutputTarget};
+use super::instrument_state::InstrumentState;
+use super::ui_state::UiState;
 
 pub const MAX_BUSES: usize = 8;
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// Tempo range accepted by the session editor and config defaults
+pub const MIN_BPM: u16 = 20;
+pub const MAX_BPM: u16 = 300;
+/// A4 reference range accepted by the session editor and config defaults
+pub const MIN_TUNING_A4: f32 = 400.0;
+pub const MAX_TUNING_A4: f32 = 480.0;
+/// Global transpose range, in semitones either way
+pub const MAX_TRANSPOSE: i8 = 24;
+/// Timeline resolution range accepted by the session editor and config defaults
+pub const MIN_TICKS_PER_BEAT: u32 = 24;
+pub const MAX_TICKS_PER_BEAT: u32 = 3840;
+/// Resolutions the session editor steps through
+pub const TICKS_PER_BEAT_CHOICES: [u32; 7] = [96, 192, 240, 480, 960, 1920, 3840];
+
+/// Hardware outputs assumed when the server's channel count isn't known
+pub const STEREO_OUTPUTS: u32 = 2;
+/// scsynth's output channel count when it isn't given one (`-o`)
+pub const SCSYNTH_DEFAULT_OUTPUTS: u32 = 8;
+
+/// Label for the hardware stereo pair starting at `channel` (0-based), as "1/2"
+pub fn output_pair_label(channel: u32) -> String {
+    format!("{}/{}", channel + 1, channel + 2)
+}
+
+/// Check that the stereo pair starting at `channel` exists on a server with `outputs` channels
+pub fn validate_output_channel(channel: u32, outputs: u32) -> Result<(), String> {
+    if channel + 2 <= outputs {
+        Ok(())
+    } else {
+        Err(format!("Out {} needs {} outputs; the server has {}", output_pair_label(channel), channel + 2, outputs))
+    }
+}
+
+/// The stereo pair after (or before) the one at `channel`, wrapping within `outputs` channels
+pub fn cycle_output_channel(channel: u32, outputs: u32, forward: bool) -> u32 {
+    let pairs = (outputs / 2).max(1);
+    let pair = (channel / 2).min(pairs - 1);
+    let next = if forward { (pair + 1) % pairs } else { (pair + pairs - 1) % pairs };
+    next * 2
+}
+
+/// How a soloed strip is heard
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SoloMode {
+    /// Solo-in-place: with its effects, pan and the buses it feeds
+    #[default]
+    InPlace,
+    /// After-fade listen: flat at its fader level, centered with its effects bypassed
+    Afl,
+}
+
+impl SoloMode {
+    pub fn name(&self) -> &'static str {
+        match self {
+            SoloMode::InPlace => "In place",
+            SoloMode::Afl => "AFL",
+        }
+    }
+
+    pub fn next(&self) -> Self {
+        match self {
+            SoloMode::InPlace => SoloMode::Afl,
+            SoloMode::Afl => SoloMode::InPlace,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum MixerSelection {
     Instrument(usize), // index into instruments vec
     Bus(u8),      // 1-8
@@ -21,7 +96,7 @@ impl Default for MixerSelection {
 }
 
 /// The subset of session fields that are cheap to clone for editing (BPM, key, scale, etc.)
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct MusicalSettings {
     pub key: Key,
     pub scale: Scale,
@@ -29,6 +104,9 @@ pub struct MusicalSettings {
     pub tuning_a4: f32,
     pub snap: bool,
     pub time_signature: (u8, u8),
+    pub transpose: i8,
+    /// Timeline resolution (PPQ). Changing it rescales everything on the timeline.
+    pub ticks_per_beat: u32,
 }
 
 impl Default for MusicalSettings {
@@ -40,6 +118,8 @@ impl Default for MusicalSettings {
             tuning_a4: 440.0,
             snap: false,
             time_signature: (4, 4),
+            transpose: 0,
+            ticks_per_beat: 480,
         }
     }
 }
@@ -55,6 +135,9 @@ pub struct SessionState {
     pub tuning_a4: f32,
     pub snap: bool,
     pub time_signature: (u8, u8),
+    /// Semitones every voice is shifted by when it plays; notes keep their stored
+    /// pitch. 0 is concert pitch.
+    pub transpose: i8,
 
     // Project state (hoisted from InstrumentState)
     pub piano_roll: PianoRollState,
@@ -63,8 +146,25 @@ pub struct SessionState {
     pub custom_synthdefs: CustomSynthDefRegistry,
     pub buses: Vec<MixerBus>,
     pub master_level: f32,
+    /// Silences every instrument, overriding solo as well as the mute flags
     pub master_mute: bool,
+    /// First hardware output channel of the master pair (0 = out 1/2). Instruments
+    /// routed to master play here.
+    pub master_output_channel: u32,
+    pub solo_mode: SoloMode,
     pub mixer_selection: MixerSelection,
+    /// Groove templates: the built-in swings plus any extracted from patterns
+    pub grooves: Vec<GrooveTemplate>,
+    /// Groove that apply actions use
+    pub selected_groove: usize,
+    /// View to resume on; filled in from the panes when saving
+    pub ui: UiState,
+    /// Extra directory of compiled synthdefs this project uses, loaded on connect
+    /// after the built-in and config ones
+    pub synthdef_dir: Option<PathBuf>,
+    /// Seed the randomize and mutate actions start from when the project opens, so
+    /// the same actions give the same results every time it is reopened
+    pub rng_seed: u64,
 }
 
 impl SessionState {
@@ -74,13 +174,14 @@ impl SessionState {
 
     pub fn new_with_defaults(defaults: MusicalSettings) -> Self {
         let buses = (1..=MAX_BUSES as u8).map(MixerBus::new).collect();
-        Self {
+        let mut session = Self {
             key: defaults.key,
             scale: defaults.scale,
             bpm: defaults.bpm,
             tuning_a4: defaults.tuning_a4,
             snap: defaults.snap,
             time_signature: defaults.time_signature,
+            transpose: defaults.transpose,
             piano_roll: PianoRollState::new(),
             automation: AutomationState::new(),
             midi_recording: MidiRecordingState::new(),
@@ -88,10 +189,37 @@ impl SessionState {
             buses,
             master_level: 1.0,
             master_mute: false,
+            master_output_channel: 0,
+            solo_mode: SoloMode::default(),
             mixer_selection: MixerSelection::default(),
+            grooves: GrooveTemplate::presets(),
+            selected_groove: 0,
+            ui: UiState::default(),
+            synthdef_dir: None,
+            rng_seed: time_seed(),
+        };
+        session.set_ticks_per_beat(defaults.ticks_per_beat.clamp(MIN_TICKS_PER_BEAT, MAX_TICKS_PER_BEAT));
+        session
+    }
+
+    pub fn selected_groove(&self) -> Option<&GrooveTemplate> {
+        self.grooves.get(self.selected_groove)
+    }
+
+    pub fn cycle_groove(&mut self, delta: i8) {
+        if !self.grooves.is_empty() {
+            let len = self.grooves.len() as i32;
+            self.selected_groove = (self.selected_groove as i32 + delta as i32).rem_euclid(len) as usize;
         }
     }
 
+    /// Add an extracted groove, named after its position, and select it
+    pub fn add_groove(&mut self, mut groove: GrooveTemplate) {
+        groove.name = format!("Groove {}", self.grooves.len() + 1);
+        self.grooves.push(groove);
+        self.selected_groove = self.grooves.len() - 1;
+    }
+
     /// Extract the cheap musical settings for editing
     pub fn musical_settings(&self) -> MusicalSettings {
         MusicalSettings {
@@ -101,10 +229,13 @@ impl SessionState {
             tuning_a4: self.tuning_a4,
             snap: self.snap,
             time_signature: self.time_signature,
+            transpose: self.transpose,
+            ticks_per_beat: self.piano_roll.ticks_per_beat,
         }
     }
 
-    /// Apply edited musical settings back
+    /// Apply edited musical settings back. The resolution is left alone: rescaling
+    /// rounds, so it only changes through `set_ticks_per_beat`.
     pub fn apply_musical_settings(&mut self, settings: &MusicalSettings) {
         self.key = settings.key;
         self.scale = settings.scale;
@@ -112,6 +243,19 @@ impl SessionState {
         self.tuning_a4 = settings.tuning_a4;
         self.snap = settings.snap;
         self.time_signature = settings.time_signature;
+        self.transpose = settings.transpose.clamp(-MAX_TRANSPOSE, MAX_TRANSPOSE);
+    }
+
+    /// The pitch that sounds for a played or stored one, after the global transpose
+    pub fn transposed(&self, pitch: u8) -> u8 {
+        (pitch as i16 + self.transpose as i16).clamp(0, 127) as u8
+    }
+
+    /// Change the tick resolution of the whole timeline: piano roll and automation
+    pub fn set_ticks_per_beat(&mut self, ticks_per_beat: u32) {
+        let from = self.piano_roll.ticks_per_beat;
+        self.piano_roll.set_ticks_per_beat(ticks_per_beat);
+        self.automation.rescale_ticks(from, self.piano_roll.ticks_per_beat);
     }
 
     pub fn bus(&self, id: u8) -> Option<&MixerBus> {
@@ -127,15 +271,109 @@ impl SessionState {
         self.buses.iter().any(|b| b.solo)
     }
 
-    /// Compute effective mute for a bus, considering solo state
-    pub fn effective_bus_mute(&self, bus: &MixerBus) -> bool {
-        if self.any_bus_solo() {
-            !bus.solo
+    /// True when any instrument or bus is soloed
+    pub fn any_solo(&self, instruments: &InstrumentState) -> bool {
+        instruments.any_instrument_solo() || self.any_bus_solo()
+    }
+
+    fn bus_soloed(&self, bus_id: u8) -> bool {
+        self.bus(bus_id).is_some_and(|b| b.solo)
+    }
+
+    /// Compute effective mute for an instrument. Master mute silences everything,
+    /// soloed or not. While anything is soloed, an instrument is audible only if it
+    /// is soloed itself or feeds a soloed bus (output or enabled send).
+    /// Mute flags are left untouched, so clearing solos restores them.
+    pub fn effective_instrument_mute(&self, instruments: &InstrumentState, inst: &Instrument) -> bool {
+        if self.master_mute {
+            return true;
+        }
+        if self.any_solo(instruments) {
+            let feeds_soloed_bus = matches!(inst.output_target, OutputTarget::Bus(id) if self.bus_soloed(id))
+                || inst.sends.iter().any(|s| s.enabled && s.level > 0.0 && self.bus_soloed(s.bus_id));
+            !(inst.solo || feeds_soloed_bus)
+        } else {
+            inst.mute
+        }
+    }
+
+    /// Compute effective mute for a bus. While anything is soloed, a bus is audible
+    /// if it is soloed or, in place, downstream of a soloed instrument (output or
+    /// enabled send), even when the bus itself is muted.
+    pub fn effective_bus_mute(&self, instruments: &InstrumentState, bus: &MixerBus) -> bool {
+        if self.any_solo(instruments) {
+            if self.solo_mode == SoloMode::Afl {
+                return !bus.solo;
+            }
+            let downstream_of_solo = instruments.instruments.iter().filter(|i| i.solo).any(|i| {
+                i.output_target == OutputTarget::Bus(bus.id)
+                    || i.sends.iter().any(|s| s.bus_id == bus.id && s.enabled && s.level > 0.0)
+            });
+            !(bus.solo || downstream_of_solo)
         } else {
             bus.mute
         }
     }
 
+    /// True while something is soloed in AFL mode, so soloed strips play flat
+    pub fn afl_listening(&self, instruments: &InstrumentState) -> bool {
+        self.solo_mode == SoloMode::Afl && self.any_solo(instruments)
+    }
+
+    /// Pan an instrument's output plays at: centered while it's heard AFL
+    pub fn effective_instrument_pan(&self, instruments: &InstrumentState, inst: &Instrument) -> f32 {
+        if inst.solo && self.afl_listening(instruments) { 0.0 } else { inst.pan }
+    }
+
+    /// Pan a bus output plays at: centered while it's heard AFL
+    pub fn effective_bus_pan(&self, instruments: &InstrumentState, bus: &MixerBus) -> f32 {
+        if bus.solo && self.afl_listening(instruments) { 0.0 } else { bus.pan }
+    }
+
+    /// Whether an instrument's effect is bypassed: by its own switch, or because the
+    /// instrument is heard AFL
+    pub fn effective_effect_bypass(&self, instruments: &InstrumentState, inst: &Instrument, effect: &EffectSlot) -> bool {
+        effect.bypassed || (inst.solo && self.afl_listening(instruments))
+    }
+
+    /// Hardware output pair of a bus or master strip; instruments have none of their own
+    pub fn output_channel(&self, selection: MixerSelection) -> Option<u32> {
+        match selection {
+            MixerSelection::Instrument(_) => None,
+            MixerSelection::Bus(id) => self.bus(id).map(|b| b.output_channel),
+            MixerSelection::Master => Some(self.master_output_channel),
+        }
+    }
+
+    pub fn set_output_channel(&mut self, selection: MixerSelection, channel: u32) {
+        match selection {
+            MixerSelection::Instrument(_) => {}
+            MixerSelection::Bus(id) => {
+                if let Some(bus) = self.bus_mut(id) {
+                    bus.output_channel = channel;
+                }
+            }
+            MixerSelection::Master => self.master_output_channel = channel,
+        }
+    }
+
+    /// Move bus and master pairs a server with `outputs` channels doesn't have back to
+    /// out 1/2, where the engine plays them anyway. Returns the names of the strips moved.
+    pub fn fit_output_channels(&mut self, outputs: u32) -> Vec<String> {
+        let mut moved = Vec::new();
+        for bus in &mut self.buses {
+            if validate_output_channel(bus.output_channel, outputs).is_err() {
+                bus.output_channel = 0;
+                moved.push(bus.name.clone());
+            }
+        }
+        if validate_output_channel(self.master_output_channel, outputs).is_err() {
+            self.master_output_channel = 0;
+            moved.push("Master".to_string());
+        }
+        moved
+    }
+
     /// Cycle between instrument/bus/master sections
     pub fn mixer_cycle_section(&mut self) {
         self.mixer_selection = match self.mixer_selection {
@@ -151,3 +389,200 @@ impl Default for SessionState {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::SourceType;
+
+    #[test]
+    fn test_set_ticks_per_beat_rescales_automation() {
+        use crate::state::automation::AutomationTarget;
+
+        let mut session = SessionState::new();
+        let lane_id = session.automation.add_lane(AutomationTarget::InstrumentLevel(0));
+        let lane = session.automation.lane_mut(lane_id).unwrap();
+        lane.add_point(0, 0.0);
+        lane.add_point(1, 0.5);
+        lane.add_point(960, 1.0);
+
+        session.set_ticks_per_beat(96);
+        assert_eq!(session.piano_roll.ticks_per_beat, 96);
+        let ticks: Vec<u32> = session.automation.lanes[0].points.iter().map(|p| p.tick).collect();
+        // Tick 1 rounds onto tick 0; the first point wins
+        assert_eq!(ticks, [0, 192]);
+    }
+
+    #[test]
+    fn test_new_project_resolution_from_defaults() {
+        let defaults = MusicalSettings { ticks_per_beat: 960, ..MusicalSettings::default() };
+        let mut session = SessionState::new_with_defaults(defaults);
+        assert_eq!(session.piano_roll.ticks_per_beat, 960);
+        // The default four-beat loop stays four beats
+        assert_eq!(session.piano_roll.loop_end, 3840);
+        assert_eq!(session.musical_settings().ticks_per_beat, 960);
+
+        // Applying edited settings never rescales; only set_ticks_per_beat does
+        let mut settings = session.musical_settings();
+        settings.ticks_per_beat = 96;
+        session.apply_musical_settings(&settings);
+        assert_eq!(session.piano_roll.ticks_per_beat, 960);
+    }
+
+    #[test]
+    fn test_transpose_shifts_sounding_pitch_only() {
+        let mut session = SessionState::new();
+        assert_eq!(session.transposed(60), 60);
+        let mut settings = session.musical_settings();
+        settings.transpose = -3;
+        session.apply_musical_settings(&settings);
+        assert_eq!(session.transposed(60), 57);
+        assert_eq!(session.transposed(1), 0);
+        settings.transpose = 100;
+        session.apply_musical_settings(&settings);
+        assert_eq!(session.transpose, MAX_TRANSPOSE);
+        assert_eq!(session.transposed(120), 127);
+        // Back to zero is concert pitch
+        settings.transpose = 0;
+        session.apply_musical_settings(&settings);
+        assert_eq!(session.transposed(60), 60);
+    }
+
+    #[test]
+    fn test_output_channels() {
+        assert_eq!(output_pair_label(0), "1/2");
+        assert_eq!(output_pair_label(2), "3/4");
+        assert!(validate_output_channel(0, STEREO_OUTPUTS).is_ok());
+        assert!(validate_output_channel(4, 6).is_ok());
+        assert_eq!(
+            validate_output_channel(2, STEREO_OUTPUTS),
+            Err("Out 3/4 needs 4 outputs; the server has 2".to_string()),
+        );
+
+        assert_eq!(cycle_output_channel(0, 6, true), 2);
+        assert_eq!(cycle_output_channel(4, 6, true), 0);
+        assert_eq!(cycle_output_channel(0, 6, false), 4);
+        // Stereo only has 1/2; an out-of-range assignment comes back into range
+        assert_eq!(cycle_output_channel(0, STEREO_OUTPUTS, true), 0);
+        assert_eq!(cycle_output_channel(6, 4, true), 0);
+
+        let mut session = SessionState::new();
+        assert_eq!(session.output_channel(MixerSelection::Master), Some(0));
+        assert_eq!(session.output_channel(MixerSelection::Instrument(0)), None);
+        session.set_output_channel(MixerSelection::Bus(2), 2);
+        assert_eq!(session.bus(2).unwrap().output_channel, 2);
+
+        // Pairs past the server's outputs are stored as the 1/2 they play on
+        session.set_output_channel(MixerSelection::Bus(3), 4);
+        session.set_output_channel(MixerSelection::Master, 6);
+        assert!(session.fit_output_channels(8).is_empty());
+        let moved = session.fit_output_channels(4);
+        assert_eq!(moved, vec![session.bus(3).unwrap().name.clone(), "Master".to_string()]);
+        assert_eq!(session.bus(2).unwrap().output_channel, 2);
+        assert_eq!(session.bus(3).unwrap().output_channel, 0);
+        assert_eq!(session.master_output_channel, 0);
+    }
+
+    #[test]
+    fn test_solo_instrument_through_muted_bus() {
+        let mut session = SessionState::new();
+        let mut instruments = InstrumentState::new();
+        let routed = instruments.add_instrument(SourceType::Saw);
+        let other = instruments.add_instrument(SourceType::Saw);
+        instruments.instrument_mut(routed).unwrap().output_target = OutputTarget::Bus(1);
+        instruments.instrument_mut(other).unwrap().mute = true;
+        session.bus_mut(1).unwrap().mute = true;
+        session.bus_mut(2).unwrap().mute = false;
+
+        instruments.instrument_mut(routed).unwrap().solo = true;
+        let bus1 = session.bus(1).unwrap();
+        let bus2 = session.bus(2).unwrap();
+        assert!(!session.effective_instrument_mute(&instruments, instruments.instrument(routed).unwrap()));
+        assert!(session.effective_instrument_mute(&instruments, instruments.instrument(other).unwrap()));
+        assert!(!session.effective_bus_mute(&instruments, bus1));
+        assert!(session.effective_bus_mute(&instruments, bus2));
+
+        // Clearing the solo restores the original mutes
+        instruments.instrument_mut(routed).unwrap().solo = false;
+        assert!(session.effective_bus_mute(&instruments, session.bus(1).unwrap()));
+        assert!(!session.effective_bus_mute(&instruments, session.bus(2).unwrap()));
+        assert!(session.effective_instrument_mute(&instruments, instruments.instrument(other).unwrap()));
+    }
+
+    #[test]
+    fn test_solo_bus_keeps_its_sources_audible() {
+        let mut session = SessionState::new();
+        let mut instruments = InstrumentState::new();
+        let feeding = instruments.add_instrument(SourceType::Saw);
+        let direct = instruments.add_instrument(SourceType::Saw);
+        instruments.instrument_mut(feeding).unwrap().output_target = OutputTarget::Bus(3);
+        session.bus_mut(3).unwrap().solo = true;
+
+        assert!(!session.effective_instrument_mute(&instruments, instruments.instrument(feeding).unwrap()));
+        assert!(session.effective_instrument_mute(&instruments, instruments.instrument(direct).unwrap()));
+        assert!(!session.effective_bus_mute(&instruments, session.bus(3).unwrap()));
+
+        // An enabled send into the soloed bus is a path too
+        let send = &mut instruments.instrument_mut(direct).unwrap().sends[2];
+        assert_eq!(send.bus_id, 3);
+        send.level = 0.5;
+        assert!(session.effective_instrument_mute(&instruments, instruments.instrument(direct).unwrap()));
+        instruments.instrument_mut(direct).unwrap().sends[2].enabled = true;
+        assert!(!session.effective_instrument_mute(&instruments, instruments.instrument(direct).unwrap()));
+    }
+
+    #[test]
+    fn test_master_mute_overrides_solo() {
+        let mut session = SessionState::new();
+        let mut instruments = InstrumentState::new();
+        let id = instruments.add_instrument(SourceType::Saw);
+        instruments.instrument_mut(id).unwrap().solo = true;
+        assert!(!session.effective_instrument_mute(&instruments, instruments.instrument(id).unwrap()));
+
+        session.master_mute = true;
+        assert!(session.effective_instrument_mute(&instruments, instruments.instrument(id).unwrap()));
+        session.master_mute = false;
+        assert!(!session.effective_instrument_mute(&instruments, instruments.instrument(id).unwrap()));
+    }
+
+    #[test]
+    fn test_afl_solo_plays_flat_without_downstream_buses() {
+        let mut session = SessionState::new();
+        let mut instruments = InstrumentState::new();
+        let id = instruments.add_instrument(SourceType::Saw);
+        let inst = instruments.instrument_mut(id).unwrap();
+        inst.output_target = OutputTarget::Bus(1);
+        inst.pan = -0.5;
+        inst.effects.push(EffectSlot::new(crate::state::instrument::EffectType::Delay));
+        session.bus_mut(1).unwrap().pan = 0.4;
+
+        // In place (the default), a soloed instrument keeps its pan, effects and bus
+        instruments.instrument_mut(id).unwrap().solo = true;
+        let inst = instruments.instrument(id).unwrap();
+        assert_eq!(session.solo_mode, SoloMode::InPlace);
+        assert_eq!(session.effective_instrument_pan(&instruments, inst), -0.5);
+        assert!(!session.effective_effect_bypass(&instruments, inst, &inst.effects[0]));
+        assert!(!session.effective_bus_mute(&instruments, session.bus(1).unwrap()));
+
+        // AFL hears it centered and dry, without the bus it feeds
+        session.solo_mode = SoloMode::Afl;
+        assert!(!session.effective_instrument_mute(&instruments, inst));
+        assert_eq!(session.effective_instrument_pan(&instruments, inst), 0.0);
+        assert!(session.effective_effect_bypass(&instruments, inst, &inst.effects[0]));
+        assert!(session.effective_bus_mute(&instruments, session.bus(1).unwrap()));
+
+        // A soloed bus is heard centered along with its sources
+        instruments.instrument_mut(id).unwrap().solo = false;
+        session.bus_mut(1).unwrap().solo = true;
+        let inst = instruments.instrument(id).unwrap();
+        let bus = session.bus(1).unwrap();
+        assert!(!session.effective_bus_mute(&instruments, bus));
+        assert_eq!(session.effective_bus_pan(&instruments, bus), 0.0);
+        assert!(!session.effective_instrument_mute(&instruments, inst));
+        assert_eq!(session.effective_instrument_pan(&instruments, inst), -0.5);
+
+        // Nothing soloed: AFL changes nothing
+        session.bus_mut(1).unwrap().solo = false;
+        assert_eq!(session.effective_bus_pan(&instruments, session.bus(1).unwrap()), 0.4);
+    }
+}
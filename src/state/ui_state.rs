@@ -0,0 +1,129 @@
+//! View state saved with a project so a session resumes where it was left.
+//! Stored in its own table, apart from the musical data; files without it load
+//! with the defaults.
+
+use super::instrument_state::InstrumentState;
+use super::session::{MixerSelection, MAX_BUSES};
+
+/// Panes a project can reopen on; modal and transient ones fall back to the default
+pub const RESUMABLE_PANES: &[&str] = &[
+    "instrument", "mixer", "piano_roll", "sequencer", "track", "waveform", "server", "midi",
+];
+
+/// Piano roll zoom levels (ticks per cell steps), finest first
+pub const MIN_PIANO_ZOOM: u8 = 1;
+pub const MAX_PIANO_ZOOM: u8 = 5;
+
+/// Where the piano roll was looking
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PianoRollView {
+    pub cursor_tick: u32,
+    pub cursor_pitch: u8,
+    pub track: usize,
+    pub zoom_level: u8,
+}
+
+impl Default for PianoRollView {
+    fn default() -> Self {
+        Self { cursor_tick: 0, cursor_pitch: 60, track: 0, zoom_level: 3 }
+    }
+}
+
+impl PianoRollView {
+    /// The same view, pulled back inside a piano roll with `tracks` tracks
+    pub fn clamped(&self, tracks: usize) -> Self {
+        Self {
+            cursor_tick: self.cursor_tick,
+            cursor_pitch: self.cursor_pitch.min(127),
+            track: self.track.min(tracks.saturating_sub(1)),
+            zoom_level: self.zoom_level.clamp(MIN_PIANO_ZOOM, MAX_PIANO_ZOOM),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct UiState {
+    /// Pane that was showing, if it's one of `RESUMABLE_PANES`
+    pub active_pane: Option<String>,
+    pub piano_roll: PianoRollView,
+}
+
+impl UiState {
+    /// The saved pane, if it can be reopened
+    pub fn resumable_pane(&self) -> Option<&str> {
+        self.active_pane.as_deref().filter(|id| RESUMABLE_PANES.contains(id))
+    }
+}
+
+/// A mixer selection pulled back onto a strip that exists: instruments past the end
+/// select the last one, and bus ids outside the bus range the nearest bus
+pub fn clamp_mixer_selection(selection: MixerSelection, instruments: &InstrumentState) -> MixerSelection {
+    match selection {
+        MixerSelection::Instrument(idx) => {
+            MixerSelection::Instrument(idx.min(instruments.instruments.len().saturating_sub(1)))
+        }
+        MixerSelection::Bus(id) => MixerSelection::Bus(id.clamp(1, MAX_BUSES as u8)),
+        MixerSelection::Master => MixerSelection::Master,
+    }
+}
+
+/// Stored form of a mixer selection: "instrument:2", "bus:3" or "master"
+pub fn mixer_selection_key(selection: MixerSelection) -> String {
+    match selection {
+        MixerSelection::Instrument(idx) => format!("instrument:{}", idx),
+        MixerSelection::Bus(id) => format!("bus:{}", id),
+        MixerSelection::Master => "master".to_string(),
+    }
+}
+
+/// Read back a stored mixer selection; anything unrecognised selects the first strip
+pub fn parse_mixer_selection(s: &str) -> MixerSelection {
+    let parsed = match s.split_once(':') {
+        Some(("instrument", idx)) => idx.parse().ok().map(MixerSelection::Instrument),
+        Some(("bus", id)) => id.parse().ok().map(MixerSelection::Bus),
+        None if s == "master" => Some(MixerSelection::Master),
+        _ => None,
+    };
+    parsed.unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::SourceType;
+
+    #[test]
+    fn test_out_of_range_selections_clamp() {
+        let mut instruments = InstrumentState::new();
+        instruments.add_instrument(SourceType::Saw);
+        instruments.add_instrument(SourceType::Saw);
+        assert_eq!(clamp_mixer_selection(MixerSelection::Instrument(7), &instruments), MixerSelection::Instrument(1));
+        assert_eq!(clamp_mixer_selection(MixerSelection::Bus(12), &instruments), MixerSelection::Bus(MAX_BUSES as u8));
+        assert_eq!(clamp_mixer_selection(MixerSelection::Bus(0), &instruments), MixerSelection::Bus(1));
+        assert_eq!(
+            clamp_mixer_selection(MixerSelection::Instrument(3), &InstrumentState::new()),
+            MixerSelection::Instrument(0),
+        );
+
+        let view = PianoRollView { cursor_tick: 960, cursor_pitch: 200, track: 4, zoom_level: 9 };
+        let clamped = view.clamped(2);
+        assert_eq!((clamped.cursor_pitch, clamped.track, clamped.zoom_level), (127, 1, MAX_PIANO_ZOOM));
+        assert_eq!(view.clamped(0).track, 0);
+    }
+
+    #[test]
+    fn test_mixer_selection_keys_round_trip() {
+        for selection in [MixerSelection::Instrument(4), MixerSelection::Bus(3), MixerSelection::Master] {
+            assert_eq!(parse_mixer_selection(&mixer_selection_key(selection)), selection);
+        }
+        assert_eq!(parse_mixer_selection("bus:x"), MixerSelection::default());
+    }
+
+    #[test]
+    fn test_only_main_views_resume() {
+        let ui = UiState { active_pane: Some("mixer".into()), ..Default::default() };
+        assert_eq!(ui.resumable_pane(), Some("mixer"));
+        let ui = UiState { active_pane: Some("file_browser".into()), ..Default::default() };
+        assert_eq!(ui.resumable_pane(), None);
+    }
+}
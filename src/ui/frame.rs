@@ -1,21 +1,19 @@
+use std::time::{Duration, Instant};
+
 use ratatui::buffer::Buffer;
 use ratatui::layout::Rect as RatatuiRect;
 use ratatui::text::{Line, Span};
 use ratatui::widgets::{Block, Borders, Paragraph, Widget};
 
-use super::{Color, Style};
+use super::widgets::TextInput;
+use super::{Color, Style, theme};
 use crate::state::AppState;
 
 /// Block characters for vertical meter: ▁▂▃▄▅▆▇█ (U+2581–U+2588)
 const BLOCK_CHARS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
 
-/// Captured view state for back/forward navigation
-#[derive(Debug, Clone)]
-pub struct ViewState {
-    pub pane_id: String,
-    pub inst_selection: Option<usize>,
-    pub edit_tab: u8,
-}
+/// How long a toast stays on screen
+const TOAST_DURATION: Duration = Duration::from_secs(4);
 
 /// Frame wrapping the active pane with border and header bar
 pub struct Frame {
@@ -25,14 +23,20 @@ pub struct Frame {
     master_peak: f32,
     /// Smoothed display value (fast attack, slow decay)
     peak_display: f32,
-    /// Navigation history (browser-style)
-    pub view_history: Vec<ViewState>,
-    /// Current position in view_history
-    pub history_cursor: usize,
     /// Whether audio is currently being recorded
     pub recording: bool,
     /// Elapsed recording time in seconds
     pub recording_secs: u64,
+    /// Warning shown in the bottom border until it expires
+    toast: Option<(String, Instant)>,
+    /// Shown in the header while no sound can play (no server connected)
+    pub audio_warning: Option<&'static str>,
+    /// Shown in the header while a save or load runs in the background
+    pub project_busy: Option<&'static str>,
+    /// A macro is being recorded
+    pub macro_recording: bool,
+    /// Name prompt for a macro that just stopped recording, shown in the bottom border
+    pub macro_name: Option<TextInput>,
 }
 
 impl Frame {
@@ -42,10 +46,13 @@ impl Frame {
             master_mute: false,
             master_peak: 0.0,
             peak_display: 0.0,
-            view_history: Vec::new(),
-            history_cursor: 0,
             recording: false,
             recording_secs: 0,
+            toast: None,
+            audio_warning: None,
+            project_busy: None,
+            macro_recording: false,
+            macro_name: None,
         }
     }
 
@@ -61,16 +68,14 @@ impl Frame {
         self.peak_display = peak.max(self.peak_display * 0.85);
     }
 
+    /// Flash a warning in the bottom border for a few seconds
+    pub fn show_toast(&mut self, message: impl Into<String>) {
+        self.toast = Some((message.into(), Instant::now()));
+    }
+
     /// Get meter color for a given row position (0=bottom, height-1=top)
     fn meter_color(row: u16, height: u16) -> Color {
-        let frac = row as f32 / height as f32;
-        if frac > 0.85 {
-            Color::METER_HIGH
-        } else if frac > 0.6 {
-            Color::METER_MID
-        } else {
-            Color::METER_LOW
-        }
+        Color::meter(row as f32 / height as f32)
     }
 
     /// Render the frame using ratatui buffer directly.
@@ -80,7 +85,7 @@ impl Frame {
         }
 
         let session = &state.session;
-        let border_style = ratatui::style::Style::from(Style::new().fg(Color::GRAY));
+        let border_style = ratatui::style::Style::from(Style::new().fg(theme().border));
 
         // Outer border
         let block = Block::default()
@@ -102,14 +107,37 @@ impl Frame {
         // Header line in the top border
         let snap_text = if session.snap { "ON" } else { "OFF" };
         let tuning_str = format!("A{:.0}", session.tuning_a4);
+        let mut mode_text = if state.note_repeat.enabled {
+            format!("[Repeat: {}] ", state.note_repeat.division.name())
+        } else {
+            String::new()
+        };
+        if state.sustain {
+            mode_text.push_str("[Sustain] ");
+        }
+        if state.mono_check {
+            mode_text.push_str("[MONO] ");
+        }
+        if session.transpose != 0 {
+            mode_text.push_str(&format!("[Transpose: {:+}] ", session.transpose));
+        }
+        if let Some(warning) = self.audio_warning {
+            mode_text.push_str(&format!("[{}] ", warning));
+        }
+        if let Some(busy) = self.project_busy {
+            mode_text.push_str(&format!("[{}] ", busy));
+        }
+        if self.macro_recording {
+            mode_text.push_str("[Recording macro] ");
+        }
         let header = format!(
-            " ILEX - {}  {}  Key: {}  Scale: {}  BPM: {}  {}/{}  Tuning: {}  [Snap: {}] ",
+            " ILEX - {}  {}  Key: {}  Scale: {}  BPM: {}  {}/{}  Tuning: {}  [Snap: {}] {}",
             self.project_name, inst_indicator,
             session.key.name(), session.scale.name(), session.bpm,
             session.time_signature.0, session.time_signature.1,
-            tuning_str, snap_text,
+            tuning_str, snap_text, mode_text,
         );
-        let header_style = ratatui::style::Style::from(Style::new().fg(Color::CYAN).bold());
+        let header_style = ratatui::style::Style::from(Style::new().fg(theme().accent).bold());
         Paragraph::new(Line::from(Span::styled(&header, header_style)))
             .render(RatatuiRect::new(area.x + 1, area.y, area.width.saturating_sub(2), 1), buf);
 
@@ -151,6 +179,21 @@ impl Frame {
         // Master meter (direct buffer writes)
         let meter_bottom_y = area.y + area.height.saturating_sub(2);
         self.render_master_meter_buf(buf, area.width, area.height, meter_bottom_y);
+
+        // Macro name prompt, or else the toast (bottom border)
+        if let Some(input) = &self.macro_name {
+            input.render_buf(buf, area.x + 2, area.y + area.height - 1, area.width.saturating_sub(4).min(50));
+        } else if let Some((message, shown)) = &self.toast {
+            if shown.elapsed() < TOAST_DURATION {
+                let text = format!(" {} ", message);
+                let width = (text.chars().count() as u16).min(area.width.saturating_sub(4));
+                let toast_style = ratatui::style::Style::from(
+                    Style::new().fg(Color::BLACK).bg(Color::ORANGE).bold(),
+                );
+                Paragraph::new(Line::from(Span::styled(text, toast_style)))
+                    .render(RatatuiRect::new(area.x + 2, area.y + area.height - 1, width, 1), buf);
+            }
+        }
     }
 
     /// Render vertical master meter on the right side (buffer version)
@@ -180,7 +223,7 @@ impl Frame {
                 let sub_level = (filled_sub - row_start) as usize;
                 (BLOCK_CHARS[sub_level.saturating_sub(1).min(7)], color)
             } else {
-                ('·', Color::DARK_GRAY)
+                ('·', theme().muted)
             };
 
             if let Some(cell) = buf.cell_mut((meter_x, y)) {
@@ -203,7 +246,7 @@ impl Frame {
                 let db_val = 20.0 * level.log10();
                 format!("{:+.0}", db_val.max(-99.0))
             };
-            let db_style = ratatui::style::Style::from(Style::new().fg(Color::DARK_GRAY));
+            let db_style = ratatui::style::Style::from(Style::new().fg(theme().muted));
             let db_x = meter_x.saturating_sub(db.len() as u16 - 1);
             for (j, ch) in db.chars().enumerate() {
                 if let Some(cell) = buf.cell_mut((db_x + j as u16, label_y)) {
@@ -50,7 +50,11 @@ fn intern(s: String) -> &'static str {
 /// - `"Ctrl+Left"` → CtrlKey(KeyCode::Left)
 /// - `"Shift+Right"` → ShiftKey(KeyCode::Right)
 /// - `"F1"` → Key(KeyCode::F(1))
+/// - `"g g"` → Sequence([Char('g'), Char('g')]) (space-separated keys)
 fn parse_key(s: &str) -> KeyPattern {
+    if s.len() > 1 && s.contains(' ') {
+        return KeyPattern::Sequence(s.split_whitespace().map(parse_key).collect());
+    }
     // Check for modifier prefixes
     if let Some(rest) = s.strip_prefix("Ctrl+") {
         if rest.len() == 1 {
@@ -207,6 +211,18 @@ mod tests {
         assert_eq!(parse_key("F12"), KeyPattern::Key(KeyCode::F(12)));
     }
 
+    #[test]
+    fn test_parse_key_sequence() {
+        assert_eq!(
+            parse_key("g g"),
+            KeyPattern::Sequence(vec![KeyPattern::Char('g'), KeyPattern::Char('g')])
+        );
+        assert_eq!(
+            parse_key("Space Enter"),
+            KeyPattern::Sequence(vec![KeyPattern::Char(' '), KeyPattern::Key(KeyCode::Enter)])
+        );
+    }
+
     #[test]
     fn test_load_embedded_keybindings() {
         let (layers, pane_keymaps) = load_keybindings();
@@ -217,4 +233,18 @@ mod tests {
         assert!(pane_keymaps.contains_key("mixer"));
         assert!(pane_keymaps.contains_key("piano_roll"));
     }
+
+    #[test]
+    fn test_panes_leave_global_toggles_alone() {
+        use crate::ui::{InputEvent, Modifiers};
+        let (layers, pane_keymaps) = load_keybindings();
+        let global = &layers.iter().find(|l| l.name == "global").unwrap().keymap;
+        for ch in ['.', '<', '>'] {
+            let event = InputEvent::new(KeyCode::Char(ch), Modifiers::none());
+            assert!(global.lookup(&event).is_some(), "{}", ch);
+            for (pane, keymap) in &pane_keymaps {
+                assert_eq!(keymap.lookup(&event), None, "{} shadows global {:?}", pane, ch);
+            }
+        }
+    }
 }
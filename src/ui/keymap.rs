@@ -18,6 +18,8 @@ pub enum KeyPattern {
     CtrlKey(KeyCode),
     /// Shift + special key (arrows, Tab, etc.)
     ShiftKey(KeyCode),
+    /// Multi-key sequence pressed one after another (e.g. `g g`)
+    Sequence(Vec<KeyPattern>),
 }
 
 impl KeyPattern {
@@ -40,6 +42,16 @@ impl KeyPattern {
             }
             KeyPattern::CtrlKey(code) => event.key == *code && event.modifiers.ctrl,
             KeyPattern::ShiftKey(code) => event.key == *code && event.modifiers.shift,
+            // Sequences span several events; see `Keymap::match_sequence`
+            KeyPattern::Sequence(_) => false,
+        }
+    }
+
+    /// The individual keys of this pattern (one unless it's a sequence)
+    fn keys(&self) -> &[KeyPattern] {
+        match self {
+            KeyPattern::Sequence(keys) => keys,
+            single => std::slice::from_ref(single),
         }
     }
 
@@ -52,10 +64,46 @@ impl KeyPattern {
             KeyPattern::Alt(ch) => format!("Alt+{}", ch),
             KeyPattern::CtrlKey(code) => format!("Ctrl+{:?}", code),
             KeyPattern::ShiftKey(code) => format!("Shift+{:?}", code),
+            KeyPattern::Sequence(keys) => keys.iter().map(|k| k.display()).collect::<Vec<_>>().join(" "),
         }
     }
 }
 
+/// Compact key label for footer hints (arrows as glyphs, short names)
+fn short_key(pattern: &KeyPattern) -> String {
+    let key_name = |code: &KeyCode| match code {
+        KeyCode::Up => "\u{2191}".to_string(),
+        KeyCode::Down => "\u{2193}".to_string(),
+        KeyCode::Left => "\u{2190}".to_string(),
+        KeyCode::Right => "\u{2192}".to_string(),
+        KeyCode::Escape => "Esc".to_string(),
+        KeyCode::Backspace => "Bksp".to_string(),
+        KeyCode::PageUp => "PgUp".to_string(),
+        KeyCode::PageDown => "PgDn".to_string(),
+        KeyCode::Delete => "Del".to_string(),
+        other => format!("{:?}", other),
+    };
+    match pattern {
+        KeyPattern::Char(' ') => "Space".to_string(),
+        KeyPattern::Key(code) => key_name(code),
+        KeyPattern::CtrlKey(code) => format!("C-{}", key_name(code)),
+        KeyPattern::ShiftKey(code) => format!("S-{}", key_name(code)),
+        KeyPattern::Sequence(keys) => keys.iter().map(short_key).collect::<Vec<_>>().join(" "),
+        other => other.display(),
+    }
+}
+
+/// Result of matching keys pressed so far against a keymap
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SequenceMatch {
+    /// The keys form a complete binding
+    Complete(&'static str),
+    /// The keys are the start of a longer sequence binding; wait for more
+    Prefix,
+    /// No binding starts with these keys
+    None,
+}
+
 /// A single key binding
 #[derive(Debug, Clone)]
 pub struct KeyBinding {
@@ -72,9 +120,11 @@ pub struct KeyBinding {
 /// - `bind_ctrl(char, action, desc)` — Ctrl + character
 /// - `bind_alt(char, action, desc)` — Alt + character
 /// - `bind_ctrl_key(KeyCode, action, desc)` — Ctrl + special key
+/// - `bind_seq(&[char], action, desc)` — multi-key sequence (e.g. `g g`)
 ///
 /// Other methods:
 /// - `lookup(&InputEvent) -> Option<&str>` — match an event to its action
+/// - `match_sequence(&[InputEvent]) -> SequenceMatch` — match keys pressed so far
 /// - `bindings() -> &[KeyBinding]` — list all bindings (for help screens)
 ///
 /// Use `bind_shift_key(KeyCode, action, desc)` for Shift + special key combos.
@@ -172,6 +222,17 @@ impl Keymap {
         self
     }
 
+    /// Add a multi-key character sequence binding (e.g. `&['g', 'g']`)
+    #[allow(dead_code)]
+    pub fn bind_seq(mut self, keys: &[char], action: &'static str, description: &'static str) -> Self {
+        self.bindings.push(KeyBinding {
+            pattern: KeyPattern::Sequence(keys.iter().map(|&c| KeyPattern::Char(c)).collect()),
+            action,
+            description,
+        });
+        self
+    }
+
     /// Create a keymap from a pre-built list of bindings
     pub fn from_bindings(bindings: Vec<KeyBinding>) -> Self {
         Self { bindings }
@@ -185,6 +246,76 @@ impl Keymap {
             .map(|b| b.action)
     }
 
+    /// Match the keys pressed so far (oldest first) against single-key and
+    /// sequence bindings. A key that is both a binding and the start of a
+    /// sequence reports `Prefix`; the caller resolves it on its own after a timeout.
+    pub fn match_sequence(&self, keys: &[InputEvent]) -> SequenceMatch {
+        let mut complete = None;
+        for binding in &self.bindings {
+            let pattern = binding.pattern.keys();
+            if pattern.len() < keys.len() || !pattern.iter().zip(keys).all(|(p, e)| p.matches(e)) {
+                continue;
+            }
+            if pattern.len() > keys.len() {
+                return SequenceMatch::Prefix;
+            }
+            complete = complete.or(Some(binding.action));
+        }
+        complete.map_or(SequenceMatch::None, SequenceMatch::Complete)
+    }
+
+    /// Bindings that continue the keys pressed so far: (remaining keys, description)
+    pub fn continuations(&self, keys: &[InputEvent]) -> Vec<(String, &'static str)> {
+        self.bindings
+            .iter()
+            .filter_map(|binding| {
+                let pattern = binding.pattern.keys();
+                let is_continuation = pattern.len() > keys.len()
+                    && pattern.iter().zip(keys).all(|(p, e)| p.matches(e));
+                is_continuation.then(|| {
+                    let rest: Vec<String> = pattern[keys.len()..].iter().map(|k| k.display()).collect();
+                    (rest.join(" "), binding.description)
+                })
+            })
+            .collect()
+    }
+
+    /// One-line hint of this keymap's bindings that fits in `max_width` columns,
+    /// e.g. `"Enter: Select | Esc: Cancel"`. Keys sharing an action are shown
+    /// together; earlier bindings take priority and entries that don't fit are dropped.
+    pub fn footer_hint(&self, max_width: usize) -> String {
+        const SEPARATOR: &str = " | ";
+        const MAX_KEYS_PER_ENTRY: usize = 2;
+
+        // Group keys by action, keeping first-seen order and description
+        let mut entries: Vec<(&'static str, Vec<String>, &'static str)> = Vec::new();
+        for binding in &self.bindings {
+            let key = short_key(&binding.pattern);
+            match entries.iter_mut().find(|(action, _, _)| *action == binding.action) {
+                Some((_, keys, _)) => {
+                    if keys.len() < MAX_KEYS_PER_ENTRY {
+                        keys.push(key);
+                    }
+                }
+                None => entries.push((binding.action, vec![key], binding.description)),
+            }
+        }
+
+        let mut hint = String::new();
+        for (_, keys, description) in entries {
+            let entry = format!("{}: {}", keys.join("/"), description);
+            let extra = if hint.is_empty() { 0 } else { SEPARATOR.chars().count() };
+            if hint.chars().count() + extra + entry.chars().count() > max_width {
+                continue;
+            }
+            if !hint.is_empty() {
+                hint.push_str(SEPARATOR);
+            }
+            hint.push_str(&entry);
+        }
+        hint
+    }
+
     /// Get all bindings (for help screens)
     pub fn bindings(&self) -> &[KeyBinding] {
         &self.bindings
@@ -232,4 +363,44 @@ mod tests {
         let unknown_event = InputEvent::new(KeyCode::Char('x'), Modifiers::none());
         assert_eq!(keymap.lookup(&unknown_event), None);
     }
+
+    #[test]
+    fn test_sequence_match() {
+        let keymap = Keymap::new()
+            .bind('g', "goto", "Go")
+            .bind_seq(&['g', 'g'], "top", "Go to top")
+            .bind('G', "bottom", "Go to bottom");
+        let g = InputEvent::new(KeyCode::Char('g'), Modifiers::none());
+        let x = InputEvent::new(KeyCode::Char('x'), Modifiers::none());
+        let shift_g = InputEvent::new(KeyCode::Char('G'), Modifiers::none());
+
+        assert_eq!(keymap.match_sequence(&[g]), SequenceMatch::Prefix);
+        assert_eq!(keymap.match_sequence(&[g, g]), SequenceMatch::Complete("top"));
+        assert_eq!(keymap.match_sequence(&[g, x]), SequenceMatch::None);
+        assert_eq!(keymap.match_sequence(&[shift_g]), SequenceMatch::Complete("bottom"));
+        // Single-event lookup still sees the standalone binding
+        assert_eq!(keymap.lookup(&g), Some("goto"));
+        assert_eq!(keymap.bindings()[1].pattern.display(), "g g");
+        assert_eq!(keymap.continuations(&[g]), vec![("g".to_string(), "Go to top")]);
+        assert!(keymap.continuations(&[g, g]).is_empty());
+    }
+
+    #[test]
+    fn test_footer_hint_groups_and_fits() {
+        let keymap = Keymap::new()
+            .bind_key(KeyCode::Enter, "select", "Select")
+            .bind_key(KeyCode::Down, "next", "Next")
+            .bind('j', "next", "Next")
+            .bind_key(KeyCode::Escape, "cancel", "Cancel")
+            .bind('&', "toggle_hidden", "Toggle hidden files");
+
+        assert_eq!(
+            keymap.footer_hint(200),
+            "Enter: Select | \u{2193}/j: Next | Esc: Cancel | &: Toggle hidden files"
+        );
+        // Lower-priority entries that don't fit are dropped
+        let narrow = keymap.footer_hint(40);
+        assert_eq!(narrow, "Enter: Select | \u{2193}/j: Next | Esc: Cancel");
+        assert!(keymap.footer_hint(5).is_empty());
+    }
 }
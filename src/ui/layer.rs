@@ -1,7 +1,11 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
+use std::time::{Duration, Instant};
 
-use super::keymap::Keymap;
-use super::InputEvent;
+use super::keymap::{Keymap, SequenceMatch};
+use super::{InputEvent, KeyCode};
+
+/// How long to wait for the next key of a sequence before resolving the keys on their own
+pub const SEQUENCE_TIMEOUT: Duration = Duration::from_millis(600);
 
 /// A named layer with a keymap and transparency setting.
 pub struct Layer {
@@ -18,12 +22,21 @@ pub enum LayerResult {
     Blocked,
     /// No layer matched the event and all layers were transparent.
     Unresolved,
+    /// The event started (or continued) a key sequence; waiting for more keys.
+    Pending,
 }
 
 /// A stack of named layers that resolves input events top-to-bottom.
 pub struct LayerStack {
     layers: HashMap<&'static str, Layer>,
     active: Vec<&'static str>, // bottom-to-top
+    /// Keys of an unfinished sequence, oldest first
+    pending: Vec<InputEvent>,
+    pending_since: Option<Instant>,
+    /// Keys to feed back through `resolve` (from a broken or timed-out sequence)
+    replay: VecDeque<InputEvent>,
+    /// How many upcoming `resolve` calls match single keys only (no sequences)
+    literal: usize,
 }
 
 impl LayerStack {
@@ -35,11 +48,108 @@ impl LayerStack {
         Self {
             layers: map,
             active: Vec::new(),
+            pending: Vec::new(),
+            pending_since: None,
+            replay: VecDeque::new(),
+            literal: 0,
+        }
+    }
+
+    /// Resolve an input event through the active layers, top to bottom.
+    ///
+    /// Keys that start a sequence binding return `Pending` until the sequence
+    /// completes. If the next key breaks the sequence, the buffered keys are
+    /// resolved on their own first (see `next_replay`).
+    pub fn resolve(&mut self, event: &InputEvent) -> LayerResult {
+        if self.literal > 0 {
+            self.literal -= 1;
+            return self.resolve_single(event);
+        }
+
+        let mut keys = std::mem::take(&mut self.pending);
+        keys.push(*event);
+        self.pending_since = None;
+
+        match self.match_sequence(&keys) {
+            SequenceMatch::Complete(action) => LayerResult::Action(action),
+            SequenceMatch::Prefix => {
+                self.pending = keys;
+                self.pending_since = Some(Instant::now());
+                LayerResult::Pending
+            }
+            SequenceMatch::None if keys.len() == 1 => self.resolve_single(event),
+            SequenceMatch::None => {
+                // Broken sequence: the buffered keys act alone, then the new key
+                // is resolved normally (it may start a sequence of its own).
+                let first = keys.remove(0);
+                self.literal = keys.len() - 1;
+                self.replay.extend(keys);
+                self.resolve_single(&first)
+            }
+        }
+    }
+
+    /// Next key to feed back through `resolve`: buffered keys from a broken
+    /// sequence, or an unfinished sequence whose timeout has expired (its keys
+    /// then resolve as standalone bindings).
+    pub fn next_replay(&mut self, now: Instant) -> Option<InputEvent> {
+        if let Some(event) = self.replay.pop_front() {
+            return Some(event);
+        }
+        let since = self.pending_since?;
+        if now.duration_since(since) < SEQUENCE_TIMEOUT {
+            return None;
         }
+        self.pending_since = None;
+        self.literal = self.pending.len();
+        self.replay.extend(self.pending.drain(..));
+        self.replay.pop_front()
     }
 
-    /// Resolve an input event through the active layer stack (top-to-bottom).
-    pub fn resolve(&self, event: &InputEvent) -> LayerResult {
+    /// Keys pressed so far in an unfinished sequence, for display (e.g. "g")
+    pub fn pending_display(&self) -> String {
+        self.pending
+            .iter()
+            .map(|event| match event.key {
+                KeyCode::Char(' ') => "Space".to_string(),
+                KeyCode::Char(c) => c.to_string(),
+                other => format!("{:?}", other),
+            })
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    /// Ways to finish the unfinished sequence (next keys, description), from the
+    /// layers that can see it. Empty when no sequence is being captured.
+    pub fn pending_continuations(&self) -> Vec<(String, &'static str)> {
+        let mut entries = Vec::new();
+        if self.pending.is_empty() {
+            return entries;
+        }
+        for name in self.active.iter().rev() {
+            if let Some(layer) = self.layers.get(name) {
+                entries.extend(layer.keymap.continuations(&self.pending));
+                if !layer.transparent {
+                    break;
+                }
+            }
+        }
+        entries
+    }
+
+    fn match_sequence(&self, keys: &[InputEvent]) -> SequenceMatch {
+        for name in self.active.iter().rev() {
+            if let Some(layer) = self.layers.get(name) {
+                match layer.keymap.match_sequence(keys) {
+                    SequenceMatch::None if layer.transparent => {}
+                    result => return result,
+                }
+            }
+        }
+        SequenceMatch::None
+    }
+
+    fn resolve_single(&self, event: &InputEvent) -> LayerResult {
         for name in self.active.iter().rev() {
             if let Some(layer) = self.layers.get(name) {
                 if let Some(action) = layer.keymap.lookup(event) {
@@ -97,3 +207,73 @@ impl LayerStack {
         self.layers.get(name).map(|l| &l.keymap)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ui::Modifiers;
+
+    fn key(c: char) -> InputEvent {
+        InputEvent::new(KeyCode::Char(c), Modifiers::none())
+    }
+
+    fn stack() -> LayerStack {
+        let global = Layer {
+            name: "global",
+            keymap: Keymap::new().bind('x', "global_x", "X"),
+            transparent: true,
+        };
+        let pane = Layer {
+            name: "pane",
+            keymap: Keymap::new()
+                .bind('g', "goto", "Go")
+                .bind_seq(&['g', 'g'], "top", "Top"),
+            transparent: true,
+        };
+        let mut stack = LayerStack::new(vec![global, pane]);
+        stack.push("global");
+        stack.set_pane_layer("pane");
+        stack
+    }
+
+    fn action(result: LayerResult) -> Option<&'static str> {
+        match result {
+            LayerResult::Action(a) => Some(a),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn test_sequence_completes() {
+        let mut stack = stack();
+        assert!(stack.pending_continuations().is_empty());
+        assert!(matches!(stack.resolve(&key('g')), LayerResult::Pending));
+        assert_eq!(stack.pending_continuations(), vec![("g".to_string(), "Top")]);
+        assert_eq!(stack.pending_display(), "g");
+        assert_eq!(action(stack.resolve(&key('g'))), Some("top"));
+        assert!(stack.pending_continuations().is_empty());
+        assert_eq!(stack.next_replay(Instant::now() + SEQUENCE_TIMEOUT), None);
+    }
+
+    #[test]
+    fn test_prefix_resolves_alone_after_timeout() {
+        let mut stack = stack();
+        let start = Instant::now();
+        assert!(matches!(stack.resolve(&key('g')), LayerResult::Pending));
+        assert_eq!(stack.next_replay(start), None);
+
+        let replayed = stack.next_replay(start + SEQUENCE_TIMEOUT * 2).unwrap();
+        assert_eq!(action(stack.resolve(&replayed)), Some("goto"));
+    }
+
+    #[test]
+    fn test_broken_sequence_replays_next_key() {
+        let mut stack = stack();
+        assert!(matches!(stack.resolve(&key('g')), LayerResult::Pending));
+        // The prefix fires on its own, then the breaking key is replayed
+        assert_eq!(action(stack.resolve(&key('x'))), Some("goto"));
+        let replayed = stack.next_replay(Instant::now()).unwrap();
+        assert_eq!(action(stack.resolve(&replayed)), Some("global_x"));
+        assert_eq!(stack.next_replay(Instant::now()), None);
+    }
+}
@@ -1,4 +1,14 @@
+use ratatui::buffer::Buffer;
 use ratatui::layout::Rect;
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Paragraph, Widget};
+
+use super::{Color, Style};
+
+/// Smallest terminal the panes can lay themselves out in. Below this a
+/// "terminal too small" message is shown instead.
+pub const MIN_WIDTH: u16 = 60;
+pub const MIN_HEIGHT: u16 = 20;
 
 /// Center a rect of `width x height` within the given `area`.
 pub fn center_rect(area: Rect, width: u16, height: u16) -> Rect {
@@ -8,3 +18,58 @@ pub fn center_rect(area: Rect, width: u16, height: u16) -> Rect {
     let h = height.min(area.height);
     Rect::new(x, y, w, h)
 }
+
+/// Fit a pane box of the desired size into `area`, keeping clear of the
+/// frame border. On a large terminal this is the same as `center_rect`;
+/// on a small one the box shrinks to the space inside the border.
+pub fn fit_rect(area: Rect, desired_w: u16, desired_h: u16) -> Rect {
+    let inner = Rect::new(
+        area.x + 1,
+        area.y + 1,
+        area.width.saturating_sub(2),
+        area.height.saturating_sub(2),
+    );
+    center_rect(inner, desired_w, desired_h)
+}
+
+/// Whether `area` is below the minimum size panes can render into
+pub fn is_too_small(area: Rect) -> bool {
+    area.width < MIN_WIDTH || area.height < MIN_HEIGHT
+}
+
+/// Draw the "terminal too small" notice centered in `area`
+pub fn render_too_small(area: Rect, buf: &mut Buffer) {
+    let lines = [
+        "Terminal too small".to_string(),
+        format!("{}x{} (need {}x{})", area.width, area.height, MIN_WIDTH, MIN_HEIGHT),
+    ];
+    let style = ratatui::style::Style::from(Style::new().fg(Color::ORANGE).bold());
+    let top = area.y + area.height.saturating_sub(lines.len() as u16) / 2;
+    for (i, text) in lines.iter().enumerate() {
+        let y = top + i as u16;
+        if y >= area.y + area.height {
+            break;
+        }
+        let line_area = center_rect(Rect::new(area.x, y, area.width, 1), text.len() as u16, 1);
+        Paragraph::new(Line::from(Span::styled(text.as_str(), style))).render(line_area, buf);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fit_rect_matches_center_rect_on_large_area() {
+        for width in [120, 121, 200, 201] {
+            let area = Rect::new(0, 0, width, 50);
+            assert_eq!(fit_rect(area, 97, 29), center_rect(area, 97, 29));
+        }
+    }
+
+    #[test]
+    fn test_fit_rect_shrinks_inside_border() {
+        let area = Rect::new(0, 0, 80, 24);
+        assert_eq!(fit_rect(area, 97, 29), Rect::new(1, 1, 78, 22));
+    }
+}
@@ -0,0 +1,244 @@
+//! Macros: the actions dispatched while the recorder is on, saved by name in
+//! macros.json under the config dir and dispatched again on demand. A step is a
+//! global keymap action (pane switching, instrument selection) or an action a pane
+//! returned; either way it goes through the same handling as when it was recorded,
+//! on whatever pane, selection and cursor are current when it plays. The file is
+//! JSON because TOML has no way to write an action's empty optional fields.
+
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use super::{Action, MidiAction, SessionAction};
+
+/// Global actions a macro never records: leaving or saving the session, master
+/// recording, and two-digit selection, which is recorded as the "select:N" it ends in
+const UNRECORDED_GLOBALS: &[&str] = &["quit", "save", "load", "record_master", "select_two_digit"];
+
+/// One recorded step of a macro
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum MacroStep {
+    /// A global keymap action such as "switch:mixer" or "select:3"
+    Global(String),
+    /// An action a pane returned, dispatched as it was
+    Dispatch(Action),
+}
+
+impl MacroStep {
+    /// Why a macro can't replay this step: Some(name) for steps the user is told
+    /// about, Some("") for the recorder's own controls and input plumbing (layer
+    /// changes, no-ops), None for steps that are recorded
+    fn unrecorded(&self) -> Option<String> {
+        match self {
+            MacroStep::Global(action) if action.starts_with("macro") => Some(String::new()),
+            MacroStep::Global(action) if UNRECORDED_GLOBALS.contains(&action.as_str()) => Some(action.clone()),
+            MacroStep::Global(_) => None,
+            MacroStep::Dispatch(action) => match action {
+                Action::None | Action::PushLayer(_) | Action::PopLayer(_) | Action::ExitPerformanceMode => {
+                    Some(String::new())
+                }
+                // Quitting, saving and the audio server depend on more than the project
+                Action::Quit | Action::Session(SessionAction::Save | SessionAction::Load) | Action::Server(_) => {
+                    Some(format!("{:?}", action))
+                }
+                // MIDI ports and CC learn depend on the hardware plugged in
+                Action::Midi(
+                    MidiAction::ConnectPort(_)
+                    | MidiAction::Disconnect
+                    | MidiAction::ConnectOutput(_)
+                    | MidiAction::DisconnectOutput
+                    | MidiAction::LearnCc(_),
+                ) => Some(format!("{:?}", action)),
+                _ => None,
+            },
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Macro {
+    pub name: String,
+    pub steps: Vec<MacroStep>,
+}
+
+#[derive(Default, Serialize, Deserialize)]
+struct MacroFile {
+    #[serde(default)]
+    macros: Vec<Macro>,
+}
+
+/// Saved macros plus the one being recorded or named. Without a file (as in tests)
+/// macros only last the session.
+#[derive(Debug, Default)]
+pub struct MacroRecorder {
+    pub macros: Vec<Macro>,
+    path: Option<PathBuf>,
+    /// Steps so far while recording
+    recording: Option<Vec<MacroStep>>,
+    /// Steps of a finished recording, waiting for a name
+    unnamed: Option<Vec<MacroStep>>,
+}
+
+impl MacroRecorder {
+    pub fn default_path() -> Option<PathBuf> {
+        dirs::config_dir().map(|d| d.join("ilex").join("macros.json"))
+    }
+
+    /// Read the macros at `path`; a missing or unreadable file gives none
+    pub fn load(path: &Path) -> Self {
+        let macros = std::fs::read_to_string(path)
+            .ok()
+            .and_then(|text| serde_json::from_str::<MacroFile>(&text).ok())
+            .map(|file| file.macros)
+            .unwrap_or_default();
+        Self { macros, path: Some(path.to_path_buf()), ..Self::default() }
+    }
+
+    pub fn save(&self) -> Result<(), String> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+        }
+        let file = MacroFile { macros: self.macros.clone() };
+        let text = serde_json::to_string_pretty(&file).map_err(|e| e.to_string())?;
+        std::fs::write(path, text).map_err(|e| e.to_string())
+    }
+
+    pub fn is_recording(&self) -> bool {
+        self.recording.is_some()
+    }
+
+    pub fn start(&mut self) {
+        self.recording = Some(Vec::new());
+        self.unnamed = None;
+    }
+
+    /// Stop recording and hold the steps until `name` or `discard`. Returns how
+    /// many steps were recorded, or None when there were none.
+    pub fn stop(&mut self) -> Option<usize> {
+        let steps = self.recording.take().filter(|steps| !steps.is_empty())?;
+        let count = steps.len();
+        self.unnamed = Some(steps);
+        Some(count)
+    }
+
+    /// First "Macro N" not taken, offered as the name of a new macro
+    pub fn next_name(&self) -> String {
+        (1..)
+            .map(|n| format!("Macro {}", n))
+            .find(|name| !self.macros.iter().any(|m| &m.name == name))
+            .unwrap_or_default()
+    }
+
+    /// Keep the stopped recording as `name`, replacing a macro of the same name.
+    /// A blank name gets `next_name`.
+    pub fn name(&mut self, name: &str) -> Option<&Macro> {
+        let steps = self.unnamed.take()?;
+        let name = match name.trim() {
+            "" => self.next_name(),
+            name => name.to_string(),
+        };
+        self.macros.retain(|m| m.name != name);
+        self.macros.push(Macro { name, steps });
+        self.macros.last()
+    }
+
+    /// Drop the stopped recording without keeping it
+    pub fn discard(&mut self) {
+        self.unnamed = None;
+    }
+
+    /// Record a step. Steps a macro can't replay are dropped; the step's name is
+    /// returned so the user can be told, unless it is the recorder's own.
+    pub fn record(&mut self, step: MacroStep) -> Option<String> {
+        let steps = self.recording.as_mut()?;
+        match step.unrecorded() {
+            Some(name) => (!name.is_empty()).then_some(name),
+            None => {
+                steps.push(step);
+                None
+            }
+        }
+    }
+
+    /// Steps of the named macro, or of the newest one
+    pub fn steps(&self, name: Option<&str>) -> Option<&[MacroStep]> {
+        let found = match name {
+            Some(name) => self.macros.iter().find(|m| m.name == name),
+            None => self.macros.last(),
+        };
+        found.map(|m| m.steps.as_slice())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::{AutomationTarget, SourceType};
+    use crate::ui::{InstrumentAction, NavAction, PianoRollAction, ServerAction};
+
+    #[test]
+    fn test_recording_skips_unreplayable_actions() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("ilex").join("macros.json");
+        let mut recorder = MacroRecorder::load(&path);
+        let global = |name: &str| MacroStep::Global(name.to_string());
+        let dispatch = MacroStep::Dispatch;
+
+        // Nothing is kept while the recorder is off
+        assert_eq!(recorder.record(global("switch:mixer")), None);
+        recorder.start();
+        recorder.record(global("macro_record"));
+        recorder.record(global("switch:piano_roll_or_sequencer"));
+        recorder.record(dispatch(Action::Nav(NavAction::PushPane("add"))));
+        recorder.record(dispatch(Action::Instrument(InstrumentAction::Add(SourceType::Saw))));
+        recorder.record(dispatch(Action::PushLayer("text_edit")));
+        recorder.record(dispatch(Action::None));
+        recorder.record(dispatch(Action::PianoRoll(PianoRollAction::TransposeNotes(vec![0, 2], -12))));
+        recorder.record(dispatch(Action::Midi(MidiAction::SetChannelRoute(1, None))));
+        recorder.record(global("select:3"));
+        assert_eq!(recorder.record(global("save")).as_deref(), Some("save"));
+        assert_eq!(recorder.record(dispatch(Action::Server(ServerAction::Connect))).as_deref(), Some("Server(Connect)"));
+        let learn = Action::Midi(MidiAction::LearnCc(Some(AutomationTarget::InstrumentLevel(1))));
+        assert!(recorder.record(dispatch(learn)).is_some());
+        assert_eq!(recorder.stop(), Some(6));
+        assert!(!recorder.is_recording());
+
+        // The recording is kept once named; a blank name gets the next free one
+        let recorded = recorder.name("  ").unwrap();
+        assert_eq!(recorded.name, "Macro 1");
+        assert_eq!(recorded.steps[0], global("switch:piano_roll_or_sequencer"));
+        assert_eq!(recorded.steps[5], global("select:3"));
+        assert_eq!(recorder.next_name(), "Macro 2");
+
+        // A discarded recording keeps nothing, and neither does an empty one
+        recorder.start();
+        recorder.record(global("switch:mixer"));
+        assert_eq!(recorder.stop(), Some(1));
+        recorder.discard();
+        assert!(recorder.name("Mixer").is_none());
+        recorder.start();
+        assert_eq!(recorder.stop(), None);
+
+        // Recording under a taken name replaces that macro
+        recorder.start();
+        recorder.record(global("switch:mixer"));
+        recorder.stop();
+        recorder.name("Go mix").unwrap();
+        recorder.start();
+        recorder.record(global("switch:track"));
+        recorder.stop();
+        recorder.name("Go mix").unwrap();
+        assert_eq!(recorder.macros.len(), 2);
+        assert_eq!(recorder.steps(Some("Go mix")), Some(&[global("switch:track")][..]));
+
+        recorder.save().unwrap();
+        let reloaded = MacroRecorder::load(&path);
+        assert_eq!(reloaded.macros, recorder.macros);
+        assert_eq!(reloaded.steps(Some("Macro 1")).map(|s| s.len()), Some(6));
+        assert_eq!(reloaded.steps(None).map(|s| s.len()), Some(1));
+        assert!(reloaded.steps(Some("Macro 2")).is_none());
+    }
+}
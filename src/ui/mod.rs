@@ -4,19 +4,22 @@ pub mod keybindings;
 pub mod keymap;
 pub mod layer;
 pub mod layout_helpers;
+pub mod macros;
 pub mod pad_keyboard;
 pub mod pane;
 pub mod piano_keyboard;
+pub mod poll;
 pub mod ratatui_impl;
 pub mod style;
 pub mod widgets;
 
-pub use frame::{Frame, ViewState};
+pub use frame::Frame;
 pub use input::{AppEvent, InputEvent, InputSource, KeyCode, Modifiers, MouseEvent, MouseEventKind, MouseButton};
 pub use keymap::Keymap;
 pub use layer::{LayerResult, LayerStack};
 pub use pad_keyboard::PadKeyboard;
-pub use pane::{Action, ChopperAction, FileSelectAction, InstrumentAction, MixerAction, NavAction, Pane, PaneManager, PianoRollAction, SequencerAction, ServerAction, SessionAction, ToggleResult};
+pub use pane::{Action, ChopperAction, FileSelectAction, InstrumentAction, MidiAction, MixerAction, NavAction, Pane, PaneManager, PianoRollAction, SequencerAction, ServerAction, SessionAction, ToggleResult};
 pub use piano_keyboard::{KeyboardLayout, PianoKeyboard, translate_key};
+pub use poll::PollInterval;
 pub use ratatui_impl::RatatuiBackend;
-pub use style::{Color, Style};
+pub use style::{color_depth, set_color_depth, set_theme, theme, Color, ColorDepth, Style, Theme};
@@ -3,12 +3,18 @@ use std::path::PathBuf;
 
 use ratatui::buffer::Buffer;
 use ratatui::layout::Rect as RatatuiRect;
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, Clear, Paragraph, Widget};
+use serde::{Deserialize, Deserializer, Serialize};
 
-use super::{InputEvent, Keymap, MouseEvent};
-use crate::state::{AppState, EffectType, FilterType, InstrumentId, MixerSelection, MusicalSettings, SourceType};
+use super::{layout_helpers, InputEvent, Keymap, MouseEvent, Style, theme};
+use crate::state::drum_sequencer::LockParam;
+use crate::state::piano_roll::Note;
+use crate::state::sampler::SliceId;
+use crate::state::{AppState, AutomationTarget, EffectType, FilterType, InstrumentId, MixerSelection, MusicalSettings, SourceType};
 
 /// Drum sequencer actions
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum SequencerAction {
     ToggleStep(usize, usize),         // (pad_idx, step_idx)
     AdjustVelocity(usize, usize, i8), // (pad_idx, step_idx, delta)
@@ -20,25 +26,66 @@ pub enum SequencerAction {
     NextPattern,
     PrevPattern,
     AdjustPadLevel(usize, f32),     // (pad_idx, delta)
+    ToggleReverse(usize),           // pad_idx
+    LoadLayer(usize),               // pad_idx
+    ClearLayers(usize),             // pad_idx
+    ToggleLayerCrossfade(usize),    // pad_idx
+    LoadRoundRobin(usize),          // pad_idx
+    ClearRoundRobin(usize),         // pad_idx
     LoadSampleResult(usize, PathBuf), // (pad_idx, path) — from file browser
+    LoadLayerResult(usize, PathBuf),  // (pad_idx, path) — from file browser
+    LoadRoundRobinResult(usize, PathBuf), // (pad_idx, path) — from file browser
+    ApplyGroove,
+    ExtractGroove,
+    /// Step the current pattern's follow-action target: off, then each pattern
+    CycleFollowTarget,
+    /// Change how many passes the follow action waits for
+    AdjustFollowRepeats(i8),
+    /// Render the current pattern to a WAV loop one pattern long
+    ExportLoop(PathBuf),
+    /// Render the current pattern to a WAV loop and load it onto a pad: (pad_idx, path)
+    BounceToPad(usize, PathBuf),
+    /// Fill a pad's row with a Euclidean rhythm: (pad_idx, pulses, rotation)
+    Euclid(usize, usize, i32),
+    /// Nudge a step's parameter lock up or down one increment: (pad_idx, step_idx, param, direction)
+    AdjustLock(usize, usize, LockParam, i8),
+    /// Drop all of a step's parameter locks: (pad_idx, step_idx)
+    ClearLocks(usize, usize),
+}
+
+/// Pane or layer name in an action. Behind an alias, serde reads it with
+/// `static_name` rather than borrowing it from the input.
+type StaticName = &'static str;
+
+/// Read a pane or layer name back from a saved macro. Names are compared, never
+/// freed, so leaking the few bytes once per load is the simplest way to get `'static`.
+fn static_name<'de, D: Deserializer<'de>>(deserializer: D) -> Result<StaticName, D::Error> {
+    String::deserialize(deserializer).map(|name| &*Box::leak(name.into_boxed_str()))
 }
 
 /// Navigation actions (pane switching, modal stack)
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum NavAction {
-    SwitchPane(&'static str),
-    #[allow(dead_code)]
-    PushPane(&'static str),
+    SwitchPane(#[serde(deserialize_with = "static_name")] StaticName),
+    PushPane(#[serde(deserialize_with = "static_name")] StaticName),
     PopPane,
 }
 
 /// Instrument actions
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum InstrumentAction {
     Add(SourceType),
+    AddFactoryPreset(usize),
     Delete(InstrumentId),
     Edit(InstrumentId),
     Update(InstrumentId),
+    Rename(InstrumentId, String),
+    CycleColor(InstrumentId),
+    ToggleSampleReverse(InstrumentId),
+    MoveUp(InstrumentId),
+    MoveDown(InstrumentId),
+    RandomizeParams(InstrumentId),
+    MutateParams(InstrumentId),
     #[allow(dead_code)]
     SetParam(InstrumentId, String, f32),
     #[allow(dead_code)]
@@ -49,6 +96,8 @@ pub enum InstrumentAction {
     MoveEffect(InstrumentId, usize, i8),
     #[allow(dead_code)]
     SetFilter(InstrumentId, Option<FilterType>),
+    /// Momentarily crossfade an effect to dry: (instrument, effect index, bypassed)
+    SetEffectBypass(InstrumentId, usize, bool),
     PlayNote(u8, u8),
     PlayNotes(Vec<u8>, u8),
     Select(usize),
@@ -57,27 +106,44 @@ pub enum InstrumentAction {
     SelectFirst,
     SelectLast,
     PlayDrumPad(usize),
+    /// One short test note through the instrument, to check its routing
+    Audition(InstrumentId),
     LoadSampleResult(InstrumentId, PathBuf),
+    LoadZoneLayerResult(InstrumentId, SliceId, PathBuf),
+    TrimSample(InstrumentId),
+    NormalizeSample(InstrumentId),
+    RevertSample(InstrumentId), // back to the sample as first loaded
 }
 
 /// Mixer actions
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum MixerAction {
     Move(i8),
     Jump(i8),
     SelectAt(MixerSelection),
-    AdjustLevel(f32),
+    AdjustLevel(f32), // step in dB
+    SetLevelAt(MixerSelection, f32), // select a strip and set its level (fader grab)
+    AdjustPan(f32),
+    SetPanAt(MixerSelection, f32), // select a strip and set its pan (typed or centered)
     ToggleMute,
     ToggleSolo,
+    CycleSoloMode, // solo-in-place <-> AFL
     CycleSection,
     CycleOutput,
     CycleOutputReverse,
     AdjustSend(u8, f32),
+    SetSend(u8, f32), // typed send level, 0.0-1.0
     ToggleSend(u8),
+    /// Sum the master output to mono while listening, leaving the mix as it is
+    ToggleMonoCheck,
+    /// Add an effect to the end of a bus's chain, cycling types like the instrument editor
+    AddBusEffect(u8),
+    /// Remove the last effect from a bus's chain
+    RemoveBusEffect(u8),
 }
 
 /// Piano roll actions
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum PianoRollAction {
     ToggleNote,
     #[allow(dead_code)]
@@ -102,10 +168,60 @@ pub enum PianoRollAction {
     PlayNote(u8, u8),
     PlayNotes(Vec<u8>, u8),
     PlayStopRecord,
+    /// Undo the last step-record entry
+    StepBack,
+    /// Add a marker at the cursor
+    AddMarker,
+    /// Remove the marker at the cursor
+    RemoveMarker,
+    RenameMarker(u32, String),
+    /// Jump to the next (1) or previous (-1) marker; moves the playhead while playing
+    JumpMarker(i8),
+    /// Apply the selected groove to the current track's notes in the loop range
+    ApplyGroove,
+    /// Extract a groove from the current track's notes in the loop range
+    ExtractGroove,
+    /// Bundle the current track's notes in the loop range into a clip
+    MakeClip,
+    /// Copy the clip under the cursor to just after it
+    DuplicateClip,
+    /// Move the clip under the cursor by a number of bars
+    MoveClip(i8),
+    /// Change how many times the clip under the cursor plays back to back
+    AdjustClipLoops(i8),
+    /// Turn the clip under the cursor back into loose notes
+    UnpackClip,
+    DeleteClip,
+    /// Move every track's loose notes into one clip per track
+    ConsolidateClips,
+    /// Shift the current track's notes at these indices by semitones
+    TransposeNotes(Vec<usize>, i8),
+    /// Snap the current track's notes at these indices to a grid (ticks)
+    QuantizeNotes(Vec<usize>, u32),
+    /// Change the velocity of the current track's notes at these indices, all by
+    /// the same amount (see `PianoRollState::adjust_velocities`)
+    AdjustNoteVelocities(Vec<usize>, i8),
+    /// Loop the span of the current track's notes at these indices, widened out to a grid (ticks)
+    LoopToSelection(Vec<usize>, u32),
+    /// Write copied notes (ticks relative to the first) at the cursor
+    PasteNotes(Vec<Note>),
+    /// Save copied notes to the phrase library under a name
+    SavePhrase(String, Vec<Note>),
+    /// Write a library phrase at the cursor: (index, root at the cursor pitch
+    /// rather than the pitch it was saved at)
+    InsertPhrase(usize, bool),
+    /// Remove a phrase from the library
+    DeletePhrase(usize),
+    /// Remove the note sounding under the cursor on the current track
+    DeleteAtCursor,
+    /// Clear a tick range on the current track (see `PianoRollState::delete_range`)
+    DeleteRange(u32, u32),
+    /// Remove the current track's notes at these indices
+    DeleteNotes(Vec<usize>),
 }
 
 /// Sample chopper actions
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum ChopperAction {
     LoadSample,
     LoadSampleResult(PathBuf),
@@ -119,10 +235,13 @@ pub enum ChopperAction {
     NudgeSliceEnd(f32),
     MoveCursor(i8),          // direction
     CommitAll,               // assign all slices to pads and return
+    TrimSilence,
+    Normalize,
+    RevertSample,            // back to the sample as first loaded
 }
 
 /// Audio server actions
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum ServerAction {
     Connect,
     Disconnect,
@@ -135,8 +254,30 @@ pub enum ServerAction {
     RecordInput,
 }
 
+/// MIDI input actions
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum MidiAction {
+    ConnectPort(usize),
+    Disconnect,
+    SetChannelRoute(u8, Option<InstrumentId>), // (channel, None = follow selection)
+    CycleProgramChangeMode,
+    CycleRecordQuantize,
+    ToggleRecordAutomation,
+    CycleAutomationFreeze,
+    ConnectOutput(usize),
+    DisconnectOutput,
+    ToggleThru,
+    CycleThruChannel,
+    /// Change the soft thru note-on velocity scale
+    AdjustThruVelocity(f32),
+    /// Map the next incoming CC to a target (None cancels)
+    LearnCc(Option<AutomationTarget>),
+    /// Remove the CC mappings to a target
+    ForgetCc(AutomationTarget),
+}
+
 /// Session/file actions
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum SessionAction {
     Save,
     Load,
@@ -144,10 +285,22 @@ pub enum SessionAction {
     UpdateSessionLive(MusicalSettings),
     OpenFileBrowser(FileSelectAction),
     ImportCustomSynthDef(PathBuf),
+    /// Add a MIDI file's channels as new instruments
+    ImportMidiFile(PathBuf),
+    /// Import every .scd file in a directory, skipping already-registered names
+    ImportSynthDefFolder(PathBuf),
+    /// Select the next (1) or previous (-1) groove template
+    CycleGroove(i8),
+    /// Render a click track for the piano roll's loop (or whole arrangement) to its own WAV stem
+    RenderClick,
+    /// Set (or clear) the project's own synthdef directory and load it if connected
+    SetSynthDefDir(Option<PathBuf>),
+    /// Use a new project random seed and restart randomize/mutate from it
+    SetRngSeed(u64),
 }
 
 /// Actions that can be returned from pane input handling
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum Action {
     None,
     Quit,
@@ -159,12 +312,13 @@ pub enum Action {
     Session(SessionAction),
     Sequencer(SequencerAction),
     Chopper(ChopperAction),
+    Midi(MidiAction),
     /// Pane signals: pop piano_mode/pad_mode layer
     ExitPerformanceMode,
     /// Push a named layer onto the layer stack
-    PushLayer(&'static str),
+    PushLayer(#[serde(deserialize_with = "static_name")] StaticName),
     /// Pop a named layer from the layer stack
-    PopLayer(&'static str),
+    PopLayer(#[serde(deserialize_with = "static_name")] StaticName),
 }
 
 /// Result of toggling performance mode (piano/pad keyboard)
@@ -183,12 +337,16 @@ pub enum ToggleResult {
 }
 
 /// Action to take when a file is selected in the file browser
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum FileSelectAction {
     ImportCustomSynthDef,
     LoadDrumSample(usize), // pad index
+    LoadPadLayer(usize),   // pad index
+    LoadPadRoundRobin(usize), // pad index
     LoadChopperSample,
     LoadPitchedSample(InstrumentId),
+    LoadZoneLayer(InstrumentId, SliceId),
+    ImportMidiFile,
 }
 
 /// Trait for UI panes (screens/views).
@@ -221,6 +379,15 @@ pub trait Pane {
     /// Called when this pane becomes inactive
     fn on_exit(&mut self, _state: &AppState) {}
 
+    /// Where in the pane the user is (such as an editor's section), kept in
+    /// navigation history so back/forward return there
+    fn nav_position(&self) -> usize {
+        0
+    }
+
+    /// Return to a position from `nav_position`
+    fn restore_nav_position(&mut self, _position: usize) {}
+
     /// Toggle performance mode (piano/pad keyboard). Returns what happened.
     fn toggle_performance_mode(&mut self, _state: &AppState) -> ToggleResult {
         ToggleResult::NotSupported
@@ -239,11 +406,66 @@ pub trait Pane {
     fn as_any_mut(&mut self) -> &mut dyn Any;
 }
 
+/// Render a pane into a scratch buffer covering only `area` (seeded with what is
+/// already drawn there), then copy it back, so panes drawn into a sub-rect
+/// can't spill into the rest of the screen.
+fn render_clipped(pane: &dyn Pane, area: RatatuiRect, buf: &mut Buffer, state: &AppState) {
+    let mut scratch = Buffer::empty(area);
+    for y in area.y..area.y + area.height {
+        for x in area.x..area.x + area.width {
+            if let (Some(src), Some(dst)) = (buf.cell((x, y)), scratch.cell_mut((x, y))) {
+                *dst = src.clone();
+            }
+        }
+    }
+    pane.render(area, &mut scratch, state);
+    for y in area.y..area.y + area.height {
+        for x in area.x..area.x + area.width {
+            if let (Some(src), Some(dst)) = (scratch.cell((x, y)), buf.cell_mut((x, y))) {
+                *dst = src.clone();
+            }
+        }
+    }
+}
+
+/// Maximum number of entries kept in the back/forward navigation history
+const NAV_HISTORY_LIMIT: usize = 64;
+
+/// Minimum terminal width for split view; narrower terminals fall back to a single pane
+const SPLIT_MIN_WIDTH: u16 = 160;
+
+/// Companion pane shown in the other half when split view is first enabled
+const SPLIT_DEFAULT_COMPANION: &str = "mixer";
+const SPLIT_FALLBACK_COMPANION: &str = "piano_roll";
+
+/// Side-by-side layout: the active pane is focused, `other` is shown but receives no input
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct SplitView {
+    other: usize,
+    /// Whether the focused (active) pane is drawn in the left half
+    active_left: bool,
+}
+
+/// A point in navigation history: the active pane plus the modal stack beneath it,
+/// the instrument selected there and the pane's own position
+#[derive(Debug, Clone, PartialEq)]
+struct NavEntry {
+    index: usize,
+    stack: Vec<usize>,
+    instrument: Option<InstrumentId>,
+    position: usize,
+}
+
 /// Manages a stack of panes with one active pane
 pub struct PaneManager {
     panes: Vec<Box<dyn Pane>>,
     active_index: usize,
     stack: Vec<usize>,
+    /// Previously visited views, most recent last
+    back_history: Vec<NavEntry>,
+    /// Views left via `back()`, most recent last
+    forward_history: Vec<NavEntry>,
+    split: Option<SplitView>,
 }
 
 impl PaneManager {
@@ -253,6 +475,9 @@ impl PaneManager {
             panes: vec![initial_pane],
             active_index: 0,
             stack: Vec::new(),
+            back_history: Vec::new(),
+            forward_history: Vec::new(),
+            split: None,
         }
     }
 
@@ -271,14 +496,53 @@ impl PaneManager {
         self.panes[self.active_index].as_mut()
     }
 
-    /// Switch to a pane by ID (flat navigation — clears the stack)
+    fn current_entry(&self, state: &AppState) -> NavEntry {
+        NavEntry {
+            index: self.active_index,
+            stack: self.stack.clone(),
+            instrument: state.instruments.selected_instrument().map(|i| i.id),
+            position: self.panes[self.active_index].nav_position(),
+        }
+    }
+
+    /// Record the current view in back history before navigating away.
+    /// A fresh navigation invalidates the forward history.
+    fn record_history(&mut self, state: &AppState) {
+        let entry = self.current_entry(state);
+        if self.back_history.last() != Some(&entry) {
+            self.back_history.push(entry);
+            if self.back_history.len() > NAV_HISTORY_LIMIT {
+                self.back_history.remove(0);
+            }
+        }
+        self.forward_history.clear();
+    }
+
+    /// Make a pane active, running exit/enter hooks if it changed.
+    /// Activating the pane shown in the other split half moves focus there.
+    fn activate(&mut self, index: usize, state: &AppState) {
+        if let Some(split) = &mut self.split {
+            if split.other == index {
+                split.other = self.active_index;
+                split.active_left = !split.active_left;
+            }
+        }
+        if index != self.active_index {
+            self.panes[self.active_index].on_exit(state);
+            self.active_index = index;
+            self.panes[self.active_index].on_enter(state);
+        }
+    }
+
+    /// Switch to a pane by ID (flat navigation — clears the stack).
+    /// In split view the focused half shows the new pane; switching to the
+    /// pane shown in the other half moves focus there instead.
     pub fn switch_to(&mut self, id: &str, state: &AppState) -> bool {
         if let Some(index) = self.panes.iter().position(|p| p.id() == id) {
-            if index != self.active_index {
-                self.panes[self.active_index].on_exit(state);
-                self.active_index = index;
-                self.panes[self.active_index].on_enter(state);
+            if index != self.active_index || !self.stack.is_empty() {
+                self.record_history(state);
             }
+            self.activate(index, state);
             self.stack.clear();
             true
         } else {
@@ -289,10 +553,9 @@ impl PaneManager {
     /// Push current pane onto the stack and switch to a new pane (for modals/overlays)
     pub fn push_to(&mut self, id: &str, state: &AppState) -> bool {
         if let Some(index) = self.panes.iter().position(|p| p.id() == id) {
+            self.record_history(state);
             self.stack.push(self.active_index);
-            self.panes[self.active_index].on_exit(state);
-            self.active_index = index;
-            self.panes[self.active_index].on_enter(state);
+            self.activate(index, state);
             true
         } else {
             false
@@ -302,15 +565,121 @@ impl PaneManager {
     /// Pop the stack and return to the previous pane
     pub fn pop(&mut self, state: &AppState) -> bool {
         if let Some(prev_index) = self.stack.pop() {
-            self.panes[self.active_index].on_exit(state);
-            self.active_index = prev_index;
-            self.panes[self.active_index].on_enter(state);
+            // Closing a modal is a navigation too; record the modal view unless
+            // we are simply retracing the entry recorded when it was pushed.
+            let returning = self.back_history.last()
+                .is_some_and(|e| e.index == prev_index && e.stack == self.stack);
+            if returning {
+                self.back_history.pop();
+                self.forward_history.clear();
+            } else {
+                self.record_history(state);
+            }
+            self.activate(prev_index, state);
             true
         } else {
             false
         }
     }
 
+    /// Return to the previously active pane (restoring its modal stack, the
+    /// instrument selected there and the pane's position).
+    /// Returns false if there is no history to go back to.
+    pub fn back(&mut self, state: &mut AppState) -> bool {
+        let Some(entry) = self.back_history.pop() else {
+            return false;
+        };
+        self.forward_history.push(self.current_entry(state));
+        self.restore(entry, state);
+        true
+    }
+
+    /// Redo a navigation undone by `back()`.
+    /// Returns false if there is nothing to go forward to.
+    pub fn forward(&mut self, state: &mut AppState) -> bool {
+        let Some(entry) = self.forward_history.pop() else {
+            return false;
+        };
+        self.back_history.push(self.current_entry(state));
+        self.restore(entry, state);
+        true
+    }
+
+    fn restore(&mut self, entry: NavEntry, state: &mut AppState) {
+        // Instruments added or deleted since move the selection's index, so it is found
+        // again by id; one deleted since is left unselected
+        state.instruments.selected = entry.instrument
+            .and_then(|id| state.instruments.instruments.iter().position(|i| i.id == id));
+        self.activate(entry.index, state);
+        self.stack = entry.stack;
+        self.panes[self.active_index].restore_nav_position(entry.position);
+    }
+
+    /// Toggle split view. The active pane keeps focus on the left; the other
+    /// half shows the mixer (or the piano roll when the mixer is focused).
+    pub fn toggle_split(&mut self) {
+        if self.split.is_some() {
+            self.split = None;
+            return;
+        }
+        let position = |id: &str| self.panes.iter().position(|p| p.id() == id);
+        let other = position(SPLIT_DEFAULT_COMPANION)
+            .filter(|&i| i != self.active_index)
+            .or_else(|| position(SPLIT_FALLBACK_COMPANION))
+            .filter(|&i| i != self.active_index);
+        if let Some(other) = other {
+            self.split = Some(SplitView { other, active_left: true });
+        }
+    }
+
+    /// Move input focus to the other half of the split
+    pub fn focus_other_split(&mut self, state: &AppState) -> bool {
+        let Some(split) = self.split else {
+            return false;
+        };
+        if !self.stack.is_empty() {
+            return false;
+        }
+        self.activate(split.other, state);
+        true
+    }
+
+    /// Swap which side each pane of the split is drawn on
+    pub fn swap_split(&mut self) {
+        if let Some(split) = &mut self.split {
+            split.active_left = !split.active_left;
+        }
+    }
+
+    /// Compute the (left, right) halves for split rendering, or None if split
+    /// view is off or the area is too narrow.
+    fn split_rects(&self, area: RatatuiRect) -> Option<(RatatuiRect, RatatuiRect)> {
+        self.split?;
+        if area.width < SPLIT_MIN_WIDTH {
+            return None;
+        }
+        // One column between the halves is left for the divider
+        let left_width = (area.width - 1) / 2;
+        let left = RatatuiRect::new(area.x, area.y, left_width, area.height);
+        let right = RatatuiRect::new(
+            area.x + left_width + 1,
+            area.y,
+            area.width - left_width - 1,
+            area.height,
+        );
+        Some((left, right))
+    }
+
+    /// The portion of `area` the active pane is rendered into (used for mouse hit-testing)
+    pub fn active_area(&self, area: RatatuiRect) -> RatatuiRect {
+        match (self.split_rects(area), self.split) {
+            (Some((left, right)), Some(split)) => {
+                if split.active_left { left } else { right }
+            }
+            _ => area,
+        }
+    }
+
     /// Process navigation actions from a pane result
     pub fn process_nav(&mut self, action: &Action, state: &AppState) {
         match action {
@@ -327,9 +696,75 @@ impl PaneManager {
         }
     }
 
-    /// Render the active pane to the buffer.
+    /// Render the active pane to the buffer, or both halves in split view.
     pub fn render(&self, area: RatatuiRect, buf: &mut Buffer, state: &AppState) {
-        self.active().render(area, buf, state);
+        if layout_helpers::is_too_small(area) {
+            layout_helpers::render_too_small(area, buf);
+            return;
+        }
+        let (Some((left, right)), Some(split)) = (self.split_rects(area), self.split) else {
+            self.active().render(area, buf, state);
+            return;
+        };
+
+        let (active_rect, other_rect) = if split.active_left { (left, right) } else { (right, left) };
+        render_clipped(self.panes[split.other].as_ref(), other_rect, buf, state);
+        render_clipped(self.active(), active_rect, buf, state);
+
+        // Divider between halves, highlighted on the focused side
+        let divider_x = left.x + left.width;
+        let style = ratatui::style::Style::from(Style::new().fg(theme().border));
+        let focus_style = ratatui::style::Style::from(Style::new().fg(theme().accent));
+        // Leave the frame's top and bottom borders intact
+        for y in area.y + 1..(area.y + area.height).saturating_sub(1) {
+            if let Some(cell) = buf.cell_mut((divider_x, y)) {
+                cell.set_char('│').set_style(style);
+            }
+        }
+        let marker = if split.active_left { '◀' } else { '▶' };
+        if let Some(cell) = buf.cell_mut((divider_x, area.y + area.height / 2)) {
+            cell.set_char(marker).set_style(focus_style);
+        }
+    }
+
+    /// Draw the which-key popup listing how an unfinished key sequence can
+    /// continue. Purely visual: input keeps going to the layer stack/pane.
+    pub fn render_which_key(&self, area: RatatuiRect, buf: &mut Buffer, prefix: &str, entries: &[(String, &str)]) {
+        if entries.is_empty() || layout_helpers::is_too_small(area) {
+            return;
+        }
+        let key_width = entries.iter().map(|(k, _)| k.chars().count()).max().unwrap_or(0);
+        let desc_width = entries.iter().map(|(_, d)| d.chars().count()).max().unwrap_or(0);
+        let title = format!(" {} … ", prefix);
+        let width = ((key_width + desc_width + 5).max(title.chars().count() + 2) as u16)
+            .min(area.width.saturating_sub(4));
+        let height = (entries.len() as u16 + 2).min(area.height.saturating_sub(4));
+
+        // Bottom-right, inside the frame border
+        let rect = RatatuiRect::new(
+            area.x + area.width.saturating_sub(width + 2),
+            area.y + area.height.saturating_sub(height + 2),
+            width,
+            height,
+        );
+        Clear.render(rect, buf);
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .title(title)
+            .border_style(ratatui::style::Style::from(Style::new().fg(theme().accent)))
+            .title_style(ratatui::style::Style::from(Style::new().fg(theme().accent).bold()));
+        let inner = block.inner(rect);
+        block.render(rect, buf);
+
+        let key_style = ratatui::style::Style::from(Style::new().fg(theme().accent).bold());
+        let desc_style = ratatui::style::Style::from(Style::new().fg(theme().foreground));
+        for (i, (key, desc)) in entries.iter().take(inner.height as usize).enumerate() {
+            let line = Line::from(vec![
+                Span::styled(format!(" {:<width$} ", key, width = key_width), key_style),
+                Span::styled(desc.to_string(), desc_style),
+            ]);
+            Paragraph::new(line).render(RatatuiRect::new(inner.x, inner.y + i as u16, inner.width, 1), buf);
+        }
     }
 
     /// Get the keymap of the active pane
@@ -352,3 +787,173 @@ impl PaneManager {
             .and_then(|p| p.as_any_mut().downcast_mut::<T>())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct TestPane {
+        id: &'static str,
+        keymap: Keymap,
+        position: usize,
+    }
+
+    impl Pane for TestPane {
+        fn id(&self) -> &'static str {
+            self.id
+        }
+
+        fn nav_position(&self) -> usize {
+            self.position
+        }
+
+        fn restore_nav_position(&mut self, position: usize) {
+            self.position = position;
+        }
+
+        fn handle_action(&mut self, _action: &str, _event: &InputEvent, _state: &AppState) -> Action {
+            Action::None
+        }
+
+        fn render(&self, _area: RatatuiRect, _buf: &mut Buffer, _state: &AppState) {}
+
+        fn keymap(&self) -> &Keymap {
+            &self.keymap
+        }
+
+        fn as_any_mut(&mut self) -> &mut dyn Any {
+            self
+        }
+    }
+
+    fn manager() -> PaneManager {
+        let pane = |id| Box::new(TestPane { id, keymap: Keymap::new(), position: 0 });
+        let mut panes = PaneManager::new(pane("a"));
+        panes.add_pane(pane("b"));
+        panes.add_pane(pane("c"));
+        panes.add_pane(pane("modal"));
+        panes.add_pane(pane("mixer"));
+        panes
+    }
+
+    #[test]
+    fn test_back_forward_across_switches() {
+        let mut state = AppState::new();
+        let mut panes = manager();
+        panes.switch_to("b", &state);
+        panes.switch_to("c", &state);
+
+        assert!(panes.back(&mut state));
+        assert_eq!(panes.active().id(), "b");
+        assert!(panes.back(&mut state));
+        assert_eq!(panes.active().id(), "a");
+        assert!(!panes.back(&mut state));
+
+        assert!(panes.forward(&mut state));
+        assert_eq!(panes.active().id(), "b");
+
+        // A new navigation drops the forward history
+        panes.switch_to("a", &state);
+        assert!(!panes.forward(&mut state));
+        assert!(panes.back(&mut state));
+        assert_eq!(panes.active().id(), "b");
+    }
+
+    #[test]
+    fn test_back_forward_restores_instrument_and_position() {
+        let mut state = AppState::new();
+        state.add_instrument(crate::state::SourceType::Saw);
+        state.add_instrument(crate::state::SourceType::Sin);
+        let mut panes = manager();
+        let position = |panes: &mut PaneManager, id| panes.get_pane_mut::<TestPane>(id).unwrap().position;
+
+        state.instruments.selected = Some(0);
+        panes.get_pane_mut::<TestPane>("a").unwrap().position = 3;
+        panes.switch_to("b", &state);
+        state.instruments.selected = Some(1);
+        panes.get_pane_mut::<TestPane>("a").unwrap().position = 0;
+
+        assert!(panes.back(&mut state));
+        assert_eq!(panes.active().id(), "a");
+        assert_eq!(state.instruments.selected, Some(0));
+        assert_eq!(position(&mut panes, "a"), 3);
+
+        assert!(panes.forward(&mut state));
+        assert_eq!(panes.active().id(), "b");
+        assert_eq!(state.instruments.selected, Some(1));
+
+        // An instrument removed since isn't reselected, and the one that moved up is
+        // still found
+        state.instruments.instruments.remove(0);
+        state.instruments.selected = Some(0);
+        assert!(panes.back(&mut state));
+        assert_eq!(state.instruments.selected, None);
+        assert!(panes.forward(&mut state));
+        assert_eq!(state.instruments.selected, Some(0));
+    }
+
+    #[test]
+    fn test_back_forward_through_modal() {
+        let mut state = AppState::new();
+        let mut panes = manager();
+        panes.switch_to("b", &state);
+        panes.push_to("modal", &state);
+
+        // Back out of the modal restores the underlying pane with an empty stack
+        assert!(panes.back(&mut state));
+        assert_eq!(panes.active().id(), "b");
+        assert!(!panes.pop(&state));
+
+        // Forward re-enters the modal with its stack, so closing it still works
+        assert!(panes.forward(&mut state));
+        assert_eq!(panes.active().id(), "modal");
+        assert!(panes.pop(&state));
+        assert_eq!(panes.active().id(), "b");
+
+        // Closing the modal retraces history rather than adding the modal to it
+        assert!(panes.back(&mut state));
+        assert_eq!(panes.active().id(), "a");
+    }
+
+    #[test]
+    fn test_split_focus_and_fallback() {
+        let state = AppState::new();
+        let mut panes = manager();
+        panes.toggle_split();
+
+        let wide = RatatuiRect::new(0, 0, 201, 40);
+        assert_eq!(panes.active_area(wide), RatatuiRect::new(0, 0, 100, 40));
+        // Too narrow: single pane gets the whole area
+        let narrow = RatatuiRect::new(0, 0, 120, 40);
+        assert_eq!(panes.active_area(narrow), narrow);
+
+        // Focus moves to the mixer half on the right
+        assert!(panes.focus_other_split(&state));
+        assert_eq!(panes.active().id(), "mixer");
+        assert_eq!(panes.active_area(wide), RatatuiRect::new(101, 0, 100, 40));
+
+        // Switching to the pane in the other half just moves focus back
+        panes.switch_to("a", &state);
+        assert_eq!(panes.active_area(wide).x, 0);
+        panes.swap_split();
+        assert_eq!(panes.active_area(wide).x, 101);
+
+        let mut buf = Buffer::empty(wide);
+        panes.render(wide, &mut buf, &state);
+        panes.toggle_split();
+        assert_eq!(panes.active_area(wide), wide);
+    }
+
+    #[test]
+    fn test_which_key_popup() {
+        let panes = manager();
+        let area = RatatuiRect::new(0, 0, 80, 24);
+        let mut buf = Buffer::empty(area);
+        panes.render_which_key(area, &mut buf, "g", &[]);
+        assert_eq!(buf, Buffer::empty(area));
+
+        panes.render_which_key(area, &mut buf, "g", &[("g".to_string(), "Go to top")]);
+        let text: String = buf.content().iter().map(|c| c.symbol()).collect();
+        assert!(text.contains("Go to top"));
+    }
+}
@@ -0,0 +1,59 @@
+//! How long the main loop waits for input. Frames come every 16 ms while anything
+//! is moving; when the app sits idle the wait backs off so it doesn't spin the CPU.
+
+use std::time::Duration;
+
+/// Wait while playing, receiving input or finishing background work
+pub const ACTIVE_POLL: Duration = Duration::from_millis(16);
+/// Longest wait once idle
+pub const MAX_IDLE_POLL: Duration = Duration::from_millis(100);
+
+#[derive(Debug, Clone, Copy)]
+pub struct PollInterval {
+    current: Duration,
+}
+
+impl PollInterval {
+    pub fn new() -> Self {
+        Self { current: ACTIVE_POLL }
+    }
+
+    pub fn timeout(&self) -> Duration {
+        self.current
+    }
+
+    /// Drop straight back to the active wait when `busy`, otherwise double the
+    /// wait up to `MAX_IDLE_POLL`, so a short pause between keys stays snappy
+    pub fn update(&mut self, busy: bool) {
+        self.current = if busy {
+            ACTIVE_POLL
+        } else {
+            (self.current * 2).min(MAX_IDLE_POLL)
+        };
+    }
+}
+
+impl Default for PollInterval {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backs_off_when_idle_and_snaps_back_when_busy() {
+        let mut poll = PollInterval::new();
+        assert_eq!(poll.timeout(), ACTIVE_POLL);
+        poll.update(false);
+        assert_eq!(poll.timeout(), ACTIVE_POLL * 2);
+        for _ in 0..10 {
+            poll.update(false);
+        }
+        assert_eq!(poll.timeout(), MAX_IDLE_POLL);
+        poll.update(true);
+        assert_eq!(poll.timeout(), ACTIVE_POLL);
+    }
+}
@@ -15,7 +15,7 @@ use ratatui::{
     Terminal,
 };
 
-use super::{AppEvent, InputEvent, InputSource, KeyCode, Modifiers, MouseButton, MouseEvent, MouseEventKind};
+use super::{theme, AppEvent, InputEvent, InputSource, KeyCode, Modifiers, MouseButton, MouseEvent, MouseEventKind};
 
 /// Ratatui-based terminal backend
 pub struct RatatuiBackend {
@@ -30,10 +30,13 @@ impl RatatuiBackend {
         Ok(Self { terminal })
     }
 
-    /// Enter raw mode and alternate screen with mouse capture
-    pub fn start(&mut self) -> io::Result<()> {
+    /// Enter raw mode and alternate screen, optionally capturing the mouse
+    pub fn start(&mut self, mouse: bool) -> io::Result<()> {
         enable_raw_mode()?;
-        execute!(io::stdout(), EnterAlternateScreen, EnableMouseCapture)?;
+        execute!(io::stdout(), EnterAlternateScreen)?;
+        if mouse {
+            execute!(io::stdout(), EnableMouseCapture)?;
+        }
         self.terminal.clear()?;
         Ok(())
     }
@@ -45,13 +48,13 @@ impl RatatuiBackend {
         Ok(())
     }
 
-    /// Begin a new frame for drawing (black background)
+    /// Begin a new frame for drawing (theme background)
     pub fn begin_frame(&self) -> io::Result<RatatuiFrame> {
         let size = self.terminal.size()?;
         let area = RatatuiRect::new(0, 0, size.width, size.height);
         let mut buffer = Buffer::empty(area);
-        // Fill entire buffer with black background
-        let bg_style = RatatuiStyle::default().bg(RatatuiColor::Rgb(0, 0, 0));
+        // Fill entire buffer with the theme background
+        let bg_style = RatatuiStyle::default().bg(RatatuiColor::from(theme().background));
         for y in 0..area.height {
             for x in 0..area.width {
                 if let Some(cell) = buffer.cell_mut((x, y)) {
@@ -1,5 +1,10 @@
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::RwLock;
+
 use ratatui::style::{Color as RatatuiColor, Modifier, Style as RatatuiStyle};
 
+use crate::state::ColorTag;
+
 /// RGB color. Construct with `Color::new(r, g, b)` or use named constants
 /// (e.g. `Color::WHITE`, `Color::PINK`, `Color::MIDI_COLOR`, `Color::METER_LOW`).
 ///
@@ -16,6 +21,120 @@ impl Color {
         Self { r, g, b }
     }
 
+    /// Parse a `#rrggbb` (or `rrggbb`) hex string
+    pub fn from_hex(hex: &str) -> Option<Self> {
+        let hex = hex.strip_prefix('#').unwrap_or(hex);
+        if hex.len() != 6 || !hex.is_ascii() {
+            return None;
+        }
+        let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).ok();
+        Some(Self::new(channel(0)?, channel(2)?, channel(4)?))
+    }
+
+    /// Display color for an instrument color tag
+    pub const fn from_tag(tag: ColorTag) -> Self {
+        match tag {
+            ColorTag::Red => Color::new(235, 90, 90),
+            ColorTag::Orange => Color::new(255, 160, 70),
+            ColorTag::Yellow => Color::new(240, 220, 80),
+            ColorTag::Green => Color::new(110, 210, 110),
+            ColorTag::Cyan => Color::new(80, 210, 220),
+            ColorTag::Blue => Color::new(100, 150, 255),
+            ColorTag::Purple => Color::new(175, 130, 240),
+            ColorTag::Pink => Color::new(255, 120, 190),
+        }
+    }
+
+    /// RGB value of an xterm 256-color palette index
+    pub const fn indexed(index: u8) -> Self {
+        const ANSI: [Color; 16] = [
+            Color::new(0, 0, 0), Color::new(128, 0, 0), Color::new(0, 128, 0), Color::new(128, 128, 0),
+            Color::new(0, 0, 128), Color::new(128, 0, 128), Color::new(0, 128, 128), Color::new(192, 192, 192),
+            Color::new(128, 128, 128), Color::new(255, 0, 0), Color::new(0, 255, 0), Color::new(255, 255, 0),
+            Color::new(0, 0, 255), Color::new(255, 0, 255), Color::new(0, 255, 255), Color::new(255, 255, 255),
+        ];
+        match index {
+            0..=15 => ANSI[index as usize],
+            16..=231 => {
+                let i = index - 16;
+                Color::new(CUBE_LEVELS[(i / 36) as usize], CUBE_LEVELS[(i / 6 % 6) as usize], CUBE_LEVELS[(i % 6) as usize])
+            }
+            _ => {
+                let v = 8 + (index - 232) * 10;
+                Color::new(v, v, v)
+            }
+        }
+    }
+
+    /// Linear blend towards `other` (t=0 → self, t=1 → other)
+    pub fn lerp(self, other: Color, t: f32) -> Color {
+        let t = t.clamp(0.0, 1.0);
+        let mix = |a: u8, b: u8| (a as f32 + (b as f32 - a as f32) * t).round() as u8;
+        Color::new(mix(self.r, other.r), mix(self.g, other.g), mix(self.b, other.b))
+    }
+
+    /// Sample evenly spaced gradient stops at `t` in 0..1
+    pub fn gradient(stops: &[Color], t: f32) -> Color {
+        match stops {
+            [] => Color::default(),
+            [only] => *only,
+            _ => {
+                let pos = t.clamp(0.0, 1.0) * (stops.len() - 1) as f32;
+                let i = (pos.floor() as usize).min(stops.len() - 2);
+                stops[i].lerp(stops[i + 1], pos - i as f32)
+            }
+        }
+    }
+
+    /// Meter color at `frac` of full scale (0=bottom, 1=top). Smooth on 256/truecolor
+    /// terminals, stepped green/yellow/red otherwise.
+    pub fn meter(frac: f32) -> Color {
+        if color_depth().supports_gradients() {
+            Color::gradient(&[Color::METER_LOW, Color::METER_LOW, Color::METER_MID, Color::METER_HIGH], frac)
+        } else if frac > 0.85 {
+            Color::METER_HIGH
+        } else if frac > 0.6 {
+            Color::METER_MID
+        } else {
+            Color::METER_LOW
+        }
+    }
+
+    /// Nearest xterm 256-color palette index (cube or grayscale ramp)
+    fn to_indexed(self) -> u8 {
+        let nearest_level = |c: u8| {
+            (0..6).min_by_key(|&i| (CUBE_LEVELS[i] as i32 - c as i32).abs()).unwrap_or(0) as u8
+        };
+        let (ri, gi, bi) = (nearest_level(self.r), nearest_level(self.g), nearest_level(self.b));
+        let cube_index = 16 + 36 * ri + 6 * gi + bi;
+
+        let avg = (self.r as u32 + self.g as u32 + self.b as u32) / 3;
+        let gray_index = 232 + ((avg.saturating_sub(8) + 5) / 10).min(23) as u8;
+
+        if self.distance(Color::indexed(gray_index)) < self.distance(Color::indexed(cube_index)) {
+            gray_index
+        } else {
+            cube_index
+        }
+    }
+
+    /// Nearest of the 16 basic ANSI colors
+    fn to_ansi16(self) -> RatatuiColor {
+        const NAMED: [RatatuiColor; 16] = [
+            RatatuiColor::Black, RatatuiColor::Red, RatatuiColor::Green, RatatuiColor::Yellow,
+            RatatuiColor::Blue, RatatuiColor::Magenta, RatatuiColor::Cyan, RatatuiColor::Gray,
+            RatatuiColor::DarkGray, RatatuiColor::LightRed, RatatuiColor::LightGreen, RatatuiColor::LightYellow,
+            RatatuiColor::LightBlue, RatatuiColor::LightMagenta, RatatuiColor::LightCyan, RatatuiColor::White,
+        ];
+        let index = (0..16u8).min_by_key(|&i| self.distance(Color::indexed(i))).unwrap_or(0);
+        NAMED[index as usize]
+    }
+
+    fn distance(self, other: Color) -> u32 {
+        let d = |a: u8, b: u8| (a as i32 - b as i32).pow(2) as u32;
+        d(self.r, other.r) + d(self.g, other.g) + d(self.b, other.b)
+    }
+
     // Basic colors
     pub const BLACK: Color = Color::new(0, 0, 0);
     pub const WHITE: Color = Color::new(255, 255, 255);
@@ -76,6 +195,163 @@ impl Color {
     pub const SOLO_COLOR: Color = Color::new(255, 220, 80);    // Solo state
 }
 
+/// Channel levels of the xterm 6x6x6 color cube
+const CUBE_LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+/// How many colors the terminal can show. Colors are defined as RGB and reduced
+/// to the terminal's palette when converted for ratatui.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum ColorDepth {
+    TrueColor = 0,
+    Indexed256 = 1,
+    Ansi16 = 2,
+    /// No color: text uses the terminal default, selections use reverse video
+    Mono = 3,
+}
+
+impl ColorDepth {
+    /// Guess the terminal's color support from NO_COLOR / COLORTERM / TERM
+    pub fn detect() -> Self {
+        Self::from_env(
+            std::env::var_os("NO_COLOR").is_some(),
+            std::env::var("COLORTERM").ok().as_deref(),
+            std::env::var("TERM").ok().as_deref(),
+        )
+    }
+
+    fn from_env(no_color: bool, colorterm: Option<&str>, term: Option<&str>) -> Self {
+        if no_color || term == Some("dumb") {
+            return ColorDepth::Mono;
+        }
+        if matches!(colorterm, Some("truecolor") | Some("24bit")) {
+            return ColorDepth::TrueColor;
+        }
+        match term {
+            Some(t) if t.contains("truecolor") || t.contains("direct") => ColorDepth::TrueColor,
+            Some(t) if t.contains("256") => ColorDepth::Indexed256,
+            _ => ColorDepth::Ansi16,
+        }
+    }
+
+    /// Parse a config value ("truecolor", "256", "16", "mono"); "auto" or unknown → None
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "truecolor" | "24bit" => Some(ColorDepth::TrueColor),
+            "256" => Some(ColorDepth::Indexed256),
+            "16" => Some(ColorDepth::Ansi16),
+            "mono" | "none" => Some(ColorDepth::Mono),
+            _ => None,
+        }
+    }
+
+    /// Whether smooth gradients are distinguishable at this depth
+    pub fn supports_gradients(self) -> bool {
+        matches!(self, ColorDepth::TrueColor | ColorDepth::Indexed256)
+    }
+}
+
+static COLOR_DEPTH: AtomicU8 = AtomicU8::new(ColorDepth::TrueColor as u8);
+
+/// The color depth used when converting colors for the terminal
+pub fn color_depth() -> ColorDepth {
+    match COLOR_DEPTH.load(Ordering::Relaxed) {
+        1 => ColorDepth::Indexed256,
+        2 => ColorDepth::Ansi16,
+        3 => ColorDepth::Mono,
+        _ => ColorDepth::TrueColor,
+    }
+}
+
+pub fn set_color_depth(depth: ColorDepth) {
+    COLOR_DEPTH.store(depth as u8, Ordering::Relaxed);
+}
+
+/// Named UI colors that panes draw from instead of literals.
+///
+/// The active theme is global so any render code can read it with `theme()`;
+/// `set_theme()` takes effect on the next frame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Theme {
+    pub name: &'static str,
+    /// Screen background
+    pub background: Color,
+    /// Primary text
+    pub foreground: Color,
+    /// Headings, focused borders and highlighted values
+    pub accent: Color,
+    /// Selected row / cursor background
+    pub selection: Color,
+    /// Secondary text, empty slots and disabled items
+    pub muted: Color,
+    /// Frame and inactive borders
+    pub border: Color,
+}
+
+impl Theme {
+    /// Default theme: the original hardcoded colors
+    pub const DARK: Theme = Theme {
+        name: "dark",
+        background: Color::BLACK,
+        foreground: Color::WHITE,
+        accent: Color::CYAN,
+        selection: Color::SELECTION_BG,
+        muted: Color::DARK_GRAY,
+        border: Color::GRAY,
+    };
+
+    pub const LIGHT: Theme = Theme {
+        name: "light",
+        background: Color::new(250, 248, 240),
+        foreground: Color::new(30, 30, 30),
+        accent: Color::new(0, 110, 160),
+        selection: Color::new(170, 200, 240),
+        muted: Color::new(140, 140, 140),
+        border: Color::new(110, 110, 110),
+    };
+
+    pub const HIGH_CONTRAST: Theme = Theme {
+        name: "high-contrast",
+        background: Color::BLACK,
+        foreground: Color::WHITE,
+        accent: Color::YELLOW,
+        selection: Color::BLUE,
+        muted: Color::new(200, 200, 200),
+        border: Color::WHITE,
+    };
+
+    pub const BUILTIN: [Theme; 3] = [Theme::DARK, Theme::LIGHT, Theme::HIGH_CONTRAST];
+
+    /// Look up a built-in theme by name
+    pub fn by_name(name: &str) -> Option<Theme> {
+        Self::BUILTIN.iter().copied().find(|t| t.name.eq_ignore_ascii_case(name))
+    }
+
+    /// The built-in theme after this one (wraps around)
+    pub fn next_builtin(&self) -> Theme {
+        let idx = Self::BUILTIN.iter().position(|t| t.name == self.name).unwrap_or(0);
+        Self::BUILTIN[(idx + 1) % Self::BUILTIN.len()]
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Theme::DARK
+    }
+}
+
+static ACTIVE_THEME: RwLock<Theme> = RwLock::new(Theme::DARK);
+
+/// The active theme
+pub fn theme() -> Theme {
+    *ACTIVE_THEME.read().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Replace the active theme (redraws pick it up on the next frame)
+pub fn set_theme(theme: Theme) {
+    *ACTIVE_THEME.write().unwrap_or_else(|e| e.into_inner()) = theme;
+}
+
 /// Text style with foreground, background, and attributes.
 ///
 /// Builder methods (all const, chainable):
@@ -140,7 +416,12 @@ impl Style {
 
 impl From<Color> for RatatuiColor {
     fn from(c: Color) -> Self {
-        RatatuiColor::Rgb(c.r, c.g, c.b)
+        match color_depth() {
+            ColorDepth::TrueColor => RatatuiColor::Rgb(c.r, c.g, c.b),
+            ColorDepth::Indexed256 => RatatuiColor::Indexed(c.to_indexed()),
+            ColorDepth::Ansi16 => c.to_ansi16(),
+            ColorDepth::Mono => RatatuiColor::Reset,
+        }
     }
 }
 
@@ -159,6 +440,59 @@ impl From<Style> for RatatuiStyle {
         if s.underline {
             rs = rs.add_modifier(Modifier::UNDERLINED);
         }
+        // Without color, a highlighted background can only be shown as reverse video
+        if color_depth() == ColorDepth::Mono && s.bg.is_some_and(|bg| bg != theme().background) {
+            rs = rs.add_modifier(Modifier::REVERSED);
+        }
         rs
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_theme_lookup_and_cycle() {
+        assert_eq!(Theme::default(), Theme::DARK);
+        assert_eq!(Theme::by_name("Light"), Some(Theme::LIGHT));
+        assert_eq!(Theme::by_name("nope"), None);
+        assert_eq!(Theme::DARK.next_builtin(), Theme::LIGHT);
+        assert_eq!(Theme::HIGH_CONTRAST.next_builtin(), Theme::DARK);
+    }
+
+    #[test]
+    fn test_color_depth_detection() {
+        assert_eq!(ColorDepth::from_env(false, Some("truecolor"), Some("xterm")), ColorDepth::TrueColor);
+        assert_eq!(ColorDepth::from_env(false, None, Some("xterm-256color")), ColorDepth::Indexed256);
+        assert_eq!(ColorDepth::from_env(false, None, Some("xterm")), ColorDepth::Ansi16);
+        assert_eq!(ColorDepth::from_env(false, None, Some("dumb")), ColorDepth::Mono);
+        assert_eq!(ColorDepth::from_env(true, Some("truecolor"), None), ColorDepth::Mono);
+        assert_eq!(ColorDepth::from_name("auto"), None);
+    }
+
+    #[test]
+    fn test_palette_reduction() {
+        assert_eq!(Color::WHITE.to_indexed(), 231);
+        assert_eq!(Color::new(128, 128, 128).to_indexed(), 244);
+        assert_eq!(Color::indexed(196), Color::new(255, 0, 0));
+        assert_eq!(Color::METER_HIGH.to_ansi16(), RatatuiColor::LightRed);
+        assert_eq!(Color::BLACK.to_ansi16(), RatatuiColor::Black);
+    }
+
+    #[test]
+    fn test_gradient() {
+        let stops = [Color::BLACK, Color::WHITE];
+        assert_eq!(Color::gradient(&stops, 0.0), Color::BLACK);
+        assert_eq!(Color::gradient(&stops, 0.5), Color::new(128, 128, 128));
+        assert_eq!(Color::gradient(&stops, 2.0), Color::WHITE);
+    }
+
+    #[test]
+    fn test_color_from_hex() {
+        assert_eq!(Color::from_hex("#3c64b4"), Some(Color::SELECTION_BG));
+        assert_eq!(Color::from_hex("FFFFFF"), Some(Color::WHITE));
+        assert_eq!(Color::from_hex("#fff"), None);
+        assert_eq!(Color::from_hex("#gg0000"), None);
+    }
+}
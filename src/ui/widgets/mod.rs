@@ -1,4 +1,4 @@
 mod text_input;
 mod select_list;
 
-pub use text_input::TextInput;
+pub use text_input::{parse_number, TextInput};
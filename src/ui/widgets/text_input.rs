@@ -1,6 +1,22 @@
 use ratatui::buffer::Buffer;
 
-use crate::ui::{Color, InputEvent, KeyCode, Style};
+use crate::ui::{Color, InputEvent, KeyCode, Style, theme};
+
+/// Parse a typed number, allowing an optional trailing `unit` such as "dB" (any case).
+/// The error names the rejected text, for showing back to the user.
+pub fn parse_number(text: &str, unit: &str) -> Result<f32, String> {
+    let trimmed = text.trim();
+    let split = trimmed.len().saturating_sub(unit.len());
+    let number = match trimmed.get(split..) {
+        Some(tail) if !unit.is_empty() && tail.eq_ignore_ascii_case(unit) => trimmed[..split].trim(),
+        _ => trimmed,
+    };
+    number
+        .parse::<f32>()
+        .ok()
+        .filter(|v| v.is_finite())
+        .ok_or_else(|| format!("Not a number: {}", trimmed))
+}
 
 /// A single-line text input widget
 pub struct TextInput {
@@ -110,7 +126,7 @@ impl TextInput {
     /// Render the text input into a ratatui buffer at the given position
     pub fn render_buf(&self, buf: &mut Buffer, x: u16, y: u16, width: u16) -> u16 {
         // Draw label
-        let label_style = ratatui::style::Style::from(Style::new().fg(Color::WHITE));
+        let label_style = ratatui::style::Style::from(Style::new().fg(theme().foreground));
         for (j, ch) in self.label.chars().enumerate() {
             if let Some(cell) = buf.cell_mut((x + j as u16, y)) {
                 cell.set_char(ch).set_style(label_style);
@@ -124,7 +140,7 @@ impl TextInput {
         let border_style = if self.focused {
             ratatui::style::Style::from(Style::new().fg(Color::BLUE))
         } else {
-            ratatui::style::Style::from(Style::new().fg(Color::GRAY))
+            ratatui::style::Style::from(Style::new().fg(theme().border))
         };
         if let Some(cell) = buf.cell_mut((input_x, y)) {
             cell.set_char('[').set_style(border_style);
@@ -138,14 +154,14 @@ impl TextInput {
         let content_width = input_width.saturating_sub(2) as usize;
 
         if self.value.is_empty() && !self.focused {
-            let ph_style = ratatui::style::Style::from(Style::new().fg(Color::GRAY));
+            let ph_style = ratatui::style::Style::from(Style::new().fg(theme().border));
             for (j, ch) in self.placeholder.chars().take(content_width).enumerate() {
                 if let Some(cell) = buf.cell_mut((content_x + j as u16, y)) {
                     cell.set_char(ch).set_style(ph_style);
                 }
             }
         } else {
-            let val_style = ratatui::style::Style::from(Style::new().fg(Color::WHITE));
+            let val_style = ratatui::style::Style::from(Style::new().fg(theme().foreground));
             for (j, ch) in self.value.chars().take(content_width).enumerate() {
                 if let Some(cell) = buf.cell_mut((content_x + j as u16, y)) {
                     cell.set_char(ch).set_style(val_style);
@@ -155,7 +171,7 @@ impl TextInput {
             // Draw cursor if focused
             if self.focused {
                 let cursor_x = content_x + self.cursor.min(content_width) as u16;
-                let cursor_style = ratatui::style::Style::from(Style::new().fg(Color::WHITE).bg(Color::SELECTION_BG));
+                let cursor_style = ratatui::style::Style::from(Style::new().fg(theme().foreground).bg(theme().selection));
                 let cursor_char = self.value.chars().nth(self.cursor).unwrap_or(' ');
                 if let Some(cell) = buf.cell_mut((cursor_x, y)) {
                     cell.set_char(cursor_char).set_style(cursor_style);
@@ -166,3 +182,19 @@ impl TextInput {
         1
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_number() {
+        assert_eq!(parse_number(" 2.5 ", ""), Ok(2.5));
+        assert_eq!(parse_number("-6dB", "dB"), Ok(-6.0));
+        assert_eq!(parse_number("-6 db", "dB"), Ok(-6.0));
+        assert_eq!(parse_number("120", "ct"), Ok(120.0));
+        assert_eq!(parse_number("loud", "dB"), Err("Not a number: loud".to_string()));
+        assert!(parse_number("", "").is_err());
+        assert!(parse_number("inf", "").is_err());
+    }
+}